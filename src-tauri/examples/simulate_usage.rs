@@ -0,0 +1,54 @@
+use tokenmeter_lib::services::simulator;
+
+/// Usage: `simulate_usage [--days N] [--models N] [--check]`
+///
+/// `--check` exits with an error when a stage exceeds its budget; CI runs it
+/// on a release build with the default sizes.
+fn main() -> anyhow::Result<()> {
+    let mut days = simulator::DEFAULT_DAYS;
+    let mut models = simulator::DEFAULT_MODELS;
+    let mut check = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--days" => days = args.next().unwrap_or_default().parse()?,
+            "--models" => models = args.next().unwrap_or_default().parse()?,
+            "--check" => check = true,
+            other => anyhow::bail!("Unknown argument: {other}"),
+        }
+    }
+
+    println!("=== Simulating {days} days x {models} models ===\n");
+
+    let dir = std::env::temp_dir().join(format!("tokenmeter-simulation-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let report = simulator::simulate(&dir, days, models);
+    std::fs::remove_dir_all(&dir).ok();
+    let report = report?;
+
+    for timing in &report.timings {
+        println!(
+            "  {:<10} {:>8.1} ms",
+            timing.stage,
+            timing.duration.as_secs_f64() * 1000.0
+        );
+    }
+    println!("\nSerialized summary: {} KB", report.summary_bytes / 1024);
+
+    if check {
+        let over = report.over_budget();
+        for (timing, budget) in &over {
+            println!(
+                "  ✗ {} took {:?}, budget {budget:?}",
+                timing.stage, timing.duration
+            );
+        }
+        if !over.is_empty() {
+            anyhow::bail!("{} stage(s) over budget", over.len());
+        }
+        println!("All stages within budget");
+    }
+
+    Ok(())
+}
@@ -1,10 +1,17 @@
+use tokenmeter_lib::config::{ClaudeCodeSourceConfig, FeatureGate, TimeoutsConfig};
 use tokenmeter_lib::services::ccusage;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     println!("=== Testing ccusage ===\n");
 
-    let usage = ccusage::fetch_usage().await?;
+    let usage = ccusage::fetch_usage(
+        &ClaudeCodeSourceConfig::default(),
+        &TimeoutsConfig::default(),
+        &FeatureGate::default(),
+        None,
+    )
+    .await?;
 
     println!("Today: ${:.2}", usage.today.cost);
     println!("This month: ${:.2}", usage.this_month.cost);
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tokenmeter_lib::services::ccusage::parse_daily_export;
+
+fuzz_target!(|data: &str| {
+    if let Ok(days) = parse_daily_export(data) {
+        assert!(days.iter().all(|day| day.cost.is_finite()));
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use chrono::{DateTime, Local};
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashSet;
+use tokenmeter_lib::services::claude_logs::parse_transcript;
+
+fuzz_target!(|data: &[u8]| {
+    let since = DateTime::from_timestamp(0, 0).unwrap().with_timezone(&Local);
+    let transcript = parse_transcript(data, since, &mut HashSet::new());
+    assert!(transcript
+        .entries
+        .iter()
+        .all(|entry| entry.total_tokens() > 0 && entry.timestamp >= since));
+});
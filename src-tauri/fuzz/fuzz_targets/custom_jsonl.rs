@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tokenmeter_lib::config::JsonlFieldMapping;
+use tokenmeter_lib::services::custom_jsonl::parse_records;
+
+fuzz_target!(|data: &[u8]| {
+    let fields = JsonlFieldMapping {
+        timestamp: "timestamp".to_string(),
+        model: "model".to_string(),
+        input_tokens: "usage.input_tokens".to_string(),
+        output_tokens: "usage.output_tokens".to_string(),
+        cache_creation_tokens: Some("usage.cache_creation_input_tokens".to_string()),
+        cache_read_tokens: Some("usage.cache_read_input_tokens".to_string()),
+        cost: Some("cost".to_string()),
+        key: Some("metadata.user".to_string()),
+    };
+    for parsed in parse_records(data, &fields) {
+        assert!(!parsed.record.date.is_empty());
+        assert!(!parsed.has_cost || parsed.record.cost.is_finite());
+    }
+});
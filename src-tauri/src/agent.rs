@@ -0,0 +1,260 @@
+use crate::clock::{self, LocalDay};
+use crate::commands::usage::{self, MAX_REFRESH_INTERVAL, MIN_REFRESH_INTERVAL};
+use crate::error::AppError;
+use crate::guard;
+use crate::perf;
+use crate::scheduler;
+use crate::services::agent_link::{self, AgentHandoff, AgentInfo, HANDOFF_PATH};
+use crate::services::local_http::{self, HttpRequest};
+use crate::services::script_runner;
+use crate::state::AppState;
+use crate::tray;
+use crate::types::UsageSummary;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+
+const USAGE: &str = "\
+Usage: tokenmeterd [--once]
+
+Collects usage in the background without the tray app, sharing its
+~/.tokenmeter data and running its scheduler, API proxy and guard endpoint.
+A tray app started alongside shows this agent's data instead of refreshing.
+
+Options:
+  --once      Refresh once, print the summary JSON and exit
+  -h, --help  Show this help";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Serve,
+    Once,
+    Help,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Mode, String> {
+    let mut mode = Mode::Serve;
+    for arg in args {
+        match arg.as_str() {
+            "--once" => mode = Mode::Once,
+            "-h" | "--help" => return Ok(Mode::Help),
+            _ => return Err(format!("Unknown argument '{arg}'")),
+        }
+    }
+    Ok(mode)
+}
+
+/// Refreshes usage and every provider like the tray app's refresh, publishing
+/// the summary to the shared state.
+async fn refresh(state: &AppState) -> Result<Arc<UsageSummary>, AppError> {
+    let _refresh_guard = state.usage_refresh_lock.lock().await;
+    let config = state.config.lock().await.clone();
+    let (result, ()) = tokio::join!(
+        usage::fetch_and_update_history(state, false),
+        usage::refresh_tray_providers(
+            state,
+            config.timeouts.provider(),
+            config.provider_cache_ttl()
+        ),
+    );
+    match result {
+        Ok(data) => {
+            state.set_usage(Arc::clone(&data)).await;
+            Ok(data)
+        }
+        Err(e) => {
+            state.record_refresh_error(e.to_string()).await;
+            Err(e)
+        }
+    }
+}
+
+/// Picks up changes the tray app or a hand edit made to `config.json`,
+/// restarting the localhost listeners whose settings changed.
+async fn reload_config(state: &Arc<AppState>) {
+    let config = state.read_config();
+    let previous = std::mem::replace(&mut *state.config.lock().await, config.clone());
+    state.move_credentials_to_keychain().await;
+    if previous.data_sources.proxy != config.data_sources.proxy {
+        state.restart_proxy(&config.data_sources.proxy).await;
+    }
+    if previous.guard != config.guard {
+        state.restart_guard(state, &config.guard).await;
+    }
+}
+
+/// Answers the GUI's handoff request with the latest summary and provider
+/// lines, once the first refresh has finished.
+async fn respond(state: &AppState, token: &str, request: &HttpRequest) -> (u16, Value) {
+    if request.path != HANDOFF_PATH {
+        return (404, json!({ "error": "Not found" }));
+    }
+    let provided = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !guard::token_matches(token, provided) {
+        return (401, json!({ "error": "Missing or invalid bearer token" }));
+    }
+    let Some(summary) = state.usage_snapshot().await else {
+        return (503, json!({ "error": "Usage data has not loaded yet" }));
+    };
+    let handoff = AgentHandoff {
+        summary: (*summary).clone(),
+        providers: tray::providers(),
+    };
+    (200, serde_json::to_value(handoff).unwrap_or(Value::Null))
+}
+
+async fn handle_connection(mut stream: TcpStream, state: &AppState, token: &str) -> Result<()> {
+    // The handoff is a GET, so whatever body a client sends is left unread
+    let request = local_http::read_head(&mut stream).await?;
+    let (status, body) = respond(state, token, &request).await;
+    local_http::write_json(&mut stream, status, &body).await
+}
+
+/// Serves the handoff endpoint on a free localhost port, recording it in the
+/// agent file so the GUI reads from here instead of refreshing itself.
+async fn serve_handoff(state: Arc<AppState>) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let token = Arc::new(guard::generate_token()?);
+    let info = AgentInfo {
+        pid: std::process::id(),
+        port: listener.local_addr()?.port(),
+        token: token.to_string(),
+    };
+    agent_link::write_info(&state.config_dir, &info)?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = Arc::clone(&state);
+        let token = Arc::clone(&token);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &state, &token).await {
+                tracing::error!("[Agent] Handoff request failed: {e}");
+            }
+        });
+    }
+}
+
+async fn serve(state: Arc<AppState>) {
+    let handoff_state = Arc::clone(&state);
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve_handoff(handoff_state).await {
+            tracing::error!("Handoff endpoint stopped: {e}");
+        }
+    });
+    let config = state.config.lock().await.clone();
+    state.restart_proxy(&config.data_sources.proxy).await;
+    state.restart_guard(&state, &config.guard).await;
+    scheduler::spawn(Arc::clone(&state));
+
+    let mut day = LocalDay::now();
+    loop {
+        if let Err(e) = refresh(&state).await {
+            tracing::error!("Refresh failed: {e}");
+        }
+        let interval = state
+            .config
+            .lock()
+            .await
+            .refresh_interval
+            .clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL);
+        tokio::select! {
+            () = tokio::time::sleep(Duration::from_secs(interval)) => {}
+            // Like the tray app, starts the new day's totals without waiting
+            () = clock::day_changed(&mut day) => state.invalidate_usage().await,
+        }
+        reload_config(&state).await;
+    }
+}
+
+/// Entry point of the `tokenmeterd` headless agent.
+#[must_use]
+pub fn run() -> ExitCode {
+    // Provider transforms run in a copy of the agent that can be killed
+    if script_runner::is_transform_worker() {
+        script_runner::run_transform_worker();
+        return ExitCode::SUCCESS;
+    }
+    let mode = match parse_args(std::env::args().skip(1)) {
+        Ok(mode) => mode,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if mode == Mode::Help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    perf::init();
+    let state = match AppState::new() {
+        Ok(state) => Arc::new(state),
+        Err(e) => {
+            tracing::error!("Failed to initialize app state: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    tauri::async_runtime::block_on(async move {
+        state.move_credentials_to_keychain().await;
+        if mode == Mode::Once {
+            return match refresh(&state).await {
+                Ok(data) => match serde_json::to_string_pretty(&*data) {
+                    Ok(json) => {
+                        println!("{json}");
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to serialize summary: {e}");
+                        ExitCode::FAILURE
+                    }
+                },
+                Err(e) => {
+                    tracing::error!("Refresh failed: {e}");
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        let _lock = match agent_link::acquire_lock(&state.config_dir) {
+            Ok(Some(lock)) => lock,
+            Ok(None) => {
+                tracing::error!("Another tokenmeterd is already running");
+                return ExitCode::FAILURE;
+            }
+            Err(e) => {
+                tracing::error!("Failed to lock the agent file: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let config_dir = state.config_dir.clone();
+        tokio::select! {
+            () = serve(state) => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+        agent_link::remove_info(&config_dir);
+        ExitCode::SUCCESS
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Mode, String> {
+        parse_args(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(args(&[]), Ok(Mode::Serve));
+        assert_eq!(args(&["--once"]), Ok(Mode::Once));
+        assert_eq!(args(&["--once", "--help"]), Ok(Mode::Help));
+        assert!(args(&["--daemon"]).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::config::{ApiProvider, AppConfig};
+use crate::services::push::{self, PushMessage};
+use crate::storage;
+use crate::tray::{daily_budget_level, usage_date, UsageLevel};
+use crate::types::{
+    BudgetAlertRecord, CachedProviderResult, Diagnostics, KeyExpiration, ProviderHistoryPoint,
+    ProviderUsageResult, UsageSummary,
+};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Serializes alert checks so overlapping refreshes can't send the same alert twice.
+static ALERT_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Highest level already alerted for `date`.
+fn alerted_level(date: NaiveDate, last: Option<BudgetAlertRecord>) -> Option<UsageLevel> {
+    let record = last.filter(|record| record.date == date)?;
+    Some(if record.over_hard_budget {
+        UsageLevel::OverHardBudget
+    } else if record.over_budget {
+        UsageLevel::OverBudget
+    } else {
+        UsageLevel::NearBudget
+    })
+}
+
+/// Whether `level` on `date` still needs an alert. Each level is sent at most
+/// once per day, and lower levels are skipped once a higher one has been sent.
+fn should_alert(level: UsageLevel, date: NaiveDate, last: Option<BudgetAlertRecord>) -> bool {
+    alerted_level(date, last).is_none_or(|alerted| level > alerted)
+}
+
+fn budget_message(level: UsageLevel, cost: f64, budget: f64) -> PushMessage {
+    match level {
+        UsageLevel::NearBudget => PushMessage {
+            title: "Approaching daily budget".to_string(),
+            body: format!("Today's spend is ${cost:.2} of your ${budget:.2} budget."),
+            high_priority: false,
+            persistent: false,
+        },
+        UsageLevel::OverBudget => PushMessage {
+            title: "Daily budget exceeded".to_string(),
+            body: format!("Today's spend is ${cost:.2}, over your ${budget:.2} budget."),
+            high_priority: true,
+            persistent: false,
+        },
+        UsageLevel::OverHardBudget => PushMessage {
+            title: "Hard daily budget reached".to_string(),
+            body: format!("Today's spend is ${cost:.2}, past your ${budget:.2} hard budget."),
+            high_priority: true,
+            persistent: true,
+        },
+    }
+}
+
+/// Sends a push notification when today's cost first crosses the near-budget,
+/// over-budget or hard-budget threshold.
+pub fn spawn_budget_alert(
+    config_dir: PathBuf,
+    diagnostics: Arc<Mutex<Diagnostics>>,
+    config: &AppConfig,
+    usage: &UsageSummary,
+) {
+    let push_config = config.alerts.push.clone();
+    let timeout = config.timeouts.integration();
+    if !push_config.enabled {
+        return;
+    }
+    let date = usage_date(usage);
+    let cost = usage.today.cost;
+    let Some(level) = daily_budget_level(usage, config) else {
+        return;
+    };
+    let budget = match level {
+        UsageLevel::OverHardBudget => config.menu_bar.hard_budget_for(date).unwrap_or_default(),
+        _ => config.menu_bar.budget_for(date),
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let _guard = ALERT_LOCK.lock().await;
+        let mut scheduler_state = storage::load_scheduler_state(&config_dir);
+        if !should_alert(level, date, scheduler_state.budget_alert) {
+            return;
+        }
+
+        let result = push::send(&push_config, &budget_message(level, cost, budget), timeout).await;
+        if let Err(e) = &result {
+            tracing::warn!("Budget push notification failed: {e}");
+        } else {
+            scheduler_state.budget_alert = Some(BudgetAlertRecord {
+                date,
+                over_budget: level >= UsageLevel::OverBudget,
+                over_hard_budget: level == UsageLevel::OverHardBudget,
+            });
+            if let Err(e) = storage::save_scheduler_state(&config_dir, &scheduler_state) {
+                tracing::warn!("Failed to save scheduler state: {e}");
+            }
+        }
+        diagnostics.lock().await.record_integration("push", result);
+    });
+}
+
+/// Percent of the balance used between the oldest point of `history` and
+/// `latest`, for providers reporting a used/total balance. Top-ups that raise
+/// the balance read as negative.
+fn balance_drain_percent(
+    history: &[ProviderHistoryPoint],
+    latest: &ProviderUsageResult,
+) -> Option<f64> {
+    let remaining = |result: &ProviderUsageResult| Some(result.total? - result.used?);
+    let baseline = &history.first()?.result;
+    let total = baseline.total.filter(|total| *total > 0.0)?;
+    Some((remaining(baseline)? - remaining(latest)?) / total * 100.0)
+}
+
+/// Whether a drain may be alerted again, at most once per `window`.
+fn should_alert_drain(last: Option<DateTime<Utc>>, now: DateTime<Utc>, window: TimeDelta) -> bool {
+    last.is_none_or(|at| now - at >= window)
+}
+
+fn drain_message(name: &str, percent: f64, window_minutes: u32) -> PushMessage {
+    PushMessage {
+        title: format!("{name} balance is draining fast"),
+        body: format!(
+            "{percent:.0}% of the balance was used in the last {window_minutes} minutes. \
+             Check for a leaked API key."
+        ),
+        high_priority: true,
+        persistent: false,
+    }
+}
+
+/// Sends a push notification for each freshly `fetched` provider whose
+/// balance fell by more than the configured share within the drain window.
+/// Expects the fetched results to be in the provider history already.
+pub fn spawn_balance_drain_alerts(
+    config_dir: PathBuf,
+    diagnostics: Arc<Mutex<Diagnostics>>,
+    config: &AppConfig,
+    providers: &[ApiProvider],
+    fetched: &[(String, CachedProviderResult)],
+) {
+    let push_config = config.alerts.push.clone();
+    let drain = config.alerts.balance_drain.clone();
+    if !push_config.enabled || !drain.enabled || fetched.is_empty() {
+        return;
+    }
+    let timeout = config.timeouts.integration();
+    let fetched: Vec<(String, String, CachedProviderResult)> = fetched
+        .iter()
+        .filter_map(|(id, cached)| {
+            let provider = providers.iter().find(|p| p.id == *id)?;
+            Some((id.clone(), provider.name.clone(), cached.clone()))
+        })
+        .collect();
+
+    tauri::async_runtime::spawn(async move {
+        let _guard = ALERT_LOCK.lock().await;
+        let mut scheduler_state = storage::load_scheduler_state(&config_dir);
+        let mut alerted = false;
+        for (id, name, cached) in fetched {
+            let since = cached.fetched_at - drain.window();
+            let history =
+                storage::load_provider_history(&config_dir, &id, since).unwrap_or_default();
+            let Some(percent) = balance_drain_percent(&history, &cached.result) else {
+                continue;
+            };
+            let last = scheduler_state.balance_drain_alerts.get(&id).copied();
+            if percent <= drain.percent
+                || !should_alert_drain(last, cached.fetched_at, drain.window())
+            {
+                continue;
+            }
+
+            let message = drain_message(&name, percent, drain.window_minutes);
+            let result = push::send(&push_config, &message, timeout).await;
+            if let Err(e) = &result {
+                tracing::warn!("Balance drain push notification failed: {e}");
+            } else {
+                scheduler_state
+                    .balance_drain_alerts
+                    .insert(id, cached.fetched_at);
+                alerted = true;
+            }
+            diagnostics.lock().await.record_integration("push", result);
+        }
+        if alerted {
+            if let Err(e) = storage::save_scheduler_state(&config_dir, &scheduler_state) {
+                tracing::warn!("Failed to save scheduler state: {e}");
+            }
+        }
+    });
+}
+
+/// Every provider env entry with an expiry date, soonest first. Dates left
+/// behind by removed variables are skipped.
+#[must_use]
+pub fn key_expirations(providers: &[ApiProvider], today: NaiveDate) -> Vec<KeyExpiration> {
+    let mut expirations: Vec<KeyExpiration> = providers
+        .iter()
+        .flat_map(|provider| {
+            provider
+                .env_expires_at
+                .iter()
+                .filter(|(variable, _)| provider.env.contains_key(*variable))
+                .map(|(variable, expires_at)| KeyExpiration {
+                    provider_id: provider.id.clone(),
+                    provider_name: provider.name.clone(),
+                    variable: variable.clone(),
+                    expires_at: *expires_at,
+                    days_left: (*expires_at - today).num_days(),
+                })
+        })
+        .collect();
+    expirations.sort_by(|a, b| {
+        a.expires_at
+            .cmp(&b.expires_at)
+            .then_with(|| a.provider_name.cmp(&b.provider_name))
+            .then_with(|| a.variable.cmp(&b.variable))
+    });
+    expirations
+}
+
+fn key_expiry_id(expiration: &KeyExpiration) -> String {
+    format!("{}/{}", expiration.provider_id, expiration.variable)
+}
+
+/// Whether `expiration` is within `days_before` of its date and has not been
+/// reminded of for that date yet.
+fn key_expiry_due(
+    expiration: &KeyExpiration,
+    days_before: u32,
+    reminded: Option<NaiveDate>,
+) -> bool {
+    expiration.days_left <= i64::from(days_before) && reminded != Some(expiration.expires_at)
+}
+
+fn key_expiry_message(expiration: &KeyExpiration) -> PushMessage {
+    let KeyExpiration {
+        provider_name,
+        variable,
+        days_left,
+        ..
+    } = expiration;
+    let title = match days_left {
+        ..=-1 => format!("{provider_name} {variable} has expired"),
+        0 => format!("{provider_name} {variable} expires today"),
+        1 => format!("{provider_name} {variable} expires tomorrow"),
+        _ => format!("{provider_name} {variable} expires in {days_left} days"),
+    };
+    PushMessage {
+        title,
+        body: format!(
+            "Rotate the key and update it in TokenMeter before {}.",
+            expiration.expires_at
+        ),
+        high_priority: *days_left <= 1,
+        persistent: false,
+    }
+}
+
+/// Sends a push reminder for each provider env entry that expires within the
+/// configured number of days, once per expiry date.
+pub fn spawn_key_expiry_alerts(
+    config_dir: PathBuf,
+    diagnostics: Arc<Mutex<Diagnostics>>,
+    config: &AppConfig,
+    providers: &[ApiProvider],
+) {
+    let push_config = config.alerts.push.clone();
+    let key_expiry = config.alerts.key_expiry.clone();
+    if !push_config.enabled || !key_expiry.enabled {
+        return;
+    }
+    let timeout = config.timeouts.integration();
+    let expirations = key_expirations(providers, chrono::Local::now().date_naive());
+    if expirations.is_empty() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let _guard = ALERT_LOCK.lock().await;
+        let mut scheduler_state = storage::load_scheduler_state(&config_dir);
+        let mut reminded = false;
+        for expiration in expirations {
+            let id = key_expiry_id(&expiration);
+            let last = scheduler_state.key_expiry_alerts.get(&id).copied();
+            if !key_expiry_due(&expiration, key_expiry.days_before, last) {
+                continue;
+            }
+
+            let result = push::send(&push_config, &key_expiry_message(&expiration), timeout).await;
+            if let Err(e) = &result {
+                tracing::warn!("Key expiry push notification failed: {e}");
+            } else {
+                scheduler_state
+                    .key_expiry_alerts
+                    .insert(id, expiration.expires_at);
+                reminded = true;
+            }
+            diagnostics.lock().await.record_integration("push", result);
+        }
+        if reminded {
+            if let Err(e) = storage::save_scheduler_state(&config_dir, &scheduler_state) {
+                tracing::warn!("Failed to save scheduler state: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    #[test]
+    fn test_should_alert_once_per_level_per_day() {
+        assert!(should_alert(UsageLevel::NearBudget, date(2), None));
+
+        let near = Some(BudgetAlertRecord {
+            date: date(2),
+            over_budget: false,
+            over_hard_budget: false,
+        });
+        assert!(!should_alert(UsageLevel::NearBudget, date(2), near));
+        assert!(should_alert(UsageLevel::OverBudget, date(2), near));
+
+        let over = Some(BudgetAlertRecord {
+            date: date(2),
+            over_budget: true,
+            over_hard_budget: false,
+        });
+        assert!(!should_alert(UsageLevel::OverBudget, date(2), over));
+        assert!(!should_alert(UsageLevel::NearBudget, date(2), over));
+        assert!(should_alert(UsageLevel::OverHardBudget, date(2), over));
+
+        let hard = Some(BudgetAlertRecord {
+            date: date(2),
+            over_budget: true,
+            over_hard_budget: true,
+        });
+        assert!(!should_alert(UsageLevel::OverHardBudget, date(2), hard));
+
+        // A new day starts over
+        assert!(should_alert(UsageLevel::NearBudget, date(3), over));
+    }
+
+    #[test]
+    fn test_budget_message() {
+        let message = budget_message(UsageLevel::OverBudget, 16.4, 15.0);
+        assert!(message.high_priority);
+        assert_eq!(
+            message.body,
+            "Today's spend is $16.40, over your $15.00 budget."
+        );
+        assert!(!budget_message(UsageLevel::NearBudget, 13.5, 15.0).high_priority);
+        assert!(budget_message(UsageLevel::OverHardBudget, 30.0, 30.0).persistent);
+    }
+
+    fn balance(minutes_ago: i64, used: f64, total: f64) -> ProviderHistoryPoint {
+        ProviderHistoryPoint {
+            fetched_at: Utc::now() - TimeDelta::minutes(minutes_ago),
+            result: ProviderUsageResult {
+                cost: None,
+                tokens: None,
+                used: Some(used),
+                total: Some(total),
+                daily: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_balance_drain_percent() {
+        let history = [balance(50, 20.0, 100.0), balance(0, 32.0, 100.0)];
+        assert_eq!(
+            balance_drain_percent(&history, &history[1].result),
+            Some(12.0)
+        );
+
+        // Topping up from 100 to 150 credits raises the balance
+        let topped_up = balance(0, 32.0, 150.0);
+        assert!(balance_drain_percent(&history, &topped_up.result).unwrap() < 0.0);
+
+        let cost_only = ProviderUsageResult {
+            cost: Some(5.0),
+            tokens: None,
+            used: None,
+            total: None,
+            daily: Vec::new(),
+        };
+        assert_eq!(balance_drain_percent(&history, &cost_only), None);
+        assert_eq!(balance_drain_percent(&[], &history[1].result), None);
+    }
+
+    #[test]
+    fn test_should_alert_drain_once_per_window() {
+        let now = Utc::now();
+        let window = TimeDelta::minutes(60);
+        assert!(should_alert_drain(None, now, window));
+        assert!(!should_alert_drain(
+            Some(now - TimeDelta::minutes(30)),
+            now,
+            window
+        ));
+        assert!(should_alert_drain(Some(now - window), now, window));
+    }
+
+    #[test]
+    fn test_key_expirations() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2025, 3, day).unwrap();
+        let provider: ApiProvider = serde_json::from_value(serde_json::json!({
+            "id": "openai",
+            "name": "OpenAI",
+            "enabled": true,
+            "fetchScript": "",
+            "transformScript": "",
+            "env": {"API_KEY": "sk-1", "ORG_KEY": "org-1"},
+            "envExpiresAt": {
+                "API_KEY": "2025-03-20",
+                "ORG_KEY": "2025-03-12",
+                "OLD_KEY": "2025-03-01"
+            },
+            "lastFetched": null,
+            "lastError": null
+        }))
+        .unwrap();
+
+        let expirations = key_expirations(&[provider], today);
+        let listed: Vec<_> = expirations
+            .iter()
+            .map(|e| (e.variable.as_str(), e.days_left))
+            .collect();
+        assert_eq!(listed, [("ORG_KEY", 2), ("API_KEY", 10)]);
+
+        let soon = &expirations[0];
+        assert!(key_expiry_due(soon, 7, None));
+        assert!(!key_expiry_due(soon, 1, None));
+        assert!(!key_expiry_due(soon, 7, Some(date(12))));
+        // A rotated key with a new date is reminded again
+        assert!(key_expiry_due(soon, 7, Some(date(1))));
+        assert!(!key_expiry_due(&expirations[1], 7, None));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::config::AppLockConfig;
+use crate::error::AppError;
+use crate::services::local_auth;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LockState {
+    Locked,
+    /// Authenticated, with the dashboard open.
+    Unlocked,
+    /// Authenticated, with the dashboard closed; locks again at this time.
+    UnlockedUntil(Instant),
+}
+
+impl LockState {
+    fn is_unlocked(self, now: Instant) -> bool {
+        match self {
+            Self::Locked => false,
+            Self::Unlocked => true,
+            Self::UnlockedUntil(until) => now < until,
+        }
+    }
+
+    /// State after the dashboard closes, keeping it unlocked for `grace`.
+    fn closed(self, grace: Duration, now: Instant) -> Self {
+        match self {
+            Self::Unlocked if !grace.is_zero() => Self::UnlockedUntil(now + grace),
+            Self::UnlockedUntil(until) if now < until => self,
+            _ => Self::Locked,
+        }
+    }
+}
+
+static STATE: Mutex<LockState> = Mutex::new(LockState::Locked);
+
+/// Held while a prompt is shown so concurrent requests share one.
+static PROMPT: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn set_state(state: LockState) {
+    if let Ok(mut current) = STATE.lock() {
+        *current = state;
+    }
+}
+
+fn is_unlocked() -> bool {
+    STATE
+        .lock()
+        .is_ok_and(|state| state.is_unlocked(Instant::now()))
+}
+
+/// Whether `config` requires authentication that has not been given yet.
+#[must_use]
+pub fn is_locked(config: AppLockConfig) -> bool {
+    config.enabled && !is_unlocked()
+}
+
+/// Fails with [`AppError::Locked`] instead of prompting, for reads that can
+/// happen while the dashboard is hidden.
+///
+/// # Errors
+/// Returns [`AppError::Locked`] if the app is locked.
+pub fn ensure_unlocked(config: AppLockConfig) -> Result<(), AppError> {
+    if is_locked(config) {
+        return Err(AppError::Locked);
+    }
+    Ok(())
+}
+
+/// Shows the prompt; callers hold [`PROMPT`].
+async fn prompt(reason: &str) -> Result<(), AppError> {
+    match local_auth::authenticate(reason).await {
+        Ok(true) => {
+            set_state(LockState::Unlocked);
+            Ok(())
+        }
+        Ok(false) => Err(AppError::Locked),
+        Err(e) => Err(AppError::Config(e.to_string())),
+    }
+}
+
+/// Prompts for system authentication, whether or not the app is locked, and
+/// unlocks it on success. `reason` completes "`TokenMeter` is trying to ...".
+///
+/// # Errors
+/// Returns an error if the prompt cannot be shown or the user does not pass it.
+pub async fn authenticate(reason: &str) -> Result<(), AppError> {
+    let _prompt = PROMPT.lock().await;
+    prompt(reason).await
+}
+
+/// Unlocks the app for the dashboard, prompting only when `config` requires
+/// it and no earlier authentication still holds. Returns whether a prompt was
+/// passed, so callers can refresh data withheld while locked.
+///
+/// # Errors
+/// Returns an error if the prompt cannot be shown or the user does not pass it.
+pub async fn unlock(config: AppLockConfig, reason: &str) -> Result<bool, AppError> {
+    if !config.enabled {
+        return Ok(false);
+    }
+    let _prompt = PROMPT.lock().await;
+    // Also true when another request passed a prompt while this one waited
+    if is_unlocked() {
+        set_state(LockState::Unlocked);
+        return Ok(false);
+    }
+    prompt(reason).await?;
+    Ok(true)
+}
+
+/// Prompt reason for saving `new` over `current`, when the save switches the
+/// lock on or off. Turning it on proves the prompt works before it can keep
+/// the dashboard shut; turning it off needs the same proof as opening it.
+#[must_use]
+pub const fn toggle_reason(current: AppLockConfig, new: AppLockConfig) -> Option<&'static str> {
+    match (current.enabled, new.enabled) {
+        (false, true) => Some("turn on the TokenMeter app lock"),
+        (true, false) => Some("turn off the TokenMeter app lock"),
+        _ => None,
+    }
+}
+
+/// Starts the grace period of `config` once the dashboard is closed, after
+/// which the app locks again.
+pub fn dashboard_closed(config: AppLockConfig) {
+    if let Ok(mut state) = STATE.lock() {
+        *state = state.closed(config.grace(), Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_state_grace_period() {
+        let now = Instant::now();
+        let grace = Duration::from_mins(5);
+
+        assert!(!LockState::Locked.is_unlocked(now));
+        assert!(LockState::Unlocked.is_unlocked(now));
+        assert_eq!(LockState::Locked.closed(grace, now), LockState::Locked);
+        assert_eq!(
+            LockState::Unlocked.closed(Duration::ZERO, now),
+            LockState::Locked
+        );
+
+        let closed = LockState::Unlocked.closed(grace, now);
+        assert!(closed.is_unlocked(now + Duration::from_secs(299)));
+        assert!(!closed.is_unlocked(now + grace));
+        // Closing again does not extend the grace period
+        assert_eq!(closed.closed(grace, now + Duration::from_mins(1)), closed);
+        assert_eq!(closed.closed(grace, now + grace), LockState::Locked);
+    }
+
+    #[test]
+    fn test_toggle_reason() {
+        let lock = |enabled| AppLockConfig {
+            enabled,
+            ..AppLockConfig::default()
+        };
+        assert_eq!(toggle_reason(lock(false), lock(false)), None);
+        assert_eq!(toggle_reason(lock(true), lock(true)), None);
+        assert!(toggle_reason(lock(false), lock(true)).is_some_and(|r| r.contains("turn on")));
+        assert!(toggle_reason(lock(true), lock(false)).is_some_and(|r| r.contains("turn off")));
+    }
+}
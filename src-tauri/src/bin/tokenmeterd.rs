@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    tokenmeter_lib::agent::run()
+}
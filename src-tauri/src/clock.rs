@@ -0,0 +1,90 @@
+use crate::commands::usage::run_refresh;
+use crate::state::AppState;
+use chrono::{DateTime, FixedOffset, NaiveDate, Offset, TimeZone};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the local date and UTC offset are checked.
+const TICK_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Local date and UTC offset that "today" is bucketed by. Either changes at
+/// midnight, with DST, when travelling or when the system clock is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalDay {
+    date: NaiveDate,
+    offset: FixedOffset,
+}
+
+impl LocalDay {
+    fn of<Tz: TimeZone>(now: &DateTime<Tz>) -> Self {
+        Self {
+            date: now.date_naive(),
+            offset: now.offset().fix(),
+        }
+    }
+
+    pub(crate) fn now() -> Self {
+        Self::of(&chrono::Local::now())
+    }
+}
+
+/// Waits until the local date or UTC offset differs from `day`, updating it.
+pub async fn day_changed(day: &mut LocalDay) {
+    let mut interval = tokio::time::interval(TICK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let current = LocalDay::now();
+        if current != *day {
+            *day = current;
+            return;
+        }
+    }
+}
+
+/// Refreshes usage whenever the local date or UTC offset changes, so the tray
+/// and dashboard don't keep showing the previous day's totals as today until
+/// the next scheduled refresh.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut day = LocalDay::now();
+        loop {
+            day_changed(&mut day).await;
+            let state = app.state::<AppState>();
+            state.invalidate_usage().await;
+            match run_refresh(&app, &state, false).await {
+                // Makes the dashboard reload the new day's data
+                Ok(_) => {
+                    let _ = app.emit("usage-preloaded", ());
+                }
+                Err(e) => tracing::error!("Refresh after date or timezone change failed: {e}"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_day_changes() {
+        let utc = chrono::Utc
+            .with_ymd_and_hms(2025, 3, 30, 22, 30, 0)
+            .unwrap();
+        let at = |minutes: i64, hours_east: i32| {
+            let offset = FixedOffset::east_opt(hours_east * 3600).unwrap();
+            LocalDay::of(&(utc + chrono::Duration::minutes(minutes)).with_timezone(&offset))
+        };
+
+        let day = at(0, 1);
+        assert_eq!(day, at(20, 1));
+        // Past midnight
+        assert_ne!(day, at(60, 1));
+        // Same date, new offset (DST)
+        let dst = at(-120, 2);
+        assert_eq!(dst.date, day.date);
+        assert_ne!(dst, day);
+        // Travelling across the date line
+        assert_ne!(day, at(0, 9));
+    }
+}
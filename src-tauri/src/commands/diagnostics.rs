@@ -0,0 +1,206 @@
+use crate::alerts;
+use crate::commands::providers::{self, REDACTED};
+use crate::config::{ApiProvider, AppConfig};
+use crate::error::AppError;
+use crate::logs;
+use crate::perf;
+use crate::services::{ccusage, credentials, support_bundle};
+use crate::state::AppState;
+use crate::types::{Diagnostics, PerfStat};
+use serde::Serialize;
+use tauri::State;
+
+/// Returns runtime health information, such as the last result of each
+/// integration, the flags detected for ccusage and the provider keys that
+/// expire soonest.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_diagnostics", skip_all)]
+pub async fn get_diagnostics(state: State<'_, AppState>) -> Result<Diagnostics, AppError> {
+    let mut diagnostics = state.diagnostics.lock().await.clone();
+    let source = state.config.lock().await.data_sources.claude_code.clone();
+    diagnostics.ccusage = ccusage::cached_capabilities(&source);
+    let providers = providers::load_providers(&state.config_dir)?;
+    diagnostics.key_expirations =
+        alerts::key_expirations(&providers, chrono::Local::now().date_naive());
+    Ok(diagnostics)
+}
+
+/// Returns duration statistics for every instrumented command and service call
+/// since launch, so slow refreshes can be attributed to their slowest step.
+#[tauri::command]
+#[tracing::instrument(name = "command.get_perf_stats", skip_all)]
+pub fn get_perf_stats() -> Vec<PerfStat> {
+    perf::stats()
+}
+
+/// Every credential held in `config`, including the snapshot URL, which may be
+/// presigned, and the ntfy topic, which anyone who knows it can read.
+fn config_secrets(config: &mut AppConfig) -> Vec<&mut String> {
+    let sources = &mut config.data_sources;
+    let optional = [
+        &mut sources.cursor.session_token,
+        &mut sources.windsurf.service_key,
+        &mut sources.openai.admin_key,
+        &mut sources.openrouter.api_key,
+        &mut sources.litellm.api_key,
+        &mut sources.copilot.token,
+        &mut config.alerts.push.ntfy_token,
+        &mut config.alerts.push.pushover_user_key,
+        &mut config.alerts.push.pushover_app_token,
+    ];
+    optional
+        .into_iter()
+        .filter_map(Option::as_mut)
+        .chain([
+            &mut config.integrations.notion.token,
+            &mut config.integrations.remote_snapshot.url,
+            &mut config.alerts.push.ntfy_topic,
+            &mut config.guard.token,
+        ])
+        .collect()
+}
+
+/// Copy of `config` safe to share, with every credential masked.
+#[must_use]
+pub fn redact_config(mut config: AppConfig) -> AppConfig {
+    for secret in config_secrets(&mut config) {
+        if !secret.is_empty() {
+            *secret = REDACTED.to_string();
+        }
+    }
+    config
+}
+
+/// Shorter values are left alone when scrubbing free text, since they'd mask
+/// ordinary words as often as credentials.
+const MIN_SCRUBBED_LEN: usize = 8;
+
+/// Credential values in `config`, `providers` and the keychain (`stored`),
+/// scrubbed from free text such as error messages before it is bundled.
+fn secret_values(config: &AppConfig, providers: &[ApiProvider], stored: &[String]) -> Vec<String> {
+    let mut config = config.clone();
+    let mut secrets: Vec<String> = config_secrets(&mut config)
+        .into_iter()
+        .map(std::mem::take)
+        .chain(stored.iter().cloned())
+        .collect();
+    for provider in providers {
+        secrets.extend(provider.env.values().cloned());
+        if let Some(settings) = &provider.oauth {
+            secrets.push(settings.client_secret.clone());
+            secrets.push(settings.refresh_token.clone());
+        }
+    }
+    secrets.retain(|secret| secret.len() >= MIN_SCRUBBED_LEN);
+    // Longest first, so a secret containing another is masked whole
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    secrets
+}
+
+/// Pretty JSON of `value` with `secrets` scrubbed, as a bundle file.
+fn bundle_json<T: Serialize>(value: &T, secrets: &[String]) -> Result<Vec<u8>, AppError> {
+    let text = serde_json::to_string_pretty(value)?;
+    Ok(support_bundle::scrub(&text, secrets, REDACTED).into_bytes())
+}
+
+/// Log `lines` with `secrets` scrubbed, as a bundle file.
+fn bundle_log(lines: &[String], secrets: &[String]) -> Vec<u8> {
+    let text = lines
+        .iter()
+        .fold(String::new(), |text, line| text + line + "\n");
+    support_bundle::scrub(&text, secrets, REDACTED).into_bytes()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionInfo {
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    created_at: String,
+}
+
+/// Writes a zip for issue reports with version info, the config and providers
+/// with their secrets stripped, diagnostics, provider fetch health (the last
+/// error of each), command timings and the warnings and errors logged since
+/// launch. Returns the bundle's path.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.create_support_bundle", skip_all)]
+pub async fn create_support_bundle(state: State<'_, AppState>) -> Result<String, AppError> {
+    let config = state.config.lock().await.clone();
+    let saved = providers::load_providers(&state.config_dir)?;
+    let secrets = secret_values(&config, &saved, &credentials::stored_values().await);
+    let now = chrono::Local::now();
+
+    let version = VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        created_at: now.to_rfc3339(),
+    };
+    let config = redact_config(config);
+    let saved: Vec<ApiProvider> = saved.into_iter().map(providers::redact_provider).collect();
+    let status = providers::get_provider_status(state.clone()).await?;
+    let diagnostics = get_diagnostics(state.clone()).await?;
+
+    let files = [
+        ("version.json", bundle_json(&version, &secrets)?),
+        ("config.json", bundle_json(&config, &secrets)?),
+        ("providers.json", bundle_json(&saved, &secrets)?),
+        ("provider_status.json", bundle_json(&status, &secrets)?),
+        ("diagnostics.json", bundle_json(&diagnostics, &secrets)?),
+        ("perf.json", bundle_json(&perf::stats(), &secrets)?),
+        ("recent.log", bundle_log(&logs::recent(), &secrets)),
+    ];
+    let stamp = now.format("%Y%m%d-%H%M%S").to_string();
+    let path = support_bundle::write_bundle(&state.config_dir, &stamp, &files)
+        .map_err(|e| AppError::Config(e.to_string()))?;
+    Ok(path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_config_strips_secrets() {
+        let mut config = AppConfig::default();
+        config.data_sources.openai.admin_key = Some("sk-admin-secret".to_string());
+        config.alerts.push.ntfy_topic = "my-private-topic".to_string();
+        config.guard.token = "guard-token".to_string();
+
+        let secrets = secret_values(&config, &[], &["ntfy-token-stored".to_string()]);
+        assert!(secrets.contains(&"sk-admin-secret".to_string()));
+        assert!(secrets.contains(&"ntfy-token-stored".to_string()));
+        assert!(secrets.contains(&"guard-token".to_string()));
+
+        let redacted = redact_config(config);
+        assert_eq!(
+            redacted.data_sources.openai.admin_key.as_deref(),
+            Some(REDACTED)
+        );
+        assert_eq!(redacted.alerts.push.ntfy_topic, REDACTED);
+        assert_eq!(redacted.guard.token, REDACTED);
+        // Unset credentials stay unset so the bundle shows what is configured
+        assert!(redacted.data_sources.cursor.session_token.is_none());
+        assert!(redacted.integrations.notion.token.is_empty());
+    }
+
+    #[test]
+    fn test_bundle_log_scrubs_secrets() {
+        let lines = vec![
+            "2025-01-06T09:00:00+00:00 WARN Provider failed: 401 for sk-live-1234".to_string(),
+            "2025-01-06T09:05:00+00:00 ERROR Refresh failed: timed out".to_string(),
+        ];
+        let log = String::from_utf8(bundle_log(&lines, &["sk-live-1234".to_string()])).unwrap();
+        assert_eq!(
+            log,
+            format!(
+                "2025-01-06T09:00:00+00:00 WARN Provider failed: 401 for {REDACTED}\n\
+                 2025-01-06T09:05:00+00:00 ERROR Refresh failed: timed out\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use crate::error::AppError;
+use crate::guard;
+
+/// Returns a new random bearer token for the guard endpoint. It takes effect once
+/// the config is saved.
+#[tauri::command]
+#[tracing::instrument(name = "command.generate_guard_token", skip_all)]
+pub fn generate_guard_token() -> Result<String, AppError> {
+    guard::generate_token().map_err(|e| AppError::Config(e.to_string()))
+}
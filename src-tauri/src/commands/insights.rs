@@ -0,0 +1,245 @@
+use crate::error::AppError;
+use crate::services::{ccusage, claude_logs, pricing, series, sources, time_profile};
+use crate::state::AppState;
+use crate::storage;
+use crate::types::{
+    ClaudeSession, DualSeries, JournalPoint, KeyUsage, MemberUsage, OrgComparison, SessionBlock,
+    TimeProfile, TopicUsage,
+};
+use tauri::State;
+
+const DEFAULT_PROFILE_DAYS: u32 = 30;
+const MAX_PROFILE_DAYS: u32 = 365;
+const DEFAULT_TOPIC_DAYS: u32 = 7;
+const DEFAULT_SERIES_DAYS: u32 = 30;
+const MAX_SERIES_DAYS: u32 = 3660;
+
+/// Aggregates Claude Code usage from the last `days` days (default 30) into a
+/// time-of-day / weekday profile.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_time_profile", skip_all)]
+pub async fn get_time_profile(
+    state: State<'_, AppState>,
+    days: Option<u32>,
+) -> Result<TimeProfile, AppError> {
+    let days = days.unwrap_or(DEFAULT_PROFILE_DAYS);
+    if days == 0 || days > MAX_PROFILE_DAYS {
+        return Err(AppError::Validation(format!(
+            "days must be between 1 and {MAX_PROFILE_DAYS}"
+        )));
+    }
+
+    let since = chrono::Local::now() - chrono::Duration::days(i64::from(days));
+    let (data_dirs, timeout) = {
+        let config = state.config.lock().await;
+        (
+            config.data_sources.claude_code.data_dirs(),
+            config.timeouts.pricing(),
+        )
+    };
+    let entries = tokio::task::spawn_blocking(move || claude_logs::read_entries(since, &data_dirs))
+        .await
+        .map_err(|e| AppError::Fetch(e.to_string()))?;
+    let prices = pricing::get_prices(timeout).await;
+
+    Ok(time_profile::build_profile(&entries, days, prices.as_ref()))
+}
+
+/// Claude Code cost per conversation title over the last `days` days (default
+/// 7), highest first. Titles come from Claude Code's conversation summaries,
+/// falling back to each conversation's first prompt.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_topics_breakdown", skip_all)]
+pub async fn get_topics_breakdown(
+    state: State<'_, AppState>,
+    days: Option<u32>,
+) -> Result<Vec<TopicUsage>, AppError> {
+    let days = days.unwrap_or(DEFAULT_TOPIC_DAYS);
+    if days == 0 || days > MAX_PROFILE_DAYS {
+        return Err(AppError::Validation(format!(
+            "days must be between 1 and {MAX_PROFILE_DAYS}"
+        )));
+    }
+
+    let since = chrono::Local::now() - chrono::Duration::days(i64::from(days));
+    let (data_dirs, timeout) = {
+        let config = state.config.lock().await;
+        (
+            config.data_sources.claude_code.data_dirs(),
+            config.timeouts.pricing(),
+        )
+    };
+    let transcripts =
+        tokio::task::spawn_blocking(move || claude_logs::read_transcripts(since, &data_dirs))
+            .await
+            .map_err(|e| AppError::Fetch(e.to_string()))?;
+    let prices = pricing::get_prices(timeout).await;
+
+    Ok(claude_logs::topic_breakdown(&transcripts, prices.as_ref()))
+}
+
+/// Claude Code 5-hour billing blocks from the last day, oldest first. The
+/// active block, if any, is last.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_session_blocks", skip_all)]
+pub async fn get_session_blocks(state: State<'_, AppState>) -> Result<Vec<SessionBlock>, AppError> {
+    let (source, timeouts, features) = {
+        let config = state.config.lock().await;
+        (
+            config.data_sources.claude_code.clone(),
+            config.timeouts,
+            config.features.clone(),
+        )
+    };
+    ccusage::fetch_blocks(&source, &timeouts, &features)
+        .await
+        .map_err(|e| AppError::Fetch(e.to_string()))
+}
+
+/// Claude Code sessions from the last 30 days as reported by `ccusage session`,
+/// most recently active first. Fails when ccusage lacks the command.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_claude_sessions", skip_all)]
+pub async fn get_claude_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<ClaudeSession>, AppError> {
+    let (source, timeouts) = {
+        let config = state.config.lock().await;
+        (config.data_sources.claude_code.clone(), config.timeouts)
+    };
+    ccusage::fetch_sessions(&source, &timeouts)
+        .await
+        .map_err(|e| AppError::Fetch(e.to_string()))
+}
+
+/// How `date`'s (default today) stored Claude Code usage evolved through the
+/// day, one point per save that changed it. Only today's trail is kept once a
+/// day is over.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_usage_journal", skip_all)]
+pub async fn get_usage_journal(
+    state: State<'_, AppState>,
+    date: Option<String>,
+) -> Result<Vec<JournalPoint>, AppError> {
+    let date = match date {
+        Some(date) => chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|_| AppError::Validation("date must be in YYYY-MM-DD format".into()))?,
+        None => chrono::Local::now().date_naive(),
+    };
+    storage::load_day_journal(&state.config_dir, &date.format("%Y-%m-%d").to_string())
+        .map_err(|e| AppError::Fetch(e.to_string()))
+}
+
+/// Daily cost and token totals over the last `days` days (default 30) as
+/// aligned arrays for the usage chart. Long ranges are combined into weekly
+/// points using the configured chart aggregation.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_dual_series", skip_all)]
+pub async fn get_dual_series(
+    state: State<'_, AppState>,
+    days: Option<u32>,
+) -> Result<DualSeries, AppError> {
+    let days = days.unwrap_or(DEFAULT_SERIES_DAYS);
+    if days == 0 || days > MAX_SERIES_DAYS {
+        return Err(AppError::Validation(format!(
+            "days must be between 1 and {MAX_SERIES_DAYS}"
+        )));
+    }
+    let aggregation = state.config.lock().await.chart_aggregation;
+    let usage = state
+        .usage_snapshot()
+        .await
+        .ok_or_else(|| AppError::Fetch("No usage data loaded yet".into()))?;
+    let today = chrono::Local::now().date_naive();
+    // The summary only carries recent history, so older days come from the shards
+    let first = (today - chrono::Duration::days(i64::from(days) - 1))
+        .format("%Y-%m-%d")
+        .to_string();
+    let carried_from = usage
+        .daily_usage
+        .iter()
+        .map(|day| day.date.clone())
+        .min()
+        .unwrap_or_else(|| today.format("%Y-%m-%d").to_string());
+    let mut daily = if first < carried_from {
+        storage::load_history_range(&state.config_dir, &first, &carried_from)
+            .map_err(|e| AppError::Fetch(e.to_string()))?
+    } else {
+        Vec::new()
+    };
+    daily.retain(|day| day.date < carried_from);
+    daily.extend(usage.daily_usage.iter().cloned());
+    Ok(series::build_dual_series(&daily, today, days, aggregation))
+}
+
+/// Per-API-key usage for `source`, including its per-key sub-sources (e.g.
+/// `"proxy"` covers every `proxy:<key>` report), highest monthly cost first.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_key_breakdown", skip_all)]
+pub async fn get_key_breakdown(
+    state: State<'_, AppState>,
+    source: String,
+) -> Result<Vec<KeyUsage>, AppError> {
+    state
+        .usage_snapshot()
+        .await
+        .map(|usage| sources::key_breakdown(&usage.sources, &source))
+        .ok_or_else(|| AppError::Fetch("No usage data loaded yet".into()))
+}
+
+/// Keys and members across all sources ranked by this month's cost, optionally
+/// limited to the top `limit`.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_member_leaderboard", skip_all)]
+pub async fn get_member_leaderboard(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<MemberUsage>, AppError> {
+    let mut leaderboard = state
+        .usage_snapshot()
+        .await
+        .map(|usage| sources::member_leaderboard(&usage.sources))
+        .ok_or_else(|| AppError::Fetch("No usage data loaded yet".into()))?;
+    if let Some(limit) = limit {
+        leaderboard.truncate(limit);
+    }
+    Ok(leaderboard)
+}
+
+/// This month's spend compared with the members reported by team and
+/// organization sources, or `None` when turned off or no source lists other
+/// members.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_org_comparison", skip_all)]
+pub async fn get_org_comparison(
+    state: State<'_, AppState>,
+) -> Result<Option<OrgComparison>, AppError> {
+    let trends = state.config.lock().await.trends.clone();
+    if !trends.org_comparison {
+        return Ok(None);
+    }
+    let usage = state
+        .usage_snapshot()
+        .await
+        .ok_or_else(|| AppError::Fetch("No usage data loaded yet".into()))?;
+    let members = sources::member_leaderboard(&usage.sources);
+    let member = trends
+        .org_member
+        .as_deref()
+        .map(str::trim)
+        .filter(|member| !member.is_empty());
+    Ok(sources::org_comparison(
+        &members,
+        member,
+        usage.this_month.cost,
+    ))
+}
@@ -0,0 +1,192 @@
+use crate::app_lock;
+use crate::error::AppError;
+use crate::services::credentials::Credential;
+use crate::services::push::{self, PushMessage};
+use crate::services::{digest, keychain, remote_snapshot};
+use crate::state::AppState;
+use tauri::State;
+
+/// Stores the SMTP password for the configured digest username in the system keychain.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.set_smtp_password", skip_all)]
+pub async fn set_smtp_password(
+    state: State<'_, AppState>,
+    password: String,
+) -> Result<(), AppError> {
+    let username = state
+        .config
+        .lock()
+        .await
+        .integrations
+        .email_digest
+        .username
+        .trim()
+        .to_string();
+    if username.is_empty() {
+        return Err(AppError::Validation(
+            "Save an SMTP username before setting the password".into(),
+        ));
+    }
+    if password.is_empty() {
+        return Err(AppError::Validation("password must not be empty".into()));
+    }
+    keychain::set_secret(&digest::password_account(&username), &password)
+        .await
+        .map_err(|e| AppError::Config(e.to_string()))
+}
+
+/// Sends the weekly digest immediately using the saved settings.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.send_test_digest", skip_all)]
+pub async fn send_test_digest(state: State<'_, AppState>) -> Result<(), AppError> {
+    let config = state.config.lock().await.integrations.email_digest.clone();
+    let daily = state
+        .usage_snapshot()
+        .await
+        .map(|usage| usage.daily_usage.clone())
+        .ok_or_else(|| AppError::Fetch("No usage data loaded yet".into()))?;
+
+    let result = digest::send_weekly_digest(&config, &daily).await;
+    let error = result.as_ref().err().map(ToString::to_string);
+    state
+        .diagnostics
+        .lock()
+        .await
+        .record_integration("emailDigest", result);
+    error.map_or(Ok(()), |e| Err(AppError::Fetch(e)))
+}
+
+/// Stores the passphrase remote snapshots are encrypted with in the system keychain.
+#[tauri::command]
+#[tracing::instrument(name = "command.set_snapshot_passphrase", skip_all)]
+pub async fn set_snapshot_passphrase(passphrase: String) -> Result<(), AppError> {
+    remote_snapshot::validate_passphrase(&passphrase)
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    keychain::set_secret(remote_snapshot::PASSPHRASE_ACCOUNT, &passphrase)
+        .await
+        .map_err(|e| AppError::Config(e.to_string()))
+}
+
+/// Removes the snapshot passphrase; pushes fail until a new one is set.
+#[tauri::command]
+#[tracing::instrument(name = "command.clear_snapshot_passphrase", skip_all)]
+pub async fn clear_snapshot_passphrase() -> Result<(), AppError> {
+    keychain::delete_secret(remote_snapshot::PASSPHRASE_ACCOUNT)
+        .await
+        .map_err(|e| AppError::Config(e.to_string()))
+}
+
+/// Whether a snapshot passphrase is stored. The passphrase itself is never returned.
+#[tauri::command]
+#[tracing::instrument(name = "command.has_snapshot_passphrase", skip_all)]
+pub async fn has_snapshot_passphrase() -> Result<bool, AppError> {
+    keychain::get_secret(remote_snapshot::PASSPHRASE_ACCOUNT)
+        .await
+        .map(|secret| secret.is_some())
+        .map_err(|e| AppError::Config(e.to_string()))
+}
+
+/// Encrypts and uploads the current usage snapshot immediately.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.push_snapshot_now", skip_all)]
+pub async fn push_snapshot_now(state: State<'_, AppState>) -> Result<(), AppError> {
+    let (config, timeout) = {
+        let config = state.config.lock().await;
+        (
+            config.integrations.remote_snapshot.clone(),
+            config.timeouts.integration(),
+        )
+    };
+    let usage = state
+        .usage_snapshot()
+        .await
+        .ok_or_else(|| AppError::Fetch("No usage data loaded yet".into()))?;
+
+    let result = remote_snapshot::push_snapshot(&config, &usage, timeout).await;
+    let error = result.as_ref().err().map(ToString::to_string);
+    state
+        .diagnostics
+        .lock()
+        .await
+        .record_integration("remoteSnapshot", result);
+    error.map_or(Ok(()), |e| Err(AppError::Fetch(e)))
+}
+
+/// Sends a test notification through the saved push service settings.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.send_test_push", skip_all)]
+pub async fn send_test_push(state: State<'_, AppState>) -> Result<(), AppError> {
+    let (config, timeout) = {
+        let config = state.config.lock().await;
+        (config.alerts.push.clone(), config.timeouts.integration())
+    };
+    let message = PushMessage {
+        title: "TokenMeter".to_string(),
+        body: "Budget alerts will arrive here.".to_string(),
+        high_priority: false,
+        persistent: false,
+    };
+
+    let result = push::send(&config, &message, timeout).await;
+    let error = result.as_ref().err().map(ToString::to_string);
+    state
+        .diagnostics
+        .lock()
+        .await
+        .record_integration("push", result);
+    error.map_or(Ok(()), |e| Err(AppError::Fetch(e)))
+}
+
+/// Stores `value` for `credential` in the system keychain.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.set_credential", skip_all)]
+pub async fn set_credential(
+    state: State<'_, AppState>,
+    credential: Credential,
+    value: String,
+) -> Result<(), AppError> {
+    app_lock::ensure_unlocked(state.config.lock().await.app_lock)?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(AppError::Validation("value must not be empty".into()));
+    }
+    keychain::set_secret(credential.account(), value)
+        .await
+        .map_err(|e| AppError::Config(e.to_string()))
+}
+
+/// Removes `credential` from the system keychain.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.clear_credential", skip_all)]
+pub async fn clear_credential(
+    state: State<'_, AppState>,
+    credential: Credential,
+) -> Result<(), AppError> {
+    app_lock::ensure_unlocked(state.config.lock().await.app_lock)?;
+    keychain::delete_secret(credential.account())
+        .await
+        .map_err(|e| AppError::Config(e.to_string()))
+}
+
+/// Credentials stored in the system keychain. Their values are never returned.
+#[tauri::command]
+#[tracing::instrument(name = "command.get_stored_credentials", skip_all)]
+pub async fn get_stored_credentials() -> Result<Vec<Credential>, AppError> {
+    let mut stored = Vec::new();
+    for credential in Credential::ALL {
+        if keychain::get_secret(credential.account())
+            .await
+            .map_err(|e| AppError::Config(e.to_string()))?
+            .is_some()
+        {
+            stored.push(credential);
+        }
+    }
+    Ok(stored)
+}
@@ -1,2 +1,8 @@
+pub mod diagnostics;
+pub mod guard;
+pub mod insights;
+pub mod integrations;
+pub mod models;
+pub mod profiles;
 pub mod providers;
 pub mod usage;
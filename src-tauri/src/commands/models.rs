@@ -0,0 +1,23 @@
+use crate::error::AppError;
+use crate::services::pricing;
+use crate::state::AppState;
+use crate::types::ModelInfo;
+use tauri::State;
+
+/// Returns models.dev metadata (context window, limits, knowledge cutoff) for a model.
+///
+/// Resolves to `None` when the model is unknown or models.dev is unreachable.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_model_info", skip_all)]
+pub async fn get_model_info(
+    state: State<'_, AppState>,
+    model: String,
+) -> Result<Option<ModelInfo>, AppError> {
+    let model = model.trim();
+    if model.is_empty() {
+        return Err(AppError::Validation("model must not be empty".into()));
+    }
+    let timeout = state.config.lock().await.timeouts.pricing();
+    Ok(pricing::get_model_info(model, timeout).await)
+}
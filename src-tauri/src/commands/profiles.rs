@@ -0,0 +1,59 @@
+use crate::commands::usage;
+use crate::error::AppError;
+use crate::profiles::{self, ProfileList};
+use crate::state::AppState;
+use crate::tray;
+use tauri::{AppHandle, Emitter, State};
+
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_profiles", skip_all)]
+pub fn get_profiles(state: State<'_, AppState>) -> ProfileList {
+    profiles::list_profiles(&state.config_dir)
+}
+
+/// Saves the current settings as the active profile and loads `name` in their
+/// place (creating it from the current settings if new), then restarts the
+/// proxy and guard as needed and refreshes usage for the new sources.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.switch_profile", skip_all)]
+pub async fn switch_profile(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<ProfileList, AppError> {
+    profiles::validate_name(&name).map_err(|e| AppError::Validation(e.to_string()))?;
+
+    // Hold the refresh lock so a running refresh never reads half-copied settings
+    let switched = {
+        let _refresh_guard = state.usage_refresh_lock.lock().await;
+        profiles::switch_profile(&state.config_dir, &name)
+            .map_err(|e| AppError::Config(e.to_string()))?
+    };
+    let list = profiles::list_profiles(&state.config_dir);
+    if !switched {
+        return Ok(list);
+    }
+
+    state.reload_provider_health().await;
+    let config = state.read_config();
+    let previous = std::mem::replace(&mut *state.config.lock().await, config.clone());
+    state.move_credentials_to_keychain().await;
+    if previous.data_sources.proxy != config.data_sources.proxy {
+        state.restart_proxy(&config.data_sources.proxy).await;
+    }
+    if previous.guard != config.guard {
+        state.restart_guard(&app, &config.guard).await;
+    }
+
+    tray::set_profiles(list.clone());
+    let _ = app.emit("config-updated", &config);
+    let _ = app.emit("profile-switched", &list);
+
+    // Sources differ between profiles, so the current summary no longer applies
+    if let Err(e) = usage::run_refresh(&app, &state, false).await {
+        tracing::warn!("Refresh after profile switch failed: {e}");
+    }
+    Ok(list)
+}
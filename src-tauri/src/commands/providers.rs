@@ -1,12 +1,23 @@
-use crate::config::ApiProvider;
+use crate::app_lock;
+use crate::config::{
+    ApiProvider, FetchStep, GraphqlQuery, HttpMethod, OAuthGrant, PaginationStrategy,
+    ProviderOAuth, ProviderPagination, ProviderRequest, ProviderRetry,
+};
 use crate::error::AppError;
-use crate::services::{script_runner, shell_utils};
+use crate::services::{json_path, oauth, provider_runner, script_runner};
 use crate::state::AppState;
+use crate::storage;
+use crate::tray;
+use crate::types::{
+    CachedProviderResult, ProviderDailyUsage, ProviderHealth, ProviderHistoryPoint, ProviderStatus,
+    ProviderUsageResult,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::process::Command;
-use tauri::State;
+use std::path::Path;
+use std::time::Instant;
+use tauri::{AppHandle, State};
 
 const ALLOWED_COMMANDS: &[&str] = &["curl", "wget", "http", "httpie"];
 const DANGEROUS_PATTERNS: &[&str] = &[
@@ -30,15 +41,6 @@ const DANGEROUS_VALUE_CHARS: &[char] = &[
     ';', '&', '|', '`', '$', '(', ')', '{', '}', '[', ']', '<', '>', '\n', '\r', '\0', '\'', '"',
 ];
 
-fn parse_fetch_script(
-    script: &str,
-    env: &HashMap<String, String>,
-) -> Result<Vec<String>, AppError> {
-    shell_utils::parse_command(script, env).ok_or_else(|| {
-        AppError::Validation("Invalid fetch script: unmatched quotes or escape sequences".into())
-    })
-}
-
 /// Validates provider ID to prevent path traversal attacks.
 fn validate_provider_id(id: &str) -> Result<(), AppError> {
     let has_path_chars = id.contains('/') || id.contains('\\') || id.contains('\0');
@@ -57,7 +59,7 @@ fn validate_provider_id(id: &str) -> Result<(), AppError> {
 }
 
 /// Validates environment variable keys and values to prevent injection.
-fn validate_env(env: &std::collections::HashMap<String, String>) -> Result<(), AppError> {
+fn validate_env(env: &HashMap<String, String>) -> Result<(), AppError> {
     for (key, value) in env {
         if key.is_empty() || key.contains('=') || key.contains('\0') {
             return Err(AppError::Validation(format!(
@@ -79,6 +81,36 @@ fn validate_env(env: &std::collections::HashMap<String, String>) -> Result<(), A
     Ok(())
 }
 
+/// Validates that the declared result currency looks like an ISO 4217 code.
+fn validate_currency(currency: Option<&str>) -> Result<(), AppError> {
+    match currency {
+        Some(code) if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) => Err(
+            AppError::Validation(format!("Invalid currency code: '{code}'")),
+        ),
+        _ => Ok(()),
+    }
+}
+
+const MAX_UNIT_LABEL_LEN: usize = 32;
+
+/// Validates that the unit label fits on one short tray line.
+fn validate_unit_label(label: Option<&str>) -> Result<(), AppError> {
+    match label {
+        Some(label)
+            if label.chars().count() > MAX_UNIT_LABEL_LEN || label.contains(char::is_control) =>
+        {
+            Err(AppError::Validation(format!(
+                "Unit label must be a single line of at most {MAX_UNIT_LABEL_LEN} characters"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_transform_paths(paths: &BTreeMap<String, String>) -> Result<(), AppError> {
+    json_path::check_mapping(paths).map_err(|e| AppError::Validation(format!("{e:#}")))
+}
+
 fn validate_fetch_script(script: &str) -> Result<(), AppError> {
     let trimmed = script.trim();
 
@@ -122,11 +154,169 @@ fn validate_fetch_script(script: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-// Tauri commands require owned types for IPC serialization
-#[allow(clippy::needless_pass_by_value)]
-#[tauri::command]
-pub fn get_providers(state: State<'_, AppState>) -> Result<Vec<ApiProvider>, AppError> {
-    let providers_dir = state.config_dir.join("providers");
+fn validate_request(request: &ProviderRequest) -> Result<(), AppError> {
+    let url = request.url.trim().to_lowercase();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(AppError::Validation(
+            "Request URL must start with http:// or https://".into(),
+        ));
+    }
+    if let Some(name) = request
+        .headers
+        .keys()
+        .find(|name| reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err())
+    {
+        return Err(AppError::Validation(format!(
+            "Invalid request header name: '{name}'"
+        )));
+    }
+    if request.method == HttpMethod::Get && request.body.is_some() {
+        return Err(AppError::Validation(
+            "GET requests cannot have a body".into(),
+        ));
+    }
+    if let Some(pagination) = &request.pagination {
+        validate_pagination(pagination)?;
+    }
+    if let Some(graphql) = &request.graphql {
+        if request.body.is_some() {
+            return Err(AppError::Validation(
+                "GraphQL requests send the query as their body".into(),
+            ));
+        }
+        validate_graphql(graphql)?;
+    }
+    Ok(())
+}
+
+fn validate_graphql(graphql: &GraphqlQuery) -> Result<(), AppError> {
+    if graphql.query.trim().is_empty() {
+        return Err(AppError::Validation("GraphQL query is required".into()));
+    }
+    // Placeholders may stand for unquoted values, so only check them at fetch
+    let variables = graphql.variables.as_deref().map(str::trim);
+    if let Some(text) = variables.filter(|text| !text.is_empty() && !text.contains("${")) {
+        let is_object =
+            serde_json::from_str::<serde_json::Value>(text).is_ok_and(|value| value.is_object());
+        if !is_object {
+            return Err(AppError::Validation(
+                "GraphQL variables must be a JSON object".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+const MAX_PAGES: u32 = 50;
+
+fn validate_pagination(pagination: &ProviderPagination) -> Result<(), AppError> {
+    if !(1..=MAX_PAGES).contains(&pagination.max_pages) {
+        return Err(AppError::Validation(format!(
+            "Pagination max pages must be between 1 and {MAX_PAGES}"
+        )));
+    }
+    let needs_param = pagination.strategy != PaginationStrategy::NextUrl;
+    if needs_param && pagination.param.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Pagination needs the query parameter it sets".into(),
+        ));
+    }
+    let needs_next_path = pagination.strategy != PaginationStrategy::Page;
+    if needs_next_path && pagination.next_path.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Pagination needs the path of the next cursor or URL".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_oauth(settings: &ProviderOAuth) -> Result<(), AppError> {
+    let url = settings.token_url.trim().to_lowercase();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(AppError::Validation(
+            "OAuth token URL must start with http:// or https://".into(),
+        ));
+    }
+    if settings.client_id.trim().is_empty() {
+        return Err(AppError::Validation("OAuth client ID is required".into()));
+    }
+    if settings.grant == OAuthGrant::RefreshToken && settings.refresh_token.trim().is_empty() {
+        return Err(AppError::Validation(
+            "OAuth refresh token is required for the refresh-token grant".into(),
+        ));
+    }
+    Ok(())
+}
+
+const MAX_RETRY_ATTEMPTS: u32 = 5;
+const RETRY_BACKOFF_MS: std::ops::RangeInclusive<u64> = 100..=30_000;
+
+fn validate_retry(retry: &ProviderRetry) -> Result<(), AppError> {
+    if retry.attempts > MAX_RETRY_ATTEMPTS {
+        return Err(AppError::Validation(format!(
+            "Retry attempts must be at most {MAX_RETRY_ATTEMPTS}"
+        )));
+    }
+    if !RETRY_BACKOFF_MS.contains(&retry.backoff_ms) {
+        return Err(AppError::Validation(format!(
+            "Retry backoff must be between {} and {} ms",
+            RETRY_BACKOFF_MS.start(),
+            RETRY_BACKOFF_MS.end()
+        )));
+    }
+    Ok(())
+}
+
+const MAX_FETCH_STEPS: usize = 5;
+
+fn validate_steps(steps: &[FetchStep]) -> Result<(), AppError> {
+    if steps.len() > MAX_FETCH_STEPS {
+        return Err(AppError::Validation(format!(
+            "A provider can have at most {MAX_FETCH_STEPS} fetch steps"
+        )));
+    }
+    for step in steps {
+        validate_request(&step.request)?;
+        for (name, path) in &step.extract {
+            let valid_name =
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_name || DANGEROUS_ENV_VARS.contains(&name.to_uppercase().as_str()) {
+                return Err(AppError::Validation(format!(
+                    "Invalid fetch step variable name: '{name}'"
+                )));
+            }
+            if path.trim().is_empty() {
+                return Err(AppError::Validation(format!(
+                    "Fetch step variable '{name}' needs a response path"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks whichever way the provider fetches: its request or its fetch script,
+/// and the steps run before it.
+fn validate_fetch(provider: &ApiProvider) -> Result<(), AppError> {
+    if let Some(settings) = &provider.oauth {
+        validate_oauth(settings)?;
+    }
+    validate_steps(&provider.steps)?;
+    provider.request.as_ref().map_or_else(
+        || validate_fetch_script(&provider.fetch_script),
+        validate_request,
+    )
+}
+
+/// Placeholder shown instead of provider secrets.
+pub const REDACTED: &str = "••••••••";
+
+/// Reads every provider saved under `config_dir`, skipping unparsable files.
+///
+/// # Errors
+/// Returns an error if the providers directory cannot be created or read.
+pub fn load_providers(config_dir: &Path) -> Result<Vec<ApiProvider>, AppError> {
+    let providers_dir = config_dir.join("providers");
     fs::create_dir_all(&providers_dir)?;
 
     let mut providers = Vec::new();
@@ -138,115 +328,704 @@ pub fn get_providers(state: State<'_, AppState>) -> Result<Vec<ApiProvider>, App
                 match serde_json::from_str::<ApiProvider>(&content) {
                     Ok(provider) => providers.push(provider),
                     Err(e) => {
-                        eprintln!("Failed to parse provider {}: {}", entry.path().display(), e);
+                        tracing::error!(
+                            "Failed to parse provider {}: {}",
+                            entry.path().display(),
+                            e
+                        );
                     }
                 }
             }
         }
     }
 
+    sort_providers(&mut providers);
     Ok(providers)
 }
 
+/// Orders providers for display: ungrouped ones first, then each group in the
+/// order of its first member, and by `sort_order` then name within either.
+pub fn sort_providers(providers: &mut [ApiProvider]) {
+    let mut group_ranks: HashMap<String, u32> = HashMap::new();
+    for provider in providers.iter() {
+        if let Some(group) = provider.group_name() {
+            let rank = group_ranks.entry(group.to_string()).or_insert(u32::MAX);
+            *rank = (*rank).min(provider.sort_order);
+        }
+    }
+    providers.sort_by_cached_key(|provider| {
+        let group = provider
+            .group_name()
+            .map(|group| (group_ranks[group], group.to_string()));
+        (
+            group,
+            provider.sort_order,
+            provider.name.to_lowercase(),
+            provider.id.clone(),
+        )
+    });
+}
+
+/// Copy of `provider` safe to hand out for display: environment values are
+/// masked and the fetch script, which may embed credentials, is cleared, as
+/// are the header values and bodies of its request and steps and the OAuth
+/// secrets.
+#[must_use]
+pub fn redact_provider(mut provider: ApiProvider) -> ApiProvider {
+    for value in provider.env.values_mut() {
+        *value = REDACTED.to_string();
+    }
+    provider.fetch_script.clear();
+    let steps = provider.steps.iter_mut().map(|step| &mut step.request);
+    for request in provider.request.iter_mut().chain(steps) {
+        for value in request.headers.values_mut() {
+            *value = REDACTED.to_string();
+        }
+        request.body = None;
+        if let Some(graphql) = &mut request.graphql {
+            graphql.variables = None;
+        }
+    }
+    if let Some(settings) = &mut provider.oauth {
+        for secret in [&mut settings.client_secret, &mut settings.refresh_token] {
+            if !secret.is_empty() {
+                *secret = REDACTED.to_string();
+            }
+        }
+    }
+    provider
+}
+
+/// Every provider with its secrets, refused while the app lock is engaged.
+// Tauri commands require owned types for IPC serialization
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_providers", skip_all)]
+pub async fn get_providers(state: State<'_, AppState>) -> Result<Vec<ApiProvider>, AppError> {
+    let lock = state.config.lock().await.app_lock;
+    app_lock::ensure_unlocked(lock)?;
+    load_providers(&state.config_dir)
+}
+
+const DEFAULT_HISTORY_DAYS: u32 = 30;
+const MAX_HISTORY_DAYS: u32 = 365;
+
+/// Fetch health of every provider, in list order, so degraded providers can
+/// be shown apart from healthy ones.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_provider_status", skip_all)]
+pub async fn get_provider_status(
+    state: State<'_, AppState>,
+) -> Result<Vec<ProviderStatus>, AppError> {
+    let providers = load_providers(&state.config_dir)?;
+    let health = state.provider_health.lock().await;
+    Ok(providers
+        .into_iter()
+        .map(|provider| {
+            let health = health
+                .get(&provider.id)
+                .cloned()
+                .unwrap_or_else(|| ProviderHealth::of(&provider));
+            ProviderStatus {
+                level: health.level(),
+                id: provider.id,
+                name: provider.name,
+                enabled: provider.enabled,
+                health,
+            }
+        })
+        .collect())
+}
+
+/// Results fetched for provider `id` over the last `range` days (default 30),
+/// oldest first, for charting balance and spend trends.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_provider_history", skip_all)]
+pub fn get_provider_history(
+    state: State<'_, AppState>,
+    id: String,
+    range: Option<u32>,
+) -> Result<Vec<ProviderHistoryPoint>, AppError> {
+    validate_provider_id(&id)?;
+    let days = range.unwrap_or(DEFAULT_HISTORY_DAYS);
+    if days == 0 || days > MAX_HISTORY_DAYS {
+        return Err(AppError::Validation(format!(
+            "range must be between 1 and {MAX_HISTORY_DAYS} days"
+        )));
+    }
+    let since = chrono::Utc::now() - chrono::TimeDelta::days(i64::from(days));
+    storage::load_provider_history(&state.config_dir, &id, since)
+        .map_err(|e| AppError::Fetch(e.to_string()))
+}
+
+/// Per-day usage provider `id` reported over the last `range` days (default
+/// 30), oldest first; empty unless its transform returns a `daily` series.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_provider_daily", skip_all)]
+pub fn get_provider_daily(
+    state: State<'_, AppState>,
+    id: String,
+    range: Option<u32>,
+) -> Result<Vec<ProviderDailyUsage>, AppError> {
+    validate_provider_id(&id)?;
+    let days = range.unwrap_or(DEFAULT_HISTORY_DAYS);
+    if days == 0 || days > MAX_HISTORY_DAYS {
+        return Err(AppError::Validation(format!(
+            "range must be between 1 and {MAX_HISTORY_DAYS} days"
+        )));
+    }
+    let since = chrono::Local::now().date_naive() - chrono::TimeDelta::days(i64::from(days) - 1);
+    storage::load_provider_daily(&state.config_dir, &id, since)
+        .map_err(|e| AppError::Fetch(e.to_string()))
+}
+
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
-pub fn save_provider(state: State<'_, AppState>, provider: ApiProvider) -> Result<(), AppError> {
+#[tracing::instrument(name = "command.save_provider", skip_all)]
+pub async fn save_provider(
+    state: State<'_, AppState>,
+    mut provider: ApiProvider,
+) -> Result<(), AppError> {
     validate_provider_id(&provider.id)?;
-    validate_fetch_script(&provider.fetch_script)?;
+    validate_fetch(&provider)?;
     validate_env(&provider.env)?;
+    validate_currency(provider.currency.as_deref())?;
+    validate_unit_label(provider.unit_label.as_deref())?;
+    validate_retry(&provider.retry)?;
+    validate_transform_paths(&provider.transform_paths)?;
+    let env = &provider.env;
+    provider
+        .env_expires_at
+        .retain(|key, _| env.contains_key(key));
+
+    // Tokens issued for old OAuth settings must not outlive them
+    let previous_oauth = load_providers(&state.config_dir)?
+        .into_iter()
+        .find(|p| p.id == provider.id)
+        .and_then(|p| p.oauth);
+    if previous_oauth.is_some() && previous_oauth != provider.oauth {
+        oauth::forget(&provider.id).await;
+    }
+
+    // The editor's copy of the fetch health may predate the latest refresh
+    if let Some(health) = state.provider_health.lock().await.get(&provider.id) {
+        provider.last_fetched.clone_from(&health.last_success);
+        provider.last_error.clone_from(&health.last_error);
+        provider.consecutive_failures = health.consecutive_failures;
+    }
+    write_provider(&state.config_dir, &provider)?;
+    forget_cached_result(&state, &provider.id).await;
+    Ok(())
+}
+
+/// A problem found by `validate_provider`, with the editor field it concerns.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderIssue {
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem `save_provider` or a fetch would report about `provider`'s
+/// settings, found without running anything.
+fn provider_issues(provider: &ApiProvider) -> Vec<ProviderIssue> {
+    let message = |e: AppError| match e {
+        AppError::Validation(message) => message,
+        e => e.to_string(),
+    };
+    let checks = [
+        ("id", validate_provider_id(&provider.id)),
+        ("fetch", validate_fetch(provider)),
+        ("env", validate_env(&provider.env)),
+        ("currency", validate_currency(provider.currency.as_deref())),
+        (
+            "unitLabel",
+            validate_unit_label(provider.unit_label.as_deref()),
+        ),
+        ("retry", validate_retry(&provider.retry)),
+        (
+            "transform",
+            validate_transform_paths(&provider.transform_paths),
+        ),
+    ];
+    let mut issues: Vec<ProviderIssue> = checks
+        .into_iter()
+        .filter_map(|(field, result)| {
+            result.err().map(|e| ProviderIssue {
+                field: field.to_string(),
+                message: message(e),
+            })
+        })
+        .collect();
+    if provider.transform_paths.is_empty() && !provider.transform_script.trim().is_empty() {
+        if let Err(e) = script_runner::check_transform_syntax(&provider.transform_script) {
+            issues.push(ProviderIssue {
+                field: "transform".to_string(),
+                message: e.to_string(),
+            });
+        }
+    }
+    issues
+}
+
+/// Statically checks a provider while it is edited, returning its problems
+/// by field. Nothing is fetched and the transform is only parsed.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.validate_provider", skip_all)]
+pub fn validate_provider(provider: ApiProvider) -> Vec<ProviderIssue> {
+    provider_issues(&provider)
+}
 
-    let providers_dir = state.config_dir.join("providers");
+/// Drops a provider's cached result so its next refresh fetches again.
+async fn forget_cached_result(state: &AppState, id: &str) {
+    let mut cache = state.provider_cache.lock().await;
+    if cache.remove(id).is_some() {
+        if let Err(e) = storage::save_provider_cache(&state.config_dir, &cache) {
+            tracing::warn!("Failed to save provider cache: {e}");
+        }
+    }
+}
+
+fn write_provider(config_dir: &Path, provider: &ApiProvider) -> Result<(), AppError> {
+    let providers_dir = config_dir.join("providers");
     fs::create_dir_all(&providers_dir)?;
 
     let id = &provider.id;
     let provider_path = providers_dir.join(format!("{id}.json"));
-    let content = serde_json::to_string_pretty(&provider)?;
+    let content = serde_json::to_string_pretty(provider)?;
     fs::write(provider_path, content)?;
     Ok(())
 }
 
+/// Sets `enabled` on the providers in `ids`, or on every provider when `ids`
+/// is `None`, writing only those whose flag changes. Returns the updated list.
+///
+/// # Errors
+/// Returns an error if an ID is invalid or a provider cannot be read or written.
+pub fn set_enabled(
+    config_dir: &Path,
+    ids: Option<&[String]>,
+    enabled: bool,
+) -> Result<Vec<ApiProvider>, AppError> {
+    if let Some(ids) = ids {
+        ids.iter().try_for_each(|id| validate_provider_id(id))?;
+    }
+    let mut providers = load_providers(config_dir)?;
+    for provider in &mut providers {
+        let selected = ids.is_none_or(|ids| ids.contains(&provider.id));
+        if selected && provider.enabled != enabled {
+            provider.enabled = enabled;
+            write_provider(config_dir, provider)?;
+        }
+    }
+    Ok(providers)
+}
+
+/// Records the outcome of a refresh on each provider it fetched: the time of
+/// a successful fetch, or the error of one that failed after every retry,
+/// counting failures in a row. Providers are re-read so edits saved during
+/// the refresh are kept. Returns the new health of each provider recorded.
+///
+/// # Errors
+/// Returns an error if a provider cannot be read or written.
+pub fn record_fetch_results(
+    config_dir: &Path,
+    fetched: &[(String, CachedProviderResult)],
+    failed: &[(String, String)],
+) -> Result<Vec<(String, ProviderHealth)>, AppError> {
+    if fetched.is_empty() && failed.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut recorded = Vec::new();
+    for mut provider in load_providers(config_dir)? {
+        let health = if let Some((_, cached)) = fetched.iter().find(|(id, _)| *id == provider.id) {
+            ProviderHealth {
+                consecutive_failures: 0,
+                last_success: Some(cached.fetched_at.to_rfc3339()),
+                last_error: None,
+            }
+        } else if let Some((_, error)) = failed.iter().find(|(id, _)| *id == provider.id) {
+            ProviderHealth {
+                consecutive_failures: provider.consecutive_failures.saturating_add(1),
+                last_success: provider.last_fetched.clone(),
+                last_error: Some(error.clone()),
+            }
+        } else {
+            continue;
+        };
+        if ProviderHealth::of(&provider) != health {
+            provider.last_fetched.clone_from(&health.last_success);
+            provider.last_error.clone_from(&health.last_error);
+            provider.consecutive_failures = health.consecutive_failures;
+            write_provider(config_dir, &provider)?;
+        }
+        recorded.push((provider.id, health));
+    }
+    Ok(recorded)
+}
+
+/// Sets each provider's `sort_order` to its position in `ids`, writing only
+/// those that change. Providers not listed move after the listed ones,
+/// keeping their relative order. Returns the reordered list.
+///
+/// # Errors
+/// Returns an error if an ID is invalid or a provider cannot be read or written.
+pub fn reorder(config_dir: &Path, ids: &[String]) -> Result<Vec<ApiProvider>, AppError> {
+    ids.iter().try_for_each(|id| validate_provider_id(id))?;
+    let listed = u32::try_from(ids.len()).unwrap_or(u32::MAX);
+    let mut providers = load_providers(config_dir)?;
+    for provider in &mut providers {
+        let sort_order = ids.iter().position(|id| *id == provider.id).map_or_else(
+            || listed.saturating_add(provider.sort_order),
+            |i| u32::try_from(i).unwrap_or(u32::MAX),
+        );
+        if provider.sort_order != sort_order {
+            provider.sort_order = sort_order;
+            write_provider(config_dir, provider)?;
+        }
+    }
+    sort_providers(&mut providers);
+    Ok(providers)
+}
+
+/// Moves providers into the order of `ids` (see [`reorder`]) and relists
+/// them in the tray menu in that order.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.reorder_providers", skip_all)]
+pub async fn reorder_providers(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<Vec<ApiProvider>, AppError> {
+    let lock = state.config.lock().await.app_lock;
+    app_lock::ensure_unlocked(lock)?;
+    let providers = reorder(&state.config_dir, &ids)?;
+    let cache = state.provider_cache.lock().await.clone();
+    tray::set_providers(provider_runner::cached_stats(&providers, &cache));
+    if let Some(usage) = state.usage_snapshot().await {
+        let config = state.config.lock().await.clone();
+        tray::update_tray_menu(&app, usage, config, &[]);
+    }
+    Ok(providers)
+}
+
+/// Enables or disables several providers at once (all of them when `ids` is
+/// omitted), then updates the tray a single time.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.set_providers_enabled", skip_all)]
+pub async fn set_providers_enabled(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    ids: Option<Vec<String>>,
+    enabled: bool,
+) -> Result<Vec<ApiProvider>, AppError> {
+    let providers = set_enabled(&state.config_dir, ids.as_deref(), enabled)?;
+    if let Some(usage) = state.usage_snapshot().await {
+        let config = state.config.lock().await.clone();
+        tray::update_tray_menu(&app, usage, config, &[]);
+    }
+    Ok(providers)
+}
+
+/// Folder under `providers/` that deleted providers are moved into.
+const TRASH_DIR: &str = ".trash";
+
+/// A provider moved to the trash, with the UTC time it was deleted.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedProvider {
+    pub provider: ApiProvider,
+    pub deleted_at: String,
+}
+
+fn trash_dir(config_dir: &Path) -> std::path::PathBuf {
+    config_dir.join("providers").join(TRASH_DIR)
+}
+
+/// Splits a trash file name of the form `{id}.{timestamp}.json`.
+fn parse_trash_name(name: &str) -> Option<(&str, &str)> {
+    let (id, stamp) = name.strip_suffix(".json")?.rsplit_once('.')?;
+    (!id.is_empty() && !stamp.is_empty() && stamp.bytes().all(|b| b.is_ascii_digit()))
+        .then_some((id, stamp))
+}
+
+/// Trash files for `id`, newest first.
+fn trash_entries(config_dir: &Path, id: &str) -> Result<Vec<std::path::PathBuf>, AppError> {
+    let dir = trash_dir(config_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries: Vec<(String, std::path::PathBuf)> = fs::read_dir(&dir)?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let (entry_id, stamp) = parse_trash_name(&name)?;
+            (entry_id == id).then(|| (stamp.to_string(), entry.path()))
+        })
+        .collect();
+    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(entries.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Moves `providers/{id}.json` into the trash, stamped with the deletion time.
+///
+/// # Errors
+/// Returns an error if the trash folder cannot be created or the file moved.
+pub fn trash_provider(config_dir: &Path, id: &str) -> Result<(), AppError> {
+    let provider_path = config_dir.join("providers").join(format!("{id}.json"));
+    if !provider_path.exists() {
+        return Ok(());
+    }
+    let dir = trash_dir(config_dir);
+    fs::create_dir_all(&dir)?;
+    let stamp = chrono::Utc::now().format("%Y%m%d%H%M%S%3f");
+    fs::rename(provider_path, dir.join(format!("{id}.{stamp}.json")))?;
+    Ok(())
+}
+
+/// Restores the most recently trashed copy of provider `id`.
+///
+/// # Errors
+/// Returns an error if nothing is trashed under `id`, or if an active
+/// provider with the same ID already exists.
+pub fn untrash_provider(config_dir: &Path, id: &str) -> Result<(), AppError> {
+    let provider_path = config_dir.join("providers").join(format!("{id}.json"));
+    if provider_path.exists() {
+        return Err(AppError::Validation(format!(
+            "A provider with ID '{id}' already exists"
+        )));
+    }
+    let newest = trash_entries(config_dir, id)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::Validation(format!("No deleted provider with ID '{id}'")))?;
+    fs::rename(newest, provider_path)?;
+    Ok(())
+}
+
+/// Lists trashed providers, newest deletion first.
+///
+/// # Errors
+/// Returns an error if the trash folder exists but cannot be read.
+pub fn load_trashed_providers(config_dir: &Path) -> Result<Vec<TrashedProvider>, AppError> {
+    let dir = trash_dir(config_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut trashed = Vec::new();
+    for entry in fs::read_dir(&dir)?.flatten() {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let Some((_, stamp)) = parse_trash_name(&name) else {
+            continue;
+        };
+        let Some(deleted_at) = chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d%H%M%S%3f")
+            .ok()
+            .map(|dt| dt.and_utc().to_rfc3339())
+        else {
+            continue;
+        };
+        match fs::read_to_string(entry.path())
+            .map_err(AppError::from)
+            .and_then(|content| Ok(serde_json::from_str::<ApiProvider>(&content)?))
+        {
+            Ok(provider) => trashed.push(TrashedProvider {
+                provider: redact_provider(provider),
+                deleted_at,
+            }),
+            Err(e) => tracing::error!("Failed to read trashed provider {name}: {e}"),
+        }
+    }
+    trashed.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(trashed)
+}
+
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
+#[tracing::instrument(name = "command.delete_provider", skip_all)]
 pub fn delete_provider(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
     validate_provider_id(&id)?;
+    trash_provider(&state.config_dir, &id)
+}
 
-    let provider_path = state
-        .config_dir
-        .join("providers")
-        .join(format!("{id}.json"));
-    if provider_path.exists() {
-        fs::remove_file(provider_path)?;
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_trashed_providers", skip_all)]
+pub fn get_trashed_providers(state: State<'_, AppState>) -> Result<Vec<TrashedProvider>, AppError> {
+    load_trashed_providers(&state.config_dir)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.restore_provider", skip_all)]
+pub fn restore_provider(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
+    validate_provider_id(&id)?;
+    untrash_provider(&state.config_dir, &id)
+}
+
+/// Permanently deletes every trashed provider.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.purge_trash", skip_all)]
+pub fn purge_trash(state: State<'_, AppState>) -> Result<(), AppError> {
+    let dir = trash_dir(&state.config_dir);
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
     }
     Ok(())
 }
 
+/// Longest stage output kept in a [`TestDebug`], in bytes.
+const DEBUG_OUTPUT_LIMIT: usize = 64 * 1024;
+
 #[derive(Serialize, Deserialize)]
 pub struct TestResult {
     pub success: bool,
     pub data: Option<serde_json::Value>,
     pub error: Option<String>,
+    pub debug: TestDebug,
+}
+
+/// What each stage of a provider test produced and how long it took, up to
+/// the stage that failed.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestDebug {
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub transform_output: Option<String>,
+    /// Lines the transform logged through `console`
+    pub console: Vec<String>,
+    pub parsed: Option<ProviderUsageResult>,
+    pub fetch_ms: Option<u64>,
+    pub transform_ms: Option<u64>,
+    pub parse_ms: Option<u64>,
 }
 
 impl TestResult {
-    const fn success(data: serde_json::Value) -> Self {
+    const fn success(data: serde_json::Value, debug: TestDebug) -> Self {
         Self {
             success: true,
             data: Some(data),
             error: None,
+            debug,
         }
     }
 
-    const fn failure(error: String) -> Self {
+    const fn failure(error: String, debug: TestDebug) -> Self {
         Self {
             success: false,
             data: None,
             error: Some(error),
+            debug,
         }
     }
-}
 
-#[allow(clippy::needless_pass_by_value)]
-#[tauri::command]
-pub async fn test_provider(provider: ApiProvider) -> Result<TestResult, AppError> {
-    validate_fetch_script(&provider.fetch_script)?;
-    validate_env(&provider.env)?;
+    /// A result that isn't a valid provider result, shown alongside the error.
+    const fn invalid(error: String, data: Option<serde_json::Value>, debug: TestDebug) -> Self {
+        Self {
+            success: false,
+            data,
+            error: Some(error),
+            debug,
+        }
+    }
+}
 
-    let parts = parse_fetch_script(&provider.fetch_script, &provider.env)?;
-    if parts.is_empty() {
-        return Err(AppError::Validation("Empty fetch script".to_string()));
+/// `output` cut to [`DEBUG_OUTPUT_LIMIT`] bytes on a character boundary.
+fn debug_output(output: &str) -> String {
+    if output.len() <= DEBUG_OUTPUT_LIMIT {
+        return output.to_string();
     }
+    let end = (0..=DEBUG_OUTPUT_LIMIT)
+        .rev()
+        .find(|i| output.is_char_boundary(*i))
+        .unwrap_or(0);
+    format!("{}… ({} bytes total)", &output[..end], output.len())
+}
 
-    let output = Command::new(&parts[0])
-        .args(&parts[1..])
-        .env_clear()
-        .envs(&provider.env)
-        .output()?;
+fn elapsed_ms(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Ok(TestResult::failure(format!("Fetch failed: {stderr}")));
-    }
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.test_provider", skip_all)]
+pub async fn test_provider(
+    state: State<'_, AppState>,
+    provider: ApiProvider,
+) -> Result<TestResult, AppError> {
+    validate_fetch(&provider)?;
+    validate_env(&provider.env)?;
 
-    let stdout = String::from_utf8(output.stdout).map_err(|e| AppError::Fetch(e.to_string()))?;
+    let timeout = state.config.lock().await.timeouts.provider();
+    let mut debug = TestDebug::default();
+    let started = Instant::now();
+    let fetched = script_runner::fetch_output(&provider, timeout).await;
+    debug.fetch_ms = Some(elapsed_ms(started));
+    let output = match fetched {
+        Ok(output) => output,
+        Err(e) => return Ok(TestResult::failure(format!("Fetch failed: {e}"), debug)),
+    };
+    debug.stdout = Some(debug_output(&output.stdout));
+    debug.stderr = Some(output.stderr)
+        .filter(|stderr| !stderr.trim().is_empty())
+        .map(|stderr| debug_output(&stderr));
 
-    if provider.transform_script.is_empty() {
-        let data: serde_json::Value = serde_json::from_str(&stdout)?;
-        return Ok(TestResult::success(data));
-    }
+    let (result, source) = if !provider.transform_paths.is_empty() {
+        let started = Instant::now();
+        let mapped = json_path::apply_mapping(&provider.transform_paths, &output.stdout);
+        debug.transform_ms = Some(elapsed_ms(started));
+        match mapped {
+            Ok(result) => {
+                debug.transform_output = Some(debug_output(&result));
+                (result, "provider mapping")
+            }
+            Err(e) => return Ok(TestResult::failure(format!("Mapping failed: {e}"), debug)),
+        }
+    } else if provider.transform_script.is_empty() {
+        (output.stdout, "provider")
+    } else {
+        let started = Instant::now();
+        let (transformed, logs) =
+            script_runner::run_transform_isolated(&provider.transform_script, &output.stdout).await;
+        debug.transform_ms = Some(elapsed_ms(started));
+        debug.console = logs.iter().map(|line| debug_output(line)).collect();
+        match transformed {
+            Ok(result) => {
+                debug.transform_output = Some(debug_output(&result));
+                (result, "provider transform")
+            }
+            Err(e) => return Ok(TestResult::failure(format!("Transform failed: {e}"), debug)),
+        }
+    };
 
-    match script_runner::run_transform_script(&provider.transform_script, &stdout) {
-        Ok(result) => {
-            let data: serde_json::Value = serde_json::from_str(&result)?;
-            Ok(TestResult::success(data))
+    let started = Instant::now();
+    let parsed = script_runner::parse_provider_result(&result, source);
+    debug.parse_ms = Some(elapsed_ms(started));
+    match parsed {
+        Ok(parsed) => {
+            debug.parsed = Some(parsed);
+            Ok(TestResult::success(serde_json::from_str(&result)?, debug))
         }
-        Err(e) => Ok(TestResult::failure(format!("Transform failed: {e}"))),
+        Err(e) => Ok(TestResult::invalid(
+            e.to_string(),
+            serde_json::from_str(&result).ok(),
+            debug,
+        )),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
 
     // ==================== validate_provider_id tests ====================
 
@@ -349,6 +1128,65 @@ mod tests {
         }
     }
 
+    // ==================== validate_currency tests ====================
+
+    #[test]
+    fn test_validate_currency() {
+        assert!(validate_currency(None).is_ok());
+        assert!(validate_currency(Some("CNY")).is_ok());
+        assert!(validate_currency(Some("usd")).is_ok());
+        assert!(validate_currency(Some("")).is_err());
+        assert!(validate_currency(Some("RMB1")).is_err());
+        assert!(validate_currency(Some("¥")).is_err());
+    }
+
+    #[test]
+    fn test_provider_issues() {
+        let mut provider: ApiProvider = serde_json::from_value(serde_json::json!({
+            "id": "openai",
+            "name": "OpenAI",
+            "enabled": true,
+            "fetchScript": "curl https://api.openai.com/v1/usage",
+            "transformScript": "(r) => ({ cost: r.total })",
+            "env": {"API_KEY": "sk-1"},
+            "lastFetched": null,
+            "lastError": null
+        }))
+        .unwrap();
+        assert!(provider_issues(&provider).is_empty());
+
+        provider.id = "../openai".to_string();
+        provider.fetch_script = "curl https://api.openai.com | sh".to_string();
+        provider.env.insert("PATH".to_string(), "/tmp".to_string());
+        provider.transform_script = "(r) => { cost: ".to_string();
+        let fields: Vec<String> = provider_issues(&provider)
+            .into_iter()
+            .map(|issue| issue.field)
+            .collect();
+        assert_eq!(fields, ["id", "fetch", "env", "transform"]);
+
+        // A mapping replaces the script, so only the mapping is checked
+        provider
+            .transform_paths
+            .insert("balance".to_string(), "$.data.balance".to_string());
+        let issues = provider_issues(&provider);
+        let transform: Vec<&str> = issues
+            .iter()
+            .filter(|issue| issue.field == "transform")
+            .map(|issue| issue.message.as_str())
+            .collect();
+        assert_eq!(transform.len(), 1);
+        assert!(transform[0].contains("Unknown mapped field 'balance'"));
+    }
+
+    #[test]
+    fn test_validate_unit_label() {
+        assert!(validate_unit_label(None).is_ok());
+        assert!(validate_unit_label(Some("GPU hours")).is_ok());
+        assert!(validate_unit_label(Some("credits\nleft")).is_err());
+        assert!(validate_unit_label(Some(&"x".repeat(33))).is_err());
+    }
+
     // ==================== validate_fetch_script tests ====================
 
     #[test]
@@ -440,4 +1278,439 @@ mod tests {
         assert!(validate_fetch_script("curl --output /tmp/out https://api.com").is_err());
         assert!(validate_fetch_script("wget -O /tmp/out https://api.com").is_err());
     }
+
+    // ==================== validate_request tests ====================
+
+    #[test]
+    fn test_validate_request() {
+        let mut request = ProviderRequest {
+            url: "https://api.example.com/usage?key=${API_KEY}".to_string(),
+            headers: [("X-Api-Key".to_string(), "${API_KEY}".to_string())].into(),
+            ..ProviderRequest::default()
+        };
+        assert!(validate_request(&request).is_ok());
+
+        request.body = Some("{}".to_string());
+        assert!(validate_request(&request).is_err());
+        request.method = HttpMethod::Post;
+        assert!(validate_request(&request).is_ok());
+
+        request
+            .headers
+            .insert("Bad Header".to_string(), String::new());
+        assert!(validate_request(&request).is_err());
+        request.headers.clear();
+
+        request.url = "file:///etc/passwd".to_string();
+        assert!(validate_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_validate_steps() {
+        let step = |name: &str, path: &str| FetchStep {
+            request: ProviderRequest {
+                url: "https://api.example.com/login".to_string(),
+                ..ProviderRequest::default()
+            },
+            extract: [(name.to_string(), path.to_string())].into(),
+        };
+        assert!(validate_steps(&[step("SESSION_TOKEN", "data.token")]).is_ok());
+        assert!(validate_steps(&[step("SESSION TOKEN", "data.token")]).is_err());
+        assert!(validate_steps(&[step("LD_PRELOAD", "data.token")]).is_err());
+        assert!(validate_steps(&[step("SESSION", " ")]).is_err());
+
+        let mut local = step("SESSION", "token");
+        local.request.url = "file:///etc/passwd".to_string();
+        assert!(validate_steps(&[local]).is_err());
+        let many = vec![step("SESSION", "token"); MAX_FETCH_STEPS + 1];
+        assert!(validate_steps(&many).is_err());
+    }
+
+    #[test]
+    fn test_validate_pagination() {
+        let mut pagination = ProviderPagination {
+            items_path: "data".to_string(),
+            next_path: "meta.next_cursor".to_string(),
+            param: "cursor".to_string(),
+            ..ProviderPagination::default()
+        };
+        assert!(validate_pagination(&pagination).is_ok());
+
+        pagination.max_pages = 0;
+        assert!(validate_pagination(&pagination).is_err());
+        pagination.max_pages = MAX_PAGES;
+
+        pagination.param.clear();
+        assert!(validate_pagination(&pagination).is_err());
+        pagination.strategy = PaginationStrategy::NextUrl;
+        assert!(validate_pagination(&pagination).is_ok());
+        pagination.next_path.clear();
+        assert!(validate_pagination(&pagination).is_err());
+
+        pagination.strategy = PaginationStrategy::Page;
+        pagination.param = "page".to_string();
+        assert!(validate_pagination(&pagination).is_ok());
+    }
+
+    #[test]
+    fn test_validate_graphql() {
+        let mut request = ProviderRequest {
+            url: "https://api.example.com/graphql".to_string(),
+            method: HttpMethod::Post,
+            graphql: Some(GraphqlQuery {
+                query: "{ usage { cost } }".to_string(),
+                variables: Some(r#"{"org": "${ORG}"}"#.to_string()),
+            }),
+            ..ProviderRequest::default()
+        };
+        assert!(validate_request(&request).is_ok());
+
+        request.body = Some("{}".to_string());
+        assert!(validate_request(&request).is_err());
+        request.body = None;
+
+        let graphql = request.graphql.as_mut().unwrap();
+        graphql.variables = Some("[1, 2]".to_string());
+        assert!(validate_request(&request).is_err());
+        let graphql = request.graphql.as_mut().unwrap();
+        graphql.variables = Some(r#"{"first": ${LIMIT}}"#.to_string());
+        assert!(validate_request(&request).is_ok());
+        let graphql = request.graphql.as_mut().unwrap();
+        graphql.query = "  ".to_string();
+        assert!(validate_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_validate_oauth() {
+        let mut settings = ProviderOAuth {
+            token_url: "https://auth.example.com/oauth/token".to_string(),
+            client_id: "tokenmeter".to_string(),
+            client_secret: "${CLIENT_SECRET}".to_string(),
+            ..ProviderOAuth::default()
+        };
+        assert!(validate_oauth(&settings).is_ok());
+
+        settings.grant = OAuthGrant::RefreshToken;
+        assert!(validate_oauth(&settings).is_err());
+        settings.refresh_token = "${REFRESH_TOKEN}".to_string();
+        assert!(validate_oauth(&settings).is_ok());
+
+        settings.client_id = " ".to_string();
+        assert!(validate_oauth(&settings).is_err());
+        settings.client_id = "tokenmeter".to_string();
+        settings.token_url = "ftp://auth.example.com".to_string();
+        assert!(validate_oauth(&settings).is_err());
+    }
+
+    #[test]
+    fn test_validate_retry() {
+        assert!(validate_retry(&ProviderRetry::default()).is_ok());
+        let retry = |attempts, backoff_ms| ProviderRetry {
+            attempts,
+            backoff_ms,
+        };
+        assert!(validate_retry(&retry(0, 100)).is_ok());
+        assert!(validate_retry(&retry(6, 1_000)).is_err());
+        assert!(validate_retry(&retry(2, 50)).is_err());
+        assert!(validate_retry(&retry(2, 60_000)).is_err());
+    }
+
+    // ==================== redact_provider tests ====================
+
+    #[test]
+    fn test_redact_provider_hides_secrets() {
+        let provider = ApiProvider {
+            id: "openai".to_string(),
+            name: "OpenAI".to_string(),
+            enabled: true,
+            fetch_script: "curl -H 'Authorization: Bearer sk-secret' https://api.openai.com"
+                .to_string(),
+            transform_script: "data.total".to_string(),
+            transform_paths: BTreeMap::new(),
+            env: HashMap::from([("API_KEY".to_string(), "sk-secret".to_string())]),
+            env_expires_at: HashMap::new(),
+            last_fetched: None,
+            last_error: None,
+            consecutive_failures: 0,
+            currency: None,
+            unit: None,
+            unit_label: None,
+            oauth: Some(ProviderOAuth {
+                client_secret: "${CLIENT_SECRET}".to_string(),
+                ..ProviderOAuth::default()
+            }),
+            request: Some(ProviderRequest {
+                method: HttpMethod::Post,
+                url: "https://api.openai.com".to_string(),
+                headers: [("Authorization".to_string(), "Bearer sk-secret".to_string())].into(),
+                body: Some("{}".to_string()),
+                pagination: None,
+                graphql: None,
+            }),
+            steps: vec![FetchStep {
+                request: ProviderRequest {
+                    method: HttpMethod::Post,
+                    url: "https://api.openai.com/login".to_string(),
+                    body: Some(r#"{"password": "hunter2"}"#.to_string()),
+                    ..ProviderRequest::default()
+                },
+                extract: [("SESSION".to_string(), "token".to_string())].into(),
+            }],
+            retry: ProviderRetry::default(),
+            group: None,
+            sort_order: 0,
+            show_in_tray: true,
+        };
+
+        let redacted = redact_provider(provider);
+        assert_eq!(
+            redacted.env.get("API_KEY").map(String::as_str),
+            Some(REDACTED)
+        );
+        assert!(redacted.fetch_script.is_empty());
+        let request = redacted.request.as_ref().unwrap();
+        assert_eq!(request.headers["Authorization"], REDACTED);
+        assert!(request.body.is_none());
+        assert!(redacted.steps[0].request.body.is_none());
+        let settings = redacted.oauth.as_ref().unwrap();
+        assert_eq!(settings.client_secret, REDACTED);
+        assert!(settings.refresh_token.is_empty());
+        assert_eq!(redacted.name, "OpenAI");
+        assert_eq!(redacted.transform_script, "data.total");
+    }
+
+    // ==================== trash tests ====================
+
+    fn temp_config_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("providers")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_set_enabled() {
+        let temp = temp_config_dir();
+        let dir = temp.path();
+        for id in ["a", "b", "c"] {
+            let provider = ApiProvider {
+                id: id.to_string(),
+                name: id.to_string(),
+                enabled: true,
+                fetch_script: String::new(),
+                transform_script: String::new(),
+                transform_paths: BTreeMap::new(),
+                env: HashMap::new(),
+                env_expires_at: HashMap::new(),
+                last_fetched: None,
+                last_error: None,
+                consecutive_failures: 0,
+                currency: None,
+                unit: None,
+                unit_label: None,
+                request: None,
+                oauth: None,
+                steps: Vec::new(),
+                retry: ProviderRetry::default(),
+                group: None,
+                sort_order: 0,
+                show_in_tray: true,
+            };
+            write_provider(dir, &provider).unwrap();
+        }
+
+        let ids = ["a".to_string(), "c".to_string()];
+        set_enabled(dir, Some(&ids), false).unwrap();
+        let mut enabled: Vec<(String, bool)> = load_providers(dir)
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.id, p.enabled))
+            .collect();
+        enabled.sort();
+        assert_eq!(
+            enabled,
+            [
+                ("a".to_string(), false),
+                ("b".to_string(), true),
+                ("c".to_string(), false)
+            ]
+        );
+
+        let all = set_enabled(dir, None, false).unwrap();
+        assert!(all.iter().all(|p| !p.enabled));
+        assert!(set_enabled(dir, Some(&["../x".to_string()]), true).is_err());
+    }
+
+    fn named(id: &str, group: Option<&str>, sort_order: u32) -> ApiProvider {
+        ApiProvider {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            fetch_script: String::new(),
+            transform_script: String::new(),
+            transform_paths: BTreeMap::new(),
+            env: HashMap::new(),
+            env_expires_at: HashMap::new(),
+            last_fetched: None,
+            last_error: None,
+            consecutive_failures: 0,
+            currency: None,
+            unit: None,
+            unit_label: None,
+            request: None,
+            oauth: None,
+            steps: Vec::new(),
+            retry: ProviderRetry::default(),
+            group: group.map(str::to_string),
+            sort_order,
+            show_in_tray: true,
+        }
+    }
+
+    #[test]
+    fn test_sort_providers() {
+        let mut providers = vec![
+            named("b", Some("Work"), 3),
+            named("a", Some("Work"), 5),
+            named("z", None, 9),
+            named("y", Some(" "), 1),
+            named("c", Some("Side"), 0),
+        ];
+        sort_providers(&mut providers);
+        let ids: Vec<&str> = providers.iter().map(|p| p.id.as_str()).collect();
+        // "Side" ranks before "Work" through its first member; blank groups count as none
+        assert_eq!(ids, ["y", "z", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_reorder() {
+        let temp = temp_config_dir();
+        let dir = temp.path();
+        for provider in [
+            named("a", None, 0),
+            named("b", None, 1),
+            named("c", None, 2),
+        ] {
+            write_provider(dir, &provider).unwrap();
+        }
+
+        let reordered = reorder(dir, &["c".to_string(), "a".to_string()]).unwrap();
+        let ids: Vec<&str> = reordered.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["c", "a", "b"]);
+        let ids: Vec<String> = load_providers(dir)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(ids, ["c", "a", "b"]);
+        assert!(reorder(dir, &["../x".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_record_fetch_results() {
+        let temp = temp_config_dir();
+        let dir = temp.path();
+        for id in ["ok", "flaky", "idle"] {
+            let provider = ApiProvider {
+                id: id.to_string(),
+                name: id.to_string(),
+                enabled: true,
+                fetch_script: String::new(),
+                transform_script: String::new(),
+                transform_paths: BTreeMap::new(),
+                env: HashMap::new(),
+                env_expires_at: HashMap::new(),
+                last_fetched: Some("2025-03-01T00:00:00+00:00".to_string()),
+                last_error: Some("earlier failure".to_string()),
+                consecutive_failures: 2,
+                currency: None,
+                unit: None,
+                unit_label: None,
+                request: None,
+                oauth: None,
+                steps: Vec::new(),
+                retry: ProviderRetry::default(),
+                group: None,
+                sort_order: 0,
+                show_in_tray: true,
+            };
+            write_provider(dir, &provider).unwrap();
+        }
+
+        let fetched_at = chrono::DateTime::parse_from_rfc3339("2025-03-10T12:00:00Z")
+            .unwrap()
+            .to_utc();
+        let fetched = [(
+            "ok".to_string(),
+            CachedProviderResult {
+                fetched_at,
+                result: crate::types::ProviderUsageResult {
+                    cost: Some(1.0),
+                    tokens: None,
+                    used: None,
+                    total: None,
+                    daily: Vec::new(),
+                },
+            },
+        )];
+        let failed = [("flaky".to_string(), "HTTP 503".to_string())];
+        let recorded = record_fetch_results(dir, &fetched, &failed).unwrap();
+        assert_eq!(recorded.len(), 2);
+
+        let providers = load_providers(dir).unwrap();
+        let find = |id: &str| providers.iter().find(|p| p.id == id).unwrap();
+        assert_eq!(
+            find("ok").last_fetched.as_deref(),
+            Some("2025-03-10T12:00:00+00:00")
+        );
+        assert!(find("ok").last_error.is_none());
+        assert_eq!(
+            find("flaky").last_fetched.as_deref(),
+            Some("2025-03-01T00:00:00+00:00")
+        );
+        assert_eq!(find("flaky").last_error.as_deref(), Some("HTTP 503"));
+        assert_eq!(find("idle").last_error.as_deref(), Some("earlier failure"));
+        assert_eq!(find("ok").consecutive_failures, 0);
+        assert_eq!(find("flaky").consecutive_failures, 3);
+        assert_eq!(find("idle").consecutive_failures, 2);
+    }
+
+    #[test]
+    fn test_parse_trash_name() {
+        assert_eq!(
+            parse_trash_name("foo..bar.20240101120000000.json"),
+            Some(("foo..bar", "20240101120000000"))
+        );
+        assert_eq!(parse_trash_name("foo.json"), None);
+        assert_eq!(parse_trash_name("foo.bar.json"), None);
+    }
+
+    #[test]
+    fn test_trash_and_restore_provider() {
+        let temp = temp_config_dir();
+        let dir = temp.path();
+        let path = dir.join("providers").join("openai.json");
+        fs::write(&path, "{\"id\":\"openai\",\"name\":\"OpenAI\"}").unwrap();
+
+        trash_provider(dir, "openai").unwrap();
+        assert!(!path.exists());
+        assert_eq!(trash_entries(dir, "openai").unwrap().len(), 1);
+
+        fs::write(&path, "{}").unwrap();
+        assert!(untrash_provider(dir, "openai").is_err());
+        fs::remove_file(&path).unwrap();
+
+        untrash_provider(dir, "openai").unwrap();
+        assert!(path.exists());
+        assert!(trash_entries(dir, "openai").unwrap().is_empty());
+        assert!(untrash_provider(dir, "missing").is_err());
+    }
+
+    #[test]
+    fn test_debug_output_truncates_on_char_boundary() {
+        assert_eq!(debug_output("{\"cost\": 1}"), "{\"cost\": 1}");
+        let long = "é".repeat(DEBUG_OUTPUT_LIMIT);
+        let cut = debug_output(&long);
+        assert!(cut.len() < long.len());
+        assert!(cut.ends_with(&format!("… ({} bytes total)", long.len())));
+    }
 }
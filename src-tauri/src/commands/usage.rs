@@ -1,52 +1,358 @@
-use crate::config::AppConfig;
+use crate::alerts;
+use crate::app_lock;
+use crate::commands::{diagnostics, providers};
+use crate::config::{
+    AppConfig, BalanceDrainConfig, CopilotSourceConfig, Feature, GuardConfig, LimitsConfig,
+    ProxySourceConfig, RefreshHook, TimeoutsConfig,
+};
 use crate::error::AppError;
-use crate::services::ccusage;
+use crate::services::{
+    agent_link, ccusage, claude_logs, digest, hooks, limits, notion, provider_runner, push,
+    remote_snapshot, sources, trends,
+};
 use crate::state::AppState;
 use crate::storage;
 use crate::tray;
-use crate::types::UsageSummary;
-use std::time::Duration;
+use crate::types::{
+    BootPayload, CachedProviderResult, FeatureInfo, ProviderHistoryPoint, SetupStatus,
+    TrayFormatPreview, UsageSummary,
+};
+use chrono::NaiveDate;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, State};
 
-const MIN_REFRESH_INTERVAL: u64 = 60;
-const MAX_REFRESH_INTERVAL: u64 = 3600;
+pub const MIN_REFRESH_INTERVAL: u64 = 60;
+pub const MAX_REFRESH_INTERVAL: u64 = 3600;
+const MIN_PROXY_PORT: u16 = 1024;
+const MIN_CCUSAGE_TIMEOUT: u64 = 10;
+const MAX_CCUSAGE_TIMEOUT: u64 = 600;
+const MAX_REQUEST_TIMEOUT: u64 = 120;
+const MAX_PROVIDER_CACHE_TTL: u64 = 86_400;
+const MAX_LOCK_GRACE_MINUTES: u32 = 60;
 
+/// Fetches usage from every source and merges it into the stored history.
+///
+/// A `full` refresh rebuilds the history inside the summary window from the
+/// fresh data alone, so days whose transcripts were deleted are dropped.
+#[tracing::instrument(name = "refresh.fetch_and_update_history", skip_all)]
 pub async fn fetch_and_update_history(
-    state: &State<'_, AppState>,
-) -> Result<UsageSummary, AppError> {
-    let mut data = ccusage::fetch_usage()
-        .await
-        .map_err(|e| AppError::Fetch(e.to_string()))?;
+    state: &AppState,
+    full: bool,
+) -> Result<Arc<UsageSummary>, AppError> {
+    let config = state.config.lock().await.clone();
+    let started = Instant::now();
+    let mut data = ccusage::fetch_usage(
+        &config.data_sources.claude_code,
+        &config.timeouts,
+        &config.features,
+        Some(&state.config_dir),
+    )
+    .await
+    .map_err(|e| AppError::Fetch(e.to_string()))?;
 
-    // Load existing history, treat errors as empty history but log warning
-    let history = match storage::load_history(&state.config_dir) {
+    // Load the stored history the summary shows, treat errors as empty history but log warning
+    let today = chrono::Local::now().date_naive();
+    let history = match storage::load_history_range(
+        &state.config_dir,
+        &sources::summary_history_start(today)
+            .format("%Y-%m-%d")
+            .to_string(),
+        &today.format("%Y-%m-%d").to_string(),
+    ) {
         Ok(h) => h,
         Err(e) => {
-            eprintln!("Warning: Failed to load history: {e}");
+            tracing::warn!("Failed to load history: {e}");
             Vec::new()
         }
     };
 
     // Merge history
-    let merged_history = storage::merge_history(&history, &data.daily_usage);
+    let merged_history = if full {
+        let window_start = today - chrono::Duration::days(sources::SUMMARY_WINDOW_DAYS - 1);
+        storage::replace_history_window(
+            &history,
+            &data.daily_usage,
+            &window_start.format("%Y-%m-%d").to_string(),
+        )
+    } else {
+        storage::merge_history(&history, &data.daily_usage)
+    };
 
     // Save merged history (best-effort, do not block fresh data)
     if let Err(e) = storage::save_history(&state.config_dir, &merged_history) {
-        eprintln!("Warning: Failed to save history: {e}");
+        tracing::warn!("Failed to save history: {e}");
     }
 
     // Update data with merged history
     data.daily_usage = merged_history;
 
+    let period_start = sources::period_start(today, config.billing_cycle_day);
+    if config.billing_cycle_day.is_some() {
+        sources::restrict_to_period(&mut data, period_start);
+    }
+
+    // Merge external sources after saving so stored history stays Claude Code only
+    let mut reports =
+        sources::fetch_external(&config.data_sources, &config.timeouts, &state.config_dir).await;
+    reports.extend(
+        storage::load_imports(&state.config_dir)
+            .into_iter()
+            .map(|(source, daily)| sources::import_report(&source, daily)),
+    );
+    sources::apply_reports_since(&mut data, &reports, period_start);
+    if config.include_provider_costs {
+        let costs = provider_costs(state, period_start).await;
+        sources::apply_provider_costs(&mut data, &costs);
+    }
+    sources::group_minor_models(&mut data.model_breakdown, config.min_model_daily_cost);
+    sources::sort_models(&mut data.model_breakdown, config.model_sort);
+    for source in &mut data.sources {
+        sources::sort_models(&mut source.models, config.model_sort);
+    }
+    trends::apply_trend(&mut data, &config.trends);
+    if config.limits.enabled {
+        data.limits =
+            Some(limits::fetch_limits(&config.data_sources.claude_code, &config.limits).await);
+    }
+    if config.menu_bar.format.contains(tray::BLOCK_RESET_VARIABLE) {
+        match ccusage::fetch_blocks(
+            &config.data_sources.claude_code,
+            &config.timeouts,
+            &config.features,
+        )
+        .await
+        {
+            Ok(blocks) => data.active_block_end = ccusage::active_block_end(&blocks),
+            Err(e) => tracing::warn!("Failed to fetch blocks: {e}"),
+        }
+    }
+
+    data.meta.fetched_at = Some(chrono::Local::now().to_rfc3339());
+    data.meta.duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    data.meta.last_error = None;
+
+    let data = Arc::new(data);
+    spawn_integrations(state, &config, &data);
+
     Ok(data)
 }
 
+/// Takes the latest data from a running `tokenmeterd` when there is one, so
+/// the app never runs ccusage or writes history alongside it. Returns `None`
+/// when the app should refresh itself, and an error while the agent has no
+/// data to hand off.
+pub async fn agent_handoff(state: &AppState) -> Option<Result<Arc<UsageSummary>, AppError>> {
+    let handoff = match agent_link::fetch_handoff(&state.config_dir).await? {
+        Ok(handoff) => handoff,
+        Err(e) => return Some(Err(AppError::Fetch(e))),
+    };
+    tray::set_providers(handoff.providers);
+    let mut summary = handoff.summary;
+    summary.meta.managed_by_agent = true;
+    Some(Ok(Arc::new(summary)))
+}
+
+/// Fetches the enabled providers concurrently and hands their lines to the tray
+/// menu, shown with the next tray update. Results cached within `ttl` are
+/// reused; fresh ones are added to the cache and saved, and each fetched
+/// provider's `last_fetched` or `last_error` is updated.
+pub async fn refresh_tray_providers(state: &AppState, timeout: Duration, ttl: Duration) {
+    let providers = match providers::load_providers(&state.config_dir) {
+        Ok(providers) => providers,
+        Err(e) => {
+            tracing::warn!("Failed to load providers: {e}");
+            Vec::new()
+        }
+    };
+    let cached = state.provider_cache.lock().await.clone();
+    let run = provider_runner::fetch_all(&providers, timeout, &cached, ttl).await;
+    tray::set_providers(run.stats);
+    record_provider_history(&state.config_dir, &run.fetched);
+    let config = state.config.lock().await.clone();
+    alerts::spawn_balance_drain_alerts(
+        state.config_dir.clone(),
+        state.diagnostics.clone(),
+        &config,
+        &providers,
+        &run.fetched,
+    );
+    alerts::spawn_key_expiry_alerts(
+        state.config_dir.clone(),
+        state.diagnostics.clone(),
+        &config,
+        &providers,
+    );
+    match providers::record_fetch_results(&state.config_dir, &run.fetched, &run.failed) {
+        Ok(recorded) => state.provider_health.lock().await.extend(recorded),
+        Err(e) => tracing::warn!("Failed to record provider fetch results: {e}"),
+    }
+
+    let mut cache = state.provider_cache.lock().await;
+    let before = cache.len();
+    cache.retain(|id, _| providers.iter().any(|p| p.id == *id));
+    if run.fetched.is_empty() && cache.len() == before {
+        return;
+    }
+    // Daily series live in the provider's daily history, not the cache
+    cache.extend(run.fetched.into_iter().map(|(id, mut cached)| {
+        cached.result.daily.clear();
+        (id, cached)
+    }));
+    if let Err(e) = storage::save_provider_cache(&state.config_dir, &cache) {
+        tracing::warn!("Failed to save provider cache: {e}");
+    }
+    drop(cache);
+}
+
+/// Latest cost reported by each enabled provider counting money, with the
+/// part spent today derived from its history since `period_start`.
+async fn provider_costs(state: &AppState, period_start: NaiveDate) -> Vec<sources::ProviderCost> {
+    let providers = match providers::load_providers(&state.config_dir) {
+        Ok(providers) => providers,
+        Err(e) => {
+            tracing::warn!("Failed to load providers: {e}");
+            return Vec::new();
+        }
+    };
+    let cache = state.provider_cache.lock().await.clone();
+    let local_midnight = |date: NaiveDate| {
+        date.and_time(chrono::NaiveTime::MIN)
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .map_or_else(chrono::Utc::now, |time| time.with_timezone(&chrono::Utc))
+    };
+    let today_start = local_midnight(chrono::Local::now().date_naive());
+    let since = local_midnight(period_start);
+
+    providers
+        .iter()
+        .filter(|provider| provider.enabled && provider.reports_money())
+        .filter_map(|provider| {
+            let latest = cache.get(&provider.id)?.result.cost?;
+            let history = storage::load_provider_history(&state.config_dir, &provider.id, since)
+                .unwrap_or_default();
+            Some(sources::ProviderCost {
+                id: provider.id.clone(),
+                label: provider.name.clone(),
+                today: sources::provider_today_cost(&history, latest, today_start),
+                this_month: latest,
+            })
+        })
+        .collect()
+}
+
+/// Appends freshly fetched provider results to their history for trend charts,
+/// merging any per-day series they report into the provider's daily history.
+fn record_provider_history(config_dir: &Path, fetched: &[(String, CachedProviderResult)]) {
+    let today = chrono::Local::now().date_naive();
+    for (id, cached) in fetched {
+        let mut point = ProviderHistoryPoint {
+            fetched_at: cached.fetched_at,
+            result: cached.result.clone(),
+        };
+        let daily = std::mem::take(&mut point.result.daily);
+        if let Err(e) = storage::append_provider_history(config_dir, id, &point) {
+            tracing::warn!("Failed to record history for provider {id}: {e}");
+        }
+        if daily.is_empty() {
+            continue;
+        }
+        if let Err(e) = storage::merge_provider_daily(config_dir, id, &daily, today) {
+            tracing::warn!("Failed to record daily usage for provider {id}: {e}");
+        }
+    }
+}
+
+/// Pushes fresh usage to enabled integrations in the background, recording each
+/// outcome in diagnostics so a slow or failing service never delays the refresh.
+fn spawn_integrations(state: &AppState, config: &AppConfig, data: &Arc<UsageSummary>) {
+    alerts::spawn_budget_alert(
+        state.config_dir.clone(),
+        state.diagnostics.clone(),
+        config,
+        data,
+    );
+
+    let notion_config = &config.integrations.notion;
+    if notion_config.enabled {
+        let notion_config = notion_config.clone();
+        let timeout = config.timeouts.integration();
+        let daily = data.daily_usage.clone();
+        let diagnostics = state.diagnostics.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = notion::sync_daily_usage(&notion_config, &daily, timeout).await;
+            if let Err(e) = &result {
+                tracing::warn!("Notion sync failed: {e}");
+            }
+            diagnostics
+                .lock()
+                .await
+                .record_integration("notion", result);
+        });
+    }
+
+    let snapshot_config = &config.integrations.remote_snapshot;
+    if snapshot_config.enabled {
+        let snapshot_config = snapshot_config.clone();
+        let timeout = config.timeouts.integration();
+        let summary = Arc::clone(data);
+        let diagnostics = state.diagnostics.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = remote_snapshot::push_snapshot(&snapshot_config, &summary, timeout).await;
+            if let Err(e) = &result {
+                tracing::warn!("Remote snapshot push failed: {e}");
+            }
+            diagnostics
+                .lock()
+                .await
+                .record_integration("remoteSnapshot", result);
+        });
+    }
+
+    let enabled_hooks: Vec<_> = config
+        .integrations
+        .hooks
+        .iter()
+        .filter(|h| h.enabled)
+        .collect();
+    if enabled_hooks.is_empty() {
+        return;
+    }
+    let summary: Arc<[u8]> = match serde_json::to_vec(&**data) {
+        Ok(json) => json.into(),
+        Err(e) => {
+            tracing::warn!("Failed to serialize summary for hooks: {e}");
+            return;
+        }
+    };
+    for hook in enabled_hooks {
+        let hook = hook.clone();
+        let timeout = config.timeouts.integration();
+        let summary = Arc::clone(&summary);
+        let diagnostics = state.diagnostics.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = hooks::run_hook(&hook, &summary, timeout).await;
+            if let Err(e) = &result {
+                tracing::warn!("Hook {} failed: {e}", hook.name);
+            }
+            diagnostics
+                .lock()
+                .await
+                .record_integration(&format!("hook:{}", hook.name), result);
+        });
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
+#[tracing::instrument(name = "command.get_usage_summary", skip_all)]
 pub async fn get_usage_summary(
     app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<UsageSummary, AppError> {
+) -> Result<Arc<UsageSummary>, AppError> {
     let refresh_interval = state
         .config
         .lock()
@@ -55,7 +361,7 @@ pub async fn get_usage_summary(
         .clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL);
     let cache_ttl = Duration::from_secs(refresh_interval);
 
-    let cached = state.usage.lock().await.clone();
+    let cached = state.usage_snapshot().await;
     let fetched_at = *state.usage_fetched_at.lock().await;
     if let (Some(data), Some(fetched_at)) = (cached, fetched_at) {
         if fetched_at.elapsed() < cache_ttl {
@@ -67,7 +373,7 @@ pub async fn get_usage_summary(
     let _refresh_guard = state.usage_refresh_lock.lock().await;
 
     // Re-check after acquiring the lock.
-    let cached = state.usage.lock().await.clone();
+    let cached = state.usage_snapshot().await;
     let fetched_at = *state.usage_fetched_at.lock().await;
     if let (Some(data), Some(fetched_at)) = (cached, fetched_at) {
         if fetched_at.elapsed() < cache_ttl {
@@ -75,46 +381,83 @@ pub async fn get_usage_summary(
         }
     }
 
-    let data = fetch_and_update_history(&state).await?;
+    let result = match agent_handoff(&state).await {
+        Some(result) => result,
+        None => fetch_and_update_history(&state, false).await,
+    };
+    let data = match result {
+        Ok(data) => data,
+        Err(e) => {
+            state.record_refresh_error(e.to_string()).await;
+            return Err(e);
+        }
+    };
 
-    *state.usage.lock().await = Some(data.clone());
-    *state.usage_fetched_at.lock().await = Some(std::time::Instant::now());
+    state.set_usage(Arc::clone(&data)).await;
     let config = state.config.lock().await.clone();
-    tray::update_tray_menu(&app, &data, &config, &[]);
+    tray::update_tray_menu(&app, Arc::clone(&data), config, &[]);
 
     Ok(data)
 }
 
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
+#[tracing::instrument(name = "command.refresh_usage", skip_all)]
 pub async fn refresh_usage(
     app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<UsageSummary, AppError> {
+) -> Result<Arc<UsageSummary>, AppError> {
+    run_refresh(&app, &state, false).await
+}
+
+/// Re-ingests usage from scratch, ignoring the summary cache and replacing
+/// recent history, for when Claude data was edited or deleted by hand.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.force_full_refresh", skip_all)]
+pub async fn force_full_refresh(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Arc<UsageSummary>, AppError> {
+    run_refresh(&app, &state, true).await
+}
+
+/// Refreshes regardless of the cache TTL, publishing the result to the tray
+/// and notifying every window when the refresh starts and ends.
+pub async fn run_refresh(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    full: bool,
+) -> Result<Arc<UsageSummary>, AppError> {
     // Emit refresh-started event to sync UI state across windows
     let _ = app.emit("refresh-started", ());
 
-    let cached = state.usage.lock().await.clone();
+    let cached = state.usage_snapshot().await;
     let config = state.config.lock().await.clone();
 
     // Acquire lock to prevent concurrent refreshing/writing
     let _refresh_guard = state.usage_refresh_lock.lock().await;
 
-    let data = match fetch_and_update_history(&state).await {
+    let result = if let Some(result) = agent_handoff(state).await {
+        result
+    } else {
+        refresh_locally(state, &config, full).await
+    };
+    let data = match result {
         Ok(data) => data,
         Err(e) => {
+            state.record_refresh_error(e.to_string()).await;
             // Emit refresh-completed even on failure to re-enable buttons
             let _ = app.emit("refresh-completed", ());
-            if let Some(usage) = cached.as_ref() {
-                tray::update_tray_menu(&app, usage, &config, &[]);
+            if let Some(usage) = cached {
+                tray::update_tray_menu(app, usage, config, &[]);
             }
             return Err(e);
         }
     };
 
-    *state.usage.lock().await = Some(data.clone());
-    *state.usage_fetched_at.lock().await = Some(std::time::Instant::now());
-    tray::update_tray_menu(&app, &data, &config, &[]);
+    state.set_usage(Arc::clone(&data)).await;
+    tray::update_tray_menu(app, Arc::clone(&data), config, &[]);
 
     // Emit refresh-completed to re-enable buttons
     let _ = app.emit("refresh-completed", ());
@@ -122,20 +465,335 @@ pub async fn refresh_usage(
     Ok(data)
 }
 
+/// Refreshes usage and provider lines in this process, the way a full refresh
+/// does when `full` is set.
+async fn refresh_locally(
+    state: &AppState,
+    config: &AppConfig,
+    full: bool,
+) -> Result<Arc<UsageSummary>, AppError> {
+    if full {
+        if let Err(e) = storage::clear_summary_cache(&state.config_dir) {
+            tracing::warn!("Failed to clear summary cache: {e}");
+        }
+    }
+
+    let (result, ()) = tokio::join!(
+        fetch_and_update_history(state, full),
+        refresh_tray_providers(
+            state,
+            config.timeouts.provider(),
+            // A full refresh refetches every provider
+            if full {
+                Duration::ZERO
+            } else {
+                config.provider_cache_ttl()
+            },
+        ),
+    );
+    result
+}
+
+/// Source tags name the import file, so they are limited to a safe charset.
+fn validate_import_source(source: &str) -> Result<(), AppError> {
+    let valid = !source.is_empty()
+        && source.len() <= 64
+        && source
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!(
+            "Import source must be 1-64 letters, digits, '-' or '_': '{source}'"
+        )))
+    }
+}
+
+/// Merges a ccusage daily JSON export from another machine into the imported
+/// usage tagged `source`, then refreshes so it shows up as its own source.
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
+#[tracing::instrument(name = "command.import_usage_dump", skip_all)]
+pub async fn import_usage_dump(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    source: String,
+    content: String,
+) -> Result<Arc<UsageSummary>, AppError> {
+    validate_import_source(&source)?;
+    let days = ccusage::parse_daily_export(&content)
+        .map_err(|e| AppError::Validation(format!("Not a ccusage daily JSON export: {e}")))?;
+    storage::save_import(&state.config_dir, &source, &days)
+        .map_err(|e| AppError::Config(e.to_string()))?;
+    run_refresh(&app, &state, false).await
+}
+
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.get_config", skip_all)]
 pub async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, AppError> {
-    let config = state.config.lock().await;
-    Ok(config.clone())
+    let config = state.config.lock().await.clone();
+    Ok(visible_config(config))
+}
+
+/// `config` as the web views may see it: with its credentials masked while
+/// the app lock is engaged.
+fn visible_config(config: AppConfig) -> AppConfig {
+    if app_lock::is_locked(config.app_lock) {
+        diagnostics::redact_config(config)
+    } else {
+        config
+    }
 }
 
+/// Config, the last usage snapshot, redacted providers and setup status in a
+/// single call, so the dashboard can paint without waiting on several
+/// roundtrips. Never triggers a refresh.
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
-pub async fn save_config(
-    app: AppHandle,
+#[tracing::instrument(name = "command.get_boot_payload", skip_all)]
+pub async fn get_boot_payload(state: State<'_, AppState>) -> Result<BootPayload, AppError> {
+    let config = visible_config(state.config.lock().await.clone());
+    let usage = state.usage_snapshot().await;
+    let providers = providers::load_providers(&state.config_dir)?;
+
+    let claude_code = &config.data_sources.claude_code;
+    let setup = SetupStatus {
+        claude_data_found: !claude_logs::project_dirs(&claude_code.data_dirs()).is_empty(),
+        ccusage_path_set: claude_code.ccusage_path().is_some(),
+        enabled_providers: providers.iter().filter(|p| p.enabled).count(),
+        usage_ready: usage.is_some(),
+    };
+
+    Ok(BootPayload {
+        config,
+        usage,
+        providers: providers
+            .into_iter()
+            .map(providers::redact_provider)
+            .collect(),
+        setup,
+    })
+}
+
+/// Renders a tray title format with the current usage and lists any
+/// placeholders it does not recognize, for live feedback while editing.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.validate_tray_format", skip_all)]
+pub async fn validate_tray_format(
     state: State<'_, AppState>,
-    config: AppConfig,
-) -> Result<(), AppError> {
+    format: String,
+) -> Result<TrayFormatPreview, AppError> {
+    let usage = state.usage_snapshot().await.unwrap_or_default();
+    Ok(TrayFormatPreview {
+        preview: tray::format_tray_title(&format, &usage),
+        unknown_variables: tray::unknown_tray_variables(&format),
+    })
+}
+
+/// Every experimental feature with its effective state under the current config.
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.list_features", skip_all)]
+pub async fn list_features(state: State<'_, AppState>) -> Result<Vec<FeatureInfo>, AppError> {
+    let config = state.config.lock().await;
+    Ok(Feature::ALL
+        .into_iter()
+        .map(|feature| FeatureInfo {
+            id: feature,
+            description: feature.description().to_string(),
+            enabled: config.features.is_enabled(feature),
+            default_enabled: feature.default_enabled(),
+        })
+        .collect())
+}
+
+/// JSON Schema of [`AppConfig`]: field names, types, defaults, documented
+/// descriptions and the numeric ranges [`save_config`] enforces.
+#[tauri::command]
+#[tracing::instrument(name = "command.get_config_schema", skip_all)]
+pub fn get_config_schema() -> Result<serde_json::Value, AppError> {
+    Ok(serde_json::to_value(schemars::schema_for!(AppConfig))?)
+}
+
+fn is_http_url(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+}
+
+fn validate_proxy_config(proxy: &ProxySourceConfig) -> Result<(), AppError> {
+    if proxy.port < MIN_PROXY_PORT {
+        return Err(AppError::Validation(format!(
+            "proxy port must be at least {MIN_PROXY_PORT}"
+        )));
+    }
+    for upstream in [
+        &proxy.openai_upstream,
+        &proxy.anthropic_upstream,
+        &proxy.ollama_upstream,
+    ] {
+        if !is_http_url(upstream) {
+            return Err(AppError::Validation(format!(
+                "proxy upstream must be an http(s) URL: {upstream}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Accepts `+`-joined combos of at least one modifier (`CmdOrCtrl`, `Cmd`,
+/// `Ctrl`, `Alt`, `Shift`) and a final letter, digit or `F1`-`F24` key.
+fn is_valid_hotkey(hotkey: &str) -> bool {
+    let parts: Vec<&str> = hotkey.split('+').map(str::trim).collect();
+    let Some((key, modifiers)) = parts.split_last() else {
+        return false;
+    };
+    let key_ok = (key.len() == 1 && key.chars().all(|c| c.is_ascii_alphanumeric()))
+        || key
+            .strip_prefix(['F', 'f'])
+            .and_then(|n| n.parse::<u8>().ok())
+            .is_some_and(|n| (1..=24).contains(&n));
+    key_ok
+        && !modifiers.is_empty()
+        && modifiers.iter().all(|m| {
+            ["cmdorctrl", "cmd", "ctrl", "alt", "shift"].contains(&m.to_ascii_lowercase().as_str())
+        })
+}
+
+fn validate_refresh_hotkey(hotkey: Option<&str>) -> Result<(), AppError> {
+    match hotkey {
+        Some(hotkey) if !is_valid_hotkey(hotkey) => Err(AppError::Validation(format!(
+            "refresh hotkey must be modifiers plus a letter, digit or F-key: {hotkey}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn validate_copilot_config(copilot: &CopilotSourceConfig) -> Result<(), AppError> {
+    if !copilot.enabled {
+        return Ok(());
+    }
+    // GitHub usernames are alphanumeric with single hyphens; anything else
+    // would change the billing API path.
+    let username = copilot.username.trim();
+    if username.is_empty()
+        || !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(AppError::Validation(
+            "Copilot username must be a GitHub username".into(),
+        ));
+    }
+    if copilot.monthly_allowance == 0 {
+        return Err(AppError::Validation(
+            "Copilot monthly allowance must be at least 1".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_guard_config(guard: &GuardConfig, proxy: &ProxySourceConfig) -> Result<(), AppError> {
+    if !guard.enabled {
+        return Ok(());
+    }
+    if guard.port < MIN_PROXY_PORT {
+        return Err(AppError::Validation(format!(
+            "guard port must be at least {MIN_PROXY_PORT}"
+        )));
+    }
+    if proxy.enabled && guard.port == proxy.port {
+        return Err(AppError::Validation(
+            "guard port must differ from the proxy port".into(),
+        ));
+    }
+    if guard.token.trim().len() < 16 {
+        return Err(AppError::Validation(
+            "guard token must be at least 16 characters".into(),
+        ));
+    }
+    if !guard.min_headroom.is_finite() || guard.min_headroom < 0.0 {
+        return Err(AppError::Validation(
+            "guard headroom must be a non-negative number".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_limits(limits: &LimitsConfig) -> Result<(), AppError> {
+    let valid = limits.near_percent.is_finite()
+        && limits.over_percent.is_finite()
+        && 0.0 <= limits.near_percent
+        && limits.near_percent < limits.over_percent
+        && limits.over_percent <= 100.0;
+    if !valid {
+        return Err(AppError::Validation(
+            "limit thresholds must satisfy 0 <= near < over <= 100".into(),
+        ));
+    }
+    Ok(())
+}
+
+const DRAIN_WINDOW_MINUTES: std::ops::RangeInclusive<u32> = 5..=1440;
+const KEY_EXPIRY_DAYS: std::ops::RangeInclusive<u32> = 1..=90;
+
+fn validate_balance_drain(drain: &BalanceDrainConfig) -> Result<(), AppError> {
+    if !(drain.percent > 0.0 && drain.percent <= 100.0) {
+        return Err(AppError::Validation(
+            "Balance drain percent must be above 0 and at most 100".into(),
+        ));
+    }
+    if !DRAIN_WINDOW_MINUTES.contains(&drain.window_minutes) {
+        return Err(AppError::Validation(format!(
+            "Balance drain window must be between {} and {} minutes",
+            DRAIN_WINDOW_MINUTES.start(),
+            DRAIN_WINDOW_MINUTES.end()
+        )));
+    }
+    Ok(())
+}
+
+fn validate_hooks(refresh_hooks: &[RefreshHook]) -> Result<(), AppError> {
+    let mut names = HashSet::new();
+    for hook in refresh_hooks {
+        let name = hook.name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation("every hook needs a name".into()));
+        }
+        if !names.insert(name) {
+            return Err(AppError::Validation(format!("duplicate hook name: {name}")));
+        }
+        hooks::parse_command(&hook.command)
+            .map_err(|e| AppError::Validation(format!("hook {name}: {e}")))?;
+    }
+    Ok(())
+}
+
+fn validate_timeouts(timeouts: &TimeoutsConfig) -> Result<(), AppError> {
+    if !(MIN_CCUSAGE_TIMEOUT..=MAX_CCUSAGE_TIMEOUT).contains(&timeouts.ccusage_secs) {
+        return Err(AppError::Validation(format!(
+            "ccusage timeout must be between {MIN_CCUSAGE_TIMEOUT} and {MAX_CCUSAGE_TIMEOUT} seconds"
+        )));
+    }
+    for (name, secs) in [
+        ("provider", timeouts.provider_secs),
+        ("pricing", timeouts.pricing_secs),
+        ("integration", timeouts.integration_secs),
+    ] {
+        if !(1..=MAX_REQUEST_TIMEOUT).contains(&secs) {
+            return Err(AppError::Validation(format!(
+                "{name} timeout must be between 1 and {MAX_REQUEST_TIMEOUT} seconds"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects settings out of range or inconsistent with each other.
+#[allow(clippy::too_many_lines)]
+fn validate_config(config: &AppConfig) -> Result<(), AppError> {
     if config.refresh_interval < MIN_REFRESH_INTERVAL
         || config.refresh_interval > MAX_REFRESH_INTERVAL
     {
@@ -144,16 +802,323 @@ pub async fn save_config(
         )));
     }
 
+    if config
+        .menu_bar
+        .weekend_budget
+        .is_some_and(|budget| !budget.is_finite() || budget < 0.0)
+    {
+        return Err(AppError::Validation(
+            "weekend_budget must be a non-negative number".into(),
+        ));
+    }
+    if config
+        .menu_bar
+        .monthly_budget
+        .is_some_and(|budget| !budget.is_finite() || budget < 0.0)
+    {
+        return Err(AppError::Validation(
+            "monthly_budget must be a non-negative number".into(),
+        ));
+    }
+    if !config.min_model_daily_cost.is_finite() || config.min_model_daily_cost < 0.0 {
+        return Err(AppError::Validation(
+            "min_model_daily_cost must be a non-negative number".into(),
+        ));
+    }
+    let notion = &config.integrations.notion;
+    if notion.enabled
+        && (notion.token.trim().is_empty()
+            || notion.database_id.trim().is_empty()
+            || notion.title_property.trim().is_empty())
+    {
+        return Err(AppError::Validation(
+            "Notion integration requires a token, database ID and title property".into(),
+        ));
+    }
+    if config.integrations.email_digest.enabled {
+        digest::validate_config(&config.integrations.email_digest)
+            .map_err(|e| AppError::Validation(e.to_string()))?;
+    }
+    if config.alerts.push.enabled {
+        push::validate_config(&config.alerts.push)
+            .map_err(|e| AppError::Validation(e.to_string()))?;
+    }
+    validate_balance_drain(&config.alerts.balance_drain)?;
+    if !KEY_EXPIRY_DAYS.contains(&config.alerts.key_expiry.days_before) {
+        return Err(AppError::Validation(format!(
+            "Key expiry reminders must be sent between {} and {} days ahead",
+            KEY_EXPIRY_DAYS.start(),
+            KEY_EXPIRY_DAYS.end()
+        )));
+    }
+    if config.integrations.remote_snapshot.enabled {
+        remote_snapshot::validate_config(&config.integrations.remote_snapshot)
+            .map_err(|e| AppError::Validation(e.to_string()))?;
+    }
+    if !(0.0..50.0).contains(&config.trends.outlier_percent) {
+        return Err(AppError::Validation(
+            "outlier_percent must be between 0 and 50".into(),
+        ));
+    }
+    if let Some(path) = config.data_sources.claude_code.ccusage_path() {
+        if !std::path::Path::new(path).is_absolute() {
+            return Err(AppError::Validation(
+                "ccusage path must be an absolute path".into(),
+            ));
+        }
+    }
+    if config
+        .data_sources
+        .claude_code
+        .data_dirs()
+        .iter()
+        .any(|dir| dir.contains(',') || !std::path::Path::new(dir).is_absolute())
+    {
+        return Err(AppError::Validation(
+            "Claude data directories must be absolute paths without commas".into(),
+        ));
+    }
+    validate_proxy_config(&config.data_sources.proxy)?;
+    let litellm = &config.data_sources.litellm;
+    if litellm.enabled && !is_http_url(&litellm.base_url) {
+        return Err(AppError::Validation(
+            "LiteLLM base URL must be an http(s) URL".into(),
+        ));
+    }
+    validate_refresh_hotkey(config.refresh_hotkey.as_deref())?;
+    if config
+        .menu_bar
+        .hard_budget_percent
+        .is_some_and(|percent| !percent.is_finite() || percent <= 100.0)
+    {
+        return Err(AppError::Validation(
+            "hard budget must be more than 100% of the soft budget".into(),
+        ));
+    }
+    if !(0.01..=100.0).contains(&config.menu_bar.privacy_scale) {
+        return Err(AppError::Validation(
+            "privacy scale must be between 0.01 and 100".into(),
+        ));
+    }
+    if config
+        .billing_cycle_day
+        .is_some_and(|day| !(1..=31).contains(&day))
+    {
+        return Err(AppError::Validation(
+            "billing cycle day must be between 1 and 31".into(),
+        ));
+    }
+    validate_copilot_config(&config.data_sources.copilot)?;
+    validate_guard_config(&config.guard, &config.data_sources.proxy)?;
+    validate_timeouts(&config.timeouts)?;
+    validate_limits(&config.limits)?;
+    validate_hooks(&config.integrations.hooks)?;
+    if config.provider_cache_ttl_secs > MAX_PROVIDER_CACHE_TTL {
+        return Err(AppError::Validation(format!(
+            "provider cache TTL must be at most {MAX_PROVIDER_CACHE_TTL} seconds"
+        )));
+    }
+    if config.app_lock.grace_minutes > MAX_LOCK_GRACE_MINUTES {
+        return Err(AppError::Validation(format!(
+            "app lock grace period must be at most {MAX_LOCK_GRACE_MINUTES} minutes"
+        )));
+    }
+    Ok(())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+#[tauri::command]
+#[tracing::instrument(name = "command.save_config", skip_all)]
+pub async fn save_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: AppConfig,
+) -> Result<(), AppError> {
+    validate_config(&config)?;
+    let current_lock = state.config.lock().await.app_lock;
+    // A locked caller only sees masked credentials and must not save them back
+    app_lock::ensure_unlocked(current_lock)?;
+    if let Some(reason) = app_lock::toggle_reason(current_lock, config.app_lock) {
+        app_lock::authenticate(reason).await?;
+    }
+
     state
         .save_config(&config)
         .map_err(|e| AppError::Config(e.to_string()))?;
-    *state.config.lock().await = config.clone();
+    let previous = std::mem::replace(&mut *state.config.lock().await, config.clone());
 
-    // Update menubar title to reflect new display format
-    if let Some(usage) = state.usage.lock().await.as_ref() {
-        tray::update_tray_menu(&app, usage, &config, &[]);
+    if previous.data_sources.proxy != config.data_sources.proxy {
+        state.restart_proxy(&config.data_sources.proxy).await;
+    }
+    if previous.guard != config.guard {
+        state.restart_guard(&app, &config.guard).await;
+    }
+
+    // Update menubar title to reflect new display format and trend baseline
+    if let Some(usage) = state.usage.write().await.as_mut() {
+        // Copies the summary only while a reader still holds the old one
+        trends::apply_trend(Arc::make_mut(usage), &config.trends);
+        tray::update_tray_menu(&app, Arc::clone(usage), config.clone(), &[]);
     }
     let _ = app.emit("config-updated", &config);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property<'a>(
+        schema: &'a serde_json::Value,
+        definition: Option<&str>,
+        field: &str,
+    ) -> &'a serde_json::Value {
+        let object = definition.map_or(schema, |name| &schema["definitions"][name]);
+        &object["properties"][field]
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_config_schema_matches_validation() {
+        let schema = get_config_schema().expect("schema serializes");
+
+        let refresh = property(&schema, None, "refreshInterval");
+        assert_eq!(
+            refresh["minimum"],
+            serde_json::json!(MIN_REFRESH_INTERVAL as f64)
+        );
+        assert_eq!(
+            refresh["maximum"],
+            serde_json::json!(MAX_REFRESH_INTERVAL as f64)
+        );
+
+        let ccusage = property(&schema, Some("TimeoutsConfig"), "ccusageSecs");
+        assert_eq!(
+            ccusage["minimum"],
+            serde_json::json!(MIN_CCUSAGE_TIMEOUT as f64)
+        );
+        assert_eq!(
+            ccusage["maximum"],
+            serde_json::json!(MAX_CCUSAGE_TIMEOUT as f64)
+        );
+        assert_eq!(ccusage["default"], serde_json::json!(60));
+        let provider = property(&schema, Some("TimeoutsConfig"), "providerSecs");
+        assert_eq!(
+            provider["maximum"],
+            serde_json::json!(MAX_REQUEST_TIMEOUT as f64)
+        );
+
+        let port = property(&schema, Some("ProxySourceConfig"), "port");
+        assert_eq!(
+            port["minimum"],
+            serde_json::json!(f64::from(MIN_PROXY_PORT))
+        );
+
+        let aliases = property(&schema, None, "modelAliases");
+        assert!(aliases["description"]
+            .as_str()
+            .is_some_and(|d| d.contains("display names")));
+
+        let allowance = property(&schema, Some("CopilotSourceConfig"), "monthlyAllowance");
+        assert_eq!(allowance["minimum"], serde_json::json!(1.0));
+    }
+
+    #[test]
+    fn test_validate_import_source() {
+        assert!(validate_import_source("laptop").is_ok());
+        assert!(validate_import_source("work_desktop-2").is_ok());
+        assert!(validate_import_source("").is_err());
+        assert!(validate_import_source("../history").is_err());
+        assert!(validate_import_source(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_is_valid_hotkey() {
+        assert!(is_valid_hotkey("CmdOrCtrl+Shift+R"));
+        assert!(is_valid_hotkey("alt+F5"));
+        assert!(is_valid_hotkey("Ctrl + 1"));
+        assert!(!is_valid_hotkey("R"));
+        assert!(!is_valid_hotkey("Shift+"));
+        assert!(!is_valid_hotkey("Hyper+R"));
+        assert!(!is_valid_hotkey("Ctrl+F25"));
+        assert!(!is_valid_hotkey("Ctrl+Enter"));
+    }
+
+    #[test]
+    fn test_validate_timeouts() {
+        let timeouts = |ccusage_secs, provider_secs| TimeoutsConfig {
+            ccusage_secs,
+            provider_secs,
+            ..TimeoutsConfig::default()
+        };
+        assert!(validate_timeouts(&TimeoutsConfig::default()).is_ok());
+        assert!(validate_timeouts(&timeouts(MIN_CCUSAGE_TIMEOUT, 1)).is_ok());
+        assert!(validate_timeouts(&timeouts(MAX_CCUSAGE_TIMEOUT, MAX_REQUEST_TIMEOUT)).is_ok());
+        assert!(validate_timeouts(&timeouts(MIN_CCUSAGE_TIMEOUT - 1, 30)).is_err());
+        assert!(validate_timeouts(&timeouts(MAX_CCUSAGE_TIMEOUT + 1, 30)).is_err());
+        assert!(validate_timeouts(&timeouts(60, 0)).is_err());
+        assert!(validate_timeouts(&timeouts(60, MAX_REQUEST_TIMEOUT + 1)).is_err());
+    }
+
+    #[test]
+    fn test_validate_limits() {
+        assert!(validate_limits(&LimitsConfig::default()).is_ok());
+        let inverted = LimitsConfig {
+            near_percent: 90.0,
+            over_percent: 80.0,
+            ..LimitsConfig::default()
+        };
+        assert!(validate_limits(&inverted).is_err());
+        let over_full = LimitsConfig {
+            over_percent: 120.0,
+            ..LimitsConfig::default()
+        };
+        assert!(validate_limits(&over_full).is_err());
+    }
+
+    #[test]
+    fn test_validate_copilot_config() {
+        let mut copilot = CopilotSourceConfig::default();
+        assert!(validate_copilot_config(&copilot).is_ok());
+
+        copilot.enabled = true;
+        assert!(validate_copilot_config(&copilot).is_err());
+        copilot.username = "octo-cat".to_string();
+        assert!(validate_copilot_config(&copilot).is_ok());
+        copilot.username = "../orgs/acme".to_string();
+        assert!(validate_copilot_config(&copilot).is_err());
+
+        copilot.username = "octocat".to_string();
+        copilot.monthly_allowance = 0;
+        assert!(validate_copilot_config(&copilot).is_err());
+    }
+
+    #[test]
+    fn test_validate_hooks() {
+        let hook = |name: &str, command: &str| RefreshHook {
+            name: name.to_string(),
+            enabled: true,
+            command: command.to_string(),
+        };
+        assert!(validate_hooks(&[]).is_ok());
+        assert!(validate_hooks(&[hook(" ", "/bin/cat")]).is_err());
+        assert!(validate_hooks(&[hook("sync", "cat")]).is_err());
+        #[cfg(unix)]
+        {
+            assert!(validate_hooks(&[hook("sync", "/bin/cat")]).is_ok());
+            assert!(validate_hooks(&[hook("sync", "/bin/cat"), hook("sync", "/bin/cat")]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_balance_drain() {
+        let mut drain = BalanceDrainConfig::default();
+        assert!(validate_balance_drain(&drain).is_ok());
+        drain.percent = 0.0;
+        assert!(validate_balance_drain(&drain).is_err());
+        drain.percent = 25.0;
+        drain.window_minutes = 2;
+        assert!(validate_balance_drain(&drain).is_err());
+    }
+}
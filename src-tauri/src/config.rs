@@ -1,8 +1,38 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Palette used to signal near-budget and over-budget levels in the tray.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
+pub enum ColorScheme {
+    /// System orange and red.
+    #[default]
+    Standard,
+    /// Fully saturated colors for maximum contrast.
+    HighContrast,
+    /// Orange and blue, distinguishable with common color vision deficiencies.
+    ColorblindSafe,
+    /// No color; appends ●/▲/■ markers to the title instead.
+    Monochrome,
+}
+
+/// How privacy mode obscures amounts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PrivacyStyle {
+    /// Replaces dollar amounts with "$•••".
+    #[default]
+    Mask,
+    /// Multiplies costs and token counts by `privacy_scale`, so trends and
+    /// proportions stay readable while the real figures do not.
+    Scale,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::struct_excessive_bools)]
 pub struct MenuBarConfig {
     pub format: String,
     pub threshold_mode: String,
@@ -10,32 +40,1075 @@ pub struct MenuBarConfig {
     #[serde(default = "default_near_budget_threshold_percent")]
     pub near_budget_threshold_percent: f64,
     pub show_color_coding: bool,
+    /// Daily budget on Saturdays and Sundays. `None` uses `fixed_budget` every day;
+    /// `0` leaves weekends unbudgeted (no color coding).
+    #[serde(default)]
+    pub weekend_budget: Option<f64>,
+    /// Budget for the current period's cost (the billing cycle, or the rolling
+    /// 30 days). `None` or `0` leaves the period unbudgeted.
+    #[serde(default)]
+    pub monthly_budget: Option<f64>,
+    /// Hard budget as a percent (above 100) of the soft daily and monthly
+    /// budgets and of the block and weekly limits. Reaching it turns the tray
+    /// red; for the daily budget it also sends a persistent alert and can deny
+    /// guard checks. `None` leaves only the soft budget.
+    #[serde(default)]
+    pub hard_budget_percent: Option<f64>,
+    /// Show a "Recent Days" submenu with the last week's daily costs in the tray menu.
+    #[serde(default)]
+    pub show_recent_days: bool,
+    /// Show the key or member with the highest cost today in the tray menu.
+    #[serde(default)]
+    pub show_top_spender: bool,
+    /// Offer a "Profile" submenu in the tray menu for switching config profiles.
+    #[serde(default)]
+    pub show_profiles: bool,
+    /// Describe the tray to screen readers in full sentences rather than reading the title.
+    #[serde(default = "default_verbose_accessibility")]
+    pub verbose_accessibility: bool,
+    #[serde(default)]
+    pub color_scheme: ColorScheme,
+    /// Obscure amounts in the tray and windows, for streaming and screen sharing.
+    #[serde(default)]
+    pub privacy_mode: bool,
+    #[serde(default)]
+    pub privacy_style: PrivacyStyle,
+    /// Factor applied to amounts by [`PrivacyStyle::Scale`].
+    #[serde(default = "default_privacy_scale")]
+    #[schemars(range(min = 0.01, max = 100))]
+    pub privacy_scale: f64,
+}
+
+const fn default_privacy_scale() -> f64 {
+    0.37
+}
+
+const fn default_verbose_accessibility() -> bool {
+    true
+}
+
+const fn default_near_budget_threshold_percent() -> f64 {
+    10.0
+}
+
+impl MenuBarConfig {
+    /// Daily budget that applies on `date`, honoring `weekend_budget` on weekends.
+    #[must_use]
+    pub fn budget_for(&self, date: chrono::NaiveDate) -> f64 {
+        use chrono::Datelike;
+        let is_weekend = matches!(date.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun);
+        match self.weekend_budget {
+            Some(budget) if is_weekend => budget,
+            _ => self.fixed_budget,
+        }
+    }
+
+    /// Hard budget that applies on `date`, if a hard tier is configured and the
+    /// day has a soft budget.
+    #[must_use]
+    pub fn hard_budget_for(&self, date: chrono::NaiveDate) -> Option<f64> {
+        self.hard_budget(self.budget_for(date))
+    }
+
+    /// Hard tier of the monthly budget, if both are configured.
+    #[must_use]
+    pub fn hard_monthly_budget(&self) -> Option<f64> {
+        self.hard_budget(self.monthly_budget.unwrap_or_default())
+    }
+
+    /// Hard budget for a `soft` budget, if a hard tier is configured and the
+    /// soft budget is set.
+    fn hard_budget(&self, soft: f64) -> Option<f64> {
+        self.hard_budget_percent
+            .filter(|_| soft > 0.0)
+            .map(|percent| soft * percent / 100.0)
+    }
+}
+
+impl Default for MenuBarConfig {
+    fn default() -> Self {
+        Self {
+            format: "${cost} ${tokens}".to_string(),
+            threshold_mode: "fixed".to_string(),
+            fixed_budget: 15.0,
+            near_budget_threshold_percent: default_near_budget_threshold_percent(),
+            show_color_coding: true,
+            weekend_budget: None,
+            monthly_budget: None,
+            hard_budget_percent: None,
+            show_recent_days: false,
+            show_top_spender: false,
+            show_profiles: false,
+            verbose_accessibility: default_verbose_accessibility(),
+            color_scheme: ColorScheme::Standard,
+            privacy_mode: false,
+            privacy_style: PrivacyStyle::Mask,
+            privacy_scale: default_privacy_scale(),
+        }
+    }
+}
+
+/// Cursor usage source settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `WorkosCursorSessionToken` cookie value. When unset, the token is read
+    /// from Cursor's local state database.
+    #[serde(default)]
+    pub session_token: Option<String>,
+}
+
+/// Windsurf (Cascade Analytics API) usage source settings.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WindsurfSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Service key with the "Analytics Read" permission.
+    #[serde(default)]
+    pub service_key: Option<String>,
+    /// Restricts results to a single user; all team members are included when unset.
+    #[serde(default)]
+    pub email: Option<String>,
+    /// USD price of one prompt credit, used to convert credits into cost.
+    #[serde(default = "default_windsurf_credit_price")]
+    pub credit_price: f64,
+}
+
+const fn default_windsurf_credit_price() -> f64 {
+    0.04
+}
+
+impl Default for WindsurfSourceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            service_key: None,
+            email: None,
+            credit_price: default_windsurf_credit_price(),
+        }
+    }
+}
+
+/// `OpenAI` platform (organization usage and costs API) source settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenAiSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Organization admin key (`sk-admin-...`); regular project keys cannot read usage.
+    /// It is kept in the keychain: a key written here is moved there when the
+    /// config is loaded, and never written back.
+    #[serde(default, skip_serializing)]
+    pub admin_key: Option<String>,
+    /// Restricts results to a single project; the whole organization is included when unset.
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+/// `OpenRouter` activity and credits source settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenRouterSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Provisioning (management) key; `/activity` rejects regular inference keys.
+    /// It is kept in the keychain: a key written here is moved there when the
+    /// config is loaded, and never written back.
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+/// `LiteLLM` proxy spend source settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LiteLlmSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Proxy base URL, e.g. `http://localhost:4000`.
+    #[serde(default)]
+    pub base_url: String,
+    /// Master or admin key allowed to read spend; omitted for unauthenticated proxies.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// GitHub Copilot premium request source settings.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CopilotSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// GitHub username whose premium requests are billed.
+    #[serde(default)]
+    pub username: String,
+    /// Fine-grained token with the "Plan" user permission (read).
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Premium requests included in the plan each month (300 on Pro and
+    /// Business, 1000 on Enterprise, 1500 on Pro+).
+    #[serde(default = "default_copilot_allowance")]
+    #[schemars(range(min = 1))]
+    pub monthly_allowance: u32,
+}
+
+const fn default_copilot_allowance() -> u32 {
+    300
+}
+
+impl Default for CopilotSourceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            username: String::new(),
+            token: None,
+            monthly_allowance: default_copilot_allowance(),
+        }
+    }
+}
+
+/// Token usage of local Ollama models, recorded by the API proxy's `/ollama`
+/// route. Local inference is free, so this source only adds tokens.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OllamaSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Local API proxy that records per-key usage for OpenAI/Anthropic-compatible clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_proxy_port")]
+    #[schemars(range(min = 1024))]
+    pub port: u16,
+    #[serde(default = "default_openai_upstream")]
+    pub openai_upstream: String,
+    #[serde(default = "default_anthropic_upstream")]
+    pub anthropic_upstream: String,
+    /// Local Ollama server that `/ollama/...` requests are forwarded to.
+    #[serde(default = "default_ollama_upstream")]
+    pub ollama_upstream: String,
+}
+
+const fn default_proxy_port() -> u16 {
+    8787
+}
+
+fn default_openai_upstream() -> String {
+    "https://api.openai.com".to_string()
+}
+
+fn default_anthropic_upstream() -> String {
+    "https://api.anthropic.com".to_string()
+}
+
+fn default_ollama_upstream() -> String {
+    "http://127.0.0.1:11434".to_string()
+}
+
+impl Default for ProxySourceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_proxy_port(),
+            openai_upstream: default_openai_upstream(),
+            anthropic_upstream: default_anthropic_upstream(),
+            ollama_upstream: default_ollama_upstream(),
+        }
+    }
+}
+
+/// Dotted JSON paths (e.g. `usage.input_tokens`) locating usage fields in each JSONL line.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonlFieldMapping {
+    pub timestamp: String,
+    pub model: String,
+    pub input_tokens: String,
+    pub output_tokens: String,
+    #[serde(default)]
+    pub cache_creation_tokens: Option<String>,
+    #[serde(default)]
+    pub cache_read_tokens: Option<String>,
+    /// Cost in USD; estimated from model prices when unset.
+    #[serde(default)]
+    pub cost: Option<String>,
+    /// API key alias or user the line is attributed to (e.g. `metadata.user_api_key_alias`
+    /// in `LiteLLM` spend logs), enabling a per-key breakdown.
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+/// User-defined source that ingests JSONL logs written by arbitrary tools.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomJsonlSourceConfig {
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub enabled: bool,
+    /// File glob supporting `*`, `?`, `**` and a leading `~/`.
+    pub path_glob: String,
+    pub fields: JsonlFieldMapping,
+}
+
+/// Package runner tried when `ccusage` is not installed globally.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CcusageRunner {
+    /// `npx -y ccusage`, then `bunx ccusage`.
+    #[default]
+    Auto,
+    Npx,
+    Bunx,
+    /// Only a globally installed ccusage is run.
+    Disabled,
+}
+
+/// How Claude Code usage is read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeCodeSourceConfig {
+    /// Absolute path of a ccusage executable. When set, it is run directly
+    /// (no login shell) in place of parsing the transcripts natively.
+    #[serde(default)]
+    pub ccusage_path: Option<String>,
+    /// Claude data directories (each holding a `projects/` folder) to read.
+    /// Empty falls back to `CLAUDE_CONFIG_DIR`, then `~/.config/claude` and `~/.claude`.
+    #[serde(default)]
+    pub data_dirs: Vec<String>,
+    /// Fallback used when `ccusage` is not on `PATH`.
+    #[serde(default)]
+    pub runner: CcusageRunner,
+}
+
+impl ClaudeCodeSourceConfig {
+    /// The configured ccusage executable, ignoring blank values.
+    #[must_use]
+    pub fn ccusage_path(&self) -> Option<&str> {
+        self.ccusage_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+    }
+
+    /// The configured data directories, ignoring blank entries.
+    #[must_use]
+    pub fn data_dirs(&self) -> Vec<String> {
+        self.data_dirs
+            .iter()
+            .map(|dir| dir.trim())
+            .filter(|dir| !dir.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Additional usage sources merged into the summary alongside ccusage.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSourcesConfig {
+    #[serde(default)]
+    pub claude_code: ClaudeCodeSourceConfig,
+    #[serde(default)]
+    pub cursor: CursorSourceConfig,
+    #[serde(default)]
+    pub windsurf: WindsurfSourceConfig,
+    #[serde(default)]
+    pub openai: OpenAiSourceConfig,
+    #[serde(default)]
+    pub openrouter: OpenRouterSourceConfig,
+    #[serde(default)]
+    pub litellm: LiteLlmSourceConfig,
+    #[serde(default)]
+    pub ollama: OllamaSourceConfig,
+    #[serde(default)]
+    pub copilot: CopilotSourceConfig,
+    #[serde(default)]
+    pub custom_jsonl: Vec<CustomJsonlSourceConfig>,
+    #[serde(default)]
+    pub proxy: ProxySourceConfig,
+}
+
+/// Notion database that daily usage rows are upserted into after each refresh.
+///
+/// The database needs a title property (default "Date") holding `YYYY-MM-DD`,
+/// number properties "Cost" and "Tokens", and a text property "Top Model".
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotionIntegrationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Internal integration secret (`secret_...` / `ntn_...`).
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub database_id: String,
+    /// Name of the database's title property, used as the upsert key.
+    #[serde(default = "default_notion_title_property")]
+    pub title_property: String,
+}
+
+fn default_notion_title_property() -> String {
+    "Date".to_string()
+}
+
+impl Default for NotionIntegrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: String::new(),
+            database_id: String::new(),
+            title_property: default_notion_title_property(),
+        }
+    }
+}
+
+/// Weekly HTML usage digest sent over SMTP by the scheduler.
+///
+/// The SMTP password is kept in the system keychain, not in this file.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailDigestConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `smtps://host:465` (implicit TLS) or `smtp://host:587` (STARTTLS required).
+    #[serde(default)]
+    pub smtp_url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub from: String,
+    #[serde(default)]
+    pub to: Vec<String>,
+    /// Day the digest is sent, 0 = Monday through 6 = Sunday.
+    #[serde(default)]
+    pub weekday: u32,
+    /// Local hour (0-23) on or after which the digest is sent.
+    #[serde(default = "default_email_digest_hour")]
+    pub hour: u32,
+}
+
+const fn default_email_digest_hour() -> u32 {
+    9
+}
+
+impl Default for EmailDigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            smtp_url: String::new(),
+            username: String::new(),
+            from: String::new(),
+            to: Vec::new(),
+            weekday: 0,
+            hour: default_email_digest_hour(),
+        }
+    }
+}
+
+/// Encrypted usage snapshot pushed after each refresh for a companion app or
+/// second machine to read.
+///
+/// The passphrase the snapshot key is derived from lives in the system keychain.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSnapshotConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Destination the snapshot is `PUT` to: a relay endpoint, `WebDAV` file or
+    /// presigned S3 object URL.
+    #[serde(default)]
+    pub url: String,
+}
+
+/// External services usage data is pushed to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrationsConfig {
+    #[serde(default)]
+    pub notion: NotionIntegrationConfig,
+    #[serde(default)]
+    pub email_digest: EmailDigestConfig,
+    #[serde(default)]
+    pub remote_snapshot: RemoteSnapshotConfig,
+    /// Local commands run after each successful refresh.
+    #[serde(default)]
+    pub hooks: Vec<RefreshHook>,
+}
+
+/// Local command run after each successful refresh with the usage summary as
+/// JSON on stdin.
+///
+/// It runs without a shell, so `command` must start with the absolute path of
+/// an executable and may not use pipes or redirection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshHook {
+    pub name: String,
+    #[serde(default)]
+    pub enabled: bool,
+    pub command: String,
+}
+
+/// Push notification service budget alerts are delivered through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PushService {
+    /// ntfy.sh or a self-hosted ntfy server.
+    #[default]
+    Ntfy,
+    Pushover,
+}
+
+/// Sends budget alerts to a phone via ntfy or Pushover.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PushNotificationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub service: PushService,
+    #[serde(default = "default_ntfy_server")]
+    pub ntfy_server: String,
+    #[serde(default)]
+    pub ntfy_topic: String,
+    /// The ntfy token and Pushover keys are kept in the keychain. These fields
+    /// only read values earlier versions saved here, so they can be moved
+    /// there; they are never written back.
+    #[serde(default, skip_serializing)]
+    pub ntfy_token: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub pushover_user_key: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub pushover_app_token: Option<String>,
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+impl Default for PushNotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            service: PushService::default(),
+            ntfy_server: default_ntfy_server(),
+            ntfy_topic: String::new(),
+            ntfy_token: None,
+            pushover_user_key: None,
+            pushover_app_token: None,
+        }
+    }
+}
+
+/// Channels near-budget and over-budget alerts are sent through.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertsConfig {
+    #[serde(default)]
+    pub push: PushNotificationConfig,
+    #[serde(default)]
+    pub balance_drain: BalanceDrainConfig,
+    #[serde(default)]
+    pub key_expiry: KeyExpiryConfig,
+}
+
+/// Alerts when a credit-based provider's (used/total) balance shrinks
+/// unusually fast, often the first sign of a leaked key. Sent through push.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceDrainConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Percent of the balance that may be used within the window.
+    #[serde(default = "default_drain_percent")]
+    #[schemars(range(min = 0, max = 100))]
+    pub percent: f64,
+    #[serde(default = "default_drain_window_minutes")]
+    #[schemars(range(min = 5, max = 1440))]
+    pub window_minutes: u32,
+}
+
+const fn default_drain_percent() -> f64 {
+    10.0
+}
+
+const fn default_drain_window_minutes() -> u32 {
+    60
+}
+
+impl Default for BalanceDrainConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            percent: default_drain_percent(),
+            window_minutes: default_drain_window_minutes(),
+        }
+    }
+}
+
+impl BalanceDrainConfig {
+    #[must_use]
+    pub fn window(&self) -> chrono::TimeDelta {
+        chrono::TimeDelta::minutes(i64::from(self.window_minutes))
+    }
+}
+
+/// Reminds, through push, that a provider env entry (typically an API key)
+/// is about to expire, so it can be rotated before fetches start failing.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyExpiryConfig {
+    #[serde(default = "default_key_expiry_enabled")]
+    pub enabled: bool,
+    /// Days before the expiry date the reminder is sent.
+    #[serde(default = "default_key_expiry_days_before")]
+    #[schemars(range(min = 1, max = 90))]
+    pub days_before: u32,
+}
+
+const fn default_key_expiry_enabled() -> bool {
+    true
+}
+
+const fn default_key_expiry_days_before() -> u32 {
+    7
+}
+
+impl Default for KeyExpiryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_key_expiry_enabled(),
+            days_before: default_key_expiry_days_before(),
+        }
+    }
+}
+
+/// What subscription limits are counted in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum LimitUnit {
+    /// Input, output and cache tokens.
+    #[default]
+    Tokens,
+    /// Assistant responses.
+    Messages,
+}
+
+/// Limit mode for Claude Pro/Max subscriptions: the tray shows how much of the
+/// rolling 5-hour and weekly limits is used instead of dollars.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub unit: LimitUnit,
+    /// Allowance per 5-hour block; 0 leaves the block untracked.
+    #[serde(default)]
+    pub block_limit: u64,
+    /// Allowance per rolling 7 days; 0 leaves the week untracked.
+    #[serde(default)]
+    pub weekly_limit: u64,
+    /// Percent of a limit used at which the tray turns orange.
+    #[serde(default = "default_limit_near_percent")]
+    #[schemars(range(min = 0, max = 100))]
+    pub near_percent: f64,
+    /// Percent of a limit used at which the tray turns red.
+    #[serde(default = "default_limit_over_percent")]
+    #[schemars(range(min = 0, max = 100))]
+    pub over_percent: f64,
+}
+
+const fn default_limit_near_percent() -> f64 {
+    75.0
+}
+
+const fn default_limit_over_percent() -> f64 {
+    95.0
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            unit: LimitUnit::default(),
+            block_limit: 0,
+            weekly_limit: 0,
+            near_percent: default_limit_near_percent(),
+            over_percent: default_limit_over_percent(),
+        }
+    }
+}
+
+/// Localhost endpoint agents call before expensive work to check remaining budget.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_guard_port")]
+    #[schemars(range(min = 1024))]
+    pub port: u16,
+    /// Bearer token callers must send; generated from the settings page.
+    #[serde(default)]
+    pub token: String,
+    /// Budget (USD) that must remain after the requested task for it to be allowed.
+    #[serde(default)]
+    pub min_headroom: f64,
+    /// Budget tier checks are made against.
+    #[serde(default)]
+    pub budget_tier: GuardBudgetTier,
 }
 
-const fn default_near_budget_threshold_percent() -> f64 {
+/// Which daily budget the guard protects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum GuardBudgetTier {
+    /// Deny once the soft budget would be used up.
+    #[default]
+    Soft,
+    /// Let agents run past the soft budget and deny only at the hard budget.
+    Hard,
+}
+
+const fn default_guard_port() -> u16 {
+    8788
+}
+
+impl Default for GuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_guard_port(),
+            token: String::new(),
+            min_headroom: 0.0,
+            budget_tier: GuardBudgetTier::default(),
+        }
+    }
+}
+
+/// Per-subsystem time limits, in seconds, for slow commands and network requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeoutsConfig {
+    /// The `ccusage` command, which can be slow on large histories. Only used
+    /// for the first runs; after that the timeout adapts to recent run times.
+    #[serde(default = "default_ccusage_timeout")]
+    #[schemars(range(min = 10, max = 600))]
+    pub ccusage_secs: u64,
+    /// Native provider APIs (Cursor, Windsurf, `OpenAI`, `OpenRouter`, `LiteLLM`).
+    #[serde(default = "default_request_timeout")]
+    #[schemars(range(min = 1, max = 120))]
+    pub provider_secs: u64,
+    /// The models.dev price list.
+    #[serde(default = "default_pricing_timeout")]
+    #[schemars(range(min = 1, max = 120))]
+    pub pricing_secs: u64,
+    /// Outbound integrations (Notion, push notifications, snapshot upload).
+    #[serde(default = "default_request_timeout")]
+    #[schemars(range(min = 1, max = 120))]
+    pub integration_secs: u64,
+}
+
+const fn default_ccusage_timeout() -> u64 {
+    60
+}
+
+const fn default_request_timeout() -> u64 {
+    15
+}
+
+const fn default_pricing_timeout() -> u64 {
+    10
+}
+
+impl TimeoutsConfig {
+    #[must_use]
+    pub const fn ccusage(&self) -> Duration {
+        Duration::from_secs(self.ccusage_secs)
+    }
+
+    #[must_use]
+    pub const fn provider(&self) -> Duration {
+        Duration::from_secs(self.provider_secs)
+    }
+
+    #[must_use]
+    pub const fn pricing(&self) -> Duration {
+        Duration::from_secs(self.pricing_secs)
+    }
+
+    #[must_use]
+    pub const fn integration(&self) -> Duration {
+        Duration::from_secs(self.integration_secs)
+    }
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            ccusage_secs: default_ccusage_timeout(),
+            provider_secs: default_request_timeout(),
+            pricing_secs: default_pricing_timeout(),
+            integration_secs: default_request_timeout(),
+        }
+    }
+}
+
+/// Baseline today's cost is compared against for trend indicators.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TrendBaseline {
+    /// Mean of the previous 30 days with usage.
+    #[default]
+    Mean30,
+    /// Median of the previous 7 days with usage.
+    Median7,
+    /// Exponentially weighted average of the previous 30 days.
+    Ewma,
+    /// Mean of the same weekday over the previous 4 weeks.
+    SameWeekday,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendsConfig {
+    #[serde(default)]
+    pub baseline: TrendBaseline,
+    /// Leave the most expensive days out of baseline averages.
+    #[serde(default)]
+    pub exclude_outliers: bool,
+    /// Share of days (highest cost first) treated as outliers when excluded.
+    #[serde(default = "default_outlier_percent")]
+    #[schemars(range(min = 0, max = 50))]
+    pub outlier_percent: f64,
+    /// Compare this month's spend with the members reported by team and
+    /// organization sources.
+    #[serde(default = "default_org_comparison")]
+    pub org_comparison: bool,
+    /// Leaderboard member (API key, user or email) that is you; your total
+    /// spend is compared when unset.
+    #[serde(default)]
+    pub org_member: Option<String>,
+}
+
+const fn default_outlier_percent() -> f64 {
     10.0
 }
 
-impl Default for MenuBarConfig {
+const fn default_org_comparison() -> bool {
+    true
+}
+
+impl Default for TrendsConfig {
     fn default() -> Self {
         Self {
-            format: "${cost} ${tokens}".to_string(),
-            threshold_mode: "fixed".to_string(),
-            fixed_budget: 15.0,
-            near_budget_threshold_percent: default_near_budget_threshold_percent(),
-            show_color_coding: true,
+            baseline: TrendBaseline::default(),
+            exclude_outliers: false,
+            outlier_percent: default_outlier_percent(),
+            org_comparison: default_org_comparison(),
+            org_member: None,
+        }
+    }
+}
+
+/// Order of model rows in the model breakdown and tray menu.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ModelSortKey {
+    /// Highest cost first.
+    #[default]
+    Cost,
+    /// Most input and output tokens first.
+    Tokens,
+    /// Alphabetical by model ID.
+    Name,
+}
+
+/// How long-range charts combine the days in each weekly bucket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ChartAggregation {
+    /// Total of the week.
+    #[default]
+    Sum,
+    /// Average per day of the week.
+    Mean,
+}
+
+/// Subsystems that can ship disabled and be switched on (or off) per user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Feature {
+    /// Reading Claude Code transcripts directly instead of running ccusage.
+    NativeParser,
+    /// Background jobs such as the weekly email digest.
+    Scheduler,
+}
+
+impl Feature {
+    pub const ALL: [Self; 2] = [Self::NativeParser, Self::Scheduler];
+
+    /// Key of the feature in [`AppConfig::features`].
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::NativeParser => "nativeParser",
+            Self::Scheduler => "scheduler",
         }
     }
+
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::NativeParser => "Parse Claude Code transcripts directly; off always runs ccusage",
+            Self::Scheduler => "Run background jobs such as the weekly email digest",
+        }
+    }
+
+    /// Whether the feature is on when the user has not set it.
+    #[must_use]
+    pub const fn default_enabled(self) -> bool {
+        match self {
+            Self::NativeParser | Self::Scheduler => true,
+        }
+    }
+}
+
+/// Per-user feature overrides keyed by [`Feature::key`]. Unset features use
+/// their default; unknown keys are kept so newer configs survive a downgrade.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct FeatureGate(HashMap<String, bool>);
+
+impl FeatureGate {
+    #[must_use]
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.0
+            .get(feature.key())
+            .copied()
+            .unwrap_or_else(|| feature.default_enabled())
+    }
+
+    /// Overrides `feature`, e.g. to ship a subsystem dark in tests.
+    pub fn set(&mut self, feature: Feature, enabled: bool) {
+        self.0.insert(feature.key().to_string(), enabled);
+    }
+}
+
+/// Requires system authentication (Touch ID or the account password) before
+/// the dashboard opens or provider secrets are read. Usage is still collected,
+/// and shown in the tray, while the app is locked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Minutes the app stays unlocked after the dashboard is closed; 0 locks it
+    /// right away.
+    #[serde(default)]
+    #[schemars(range(max = 60))]
+    pub grace_minutes: u32,
+}
+
+impl AppLockConfig {
+    #[must_use]
+    pub fn grace(&self) -> Duration {
+        Duration::from_secs(u64::from(self.grace_minutes) * 60)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfig {
+    /// Seconds between automatic usage refreshes.
+    #[schemars(range(min = 60, max = 3600))]
     pub refresh_interval: u64,
     pub launch_at_login: bool,
     pub menu_bar: MenuBarConfig,
     #[serde(default)]
     pub language: Option<String>,
+    #[serde(default)]
+    pub data_sources: DataSourcesConfig,
+    #[serde(default)]
+    pub trends: TrendsConfig,
+    /// Short display names keyed by full model ID (e.g. "Opus" for "claude-opus-4-5").
+    #[serde(default)]
+    pub model_aliases: HashMap<String, String>,
+    /// Models averaging less than this cost per day are grouped into an "Other" row.
+    #[serde(default)]
+    #[schemars(range(min = 0))]
+    pub min_model_daily_cost: f64,
+    /// Order of model rows; ties fall back to cost, then model ID.
+    #[serde(default)]
+    pub model_sort: ModelSortKey,
+    /// How weekly points of long-range charts combine their days.
+    #[serde(default)]
+    pub chart_aggregation: ChartAggregation,
+    /// Shortcut for a full refresh while a `TokenMeter` window is focused,
+    /// e.g. `CmdOrCtrl+Shift+R`.
+    #[serde(default)]
+    pub refresh_hotkey: Option<String>,
+    /// Day of the month (1-31) the billing cycle starts on. When set, the
+    /// monthly totals cover the current cycle instead of the last 30 days; in
+    /// shorter months the cycle starts on the last day.
+    #[serde(default)]
+    #[schemars(range(min = 1, max = 31))]
+    pub billing_cycle_day: Option<u32>,
+    #[serde(default)]
+    pub integrations: IntegrationsConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub guard: GuardConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutsConfig,
+    /// Seconds a provider's last successful result is reused before its fetch
+    /// runs again; 0 fetches on every refresh.
+    #[serde(default = "default_provider_cache_ttl")]
+    #[schemars(range(min = 0, max = 86400))]
+    pub provider_cache_ttl_secs: u64,
+    /// Adds the costs reported by enabled providers to the today and monthly
+    /// totals, so the tray cost covers all tracked spend.
+    #[serde(default)]
+    pub include_provider_costs: bool,
+    #[serde(default)]
+    pub app_lock: AppLockConfig,
+    /// Experimental feature overrides.
+    #[serde(default)]
+    pub features: FeatureGate,
+}
+
+const fn default_provider_cache_ttl() -> u64 {
+    300
+}
+
+impl AppConfig {
+    #[must_use]
+    pub const fn provider_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.provider_cache_ttl_secs)
+    }
+
+    /// Display name for `model`, using its alias when one is configured.
+    #[must_use]
+    pub fn model_display_name<'a>(&'a self, model: &'a str) -> &'a str {
+        self.model_aliases
+            .get(model)
+            .map(|alias| alias.trim())
+            .filter(|alias| !alias.is_empty())
+            .unwrap_or(model)
+    }
 }
 
 impl Default for AppConfig {
@@ -45,11 +1118,231 @@ impl Default for AppConfig {
             launch_at_login: false,
             menu_bar: MenuBarConfig::default(),
             language: None,
+            data_sources: DataSourcesConfig::default(),
+            trends: TrendsConfig::default(),
+            model_aliases: HashMap::new(),
+            min_model_daily_cost: 0.0,
+            model_sort: ModelSortKey::default(),
+            chart_aggregation: ChartAggregation::default(),
+            refresh_hotkey: None,
+            billing_cycle_day: None,
+            integrations: IntegrationsConfig::default(),
+            alerts: AlertsConfig::default(),
+            guard: GuardConfig::default(),
+            limits: LimitsConfig::default(),
+            timeouts: TimeoutsConfig::default(),
+            provider_cache_ttl_secs: default_provider_cache_ttl(),
+            include_provider_costs: false,
+            app_lock: AppLockConfig::default(),
+            features: FeatureGate::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+}
+
+/// HTTP request a provider makes directly instead of running a fetch script.
+/// `${VAR}` in the URL, header values and body is replaced from the provider env.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderRequest {
+    #[serde(default)]
+    pub method: HttpMethod,
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Follows a paginated response, combining every page before the transform.
+    #[serde(default)]
+    pub pagination: Option<ProviderPagination>,
+    /// Sends a GraphQL operation as the JSON body of a POST instead of `body`.
+    #[serde(default)]
+    pub graphql: Option<GraphqlQuery>,
+}
+
+/// GraphQL operation a provider request sends. A response with `errors` and
+/// no `data` fails the fetch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlQuery {
+    pub query: String,
+    /// JSON object of variables; `${VAR}` is replaced from the provider env.
+    #[serde(default)]
+    pub variables: Option<String>,
+}
+
+/// How a paginated provider request finds its next page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PaginationStrategy {
+    /// Sends the cursor found at `next_path` as the `param` query parameter.
+    #[default]
+    Cursor,
+    /// Sets the `param` query parameter to 1, 2, ... until a page has no items.
+    Page,
+    /// Requests the URL found at `next_path`, which must be on the same origin.
+    NextUrl,
+}
+
+/// Pagination of a provider request.
+///
+/// The arrays at `items_path` of every page are concatenated into the first
+/// page, which the transform script receives. Paths are dotted (`data.items`, `meta.next_cursor`); an empty `items_path`
+/// means the response itself is the array.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderPagination {
+    #[serde(default)]
+    pub strategy: PaginationStrategy,
+    #[serde(default)]
+    pub items_path: String,
+    /// Cursor or next URL location, for the cursor and next-URL strategies.
+    #[serde(default)]
+    pub next_path: String,
+    /// Query parameter set by the cursor and page strategies.
+    #[serde(default)]
+    pub param: String,
+    /// Pages fetched at most; later pages are left out.
+    #[serde(default = "default_max_pages")]
+    #[schemars(range(min = 1, max = 50))]
+    pub max_pages: u32,
+}
+
+const fn default_max_pages() -> u32 {
+    10
+}
+
+impl Default for ProviderPagination {
+    fn default() -> Self {
+        Self {
+            strategy: PaginationStrategy::default(),
+            items_path: String::new(),
+            next_path: String::new(),
+            param: String::new(),
+            max_pages: default_max_pages(),
+        }
+    }
+}
+
+/// Request run before a provider's fetch whose JSON response sets variables
+/// for later steps and the fetch, e.g. logging in to get a session token.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchStep {
+    pub request: ProviderRequest,
+    /// Dotted response paths (`data.token`) keyed by the variable they set,
+    /// used as `${NAME}` afterwards.
+    #[serde(default)]
+    pub extract: BTreeMap<String, String>,
+}
+
+/// OAuth 2.0 grant a provider uses to obtain access tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum OAuthGrant {
+    #[default]
+    ClientCredentials,
+    RefreshToken,
+}
+
+/// OAuth 2.0 settings for providers whose usage APIs need short-lived access
+/// tokens.
+///
+/// The token is cached until it expires and exposed to the fetch as
+/// `${OAUTH_ACCESS_TOKEN}`. `${VAR}` in the secrets is replaced from the env.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderOAuth {
+    #[serde(default)]
+    pub grant: OAuthGrant,
+    pub token_url: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Initial refresh token for the refresh-token grant. Rotated tokens are
+    /// kept in the system keychain.
+    #[serde(default)]
+    pub refresh_token: String,
+}
+
+/// How a provider's failed fetches are retried before it is marked as failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderRetry {
+    /// Retries after the first failed attempt; 0 disables retrying.
+    #[serde(default = "default_retry_attempts")]
+    #[schemars(range(max = 5))]
+    pub attempts: u32,
+    /// Delay before the first retry, doubled before each further one.
+    #[serde(default = "default_retry_backoff_ms")]
+    #[schemars(range(min = 100, max = 30_000))]
+    pub backoff_ms: u64,
+}
+
+const fn default_retry_attempts() -> u32 {
+    2
+}
+
+const fn default_retry_backoff_ms() -> u64 {
+    1_000
+}
+
+impl Default for ProviderRetry {
+    fn default() -> Self {
+        Self {
+            attempts: default_retry_attempts(),
+            backoff_ms: default_retry_backoff_ms(),
+        }
+    }
+}
+
+impl ProviderRetry {
+    /// Delay before retry number `retry` (0-based).
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        Duration::from_millis(self.backoff_ms.saturating_mul(1 << retry.min(16)))
+    }
+}
+
+/// What a provider's amounts count, for writing them in the tray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderUnit {
+    /// Money in the provider's currency, converted to dollars.
+    Usd,
+    Credits,
+    Requests,
+    Tokens,
+}
+
+impl ProviderUnit {
+    #[must_use]
+    pub const fn is_money(self) -> bool {
+        matches!(self, Self::Usd)
+    }
+
+    /// Word written after amounts; dollars are prefixed with `$` instead.
+    #[must_use]
+    pub const fn label(self) -> Option<&'static str> {
+        match self {
+            Self::Usd => None,
+            Self::Credits => Some("credits"),
+            Self::Requests => Some("requests"),
+            Self::Tokens => Some("tokens"),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiProvider {
     pub id: String,
@@ -57,9 +1350,82 @@ pub struct ApiProvider {
     pub enabled: bool,
     pub fetch_script: String,
     pub transform_script: String,
+    /// Result fields mapped to `JSONPath` expressions (`cost: $.data.total_cost`),
+    /// evaluated natively; takes precedence over `transform_script` when set.
+    #[serde(default)]
+    pub transform_paths: BTreeMap<String, String>,
     pub env: HashMap<String, String>,
+    /// Date env entries (typically API keys) expire, keyed by variable name.
+    #[serde(default)]
+    #[schemars(with = "HashMap<String, String>")]
+    pub env_expires_at: HashMap<String, chrono::NaiveDate>,
     pub last_fetched: Option<String>,
     pub last_error: Option<String>,
+    /// Fetches failed in a row since the last success.
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// ISO 4217 code of the currency the provider reports costs in (USD when unset).
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// What the cost and used/total balance count; plain numbers when unset.
+    #[serde(default)]
+    pub unit: Option<ProviderUnit>,
+    /// Word written after amounts instead of the unit's own, e.g. "GPU hours".
+    #[serde(default)]
+    pub unit_label: Option<String>,
+    /// Request made with the built-in HTTP client; takes precedence over
+    /// `fetch_script`, which may then be empty.
+    #[serde(default)]
+    pub request: Option<ProviderRequest>,
+    #[serde(default)]
+    pub oauth: Option<ProviderOAuth>,
+    /// Requests run in order before the fetch, chaining variables into it.
+    #[serde(default)]
+    pub steps: Vec<FetchStep>,
+    #[serde(default)]
+    pub retry: ProviderRetry,
+    /// Section the provider is listed under in the tray menu and dashboard;
+    /// ungrouped providers come first.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Position within the provider list, set by `reorder_providers`; ties
+    /// fall back to the name.
+    #[serde(default)]
+    pub sort_order: u32,
+    /// List the provider in the tray menu; when off it is still fetched for
+    /// the dashboard while enabled.
+    #[serde(default = "default_show_in_tray")]
+    pub show_in_tray: bool,
+}
+
+const fn default_show_in_tray() -> bool {
+    true
+}
+
+impl ApiProvider {
+    /// Trimmed unit label, or `None` to use the unit's own word.
+    #[must_use]
+    pub fn unit_label(&self) -> Option<&str> {
+        self.unit_label
+            .as_deref()
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+    }
+
+    /// Whether the provider's cost is money, so it can count towards spend.
+    #[must_use]
+    pub fn reports_money(&self) -> bool {
+        self.unit.is_none_or(ProviderUnit::is_money)
+    }
+
+    /// Trimmed group name, or `None` when the provider is ungrouped.
+    #[must_use]
+    pub fn group_name(&self) -> Option<&str> {
+        self.group
+            .as_deref()
+            .map(str::trim)
+            .filter(|group| !group.is_empty())
+    }
 }
 
 #[cfg(test)]
@@ -75,6 +1441,12 @@ mod tests {
         assert_eq!(config.menu_bar.fixed_budget, 15.0);
         assert_eq!(config.menu_bar.near_budget_threshold_percent, 10.0);
         assert!(config.language.is_none());
+        assert!(!config.data_sources.cursor.enabled);
+        assert_eq!(config.min_model_daily_cost, 0.0);
+        assert!(!config.integrations.notion.enabled);
+        assert_eq!(config.integrations.notion.title_property, "Date");
+        assert!(!config.integrations.email_digest.enabled);
+        assert_eq!(config.integrations.email_digest.hour, 9);
     }
 
     #[test]
@@ -119,6 +1491,129 @@ mod tests {
         assert_eq!(config.language, Some("zh".to_string()));
     }
 
+    #[test]
+    fn test_app_config_deserialize_with_data_sources() {
+        let json = r#"{
+            "refreshInterval": 600,
+            "launchAtLogin": false,
+            "menuBar": {
+                "format": "${cost}",
+                "thresholdMode": "fixed",
+                "fixedBudget": 15.0,
+                "showColorCoding": true
+            },
+            "dataSources": {
+                "cursor": { "enabled": true },
+                "windsurf": { "enabled": true, "serviceKey": "key" },
+                "openai": { "enabled": true, "adminKey": "sk-admin-test" },
+                "openrouter": { "enabled": true, "apiKey": "sk-or-v1-test" },
+                "litellm": { "enabled": true, "baseUrl": "http://localhost:4000" },
+                "customJsonl": [{
+                    "id": "aider",
+                    "enabled": true,
+                    "pathGlob": "~/.aider/**/*.jsonl",
+                    "fields": {
+                        "timestamp": "ts",
+                        "model": "model",
+                        "inputTokens": "usage.input",
+                        "outputTokens": "usage.output"
+                    }
+                }]
+            }
+        }"#;
+
+        let config: AppConfig =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        assert!(config.data_sources.cursor.enabled);
+        assert!(config.data_sources.cursor.session_token.is_none());
+        assert!(config.data_sources.windsurf.enabled);
+        assert_eq!(config.data_sources.windsurf.credit_price, 0.04);
+        assert!(config.data_sources.openai.enabled);
+        assert!(config.data_sources.openai.project_id.is_none());
+        // Read so it can be moved to the keychain, never written back
+        assert_eq!(
+            config.data_sources.openai.admin_key.as_deref(),
+            Some("sk-admin-test")
+        );
+        let saved = serde_json::to_string(&config).expect("config should serialize");
+        assert!(!saved.contains("sk-admin-test"));
+        assert!(!saved.contains("sk-or-v1-test"));
+        assert!(config.data_sources.openrouter.enabled);
+        assert_eq!(
+            config.data_sources.litellm.base_url,
+            "http://localhost:4000"
+        );
+        assert!(config.data_sources.litellm.api_key.is_none());
+        assert_eq!(
+            config.data_sources.openrouter.api_key.as_deref(),
+            Some("sk-or-v1-test")
+        );
+        assert_eq!(config.data_sources.custom_jsonl.len(), 1);
+        assert!(config.data_sources.custom_jsonl[0].fields.cost.is_none());
+        assert_eq!(config.data_sources.proxy, ProxySourceConfig::default());
+        assert_eq!(config.data_sources.proxy.port, 8787);
+        assert_eq!(config.trends.baseline, TrendBaseline::Mean30);
+        assert!(!config.trends.exclude_outliers);
+        assert_eq!(config.trends.outlier_percent, 10.0);
+    }
+
+    #[test]
+    fn test_model_display_name() {
+        let mut config = AppConfig::default();
+        config
+            .model_aliases
+            .insert("claude-opus-4-5".to_string(), "Opus".to_string());
+        config
+            .model_aliases
+            .insert("gpt-4o".to_string(), "  ".to_string());
+
+        assert_eq!(config.model_display_name("claude-opus-4-5"), "Opus");
+        assert_eq!(config.model_display_name("gpt-4o"), "gpt-4o");
+        assert_eq!(
+            config.model_display_name("claude-sonnet-4"),
+            "claude-sonnet-4"
+        );
+    }
+
+    #[test]
+    fn test_trend_baseline_serde() {
+        let config: TrendsConfig = serde_json::from_str(r#"{"baseline":"sameWeekday"}"#)
+            .expect("test JSON should parse correctly");
+        assert_eq!(config.baseline, TrendBaseline::SameWeekday);
+        assert_eq!(
+            serde_json::to_string(&TrendBaseline::Median7).expect("should serialize"),
+            r#""median7""#
+        );
+    }
+
+    #[test]
+    fn test_feature_gate() {
+        let config: AppConfig = serde_json::from_str(
+            r#"{
+                "refreshInterval": 300,
+                "launchAtLogin": false,
+                "menuBar": { "format": "${cost}", "thresholdMode": "fixed", "fixedBudget": 15.0, "showColorCoding": true },
+                "features": { "scheduler": false, "someFutureFeature": true }
+            }"#,
+        )
+        .expect("test JSON should parse correctly");
+        assert!(!config.features.is_enabled(Feature::Scheduler));
+        assert!(config.features.is_enabled(Feature::NativeParser));
+
+        let json = serde_json::to_value(&config.features).expect("should serialize");
+        assert_eq!(json["someFutureFeature"], true);
+
+        let mut gate = FeatureGate::default();
+        gate.set(Feature::NativeParser, false);
+        assert!(!gate.is_enabled(Feature::NativeParser));
+        for feature in Feature::ALL {
+            assert_eq!(
+                serde_json::to_value(feature).expect("should serialize"),
+                feature.key()
+            );
+        }
+    }
+
     #[test]
     fn test_menu_bar_config_default() {
         let config = MenuBarConfig::default();
@@ -126,6 +1621,56 @@ mod tests {
         assert_eq!(config.threshold_mode, "fixed");
         assert_eq!(config.near_budget_threshold_percent, 10.0);
         assert!(config.show_color_coding);
+        assert!(config.weekend_budget.is_none());
+        assert!(!config.show_recent_days);
+        assert!(config.verbose_accessibility);
+        assert_eq!(config.color_scheme, ColorScheme::Standard);
+    }
+
+    #[test]
+    fn test_menu_bar_hard_budget_for() {
+        let monday = chrono::NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        let saturday = chrono::NaiveDate::from_ymd_opt(2025, 1, 4).unwrap();
+        let mut config = MenuBarConfig::default();
+        assert_eq!(config.hard_budget_for(monday), None);
+
+        config.hard_budget_percent = Some(150.0);
+        config.weekend_budget = Some(0.0);
+        assert_eq!(config.hard_budget_for(monday), Some(22.5));
+        assert_eq!(config.hard_budget_for(saturday), None);
+    }
+
+    #[test]
+    fn test_menu_bar_hard_monthly_budget() {
+        let mut config = MenuBarConfig {
+            hard_budget_percent: Some(150.0),
+            ..MenuBarConfig::default()
+        };
+        assert_eq!(config.hard_monthly_budget(), None);
+
+        config.monthly_budget = Some(0.0);
+        assert_eq!(config.hard_monthly_budget(), None);
+        config.monthly_budget = Some(300.0);
+        assert_eq!(config.hard_monthly_budget(), Some(450.0));
+
+        config.hard_budget_percent = None;
+        assert_eq!(config.hard_monthly_budget(), None);
+    }
+
+    #[test]
+    fn test_menu_bar_budget_for_weekend() {
+        let monday = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).expect("valid date");
+        let saturday = chrono::NaiveDate::from_ymd_opt(2024, 1, 20).expect("valid date");
+
+        let mut config = MenuBarConfig::default();
+        assert_eq!(config.budget_for(saturday), 15.0);
+
+        config.weekend_budget = Some(5.0);
+        assert_eq!(config.budget_for(monday), 15.0);
+        assert_eq!(config.budget_for(saturday), 5.0);
+
+        config.weekend_budget = Some(0.0);
+        assert_eq!(config.budget_for(saturday), 0.0);
     }
 
     #[test]
@@ -146,5 +1691,19 @@ mod tests {
         assert!(provider.enabled);
         assert_eq!(provider.env.get("API_KEY"), Some(&"xxx".to_string()));
         assert!(provider.last_fetched.is_none());
+        assert!(provider.currency.is_none());
+        assert_eq!(provider.retry, ProviderRetry::default());
+        assert!(provider.show_in_tray);
+    }
+
+    #[test]
+    fn test_provider_retry_backoff() {
+        let retry = ProviderRetry {
+            attempts: 3,
+            backoff_ms: 500,
+        };
+        assert_eq!(retry.backoff(0), Duration::from_millis(500));
+        assert_eq!(retry.backoff(2), Duration::from_secs(2));
+        assert!(retry.backoff(u32::MAX) > Duration::from_hours(1));
     }
 }
@@ -19,6 +19,9 @@ pub enum AppError {
 
     #[error("Validation error: {0}")]
     Validation(String),
+
+    #[error("TokenMeter is locked; authenticate to continue")]
+    Locked,
 }
 
 impl<T> From<std::sync::PoisonError<T>> for AppError {
@@ -0,0 +1,392 @@
+use crate::config::{GuardBudgetTier, GuardConfig, MenuBarConfig};
+use crate::services::local_http::{self, HttpRequest};
+use crate::state::StateHandle;
+use crate::tray::usage_date;
+use anyhow::Result;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+const CHECK_PATH: &str = "/guard/check";
+const LOG_FILE: &str = "guard.jsonl";
+/// The request log is rotated to `guard.jsonl.1` past this size.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const TOKEN_BYTES: usize = 24;
+/// A check body is a small JSON object; anything larger is rejected unread.
+const MAX_CHECK_BODY_BYTES: usize = 4 * 1024;
+
+/// Body of `POST /guard/check`. An empty body checks with no estimated cost.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckRequest {
+    /// Expected cost (USD) of the task about to start.
+    #[serde(default)]
+    estimated_cost: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Decision {
+    allow: bool,
+    reason: String,
+    today_cost: f64,
+    budget: Option<f64>,
+    remaining: Option<f64>,
+    estimated_cost: f64,
+}
+
+/// Allows the task when at least `min_headroom` of today's budget would remain
+/// after spending `estimated_cost`. Unbudgeted days always allow.
+fn evaluate(today_cost: f64, budget: f64, min_headroom: f64, estimated_cost: f64) -> Decision {
+    if budget <= 0.0 {
+        return Decision {
+            allow: true,
+            reason: "No daily budget is set".to_string(),
+            today_cost,
+            budget: None,
+            remaining: None,
+            estimated_cost,
+        };
+    }
+
+    let remaining = budget - today_cost;
+    let headroom = remaining - estimated_cost;
+    let allow = headroom >= min_headroom;
+    let reason = if allow {
+        format!("${headroom:.2} of today's ${budget:.2} budget would remain")
+    } else {
+        format!(
+            "${remaining:.2} of today's ${budget:.2} budget remains; \
+             ${min_headroom:.2} must be left after an estimated ${estimated_cost:.2}"
+        )
+    };
+    Decision {
+        allow,
+        reason,
+        today_cost,
+        budget: Some(budget),
+        remaining: Some(remaining),
+        estimated_cost,
+    }
+}
+
+/// Budget on `date` for the guard's tier. The hard tier falls back to the soft
+/// budget when no hard budget is configured.
+fn tier_budget(menu_bar: &MenuBarConfig, tier: GuardBudgetTier, date: chrono::NaiveDate) -> f64 {
+    match tier {
+        GuardBudgetTier::Soft => menu_bar.budget_for(date),
+        GuardBudgetTier::Hard => menu_bar
+            .hard_budget_for(date)
+            .unwrap_or_else(|| menu_bar.budget_for(date)),
+    }
+}
+
+/// Compares tokens without returning early on the first differing byte.
+pub fn token_matches(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+/// Validates routing and auth, which only need the headers, returning the
+/// error response to send when the request isn't allowed.
+fn authorize(config: &GuardConfig, request: &HttpRequest) -> Result<(), (u16, Value)> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    if path != CHECK_PATH {
+        return Err((404, error_body("Not found")));
+    }
+    if request.method != "POST" {
+        return Err((405, error_body("Use POST")));
+    }
+
+    let provided = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or_default();
+    if config.token.is_empty() || !token_matches(&config.token, provided) {
+        return Err((401, error_body("Missing or invalid bearer token")));
+    }
+    Ok(())
+}
+
+/// Validates routing, auth and the body, returning the error response to send
+/// when the request can't be checked.
+fn parse_check(config: &GuardConfig, request: &HttpRequest) -> Result<CheckRequest, (u16, Value)> {
+    authorize(config, request)?;
+    if request.body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(CheckRequest::default());
+    }
+    let check: CheckRequest = serde_json::from_slice(&request.body)
+        .map_err(|e| (400, error_body(&format!("Invalid JSON body: {e}"))))?;
+    if !check.estimated_cost.is_finite() || check.estimated_cost < 0.0 {
+        return Err((
+            400,
+            error_body("estimatedCost must be a non-negative number"),
+        ));
+    }
+    Ok(check)
+}
+
+struct GuardContext<H> {
+    app: H,
+    config: GuardConfig,
+    log_path: PathBuf,
+    log_lock: Mutex<()>,
+}
+
+impl<H: StateHandle> GuardContext<H> {
+    async fn respond(&self, request: &HttpRequest) -> (u16, Value) {
+        let check = match parse_check(&self.config, request) {
+            Ok(check) => check,
+            Err(response) => return response,
+        };
+
+        let state = self.app.app_state();
+        let today = state
+            .usage_snapshot()
+            .await
+            .map(|usage| (usage.today.cost, usage_date(&usage)));
+        let Some((today_cost, date)) = today else {
+            return (503, error_body("Usage data has not loaded yet"));
+        };
+        let budget = tier_budget(
+            &state.config.lock().await.menu_bar,
+            self.config.budget_tier,
+            date,
+        );
+
+        let decision = evaluate(
+            today_cost,
+            budget,
+            self.config.min_headroom,
+            check.estimated_cost,
+        );
+        (200, serde_json::to_value(decision).unwrap_or(Value::Null))
+    }
+
+    /// Appends one line per request to the guard log, rotating it when large.
+    async fn log(&self, peer: SocketAddr, request: &HttpRequest, status: u16, body: &Value) {
+        let entry = json!({
+            "timestamp": chrono::Local::now().to_rfc3339(),
+            "peer": peer.to_string(),
+            "userAgent": request.header("user-agent"),
+            "method": request.method,
+            "path": request.path,
+            "status": status,
+            "allow": body.get("allow"),
+            "estimatedCost": body.get("estimatedCost"),
+        });
+
+        let _guard = self.log_lock.lock().await;
+        let result = (|| -> std::io::Result<()> {
+            if std::fs::metadata(&self.log_path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+                std::fs::rename(&self.log_path, self.log_path.with_extension("jsonl.1"))?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.log_path)?;
+            writeln!(file, "{entry}")
+        })();
+        if let Err(e) = result {
+            tracing::error!("[Guard] Failed to write request log: {e}");
+        }
+    }
+}
+
+async fn handle_connection<H: StateHandle>(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    ctx: Arc<GuardContext<H>>,
+) -> Result<()> {
+    let mut request = match local_http::read_head(&mut stream).await {
+        Ok(request) => request,
+        Err(e) => {
+            return local_http::write_json(&mut stream, 400, &error_body(&e.to_string())).await;
+        }
+    };
+    // Unauthenticated clients are turned away before any more of the body is read
+    let (status, body) = match authorize(&ctx.config, &request) {
+        Err(response) => response,
+        Ok(()) => {
+            match local_http::read_body(&mut stream, &mut request, MAX_CHECK_BODY_BYTES).await {
+                Ok(()) => ctx.respond(&request).await,
+                Err(e) => (400, error_body(&e.to_string())),
+            }
+        }
+    };
+    ctx.log(peer, &request, status, &body).await;
+    local_http::write_json(&mut stream, status, &body).await
+}
+
+/// Serves `POST /guard/check` on `127.0.0.1:<port>` until the task is aborted.
+///
+/// # Errors
+/// Returns an error if the port can't be bound or accepting connections fails.
+pub async fn serve<H: StateHandle>(app: H, config: GuardConfig, config_dir: PathBuf) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", config.port)).await?;
+    let ctx = Arc::new(GuardContext {
+        app,
+        config,
+        log_path: config_dir.join(LOG_FILE),
+        log_lock: Mutex::new(()),
+    });
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let ctx = Arc::clone(&ctx);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, peer, ctx).await {
+                tracing::error!("[Guard] Request failed: {e}");
+            }
+        });
+    }
+}
+
+/// Generates a random hex bearer token for the guard endpoint.
+///
+/// # Errors
+/// Returns an error if the system random generator fails.
+pub fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| anyhow::anyhow!("Failed to generate random bytes"))?;
+    Ok(bytes.iter().fold(String::new(), |mut token, byte| {
+        let _ = write!(token, "{byte:02x}");
+        token
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> GuardConfig {
+        GuardConfig {
+            enabled: true,
+            token: "secret-token".to_string(),
+            ..GuardConfig::default()
+        }
+    }
+
+    fn request(method: &str, path: &str, auth: Option<&str>, body: &str) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: auth
+                .map(|token| vec![("Authorization".to_string(), format!("Bearer {token}"))])
+                .unwrap_or_default(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let decision = evaluate(10.0, 15.0, 1.0, 2.0);
+        assert!(decision.allow);
+        assert_eq!(decision.remaining, Some(5.0));
+
+        let decision = evaluate(10.0, 15.0, 1.0, 4.5);
+        assert!(!decision.allow);
+        assert!(decision.reason.contains("$5.00 of today's $15.00"));
+
+        // Already over budget
+        assert!(!evaluate(16.0, 15.0, 0.0, 0.0).allow);
+
+        let decision = evaluate(100.0, 0.0, 5.0, 50.0);
+        assert!(decision.allow);
+        assert_eq!(decision.budget, None);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_tier_budget() {
+        let monday = chrono::NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        let mut menu_bar = MenuBarConfig::default();
+        assert_eq!(tier_budget(&menu_bar, GuardBudgetTier::Hard, monday), 15.0);
+
+        menu_bar.hard_budget_percent = Some(200.0);
+        assert_eq!(tier_budget(&menu_bar, GuardBudgetTier::Soft, monday), 15.0);
+        assert_eq!(tier_budget(&menu_bar, GuardBudgetTier::Hard, monday), 30.0);
+    }
+
+    #[test]
+    fn test_parse_check() {
+        let config = config();
+        let status = |req: &HttpRequest| parse_check(&config, req).err().map(|(s, _)| s);
+
+        assert_eq!(
+            status(&request("POST", "/other", Some("secret-token"), "")),
+            Some(404)
+        );
+        assert_eq!(
+            status(&request("GET", CHECK_PATH, Some("secret-token"), "")),
+            Some(405)
+        );
+        assert_eq!(status(&request("POST", CHECK_PATH, None, "")), Some(401));
+        assert_eq!(
+            status(&request("POST", CHECK_PATH, Some("secret-tokem"), "")),
+            Some(401)
+        );
+        assert_eq!(
+            status(&request("POST", CHECK_PATH, Some("secret-token"), "{")),
+            Some(400)
+        );
+        assert_eq!(
+            status(&request(
+                "POST",
+                CHECK_PATH,
+                Some("secret-token"),
+                r#"{"estimatedCost":-1}"#
+            )),
+            Some(400)
+        );
+
+        let check = parse_check(
+            &config,
+            &request(
+                "POST",
+                CHECK_PATH,
+                Some("secret-token"),
+                r#"{"estimatedCost":2.5}"#,
+            ),
+        )
+        .expect("valid request");
+        assert!((check.estimated_cost - 2.5).abs() < f64::EPSILON);
+        assert!(parse_check(
+            &config,
+            &request("POST", CHECK_PATH, Some("secret-token"), "")
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_empty_token_rejects_everything() {
+        let config = GuardConfig::default();
+        assert!(parse_check(&config, &request("POST", CHECK_PATH, Some(""), "")).is_err());
+    }
+
+    #[test]
+    fn test_generate_token() {
+        let token = generate_token().expect("token");
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token().expect("token"));
+    }
+}
@@ -3,19 +3,51 @@
 #![warn(clippy::nursery)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod agent;
+mod alerts;
+mod app_lock;
+mod clock;
 mod commands;
 pub mod config;
 mod error;
+mod guard;
+mod logs;
+mod perf;
+mod privacy;
+pub mod profiles;
+mod scheduler;
 pub mod services;
 pub mod state;
-mod storage;
-mod tray;
+pub mod storage;
+pub mod tray;
 pub mod types;
 
-use commands::providers::{delete_provider, get_providers, save_provider, test_provider};
-use commands::usage::{get_config, get_usage_summary, refresh_usage, save_config};
+use commands::diagnostics::{create_support_bundle, get_diagnostics, get_perf_stats};
+use commands::guard::generate_guard_token;
+use commands::insights::{
+    get_claude_sessions, get_dual_series, get_key_breakdown, get_member_leaderboard,
+    get_org_comparison, get_session_blocks, get_time_profile, get_topics_breakdown,
+    get_usage_journal,
+};
+use commands::integrations::{
+    clear_credential, clear_snapshot_passphrase, get_stored_credentials, has_snapshot_passphrase,
+    push_snapshot_now, send_test_digest, send_test_push, set_credential, set_smtp_password,
+    set_snapshot_passphrase,
+};
+use commands::models::get_model_info;
+use commands::profiles::{get_profiles, switch_profile};
+use commands::providers::{
+    delete_provider, get_provider_daily, get_provider_history, get_provider_status, get_providers,
+    get_trashed_providers, purge_trash, reorder_providers, restore_provider, save_provider,
+    set_providers_enabled, test_provider, validate_provider,
+};
+use commands::usage::{
+    force_full_refresh, get_boot_payload, get_config, get_config_schema, get_usage_summary,
+    import_usage_dump, list_features, refresh_usage, save_config, validate_tray_format,
+};
 use state::AppState;
 #[cfg(not(target_os = "macos"))]
+use std::sync::Arc;
 use std::time::Duration;
 use tauri::{Emitter, Manager};
 
@@ -42,6 +74,7 @@ fn set_dock_visible(app: &tauri::AppHandle, visible: bool) {
 /// a navigation event to switch to the dashboard tab.
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
+#[tracing::instrument(name = "command.open_dashboard", skip_all)]
 fn open_dashboard(app: tauri::AppHandle) {
     show_window_with_dock(&app);
     navigate_to(&app, "dashboard");
@@ -53,12 +86,14 @@ fn open_dashboard(app: tauri::AppHandle) {
 /// a navigation event to switch to the settings tab.
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
+#[tracing::instrument(name = "command.open_settings", skip_all)]
 fn open_settings(app: tauri::AppHandle) {
     show_window_with_dock(&app);
     navigate_to(&app, "settings");
 }
 
 #[tauri::command]
+#[tracing::instrument(name = "command.set_launch_at_login", skip_all)]
 async fn set_launch_at_login(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
     use tauri_plugin_autostart::ManagerExt;
     let autostart = app.autolaunch();
@@ -76,18 +111,31 @@ fn spawn_preload_task(app_handle: tauri::AppHandle) {
         let state = app_handle.state::<AppState>();
         // Acquire usage_refresh_lock before fetching to avoid race conditions with initial UI requests
         let _refresh_guard = state.usage_refresh_lock.lock().await;
+        state.move_credentials_to_keychain().await;
 
-        match commands::usage::fetch_and_update_history(&state).await {
+        let (timeout, ttl) = {
+            let config = state.config.lock().await;
+            (config.timeouts.provider(), config.provider_cache_ttl())
+        };
+        let result = if let Some(result) = commands::usage::agent_handoff(&state).await {
+            result
+        } else {
+            let (result, ()) = tokio::join!(
+                commands::usage::fetch_and_update_history(&state, false),
+                commands::usage::refresh_tray_providers(&state, timeout, ttl),
+            );
+            result
+        };
+        match result {
             Ok(data) => {
-                *state.usage.lock().await = Some(data.clone());
-                *state.usage_fetched_at.lock().await = Some(std::time::Instant::now());
+                state.set_usage(Arc::clone(&data)).await;
                 let config = state.config.lock().await.clone();
-                tray::update_tray_menu(&app_handle, &data, &config, &[]);
+                tray::update_tray_menu(&app_handle, data, config, &[]);
                 // Emit event to notify frontend that data is ready
                 let _ = app_handle.emit("usage-preloaded", ());
             }
             Err(e) => {
-                eprintln!("Background preload failed: {e}");
+                tracing::error!("Background preload failed: {e}");
                 tray::update_tray_error(&app_handle);
             }
         }
@@ -96,14 +144,23 @@ fn spawn_preload_task(app_handle: tauri::AppHandle) {
 
 #[allow(clippy::needless_pass_by_value)]
 #[tauri::command]
+#[tracing::instrument(name = "command.quit_app", skip_all)]
 fn quit_app(app: tauri::AppHandle) {
     app.exit(0);
 }
 
 /// # Panics
 /// Panics if the Tauri application fails to start.
+#[allow(clippy::too_many_lines)]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Provider transforms run in a copy of the app that can be killed
+    if services::script_runner::is_transform_worker() {
+        services::script_runner::run_transform_worker();
+        return;
+    }
+    perf::init();
+
     let builder = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_autostart::init(
@@ -126,6 +183,7 @@ pub fn run() {
             let state = AppState::new().expect(
                 "Failed to initialize app state. Please check if ~/.tokenmeter directory is writable.",
             );
+            tray::set_profiles(profiles::list_profiles(&state.config_dir));
             app.manage(state);
             tray::setup_tray(app.handle())?;
 
@@ -143,6 +201,18 @@ pub fn run() {
 
             // Start background preload of usage data
             spawn_preload_task(app.handle().clone());
+            scheduler::spawn(app.handle().clone());
+            tray::spawn_block_reset_timer(app.handle().clone());
+            clock::spawn(app.handle().clone());
+
+            // Start the local API proxy and guard endpoint if enabled
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle.state::<AppState>();
+                let config = state.config.lock().await.clone();
+                state.restart_proxy(&config.data_sources.proxy).await;
+                state.restart_guard(&app_handle, &config.guard).await;
+            });
 
             Ok(())
         })
@@ -153,6 +223,14 @@ pub fn run() {
                     let _ = window.hide();
                     api.prevent_close();
 
+                    if window.label() == MAIN_WINDOW_LABEL {
+                        let app = window.app_handle().clone();
+                        tauri::async_runtime::spawn(async move {
+                            let lock = app.state::<AppState>().config.lock().await.app_lock;
+                            app_lock::dashboard_closed(lock);
+                        });
+                    }
+
                     #[cfg(target_os = "macos")]
                     {
                         // Only hide dock if it's the main window being closed
@@ -192,12 +270,53 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             get_usage_summary,
             refresh_usage,
+            force_full_refresh,
+            import_usage_dump,
             get_config,
+            get_boot_payload,
+            get_config_schema,
+            list_features,
+            validate_tray_format,
             save_config,
+            get_model_info,
+            get_time_profile,
+            get_key_breakdown,
+            get_session_blocks,
+            get_claude_sessions,
+            get_topics_breakdown,
+            get_usage_journal,
+            get_dual_series,
+            get_member_leaderboard,
+            get_org_comparison,
+            get_diagnostics,
+            get_perf_stats,
+            create_support_bundle,
+            set_smtp_password,
+            send_test_digest,
+            set_snapshot_passphrase,
+            clear_snapshot_passphrase,
+            has_snapshot_passphrase,
+            push_snapshot_now,
+            send_test_push,
+            set_credential,
+            clear_credential,
+            get_stored_credentials,
+            generate_guard_token,
             get_providers,
+            get_provider_history,
+            get_provider_daily,
+            get_provider_status,
             save_provider,
             delete_provider,
+            get_trashed_providers,
+            restore_provider,
+            purge_trash,
+            set_providers_enabled,
+            reorder_providers,
+            get_profiles,
+            switch_profile,
             test_provider,
+            validate_provider,
             open_dashboard,
             open_settings,
             set_launch_at_login,
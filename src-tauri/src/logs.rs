@@ -0,0 +1,57 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tracing::Level;
+
+/// Lines kept; older ones are dropped as new ones arrive.
+const CAPACITY: usize = 500;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Prints a warning or error to stderr and keeps it, timestamped, among the
+/// recent log lines bundled for issue reports.
+pub fn record(level: Level, message: &str) {
+    if level == Level::WARN {
+        eprintln!("Warning: {message}");
+    } else {
+        eprintln!("{message}");
+    }
+    let line = format!(
+        "{} {level} {message}",
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+    );
+    if let Ok(mut lines) = RECENT.lock() {
+        push_line(&mut lines, line);
+    }
+}
+
+fn push_line(lines: &mut VecDeque<String>, line: String) {
+    if lines.len() == CAPACITY {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// Warnings and errors logged since launch, oldest first, up to the last
+/// [`CAPACITY`] lines.
+#[must_use]
+pub fn recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .map_or_else(|_| Vec::new(), |lines| lines.iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_line_drops_oldest() {
+        let mut lines = VecDeque::new();
+        for i in 0..=CAPACITY {
+            push_line(&mut lines, i.to_string());
+        }
+        assert_eq!(lines.len(), CAPACITY);
+        assert_eq!(lines.front().map(String::as_str), Some("1"));
+        assert_eq!(lines.back(), Some(&CAPACITY.to_string()));
+    }
+}
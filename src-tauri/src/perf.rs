@@ -0,0 +1,268 @@
+use crate::logs;
+use crate::types::{PerfBucket, PerfStat};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Only spans and events from this crate are handled; dependencies' are ignored.
+const TARGET_PREFIX: &str = "tokenmeter_lib";
+
+/// Upper bounds (inclusive, in milliseconds) of the histogram buckets. Slower
+/// spans land in a final overflow bucket.
+const BUCKET_BOUNDS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 10_000, 60_000];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    count: u64,
+    total_ms: f64,
+    max_ms: f64,
+    buckets: [u64; BUCKET_BOUNDS_MS.len() + 1],
+}
+
+impl Histogram {
+    #[allow(clippy::cast_precision_loss)]
+    fn record(&mut self, ms: f64) {
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|&bound| ms <= bound as f64)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    /// Upper bound of the bucket holding the `quantile` of samples; the
+    /// largest sample when that is the overflow bucket.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn quantile_ms(&self, quantile: f64) -> f64 {
+        let rank = ((self.count as f64) * quantile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return BUCKET_BOUNDS_MS
+                    .get(i)
+                    .map_or(self.max_ms, |&bound| (bound as f64).min(self.max_ms));
+            }
+        }
+        self.max_ms
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn to_stat(&self, name: &str) -> PerfStat {
+        PerfStat {
+            name: name.to_string(),
+            count: self.count,
+            total_ms: self.total_ms,
+            mean_ms: self.total_ms / self.count.max(1) as f64,
+            max_ms: self.max_ms,
+            p50_ms: self.quantile_ms(0.5),
+            p95_ms: self.quantile_ms(0.95),
+            buckets: self
+                .buckets
+                .iter()
+                .enumerate()
+                .map(|(i, &count)| PerfBucket {
+                    le_ms: BUCKET_BOUNDS_MS.get(i).copied(),
+                    count,
+                })
+                .collect(),
+        }
+    }
+}
+
+static HISTOGRAMS: Mutex<BTreeMap<&'static str, Histogram>> = Mutex::new(BTreeMap::new());
+
+fn record_duration(name: &'static str, ms: f64) {
+    if let Ok(mut histograms) = HISTOGRAMS.lock() {
+        histograms.entry(name).or_default().record(ms);
+    }
+}
+
+/// Duration statistics of every span closed since launch, by span name.
+#[must_use]
+pub fn stats() -> Vec<PerfStat> {
+    HISTOGRAMS.lock().map_or_else(
+        |_| Vec::new(),
+        |histograms| {
+            histograms
+                .iter()
+                .map(|(name, histogram)| histogram.to_stat(name))
+                .collect()
+        },
+    )
+}
+
+/// A span that has not closed yet.
+struct OpenSpan {
+    name: &'static str,
+    started: Instant,
+    /// Live handles; the span closes when the last one is dropped.
+    refs: usize,
+}
+
+/// Formatted `message` field of an event.
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+/// Tracing subscriber that times this crate's spans from creation to close
+/// (wall time, including time spent awaiting) into in-memory histograms.
+///
+/// Its warning and error events are printed and kept in the recent log.
+#[derive(Default)]
+pub struct PerfSubscriber {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, OpenSpan>>,
+}
+
+impl Subscriber for PerfSubscriber {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if self.enabled(metadata) {
+            Interest::always()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        (metadata.is_span() || *metadata.level() <= Level::WARN)
+            && metadata.target().starts_with(TARGET_PREFIX)
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.insert(
+                id,
+                OpenSpan {
+                    name: span.metadata().name(),
+                    started: Instant::now(),
+                    refs: 1,
+                },
+            );
+        }
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        logs::record(*event.metadata().level(), &message.0);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Ok(mut spans) = self.spans.lock() {
+            if let Some(span) = spans.get_mut(&id.into_u64()) {
+                span.refs += 1;
+            }
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let Ok(mut spans) = self.spans.lock() else {
+            return false;
+        };
+        let key = id.into_u64();
+        let Some(span) = spans.get_mut(&key) else {
+            return false;
+        };
+        span.refs -= 1;
+        if span.refs > 0 {
+            return false;
+        }
+        let span = spans.remove(&key).expect("span is present");
+        drop(spans);
+        record_duration(span.name, span.started.elapsed().as_secs_f64() * 1000.0);
+        true
+    }
+}
+
+/// Installs [`PerfSubscriber`] as the global subscriber. Does nothing if one
+/// is already set.
+pub fn init() {
+    let _ = tracing::subscriber::set_global_default(PerfSubscriber::default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_quantiles() {
+        let mut histogram = Histogram::default();
+        for ms in [0.5, 3.0, 4.0, 20.0, 90_000.0] {
+            histogram.record(ms);
+        }
+        let stat = histogram.to_stat("test");
+        assert_eq!(stat.count, 5);
+        assert!((stat.max_ms - 90_000.0).abs() < f64::EPSILON);
+        assert!((stat.p50_ms - 5.0).abs() < f64::EPSILON);
+        assert!((stat.p95_ms - 90_000.0).abs() < f64::EPSILON);
+        assert_eq!(stat.buckets[1].le_ms, Some(5));
+        assert_eq!(stat.buckets[1].count, 2);
+        assert_eq!(
+            stat.buckets.last().map(|b| (b.le_ms, b.count)),
+            Some((None, 1))
+        );
+    }
+
+    #[test]
+    fn test_subscriber_times_closed_spans() {
+        tracing::subscriber::with_default(PerfSubscriber::default(), || {
+            let span = tracing::info_span!("perf_test_span");
+            let clone = span.clone();
+            drop(span.entered());
+            assert!(!stats().iter().any(|s| s.name == "perf_test_span"));
+            drop(clone);
+            tracing::info_span!("perf_test_span").in_scope(|| {});
+        });
+
+        let stat = stats()
+            .into_iter()
+            .find(|s| s.name == "perf_test_span")
+            .expect("span recorded");
+        assert_eq!(stat.count, 2);
+    }
+
+    #[test]
+    fn test_subscriber_keeps_warnings() {
+        tracing::subscriber::with_default(PerfSubscriber::default(), || {
+            tracing::warn!("perf test warning {}", 42);
+            tracing::info!("perf test info");
+        });
+
+        let recent = logs::recent();
+        assert!(recent
+            .iter()
+            .any(|line| line.ends_with(" WARN perf test warning 42")));
+        assert!(!recent.iter().any(|line| line.contains("perf test info")));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::types::{DailyUsage, ModelUsage, ProviderUsageResult, UsageData, UsageSummary};
+
+/// Shown in place of each dollar amount while privacy mode masks them.
+pub const MASKED_AMOUNT: &str = "$•••";
+
+/// Replaces every dollar amount in `text`, e.g. "$12.50", with [`MASKED_AMOUNT`].
+#[must_use]
+pub fn mask_amounts(text: &str) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' || !chars.peek().is_some_and(char::is_ascii_digit) {
+            masked.push(c);
+            continue;
+        }
+        masked.push_str(MASKED_AMOUNT);
+        while chars
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+        {
+            chars.next();
+        }
+    }
+    masked
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn scale_tokens(tokens: u64, factor: f64) -> u64 {
+    (tokens as f64 * factor).round() as u64
+}
+
+fn scale_data(data: &mut UsageData, factor: f64) {
+    data.cost *= factor;
+    data.input_tokens = scale_tokens(data.input_tokens, factor);
+    data.output_tokens = scale_tokens(data.output_tokens, factor);
+    data.cache_creation_input_tokens = scale_tokens(data.cache_creation_input_tokens, factor);
+    data.cache_read_input_tokens = scale_tokens(data.cache_read_input_tokens, factor);
+    data.total_tokens = scale_tokens(data.total_tokens, factor);
+}
+
+fn scale_models(models: &mut [ModelUsage], factor: f64) {
+    for model in models {
+        model.cost *= factor;
+        model.input_tokens = scale_tokens(model.input_tokens, factor);
+        model.output_tokens = scale_tokens(model.output_tokens, factor);
+    }
+}
+
+fn scale_day(day: &mut DailyUsage, factor: f64) {
+    day.cost *= factor;
+    day.input_tokens = scale_tokens(day.input_tokens, factor);
+    day.output_tokens = scale_tokens(day.output_tokens, factor);
+    day.cache_creation_input_tokens = scale_tokens(day.cache_creation_input_tokens, factor);
+    day.cache_read_input_tokens = scale_tokens(day.cache_read_input_tokens, factor);
+    scale_models(&mut day.models, factor);
+}
+
+fn scale_credits(credits: &mut ProviderUsageResult, factor: f64) {
+    for amount in [&mut credits.cost, &mut credits.used, &mut credits.total]
+        .into_iter()
+        .flatten()
+    {
+        *amount *= factor;
+    }
+    credits.tokens = credits.tokens.map(|tokens| scale_tokens(tokens, factor));
+}
+
+/// Copy of `usage` with every cost and token count multiplied by `factor`.
+/// Ratios such as the trend and budget percentages are unchanged.
+#[must_use]
+pub fn scale_summary(usage: &UsageSummary, factor: f64) -> UsageSummary {
+    let mut scaled = usage.clone();
+    scale_data(&mut scaled.today, factor);
+    scale_data(&mut scaled.this_month, factor);
+    for day in &mut scaled.daily_usage {
+        scale_day(day, factor);
+    }
+    scale_models(&mut scaled.model_breakdown, factor);
+    for source in &mut scaled.sources {
+        scale_data(&mut source.today, factor);
+        scale_data(&mut source.this_month, factor);
+        scale_models(&mut source.models, factor);
+        for key in &mut source.keys {
+            scale_data(&mut key.today, factor);
+            scale_data(&mut key.this_month, factor);
+        }
+        if let Some(credits) = &mut source.credits {
+            scale_credits(credits, factor);
+        }
+    }
+    scaled
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::services::sources;
+
+    #[test]
+    fn test_mask_amounts() {
+        assert_eq!(mask_amounts("$12.50 1.2M"), "$••• 1.2M");
+        assert_eq!(
+            mask_amounts("2025-03-10  $1,234.00 and $0.5"),
+            "2025-03-10  $••• and $•••"
+        );
+        assert_eq!(mask_amounts("$-- +12%"), "$-- +12%");
+        assert_eq!(mask_amounts("🔋 api: $1.50"), "🔋 api: $•••");
+    }
+
+    #[test]
+    fn test_scale_summary() {
+        let day = DailyUsage {
+            date: "2025-03-10".to_string(),
+            cost: 10.0,
+            input_tokens: 1_000,
+            output_tokens: 500,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            models: vec![ModelUsage {
+                model: "claude-opus-4-5".to_string(),
+                cost: 10.0,
+                input_tokens: 1_000,
+                output_tokens: 500,
+            }],
+        };
+        let usage = sources::summary_from_daily(vec![day], "2025-03-10");
+
+        let scaled = scale_summary(&usage, 0.5);
+        assert_eq!(scaled.today.cost, 5.0);
+        assert_eq!(scaled.today.input_tokens, 500);
+        assert_eq!(scaled.this_month.output_tokens, 250);
+        assert_eq!(scaled.daily_usage[0].models[0].cost, 5.0);
+        assert_eq!(scaled.model_breakdown[0].input_tokens, 500);
+        assert_eq!(usage.today.cost, 10.0);
+    }
+}
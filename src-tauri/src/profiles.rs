@@ -0,0 +1,174 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Saved copies of each profile's `config.json` and `providers/`. The active
+/// profile's settings stay in their usual place, so nothing else needs to know
+/// about profiles. Usage history is shared by every profile.
+const PROFILES_DIR: &str = "profiles";
+/// Name of the active profile, in `profiles/active`.
+const ACTIVE_FILE: &str = "active";
+const CONFIG_FILE: &str = "config.json";
+const PROVIDERS_DIR: &str = "providers";
+
+/// Profile in use before any profile was created.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Saved profiles and which one is active.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    /// Every profile name, sorted, including the active one.
+    pub names: Vec<String>,
+    pub active: String,
+}
+
+/// Profile names become directory names, so they are limited to a safe charset.
+///
+/// # Errors
+/// Returns an error unless `name` is 1-64 letters, digits, `-` or `_`, or if
+/// it collides with the active-profile marker.
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = name != ACTIVE_FILE
+        && !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!("Profile name must be 1-64 letters, digits, '-' or '_': '{name}'");
+    }
+    Ok(())
+}
+
+/// Name of the active profile, [`DEFAULT_PROFILE`] until one is switched to.
+#[must_use]
+pub fn active_profile(config_dir: &Path) -> String {
+    fs::read_to_string(config_dir.join(PROFILES_DIR).join(ACTIVE_FILE))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| validate_name(name).is_ok())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Lists saved profiles; the active one is included even before it is saved.
+#[must_use]
+pub fn list_profiles(config_dir: &Path) -> ProfileList {
+    let active = active_profile(config_dir);
+    let mut names: Vec<String> = fs::read_dir(config_dir.join(PROFILES_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| validate_name(name).is_ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !names.contains(&active) {
+        names.push(active.clone());
+    }
+    names.sort();
+    ProfileList { names, active }
+}
+
+/// Copies the config and provider files of `from` into `to`, replacing the
+/// ones `to` had. The provider trash is left where it is.
+fn copy_settings(from: &Path, to: &Path) -> Result<()> {
+    let to_providers = to.join(PROVIDERS_DIR);
+    fs::create_dir_all(&to_providers)?;
+    for entry in fs::read_dir(&to_providers)?.flatten() {
+        if entry.path().extension().is_some_and(|e| e == "json") {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    if let Ok(entries) = fs::read_dir(from.join(PROVIDERS_DIR)) {
+        for entry in entries.flatten() {
+            if entry.path().extension().is_some_and(|e| e == "json") {
+                fs::copy(entry.path(), to_providers.join(entry.file_name()))?;
+            }
+        }
+    }
+
+    let from_config = from.join(CONFIG_FILE);
+    if from_config.exists() {
+        fs::copy(from_config, to.join(CONFIG_FILE))?;
+    } else if to.join(CONFIG_FILE).exists() {
+        fs::remove_file(to.join(CONFIG_FILE))?;
+    }
+    Ok(())
+}
+
+/// Saves the current settings as the active profile and puts `name` in their
+/// place. Returns `false` if `name` is already active.
+///
+/// A profile that does not exist yet starts as a copy of the current settings.
+///
+/// # Errors
+/// Returns an error if `name` is invalid or the settings cannot be copied.
+pub fn switch_profile(config_dir: &Path, name: &str) -> Result<bool> {
+    validate_name(name)?;
+    let active = active_profile(config_dir);
+    if active == name {
+        return Ok(false);
+    }
+
+    let profiles_dir = config_dir.join(PROFILES_DIR);
+    copy_settings(config_dir, &profiles_dir.join(&active))?;
+    let target = profiles_dir.join(name);
+    if target.is_dir() {
+        copy_settings(&target, config_dir)?;
+    } else {
+        copy_settings(config_dir, &target)?;
+    }
+    fs::write(profiles_dir.join(ACTIVE_FILE), name)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("work").is_ok());
+        assert!(validate_name("side_project-2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../work").is_err());
+        assert!(validate_name(ACTIVE_FILE).is_err());
+    }
+
+    #[test]
+    fn test_switch_profile_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join(PROVIDERS_DIR)).unwrap();
+        fs::write(dir.join(CONFIG_FILE), "personal").unwrap();
+        fs::write(dir.join(PROVIDERS_DIR).join("home.json"), "{}").unwrap();
+        assert_eq!(list_profiles(dir).names, [DEFAULT_PROFILE]);
+
+        // A new profile starts from the current settings
+        assert!(switch_profile(dir, "work").unwrap());
+        assert!(!switch_profile(dir, "work").unwrap());
+        fs::write(dir.join(CONFIG_FILE), "work").unwrap();
+        fs::remove_file(dir.join(PROVIDERS_DIR).join("home.json")).unwrap();
+        fs::write(dir.join(PROVIDERS_DIR).join("office.json"), "{}").unwrap();
+
+        assert!(switch_profile(dir, DEFAULT_PROFILE).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join(CONFIG_FILE)).unwrap(),
+            "personal"
+        );
+        assert!(dir.join(PROVIDERS_DIR).join("home.json").exists());
+        assert!(!dir.join(PROVIDERS_DIR).join("office.json").exists());
+
+        let list = list_profiles(dir);
+        assert_eq!(list.names, [DEFAULT_PROFILE, "work"]);
+        assert_eq!(list.active, DEFAULT_PROFILE);
+
+        switch_profile(dir, "work").unwrap();
+        assert_eq!(fs::read_to_string(dir.join(CONFIG_FILE)).unwrap(), "work");
+        assert!(dir.join(PROVIDERS_DIR).join("office.json").exists());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::config::Feature;
+use crate::services::digest;
+use crate::state::{AppState, StateHandle};
+use crate::storage;
+use std::time::{Duration, Instant};
+
+/// How often due jobs are checked.
+const TICK_INTERVAL: Duration = Duration::from_mins(1);
+/// Delay before retrying a job that failed.
+const RETRY_DELAY: Duration = Duration::from_hours(1);
+
+/// Starts the background scheduler that runs time-based jobs (currently the
+/// weekly email digest). Jobs are skipped while the scheduler feature is off.
+pub fn spawn<H: StateHandle>(app: H) {
+    tauri::async_runtime::spawn(async move {
+        let mut digest_retry_at: Option<Instant> = None;
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let enabled = app
+                .app_state()
+                .config
+                .lock()
+                .await
+                .features
+                .is_enabled(Feature::Scheduler);
+            if !enabled || digest_retry_at.is_some_and(|at| Instant::now() < at) {
+                continue;
+            }
+            if let Some(succeeded) = run_email_digest(app.app_state()).await {
+                digest_retry_at = (!succeeded).then(|| Instant::now() + RETRY_DELAY);
+            }
+        }
+    });
+}
+
+/// Sends the weekly digest when due. Returns `None` when nothing was attempted.
+async fn run_email_digest(state: &AppState) -> Option<bool> {
+    let config = state.config.lock().await.integrations.email_digest.clone();
+    let mut scheduler_state = storage::load_scheduler_state(&state.config_dir);
+
+    let now = chrono::Local::now();
+    if !digest::is_due(&config, now, scheduler_state.email_digest_last_sent) {
+        return None;
+    }
+    // Wait for the first refresh rather than sending an empty digest
+    let usage = state.usage_snapshot().await?;
+    // A running tokenmeterd sends the digest itself
+    if usage.meta.managed_by_agent {
+        return None;
+    }
+    let daily = usage.daily_usage.clone();
+
+    let result = digest::send_weekly_digest(&config, &daily).await;
+    let succeeded = result.is_ok();
+    if let Err(e) = &result {
+        tracing::warn!("Weekly email digest failed: {e}");
+    } else {
+        scheduler_state.email_digest_last_sent = Some(now.date_naive());
+        if let Err(e) = storage::save_scheduler_state(&state.config_dir, &scheduler_state) {
+            tracing::warn!("Failed to save scheduler state: {e}");
+        }
+    }
+    state
+        .diagnostics
+        .lock()
+        .await
+        .record_integration("emailDigest", result);
+    Some(succeeded)
+}
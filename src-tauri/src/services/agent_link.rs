@@ -0,0 +1,216 @@
+use crate::types::{ProviderTrayStats, UsageSummary};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Written by a running `tokenmeterd` and removed when it exits.
+const AGENT_FILE: &str = "agent.json";
+/// Locked by a running `tokenmeterd` for as long as it runs.
+const LOCK_FILE: &str = "agent.lock";
+/// Path the agent serves its latest data on.
+pub const HANDOFF_PATH: &str = "/handoff";
+/// A live agent answers on localhost well within this.
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How the GUI reaches a running headless agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentInfo {
+    pub pid: u32,
+    pub port: u16,
+    /// Bearer token the handoff endpoint requires.
+    pub token: String,
+}
+
+/// Latest data of the agent, replacing a refresh of the GUI's own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentHandoff {
+    pub summary: UsageSummary,
+    pub providers: Vec<ProviderTrayStats>,
+}
+
+fn info_path(config_dir: &Path) -> PathBuf {
+    config_dir.join(AGENT_FILE)
+}
+
+/// Proof that this process is the agent for a config directory. The lock is
+/// released when this is dropped or the process exits, however it exits, so
+/// an agent that crashed never blocks the next one.
+#[derive(Debug)]
+pub struct AgentLock {
+    _file: fs::File,
+}
+
+/// Takes the agent lock under `config_dir`, or returns `None` when another
+/// agent holds it.
+///
+/// # Errors
+/// Returns an error if the lock file cannot be opened or locked.
+pub fn acquire_lock(config_dir: &Path) -> Result<Option<AgentLock>> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(config_dir.join(LOCK_FILE))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(AgentLock { _file: file })),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Whether an agent holds the lock under `config_dir`. Unlike a recorded pid,
+/// this cannot be fooled by another process reusing a dead agent's pid.
+fn agent_running(config_dir: &Path) -> bool {
+    let Ok(file) = fs::File::open(config_dir.join(LOCK_FILE)) else {
+        return false;
+    };
+    matches!(file.try_lock_shared(), Err(fs::TryLockError::WouldBlock))
+}
+
+/// Records `info` for the GUI, readable only by the current user since it
+/// holds the handoff token.
+///
+/// It is created with that mode under a temporary name and renamed into place,
+/// so the token is never readable even briefly.
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+pub fn write_info(config_dir: &Path, info: &AgentInfo) -> Result<()> {
+    let path = info_path(config_dir);
+    let json = serde_json::to_string_pretty(info)?;
+    let temp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    let _ = fs::remove_file(&temp);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options
+        .open(&temp)
+        .and_then(|mut file| file.write_all(json.as_bytes()))
+        .and_then(|()| fs::rename(&temp, &path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Removes the agent file, e.g. when the agent shuts down.
+pub fn remove_info(config_dir: &Path) {
+    let _ = fs::remove_file(info_path(config_dir));
+}
+
+fn read_info(config_dir: &Path) -> Option<AgentInfo> {
+    let content = fs::read_to_string(info_path(config_dir)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn handoff_request(info: &AgentInfo) -> Option<reqwest::RequestBuilder> {
+    // A system proxy must not see the token or be asked for localhost
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(HANDOFF_TIMEOUT)
+        .build()
+        .ok()?;
+    Some(
+        client
+            .get(format!("http://127.0.0.1:{}{HANDOFF_PATH}", info.port))
+            .bearer_auth(&info.token),
+    )
+}
+
+/// Data of the agent running under `config_dir`, or `None` when no agent is
+/// running and the app should refresh itself.
+///
+/// An agent that answers without data, e.g. before its first refresh, still
+/// manages the data, so its message is returned instead of letting the app
+/// refresh next to it.
+pub async fn fetch_handoff(config_dir: &Path) -> Option<Result<AgentHandoff, String>> {
+    if !agent_running(config_dir) {
+        return None;
+    }
+    let Some(info) = read_info(config_dir) else {
+        return Some(Err("tokenmeterd is starting".to_string()));
+    };
+    let Some(request) = handoff_request(&info) else {
+        return Some(Err("Failed to build the handoff request".to_string()));
+    };
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return Some(Err(format!("tokenmeterd is not answering: {e}"))),
+    };
+    if !response.status().is_success() {
+        let status = response.status();
+        let message = response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| format!("HTTP {status}"));
+        return Some(Err(format!("tokenmeterd has no usage data: {message}")));
+    }
+    Some(
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Unreadable tokenmeterd data: {e}")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_info_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let info = AgentInfo {
+            pid: 42,
+            port: 48_123,
+            token: "secret".to_string(),
+        };
+
+        write_info(dir, &info).unwrap();
+        assert_eq!(read_info(dir), Some(info.clone()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(info_path(dir)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            // Replacing a file others could read leaves it private too
+            fs::set_permissions(info_path(dir), fs::Permissions::from_mode(0o644)).unwrap();
+            write_info(dir, &info).unwrap();
+            let mode = fs::metadata(info_path(dir)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        remove_info(dir);
+        assert_eq!(read_info(dir), None);
+    }
+
+    #[test]
+    fn test_agent_lock() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        assert!(!agent_running(dir));
+
+        let lock = acquire_lock(dir).unwrap().expect("lock is free");
+        assert!(agent_running(dir));
+        assert!(acquire_lock(dir).unwrap().is_none());
+
+        // The lock file stays behind, but no longer counts as a running agent
+        drop(lock);
+        assert!(!agent_running(dir));
+        assert!(acquire_lock(dir).unwrap().is_some());
+    }
+}
@@ -1,12 +1,65 @@
-use crate::services::pricing;
-use crate::types::{DailyUsage, ModelUsage, UsageData, UsageSummary};
+use crate::config::{CcusageRunner, ClaudeCodeSourceConfig, Feature, FeatureGate, TimeoutsConfig};
+use crate::services::claude_logs::{self, LogEntry};
+use crate::services::json_schema::{self, Field, Shape};
+use crate::services::pricing::{self, ModelPrice};
+use crate::services::sources;
+use crate::storage;
+use crate::types::{
+    CcusageCapabilities, ClaudeSession, DailyUsage, ModelUsage, RefreshMeta, SessionBlock,
+    SummaryCache, UsageData, UsageSummary,
+};
 use anyhow::Result;
+use chrono::{DateTime, DurationRound, Utc};
 use serde::Deserialize;
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::process::Command;
 use tokio::time::timeout;
 
+/// Shape of a daily entry, mirroring [`CcusageDailyEntry`].
+static DAILY_ENTRY_SHAPE: Shape = Shape::Object(&[
+    Field::required("date", Shape::String),
+    Field::required("inputTokens", Shape::Unsigned),
+    Field::required("outputTokens", Shape::Unsigned),
+    Field::optional("cacheCreationTokens", Shape::Unsigned),
+    Field::optional("cacheReadTokens", Shape::Unsigned),
+    Field::required("totalTokens", Shape::Unsigned),
+    Field::required("totalCost", Shape::Number),
+    Field::required(
+        "modelBreakdowns",
+        Shape::Array(&Shape::Object(&[
+            Field::required("modelName", Shape::String),
+            Field::required("inputTokens", Shape::Unsigned),
+            Field::required("outputTokens", Shape::Unsigned),
+            Field::optional("cacheCreationTokens", Shape::Unsigned),
+            Field::optional("cacheReadTokens", Shape::Unsigned),
+            Field::required("cost", Shape::Number),
+        ])),
+    ),
+]);
+
+static DAILY_EXPORT_SHAPE: Shape =
+    Shape::Object(&[Field::required("daily", Shape::Array(&DAILY_ENTRY_SHAPE))]);
+
+static DAILY_RESPONSE_SHAPE: Shape = Shape::Object(&[
+    Field::required("daily", Shape::Array(&DAILY_ENTRY_SHAPE)),
+    Field::required(
+        "totals",
+        Shape::Object(&[
+            Field::required("inputTokens", Shape::Unsigned),
+            Field::required("outputTokens", Shape::Unsigned),
+            Field::optional("cacheCreationTokens", Shape::Unsigned),
+            Field::optional("cacheReadTokens", Shape::Unsigned),
+            Field::required("totalCost", Shape::Number),
+            Field::required("totalTokens", Shape::Unsigned),
+        ]),
+    ),
+]);
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct CcusageResponse {
@@ -14,6 +67,13 @@ struct CcusageResponse {
     totals: CcusageTotals,
 }
 
+/// A ccusage daily report as exported by `ccusage daily --json`; totals are
+/// recomputed from the days, so they are not required.
+#[derive(Debug, Deserialize)]
+struct CcusageExport {
+    daily: Vec<CcusageDailyEntry>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct CcusageDailyEntry {
@@ -51,8 +111,6 @@ struct CcusageTotals {
     total_tokens: u64,
 }
 
-const COMMAND_TIMEOUT_SECS: u64 = 60;
-
 /// Allowed shells for security - only well-known system shells.
 const ALLOWED_SHELLS: &[&str] = &[
     "/bin/bash",
@@ -72,8 +130,11 @@ const DEFAULT_SHELL: &str = "/bin/zsh";
 // ~/.zshrc (interactive) instead of ~/.zprofile (login). We keep the command
 // non-interactive, but add a small, safe bootstrap that covers common install paths
 // (Homebrew) and popular Node version managers.
+//
+// When ccusage isn't installed globally, the configured package runner (npx/bunx)
+// is tried before failing with the shell's "not found" exit code.
 #[allow(clippy::literal_string_with_formatting_args)]
-fn build_ccusage_shell_script() -> String {
+fn build_ccusage_shell_script(args: &str, runner: CcusageRunner) -> String {
     let prelude = r#"
 export PATH="/opt/homebrew/bin:/usr/local/bin:$PATH"
 
@@ -97,10 +158,25 @@ if [ -s "$HOME/.volta/load.sh" ]; then
 fi
 "#;
 
-    format!(
-        "{prelude}\nccusage --json --days 30 --offline",
+    let fallbacks: &[(&str, &str)] = match runner {
+        CcusageRunner::Auto => &[("npx", "npx -y ccusage"), ("bunx", "bunx ccusage")],
+        CcusageRunner::Npx => &[("npx", "npx -y ccusage")],
+        CcusageRunner::Bunx => &[("bunx", "bunx ccusage")],
+        CcusageRunner::Disabled => &[],
+    };
+
+    let mut script = format!(
+        "{prelude}\n\nif command -v ccusage >/dev/null 2>&1; then\n  exec ccusage {args}\n",
         prelude = prelude.trim()
-    )
+    );
+    for (binary, command) in fallbacks {
+        let _ = write!(
+            script,
+            "elif command -v {binary} >/dev/null 2>&1; then\n  exec {command} {args}\n"
+        );
+    }
+    script.push_str("else\n  echo \"ccusage: command not found\" >&2\n  exit 127\nfi");
+    script
 }
 
 /// Gets the user's default shell with security validation.
@@ -112,16 +188,285 @@ fn get_user_shell() -> &'static str {
         .unwrap_or(DEFAULT_SHELL)
 }
 
-/// Fetches usage data from ccusage CLI tool.
+/// Data directories to parse natively, or `None` when ccusage should be run:
+/// a ccusage executable is configured, the native parser feature is off, or
+/// no transcripts exist.
+fn native_data_dirs(
+    config: &ClaudeCodeSourceConfig,
+    features: &FeatureGate,
+) -> Option<Vec<String>> {
+    let data_dirs = config.data_dirs();
+    (config.ccusage_path().is_none()
+        && features.is_enabled(Feature::NativeParser)
+        && !claude_logs::project_dirs(&data_dirs).is_empty())
+    .then_some(data_dirs)
+}
+
+/// Fetches Claude Code usage for the last 30 days.
+///
+/// Transcripts under `~/.claude/projects` are parsed directly; the ccusage CLI
+/// is only run when no transcripts are found, an explicit `ccusage_path` is
+/// configured or the native parser feature is off, so ccusage is optional. With a
+/// `cache_dir`, the parsed summary is cached there and reused until a
+/// transcript changes or the day rolls over.
 ///
 /// # Errors
-/// Returns an error if:
+/// Returns an error if no transcripts are found and:
 /// - ccusage command is not found
 /// - ccusage command times out
 /// - ccusage command fails
 /// - Output cannot be parsed as JSON
-#[allow(clippy::too_many_lines)]
-pub async fn fetch_usage() -> Result<UsageSummary> {
+#[tracing::instrument(name = "service.ccusage", skip_all)]
+pub async fn fetch_usage(
+    config: &ClaudeCodeSourceConfig,
+    timeouts: &TimeoutsConfig,
+    features: &FeatureGate,
+    cache_dir: Option<&Path>,
+) -> Result<UsageSummary> {
+    if let Some(data_dirs) = native_data_dirs(config, features) {
+        let now = chrono::Local::now();
+        let today = now.date_naive();
+        let window_start = today - chrono::Duration::days(sources::SUMMARY_WINDOW_DAYS - 1);
+        let since = window_start
+            .and_time(chrono::NaiveTime::MIN)
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .unwrap_or_else(|| {
+                chrono::Local::now() - chrono::Duration::days(sources::SUMMARY_WINDOW_DAYS)
+            });
+        let today_str = today.format("%Y-%m-%d").to_string();
+
+        let dirs = data_dirs.clone();
+        let fingerprint = tokio::task::spawn_blocking(move || claude_logs::fingerprint(since, &dirs))
+                .await
+                // Entries fall into days by the UTC offset, so a timezone
+                // change must miss the cache too
+                .map(|digest| format!("{today_str}{}:{digest}", now.offset()))?;
+        if let Some(cached) = cache_dir
+            .and_then(storage::load_summary_cache)
+            .filter(|cache| cache.fingerprint == fingerprint)
+        {
+            return Ok(read_via(cached.summary, NATIVE_READER.to_string()));
+        }
+
+        let entries =
+            tokio::task::spawn_blocking(move || claude_logs::read_entries(since, &data_dirs))
+                .await?;
+        if !entries.is_empty() {
+            let needs_prices = entries.iter().any(|e| e.cost.is_none());
+            let prices = if needs_prices {
+                pricing::get_prices(timeouts.pricing()).await
+            } else {
+                None
+            };
+            let summary = claude_logs::summarize(&entries, prices.as_ref(), &today_str);
+
+            // Costs estimated without prices are incomplete; recompute next time
+            if let Some(dir) = cache_dir.filter(|_| !needs_prices || prices.is_some()) {
+                let cache = SummaryCache {
+                    fingerprint,
+                    summary: summary.clone(),
+                };
+                if let Err(e) = storage::save_summary_cache(dir, &cache) {
+                    tracing::warn!("Failed to save summary cache: {e}");
+                }
+            }
+            return Ok(read_via(summary, NATIVE_READER.to_string()));
+        }
+    }
+
+    let capabilities = capabilities(config, timeouts).await;
+    let summary = fetch_cli_usage(config, timeouts, &capabilities).await?;
+    let label = capabilities
+        .version
+        .map_or_else(|| "ccusage".to_string(), |v| format!("ccusage {v}"));
+    Ok(read_via(summary, label))
+}
+
+/// Reader reported for summaries parsed from the transcripts directly.
+const NATIVE_READER: &str = "native";
+
+/// Detected capabilities per executable and runner, probed once per launch.
+static CAPABILITY_CACHE: OnceLock<Mutex<HashMap<String, CcusageCapabilities>>> = OnceLock::new();
+
+fn capability_key(config: &ClaudeCodeSourceConfig) -> String {
+    format!("{:?}|{:?}", config.ccusage_path(), config.runner)
+}
+
+fn read_via(mut summary: UsageSummary, reader: String) -> UsageSummary {
+    summary
+        .meta
+        .source_versions
+        .insert(sources::CLAUDE_CODE_SOURCE_ID.to_string(), reader);
+    summary
+}
+
+/// Capabilities of the configured ccusage, probed with `--version` and the
+/// `daily`, `blocks` and `session` help the first time they are needed.
+async fn capabilities(
+    config: &ClaudeCodeSourceConfig,
+    timeouts: &TimeoutsConfig,
+) -> CcusageCapabilities {
+    let key = capability_key(config);
+    let cache = CAPABILITY_CACHE.get_or_init(Default::default);
+    if let Some(capabilities) = cache.lock().ok().and_then(|c| c.get(&key).cloned()) {
+        return capabilities;
+    }
+
+    let limit = timeouts.ccusage();
+    let (version, daily_help, blocks_help, session_help) = tokio::join!(
+        run_ccusage(config, &["--version"], limit),
+        run_ccusage(config, &["daily", "--help"], limit),
+        run_ccusage(config, &["blocks", "--help"], limit),
+        run_ccusage(config, &["session", "--help"], limit),
+    );
+    let mut capabilities = match (daily_help, blocks_help) {
+        (Ok(daily), Ok(blocks)) => parse_capabilities(&daily, &blocks),
+        (daily, blocks) => {
+            if let Some(e) = daily.err().or_else(|| blocks.err()) {
+                tracing::warn!("Could not read ccusage help, assuming default flags: {e}");
+            }
+            CcusageCapabilities::default()
+        }
+    };
+    capabilities.session = session_help.is_ok();
+    capabilities.version = version
+        .ok()
+        .and_then(|stdout| parse_version(&stdout).map(str::to_string));
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(key, capabilities.clone());
+    }
+    capabilities
+}
+
+/// Capabilities already probed for the configured ccusage, for diagnostics.
+#[must_use]
+pub fn cached_capabilities(config: &ClaudeCodeSourceConfig) -> Option<CcusageCapabilities> {
+    CAPABILITY_CACHE
+        .get()?
+        .lock()
+        .ok()?
+        .get(&capability_key(config))
+        .cloned()
+}
+
+/// Whether `help` documents `flag`, as in "--offline, -O" or "--days <n>".
+fn help_lists(help: &str, flag: &str) -> bool {
+    help.split(|c: char| c.is_whitespace() || matches!(c, ',' | '=' | '[' | ']'))
+        .any(|word| word == flag)
+}
+
+fn parse_capabilities(daily_help: &str, blocks_help: &str) -> CcusageCapabilities {
+    CcusageCapabilities {
+        version: None,
+        help_read: true,
+        offline: help_lists(daily_help, "--offline"),
+        days: help_lists(daily_help, "--days"),
+        since: help_lists(daily_help, "--since"),
+        breakdown: help_lists(daily_help, "--breakdown"),
+        recent: help_lists(blocks_help, "--recent"),
+        session: false,
+    }
+}
+
+/// `ccusage` arguments for the last 30 days of daily usage as JSON, using the
+/// day-range flag the version supports.
+fn daily_args(capabilities: &CcusageCapabilities, today: chrono::NaiveDate) -> Vec<String> {
+    let mut args = vec!["--json".to_string()];
+    if capabilities.days {
+        args.extend(["--days".to_string(), "30".to_string()]);
+    } else if capabilities.since {
+        let since = today - chrono::Duration::days(29);
+        args.extend(["--since".to_string(), since.format("%Y%m%d").to_string()]);
+    }
+    if capabilities.offline {
+        args.push("--offline".to_string());
+    }
+    if capabilities.breakdown {
+        args.push("--breakdown".to_string());
+    }
+    args
+}
+
+fn blocks_args(capabilities: &CcusageCapabilities) -> Vec<&'static str> {
+    let mut args = vec!["blocks", "--json"];
+    if capabilities.recent {
+        args.push("--recent");
+    }
+    if capabilities.offline {
+        args.push("--offline");
+    }
+    args
+}
+
+/// Last output line when it looks like a version number (`15.2.0`, `v15.2.0`).
+fn parse_version(stdout: &str) -> Option<&str> {
+    let line = stdout.lines().map(str::trim).rfind(|l| !l.is_empty())?;
+    let version = line.strip_prefix('v').unwrap_or(line);
+    (version.len() <= 32
+        && version.starts_with(|c: char| c.is_ascii_digit())
+        && !version.contains(char::is_whitespace))
+    .then_some(version)
+}
+
+/// Runs `ccusage <args>` and returns its stdout. A configured executable is run
+/// directly; otherwise ccusage is resolved through the user's login shell.
+/// Configured data directories are passed on through `CLAUDE_CONFIG_DIR`.
+async fn run_ccusage(
+    config: &ClaudeCodeSourceConfig,
+    args: &[&str],
+    command_timeout: Duration,
+) -> Result<String> {
+    let mut cmd = config.ccusage_path().map_or_else(
+        || login_shell_command(args, config.runner),
+        |path| {
+            let mut cmd = Command::new(path);
+            cmd.args(args);
+            cmd
+        },
+    );
+    let data_dirs = config.data_dirs();
+    if !data_dirs.is_empty() {
+        cmd.env("CLAUDE_CONFIG_DIR", data_dirs.join(","));
+    }
+
+    let output = timeout(command_timeout, cmd.output())
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "ccusage command timed out after {}s",
+                command_timeout.as_secs()
+            )
+        })?
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                anyhow::anyhow!(
+                    "ccusage not found. Please install it first: npm install -g ccusage"
+                )
+            } else {
+                anyhow::anyhow!("Failed to execute ccusage: {e}")
+            }
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Treat common command-not-found exit code (127) as missing.
+        if output.status.code() == Some(127)
+            || stderr.contains("command not found")
+            || stderr.contains("not found")
+        {
+            return Err(anyhow::anyhow!(
+                "ccusage not found. Please install it first: npm install -g ccusage"
+            ));
+        }
+        return Err(anyhow::anyhow!("ccusage failed: {stderr}"));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Builds a non-interactive login shell command that runs `ccusage <args>`.
+fn login_shell_command(args: &[&str], runner: CcusageRunner) -> Command {
     // Use shell to execute command to inherit user's PATH (including nvm, etc.)
     let shell = get_user_shell();
 
@@ -142,39 +487,127 @@ pub async fn fetch_usage() -> Result<UsageSummary> {
         cmd.env("HOME", home_path);
     }
 
-    let script = build_ccusage_shell_script();
+    let script = build_ccusage_shell_script(&args.join(" "), runner);
 
     // Use -l to load login shell config; keep it non-interactive to avoid prompts/hangs.
-    let output = timeout(
-        Duration::from_secs(COMMAND_TIMEOUT_SECS),
-        cmd.args(["-l", "-c", script.as_str()]).output(),
-    )
-    .await
-    .map_err(|_| anyhow::anyhow!("ccusage command timed out after {COMMAND_TIMEOUT_SECS}s"))?
-    .map_err(|e| {
-        if e.kind() == std::io::ErrorKind::NotFound {
-            anyhow::anyhow!("ccusage not found. Please install it first: npm install -g ccusage")
-        } else {
-            anyhow::anyhow!("Failed to execute ccusage: {e}")
-        }
-    })?;
+    cmd.args(["-l", "-c", script.as_str()]);
+    cmd
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        // Treat common command-not-found exit code (127) as missing.
-        if output.status.code() == Some(127)
-            || stderr.contains("command not found")
-            || stderr.contains("not found")
-        {
-            return Err(anyhow::anyhow!(
-                "ccusage not found. Please install it first: npm install -g ccusage"
-            ));
+/// Converts ccusage daily entries to per-day usage, pricing each model row
+/// with `calc_cost` when a day reports no total.
+fn to_daily_usage(
+    daily: &[CcusageDailyEntry],
+    calc_cost: impl Fn(&CcusageModelBreakdown) -> f64,
+) -> Vec<DailyUsage> {
+    daily
+        .iter()
+        .map(|d| {
+            let day_cost = if d.total_cost > 0.0 {
+                d.total_cost
+            } else {
+                d.model_breakdowns.iter().map(&calc_cost).sum()
+            };
+            DailyUsage {
+                date: d.date.clone(),
+                cost: day_cost,
+                input_tokens: d.input_tokens,
+                output_tokens: d.output_tokens,
+                cache_creation_input_tokens: d.cache_creation_tokens.unwrap_or(0),
+                cache_read_input_tokens: d.cache_read_tokens.unwrap_or(0),
+                models: d
+                    .model_breakdowns
+                    .iter()
+                    .map(|m| ModelUsage {
+                        model: m.model_name.clone(),
+                        cost: calc_cost(m),
+                        input_tokens: m.input_tokens,
+                        output_tokens: m.output_tokens,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Parses a `ccusage daily --json` export, such as one produced on another
+/// machine, into per-day usage using the costs it reports.
+///
+/// # Errors
+/// Returns an error if `json` is not a ccusage daily report.
+pub fn parse_daily_export(json: &str) -> Result<Vec<DailyUsage>> {
+    let export: CcusageExport = json_schema::parse(json, &DAILY_EXPORT_SHAPE, "ccusage export")?;
+    Ok(to_daily_usage(&export.daily, |m| m.cost))
+}
+
+/// Number of recent ccusage report runs the adaptive timeout is based on.
+const RUN_DURATION_SAMPLES: usize = 10;
+
+/// Runs recorded before the adaptive timeout replaces the configured one.
+const MIN_RUN_DURATION_SAMPLES: usize = 3;
+
+/// The adaptive timeout is this multiple of the median recent run.
+const ADAPTIVE_TIMEOUT_FACTOR: u32 = 3;
+
+const MIN_ADAPTIVE_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_ADAPTIVE_TIMEOUT: Duration = Duration::from_mins(10);
+
+/// How long recent ccusage report runs took, oldest first. Kept in memory only.
+static RUN_DURATIONS: Mutex<VecDeque<Duration>> = Mutex::new(VecDeque::new());
+
+fn record_run_duration(duration: Duration) {
+    if let Ok(mut durations) = RUN_DURATIONS.lock() {
+        if durations.len() == RUN_DURATION_SAMPLES {
+            durations.pop_front();
         }
-        return Err(anyhow::anyhow!("ccusage failed: {stderr}"));
+        durations.push_back(duration);
+    }
+}
+
+/// Timeout for the next ccusage report run: three times the median of the
+/// recent runs, bounded to 10s..10min, or `configured` until enough runs have
+/// been seen. Large histories get more time while small ones still fail fast.
+fn adaptive_timeout(durations: &VecDeque<Duration>, configured: Duration) -> Duration {
+    if durations.len() < MIN_RUN_DURATION_SAMPLES {
+        return configured;
     }
+    let mut sorted: Vec<Duration> = durations.iter().copied().collect();
+    sorted.sort_unstable();
+    (sorted[sorted.len() / 2] * ADAPTIVE_TIMEOUT_FACTOR)
+        .clamp(MIN_ADAPTIVE_TIMEOUT, MAX_ADAPTIVE_TIMEOUT)
+}
+
+/// Runs a ccusage report under the adaptive timeout and records its duration.
+async fn run_ccusage_timed(
+    config: &ClaudeCodeSourceConfig,
+    args: &[&str],
+    timeouts: &TimeoutsConfig,
+) -> Result<String> {
+    let limit = RUN_DURATIONS.lock().map_or_else(
+        |_| timeouts.ccusage(),
+        |durations| adaptive_timeout(&durations, timeouts.ccusage()),
+    );
+    let started = Instant::now();
+    let result = run_ccusage(config, args, limit).await;
+    let elapsed = started.elapsed();
+    // A run that timed out took at least `limit`, which raises the next one
+    if result.is_ok() || elapsed >= limit {
+        record_run_duration(elapsed);
+    }
+    result
+}
 
-    let stdout = String::from_utf8(output.stdout)?;
-    let response: CcusageResponse = serde_json::from_str(&stdout)?;
+/// Fetches usage data from ccusage CLI tool.
+#[allow(clippy::too_many_lines)]
+async fn fetch_cli_usage(
+    config: &ClaudeCodeSourceConfig,
+    timeouts: &TimeoutsConfig,
+    capabilities: &CcusageCapabilities,
+) -> Result<UsageSummary> {
+    let args = daily_args(capabilities, chrono::Local::now().date_naive());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let stdout = run_ccusage_timed(config, &args, timeouts).await?;
+    let response: CcusageResponse = json_schema::parse(&stdout, &DAILY_RESPONSE_SHAPE, "ccusage")?;
 
     // Check if we need fallback prices (any model has cost=0 but has tokens)
     let needs_fallback = response.daily.iter().any(|day| {
@@ -184,7 +617,7 @@ pub async fn fetch_usage() -> Result<UsageSummary> {
     });
 
     let fallback_prices = if needs_fallback {
-        pricing::get_prices().await
+        pricing::get_prices(timeouts.pricing()).await
     } else {
         None
     };
@@ -245,35 +678,7 @@ pub async fn fetch_usage() -> Result<UsageSummary> {
         total_tokens: response.totals.total_tokens,
     };
 
-    let daily_usage: Vec<DailyUsage> = response
-        .daily
-        .iter()
-        .map(|d| {
-            let day_cost = if d.total_cost > 0.0 {
-                d.total_cost
-            } else {
-                d.model_breakdowns.iter().map(calc_cost).sum()
-            };
-            DailyUsage {
-                date: d.date.clone(),
-                cost: day_cost,
-                input_tokens: d.input_tokens,
-                output_tokens: d.output_tokens,
-                cache_creation_input_tokens: d.cache_creation_tokens.unwrap_or(0),
-                cache_read_input_tokens: d.cache_read_tokens.unwrap_or(0),
-                models: d
-                    .model_breakdowns
-                    .iter()
-                    .map(|m| ModelUsage {
-                        model: m.model_name.clone(),
-                        cost: calc_cost(m),
-                        input_tokens: m.input_tokens,
-                        output_tokens: m.output_tokens,
-                    })
-                    .collect(),
-            }
-        })
-        .collect();
+    let daily_usage = to_daily_usage(&response.daily, calc_cost);
 
     // Aggregate model breakdown across all days
     let mut model_map: HashMap<String, ModelUsage> = HashMap::new();
@@ -302,6 +707,391 @@ pub async fn fetch_usage() -> Result<UsageSummary> {
         this_month,
         daily_usage,
         model_breakdown,
+        sources: Vec::new(),
+        trend: None,
+        limits: None,
+        active_block_end: None,
+        meta: RefreshMeta::default(),
+    })
+}
+
+/// Length of a Claude billing window.
+const BLOCK_DURATION: chrono::TimeDelta = chrono::TimeDelta::hours(5);
+
+/// Shape of `ccusage blocks --json`, mirroring [`CcusageBlock`].
+static BLOCKS_RESPONSE_SHAPE: Shape = Shape::Object(&[Field::required(
+    "blocks",
+    Shape::Array(&Shape::Object(&[
+        Field::required("startTime", Shape::String),
+        Field::required("endTime", Shape::String),
+        Field::optional("actualEndTime", Shape::String),
+        Field::defaulted("isActive", Shape::Bool),
+        Field::defaulted("isGap", Shape::Bool),
+        Field::required(
+            "tokenCounts",
+            Shape::Object(&[
+                Field::required("inputTokens", Shape::Unsigned),
+                Field::required("outputTokens", Shape::Unsigned),
+                Field::defaulted("cacheCreationInputTokens", Shape::Unsigned),
+                Field::defaulted("cacheReadInputTokens", Shape::Unsigned),
+            ]),
+        ),
+        Field::required("costUSD", Shape::Number),
+        Field::defaulted("models", Shape::Array(&Shape::String)),
+        Field::optional(
+            "projection",
+            Shape::Object(&[Field::required("totalCost", Shape::Number)]),
+        ),
+    ])),
+)]);
+
+#[derive(Debug, Deserialize)]
+struct CcusageBlocksResponse {
+    blocks: Vec<CcusageBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CcusageBlock {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    actual_end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    is_active: bool,
+    #[serde(default)]
+    is_gap: bool,
+    token_counts: CcusageBlockTokens,
+    #[serde(rename = "costUSD")]
+    cost_usd: f64,
+    #[serde(default)]
+    models: Vec<String>,
+    /// ccusage's own end-of-block estimate for the active block
+    #[serde(default)]
+    projection: Option<CcusageProjection>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CcusageProjection {
+    total_cost: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::struct_field_names)]
+struct CcusageBlockTokens {
+    input_tokens: u64,
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+/// Start of the UTC hour containing `time`, where ccusage anchors blocks.
+fn floor_to_hour(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(chrono::TimeDelta::hours(1))
+        .unwrap_or(time)
+}
+
+/// Cost at the end of the block if spending continues at the rate so far.
+#[allow(clippy::cast_precision_loss)]
+fn project_cost(cost: f64, elapsed_minutes: u64, remaining_minutes: u64) -> Option<f64> {
+    (elapsed_minutes > 0)
+        .then(|| cost * (elapsed_minutes + remaining_minutes) as f64 / elapsed_minutes as f64)
+}
+
+fn minutes(delta: chrono::TimeDelta) -> u64 {
+    u64::try_from(delta.num_minutes()).unwrap_or(0)
+}
+
+/// Fills in the timing fields of a block that started at `start` and last saw
+/// usage at `last_activity`.
+fn block_timing(
+    block: &mut SessionBlock,
+    start: DateTime<Utc>,
+    last_activity: DateTime<Utc>,
+    now: DateTime<Utc>,
+) {
+    let end = start + BLOCK_DURATION;
+    block.start_time = start.to_rfc3339();
+    block.end_time = end.to_rfc3339();
+    block.is_active = now < end && now - last_activity < BLOCK_DURATION;
+    if block.is_active {
+        block.elapsed_minutes = minutes(now - start);
+        block.remaining_minutes = minutes(end - now);
+        block.projected_cost =
+            project_cost(block.cost, block.elapsed_minutes, block.remaining_minutes);
+    } else {
+        block.elapsed_minutes = minutes(last_activity - start);
+    }
+}
+
+/// Groups transcript entries into 5-hour billing blocks like `ccusage blocks`.
+///
+/// A block starts at the hour of its first entry, and a new one begins once
+/// five hours have passed since that start or since the previous entry.
+#[must_use]
+pub fn build_blocks<S: BuildHasher>(
+    entries: &[LogEntry],
+    prices: Option<&HashMap<String, ModelPrice, S>>,
+    now: DateTime<Utc>,
+) -> Vec<SessionBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(SessionBlock, DateTime<Utc>, DateTime<Utc>)> = None;
+
+    for entry in entries {
+        let timestamp = entry.timestamp.with_timezone(&Utc);
+        let starts_new = current.as_ref().is_none_or(|(_, start, last)| {
+            timestamp - *start >= BLOCK_DURATION || timestamp - *last >= BLOCK_DURATION
+        });
+        if starts_new {
+            if let Some((mut block, start, last)) = current.take() {
+                block_timing(&mut block, start, last, now);
+                blocks.push(block);
+            }
+            current = Some((SessionBlock::default(), floor_to_hour(timestamp), timestamp));
+        }
+
+        if let Some((block, _, last)) = current.as_mut() {
+            *last = timestamp;
+            block.input_tokens += entry.input_tokens;
+            block.output_tokens += entry.output_tokens;
+            block.cache_creation_input_tokens += entry.cache_creation_input_tokens;
+            block.cache_read_input_tokens += entry.cache_read_input_tokens;
+            block.total_tokens += entry.total_tokens();
+            block.cost += entry.resolved_cost(prices);
+            if !block.models.contains(&entry.model) {
+                block.models.push(entry.model.clone());
+            }
+        }
+    }
+    if let Some((mut block, start, last)) = current {
+        block_timing(&mut block, start, last, now);
+        blocks.push(block);
+    }
+    blocks
+}
+
+fn block_from_cli(block: CcusageBlock, now: DateTime<Utc>) -> SessionBlock {
+    let tokens = &block.token_counts;
+    let mut session = SessionBlock {
+        input_tokens: tokens.input_tokens,
+        output_tokens: tokens.output_tokens,
+        cache_creation_input_tokens: tokens.cache_creation_input_tokens,
+        cache_read_input_tokens: tokens.cache_read_input_tokens,
+        total_tokens: tokens.input_tokens
+            + tokens.output_tokens
+            + tokens.cache_creation_input_tokens
+            + tokens.cache_read_input_tokens,
+        cost: block.cost_usd,
+        models: block.models,
+        ..SessionBlock::default()
+    };
+    let last_activity = block.actual_end_time.unwrap_or(block.start_time);
+    block_timing(&mut session, block.start_time, last_activity, now);
+    // ccusage knows the block end and activity better than the recomputation
+    session.end_time = block.end_time.to_rfc3339();
+    session.is_active = block.is_active;
+    if block.is_active {
+        if let Some(projection) = block.projection {
+            session.projected_cost = Some(projection.total_cost);
+        }
+    } else {
+        session.remaining_minutes = 0;
+        session.projected_cost = None;
+    }
+    session
+}
+
+/// Returns the 5-hour billing blocks of the last day, oldest first.
+///
+/// Like [`fetch_usage`], transcripts are parsed directly and `ccusage blocks`
+/// is only run when none are found, a ccusage executable is configured or the
+/// native parser feature is off.
+///
+/// # Errors
+/// Returns an error if no transcripts are found and ccusage fails or its output
+/// cannot be parsed.
+#[tracing::instrument(name = "service.ccusage_blocks", skip_all)]
+pub async fn fetch_blocks(
+    config: &ClaudeCodeSourceConfig,
+    timeouts: &TimeoutsConfig,
+    features: &FeatureGate,
+) -> Result<Vec<SessionBlock>> {
+    let now = Utc::now();
+    // Blocks that ended in the last day can have started up to five hours earlier
+    let since = (now - chrono::TimeDelta::days(1) - BLOCK_DURATION).with_timezone(&chrono::Local);
+
+    if let Some(data_dirs) = native_data_dirs(config, features) {
+        let entries =
+            tokio::task::spawn_blocking(move || claude_logs::read_entries(since, &data_dirs))
+                .await?;
+        let prices = if entries.iter().any(|e| e.cost.is_none()) {
+            pricing::get_prices(timeouts.pricing()).await
+        } else {
+            None
+        };
+        let cutoff = now - chrono::TimeDelta::days(1);
+        return Ok(build_blocks(&entries, prices.as_ref(), now)
+            .into_iter()
+            .filter(|block| {
+                block.is_active
+                    || DateTime::parse_from_rfc3339(&block.end_time).is_ok_and(|end| end >= cutoff)
+            })
+            .collect());
+    }
+
+    let capabilities = capabilities(config, timeouts).await;
+    let stdout = run_ccusage_timed(config, &blocks_args(&capabilities), timeouts).await?;
+    let response: CcusageBlocksResponse =
+        json_schema::parse(&stdout, &BLOCKS_RESPONSE_SHAPE, "ccusage blocks")?;
+    // Without `--recent` every block is listed, so keep the last day ourselves
+    let cutoff = now - chrono::TimeDelta::days(1);
+    Ok(response
+        .blocks
+        .into_iter()
+        .filter(|block| !block.is_gap)
+        .filter(|block| capabilities.recent || block.is_active || block.end_time >= cutoff)
+        .map(|block| block_from_cli(block, now))
+        .collect())
+}
+
+/// Shape of `ccusage session --json`, mirroring [`CcusageSession`].
+static SESSIONS_RESPONSE_SHAPE: Shape = Shape::Object(&[Field::required(
+    "sessions",
+    Shape::Array(&Shape::Object(&[
+        Field::required("sessionId", Shape::String),
+        Field::defaulted("projectPath", Shape::String),
+        Field::defaulted("lastActivity", Shape::String),
+        Field::required("inputTokens", Shape::Unsigned),
+        Field::required("outputTokens", Shape::Unsigned),
+        Field::defaulted("cacheCreationTokens", Shape::Unsigned),
+        Field::defaulted("cacheReadTokens", Shape::Unsigned),
+        Field::defaulted("totalTokens", Shape::Unsigned),
+        Field::required("totalCost", Shape::Number),
+        Field::defaulted("modelsUsed", Shape::Array(&Shape::String)),
+    ])),
+)]);
+
+#[derive(Debug, Deserialize)]
+struct CcusageSessionsResponse {
+    sessions: Vec<CcusageSession>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CcusageSession {
+    session_id: String,
+    #[serde(default)]
+    project_path: String,
+    #[serde(default)]
+    last_activity: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_tokens: u64,
+    #[serde(default)]
+    cache_read_tokens: u64,
+    #[serde(default)]
+    total_tokens: u64,
+    total_cost: f64,
+    #[serde(default)]
+    models_used: Vec<String>,
+}
+
+fn session_from_cli(session: CcusageSession) -> ClaudeSession {
+    let total_tokens = if session.total_tokens > 0 {
+        session.total_tokens
+    } else {
+        session.input_tokens
+            + session.output_tokens
+            + session.cache_creation_tokens
+            + session.cache_read_tokens
+    };
+    ClaudeSession {
+        session_id: session.session_id,
+        project_path: session.project_path,
+        last_activity: session.last_activity,
+        input_tokens: session.input_tokens,
+        output_tokens: session.output_tokens,
+        cache_creation_tokens: session.cache_creation_tokens,
+        cache_read_tokens: session.cache_read_tokens,
+        total_tokens,
+        cost: session.total_cost,
+        models: session.models_used,
+    }
+}
+
+/// `ccusage session` arguments for sessions active in the last 30 days.
+fn session_args(capabilities: &CcusageCapabilities, today: chrono::NaiveDate) -> Vec<String> {
+    let mut args = vec!["session".to_string(), "--json".to_string()];
+    if capabilities.since {
+        let since = today - chrono::Duration::days(29);
+        args.extend(["--since".to_string(), since.format("%Y%m%d").to_string()]);
+    }
+    if capabilities.offline {
+        args.push("--offline".to_string());
+    }
+    args
+}
+
+/// Returns Claude Code sessions from `ccusage session`, most recently active
+/// first.
+///
+/// # Errors
+/// Returns an error if the configured ccusage has no `session` command, or it
+/// fails or its output cannot be parsed.
+#[tracing::instrument(name = "service.ccusage_sessions", skip_all)]
+pub async fn fetch_sessions(
+    config: &ClaudeCodeSourceConfig,
+    timeouts: &TimeoutsConfig,
+) -> Result<Vec<ClaudeSession>> {
+    let capabilities = capabilities(config, timeouts).await;
+    if !capabilities.session {
+        return Err(anyhow::anyhow!(
+            "This ccusage version does not support the session command"
+        ));
+    }
+    let args = session_args(&capabilities, chrono::Local::now().date_naive());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let stdout = run_ccusage_timed(config, &args, timeouts).await?;
+    let response: CcusageSessionsResponse =
+        json_schema::parse(&stdout, &SESSIONS_RESPONSE_SHAPE, "ccusage session")?;
+    let mut sessions: Vec<ClaudeSession> = response
+        .sessions
+        .into_iter()
+        .map(session_from_cli)
+        .collect();
+    sessions.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+    Ok(sessions)
+}
+
+/// End of the active block in `blocks`, as RFC 3339.
+#[must_use]
+pub fn active_block_end(blocks: &[SessionBlock]) -> Option<String> {
+    blocks
+        .iter()
+        .rfind(|block| block.is_active)
+        .map(|block| block.end_time.clone())
+}
+
+/// Time left until the RFC 3339 block end `end`, e.g. "1h42m" or "7m".
+///
+/// Partial minutes round up so the countdown reads "1m" rather than "0m" at
+/// the end. Returns `None` once the block has ended or when `end` cannot be
+/// parsed.
+#[must_use]
+pub fn format_block_remaining(end: &str, now: DateTime<Utc>) -> Option<String> {
+    let end = DateTime::parse_from_rfc3339(end).ok()?.with_timezone(&Utc);
+    let seconds = (end - now).num_seconds();
+    if seconds <= 0 {
+        return None;
+    }
+    let minutes = (seconds + 59) / 60;
+    Some(match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h{m:02}m"),
     })
 }
 
@@ -310,6 +1100,114 @@ pub async fn fetch_usage() -> Result<UsageSummary> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_daily_export() {
+        let json = r#"{"daily": [{
+            "date": "2024-01-01", "inputTokens": 10, "outputTokens": 20,
+            "cacheCreationTokens": 3, "cacheReadTokens": 4, "totalTokens": 37,
+            "totalCost": 0.0,
+            "modelBreakdowns": [
+                {"modelName": "claude-opus-4-5", "inputTokens": 10, "outputTokens": 20, "cost": 1.5}
+            ]
+        }]}"#;
+        let days = parse_daily_export(json).unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].date, "2024-01-01");
+        assert!((days[0].cost - 1.5).abs() < f64::EPSILON);
+        assert_eq!(days[0].cache_read_input_tokens, 4);
+        assert_eq!(days[0].models[0].model, "claude-opus-4-5");
+        assert!(parse_daily_export("{}").is_err());
+
+        let wrong = json.replace(r#""totalCost": 0.0"#, r#""totalCost": "0.0""#);
+        assert_eq!(
+            parse_daily_export(&wrong).unwrap_err().to_string(),
+            "Invalid ccusage export output: daily[0].totalCost: expected number, found string"
+        );
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("15.2.0\n"), Some("15.2.0"));
+        assert_eq!(parse_version("npm warn exec\nv16.0.1\n\n"), Some("16.0.1"));
+        assert_eq!(parse_version("{\"daily\": []}"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn test_parse_capabilities() {
+        let daily_help = "USAGE: ccusage daily [OPTIONS]\n\n\
+            OPTIONS:\n  -s, --since <date>   Filter from date\n  \
+            -O, --offline        Use cached pricing\n  -b, --breakdown      Per-model costs\n";
+        let capabilities = parse_capabilities(daily_help, "  --recent  Show recent blocks");
+        assert!(capabilities.help_read);
+        assert!(capabilities.offline && capabilities.since && capabilities.breakdown);
+        assert!(!capabilities.days);
+        assert!(capabilities.recent);
+
+        let old = parse_capabilities("--days=<n>  --offline-mode", "");
+        assert!(old.days);
+        assert!(!old.offline && !old.recent);
+    }
+
+    #[test]
+    fn test_ccusage_args_follow_capabilities() {
+        let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 30).unwrap();
+        let defaults = CcusageCapabilities::default();
+        assert_eq!(
+            daily_args(&defaults, today),
+            ["--json", "--days", "30", "--offline"]
+        );
+        assert_eq!(
+            blocks_args(&defaults),
+            ["blocks", "--json", "--recent", "--offline"]
+        );
+
+        let newer = CcusageCapabilities {
+            days: false,
+            since: true,
+            breakdown: true,
+            recent: false,
+            ..defaults
+        };
+        assert_eq!(
+            daily_args(&newer, today),
+            ["--json", "--since", "20250301", "--offline", "--breakdown"]
+        );
+        assert_eq!(blocks_args(&newer), ["blocks", "--json", "--offline"]);
+    }
+
+    #[test]
+    fn test_build_ccusage_shell_script_fallbacks() {
+        let auto = build_ccusage_shell_script("--json", CcusageRunner::Auto);
+        assert!(auto.contains("exec ccusage --json"));
+        let npx = auto
+            .find("exec npx -y ccusage --json")
+            .expect("npx fallback");
+        let bunx = auto
+            .find("exec bunx ccusage --json")
+            .expect("bunx fallback");
+        assert!(npx < bunx);
+        assert!(auto.ends_with("exit 127\nfi"));
+
+        let bun_only = build_ccusage_shell_script("--json", CcusageRunner::Bunx);
+        assert!(bun_only.contains("bunx ccusage --json"));
+        assert!(!bun_only.contains("npx -y"));
+
+        let disabled = build_ccusage_shell_script("--json", CcusageRunner::Disabled);
+        assert!(!disabled.contains("npx") && !disabled.contains("bunx"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ccusage_shell_script_is_valid_sh() {
+        let script = build_ccusage_shell_script("--json --days 30", CcusageRunner::Auto);
+        let status = std::process::Command::new("/bin/sh")
+            .args(["-n", "-c", &script])
+            .status()
+            .expect("sh available");
+        assert!(status.success());
+    }
+
     #[test]
     fn test_parse_ccusage_response() {
         let json = r#"{
@@ -494,4 +1392,260 @@ mod tests {
         assert!(response.daily[0].cache_read_tokens.is_none());
         assert!(response.totals.cache_creation_tokens.is_none());
     }
+
+    fn log_entry(timestamp: &str, cost: f64) -> LogEntry {
+        LogEntry {
+            timestamp: DateTime::parse_from_rfc3339(timestamp)
+                .expect("valid timestamp")
+                .with_timezone(&chrono::Local),
+            model: "claude-sonnet-4".to_string(),
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            cost: Some(cost),
+        }
+    }
+
+    fn utc(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .expect("valid timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_build_blocks() {
+        let entries = [
+            log_entry("2025-06-01T08:20:00Z", 1.0),
+            log_entry("2025-06-01T12:50:00Z", 2.0),
+            // Past the 5-hour window of the 08:00 block
+            log_entry("2025-06-01T13:10:00Z", 3.0),
+            log_entry("2025-06-01T14:00:00Z", 1.0),
+        ];
+        let now = utc("2025-06-01T15:00:00Z");
+        let blocks = build_blocks::<std::collections::hash_map::RandomState>(&entries, None, now);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].start_time, "2025-06-01T08:00:00+00:00");
+        assert_eq!(blocks[0].cost, 3.0);
+        assert!(!blocks[0].is_active);
+        assert_eq!(blocks[0].elapsed_minutes, 290);
+        assert_eq!(blocks[0].remaining_minutes, 0);
+        assert_eq!(blocks[0].projected_cost, None);
+
+        let active = &blocks[1];
+        assert_eq!(active.start_time, "2025-06-01T13:00:00+00:00");
+        assert!(active.is_active);
+        assert_eq!(active.total_tokens, 300);
+        assert_eq!(active.elapsed_minutes, 120);
+        assert_eq!(active.remaining_minutes, 180);
+        assert_eq!(active.projected_cost, Some(10.0));
+    }
+
+    #[test]
+    fn test_build_blocks_splits_on_idle_gap() {
+        let entries = [
+            log_entry("2025-06-01T01:00:00Z", 1.0),
+            log_entry("2025-06-01T09:30:00Z", 1.0),
+        ];
+        let now = utc("2025-06-02T00:00:00Z");
+        let blocks = build_blocks::<std::collections::hash_map::RandomState>(&entries, None, now);
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks.iter().all(|b| !b.is_active));
+    }
+
+    #[test]
+    fn test_adaptive_timeout() {
+        let configured = Duration::from_mins(1);
+        let secs = |values: &[u64]| -> VecDeque<Duration> {
+            values.iter().map(|&s| Duration::from_secs(s)).collect()
+        };
+
+        assert_eq!(adaptive_timeout(&secs(&[1, 2]), configured), configured);
+        assert_eq!(
+            adaptive_timeout(&secs(&[40, 50, 45]), configured),
+            Duration::from_secs(135)
+        );
+        // An outlier does not move the median
+        assert_eq!(
+            adaptive_timeout(&secs(&[2, 3, 300, 2]), configured),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            adaptive_timeout(&secs(&[400, 500, 450]), configured),
+            MAX_ADAPTIVE_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn test_active_block_end() {
+        let entries = [
+            log_entry("2025-06-01T08:20:00Z", 1.0),
+            log_entry("2025-06-01T13:10:00Z", 1.0),
+        ];
+        let blocks = build_blocks::<std::collections::hash_map::RandomState>(
+            &entries,
+            None,
+            utc("2025-06-01T15:00:00Z"),
+        );
+        assert_eq!(
+            active_block_end(&blocks).as_deref(),
+            Some("2025-06-01T18:00:00+00:00")
+        );
+        assert_eq!(active_block_end(&blocks[..1]), None);
+    }
+
+    #[test]
+    fn test_format_block_remaining() {
+        let end = "2025-06-01T18:00:00+00:00";
+        let remaining = |now: &str| format_block_remaining(end, utc(now));
+        assert_eq!(remaining("2025-06-01T16:18:00Z").as_deref(), Some("1h42m"));
+        assert_eq!(remaining("2025-06-01T15:55:00Z").as_deref(), Some("2h05m"));
+        assert_eq!(remaining("2025-06-01T17:53:00Z").as_deref(), Some("7m"));
+        assert_eq!(remaining("2025-06-01T17:59:30Z").as_deref(), Some("1m"));
+        assert_eq!(remaining("2025-06-01T18:00:00Z"), None);
+        assert_eq!(
+            format_block_remaining("soon", utc("2025-06-01T17:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_ccusage_blocks() {
+        let json = r#"{
+            "blocks": [
+                {
+                    "id": "2025-06-01T08:00:00.000Z",
+                    "startTime": "2025-06-01T08:00:00.000Z",
+                    "endTime": "2025-06-01T13:00:00.000Z",
+                    "actualEndTime": "2025-06-01T10:00:00.000Z",
+                    "isActive": true,
+                    "isGap": false,
+                    "entries": 3,
+                    "tokenCounts": {
+                        "inputTokens": 100,
+                        "outputTokens": 50,
+                        "cacheCreationInputTokens": 10,
+                        "cacheReadInputTokens": 40
+                    },
+                    "totalTokens": 200,
+                    "costUSD": 2.0,
+                    "models": ["claude-sonnet-4"]
+                },
+                {
+                    "id": "gap",
+                    "startTime": "2025-06-01T03:00:00.000Z",
+                    "endTime": "2025-06-01T08:00:00.000Z",
+                    "isGap": true,
+                    "tokenCounts": { "inputTokens": 0, "outputTokens": 0 },
+                    "costUSD": 0
+                }
+            ]
+        }"#;
+
+        let response: CcusageBlocksResponse =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        assert_eq!(response.blocks.len(), 2);
+        assert!(response.blocks[1].is_gap);
+
+        let block = response
+            .blocks
+            .into_iter()
+            .next()
+            .map(|b| block_from_cli(b, utc("2025-06-01T10:00:00Z")))
+            .expect("one block");
+        assert!(block.is_active);
+        assert_eq!(block.total_tokens, 200);
+        assert_eq!(block.elapsed_minutes, 120);
+        assert_eq!(block.remaining_minutes, 180);
+        assert_eq!(block.projected_cost, Some(5.0));
+    }
+
+    #[test]
+    fn test_block_from_cli_uses_projection() {
+        let json = r#"{
+            "startTime": "2025-06-01T08:00:00.000Z",
+            "endTime": "2025-06-01T13:00:00.000Z",
+            "actualEndTime": "2025-06-01T10:00:00.000Z",
+            "isActive": true,
+            "tokenCounts": { "inputTokens": 100, "outputTokens": 50 },
+            "costUSD": 2.0,
+            "projection": { "totalTokens": 400, "totalCost": 4.75, "remainingMinutes": 180 }
+        }"#;
+        let block: CcusageBlock = serde_json::from_str(json).expect("block should parse");
+        let block = block_from_cli(block, utc("2025-06-01T10:00:00Z"));
+        assert_eq!(block.projected_cost, Some(4.75));
+    }
+
+    #[test]
+    fn test_parse_ccusage_sessions() {
+        let json = r#"{
+            "sessions": [
+                {
+                    "sessionId": "-Users-me-old",
+                    "inputTokens": 10,
+                    "outputTokens": 5,
+                    "totalCost": 0.1,
+                    "lastActivity": "2025-05-30"
+                },
+                {
+                    "sessionId": "-Users-me-app",
+                    "projectPath": "me/app",
+                    "inputTokens": 100,
+                    "outputTokens": 50,
+                    "cacheCreationTokens": 10,
+                    "cacheReadTokens": 40,
+                    "totalTokens": 200,
+                    "totalCost": 1.25,
+                    "lastActivity": "2025-06-01",
+                    "modelsUsed": ["claude-sonnet-4"],
+                    "modelBreakdowns": []
+                }
+            ],
+            "totals": { "totalCost": 1.35 }
+        }"#;
+        let response: CcusageSessionsResponse =
+            serde_json::from_str(json).expect("sessions should parse");
+        let sessions: Vec<ClaudeSession> = response
+            .sessions
+            .into_iter()
+            .map(session_from_cli)
+            .collect();
+        assert_eq!(sessions[0].total_tokens, 15);
+        assert_eq!(sessions[1].project_path, "me/app");
+        assert_eq!(sessions[1].total_tokens, 200);
+        assert_eq!(sessions[1].cost, 1.25);
+        assert_eq!(sessions[1].models, ["claude-sonnet-4"]);
+
+        let today = chrono::NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        assert_eq!(
+            session_args(&CcusageCapabilities::default(), today),
+            ["session", "--json", "--offline"]
+        );
+    }
+
+    #[test]
+    fn test_parse_ccusage_rejects_truncated_and_corrupted_output() {
+        let json = r#"{"daily":[{"date":"2024-01-15","inputTokens":1000,"outputTokens":500,"totalTokens":1500,"totalCost":0.05,"modelBreakdowns":[{"modelName":"claude-3-opus","inputTokens":1000,"outputTokens":500,"cost":0.05}]}],"totals":{"inputTokens":1000,"outputTokens":500,"totalTokens":1500,"totalCost":0.05}}"#;
+        assert!(serde_json::from_str::<CcusageResponse>(json).is_ok());
+
+        // Every strict prefix is incomplete JSON and must fail cleanly.
+        for end in 0..json.len() {
+            assert!(serde_json::from_str::<CcusageResponse>(&json[..end]).is_err());
+        }
+
+        // Swapping single bytes for hostile characters must never panic.
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        for _ in 0..2000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let mut bytes = json.as_bytes().to_vec();
+            let at = usize::try_from(state % bytes.len() as u64).unwrap();
+            bytes[at] = b"-0e9\"{}[],:x "[usize::try_from(state >> 32).unwrap() % 13];
+            if let Ok(text) = std::str::from_utf8(&bytes) {
+                let _ = serde_json::from_str::<CcusageResponse>(text);
+            }
+        }
+    }
 }
@@ -0,0 +1,554 @@
+use crate::services::custom_jsonl;
+use crate::services::pricing::{self, ModelPrice};
+use crate::services::sources::{self, UsageRecord};
+use crate::types::{TopicUsage, UsageSummary};
+use chrono::{DateTime, Local};
+use ring::digest;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::hash::BuildHasher;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Lines longer than this are skipped rather than parsed.
+const MAX_LINE_LENGTH: usize = 1_000_000;
+
+/// Longest conversation title kept, in characters.
+const MAX_TITLE_CHARS: usize = 80;
+
+/// A single assistant response recorded in a Claude Code transcript.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Local>,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    /// Cost recorded by Claude Code itself, when present.
+    pub cost: Option<f64>,
+}
+
+impl LogEntry {
+    #[must_use]
+    pub const fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+
+    /// Cost recorded by Claude Code, or estimated from `prices` when missing.
+    #[must_use]
+    pub fn resolved_cost<S: BuildHasher>(
+        &self,
+        prices: Option<&HashMap<String, ModelPrice, S>>,
+    ) -> f64 {
+        self.cost.unwrap_or_else(|| {
+            prices.map_or(0.0, |prices| {
+                pricing::calculate_fallback_cost(
+                    &self.model,
+                    self.input_tokens,
+                    self.output_tokens,
+                    prices,
+                )
+            })
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptLine {
+    timestamp: Option<String>,
+    request_id: Option<String>,
+    message: Option<TranscriptMessage>,
+    #[serde(rename = "costUSD")]
+    cost_usd: Option<f64>,
+}
+
+/// Assistant usage entries from one transcript file, which holds a single
+/// Claude Code conversation.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    /// Claude Code's summary of the conversation, or else its first prompt.
+    pub title: Option<String>,
+    pub entries: Vec<LogEntry>,
+}
+
+/// A summary or user prompt line, read for the conversation title.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TitleLine {
+    #[serde(rename = "type")]
+    kind: String,
+    summary: Option<String>,
+    #[serde(default)]
+    is_meta: bool,
+    message: Option<PromptMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromptMessage {
+    content: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptMessage {
+    id: Option<String>,
+    model: Option<String>,
+    usage: Option<TranscriptUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(clippy::struct_field_names)]
+struct TranscriptUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+/// Claude Code project directories under `data_dirs`. Without configured
+/// directories this honors `CLAUDE_CONFIG_DIR` (comma-separated) and otherwise
+/// checks both `~/.config/claude` and `~/.claude`.
+#[must_use]
+pub fn project_dirs(data_dirs: &[String]) -> Vec<PathBuf> {
+    let roots: Vec<PathBuf> = if data_dirs.is_empty() {
+        match std::env::var("CLAUDE_CONFIG_DIR") {
+            Ok(value) if !value.trim().is_empty() => value
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            _ => dirs::home_dir().map_or_else(Vec::new, |home| {
+                vec![home.join(".config").join("claude"), home.join(".claude")]
+            }),
+        }
+    } else {
+        data_dirs.iter().map(PathBuf::from).collect()
+    };
+
+    roots
+        .into_iter()
+        .map(|root| root.join("projects"))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// Parses one transcript line, returning the entry and its dedup key.
+fn parse_line(line: &str) -> Option<(LogEntry, Option<String>)> {
+    let parsed: TranscriptLine = serde_json::from_str(line).ok()?;
+    let message = parsed.message?;
+    let usage = message.usage?;
+    let timestamp = DateTime::parse_from_rfc3339(parsed.timestamp.as_deref()?)
+        .ok()?
+        .with_timezone(&Local);
+
+    let dedup_key = match (&message.id, &parsed.request_id) {
+        (Some(message_id), Some(request_id)) => Some(format!("{message_id}:{request_id}")),
+        _ => None,
+    };
+
+    let entry = LogEntry {
+        timestamp,
+        model: message
+            .model
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| "unknown".to_string()),
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        cache_creation_input_tokens: usage.cache_creation_input_tokens,
+        cache_read_input_tokens: usage.cache_read_input_tokens,
+        cost: parsed.cost_usd,
+    };
+    (entry.total_tokens() > 0).then_some((entry, dedup_key))
+}
+
+/// First line of `text` with whitespace collapsed, shortened to
+/// [`MAX_TITLE_CHARS`]; `None` when it is blank.
+fn clean_title(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let title = words.join(" ");
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return Some(title);
+    }
+    let short: String = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+    Some(format!("{}…", short.trim_end()))
+}
+
+/// Parses a title candidate from one transcript line: Claude Code's summary
+/// (`true`) or the text of a prompt the user typed (`false`). Tool results,
+/// meta messages and slash command output are skipped.
+fn parse_title(line: &str) -> Option<(String, bool)> {
+    let parsed: TitleLine = serde_json::from_str(line).ok()?;
+    match parsed.kind.as_str() {
+        "summary" => clean_title(parsed.summary.as_deref()?).map(|title| (title, true)),
+        "user" if !parsed.is_meta => {
+            let content = parsed.message?.content;
+            let text = match &content {
+                serde_json::Value::String(text) => text.as_str(),
+                serde_json::Value::Array(blocks) => blocks
+                    .iter()
+                    .find(|block| block["type"] == "text")
+                    .and_then(|block| block["text"].as_str())?,
+                _ => return None,
+            };
+            if text.trim_start().starts_with('<') {
+                return None;
+            }
+            clean_title(text).map(|title| (title, false))
+        }
+        _ => None,
+    }
+}
+
+/// Transcript files that may hold entries at or after `since`; files last
+/// modified earlier are skipped.
+fn transcript_files(since: DateTime<Local>, data_dirs: &[String]) -> Vec<PathBuf> {
+    let since_system: SystemTime = since.into();
+    project_dirs(data_dirs)
+        .into_iter()
+        .flat_map(|dir| custom_jsonl::expand_glob(&format!("{}/**/*.jsonl", dir.display())))
+        .filter(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok();
+            modified.is_none_or(|m| m >= since_system)
+        })
+        .collect()
+}
+
+/// Hex SHA-256 over the path, size and modification time of every transcript
+/// [`read_entries`] would read for `since`, so unchanged logs can be detected
+/// without parsing them.
+#[must_use]
+pub fn fingerprint(since: DateTime<Local>, data_dirs: &[String]) -> String {
+    let mut files: Vec<String> = transcript_files(since, data_dirs)
+        .into_iter()
+        .map(|path| {
+            let metadata = path.metadata().ok();
+            let modified = metadata
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_nanos());
+            let len = metadata.map_or(0, |m| m.len());
+            format!("{}\0{len}\0{modified}", path.display())
+        })
+        .collect();
+    files.sort();
+
+    let mut context = digest::Context::new(&digest::SHA256);
+    context.update(since.to_rfc3339().as_bytes());
+    for file in &files {
+        context.update(b"\n");
+        context.update(file.as_bytes());
+    }
+    context
+        .finish()
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Parses one transcript's lines, keeping usage entries at or after `since`
+/// whose message and request id aren't in `seen` yet, and adding theirs.
+/// Lines that aren't usage or a title are skipped.
+#[must_use]
+pub fn parse_transcript<R: BufRead, S: BuildHasher>(
+    reader: R,
+    since: DateTime<Local>,
+    seen: &mut HashSet<String, S>,
+) -> Transcript {
+    let mut transcript = Transcript::default();
+    let mut has_summary = false;
+    for line in reader.lines().map_while(std::result::Result::ok) {
+        if line.len() > MAX_LINE_LENGTH {
+            continue;
+        }
+        if !line.contains("\"usage\"") {
+            // A summary replaces a title taken from the first prompt
+            let candidate = line.contains("\"type\":\"summary\"")
+                || (transcript.title.is_none() && line.contains("\"type\":\"user\""));
+            if !has_summary && candidate {
+                if let Some((title, is_summary)) = parse_title(&line) {
+                    if is_summary || transcript.title.is_none() {
+                        transcript.title = Some(title);
+                        has_summary = is_summary;
+                    }
+                }
+            }
+            continue;
+        }
+        let Some((entry, dedup_key)) = parse_line(&line) else {
+            continue;
+        };
+        if entry.timestamp < since {
+            continue;
+        }
+        if let Some(key) = dedup_key {
+            if !seen.insert(key) {
+                continue;
+            }
+        }
+        transcript.entries.push(entry);
+    }
+    transcript
+}
+
+/// Reads every Claude Code transcript in `data_dirs` (see [`project_dirs`])
+/// with its assistant usage entries at or after `since`.
+///
+/// Files last modified before `since` are skipped, and streamed duplicates of the
+/// same response (same message and request id) are counted once, matching ccusage.
+#[must_use]
+pub fn read_transcripts(since: DateTime<Local>, data_dirs: &[String]) -> Vec<Transcript> {
+    let mut seen = HashSet::new();
+    let mut transcripts = Vec::new();
+
+    for path in transcript_files(since, data_dirs) {
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        transcripts.push(parse_transcript(BufReader::new(file), since, &mut seen));
+    }
+
+    transcripts
+}
+
+/// Reads assistant usage entries at or after `since` from all Claude Code
+/// transcripts in `data_dirs`, oldest first (see [`read_transcripts`]).
+#[must_use]
+pub fn read_entries(since: DateTime<Local>, data_dirs: &[String]) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = read_transcripts(since, data_dirs)
+        .into_iter()
+        .flat_map(|transcript| transcript.entries)
+        .collect();
+    entries.sort_by_key(|e| e.timestamp);
+    entries
+}
+
+/// Cost per conversation title across `transcripts`, highest first.
+/// Conversations without usage are left out; untitled ones share one row.
+#[must_use]
+pub fn topic_breakdown<S: BuildHasher>(
+    transcripts: &[Transcript],
+    prices: Option<&HashMap<String, ModelPrice, S>>,
+) -> Vec<TopicUsage> {
+    let mut topics: HashMap<Option<&str>, TopicUsage> = HashMap::new();
+    for transcript in transcripts {
+        let Some(last) = transcript.entries.iter().map(|e| e.timestamp).max() else {
+            continue;
+        };
+        let last_activity = last.format("%Y-%m-%d").to_string();
+        let topic = topics
+            .entry(transcript.title.as_deref())
+            .or_insert_with(|| TopicUsage {
+                title: transcript.title.clone(),
+                ..TopicUsage::default()
+            });
+        topic.sessions += 1;
+        for entry in &transcript.entries {
+            topic.total_tokens += entry.total_tokens();
+            topic.cost += entry.resolved_cost(prices);
+        }
+        if last_activity > topic.last_activity {
+            topic.last_activity = last_activity;
+        }
+    }
+
+    let mut topics: Vec<TopicUsage> = topics.into_values().collect();
+    topics.sort_by(|a, b| {
+        b.cost
+            .total_cmp(&a.cost)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    topics
+}
+
+/// Aggregates transcript entries into the same per-day, per-model summary
+/// `ccusage --json` produces.
+#[must_use]
+pub fn summarize<S: BuildHasher>(
+    entries: &[LogEntry],
+    prices: Option<&HashMap<String, ModelPrice, S>>,
+    today: &str,
+) -> UsageSummary {
+    let records = entries.iter().map(|entry| UsageRecord {
+        date: entry.timestamp.format("%Y-%m-%d").to_string(),
+        model: entry.model.clone(),
+        input_tokens: entry.input_tokens,
+        output_tokens: entry.output_tokens,
+        cache_creation_input_tokens: entry.cache_creation_input_tokens,
+        cache_read_input_tokens: entry.cache_read_input_tokens,
+        cost: entry.resolved_cost(prices),
+        key: None,
+    });
+    sources::summary_from_daily(sources::aggregate_records(records), today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assistant_line() {
+        let line = r#"{"type":"assistant","timestamp":"2025-06-01T12:30:00Z","requestId":"req_1","message":{"id":"msg_1","model":"claude-sonnet-4-20250514","usage":{"input_tokens":10,"output_tokens":20,"cache_creation_input_tokens":30,"cache_read_input_tokens":40}},"costUSD":0.5}"#;
+        let (entry, key) = parse_line(line).expect("line should parse");
+        assert_eq!(entry.model, "claude-sonnet-4-20250514");
+        assert_eq!(entry.total_tokens(), 100);
+        assert_eq!(entry.cost, Some(0.5));
+        assert_eq!(key.as_deref(), Some("msg_1:req_1"));
+    }
+
+    fn entry(timestamp: &str, model: &str, input: u64, cost: Option<f64>) -> LogEntry {
+        LogEntry {
+            timestamp: DateTime::parse_from_rfc3339(timestamp)
+                .expect("valid timestamp")
+                .with_timezone(&Local),
+            model: model.to_string(),
+            input_tokens: input,
+            output_tokens: 10,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 5,
+            cost,
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let entries = [
+            entry("2025-06-01T12:00:00Z", "claude-sonnet-4", 100, Some(1.0)),
+            entry("2025-06-02T12:00:00Z", "claude-sonnet-4", 200, Some(2.0)),
+            entry("2025-06-02T13:00:00Z", "claude-opus-4", 1_000_000, None),
+        ];
+        let mut prices = HashMap::new();
+        prices.insert(
+            "claude-opus-4".to_string(),
+            ModelPrice {
+                input: 15.0,
+                output: 75.0,
+            },
+        );
+        let today = entries[2].timestamp.format("%Y-%m-%d").to_string();
+
+        let summary = summarize(&entries, Some(&prices), &today);
+        assert_eq!(summary.daily_usage.len(), 2);
+        assert_eq!(summary.model_breakdown.len(), 2);
+        assert_eq!(summary.this_month.input_tokens, 1_000_300);
+        assert_eq!(summary.this_month.total_tokens, 1_000_345);
+        assert!((summary.today.cost - (2.0 + 15.00075)).abs() < 1e-9);
+        assert!((summary.this_month.cost - (3.0 + 15.00075)).abs() < 1e-9);
+
+        let unpriced = summarize::<std::collections::hash_map::RandomState>(&entries, None, &today);
+        assert!((unpriced.this_month.cost - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_line_skips_non_usage_lines() {
+        assert!(parse_line(
+            r#"{"type":"user","timestamp":"2025-06-01T12:30:00Z","message":{"role":"user"}}"#
+        )
+        .is_none());
+        assert!(parse_line(r#"{"type":"summary","summary":"x"}"#).is_none());
+        assert!(parse_line("not json").is_none());
+        let zero = r#"{"timestamp":"2025-06-01T12:30:00Z","message":{"usage":{"input_tokens":0,"output_tokens":0}}}"#;
+        assert!(parse_line(zero).is_none());
+    }
+
+    #[test]
+    fn test_read_entries_merges_configured_data_dirs() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path();
+        let line = |id: &str| {
+            format!(
+                r#"{{"timestamp":"2025-06-01T12:30:00Z","requestId":"req_{id}","message":{{"id":"msg_{id}","model":"claude-sonnet-4-20250514","usage":{{"input_tokens":10,"output_tokens":20}}}}}}"#
+            )
+        };
+        for (home, ids) in [("work", ["a", "b"]), ("personal", ["b", "c"])] {
+            let project = base.join(home).join("projects").join("demo");
+            std::fs::create_dir_all(&project).unwrap();
+            let body: Vec<String> = ids.iter().map(|id| line(id)).collect();
+            std::fs::write(project.join("session.jsonl"), body.join("\n")).unwrap();
+        }
+        std::fs::create_dir_all(base.join("empty")).unwrap();
+
+        let dirs: Vec<String> = ["work", "personal", "empty"]
+            .iter()
+            .map(|home| base.join(home).to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(project_dirs(&dirs).len(), 2);
+
+        let since = DateTime::parse_from_rfc3339("2025-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Local);
+        assert_eq!(read_entries(since, &dirs).len(), 3);
+        assert_eq!(read_entries(since, &dirs[..1]).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_title() {
+        let summary = r#"{"type":"summary","summary":"Fix tray  refresh\nbug","leafUuid":"u1"}"#;
+        assert_eq!(
+            parse_title(summary),
+            Some(("Fix tray refresh".to_string(), true))
+        );
+
+        let prompt = r#"{"type":"user","message":{"role":"user","content":"Add a dark mode"}}"#;
+        assert_eq!(
+            parse_title(prompt),
+            Some(("Add a dark mode".to_string(), false))
+        );
+        let blocks = r#"{"type":"user","message":{"content":[{"type":"text","text":"Why?"}]}}"#;
+        assert_eq!(parse_title(blocks), Some(("Why?".to_string(), false)));
+
+        let tool =
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","content":"ok"}]}}"#;
+        assert!(parse_title(tool).is_none());
+        let meta = r#"{"type":"user","isMeta":true,"message":{"content":"Caveat"}}"#;
+        assert!(parse_title(meta).is_none());
+        let command =
+            r#"{"type":"user","message":{"content":"<command-name>/clear</command-name>"}}"#;
+        assert!(parse_title(command).is_none());
+
+        let long = clean_title(&"word ".repeat(40)).unwrap();
+        assert_eq!(long.chars().count(), MAX_TITLE_CHARS);
+        assert!(long.ends_with('…'));
+    }
+
+    #[test]
+    fn test_topic_breakdown() {
+        let transcript = |title: Option<&str>, entries: Vec<LogEntry>| Transcript {
+            title: title.map(str::to_string),
+            entries,
+        };
+        let day = |timestamp: &str, cost: f64| vec![entry(timestamp, "m", 100, Some(cost))];
+        let transcripts = [
+            transcript(Some("Fix login"), day("2025-06-01T12:00:00Z", 1.0)),
+            transcript(Some("Fix login"), day("2025-06-03T12:00:00Z", 2.0)),
+            transcript(Some("Write docs"), day("2025-06-02T12:00:00Z", 4.0)),
+            transcript(None, day("2025-06-02T12:00:00Z", 0.5)),
+            transcript(Some("Idle"), Vec::new()),
+        ];
+
+        let topics = topic_breakdown::<std::collections::hash_map::RandomState>(&transcripts, None);
+        let titles: Vec<Option<&str>> = topics.iter().map(|t| t.title.as_deref()).collect();
+        assert_eq!(titles, [Some("Write docs"), Some("Fix login"), None]);
+        assert_eq!(topics[1].sessions, 2);
+        assert!((topics[1].cost - 3.0).abs() < 1e-9);
+        assert_eq!(topics[1].total_tokens, 230);
+        let latest = transcripts[1].entries[0].timestamp.format("%Y-%m-%d");
+        assert_eq!(topics[1].last_activity, latest.to_string());
+    }
+}
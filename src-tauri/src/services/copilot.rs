@@ -0,0 +1,143 @@
+use crate::config::CopilotSourceConfig;
+use crate::services::http;
+use crate::services::sources::SourceReport;
+use crate::types::ProviderUsageResult;
+use anyhow::Result;
+use chrono::Datelike;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub const SOURCE_ID: &str = "copilot";
+const SOURCE_LABEL: &str = "Copilot";
+
+const API_BASE: &str = "https://api.github.com";
+const API_VERSION: &str = "2022-11-28";
+const PREMIUM_REQUEST_PRODUCT: &str = "copilot";
+
+/// Premium request usage report for one month.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PremiumRequestUsage {
+    #[serde(default)]
+    usage_items: Vec<UsageItem>,
+}
+
+/// Requests made with one model, before included requests are discounted.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageItem {
+    #[serde(default)]
+    product: String,
+    #[serde(default)]
+    gross_quantity: f64,
+}
+
+/// Premium requests used this month against the plan's monthly allowance.
+fn premium_requests(usage: &PremiumRequestUsage, allowance: u32) -> ProviderUsageResult {
+    let used = usage
+        .usage_items
+        .iter()
+        .filter(|item| item.product.eq_ignore_ascii_case(PREMIUM_REQUEST_PRODUCT))
+        .map(|item| item.gross_quantity)
+        .sum();
+    ProviderUsageResult {
+        cost: None,
+        tokens: None,
+        used: Some(used),
+        total: Some(f64::from(allowance)),
+        daily: Vec::new(),
+    }
+}
+
+/// Fetches this month's Copilot premium requests from GitHub's billing API.
+///
+/// Only the month's request count is reported; it is shown in the tray
+/// against the configured allowance, so the source adds no daily usage.
+///
+/// # Errors
+/// Returns an error if the username or token is missing, or the request
+/// fails or cannot be parsed.
+#[tracing::instrument(name = "source.copilot", skip_all)]
+pub async fn fetch_usage(config: &CopilotSourceConfig, timeout: Duration) -> Result<SourceReport> {
+    let username = config.username.trim();
+    if username.is_empty() {
+        anyhow::bail!("GitHub username is not configured");
+    }
+    let token = config
+        .token
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("GitHub token is not configured"))?;
+
+    let today = chrono::Local::now().date_naive();
+    let usage: PremiumRequestUsage = http::client()
+        .get(format!(
+            "{API_BASE}/users/{username}/settings/billing/premium_request/usage"
+        ))
+        .timeout(timeout)
+        .bearer_auth(token)
+        .header("Accept", "application/vnd.github+json")
+        .header("X-GitHub-Api-Version", API_VERSION)
+        .query(&[
+            ("year", today.year().to_string()),
+            ("month", today.month().to_string()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(SourceReport {
+        id: SOURCE_ID.to_string(),
+        label: SOURCE_LABEL.to_string(),
+        daily: Vec::new(),
+        keys: BTreeMap::new(),
+        credits: Some(premium_requests(&usage, config.monthly_allowance)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_premium_request_usage() {
+        let json = r#"{
+            "timePeriod": { "year": 2025, "month": 6 },
+            "user": "octocat",
+            "usageItems": [
+                {
+                    "product": "Copilot",
+                    "sku": "Copilot Premium Request",
+                    "model": "Claude Sonnet 4",
+                    "unitType": "requests",
+                    "pricePerUnit": 0.04,
+                    "grossQuantity": 120,
+                    "grossAmount": 4.8,
+                    "discountQuantity": 120,
+                    "discountAmount": 4.8,
+                    "netQuantity": 0,
+                    "netAmount": 0.0
+                },
+                {
+                    "product": "Copilot",
+                    "model": "GPT-5",
+                    "grossQuantity": 30.5
+                },
+                {
+                    "product": "Actions",
+                    "grossQuantity": 1000
+                }
+            ]
+        }"#;
+        let usage: PremiumRequestUsage =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        let result = premium_requests(&usage, 300);
+        assert_eq!(result.used, Some(150.5));
+        assert_eq!(result.total, Some(300.0));
+        assert!(result.format_display("Copilot").contains("150/300 (50%)"));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::config::AppConfig;
+use crate::services::keychain;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// A credential kept in the system keychain instead of `config.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Credential {
+    NtfyToken,
+    PushoverUserKey,
+    PushoverAppToken,
+    OpenAiAdminKey,
+    OpenRouterApiKey,
+}
+
+impl Credential {
+    pub const ALL: [Self; 5] = [
+        Self::NtfyToken,
+        Self::PushoverUserKey,
+        Self::PushoverAppToken,
+        Self::OpenAiAdminKey,
+        Self::OpenRouterApiKey,
+    ];
+
+    /// Keychain account the credential is stored under.
+    #[must_use]
+    pub const fn account(self) -> &'static str {
+        match self {
+            Self::NtfyToken => "push:ntfy-token",
+            Self::PushoverUserKey => "push:pushover-user-key",
+            Self::PushoverAppToken => "push:pushover-app-token",
+            Self::OpenAiAdminKey => "openai:admin-key",
+            Self::OpenRouterApiKey => "openrouter:api-key",
+        }
+    }
+
+    /// Field of `config` earlier versions kept the credential in.
+    const fn legacy_field(self, config: &mut AppConfig) -> &mut Option<String> {
+        let push = &mut config.alerts.push;
+        match self {
+            Self::NtfyToken => &mut push.ntfy_token,
+            Self::PushoverUserKey => &mut push.pushover_user_key,
+            Self::PushoverAppToken => &mut push.pushover_app_token,
+            Self::OpenAiAdminKey => &mut config.data_sources.openai.admin_key,
+            Self::OpenRouterApiKey => &mut config.data_sources.openrouter.api_key,
+        }
+    }
+}
+
+/// Value of `credential` from the keychain, falling back to `legacy`, a value
+/// still in `config.json` that could not be moved to the keychain yet.
+///
+/// # Errors
+/// Returns an error if the keychain cannot be read and there is no `legacy`
+/// value to fall back to.
+pub async fn lookup(credential: Credential, legacy: Option<&str>) -> Result<Option<String>> {
+    let legacy = legacy
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToString::to_string);
+    match keychain::get_secret(credential.account()).await {
+        Ok(Some(value)) if !value.trim().is_empty() => Ok(Some(value.trim().to_string())),
+        Ok(_) => Ok(legacy),
+        Err(e) => legacy.map(Some).ok_or(e),
+    }
+}
+
+/// Values of every credential stored in the keychain, for scrubbing them
+/// from free text. Credentials that can't be read are left out.
+pub async fn stored_values() -> Vec<String> {
+    let mut values = Vec::new();
+    for credential in Credential::ALL {
+        if let Ok(Some(value)) = keychain::get_secret(credential.account()).await {
+            values.push(value);
+        }
+    }
+    values
+}
+
+/// Moves credentials found in `config` into the keychain and clears them from
+/// `config`. Returns whether any were moved.
+///
+/// The values come from earlier versions or hand edits of `config.json`; the
+/// caller saves the config once they are moved, which drops them from disk.
+pub async fn move_to_keychain(config: &mut AppConfig) -> bool {
+    let mut moved = false;
+    for credential in Credential::ALL {
+        let field = credential.legacy_field(config);
+        let Some(value) = field
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+        else {
+            *field = None;
+            continue;
+        };
+        match keychain::set_secret(credential.account(), &value).await {
+            Ok(()) => {
+                *credential.legacy_field(config) = None;
+                moved = true;
+            }
+            Err(e) => tracing::warn!("Failed to move {credential:?} to the keychain: {e}"),
+        }
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credential_accounts_are_distinct() {
+        let mut accounts: Vec<&str> = Credential::ALL.iter().map(|c| c.account()).collect();
+        accounts.sort_unstable();
+        accounts.dedup();
+        assert_eq!(accounts.len(), Credential::ALL.len());
+    }
+
+    #[test]
+    fn test_credential_serde() {
+        let credential: Credential = serde_json::from_str(r#""pushoverAppToken""#).unwrap();
+        assert_eq!(credential, Credential::PushoverAppToken);
+        let credential: Credential = serde_json::from_str(r#""openAiAdminKey""#).unwrap();
+        assert_eq!(credential, Credential::OpenAiAdminKey);
+        assert!(serde_json::from_str::<Credential>(r#""smtp""#).is_err());
+    }
+
+    #[test]
+    fn test_legacy_field() {
+        let mut config = AppConfig::default();
+        config.alerts.push.pushover_user_key = Some("user".to_string());
+        assert_eq!(
+            Credential::PushoverUserKey
+                .legacy_field(&mut config)
+                .as_deref(),
+            Some("user")
+        );
+        assert!(Credential::NtfyToken.legacy_field(&mut config).is_none());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::config::CursorSourceConfig;
+use crate::services::http;
+use crate::services::sources::{self, SourceReport, UsageRecord};
+use crate::types::DailyUsage;
+use anyhow::Result;
+use base64::Engine;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OpenFlags};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const SOURCE_ID: &str = "cursor";
+const SOURCE_LABEL: &str = "Cursor";
+
+const USAGE_EVENTS_URL: &str = "https://cursor.com/api/dashboard/get-filtered-usage-events";
+const CURSOR_ORIGIN: &str = "https://cursor.com";
+const ACCESS_TOKEN_KEY: &str = "cursorAuth/accessToken";
+const PAGE_SIZE: usize = 500;
+const MAX_PAGES: usize = 20;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageEventsResponse {
+    #[serde(default)]
+    total_usage_events_count: usize,
+    #[serde(default)]
+    usage_events_display: Vec<UsageEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageEvent {
+    /// Milliseconds since the Unix epoch, encoded as a string.
+    timestamp: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    token_usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_write_tokens: u64,
+    #[serde(default)]
+    cache_read_tokens: u64,
+    #[serde(default)]
+    total_cents: f64,
+}
+
+/// Location of Cursor's VS Code-style global state database.
+fn local_state_db_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| {
+        dir.join("Cursor")
+            .join("User")
+            .join("globalStorage")
+            .join("state.vscdb")
+    })
+}
+
+/// Reads the signed-in user's access token from Cursor's local state database.
+fn read_local_access_token() -> Result<String> {
+    let path = local_state_db_path()
+        .filter(|p| p.exists())
+        .ok_or_else(|| anyhow::anyhow!("Cursor state database not found. Is Cursor installed?"))?;
+
+    let conn = Connection::open_with_flags(
+        &path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    let value: Value = conn
+        .query_row(
+            "SELECT value FROM ItemTable WHERE key = ?1",
+            [ACCESS_TOKEN_KEY],
+            |row| row.get(0),
+        )
+        .map_err(|_| anyhow::anyhow!("Not signed in to Cursor (no access token found)"))?;
+
+    let token = match value {
+        Value::Text(text) => text,
+        Value::Blob(bytes) => String::from_utf8(bytes)?,
+        _ => return Err(anyhow::anyhow!("Unexpected Cursor access token format")),
+    };
+    Ok(token.trim().trim_matches('"').to_string())
+}
+
+/// Extracts the Cursor user ID from the `sub` claim of a JWT access token.
+fn user_id_from_token(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    let sub = claims.get("sub")?.as_str()?;
+    Some(sub.rsplit('|').next().unwrap_or(sub).to_string())
+}
+
+/// Resolves the `WorkosCursorSessionToken` cookie value from config or the local database.
+fn resolve_session_token(config: &CursorSourceConfig) -> Result<String> {
+    if let Some(token) = config
+        .session_token
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        return Ok(token.to_string());
+    }
+
+    let access_token = read_local_access_token()?;
+    let user_id = user_id_from_token(&access_token)
+        .ok_or_else(|| anyhow::anyhow!("Cursor access token is not a valid JWT"))?;
+    Ok(format!("{user_id}%3A%3A{access_token}"))
+}
+
+fn event_local_date(timestamp: &str) -> Option<String> {
+    let millis: i64 = timestamp.parse().ok()?;
+    let utc = chrono::DateTime::from_timestamp_millis(millis)?;
+    Some(
+        utc.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d")
+            .to_string(),
+    )
+}
+
+fn aggregate_events(events: Vec<UsageEvent>) -> Vec<DailyUsage> {
+    sources::aggregate_records(events.into_iter().filter_map(|event| {
+        let date = event_local_date(&event.timestamp)?;
+        let usage = event.token_usage.unwrap_or_default();
+        let model = if event.model.is_empty() {
+            "unknown".to_string()
+        } else {
+            event.model
+        };
+        Some(UsageRecord {
+            date,
+            model,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: usage.cache_write_tokens,
+            cache_read_input_tokens: usage.cache_read_tokens,
+            cost: usage.total_cents / 100.0,
+            key: None,
+        })
+    }))
+}
+
+/// Fetches Cursor usage events for the summary window from the Cursor dashboard API.
+///
+/// # Errors
+/// Returns an error if no session token is available, the request fails,
+/// or the response cannot be parsed.
+#[tracing::instrument(name = "source.cursor", skip_all)]
+pub async fn fetch_usage(config: &CursorSourceConfig, timeout: Duration) -> Result<SourceReport> {
+    let session_token = resolve_session_token(config)?;
+    let client = http::client();
+
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::days(sources::SUMMARY_WINDOW_DAYS);
+
+    let mut events = Vec::new();
+    for page in 1..=MAX_PAGES {
+        let body = serde_json::json!({
+            "startDate": start.timestamp_millis().to_string(),
+            "endDate": end.timestamp_millis().to_string(),
+            "page": page,
+            "pageSize": PAGE_SIZE,
+        });
+        let response: UsageEventsResponse = client
+            .post(USAGE_EVENTS_URL)
+            .header(
+                "Cookie",
+                format!("WorkosCursorSessionToken={session_token}"),
+            )
+            .header("Origin", CURSOR_ORIGIN)
+            .timeout(timeout)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let page_len = response.usage_events_display.len();
+        events.extend(response.usage_events_display);
+        if page_len < PAGE_SIZE || events.len() >= response.total_usage_events_count {
+            break;
+        }
+    }
+
+    Ok(SourceReport {
+        id: SOURCE_ID.to_string(),
+        label: SOURCE_LABEL.to_string(),
+        daily: aggregate_events(events),
+        keys: BTreeMap::new(),
+        credits: None,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_id_from_token() {
+        let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(r#"{"sub":"auth0|user_abc123"}"#);
+        let token = format!("header.{payload}.signature");
+        assert_eq!(user_id_from_token(&token), Some("user_abc123".to_string()));
+        assert_eq!(user_id_from_token("not-a-jwt"), None);
+    }
+
+    #[test]
+    fn test_resolve_session_token_prefers_config() {
+        let config = CursorSourceConfig {
+            enabled: true,
+            session_token: Some("  user_1%3A%3Atoken  ".to_string()),
+        };
+        assert_eq!(
+            resolve_session_token(&config).expect("configured token should be used"),
+            "user_1%3A%3Atoken"
+        );
+    }
+
+    #[test]
+    fn test_parse_usage_events() {
+        let json = r#"{
+            "totalUsageEventsCount": 2,
+            "usageEventsDisplay": [
+                {
+                    "timestamp": "1705312800000",
+                    "model": "claude-4-sonnet",
+                    "tokenUsage": {
+                        "inputTokens": 1000,
+                        "outputTokens": 200,
+                        "cacheWriteTokens": 50,
+                        "cacheReadTokens": 300,
+                        "totalCents": 12.5
+                    }
+                },
+                {
+                    "timestamp": "1705312900000",
+                    "model": "",
+                    "kind": "USAGE_EVENT_KIND_INCLUDED_IN_PRO"
+                }
+            ]
+        }"#;
+
+        let response: UsageEventsResponse =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        assert_eq!(response.total_usage_events_count, 2);
+
+        let daily = aggregate_events(response.usage_events_display);
+        let models: usize = daily.iter().map(|d| d.models.len()).sum();
+        let cost: f64 = daily.iter().map(|d| d.cost).sum();
+        let input: u64 = daily.iter().map(|d| d.input_tokens).sum();
+        assert_eq!(models, 2);
+        assert_eq!(cost, 0.125);
+        assert_eq!(input, 1000);
+        assert!(daily
+            .iter()
+            .flat_map(|d| &d.models)
+            .any(|m| m.model == "unknown"));
+    }
+}
@@ -0,0 +1,434 @@
+use crate::config::{CustomJsonlSourceConfig, JsonlFieldMapping};
+use crate::services::pricing;
+use crate::services::sources::{self, SourceReport, UsageRecord};
+use anyhow::Result;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Upper bound on files matched by a single glob to keep refreshes bounded.
+const MAX_FILES: usize = 10_000;
+/// Lines longer than this are skipped rather than parsed.
+const MAX_LINE_LENGTH: usize = 1_000_000;
+/// Epoch values above this are treated as milliseconds rather than seconds.
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// A parsed line plus whether its cost came from the log itself.
+#[derive(Debug, Clone)]
+pub struct ParsedRecord {
+    pub record: UsageRecord,
+    pub has_cost: bool,
+}
+
+/// Matches a single path component against a pattern supporting `*` and `?`.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn has_wildcard(part: &str) -> bool {
+    part.contains('*') || part.contains('?')
+}
+
+fn expand_home(pattern: &str) -> PathBuf {
+    if let Some(rest) = pattern.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(pattern)
+}
+
+/// Expands a path glob (`*`, `?` and `**` for any depth, leading `~/` for home)
+/// into the sorted list of matching files.
+#[must_use]
+pub fn expand_glob(pattern: &str) -> Vec<PathBuf> {
+    let path = expand_home(pattern);
+    let mut base = PathBuf::new();
+    let mut rest: Vec<String> = Vec::new();
+
+    for component in path.components() {
+        let part = component.as_os_str().to_string_lossy().to_string();
+        if rest.is_empty() && !has_wildcard(&part) {
+            base.push(component);
+        } else {
+            rest.push(part);
+        }
+    }
+
+    let mut matches = Vec::new();
+    if rest.is_empty() {
+        if base.is_file() {
+            matches.push(base);
+        }
+        return matches;
+    }
+    if rest.last().is_some_and(|p| p == "**") {
+        rest.push("*".to_string());
+    }
+
+    walk(&base, &rest, &mut matches);
+    matches.sort();
+    matches.dedup();
+    matches
+}
+
+fn walk(dir: &Path, parts: &[String], out: &mut Vec<PathBuf>) {
+    let Some((first, remaining)) = parts.split_first() else {
+        return;
+    };
+    if out.len() >= MAX_FILES {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    if first == "**" {
+        walk(dir, remaining, out);
+        for entry in entries.flatten() {
+            // Do not follow symlinked directories to avoid cycles.
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                walk(&entry.path(), parts, out);
+            }
+        }
+        return;
+    }
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !wildcard_match(first, &name) {
+            continue;
+        }
+        let path = entry.path();
+        if remaining.is_empty() {
+            if path.is_file() && out.len() < MAX_FILES {
+                out.push(path);
+            }
+        } else if path.is_dir() {
+            walk(&path, remaining, out);
+        }
+    }
+}
+
+/// Resolves a dotted field path (`usage.input_tokens`, `choices.0.model`) in a JSON value.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| match current {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => current.get(segment),
+        })
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn value_as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n
+            .as_u64()
+            .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Converts an RFC 3339 string, plain date, or epoch seconds/milliseconds
+/// into a local `YYYY-MM-DD` date.
+fn timestamp_to_local_date(value: &Value) -> Option<String> {
+    let local = match value {
+        Value::Number(n) => {
+            let raw = n.as_i64()?;
+            let utc = if raw > EPOCH_MILLIS_THRESHOLD {
+                chrono::DateTime::from_timestamp_millis(raw)?
+            } else {
+                chrono::DateTime::from_timestamp(raw, 0)?
+            };
+            utc.with_timezone(&chrono::Local).date_naive()
+        }
+        Value::String(s) => {
+            let s = s.trim();
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+                dt.with_timezone(&chrono::Local).date_naive()
+            } else {
+                chrono::NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok()?
+            }
+        }
+        _ => return None,
+    };
+    Some(local.format("%Y-%m-%d").to_string())
+}
+
+fn optional_u64(entry: &Value, path: Option<&str>) -> u64 {
+    path.and_then(|p| lookup(entry, p))
+        .and_then(value_as_u64)
+        .unwrap_or(0)
+}
+
+fn parse_line(line: &str, fields: &JsonlFieldMapping) -> Option<ParsedRecord> {
+    let entry: Value = serde_json::from_str(line).ok()?;
+    let date = timestamp_to_local_date(lookup(&entry, &fields.timestamp)?)?;
+    let model = lookup(&entry, &fields.model)
+        .and_then(Value::as_str)
+        .filter(|m| !m.is_empty())
+        .unwrap_or("unknown")
+        .to_string();
+    let cost = fields
+        .cost
+        .as_deref()
+        .and_then(|p| lookup(&entry, p))
+        .and_then(value_as_f64);
+    let key = fields
+        .key
+        .as_deref()
+        .and_then(|p| lookup(&entry, p))
+        .and_then(|value| match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+        .filter(|key| !key.is_empty());
+
+    Some(ParsedRecord {
+        record: UsageRecord {
+            date,
+            model,
+            input_tokens: optional_u64(&entry, Some(fields.input_tokens.as_str())),
+            output_tokens: optional_u64(&entry, Some(fields.output_tokens.as_str())),
+            cache_creation_input_tokens: optional_u64(
+                &entry,
+                fields.cache_creation_tokens.as_deref(),
+            ),
+            cache_read_input_tokens: optional_u64(&entry, fields.cache_read_tokens.as_deref()),
+            cost: cost.unwrap_or(0.0),
+            key,
+        },
+        has_cost: cost.is_some(),
+    })
+}
+
+/// Records of the lines in `reader` that `fields` can read. Blank, overlong
+/// and invalid lines, or ones without a parseable timestamp, are skipped.
+#[must_use]
+pub fn parse_records<R: BufRead>(reader: R, fields: &JsonlFieldMapping) -> Vec<ParsedRecord> {
+    reader
+        .lines()
+        .map_while(std::result::Result::ok)
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.len() > MAX_LINE_LENGTH {
+                return None;
+            }
+            parse_line(line, fields)
+        })
+        .collect()
+}
+
+fn read_records(path_glob: &str, fields: &JsonlFieldMapping) -> Vec<ParsedRecord> {
+    let mut records = Vec::new();
+    for path in expand_glob(path_glob) {
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        records.extend(parse_records(BufReader::new(file), fields));
+    }
+    records
+}
+
+/// Reads usage from JSONL files matched by a user-defined glob and field mapping.
+///
+/// Lines that are not valid JSON or lack a parseable timestamp are skipped.
+/// When the mapping has no cost field (or a line lacks it), cost is estimated
+/// from models.dev prices.
+///
+/// # Errors
+/// Returns an error if the source is misconfigured or the file scan task fails.
+#[tracing::instrument(name = "source.custom_jsonl", skip_all)]
+pub async fn fetch_usage(
+    config: &CustomJsonlSourceConfig,
+    pricing_timeout: Duration,
+) -> Result<SourceReport> {
+    if config.path_glob.trim().is_empty() {
+        return Err(anyhow::anyhow!(
+            "Custom JSONL source '{}' has no path",
+            config.id
+        ));
+    }
+
+    let path_glob = config.path_glob.clone();
+    let fields = config.fields.clone();
+    let parsed = tokio::task::spawn_blocking(move || read_records(&path_glob, &fields)).await?;
+
+    let needs_prices = parsed
+        .iter()
+        .any(|p| !p.has_cost && (p.record.input_tokens > 0 || p.record.output_tokens > 0));
+    let prices = if needs_prices {
+        pricing::get_prices(pricing_timeout).await
+    } else {
+        None
+    };
+
+    let records: Vec<UsageRecord> = parsed
+        .into_iter()
+        .map(|p| {
+            let mut record = p.record;
+            if !p.has_cost {
+                if let Some(ref prices) = prices {
+                    record.cost = pricing::calculate_fallback_cost(
+                        &record.model,
+                        record.input_tokens,
+                        record.output_tokens,
+                        prices,
+                    );
+                }
+            }
+            record
+        })
+        .collect();
+
+    Ok(SourceReport {
+        id: config.id.clone(),
+        label: if config.label.is_empty() {
+            config.id.clone()
+        } else {
+            config.label.clone()
+        },
+        keys: sources::aggregate_records_by_key(&records),
+        credits: None,
+        daily: sources::aggregate_records(records),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn fields() -> JsonlFieldMapping {
+        JsonlFieldMapping {
+            timestamp: "ts".to_string(),
+            model: "response.model".to_string(),
+            input_tokens: "response.usage.prompt_tokens".to_string(),
+            output_tokens: "response.usage.completion_tokens".to_string(),
+            cache_creation_tokens: None,
+            cache_read_tokens: None,
+            cost: Some("cost".to_string()),
+            key: Some("metadata.key_alias".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.jsonl", "session.jsonl"));
+        assert!(wildcard_match("log-??.jsonl", "log-01.jsonl"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a*b*c", "aXXbYYc"));
+        assert!(!wildcard_match("*.jsonl", "session.json"));
+        assert!(!wildcard_match("log-?.jsonl", "log-01.jsonl"));
+    }
+
+    #[test]
+    fn test_lookup_nested_and_array_paths() {
+        let value: Value = serde_json::json!({
+            "usage": { "input": 5 },
+            "choices": [{ "model": "gpt-4o" }]
+        });
+        assert_eq!(lookup(&value, "usage.input"), Some(&serde_json::json!(5)));
+        assert_eq!(
+            lookup(&value, "choices.0.model").and_then(Value::as_str),
+            Some("gpt-4o")
+        );
+        assert!(lookup(&value, "usage.missing").is_none());
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        assert_eq!(
+            timestamp_to_local_date(&serde_json::json!("2024-03-05")),
+            Some("2024-03-05".to_string())
+        );
+        assert!(timestamp_to_local_date(&serde_json::json!("2024-03-05T12:00:00Z")).is_some());
+        assert!(timestamp_to_local_date(&serde_json::json!(1_709_640_000)).is_some());
+        assert_eq!(
+            timestamp_to_local_date(&serde_json::json!(1_709_640_000)),
+            timestamp_to_local_date(&serde_json::json!(1_709_640_000_000_i64))
+        );
+        assert!(timestamp_to_local_date(&serde_json::json!("yesterday")).is_none());
+    }
+
+    #[test]
+    fn test_parse_line_with_mapping() {
+        let line = r#"{"ts":"2024-03-05","cost":"0.5","metadata":{"key_alias":"ci-bot"},"response":{"model":"gpt-4o","usage":{"prompt_tokens":100,"completion_tokens":20}}}"#;
+        let parsed = parse_line(line, &fields()).expect("line should parse");
+        assert_eq!(parsed.record.date, "2024-03-05");
+        assert_eq!(parsed.record.model, "gpt-4o");
+        assert_eq!(parsed.record.input_tokens, 100);
+        assert_eq!(parsed.record.output_tokens, 20);
+        assert_eq!(parsed.record.cost, 0.5);
+        assert_eq!(parsed.record.key.as_deref(), Some("ci-bot"));
+        assert!(parsed.has_cost);
+
+        let no_cost = r#"{"ts":"2024-03-05","response":{"usage":{"prompt_tokens":1}}}"#;
+        let parsed = parse_line(no_cost, &fields()).expect("line should parse");
+        assert_eq!(parsed.record.model, "unknown");
+        assert_eq!(parsed.record.key, None);
+        assert!(!parsed.has_cost);
+
+        assert!(parse_line("not json", &fields()).is_none());
+        assert!(parse_line(r#"{"response":{}}"#, &fields()).is_none());
+    }
+
+    #[test]
+    fn test_expand_glob_and_read_records() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        let nested = dir.join("project").join("sessions");
+        fs::create_dir_all(&nested).expect("nested dir should be creatable");
+        fs::write(
+            nested.join("a.jsonl"),
+            "{\"ts\":\"2024-03-05\",\"cost\":1,\"response\":{\"model\":\"m\"}}\n\ngarbage\n",
+        )
+        .expect("fixture should be writable");
+        fs::write(dir.join("b.jsonl"), "{\"ts\":\"2024-03-06\",\"cost\":2}\n")
+            .expect("fixture should be writable");
+        fs::write(dir.join("ignored.txt"), "{}").expect("fixture should be writable");
+
+        let pattern = format!("{}/**/*.jsonl", dir.display());
+        let files = expand_glob(&pattern);
+        assert_eq!(files.len(), 2);
+
+        let records = read_records(&pattern, &fields());
+        assert_eq!(records.len(), 2);
+        let total: f64 = records.iter().map(|r| r.record.cost).sum();
+        assert_eq!(total, 3.0);
+    }
+}
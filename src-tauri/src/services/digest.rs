@@ -0,0 +1,435 @@
+use crate::config::EmailDigestConfig;
+use crate::services::keychain;
+use crate::types::{format_number, DailyUsage, ModelUsage};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+const SEND_TIMEOUT: Duration = Duration::from_mins(1);
+const REPORT_DAYS: i64 = 7;
+const TOP_MODELS: usize = 5;
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<body style="font-family: -apple-system, 'Segoe UI', sans-serif; color: #111;">
+<h2 style="margin-bottom: 4px;">TokenMeter weekly digest</h2>
+<p style="color: #666; margin-top: 0;">{{start}} &ndash; {{end}}</p>
+<p style="font-size: 28px; margin: 16px 0 4px;"><strong>{{total_cost}}</strong></p>
+<p style="color: #666; margin-top: 0;">{{total_tokens}} tokens &middot; {{change}} vs. previous week</p>
+<h3>Daily</h3>
+<table cellpadding="6" style="border-collapse: collapse;">
+<tr><th align="left">Date</th><th align="right">Cost</th><th align="right">Tokens</th></tr>
+{{day_rows}}
+</table>
+<h3>Top models</h3>
+<table cellpadding="6" style="border-collapse: collapse;">
+<tr><th align="left">Model</th><th align="right">Cost</th></tr>
+{{model_rows}}
+</table>
+</body>
+</html>
+"#;
+
+/// Keychain account holding the SMTP password for `username`.
+#[must_use]
+pub fn password_account(username: &str) -> String {
+    format!("smtp:{username}")
+}
+
+/// Usage totals for one week, plus the week before for comparison.
+#[derive(Debug, Clone)]
+pub struct WeeklyReport {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub total_cost: f64,
+    pub total_tokens: u64,
+    pub previous_cost: f64,
+    /// One entry per day of the week, oldest first (zero when there was no usage).
+    pub days: Vec<(NaiveDate, f64, u64)>,
+    /// Highest-cost models of the week.
+    pub top_models: Vec<ModelUsage>,
+}
+
+/// Builds the report for the seven days ending on `end` (inclusive).
+#[must_use]
+pub fn build_weekly_report(daily: &[DailyUsage], end: NaiveDate) -> WeeklyReport {
+    let start = end - chrono::Duration::days(REPORT_DAYS - 1);
+    let previous_start = start - chrono::Duration::days(REPORT_DAYS);
+    let find = |date: NaiveDate| {
+        let key = date.format("%Y-%m-%d").to_string();
+        daily.iter().find(|d| d.date == key)
+    };
+
+    let mut report = WeeklyReport {
+        start,
+        end,
+        total_cost: 0.0,
+        total_tokens: 0,
+        previous_cost: 0.0,
+        days: Vec::new(),
+        top_models: Vec::new(),
+    };
+
+    for date in start.iter_days().take_while(|d| *d <= end) {
+        let Some(day) = find(date) else {
+            report.days.push((date, 0.0, 0));
+            continue;
+        };
+        let tokens = day.input_tokens
+            + day.output_tokens
+            + day.cache_creation_input_tokens
+            + day.cache_read_input_tokens;
+        report.total_cost += day.cost;
+        report.total_tokens += tokens;
+        report.days.push((date, day.cost, tokens));
+
+        for model in &day.models {
+            if let Some(existing) = report
+                .top_models
+                .iter_mut()
+                .find(|m| m.model == model.model)
+            {
+                existing.cost += model.cost;
+                existing.input_tokens += model.input_tokens;
+                existing.output_tokens += model.output_tokens;
+            } else {
+                report.top_models.push(model.clone());
+            }
+        }
+    }
+    report.top_models.sort_by(|a, b| b.cost.total_cmp(&a.cost));
+    report.top_models.truncate(TOP_MODELS);
+
+    report.previous_cost = previous_start
+        .iter_days()
+        .take_while(|d| *d < start)
+        .filter_map(find)
+        .map(|d| d.cost)
+        .sum();
+
+    report
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_change(report: &WeeklyReport) -> String {
+    if report.previous_cost <= 0.0 {
+        return "no usage".to_string();
+    }
+    let percent = (report.total_cost - report.previous_cost) / report.previous_cost * 100.0;
+    format!("{percent:+.0}%")
+}
+
+/// Renders the report into the HTML email template.
+#[must_use]
+pub fn render_html(report: &WeeklyReport) -> String {
+    let day_rows: Vec<String> = report
+        .days
+        .iter()
+        .map(|(date, cost, tokens)| {
+            format!(
+                r#"<tr><td>{}</td><td align="right">${cost:.2}</td><td align="right">{}</td></tr>"#,
+                date.format("%a %b %-d"),
+                format_number(*tokens)
+            )
+        })
+        .collect();
+    let model_rows: Vec<String> = report
+        .top_models
+        .iter()
+        .map(|m| {
+            format!(
+                r#"<tr><td>{}</td><td align="right">${:.2}</td></tr>"#,
+                escape_html(&m.model),
+                m.cost
+            )
+        })
+        .collect();
+
+    HTML_TEMPLATE
+        .replace("{{start}}", &report.start.format("%b %-d").to_string())
+        .replace("{{end}}", &report.end.format("%b %-d, %Y").to_string())
+        .replace("{{total_cost}}", &format!("${:.2}", report.total_cost))
+        .replace("{{total_tokens}}", &format_number(report.total_tokens))
+        .replace("{{change}}", &format_change(report))
+        .replace("{{day_rows}}", &day_rows.join("\n"))
+        .replace("{{model_rows}}", &model_rows.join("\n"))
+}
+
+/// Builds the full RFC 5322 message (headers and HTML body) with CRLF line endings.
+#[must_use]
+pub fn build_message(
+    config: &EmailDigestConfig,
+    report: &WeeklyReport,
+    now: DateTime<Local>,
+) -> String {
+    let subject = format!(
+        "TokenMeter weekly digest: ${:.2} ({} - {})",
+        report.total_cost,
+        report.start.format("%b %-d"),
+        report.end.format("%b %-d")
+    );
+    let headers = [
+        format!("From: {}", config.from.trim()),
+        format!("To: {}", config.to.join(", ")),
+        format!("Subject: {subject}"),
+        format!("Date: {}", now.to_rfc2822()),
+        "MIME-Version: 1.0".to_string(),
+        "Content-Type: text/html; charset=utf-8".to_string(),
+        "Content-Transfer-Encoding: 8bit".to_string(),
+    ];
+    let body = render_html(report).lines().collect::<Vec<_>>().join("\r\n");
+    format!("{}\r\n\r\n{body}\r\n", headers.join("\r\n"))
+}
+
+/// Checks the digest settings, rejecting values that could inject headers or
+/// curl options.
+///
+/// # Errors
+/// Returns a description of the first invalid field.
+pub fn validate_config(config: &EmailDigestConfig) -> Result<()> {
+    let url = config.smtp_url.trim();
+    if !(url.starts_with("smtps://") || url.starts_with("smtp://")) {
+        return Err(anyhow::anyhow!(
+            "SMTP server must start with smtps:// or smtp://"
+        ));
+    }
+    if config.to.is_empty() {
+        return Err(anyhow::anyhow!("At least one recipient is required"));
+    }
+    for address in std::iter::once(&config.from).chain(&config.to) {
+        let address = address.trim();
+        if !address.contains('@') || address.starts_with('-') {
+            return Err(anyhow::anyhow!("Invalid email address: {address}"));
+        }
+    }
+    let fields = [&config.smtp_url, &config.username, &config.from]
+        .into_iter()
+        .chain(&config.to);
+    if fields.into_iter().any(|f| f.contains(['\r', '\n'])) {
+        return Err(anyhow::anyhow!(
+            "Email settings must not contain line breaks"
+        ));
+    }
+    if config.weekday > 6 || config.hour > 23 {
+        return Err(anyhow::anyhow!("Digest weekday must be 0-6 and hour 0-23"));
+    }
+    Ok(())
+}
+
+/// Whether the weekly digest should be sent at `now`, given the date it was last sent.
+#[must_use]
+pub fn is_due(
+    config: &EmailDigestConfig,
+    now: DateTime<Local>,
+    last_sent: Option<NaiveDate>,
+) -> bool {
+    config.enabled
+        && now.weekday().num_days_from_monday() == config.weekday
+        && now.hour() >= config.hour
+        && last_sent != Some(now.date_naive())
+}
+
+/// Escapes a value for a double-quoted curl config entry.
+fn curl_config_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Sends `message` through curl's SMTP client.
+///
+/// Credentials are passed on stdin as a curl config file so they never appear
+/// in the process list.
+async fn send_message(config: &EmailDigestConfig, password: &str, message: &str) -> Result<()> {
+    let message_path =
+        std::env::temp_dir().join(format!("tokenmeter-digest-{}.eml", std::process::id()));
+    tokio::fs::write(&message_path, message).await?;
+
+    let mut cmd = Command::new("curl");
+    cmd.args(["--silent", "--show-error", "--ssl-reqd", "--url"])
+        .arg(config.smtp_url.trim())
+        .arg("--mail-from")
+        .arg(config.from.trim());
+    for recipient in &config.to {
+        cmd.arg("--mail-rcpt").arg(recipient.trim());
+    }
+    cmd.arg("--upload-file")
+        .arg(&message_path)
+        .args(["--config", "-"])
+        .env_clear()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let result = async {
+        let mut child = cmd.spawn().context("Failed to run curl")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let credentials = format!("{}:{password}", config.username.trim());
+            stdin
+                .write_all(format!("user = {}\n", curl_config_quote(&credentials)).as_bytes())
+                .await?;
+        }
+        let output = tokio::time::timeout(SEND_TIMEOUT, child.wait_with_output())
+            .await
+            .map_err(|_| anyhow::anyhow!("Sending email timed out"))??;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("SMTP send failed: {}", stderr.trim()));
+        }
+        Ok(())
+    }
+    .await;
+
+    let _ = tokio::fs::remove_file(&message_path).await;
+    result
+}
+
+/// Builds the digest for the week ending yesterday and emails it.
+///
+/// # Errors
+/// Returns an error if the settings are invalid, the password is missing from the
+/// keychain, or the SMTP server rejects the message.
+#[tracing::instrument(name = "service.digest", skip_all)]
+pub async fn send_weekly_digest(config: &EmailDigestConfig, daily: &[DailyUsage]) -> Result<()> {
+    validate_config(config)?;
+    let password = keychain::get_secret(&password_account(config.username.trim()))
+        .await?
+        .context("SMTP password is not set")?;
+
+    let now = Local::now();
+    let report = build_weekly_report(daily, now.date_naive() - chrono::Duration::days(1));
+    send_message(config, &password, &build_message(config, &report, now)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(date: &str, cost: f64, models: &[(&str, f64)]) -> DailyUsage {
+        DailyUsage {
+            date: date.to_string(),
+            cost,
+            input_tokens: 1000,
+            output_tokens: 0,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            models: models
+                .iter()
+                .map(|(model, cost)| ModelUsage {
+                    model: (*model).to_string(),
+                    cost: *cost,
+                    input_tokens: 0,
+                    output_tokens: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn digest_config() -> EmailDigestConfig {
+        EmailDigestConfig {
+            enabled: true,
+            smtp_url: "smtps://smtp.example.com:465".to_string(),
+            username: "me@example.com".to_string(),
+            from: "me@example.com".to_string(),
+            to: vec!["me@example.com".to_string()],
+            weekday: 0,
+            hour: 9,
+        }
+    }
+
+    #[test]
+    fn test_build_weekly_report() {
+        let daily = vec![
+            day("2025-01-01", 10.0, &[]), // previous week
+            day("2025-01-08", 4.0, &[("claude-opus-4-5", 3.0), ("<b>", 1.0)]),
+            day("2025-01-12", 1.0, &[("claude-opus-4-5", 1.0)]),
+            day("2025-01-13", 99.0, &[]), // after the report
+        ];
+        let end = NaiveDate::from_ymd_opt(2025, 1, 12).expect("valid date");
+        let report = build_weekly_report(&daily, end);
+
+        assert_eq!(report.days.len(), 7);
+        assert_eq!(report.start.to_string(), "2025-01-06");
+        assert!((report.total_cost - 5.0).abs() < 1e-9);
+        assert_eq!(report.total_tokens, 2000);
+        assert!((report.previous_cost - 10.0).abs() < 1e-9);
+        assert_eq!(report.top_models[0].model, "claude-opus-4-5");
+        assert!((report.top_models[0].cost - 4.0).abs() < 1e-9);
+
+        let html = render_html(&report);
+        assert!(html.contains("$5.00"));
+        assert!(html.contains("-50%"));
+        assert!(html.contains("&lt;b&gt;"));
+        assert!(!html.contains("{{"));
+    }
+
+    #[test]
+    fn test_build_message_headers() {
+        let report = build_weekly_report(
+            &[],
+            NaiveDate::from_ymd_opt(2025, 1, 12).expect("valid date"),
+        );
+        let now = Local
+            .with_ymd_and_hms(2025, 1, 13, 9, 0, 0)
+            .single()
+            .expect("valid local time");
+        let message = build_message(&digest_config(), &report, now);
+        assert!(message.starts_with("From: me@example.com\r\nTo: me@example.com\r\n"));
+        assert!(message.contains("Subject: TokenMeter weekly digest: $0.00 (Jan 6 - Jan 12)\r\n"));
+        assert!(message.contains("\r\n\r\n<!DOCTYPE html>"));
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert!(validate_config(&digest_config()).is_ok());
+
+        let mut config = digest_config();
+        config.smtp_url = "https://smtp.example.com".to_string();
+        assert!(validate_config(&config).is_err());
+
+        let mut config = digest_config();
+        config.to = vec!["me@example.com\r\nBcc: x@example.com".to_string()];
+        assert!(validate_config(&config).is_err());
+
+        let mut config = digest_config();
+        config.from = "--upload-file".to_string();
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_is_due() {
+        let config = digest_config();
+        // 2025-01-13 is a Monday
+        let monday_early = Local
+            .with_ymd_and_hms(2025, 1, 13, 8, 0, 0)
+            .single()
+            .expect("valid local time");
+        let monday = Local
+            .with_ymd_and_hms(2025, 1, 13, 9, 30, 0)
+            .single()
+            .expect("valid local time");
+        let tuesday = Local
+            .with_ymd_and_hms(2025, 1, 14, 9, 30, 0)
+            .single()
+            .expect("valid local time");
+
+        assert!(!is_due(&config, monday_early, None));
+        assert!(is_due(&config, monday, None));
+        assert!(!is_due(&config, monday, Some(monday.date_naive())));
+        assert!(!is_due(&config, tuesday, None));
+    }
+
+    #[test]
+    fn test_curl_config_quote() {
+        assert_eq!(curl_config_quote(r#"u:p"a\b"#), r#""u:p\"a\\b""#);
+    }
+}
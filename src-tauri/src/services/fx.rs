@@ -0,0 +1,203 @@
+use crate::services::http;
+use crate::types::ProviderUsageResult;
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+const FX_RATES_URL: &str = "https://open.er-api.com/v6/latest/USD";
+const FETCH_TIMEOUT_SECS: u64 = 10;
+const CACHE_TTL: Duration = Duration::from_hours(12);
+
+/// Currency all costs are displayed and aggregated in (ccusage reports USD).
+pub const DISPLAY_CURRENCY: &str = "USD";
+
+/// Exchange rates quoted as units of each currency per 1 USD.
+#[derive(Debug, Clone)]
+pub struct FxRates {
+    rates: HashMap<String, f64>,
+}
+
+impl FxRates {
+    #[must_use]
+    pub fn new(rates: HashMap<String, f64>) -> Self {
+        let mut rates: HashMap<String, f64> = rates
+            .into_iter()
+            .filter(|(_, rate)| rate.is_finite() && *rate > 0.0)
+            .map(|(code, rate)| (code.to_uppercase(), rate))
+            .collect();
+        rates.insert("USD".to_string(), 1.0);
+        Self { rates }
+    }
+
+    /// Converts `amount` between ISO 4217 currency codes (case-insensitive).
+    ///
+    /// Returns `None` if either currency is unknown.
+    #[must_use]
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        if from.eq_ignore_ascii_case(to) {
+            return Some(amount);
+        }
+        let from_rate = self.rates.get(&from.to_uppercase())?;
+        let to_rate = self.rates.get(&to.to_uppercase())?;
+        Some(amount / from_rate * to_rate)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RatesResponse {
+    result: String,
+    #[serde(default)]
+    rates: HashMap<String, f64>,
+}
+
+static RATES_CACHE: OnceLock<RwLock<Option<(FxRates, Instant)>>> = OnceLock::new();
+
+fn get_cache() -> &'static RwLock<Option<(FxRates, Instant)>> {
+    RATES_CACHE.get_or_init(|| RwLock::new(None))
+}
+
+/// Fetches current exchange rates.
+///
+/// # Errors
+/// Returns an error if the HTTP request fails or the response cannot be parsed.
+#[tracing::instrument(name = "service.fx", skip_all)]
+pub async fn fetch_rates() -> Result<FxRates> {
+    let response: RatesResponse = http::client()
+        .get(FX_RATES_URL)
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if response.result != "success" {
+        return Err(anyhow::anyhow!(
+            "Exchange rate service returned '{}'",
+            response.result
+        ));
+    }
+
+    let rates = FxRates::new(response.rates);
+    *get_cache().write().await = Some((rates.clone(), Instant::now()));
+    Ok(rates)
+}
+
+/// Gets cached exchange rates, refreshing them when older than the cache TTL.
+/// Falls back to stale rates if the refresh fails.
+pub async fn get_rates() -> Option<FxRates> {
+    let cached = get_cache().read().await.clone();
+    if let Some((rates, fetched_at)) = &cached {
+        if fetched_at.elapsed() < CACHE_TTL {
+            return Some(rates.clone());
+        }
+    }
+
+    match fetch_rates().await {
+        Ok(rates) => Some(rates),
+        Err(e) => {
+            tracing::warn!("Failed to fetch exchange rates: {e}");
+            cached.map(|(rates, _)| rates)
+        }
+    }
+}
+
+/// Converts a provider result's `cost` from `currency` into [`DISPLAY_CURRENCY`].
+///
+/// The cost is dropped rather than kept unconverted when no exchange rate is
+/// available, so values in different currencies are never added together.
+#[must_use]
+pub fn normalize_provider_result(
+    mut result: ProviderUsageResult,
+    currency: Option<&str>,
+    rates: Option<&FxRates>,
+) -> ProviderUsageResult {
+    let Some(currency) = currency
+        .map(str::trim)
+        .filter(|c| !c.is_empty() && !c.eq_ignore_ascii_case(DISPLAY_CURRENCY))
+    else {
+        return result;
+    };
+    let convert = |cost: f64| rates.and_then(|r| r.convert(cost, currency, DISPLAY_CURRENCY));
+    if let Some(cost) = result.cost {
+        result.cost = convert(cost);
+        if result.cost.is_none() {
+            tracing::warn!("No exchange rate for {currency}, dropping provider cost");
+        }
+    }
+    for day in &mut result.daily {
+        day.cost = day.cost.and_then(convert);
+    }
+    result
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn rates() -> FxRates {
+        FxRates::new(HashMap::from([
+            ("CNY".to_string(), 7.2),
+            ("eur".to_string(), 0.9),
+            ("BAD".to_string(), 0.0),
+        ]))
+    }
+
+    #[test]
+    fn test_convert_between_currencies() {
+        let rates = rates();
+        let usd = rates
+            .convert(72.0, "CNY", "USD")
+            .expect("CNY should be known");
+        assert!((usd - 10.0).abs() < 1e-9);
+        let eur = rates
+            .convert(72.0, "cny", "EUR")
+            .expect("EUR should be known");
+        assert!((eur - 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_convert_unknown_or_invalid_currency() {
+        let rates = rates();
+        assert!(rates.convert(1.0, "XYZ", "USD").is_none());
+        assert!(rates.convert(1.0, "BAD", "USD").is_none());
+        assert_eq!(rates.convert(5.0, "XYZ", "xyz"), Some(5.0));
+    }
+
+    #[test]
+    fn test_normalize_provider_result() {
+        let result = ProviderUsageResult {
+            cost: Some(36.0),
+            tokens: Some(1000),
+            used: Some(5.0),
+            total: Some(10.0),
+            daily: Vec::new(),
+        };
+
+        let converted = normalize_provider_result(result.clone(), Some("CNY"), Some(&rates()));
+        assert!((converted.cost.expect("cost should convert") - 5.0).abs() < 1e-9);
+        assert_eq!(converted.used, Some(5.0));
+
+        let unchanged = normalize_provider_result(result.clone(), Some("usd"), None);
+        assert_eq!(unchanged.cost, Some(36.0));
+        let unchanged = normalize_provider_result(result.clone(), None, None);
+        assert_eq!(unchanged.cost, Some(36.0));
+
+        let dropped = normalize_provider_result(result, Some("CNY"), None);
+        assert!(dropped.cost.is_none());
+        assert_eq!(dropped.tokens, Some(1000));
+    }
+
+    #[test]
+    fn test_parse_rates_response() {
+        let json = r#"{"result":"success","base_code":"USD","rates":{"USD":1,"CNY":7.1}}"#;
+        let response: RatesResponse =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        assert_eq!(response.result, "success");
+        assert_eq!(response.rates.len(), 2);
+    }
+}
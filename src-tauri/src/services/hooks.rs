@@ -0,0 +1,162 @@
+use crate::config::RefreshHook;
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Shell syntax rejected in hook commands. Hooks run without a shell, so it
+/// would be passed through literally and almost certainly signals a mistake.
+const SHELL_PATTERNS: &[&str] = &[";", "&", "|", "`", "$(", "${", "\n", "\r", ">", "<"];
+
+/// Variables passed through from the app's environment; everything else is
+/// cleared so secrets in it don't leak into hooks.
+const PASSED_ENV_VARS: &[&str] = &["PATH", "HOME", "USER", "LANG", "TMPDIR"];
+
+/// Names the hook being run, for scripts shared by several hooks.
+const HOOK_NAME_VAR: &str = "TOKENMETER_HOOK";
+
+/// Longest excerpt of a failed hook's stderr kept in its error.
+const STDERR_EXCERPT: usize = 200;
+
+/// Splits a hook command into the program and its arguments, checking that it
+/// names an executable by absolute path and uses no shell syntax.
+///
+/// # Errors
+/// Returns an error describing why the command is rejected.
+pub fn parse_command(command: &str) -> Result<Vec<String>> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Err(anyhow::anyhow!("Hook command is empty"));
+    }
+    if let Some(pattern) = SHELL_PATTERNS.iter().find(|p| command.contains(*p)) {
+        return Err(anyhow::anyhow!(
+            "Hook command contains shell syntax '{}'; hooks run without a shell",
+            pattern.escape_debug()
+        ));
+    }
+    let parts = shlex::split(command)
+        .ok_or_else(|| anyhow::anyhow!("Hook command has unmatched quotes or escapes"))?;
+    if parts.is_empty() {
+        return Err(anyhow::anyhow!("Hook command is empty"));
+    }
+
+    let program = Path::new(&parts[0]);
+    if !program.is_absolute() {
+        return Err(anyhow::anyhow!(
+            "Hook command must start with the absolute path of a program"
+        ));
+    }
+    if !program.is_file() {
+        return Err(anyhow::anyhow!(
+            "Hook program not found: {}",
+            program.display()
+        ));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = program.metadata()?.permissions().mode();
+        if mode & 0o111 == 0 {
+            return Err(anyhow::anyhow!(
+                "Hook program is not executable: {}",
+                program.display()
+            ));
+        }
+    }
+    Ok(parts)
+}
+
+/// Runs `hook` with `summary` (JSON) on stdin, giving up after `timeout`.
+///
+/// # Errors
+/// Returns an error if the command is invalid, cannot start, times out or
+/// exits unsuccessfully; the error includes the start of its stderr.
+pub async fn run_hook(hook: &RefreshHook, summary: &[u8], timeout: Duration) -> Result<()> {
+    let parts = parse_command(&hook.command)?;
+    let env = std::env::vars().filter(|(key, _)| PASSED_ENV_VARS.contains(&key.as_str()));
+    let mut child = Command::new(&parts[0])
+        .args(&parts[1..])
+        .env_clear()
+        .envs(env)
+        .env(HOOK_NAME_VAR, &hook.name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start hook {}", hook.name))?;
+
+    let run = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            // Hooks that ignore stdin may exit before reading it
+            if let Err(e) = stdin.write_all(summary).await {
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    return Err(e);
+                }
+            }
+        }
+        child.wait_with_output().await
+    };
+    let output = tokio::time::timeout(timeout, run)
+        .await
+        .map_err(|_| anyhow::anyhow!("Hook timed out after {}s", timeout.as_secs()))??;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let excerpt: String = stderr.trim().chars().take(STDERR_EXCERPT).collect();
+        return Err(anyhow::anyhow!(
+            "Hook exited with {}: {excerpt}",
+            output.status
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command_validation() {
+        assert!(parse_command("  ").is_err());
+        // Only a comment, which splits into no arguments
+        assert!(parse_command("#sync").is_err());
+        assert!(parse_command("sync-usage --all").is_err());
+        assert!(parse_command("/bin/cat | tee /tmp/usage.json").is_err());
+        assert!(parse_command("/bin/cat > /tmp/usage.json").is_err());
+        assert!(parse_command("/bin/echo $(whoami)").is_err());
+        assert!(parse_command("/bin/echo 'unterminated").is_err());
+        assert!(parse_command("/definitely/not/a/program").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_command_splits_arguments() {
+        let parts = parse_command("/bin/sh -c 'exit 0'").unwrap();
+        assert_eq!(parts, ["/bin/sh", "-c", "exit 0"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_hook() {
+        let hook = |command: &str| RefreshHook {
+            name: "test".to_string(),
+            enabled: true,
+            command: command.to_string(),
+        };
+        let timeout = Duration::from_secs(5);
+        let summary = br#"{"today": {"cost": 1.5}}"#;
+
+        assert!(run_hook(&hook("/bin/cat"), summary, timeout).await.is_ok());
+        assert!(run_hook(&hook("/bin/sh -c 'exit 3'"), summary, timeout)
+            .await
+            .is_err());
+
+        let err = run_hook(&hook("/bin/ls /definitely-missing"), summary, timeout)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("definitely-missing"));
+    }
+}
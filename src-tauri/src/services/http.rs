@@ -0,0 +1,28 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const USER_AGENT: &str = "TokenMeter/1.0";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Returns the HTTP client shared by every outbound request, so connections
+/// are pooled and the user agent and connect timeout are set in one place.
+///
+/// The client has no overall timeout because the API proxy streams long
+/// responses; callers set one per request with `RequestBuilder::timeout`.
+/// System proxy settings (`HTTPS_PROXY` etc.) are honored.
+pub fn client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .build()
+            .unwrap_or_else(|e| {
+                tracing::error!("[HTTP] Failed to build configured client, using defaults: {e}");
+                reqwest::Client::new()
+            })
+    })
+}
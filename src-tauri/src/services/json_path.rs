@@ -0,0 +1,278 @@
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Result fields a provider's `JSONPath` mapping can set.
+pub const MAPPED_FIELDS: &[&str] = &["cost", "tokens", "used", "total"];
+
+/// Step of a `JSONPath` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `.name` or `['name']`
+    Child(String),
+    /// `[2]`, or `[-1]` counting from the end
+    Index(i64),
+    /// `.*` or `[*]`: every array element or object value
+    Wildcard,
+    /// `..name`: the field at any depth
+    Descendant(String),
+}
+
+/// Parses the supported subset of `JSONPath`: `$` followed by `.name`,
+/// `['name']`, `[index]`, `.*`, `[*]` and `..name`.
+fn parse(path: &str) -> Result<Vec<Segment>> {
+    let invalid = |reason: &str| anyhow::anyhow!("Invalid JSONPath '{path}': {reason}");
+    let Some(rest) = path.trim().strip_prefix('$') else {
+        return Err(invalid("must start with $"));
+    };
+
+    let mut segments = Vec::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let descendant = chars.next_if_eq(&'.').is_some();
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| *c != '.' && *c != '[') {
+                    name.push(c);
+                }
+                match (descendant, name.as_str()) {
+                    (_, "") => return Err(invalid("expected a field name after '.'")),
+                    (true, _) => segments.push(Segment::Descendant(name)),
+                    (false, "*") => segments.push(Segment::Wildcard),
+                    (false, _) => segments.push(Segment::Child(name)),
+                }
+            }
+            '[' => {
+                let mut inner = String::new();
+                let mut quote = None;
+                loop {
+                    let c = chars.next().ok_or_else(|| invalid("unclosed '['"))?;
+                    match (quote, c) {
+                        (None, ']') => break,
+                        (None, '\'' | '"') => quote = Some(c),
+                        (Some(q), c) if c == q => quote = None,
+                        _ => {}
+                    }
+                    inner.push(c);
+                }
+                let inner = inner.trim();
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|name| name.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"')?.strip_suffix('"'));
+                if let Some(name) = quoted {
+                    segments.push(Segment::Child(name.to_string()));
+                } else if inner == "*" {
+                    segments.push(Segment::Wildcard);
+                } else {
+                    let index = inner
+                        .parse()
+                        .map_err(|_| invalid("expected an index, '*' or a quoted name in '[]'"))?;
+                    segments.push(Segment::Index(index));
+                }
+            }
+            _ => return Err(invalid("expected '.' or '['")),
+        }
+    }
+    Ok(segments)
+}
+
+/// Every field named `name` in `value` or below it, in document order.
+fn descendants<'a>(value: &'a Value, name: &str, found: &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(field) = object.get(name) {
+                found.push(field);
+            }
+            for child in object.values() {
+                descendants(child, name, found);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                descendants(item, name, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Values `segments` select in `root`.
+fn select<'a>(root: &'a Value, segments: &[Segment]) -> Vec<&'a Value> {
+    let mut current = vec![root];
+    for segment in segments {
+        let mut next = Vec::new();
+        for value in current {
+            match (segment, value) {
+                (Segment::Child(name), Value::Object(object)) => next.extend(object.get(name)),
+                (Segment::Index(index), Value::Array(items)) => {
+                    let position = if *index < 0 {
+                        usize::try_from(index.unsigned_abs())
+                            .ok()
+                            .and_then(|back| items.len().checked_sub(back))
+                    } else {
+                        usize::try_from(*index).ok()
+                    };
+                    next.extend(position.and_then(|i| items.get(i)));
+                }
+                (Segment::Wildcard, Value::Array(items)) => next.extend(items),
+                (Segment::Wildcard, Value::Object(object)) => next.extend(object.values()),
+                (Segment::Descendant(name), value) => descendants(value, name, &mut next),
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+/// A matched number, or a string holding one as many APIs send amounts.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok().filter(|n: &f64| n.is_finite()),
+        _ => None,
+    }
+}
+
+/// `n` as JSON, whole numbers without a fraction so `tokens` stays an integer.
+#[allow(clippy::cast_possible_truncation)]
+fn number_value(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+        Value::from(n as i64)
+    } else {
+        Value::from(n)
+    }
+}
+
+/// Checks that `mapping` only sets [`MAPPED_FIELDS`] and that its paths parse.
+///
+/// # Errors
+/// Returns an error naming the first unknown field or invalid path.
+pub fn check_mapping(mapping: &BTreeMap<String, String>) -> Result<()> {
+    for (field, path) in mapping {
+        if !MAPPED_FIELDS.contains(&field.as_str()) {
+            return Err(anyhow::anyhow!(
+                "Unknown mapped field '{field}'; expected one of {}",
+                MAPPED_FIELDS.join(", ")
+            ));
+        }
+        parse(path).with_context(|| format!("Invalid path for {field}"))?;
+    }
+    Ok(())
+}
+
+/// Builds a provider result from `json` by evaluating each field's `JSONPath`.
+/// Paths matching several values (`$.data[*].cost`) add them up; fields whose
+/// path matches nothing are left null.
+///
+/// # Errors
+/// Returns an error if `json` is not JSON, the mapping is invalid, a path
+/// matches something other than a number, or no path matches at all.
+pub fn apply_mapping(mapping: &BTreeMap<String, String>, json: &str) -> Result<String> {
+    check_mapping(mapping)?;
+    let response: Value = serde_json::from_str(json).context("Response is not JSON")?;
+
+    let mut result = Map::new();
+    for (field, path) in mapping {
+        let mut total: Option<f64> = None;
+        for value in select(&response, &parse(path)?) {
+            if value.is_null() {
+                continue;
+            }
+            let n = number(value).ok_or_else(|| {
+                let found = match value {
+                    Value::Object(_) => "an object",
+                    Value::Array(_) => "an array",
+                    Value::Bool(_) => "a boolean",
+                    _ => "text",
+                };
+                anyhow::anyhow!("'{path}' for {field} matched {found}, not a number")
+            })?;
+            total = Some(total.unwrap_or(0.0) + n);
+        }
+        result.insert(field.clone(), total.map_or(Value::Null, number_value));
+    }
+
+    if result.values().all(Value::is_null) {
+        let paths: Vec<&str> = mapping.values().map(String::as_str).collect();
+        return Err(anyhow::anyhow!(
+            "None of the paths matched the response: {}",
+            paths.join(", ")
+        ));
+    }
+    Ok(Value::Object(result).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mapping(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(field, path)| ((*field).to_string(), (*path).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("$.data['total cost'][0].*..cost").unwrap(),
+            [
+                Segment::Child("data".to_string()),
+                Segment::Child("total cost".to_string()),
+                Segment::Index(0),
+                Segment::Wildcard,
+                Segment::Descendant("cost".to_string()),
+            ]
+        );
+        assert_eq!(parse(" $ ").unwrap(), []);
+        assert!(parse("data.total").is_err());
+        assert!(parse("$.").is_err());
+        assert!(parse("$.items[").is_err());
+        assert!(parse("$.items[first]").is_err());
+        assert!(parse("$total").is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let value = json!({
+            "data": {"items": [{"cost": 1}, {"cost": 2}, {"cost": 3, "nested": {"cost": 4}}]}
+        });
+        let select_path = |path: &str| select(&value, &parse(path).unwrap());
+        assert_eq!(select_path("$.data.items[-1].cost"), [&json!(3)]);
+        assert_eq!(select_path("$.data.items[*].cost").len(), 3);
+        assert_eq!(select_path("$..cost").len(), 4);
+        assert!(select_path("$.data.items[5]").is_empty());
+        assert!(select_path("$.data.missing.cost").is_empty());
+    }
+
+    #[test]
+    fn test_apply_mapping() {
+        let response = r#"{"data": {"total_cost": "12.50", "usage": [{"tokens": 1000},
+            {"tokens": 234}], "quota": null}}"#;
+        let result = apply_mapping(
+            &mapping(&[
+                ("cost", "$.data.total_cost"),
+                ("tokens", "$.data.usage[*].tokens"),
+                ("total", "$.data.quota"),
+            ]),
+            response,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&result).unwrap(),
+            json!({"cost": 12.5, "tokens": 1234, "total": null})
+        );
+
+        let err = apply_mapping(&mapping(&[("cost", "$.data")]), response).unwrap_err();
+        assert!(err.to_string().contains("not a number"));
+        let err = apply_mapping(&mapping(&[("cost", "$.missing")]), response).unwrap_err();
+        assert!(err.to_string().contains("None of the paths matched"));
+        assert!(apply_mapping(&mapping(&[("balance", "$.data")]), response).is_err());
+        assert!(apply_mapping(&mapping(&[("cost", "$.data")]), "not json").is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+
+/// Longest snippet of offending output written to the log.
+const SNIPPET_LENGTH: usize = 200;
+
+/// Expected shape of a JSON value, checked before deserializing external
+/// output so errors can name the offending path.
+#[derive(Debug)]
+pub enum Shape {
+    Any,
+    Bool,
+    Number,
+    /// Non-negative integer
+    Unsigned,
+    String,
+    Array(&'static Self),
+    /// Object with the listed fields; unknown fields are allowed.
+    Object(&'static [Field]),
+}
+
+#[derive(Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub shape: Shape,
+    pub required: bool,
+    pub nullable: bool,
+}
+
+impl Field {
+    /// A field that must be present and not null.
+    #[must_use]
+    pub const fn required(name: &'static str, shape: Shape) -> Self {
+        Self {
+            name,
+            shape,
+            required: true,
+            nullable: false,
+        }
+    }
+
+    /// A field that may be missing but not null (`#[serde(default)]`).
+    #[must_use]
+    pub const fn defaulted(name: &'static str, shape: Shape) -> Self {
+        Self {
+            name,
+            shape,
+            required: false,
+            nullable: false,
+        }
+    }
+
+    /// A field that may be missing or null (`Option<T>`).
+    #[must_use]
+    pub const fn optional(name: &'static str, shape: Shape) -> Self {
+        Self {
+            name,
+            shape,
+            required: false,
+            nullable: true,
+        }
+    }
+}
+
+/// Where and how a value failed its [`Shape`], e.g.
+/// "daily[3].totalCost: expected number, found string".
+#[derive(Debug, PartialEq, Eq)]
+pub struct SchemaError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+const fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn mismatch(path: &str, expected: &str, value: &Value) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        message: format!("expected {expected}, found {}", kind(value)),
+    }
+}
+
+fn check<'a>(value: &'a Value, shape: &Shape, path: &str) -> Result<(), (SchemaError, &'a Value)> {
+    match (shape, value) {
+        (Shape::Any, _)
+        | (Shape::Bool, Value::Bool(_))
+        | (Shape::Number, Value::Number(_))
+        | (Shape::String, Value::String(_)) => Ok(()),
+        (Shape::Unsigned, Value::Number(n)) if n.is_u64() => Ok(()),
+        (Shape::Array(item), Value::Array(items)) => {
+            for (i, element) in items.iter().enumerate() {
+                check(element, item, &format!("{path}[{i}]"))?;
+            }
+            Ok(())
+        }
+        (Shape::Object(fields), Value::Object(map)) => {
+            for field in *fields {
+                let field_path = if path.is_empty() {
+                    field.name.to_string()
+                } else {
+                    format!("{path}.{}", field.name)
+                };
+                match map.get(field.name) {
+                    None if field.required => {
+                        let error = SchemaError {
+                            path: field_path,
+                            message: "missing field".to_string(),
+                        };
+                        return Err((error, value));
+                    }
+                    None => {}
+                    Some(Value::Null) if field.nullable => {}
+                    Some(field_value) => check(field_value, &field.shape, &field_path)?,
+                }
+            }
+            Ok(())
+        }
+        (Shape::Unsigned, _) => Err((mismatch(path, "non-negative integer", value), value)),
+        (Shape::Bool, _) => Err((mismatch(path, "boolean", value), value)),
+        (Shape::Number, _) => Err((mismatch(path, "number", value), value)),
+        (Shape::String, _) => Err((mismatch(path, "string", value), value)),
+        (Shape::Array(_), _) => Err((mismatch(path, "array", value), value)),
+        (Shape::Object(_), _) => Err((mismatch(path, "object", value), value)),
+    }
+}
+
+/// Checks `value` against `shape`, reporting the first mismatch.
+///
+/// # Errors
+/// Returns the path and reason of the first value that does not fit.
+pub fn validate(value: &Value, shape: &Shape) -> Result<(), SchemaError> {
+    check(value, shape, "").map_err(|(e, _)| e)
+}
+
+/// At most [`SNIPPET_LENGTH`] characters of `text`, marked when cut.
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(SNIPPET_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// The text around a syntax error at `line` and `column` (both 1-based).
+fn snippet_at(text: &str, line: usize, column: usize) -> String {
+    let line_text = text.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+    let start = line_text
+        .char_indices()
+        .map(|(i, _)| i)
+        .take_while(|&i| i + SNIPPET_LENGTH / 2 < column)
+        .last()
+        .unwrap_or(0);
+    truncate(&line_text[start..])
+}
+
+/// Parses `text`, output of `source` (e.g. "ccusage"), as `T` after checking
+/// it against `shape`.
+///
+/// Errors name the offending path, e.g. "Invalid ccusage output:
+/// daily[3].totalCost: expected number, found string"; the offending snippet
+/// is logged, truncated, for debugging.
+///
+/// # Errors
+/// Returns an error if `text` is not JSON or does not fit `shape` or `T`.
+pub fn parse<T: DeserializeOwned>(text: &str, shape: &Shape, source: &str) -> Result<T> {
+    let value: Value = serde_json::from_str(text).map_err(|e| {
+        tracing::warn!(
+            "{source} returned invalid JSON ({e}) near: {}",
+            snippet_at(text, e.line(), e.column())
+        );
+        anyhow::anyhow!("Invalid {source} output: {e}")
+    })?;
+    if let Err((e, offending)) = check(&value, shape, "") {
+        tracing::warn!(
+            "{source} output does not match the expected schema ({e}): {}",
+            truncate(&offending.to_string())
+        );
+        return Err(anyhow::anyhow!("Invalid {source} output: {e}"));
+    }
+    serde_json::from_value(value).map_err(|e| anyhow::anyhow!("Invalid {source} output: {e}"))
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    static ENTRY: Shape = Shape::Object(&[
+        Field::required("date", Shape::String),
+        Field::required("totalCost", Shape::Number),
+        Field::optional("cacheTokens", Shape::Unsigned),
+        Field::defaulted("models", Shape::Array(&Shape::String)),
+    ]);
+    static REPORT: Shape = Shape::Object(&[Field::required("daily", Shape::Array(&ENTRY))]);
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Report {
+        daily: Vec<Entry>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Entry {
+        total_cost: f64,
+    }
+
+    fn error(json: &str) -> String {
+        validate(&serde_json::from_str(json).unwrap(), &REPORT)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let day = r#"{"date": "2025-01-01", "totalCost": 1}"#;
+        assert_eq!(
+            error(&format!(
+                r#"{{"daily": [{day}, {day}, {day}, {{"date": "x", "totalCost": "1.5"}}]}}"#
+            )),
+            "daily[3].totalCost: expected number, found string"
+        );
+        assert_eq!(
+            error(r#"{"daily": [{"totalCost": 1}]}"#),
+            "daily[0].date: missing field"
+        );
+        assert_eq!(
+            error(r#"{"daily": [{"date": "x", "totalCost": 1, "cacheTokens": -4}]}"#),
+            "daily[0].cacheTokens: expected non-negative integer, found number"
+        );
+        assert_eq!(
+            error(r#"{"daily": [{"date": "x", "totalCost": 1, "models": null}]}"#),
+            "daily[0].models: expected array, found null"
+        );
+        assert_eq!(error("[]"), "expected object, found array");
+
+        let ok = r#"{"daily": [{"date": "x", "totalCost": 2, "cacheTokens": null, "x": 1}]}"#;
+        assert!(validate(&serde_json::from_str(ok).unwrap(), &REPORT).is_ok());
+    }
+
+    #[test]
+    fn test_parse() {
+        let report: Report = parse(
+            r#"{"daily": [{"date": "x", "totalCost": 2.5}]}"#,
+            &REPORT,
+            "test",
+        )
+        .unwrap();
+        assert_eq!(report.daily.len(), 1);
+        assert_eq!(report.daily[0].total_cost, 2.5);
+
+        let err = parse::<Report>(r#"{"daily": {}}"#, &REPORT, "test").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid test output: daily: expected array, found object"
+        );
+        let err = parse::<Report>(r#"{"daily": [}"#, &REPORT, "test").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid test output: "));
+    }
+
+    #[test]
+    fn test_snippets_are_truncated() {
+        let long = "x".repeat(500);
+        assert_eq!(truncate(&long).chars().count(), SNIPPET_LENGTH + 1);
+        assert_eq!(truncate("short"), "short");
+        let snippet = snippet_at(&format!("{long}!{long}"), 1, 501);
+        assert!(snippet.contains('!'));
+        assert!(snippet.chars().count() <= SNIPPET_LENGTH + 1);
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Service name secrets are filed under in the macOS keychain / libsecret keyring.
+/// Secrets never touch `~/.tokenmeter`.
+const SERVICE: &str = "TokenMeter";
+
+/// Looks up the secret stored for `account`, returning `None` when absent.
+///
+/// # Errors
+/// Returns an error if the platform has no supported credential store.
+#[cfg(target_os = "macos")]
+pub async fn get_secret(account: &str) -> Result<Option<String>> {
+    let output = Command::new("/usr/bin/security")
+        .args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"])
+        .output()
+        .await
+        .context("Failed to run security")?;
+    Ok(output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string()
+    }))
+}
+
+/// Stores (or replaces) the secret for `account`.
+///
+/// Written through the Security framework rather than the `security` tool,
+/// whose only way to take a password is its argv, visible to every local user.
+///
+/// # Errors
+/// Returns an error if the credential store rejects the write.
+#[cfg(target_os = "macos")]
+pub async fn set_secret(account: &str, secret: &str) -> Result<()> {
+    let account = account.to_string();
+    let secret = secret.to_string();
+    tokio::task::spawn_blocking(move || {
+        security_framework::passwords::set_generic_password(SERVICE, &account, secret.as_bytes())
+    })
+    .await?
+    .context("Failed to store secret in the keychain")
+}
+
+/// Removes the secret for `account`. Missing entries are not an error.
+///
+/// # Errors
+/// Returns an error if the credential store can't be reached.
+#[cfg(target_os = "macos")]
+pub async fn delete_secret(account: &str) -> Result<()> {
+    Command::new("/usr/bin/security")
+        .args(["delete-generic-password", "-s", SERVICE, "-a", account])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .context("Failed to run security")?;
+    Ok(())
+}
+
+/// Looks up the secret stored for `account`, returning `None` when absent.
+///
+/// # Errors
+/// Returns an error if the platform has no supported credential store.
+#[cfg(target_os = "linux")]
+pub async fn get_secret(account: &str) -> Result<Option<String>> {
+    let output = Command::new("secret-tool")
+        .args(["lookup", "service", SERVICE, "account", account])
+        .output()
+        .await
+        .context("Failed to run secret-tool (is libsecret installed?)")?;
+    Ok(output
+        .status
+        .success()
+        .then(|| {
+            String::from_utf8_lossy(&output.stdout)
+                .trim_end()
+                .to_string()
+        })
+        .filter(|secret| !secret.is_empty()))
+}
+
+/// Stores (or replaces) the secret for `account`.
+///
+/// # Errors
+/// Returns an error if the credential store rejects the write.
+#[cfg(target_os = "linux")]
+pub async fn set_secret(account: &str, secret: &str) -> Result<()> {
+    let label = format!("{SERVICE} ({account})");
+    let mut child = Command::new("secret-tool")
+        .args([
+            "store", "--label", &label, "service", SERVICE, "account", account,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .context("Failed to run secret-tool (is libsecret installed?)")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(secret.as_bytes()).await?;
+    }
+    if !child.wait().await?.success() {
+        return Err(anyhow::anyhow!("Failed to store secret in the keyring"));
+    }
+    Ok(())
+}
+
+/// Removes the secret for `account`. Missing entries are not an error.
+///
+/// # Errors
+/// Returns an error if the credential store can't be reached.
+#[cfg(target_os = "linux")]
+pub async fn delete_secret(account: &str) -> Result<()> {
+    Command::new("secret-tool")
+        .args(["clear", "service", SERVICE, "account", account])
+        .status()
+        .await
+        .context("Failed to run secret-tool (is libsecret installed?)")?;
+    Ok(())
+}
+
+/// Looks up the secret stored for `account`, returning `None` when absent.
+///
+/// # Errors
+/// Returns an error if the platform has no supported credential store.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+#[allow(clippy::unused_async)]
+pub async fn get_secret(_account: &str) -> Result<Option<String>> {
+    Err(anyhow::anyhow!(
+        "Secure credential storage is not supported on this platform"
+    ))
+}
+
+/// Stores (or replaces) the secret for `account`.
+///
+/// # Errors
+/// Returns an error if the platform has no supported credential store.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+#[allow(clippy::unused_async)]
+pub async fn set_secret(_account: &str, _secret: &str) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "Secure credential storage is not supported on this platform"
+    ))
+}
+
+/// Removes the secret for `account`. Missing entries are not an error.
+///
+/// # Errors
+/// Returns an error if the platform has no supported credential store.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+#[allow(clippy::unused_async)]
+pub async fn delete_secret(_account: &str) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "Secure credential storage is not supported on this platform"
+    ))
+}
@@ -0,0 +1,127 @@
+use crate::config::{ClaudeCodeSourceConfig, LimitUnit, LimitsConfig};
+use crate::services::ccusage;
+use crate::services::claude_logs::{self, LogEntry};
+use crate::services::pricing::ModelPrice;
+use crate::types::LimitUsage;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Length of the rolling weekly limit window.
+const WEEK: chrono::TimeDelta = chrono::TimeDelta::days(7);
+
+const fn amount(entry: &LogEntry, unit: LimitUnit) -> u64 {
+    match unit {
+        LimitUnit::Tokens => entry.total_tokens(),
+        LimitUnit::Messages => 1,
+    }
+}
+
+fn used_since(entries: &[LogEntry], since: DateTime<Utc>, unit: LimitUnit) -> u64 {
+    entries
+        .iter()
+        .filter(|e| e.timestamp.with_timezone(&Utc) >= since)
+        .map(|e| amount(e, unit))
+        .sum()
+}
+
+/// Measures `entries` (sorted by time) against the configured limits: the
+/// active 5-hour block and the last seven days.
+#[must_use]
+pub fn measure(entries: &[LogEntry], config: &LimitsConfig, now: DateTime<Utc>) -> LimitUsage {
+    let active = ccusage::build_blocks(entries, None::<&HashMap<String, ModelPrice>>, now)
+        .into_iter()
+        .rfind(|block| block.is_active);
+    let (block_used, block_resets_at) = active
+        .and_then(|block| {
+            let start = DateTime::parse_from_rfc3339(&block.start_time).ok()?;
+            let used = used_since(entries, start.with_timezone(&Utc), config.unit);
+            Some((used, Some(block.end_time)))
+        })
+        .unwrap_or((0, None));
+
+    LimitUsage {
+        unit: config.unit,
+        block_used,
+        block_limit: config.block_limit,
+        block_resets_at,
+        weekly_used: used_since(entries, now - WEEK, config.unit),
+        weekly_limit: config.weekly_limit,
+    }
+}
+
+/// Reads the last week of Claude Code transcripts and measures them against
+/// the configured limits.
+#[tracing::instrument(name = "service.limits", skip_all)]
+pub async fn fetch_limits(source: &ClaudeCodeSourceConfig, config: &LimitsConfig) -> LimitUsage {
+    let now = Utc::now();
+    let since = (now - WEEK).with_timezone(&chrono::Local);
+    let data_dirs = source.data_dirs();
+    let entries = tokio::task::spawn_blocking(move || claude_logs::read_entries(since, &data_dirs))
+        .await
+        .unwrap_or_default();
+    measure(&entries, config, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(at: DateTime<Utc>, tokens: u64) -> LogEntry {
+        LogEntry {
+            timestamp: at.with_timezone(&chrono::Local),
+            model: "claude-opus-4-5".to_string(),
+            input_tokens: tokens,
+            output_tokens: 0,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            cost: None,
+        }
+    }
+
+    #[test]
+    fn test_measure_block_and_week() {
+        let now = DateTime::parse_from_rfc3339("2025-03-10T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let hours = |h: i64| now - chrono::TimeDelta::hours(h);
+        let entries = vec![
+            entry(hours(24 * 8), 1_000),
+            entry(hours(30), 200),
+            entry(hours(2), 50),
+            entry(hours(1), 25),
+        ];
+        let config = LimitsConfig {
+            enabled: true,
+            block_limit: 100,
+            weekly_limit: 1_000,
+            ..LimitsConfig::default()
+        };
+
+        let usage = measure(&entries, &config, now);
+        assert_eq!(usage.block_used, 75);
+        assert_eq!(usage.weekly_used, 275);
+        assert_eq!(usage.block_percent(), Some(75.0));
+        assert!(usage.block_resets_at.is_some());
+
+        let messages = measure(
+            &entries,
+            &LimitsConfig {
+                unit: LimitUnit::Messages,
+                ..config
+            },
+            now,
+        );
+        assert_eq!((messages.block_used, messages.weekly_used), (2, 3));
+    }
+
+    #[test]
+    fn test_measure_without_active_block() {
+        let now = Utc::now();
+        let entries = vec![entry(now - chrono::TimeDelta::hours(6), 10)];
+        let usage = measure(&entries, &LimitsConfig::default(), now);
+        assert_eq!(usage.block_used, 0);
+        assert!(usage.block_resets_at.is_none());
+        assert_eq!(usage.block_percent(), None);
+        assert_eq!(usage.weekly_used, 10);
+    }
+}
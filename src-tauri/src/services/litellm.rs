@@ -0,0 +1,207 @@
+use crate::config::LiteLlmSourceConfig;
+use crate::services::http;
+use crate::services::sources::{self, SourceReport, UsageRecord};
+use crate::types::ProviderUsageResult;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub const SOURCE_ID: &str = "litellm";
+const SOURCE_LABEL: &str = "LiteLLM";
+
+/// One day of `/spend/logs` when queried with a date range.
+#[derive(Debug, Deserialize)]
+struct SpendDay {
+    /// `YYYY-MM-DD`, optionally followed by a time.
+    #[serde(rename = "startTime")]
+    start_time: String,
+    #[serde(default)]
+    spend: f64,
+    /// Spend per model in USD.
+    #[serde(default)]
+    models: BTreeMap<String, f64>,
+    /// Spend per user ID in USD; requests without a user are keyed by "".
+    #[serde(default)]
+    users: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GlobalSpend {
+    #[serde(default)]
+    spend: f64,
+    #[serde(default)]
+    max_budget: Option<f64>,
+}
+
+/// Per-model spend for the summary plus per-user spend for the key breakdown.
+/// `LiteLLM` only reports tokens on individual logs, so costs come without them.
+fn spend_records(days: Vec<SpendDay>) -> Vec<UsageRecord> {
+    let mut records = Vec::new();
+    for day in days {
+        let Some(date) = day.start_time.get(..10).map(str::to_string) else {
+            continue;
+        };
+        let models_cost: f64 = day.models.values().sum();
+        records.extend(day.models.into_iter().map(|(model, cost)| UsageRecord {
+            date: date.clone(),
+            model,
+            cost,
+            ..UsageRecord::default()
+        }));
+        // Spend the proxy could not attribute to a model
+        if day.spend - models_cost > 1e-9 {
+            records.push(UsageRecord {
+                date: date.clone(),
+                model: "unknown".to_string(),
+                cost: day.spend - models_cost,
+                ..UsageRecord::default()
+            });
+        }
+        records.extend(
+            day.users
+                .into_iter()
+                .filter(|(user, _)| !user.is_empty())
+                .map(|(user, cost)| UsageRecord {
+                    date: date.clone(),
+                    model: "unknown".to_string(),
+                    cost,
+                    key: Some(user),
+                    ..UsageRecord::default()
+                }),
+        );
+    }
+    records
+}
+
+fn credits_result(global: &GlobalSpend) -> Option<ProviderUsageResult> {
+    global
+        .max_budget
+        .filter(|budget| *budget > 0.0)
+        .map(|budget| ProviderUsageResult {
+            cost: None,
+            tokens: None,
+            used: Some(global.spend),
+            total: Some(budget),
+            daily: Vec::new(),
+        })
+}
+
+async fn get_json<T: DeserializeOwned>(
+    url: String,
+    api_key: Option<&str>,
+    query: &[(&str, String)],
+    timeout: Duration,
+) -> Result<T> {
+    let mut request = http::client().get(url).timeout(timeout).query(query);
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    Ok(request.send().await?.error_for_status()?.json().await?)
+}
+
+/// Fetches the last 30 days of spend from a `LiteLLM` proxy.
+///
+/// Daily spend per model and per user comes from `/spend/logs`; when the proxy
+/// has a global budget, `/global/spend` is reported as the credit balance. A
+/// failing budget request is logged and leaves the balance unset.
+///
+/// # Errors
+/// Returns an error if no base URL is configured, the spend request fails,
+/// or its response cannot be parsed.
+#[tracing::instrument(name = "source.litellm", skip_all)]
+pub async fn fetch_usage(config: &LiteLlmSourceConfig, timeout: Duration) -> Result<SourceReport> {
+    let base_url = config.base_url.trim().trim_end_matches('/');
+    if base_url.is_empty() {
+        anyhow::bail!("LiteLLM base URL is not configured");
+    }
+    let api_key = config
+        .api_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty());
+
+    let today = chrono::Local::now().date_naive();
+    let start = today - chrono::Duration::days(sources::SUMMARY_WINDOW_DAYS - 1);
+    let query = [
+        ("start_date", start.format("%Y-%m-%d").to_string()),
+        (
+            "end_date",
+            (today + chrono::Duration::days(1))
+                .format("%Y-%m-%d")
+                .to_string(),
+        ),
+    ];
+
+    let (days, global) = tokio::join!(
+        get_json::<Vec<SpendDay>>(format!("{base_url}/spend/logs"), api_key, &query, timeout),
+        get_json::<GlobalSpend>(format!("{base_url}/global/spend"), api_key, &[], timeout),
+    );
+
+    let credits = global
+        .inspect_err(|e| tracing::warn!("Failed to fetch LiteLLM global spend: {e}"))
+        .ok()
+        .and_then(|global| credits_result(&global));
+
+    let records = spend_records(days?);
+    Ok(SourceReport {
+        id: SOURCE_ID.to_string(),
+        label: SOURCE_LABEL.to_string(),
+        daily: sources::aggregate_records(records.iter().filter(|r| r.key.is_none()).cloned()),
+        keys: sources::aggregate_records_by_key(&records),
+        credits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spend_logs() {
+        let json = r#"[
+            {
+                "startTime": "2025-03-01",
+                "spend": 1.5,
+                "models": { "gpt-4o": 1.0, "claude-3-5-sonnet": 0.25 },
+                "users": { "alice": 1.0, "": 0.5 }
+            },
+            {
+                "startTime": "2025-03-02T00:00:00",
+                "spend": 0.4,
+                "models": { "gpt-4o": 0.4 },
+                "users": { "bob": 0.4 }
+            }
+        ]"#;
+
+        let days: Vec<SpendDay> =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        let records = spend_records(days);
+        let daily = sources::aggregate_records(records.iter().filter(|r| r.key.is_none()).cloned());
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].date, "2025-03-01");
+        assert!((daily[0].cost - 1.5).abs() < 1e-9);
+        assert!(daily[0].models.iter().any(|m| m.model == "unknown"));
+        assert_eq!(daily[1].date, "2025-03-02");
+
+        let keys = sources::aggregate_records_by_key(&records);
+        assert_eq!(keys.keys().collect::<Vec<_>>(), ["alice", "bob"]);
+        assert!((keys["alice"][0].cost - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_global_spend_credits() {
+        let with_budget: GlobalSpend =
+            serde_json::from_str(r#"{ "spend": 12.5, "max_budget": 50.0 }"#)
+                .expect("test JSON should parse correctly");
+        let credits = credits_result(&with_budget).expect("budget set");
+        assert_eq!(credits.used, Some(12.5));
+        assert_eq!(credits.total, Some(50.0));
+
+        let unlimited: GlobalSpend =
+            serde_json::from_str(r#"{ "spend": 12.5, "max_budget": null }"#)
+                .expect("test JSON should parse correctly");
+        assert!(credits_result(&unlimited).is_none());
+    }
+}
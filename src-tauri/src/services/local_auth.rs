@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+#[cfg(target_os = "linux")]
+use tokio::process::Command;
+
+/// Starts the `LocalAuthentication` prompt; `done` receives whether the user
+/// passed it. Kept out of [`authenticate`] so no Objective-C object is held
+/// across an await.
+#[cfg(target_os = "macos")]
+fn start_evaluation(reason: &str, done: tokio::sync::oneshot::Sender<bool>) {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    let done = std::sync::Mutex::new(Some(done));
+    // SAFETY: the reply block owns everything it touches, and
+    // LocalAuthentication calls it exactly once, on a private queue.
+    unsafe {
+        let context = LAContext::new();
+        let reply_context = context.clone();
+        let reply = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+            if let Some(done) = done.lock().ok().and_then(|mut done| done.take()) {
+                let _ = done.send(success.as_bool());
+            }
+            reply_context.invalidate();
+        });
+        context.evaluatePolicy_localizedReason_reply(
+            LAPolicy::DeviceOwnerAuthentication,
+            &NSString::from_str(reason),
+            &reply,
+        );
+    }
+}
+
+/// Asks the user to authenticate with Touch ID or their account password,
+/// showing `reason` (e.g. "open the dashboard") in the prompt. Returns whether
+/// they did; cancelling the prompt counts as a failure.
+///
+/// # Errors
+/// Returns an error if the system authentication prompt cannot be shown.
+#[cfg(target_os = "macos")]
+pub async fn authenticate(reason: &str) -> Result<bool> {
+    let (done, result) = tokio::sync::oneshot::channel();
+    start_evaluation(reason, done);
+    result
+        .await
+        .context("System authentication ended without a result")
+}
+
+/// Asks the user to authenticate through polkit, which shows the desktop's
+/// password prompt. Returns whether they did; dismissing the prompt counts as
+/// a failure.
+///
+/// # Errors
+/// Returns an error if `pkcheck` cannot be run.
+#[cfg(target_os = "linux")]
+pub async fn authenticate(_reason: &str) -> Result<bool> {
+    let status = Command::new("pkcheck")
+        .args([
+            "--action-id",
+            "org.freedesktop.policykit.exec",
+            "--process",
+            &std::process::id().to_string(),
+            "--allow-user-interaction",
+        ])
+        .status()
+        .await
+        .context("Failed to run pkcheck (is polkit installed?)")?;
+    Ok(status.success())
+}
+
+/// Asks the user to authenticate with the operating system.
+///
+/// # Errors
+/// Always; the platform has no supported authentication prompt.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+#[allow(clippy::unused_async)]
+pub async fn authenticate(_reason: &str) -> Result<bool> {
+    Err(anyhow::anyhow!(
+        "System authentication is not supported on this platform"
+    ))
+}
@@ -0,0 +1,309 @@
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::timeout;
+
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+const READ_CHUNK_BYTES: usize = 8 * 1024;
+/// Each of the headers and the body must arrive within this, so a client
+/// can't hold a connection open by sending slowly or not at all.
+pub const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Minimal HTTP/1.1 request used by the localhost listeners.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Returns the first header value matching `name` (case-insensitive).
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn find_header_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4).position(|w| w == b"\r\n\r\n")
+}
+
+/// Parses the request line and headers (everything before the blank line).
+fn parse_head(head: &str) -> Result<HttpRequest> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(anyhow::anyhow!("Malformed request line: '{request_line}'"));
+    };
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    Ok(HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body: Vec::new(),
+    })
+}
+
+/// Reads the request line and headers. `body` holds whatever part of the body
+/// arrived with them; [`read_body`] reads the rest.
+///
+/// # Errors
+/// Returns an error if the connection closes early, the headers exceed
+/// their limit or don't arrive within [`READ_TIMEOUT`].
+pub async fn read_head<R: AsyncRead + Unpin>(reader: &mut R) -> Result<HttpRequest> {
+    let mut buf = Vec::with_capacity(READ_CHUNK_BYTES);
+    let mut chunk = vec![0u8; READ_CHUNK_BYTES];
+
+    let header_end = timeout(READ_TIMEOUT, async {
+        loop {
+            if let Some(pos) = find_header_end(&buf) {
+                return Ok(pos);
+            }
+            if buf.len() > MAX_HEADER_BYTES {
+                return Err(anyhow::anyhow!(
+                    "Request headers exceed {MAX_HEADER_BYTES} bytes"
+                ));
+            }
+            let n = reader.read(&mut chunk).await?;
+            if n == 0 {
+                return Err(anyhow::anyhow!(
+                    "Connection closed before headers were complete"
+                ));
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("Timed out reading request headers"))??;
+
+    let mut request = parse_head(std::str::from_utf8(&buf[..header_end])?)?;
+    request.body = buf.split_off(header_end + 4);
+    Ok(request)
+}
+
+/// Reads the rest of `request`'s `Content-Length` body, up to `max_bytes`.
+///
+/// # Errors
+/// Returns an error if the body is larger than `max_bytes`, uses chunked
+/// transfer encoding, or doesn't arrive completely within [`READ_TIMEOUT`].
+pub async fn read_body<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    request: &mut HttpRequest,
+    max_bytes: usize,
+) -> Result<()> {
+    if request
+        .header("transfer-encoding")
+        .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"))
+    {
+        return Err(anyhow::anyhow!("Chunked request bodies are not supported"));
+    }
+    let content_length: usize = request
+        .header("content-length")
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| anyhow::anyhow!("Invalid Content-Length header"))?
+        .unwrap_or(0);
+    if content_length > max_bytes {
+        return Err(anyhow::anyhow!("Request body exceeds {max_bytes} bytes"));
+    }
+
+    let body = &mut request.body;
+    let mut chunk = vec![0u8; READ_CHUNK_BYTES];
+    timeout(READ_TIMEOUT, async {
+        while body.len() < content_length {
+            let n = reader.read(&mut chunk).await?;
+            if n == 0 {
+                return Err(anyhow::anyhow!(
+                    "Connection closed before body was complete"
+                ));
+            }
+            body.extend_from_slice(&chunk[..n]);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("Timed out reading request body"))??;
+    body.truncate(content_length);
+    Ok(())
+}
+
+/// Reads a single HTTP/1.1 request with a `Content-Length` body.
+///
+/// # Errors
+/// Returns an error if the connection closes early, limits are exceeded,
+/// the request is too slow, or it uses chunked transfer encoding.
+pub async fn read_request<R: AsyncRead + Unpin>(reader: &mut R) -> Result<HttpRequest> {
+    let mut request = read_head(reader).await?;
+    read_body(reader, &mut request, MAX_BODY_BYTES).await?;
+    Ok(request)
+}
+
+fn status_line(status: u16) -> String {
+    let reason = reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("");
+    format!("HTTP/1.1 {status} {reason}\r\n")
+}
+
+/// Writes a complete response with a fixed-length body and closes the exchange.
+///
+/// # Errors
+/// Returns an error if writing to the socket fails.
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+) -> Result<()> {
+    let head = format!(
+        "{}Content-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status_line(status),
+        body.len()
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Writes a JSON response.
+///
+/// # Errors
+/// Returns an error if writing to the socket fails.
+pub async fn write_json<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: u16,
+    value: &serde_json::Value,
+) -> Result<()> {
+    write_response(
+        writer,
+        status,
+        "application/json",
+        value.to_string().as_bytes(),
+    )
+    .await
+}
+
+/// Writes the status line and headers for a chunked (streaming) response.
+///
+/// # Errors
+/// Returns an error if writing to the socket fails.
+pub async fn write_chunked_head<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: u16,
+    headers: &[(String, String)],
+) -> Result<()> {
+    let mut head = status_line(status);
+    for (name, value) in headers {
+        let _ = write!(head, "{name}: {value}\r\n");
+    }
+    head.push_str("Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n");
+    writer.write_all(head.as_bytes()).await?;
+    Ok(())
+}
+
+/// Writes one chunk of a chunked response body.
+///
+/// # Errors
+/// Returns an error if writing to the socket fails.
+pub async fn write_chunk<W: AsyncWrite + Unpin>(writer: &mut W, data: &[u8]) -> Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    writer
+        .write_all(format!("{:x}\r\n", data.len()).as_bytes())
+        .await?;
+    writer.write_all(data).await?;
+    writer.write_all(b"\r\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Terminates a chunked response body.
+///
+/// # Errors
+/// Returns an error if writing to the socket fails.
+pub async fn finish_chunks<W: AsyncWrite + Unpin>(writer: &mut W) -> Result<()> {
+    writer.write_all(b"0\r\n\r\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request_with_body() {
+        let raw = b"POST /v1/messages HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\nX-Api-Key: sk-test\r\n\r\nhello";
+        let mut reader = &raw[..];
+        let request = read_request(&mut reader)
+            .await
+            .expect("request should parse");
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/v1/messages");
+        assert_eq!(request.header("x-api-key"), Some("sk-test"));
+        assert_eq!(request.body, b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_read_request_rejects_truncated_body() {
+        let raw = b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort";
+        let mut reader = &raw[..];
+        assert!(read_request(&mut reader).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_body_limit() {
+        let raw = b"POST / HTTP/1.1\r\nContent-Length: 6\r\n\r\nhello!";
+        let mut reader = &raw[..];
+        let mut request = read_head(&mut reader).await.expect("head should parse");
+        assert!(read_body(&mut reader, &mut request, 5).await.is_err());
+
+        let mut reader = &raw[..];
+        let mut request = read_head(&mut reader).await.expect("head should parse");
+        read_body(&mut reader, &mut request, 6)
+            .await
+            .expect("body should fit");
+        assert_eq!(request.body, b"hello!");
+    }
+
+    #[test]
+    fn test_parse_head_malformed() {
+        assert!(parse_head("").is_err());
+        assert!(parse_head("GET").is_err());
+        assert!(parse_head("GET / HTTP/1.1").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_write_chunked_response() {
+        let mut out = Vec::new();
+        write_chunked_head(&mut out, 200, &[("X-Test".to_string(), "1".to_string())])
+            .await
+            .expect("head should be written");
+        write_chunk(&mut out, b"abc")
+            .await
+            .expect("chunk should be written");
+        finish_chunks(&mut out)
+            .await
+            .expect("trailer should be written");
+        let text = String::from_utf8(out).expect("response should be UTF-8");
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("X-Test: 1\r\n"));
+        assert!(text.ends_with("3\r\nabc\r\n0\r\n\r\n"));
+    }
+}
@@ -1,4 +1,37 @@
+pub mod agent_link;
 pub mod ccusage;
+pub mod claude_logs;
+pub mod copilot;
+pub mod credentials;
+pub mod cursor;
+pub mod custom_jsonl;
+pub mod digest;
+pub mod fx;
+pub mod hooks;
+pub mod http;
+pub mod json_path;
+pub mod json_schema;
+pub mod keychain;
+pub mod limits;
+pub mod litellm;
+pub mod local_auth;
+pub mod local_http;
+pub mod notion;
+pub mod oauth;
+pub mod ollama;
+pub mod openai;
+pub mod openrouter;
 pub mod pricing;
+pub mod provider_runner;
+pub mod proxy;
+pub mod push;
+pub mod remote_snapshot;
 pub mod script_runner;
+pub mod series;
 pub mod shell_utils;
+pub mod simulator;
+pub mod sources;
+pub mod support_bundle;
+pub mod time_profile;
+pub mod trends;
+pub mod windsurf;
@@ -0,0 +1,269 @@
+use crate::config::NotionIntegrationConfig;
+use crate::services::http;
+use crate::types::DailyUsage;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+const NOTION_API_URL: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+/// Notion allows an average of three requests per second per integration.
+const REQUEST_INTERVAL: Duration = Duration::from_millis(350);
+/// Number of most recent days re-checked on every sync.
+const SYNC_DAYS: usize = 7;
+
+const COST_PROPERTY: &str = "Cost";
+const TOKENS_PROPERTY: &str = "Tokens";
+const TOP_MODEL_PROPERTY: &str = "Top Model";
+
+/// Page ids and last-written values per date, so unchanged rows aren't re-sent.
+#[derive(Default)]
+struct SyncCache {
+    database_id: String,
+    page_ids: HashMap<String, String>,
+    synced: HashMap<String, String>,
+}
+
+static SYNC_CACHE: OnceLock<Mutex<SyncCache>> = OnceLock::new();
+
+fn get_cache() -> &'static Mutex<SyncCache> {
+    SYNC_CACHE.get_or_init(|| Mutex::new(SyncCache::default()))
+}
+
+/// One database row derived from a day of usage.
+#[derive(Debug, Clone, PartialEq)]
+struct DailyRow {
+    date: String,
+    cost: f64,
+    tokens: u64,
+    top_model: String,
+}
+
+impl DailyRow {
+    fn from_day(day: &DailyUsage) -> Self {
+        let top_model = day
+            .models
+            .iter()
+            .max_by(|a, b| a.cost.total_cmp(&b.cost))
+            .map(|m| m.model.clone())
+            .unwrap_or_default();
+        Self {
+            date: day.date.clone(),
+            cost: (day.cost * 100.0).round() / 100.0,
+            tokens: day.input_tokens
+                + day.output_tokens
+                + day.cache_creation_input_tokens
+                + day.cache_read_input_tokens,
+            top_model,
+        }
+    }
+
+    /// Value compared against the cache to decide whether the row changed.
+    fn fingerprint(&self) -> String {
+        format!("{:.2}|{}|{}", self.cost, self.tokens, self.top_model)
+    }
+
+    fn properties(&self, title_property: &str) -> Value {
+        json!({
+            title_property: { "title": [{ "text": { "content": self.date } }] },
+            COST_PROPERTY: { "number": self.cost },
+            TOKENS_PROPERTY: { "number": self.tokens },
+            TOP_MODEL_PROPERTY: { "rich_text": [{ "text": { "content": self.top_model } }] },
+        })
+    }
+}
+
+/// Rows for the most recent [`SYNC_DAYS`] days, newest last.
+fn recent_rows(daily: &[DailyUsage]) -> Vec<DailyRow> {
+    let mut days: Vec<&DailyUsage> = daily.iter().collect();
+    days.sort_by(|a, b| a.date.cmp(&b.date));
+    let start = days.len().saturating_sub(SYNC_DAYS);
+    days[start..]
+        .iter()
+        .map(|d| DailyRow::from_day(d))
+        .collect()
+}
+
+struct NotionClient<'a> {
+    http: &'static reqwest::Client,
+    timeout: Duration,
+    config: &'a NotionIntegrationConfig,
+}
+
+impl NotionClient<'_> {
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        tokio::time::sleep(REQUEST_INTERVAL).await;
+        let response = request
+            .timeout(self.timeout)
+            .bearer_auth(self.config.token.trim())
+            .header("Notion-Version", NOTION_VERSION)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = body
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            return Err(anyhow::anyhow!("Notion API returned {status}: {message}"));
+        }
+        Ok(body)
+    }
+
+    async fn find_page(&self, date: &str) -> Result<Option<String>> {
+        let url = format!(
+            "{NOTION_API_URL}/databases/{}/query",
+            self.config.database_id.trim()
+        );
+        let body = json!({
+            "filter": { "property": self.config.title_property, "title": { "equals": date } },
+            "page_size": 1,
+        });
+        let response = self.send(self.http.post(url).json(&body)).await?;
+        Ok(response["results"]
+            .get(0)
+            .and_then(|page| page["id"].as_str())
+            .map(str::to_string))
+    }
+
+    async fn upsert(&self, row: &DailyRow, page_id: Option<&str>) -> Result<String> {
+        let properties = row.properties(&self.config.title_property);
+        let request = page_id.map_or_else(
+            || {
+                self.http
+                    .post(format!("{NOTION_API_URL}/pages"))
+                    .json(&json!({
+                        "parent": { "database_id": self.config.database_id.trim() },
+                        "properties": properties,
+                    }))
+            },
+            |id| {
+                self.http
+                    .patch(format!("{NOTION_API_URL}/pages/{id}"))
+                    .json(&json!({ "properties": properties }))
+            },
+        );
+        let response = self.send(request).await?;
+        response["id"]
+            .as_str()
+            .map(str::to_string)
+            .context("Notion response is missing the page id")
+    }
+}
+
+/// Upserts the most recent days of usage into the configured Notion database.
+///
+/// Rows already written with the same values are skipped, and requests are spaced
+/// to stay under Notion's rate limit.
+///
+/// # Errors
+/// Returns an error if the integration is misconfigured or a Notion request fails.
+#[tracing::instrument(name = "service.notion", skip_all)]
+pub async fn sync_daily_usage(
+    config: &NotionIntegrationConfig,
+    daily: &[DailyUsage],
+    timeout: Duration,
+) -> Result<()> {
+    if config.token.trim().is_empty() || config.database_id.trim().is_empty() {
+        return Err(anyhow::anyhow!("Notion token and database ID are required"));
+    }
+
+    let client = NotionClient {
+        http: http::client(),
+        timeout,
+        config,
+    };
+
+    // Held for the whole sync so overlapping refreshes don't create duplicate rows
+    let mut cache = get_cache().lock().await;
+    if cache.database_id != config.database_id {
+        *cache = SyncCache {
+            database_id: config.database_id.clone(),
+            ..SyncCache::default()
+        };
+    }
+
+    for row in recent_rows(daily) {
+        let fingerprint = row.fingerprint();
+        if cache.synced.get(&row.date) == Some(&fingerprint) {
+            continue;
+        }
+
+        let page_id = match cache.page_ids.get(&row.date) {
+            Some(id) => Some(id.clone()),
+            None => client.find_page(&row.date).await?,
+        };
+        let page_id = client.upsert(&row, page_id.as_deref()).await?;
+        cache.page_ids.insert(row.date.clone(), page_id);
+        cache.synced.insert(row.date, fingerprint);
+    }
+    drop(cache);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ModelUsage;
+
+    fn day(date: &str, cost: f64, models: &[(&str, f64)]) -> DailyUsage {
+        DailyUsage {
+            date: date.to_string(),
+            cost,
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_creation_input_tokens: 10,
+            cache_read_input_tokens: 5,
+            models: models
+                .iter()
+                .map(|(model, cost)| ModelUsage {
+                    model: (*model).to_string(),
+                    cost: *cost,
+                    input_tokens: 0,
+                    output_tokens: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_daily_row_from_day() {
+        let row = DailyRow::from_day(&day(
+            "2025-01-02",
+            3.456,
+            &[("claude-haiku-4-5", 0.4), ("claude-opus-4-5", 3.0)],
+        ));
+        assert_eq!(row.top_model, "claude-opus-4-5");
+        assert_eq!(row.tokens, 165);
+        assert!((row.cost - 3.46).abs() < 1e-9);
+
+        let properties = row.properties("Day");
+        assert_eq!(
+            properties["Day"]["title"][0]["text"]["content"],
+            "2025-01-02"
+        );
+        assert_eq!(properties["Tokens"]["number"], 165);
+        assert_eq!(
+            properties["Top Model"]["rich_text"][0]["text"]["content"],
+            "claude-opus-4-5"
+        );
+    }
+
+    #[test]
+    fn test_recent_rows_keeps_latest_days() {
+        let daily: Vec<DailyUsage> = (1..=10)
+            .rev()
+            .map(|d| day(&format!("2025-01-{d:02}"), 1.0, &[]))
+            .collect();
+        let rows = recent_rows(&daily);
+        assert_eq!(rows.len(), SYNC_DAYS);
+        assert_eq!(rows[0].date, "2025-01-04");
+        assert_eq!(rows[SYNC_DAYS - 1].date, "2025-01-10");
+        assert!(rows[0].top_model.is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::config::{OAuthGrant, ProviderOAuth};
+use crate::services::{http, keychain, shell_utils};
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Env var the access token is exposed to the provider fetch as.
+pub const ACCESS_TOKEN_VAR: &str = "OAUTH_ACCESS_TOKEN";
+/// Lifetime assumed when the token endpoint does not return `expires_in`.
+const DEFAULT_EXPIRES_IN: u64 = 3600;
+/// Tokens are renewed this long before they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_mins(1);
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// Access tokens keyed by provider id, with when they stop being used.
+static TOKEN_CACHE: OnceLock<Mutex<HashMap<String, (String, Instant)>>> = OnceLock::new();
+
+fn get_cache() -> &'static Mutex<HashMap<String, (String, Instant)>> {
+    TOKEN_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Keychain account holding the latest rotated refresh token of a provider.
+fn refresh_token_account(provider_id: &str) -> String {
+    format!("provider-oauth:{provider_id}")
+}
+
+/// When a token that lives `expires_in` seconds from `now` should be renewed.
+fn renew_at(now: Instant, expires_in: Option<u64>) -> Instant {
+    let lifetime = Duration::from_secs(expires_in.unwrap_or(DEFAULT_EXPIRES_IN));
+    now + lifetime.saturating_sub(EXPIRY_MARGIN)
+}
+
+/// Returns a valid access token for `provider_id`, requesting a new one from
+/// the token endpoint when the cached token is missing or about to expire.
+///
+/// # Errors
+/// Returns an error if the token request fails or returns no access token.
+#[allow(clippy::implicit_hasher)]
+// The cache stays locked during the request so a rotated refresh token is
+// never redeemed twice by concurrent fetches
+#[allow(clippy::significant_drop_tightening)]
+#[tracing::instrument(name = "service.oauth", skip_all)]
+pub async fn access_token(
+    provider_id: &str,
+    oauth: &ProviderOAuth,
+    env: &HashMap<String, String>,
+    timeout: Duration,
+) -> Result<String> {
+    let mut cache = get_cache().lock().await;
+    if let Some((token, renew)) = cache.get(provider_id) {
+        if Instant::now() < *renew {
+            return Ok(token.clone());
+        }
+    }
+
+    let account = refresh_token_account(provider_id);
+    let mut form = vec![(
+        "client_id",
+        shell_utils::substitute_env_vars(&oauth.client_id, env),
+    )];
+    let client_secret = shell_utils::substitute_env_vars(&oauth.client_secret, env);
+    if !client_secret.is_empty() {
+        form.push(("client_secret", client_secret));
+    }
+    if !oauth.scopes.is_empty() {
+        form.push(("scope", oauth.scopes.join(" ")));
+    }
+    match oauth.grant {
+        OAuthGrant::ClientCredentials => form.push(("grant_type", "client_credentials".into())),
+        OAuthGrant::RefreshToken => {
+            let refresh_token = match keychain::get_secret(&account).await {
+                Ok(Some(rotated)) => rotated,
+                _ => shell_utils::substitute_env_vars(&oauth.refresh_token, env),
+            };
+            form.push(("grant_type", "refresh_token".into()));
+            form.push(("refresh_token", refresh_token));
+        }
+    }
+
+    let response = http::client()
+        .post(shell_utils::substitute_env_vars(&oauth.token_url, env))
+        .timeout(timeout)
+        .form(&form)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "Token request failed: HTTP {status}: {body}"
+        ));
+    }
+    let token: TokenResponse = response.json().await?;
+
+    if oauth.grant == OAuthGrant::RefreshToken {
+        if let Some(rotated) = &token.refresh_token {
+            if let Err(e) = keychain::set_secret(&account, rotated).await {
+                tracing::warn!("Failed to store rotated refresh token: {e}");
+            }
+        }
+    }
+
+    cache.insert(
+        provider_id.to_string(),
+        (
+            token.access_token.clone(),
+            renew_at(Instant::now(), token.expires_in),
+        ),
+    );
+    Ok(token.access_token)
+}
+
+/// Drops the cached access token and rotated refresh token of `provider_id`,
+/// so edited OAuth settings take effect on the next fetch.
+pub async fn forget(provider_id: &str) {
+    get_cache().lock().await.remove(provider_id);
+    if let Err(e) = keychain::delete_secret(&refresh_token_account(provider_id)).await {
+        tracing::warn!("Failed to remove stored refresh token: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_response_parsing() {
+        let token: TokenResponse =
+            serde_json::from_str(r#"{"access_token":"abc","token_type":"Bearer"}"#).unwrap();
+        assert_eq!(token.access_token, "abc");
+        assert!(token.expires_in.is_none());
+        assert!(token.refresh_token.is_none());
+
+        let token: TokenResponse = serde_json::from_str(
+            r#"{"access_token":"abc","expires_in":7200,"refresh_token":"next"}"#,
+        )
+        .unwrap();
+        assert_eq!(token.expires_in, Some(7200));
+        assert_eq!(token.refresh_token.as_deref(), Some("next"));
+    }
+
+    #[test]
+    fn test_renew_at() {
+        let now = Instant::now();
+        assert_eq!(renew_at(now, Some(600)), now + Duration::from_mins(9));
+        assert_eq!(
+            renew_at(now, None),
+            now + Duration::from_secs(DEFAULT_EXPIRES_IN).saturating_sub(EXPIRY_MARGIN)
+        );
+        // Tokens shorter than the margin are renewed on every fetch
+        assert_eq!(renew_at(now, Some(30)), now);
+    }
+}
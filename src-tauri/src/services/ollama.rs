@@ -0,0 +1,64 @@
+use crate::services::proxy;
+use crate::services::sources::{self, SourceReport};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const SOURCE_ID: &str = "ollama";
+const SOURCE_LABEL: &str = "Ollama";
+
+/// Tallies tokens of requests sent to a local Ollama server through the API proxy.
+///
+/// Ollama itself keeps no usage history (`/api/ps` only lists loaded models),
+/// so requests to the proxy's `/ollama` route are the record. Every request is
+/// zero cost.
+///
+/// # Errors
+/// Returns an error if the proxy log scan task fails.
+#[tracing::instrument(name = "source.ollama", skip_all)]
+pub async fn fetch_usage(config_dir: &Path) -> Result<SourceReport> {
+    let records = proxy::provider_records(config_dir, proxy::OLLAMA_PROVIDER).await?;
+    Ok(SourceReport {
+        id: SOURCE_ID.to_string(),
+        label: SOURCE_LABEL.to_string(),
+        daily: sources::aggregate_records(records),
+        keys: BTreeMap::new(),
+        credits: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_usage_reads_only_ollama_requests() {
+        let temp = tempfile::tempdir().expect("create temp dir");
+        let config_dir = temp.path();
+        let log_dir = proxy::usage_dir(config_dir);
+        std::fs::create_dir_all(&log_dir).expect("create log dir");
+        std::fs::write(
+            log_dir.join("usage.jsonl"),
+            concat!(
+                r#"{"timestamp":"2025-08-24T12:00:00+00:00","provider":"ollama","key":"unknown","model":"llama3.2","inputTokens":26,"outputTokens":12}"#,
+                "\n",
+                r#"{"timestamp":"2025-08-24T12:30:00+00:00","provider":"ollama","key":"unknown","model":"llama3.2","inputTokens":4,"outputTokens":8}"#,
+                "\n",
+                r#"{"timestamp":"2025-08-24T12:00:00+00:00","provider":"openai","key":"…abcd","model":"gpt-4o","inputTokens":100,"outputTokens":50}"#,
+                "\n",
+            ),
+        )
+        .expect("write log");
+
+        let report = fetch_usage(config_dir).await.expect("report");
+
+        assert_eq!(report.id, "ollama");
+        assert_eq!(report.daily.len(), 1);
+        let day = &report.daily[0];
+        assert_eq!(day.input_tokens, 30);
+        assert_eq!(day.output_tokens, 20);
+        assert!(day.cost.abs() < f64::EPSILON);
+        assert_eq!(day.models.len(), 1);
+        assert_eq!(day.models[0].model, "llama3.2");
+    }
+}
@@ -0,0 +1,276 @@
+use crate::config::OpenAiSourceConfig;
+use crate::services::credentials::{self, Credential};
+use crate::services::http;
+use crate::services::sources::{self, SourceReport, UsageRecord};
+use crate::types::DailyUsage;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub const SOURCE_ID: &str = "openai";
+const SOURCE_LABEL: &str = "OpenAI";
+
+const API_BASE: &str = "https://api.openai.com/v1/organization";
+/// Upper bound on pages followed per endpoint; a 30-day window fits in one.
+const MAX_PAGES: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    #[serde(default = "Vec::new")]
+    data: Vec<Bucket<T>>,
+    #[serde(default)]
+    has_more: bool,
+    /// Cursor for the following page.
+    #[serde(default, rename = "next_page")]
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Bucket<T> {
+    /// Unix seconds at the start of the (UTC) day.
+    start_time: i64,
+    #[serde(default = "Vec::new")]
+    results: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionsResult {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    /// Cached prompt tokens, already included in `input_tokens`.
+    #[serde(default)]
+    input_cached_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostResult {
+    #[serde(default)]
+    amount: Option<CostAmount>,
+    /// e.g. "gpt-4o-2024-08-06, input"
+    #[serde(default)]
+    line_item: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostAmount {
+    #[serde(default)]
+    value: f64,
+}
+
+fn bucket_date(start_time: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(start_time, 0).map(|t| t.format("%Y-%m-%d").to_string())
+}
+
+/// Model a cost line item belongs to; items without a model suffix (tools,
+/// storage) are kept under their own name.
+fn line_item_model(line_item: Option<&str>) -> String {
+    line_item
+        .map(|item| {
+            item.split_once(", ")
+                .map_or(item, |(model, _)| model)
+                .trim()
+        })
+        .filter(|model| !model.is_empty())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+fn token_records(buckets: Vec<Bucket<CompletionsResult>>) -> Vec<UsageRecord> {
+    buckets
+        .into_iter()
+        .filter_map(|bucket| Some((bucket_date(bucket.start_time)?, bucket.results)))
+        .flat_map(|(date, results)| {
+            results.into_iter().map(move |r| UsageRecord {
+                date: date.clone(),
+                model: r
+                    .model
+                    .filter(|m| !m.is_empty())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                input_tokens: r.input_tokens.saturating_sub(r.input_cached_tokens),
+                output_tokens: r.output_tokens,
+                cache_read_input_tokens: r.input_cached_tokens,
+                ..UsageRecord::default()
+            })
+        })
+        .collect()
+}
+
+fn cost_records(buckets: Vec<Bucket<CostResult>>) -> Vec<UsageRecord> {
+    buckets
+        .into_iter()
+        .filter_map(|bucket| Some((bucket_date(bucket.start_time)?, bucket.results)))
+        .flat_map(|(date, results)| {
+            results.into_iter().map(move |r| UsageRecord {
+                date: date.clone(),
+                model: line_item_model(r.line_item.as_deref()),
+                cost: r.amount.map_or(0.0, |a| a.value),
+                ..UsageRecord::default()
+            })
+        })
+        .collect()
+}
+
+/// Merges token usage and billed costs into per-day, per-model totals.
+fn aggregate(
+    usage: Vec<Bucket<CompletionsResult>>,
+    costs: Vec<Bucket<CostResult>>,
+) -> Vec<DailyUsage> {
+    sources::aggregate_records(token_records(usage).into_iter().chain(cost_records(costs)))
+}
+
+/// Fetches every page of daily buckets from `endpoint`.
+async fn fetch_buckets<T: DeserializeOwned>(
+    endpoint: &str,
+    admin_key: &str,
+    query: &[(&str, String)],
+    timeout: Duration,
+) -> Result<Vec<Bucket<T>>> {
+    let mut buckets = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    for _ in 0..MAX_PAGES {
+        let mut request = http::client()
+            .get(format!("{API_BASE}/{endpoint}"))
+            .timeout(timeout)
+            .bearer_auth(admin_key)
+            .query(query);
+        if let Some(token) = &page_token {
+            request = request.query(&[("page", token)]);
+        }
+
+        let page: Page<T> = request.send().await?.error_for_status()?.json().await?;
+        buckets.extend(page.data);
+        match page.next.filter(|_| page.has_more) {
+            Some(next) => page_token = Some(next),
+            None => break,
+        }
+    }
+
+    Ok(buckets)
+}
+
+/// Fetches the last 30 days of `OpenAI` platform usage.
+///
+/// Token counts come from the completions usage API grouped by model and cost
+/// from the costs API grouped by line item, both in daily UTC buckets.
+///
+/// # Errors
+/// Returns an error if no admin key is stored, a request fails,
+/// or a response cannot be parsed.
+#[tracing::instrument(name = "source.openai", skip_all)]
+pub async fn fetch_usage(config: &OpenAiSourceConfig, timeout: Duration) -> Result<SourceReport> {
+    let admin_key = credentials::lookup(Credential::OpenAiAdminKey, config.admin_key.as_deref())
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("OpenAI admin key is not configured"))?;
+
+    let start = chrono::Utc::now() - chrono::Duration::days(sources::SUMMARY_WINDOW_DAYS);
+    let mut query = vec![
+        ("start_time", start.timestamp().to_string()),
+        ("bucket_width", "1d".to_string()),
+        ("limit", (sources::SUMMARY_WINDOW_DAYS + 1).to_string()),
+    ];
+    if let Some(project) = config.project_id.as_deref().filter(|p| !p.is_empty()) {
+        query.push(("project_ids", project.to_string()));
+    }
+
+    let mut usage_query = query.clone();
+    usage_query.push(("group_by", "model".to_string()));
+    let mut cost_query = query;
+    cost_query.push(("group_by", "line_item".to_string()));
+
+    let (usage, costs) = tokio::try_join!(
+        fetch_buckets("usage/completions", &admin_key, &usage_query, timeout),
+        fetch_buckets("costs", &admin_key, &cost_query, timeout),
+    )?;
+
+    Ok(SourceReport {
+        id: SOURCE_ID.to_string(),
+        label: SOURCE_LABEL.to_string(),
+        daily: aggregate(usage, costs),
+        keys: BTreeMap::new(),
+        credits: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_usage_and_costs() {
+        let usage_json = r#"{
+            "object": "page",
+            "data": [{
+                "object": "bucket",
+                "start_time": 1746057600,
+                "end_time": 1746144000,
+                "results": [{
+                    "object": "organization.usage.completions.result",
+                    "input_tokens": 1000,
+                    "output_tokens": 200,
+                    "input_cached_tokens": 400,
+                    "num_model_requests": 3,
+                    "model": "gpt-4o-2024-08-06"
+                }]
+            }],
+            "has_more": true,
+            "next_page": "page_abc"
+        }"#;
+        let costs_json = r#"{
+            "object": "page",
+            "data": [
+                {
+                    "start_time": 1746057600,
+                    "results": [
+                        { "amount": { "value": 0.25, "currency": "usd" }, "line_item": "gpt-4o-2024-08-06, input" },
+                        { "amount": { "value": 0.5, "currency": "usd" }, "line_item": "gpt-4o-2024-08-06, output" },
+                        { "amount": { "value": 0.1, "currency": "usd" }, "line_item": "web search tool calls" }
+                    ]
+                },
+                { "start_time": 1746144000, "results": [] }
+            ],
+            "has_more": false,
+            "next_page": null
+        }"#;
+
+        let usage: Page<CompletionsResult> =
+            serde_json::from_str(usage_json).expect("test JSON should parse correctly");
+        assert!(usage.has_more);
+        assert_eq!(usage.next.as_deref(), Some("page_abc"));
+        let costs: Page<CostResult> =
+            serde_json::from_str(costs_json).expect("test JSON should parse correctly");
+
+        let daily = aggregate(usage.data, costs.data);
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].date, "2025-05-01");
+        assert!((daily[0].cost - 0.85).abs() < 1e-9);
+        assert_eq!(daily[0].input_tokens, 600);
+        assert_eq!(daily[0].cache_read_input_tokens, 400);
+        assert_eq!(daily[0].output_tokens, 200);
+
+        let gpt = daily[0]
+            .models
+            .iter()
+            .find(|m| m.model == "gpt-4o-2024-08-06")
+            .expect("model row");
+        assert!((gpt.cost - 0.75).abs() < 1e-9);
+        assert_eq!(gpt.output_tokens, 200);
+        assert!(daily[0]
+            .models
+            .iter()
+            .any(|m| m.model == "web search tool calls"));
+    }
+
+    #[test]
+    fn test_line_item_model() {
+        assert_eq!(line_item_model(Some("o3, cached input")), "o3");
+        assert_eq!(line_item_model(Some("file search")), "file search");
+        assert_eq!(line_item_model(None), "unknown");
+    }
+}
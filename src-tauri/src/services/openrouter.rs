@@ -0,0 +1,198 @@
+use crate::config::OpenRouterSourceConfig;
+use crate::services::credentials::{self, Credential};
+use crate::services::http;
+use crate::services::sources::{self, SourceReport, UsageRecord};
+use crate::types::{DailyUsage, ProviderUsageResult};
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub const SOURCE_ID: &str = "openrouter";
+const SOURCE_LABEL: &str = "OpenRouter";
+
+const CREDITS_URL: &str = "https://openrouter.ai/api/v1/credits";
+const ACTIVITY_URL: &str = "https://openrouter.ai/api/v1/activity";
+
+#[derive(Debug, Deserialize)]
+struct CreditsResponse {
+    data: Credits,
+}
+
+#[derive(Debug, Deserialize)]
+struct Credits {
+    #[serde(default)]
+    total_credits: f64,
+    #[serde(default)]
+    total_usage: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActivityResponse {
+    #[serde(default)]
+    data: Vec<ActivityItem>,
+}
+
+/// One day of usage for one model endpoint.
+#[derive(Debug, Deserialize)]
+struct ActivityItem {
+    /// `YYYY-MM-DD`, optionally followed by a time.
+    date: String,
+    #[serde(default)]
+    model: String,
+    /// Spend in USD.
+    #[serde(default)]
+    usage: f64,
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    reasoning_tokens: u64,
+}
+
+fn aggregate_activity(items: Vec<ActivityItem>) -> Vec<DailyUsage> {
+    sources::aggregate_records(items.into_iter().filter_map(|item| {
+        let date = item.date.get(..10)?.to_string();
+        let model = if item.model.is_empty() {
+            "unknown".to_string()
+        } else {
+            item.model
+        };
+        Some(UsageRecord {
+            date,
+            model,
+            input_tokens: item.prompt_tokens,
+            output_tokens: item.completion_tokens + item.reasoning_tokens,
+            cost: item.usage,
+            ..UsageRecord::default()
+        })
+    }))
+}
+
+const fn credits_result(credits: &Credits) -> ProviderUsageResult {
+    ProviderUsageResult {
+        cost: None,
+        tokens: None,
+        used: Some(credits.total_usage),
+        total: Some(credits.total_credits),
+        daily: Vec::new(),
+    }
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    url: &str,
+    api_key: &str,
+    timeout: Duration,
+) -> Result<T> {
+    Ok(http::client()
+        .get(url)
+        .timeout(timeout)
+        .bearer_auth(api_key)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Fetches the last 30 days of `OpenRouter` activity and the account's credit balance.
+///
+/// Daily spend and tokens per model come from `/activity`; the purchased and
+/// used credits from `/credits` are attached to the report for the tray. A
+/// failing balance request is logged and leaves the balance unset.
+///
+/// # Errors
+/// Returns an error if no API key is stored, the activity request fails,
+/// or its response cannot be parsed.
+#[tracing::instrument(name = "source.openrouter", skip_all)]
+pub async fn fetch_usage(
+    config: &OpenRouterSourceConfig,
+    timeout: Duration,
+) -> Result<SourceReport> {
+    let api_key = credentials::lookup(Credential::OpenRouterApiKey, config.api_key.as_deref())
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("OpenRouter API key is not configured"))?;
+
+    let (activity, credits) = tokio::join!(
+        get_json::<ActivityResponse>(ACTIVITY_URL, &api_key, timeout),
+        get_json::<CreditsResponse>(CREDITS_URL, &api_key, timeout),
+    );
+
+    let credits = credits
+        .inspect_err(|e| tracing::warn!("Failed to fetch OpenRouter credits: {e}"))
+        .ok()
+        .map(|response| credits_result(&response.data));
+
+    Ok(SourceReport {
+        id: SOURCE_ID.to_string(),
+        label: SOURCE_LABEL.to_string(),
+        daily: aggregate_activity(activity?.data),
+        keys: BTreeMap::new(),
+        credits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_activity_response() {
+        let json = r#"{
+            "data": [
+                {
+                    "date": "2025-08-24",
+                    "model": "openai/gpt-4.1",
+                    "model_permaslug": "openai/gpt-4.1-2025-04-14",
+                    "endpoint_id": "ep_1",
+                    "provider_name": "OpenAI",
+                    "usage": 0.015,
+                    "byok_usage_inference": 0,
+                    "requests": 2,
+                    "prompt_tokens": 50,
+                    "completion_tokens": 125,
+                    "reasoning_tokens": 25
+                },
+                {
+                    "date": "2025-08-24",
+                    "model": "openai/gpt-4.1",
+                    "endpoint_id": "ep_2",
+                    "usage": 0.005,
+                    "prompt_tokens": 10,
+                    "completion_tokens": 5
+                },
+                {
+                    "date": "2025-08-25 00:00:00",
+                    "model": "anthropic/claude-sonnet-4",
+                    "usage": 1.25,
+                    "prompt_tokens": 1000,
+                    "completion_tokens": 300
+                }
+            ]
+        }"#;
+
+        let response: ActivityResponse =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        let daily = aggregate_activity(response.data);
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].date, "2025-08-24");
+        assert_eq!(daily[0].models.len(), 1);
+        assert!((daily[0].cost - 0.02).abs() < 1e-9);
+        assert_eq!(daily[0].input_tokens, 60);
+        assert_eq!(daily[0].output_tokens, 155);
+        assert_eq!(daily[1].date, "2025-08-25");
+        assert!((daily[1].cost - 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_credits_response() {
+        let json = r#"{ "data": { "total_credits": 25.0, "total_usage": 10.5 } }"#;
+        let response: CreditsResponse =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        let result = credits_result(&response.data);
+        assert_eq!(result.used, Some(10.5));
+        assert_eq!(result.total, Some(25.0));
+        assert!(result.format_display("OpenRouter").contains("(42%)"));
+    }
+}
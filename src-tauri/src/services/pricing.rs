@@ -1,13 +1,19 @@
-use anyhow::Result;
+use crate::services::http;
+use crate::types::ModelInfo;
+use anyhow::{anyhow, Result};
+use reqwest::{header, StatusCode};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::hash::BuildHasher;
 use std::sync::OnceLock;
-use std::time::Duration;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 
 const MODELS_DEV_URL: &str = "https://models.dev/api.json";
-const FETCH_TIMEOUT_SECS: u64 = 10;
+/// models.dev prices change rarely, so it is asked at most every few hours.
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_hours(6);
+/// Retry delay after a failed fetch while nothing is cached yet.
+const FAILED_RETRY_INTERVAL: Duration = Duration::from_mins(5);
 
 #[derive(Debug, Clone)]
 pub struct ModelPrice {
@@ -29,8 +35,16 @@ struct ProviderData {
 
 #[derive(Debug, Deserialize)]
 struct ModelData {
+    #[serde(default)]
+    name: Option<String>,
     #[serde(default)]
     cost: CostData,
+    #[serde(default)]
+    limit: LimitData,
+    #[serde(default)]
+    knowledge: Option<String>,
+    #[serde(default)]
+    release_date: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -41,32 +55,114 @@ struct CostData {
     output: f64,
 }
 
+#[derive(Debug, Deserialize, Default)]
+struct LimitData {
+    #[serde(default)]
+    context: Option<u64>,
+    #[serde(default)]
+    output: Option<u64>,
+}
+
 static PRICE_CACHE: OnceLock<RwLock<Option<HashMap<String, ModelPrice>>>> = OnceLock::new();
+static INFO_CACHE: OnceLock<RwLock<Option<HashMap<String, ModelInfo>>>> = OnceLock::new();
 
 fn get_cache() -> &'static RwLock<Option<HashMap<String, ModelPrice>>> {
     PRICE_CACHE.get_or_init(|| RwLock::new(None))
 }
 
+fn get_info_cache() -> &'static RwLock<Option<HashMap<String, ModelInfo>>> {
+    INFO_CACHE.get_or_init(|| RwLock::new(None))
+}
+
+/// Conditional-request validators and timing of the last models.dev fetch.
+#[derive(Debug)]
+struct FetchState {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    last_attempt: Option<Instant>,
+}
+
+impl FetchState {
+    /// Whether enough time has passed since the last attempt to ask models.dev
+    /// again. Failed fetches count as attempts so an outage isn't hammered.
+    fn is_due(&self, has_cache: bool, now: Instant) -> bool {
+        let interval = if has_cache {
+            MIN_REFETCH_INTERVAL
+        } else {
+            FAILED_RETRY_INTERVAL
+        };
+        self.last_attempt
+            .is_none_or(|at| now.duration_since(at) >= interval)
+    }
+}
+
+/// Held for the whole fetch, so concurrent callers wait for the in-flight
+/// request and then reuse its result instead of issuing their own.
+static FETCH_STATE: Mutex<FetchState> = Mutex::const_new(FetchState {
+    etag: None,
+    last_modified: None,
+    last_attempt: None,
+});
+
+fn header_value(response: &reqwest::Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
 /// Fetches model prices from models.dev API.
 ///
+/// Model metadata (context window, limits, knowledge cutoff) from the same
+/// response is cached alongside for [`get_model_info`]. Requests are
+/// coalesced, rate limited to [`MIN_REFETCH_INTERVAL`] and conditional, so
+/// the cached prices are returned when models.dev was asked recently or
+/// reports no change.
+///
 /// # Errors
-/// Returns an error if the HTTP request fails or the response cannot be parsed.
-pub async fn fetch_prices() -> Result<HashMap<String, ModelPrice>> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
-        .build()?;
-
-    let response: ModelsDevResponse = client
-        .get(MODELS_DEV_URL)
-        .header("User-Agent", "TokenMeter/1.0")
-        .send()
-        .await?
-        .json()
-        .await?;
+/// Returns an error if the HTTP request fails or the response cannot be parsed,
+/// or if nothing is cached and the last attempt failed too recently to retry.
+#[tracing::instrument(name = "service.pricing", skip_all)]
+pub async fn fetch_prices(timeout: Duration) -> Result<HashMap<String, ModelPrice>> {
+    let mut state = FETCH_STATE.lock().await;
+    let cached = get_cache().read().await.clone();
+    if !state.is_due(cached.is_some(), Instant::now()) {
+        return cached.ok_or_else(|| anyhow!("models.dev fetch failed recently; retrying later"));
+    }
+    state.last_attempt = Some(Instant::now());
+
+    let mut request = http::client().get(MODELS_DEV_URL).timeout(timeout);
+    if cached.is_some() {
+        if let Some(etag) = &state.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &state.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(prices) = cached {
+            return Ok(prices);
+        }
+    }
+    let response = response.error_for_status()?;
+    let etag = header_value(&response, header::ETAG);
+    let last_modified = header_value(&response, header::LAST_MODIFIED);
+    let response: ModelsDevResponse = response.json().await?;
 
     let mut prices = HashMap::new();
-    for provider in response.providers.values() {
+    let mut infos = HashMap::new();
+    // Sort providers so models listed by several providers resolve deterministically
+    let mut providers: Vec<_> = response.providers.iter().collect();
+    providers.sort_by_key(|(provider_id, _)| *provider_id);
+    for (provider_id, provider) in providers {
         for (model_id, model_data) in &provider.models {
+            infos
+                .entry(model_id.clone())
+                .or_insert_with(|| model_info(provider_id, model_id, model_data));
             if model_data.cost.input > 0.0 || model_data.cost.output > 0.0 {
                 prices.insert(
                     model_id.clone(),
@@ -81,20 +177,71 @@ pub async fn fetch_prices() -> Result<HashMap<String, ModelPrice>> {
 
     // Update cache
     *get_cache().write().await = Some(prices.clone());
+    *get_info_cache().write().await = Some(infos);
+    state.etag = etag;
+    state.last_modified = last_modified;
+    drop(state);
 
     Ok(prices)
 }
 
-/// Gets cached prices or fetches them if not available.
-pub async fn get_prices() -> Option<HashMap<String, ModelPrice>> {
-    // Try to get from cache first
+/// Gets cached prices, refreshing them from models.dev when they are due.
+///
+/// Cached prices are returned as-is while another refresh is in flight or if
+/// the refresh fails.
+pub async fn get_prices(timeout: Duration) -> Option<HashMap<String, ModelPrice>> {
     let cached = get_cache().read().await.clone();
-    if let Some(prices) = cached {
-        return Some(prices);
+    if cached.is_some() {
+        let due = FETCH_STATE
+            .try_lock()
+            .is_ok_and(|state| state.is_due(true, Instant::now()));
+        if !due {
+            return cached;
+        }
     }
 
-    // Fetch if not cached
-    fetch_prices().await.ok()
+    fetch_prices(timeout).await.ok().or(cached)
+}
+
+fn model_info(provider_id: &str, model_id: &str, data: &ModelData) -> ModelInfo {
+    ModelInfo {
+        id: model_id.to_string(),
+        name: data.name.clone().unwrap_or_else(|| model_id.to_string()),
+        provider: provider_id.to_string(),
+        context_window: data.limit.context.filter(|&c| c > 0),
+        max_output_tokens: data.limit.output.filter(|&o| o > 0),
+        knowledge_cutoff: data.knowledge.clone(),
+        release_date: data.release_date.clone(),
+    }
+}
+
+/// Gets metadata for a model, fetching models.dev data if not cached.
+///
+/// Uses the same exact-then-fuzzy name matching as [`calculate_fallback_cost`].
+pub async fn get_model_info(model_name: &str, timeout: Duration) -> Option<ModelInfo> {
+    let cached = get_info_cache().read().await.is_some();
+    if !cached {
+        fetch_prices(timeout).await.ok()?;
+    }
+    let infos = get_info_cache().read().await;
+    find_model(model_name, infos.as_ref()?).cloned()
+}
+
+/// Finds a model entry by exact name, falling back to a case-insensitive
+/// substring match in either direction.
+fn find_model<'a, V, S: BuildHasher>(
+    model_name: &str,
+    models: &'a HashMap<String, V, S>,
+) -> Option<&'a V> {
+    if let Some(value) = models.get(model_name) {
+        return Some(value);
+    }
+
+    let model_lower = model_name.to_lowercase();
+    models.iter().find_map(|(key, value)| {
+        let key_lower = key.to_lowercase();
+        (model_lower.contains(&key_lower) || key_lower.contains(&model_lower)).then_some(value)
+    })
 }
 
 /// Calculates cost using fallback prices when original cost is 0.
@@ -106,21 +253,9 @@ pub fn calculate_fallback_cost<S: BuildHasher>(
     output_tokens: u64,
     prices: &HashMap<String, ModelPrice, S>,
 ) -> f64 {
-    // Exact match
-    if let Some(price) = prices.get(model_name) {
-        return calculate_cost(input_tokens, output_tokens, price);
-    }
-
-    // Fuzzy match: find key containing model name (case insensitive)
-    let model_lower = model_name.to_lowercase();
-    for (key, price) in prices {
-        let key_lower = key.to_lowercase();
-        if model_lower.contains(&key_lower) || key_lower.contains(&model_lower) {
-            return calculate_cost(input_tokens, output_tokens, price);
-        }
-    }
-
-    0.0
+    find_model(model_name, prices).map_or(0.0, |price| {
+        calculate_cost(input_tokens, output_tokens, price)
+    })
 }
 
 #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
@@ -167,6 +302,73 @@ mod tests {
         assert!((cost - 0.0525).abs() < 0.0001);
     }
 
+    #[test]
+    fn test_parse_model_metadata() {
+        let json = r#"{
+            "anthropic": {
+                "models": {
+                    "claude-sonnet-4-5": {
+                        "name": "Claude Sonnet 4.5",
+                        "knowledge": "2025-03",
+                        "release_date": "2025-09-29",
+                        "cost": { "input": 3, "output": 15 },
+                        "limit": { "context": 200000, "output": 64000 }
+                    },
+                    "legacy": { "limit": { "context": 0 } }
+                }
+            }
+        }"#;
+
+        let response: ModelsDevResponse =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        let provider = &response.providers["anthropic"];
+
+        let info = model_info(
+            "anthropic",
+            "claude-sonnet-4-5",
+            &provider.models["claude-sonnet-4-5"],
+        );
+        assert_eq!(info.name, "Claude Sonnet 4.5");
+        assert_eq!(info.context_window, Some(200_000));
+        assert_eq!(info.max_output_tokens, Some(64_000));
+        assert_eq!(info.knowledge_cutoff.as_deref(), Some("2025-03"));
+
+        let legacy = model_info("anthropic", "legacy", &provider.models["legacy"]);
+        assert_eq!(legacy.name, "legacy");
+        assert!(legacy.context_window.is_none());
+        assert!(legacy.release_date.is_none());
+    }
+
+    #[test]
+    fn test_find_model_prefers_exact_match() {
+        let mut models = HashMap::new();
+        models.insert("claude-sonnet-4".to_string(), 1);
+        models.insert("claude-sonnet-4-5".to_string(), 2);
+
+        assert_eq!(find_model("claude-sonnet-4-5", &models), Some(&2));
+        assert!(find_model("CLAUDE-SONNET-4-5-20250929", &models).is_some());
+        assert!(find_model("gpt-4o", &models).is_none());
+    }
+
+    #[test]
+    fn test_fetch_state_is_due() {
+        let now = Instant::now();
+        let mut state = FetchState {
+            etag: None,
+            last_modified: None,
+            last_attempt: None,
+        };
+        assert!(state.is_due(true, now));
+        assert!(state.is_due(false, now));
+
+        state.last_attempt = Some(now);
+        let later = now + Duration::from_mins(10);
+        assert!(!state.is_due(true, later));
+        assert!(state.is_due(false, later));
+        assert!(!state.is_due(false, now + Duration::from_mins(1)));
+        assert!(state.is_due(true, now + MIN_REFETCH_INTERVAL));
+    }
+
     #[test]
     fn test_calculate_fallback_cost_no_match() {
         let prices = HashMap::new();
@@ -0,0 +1,353 @@
+use crate::config::ApiProvider;
+use crate::services::fx::{self, FxRates};
+use crate::services::script_runner;
+use crate::types::{
+    CachedProviderResult, ProviderResultCache, ProviderTrayStats, ProviderUsageResult,
+};
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use std::time::Duration;
+
+/// Tray lines for one run, plus the results fetched successfully during it.
+pub struct ProviderRun {
+    pub stats: Vec<ProviderTrayStats>,
+    pub fetched: Vec<(String, CachedProviderResult)>,
+    /// Providers whose fetch still failed after its last retry, with the error.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Whether `cached` was fetched less than `ttl` before `now`.
+fn is_fresh(cached: &CachedProviderResult, ttl: Duration, now: DateTime<Utc>) -> bool {
+    chrono::TimeDelta::from_std(ttl).is_ok_and(|ttl| now - cached.fetched_at < ttl)
+}
+
+/// Fetches one provider once, giving up after `timeout`.
+async fn fetch_attempt(
+    provider: &ApiProvider,
+    rates: Option<&FxRates>,
+    timeout: Duration,
+) -> Result<ProviderUsageResult, String> {
+    let fetch = script_runner::fetch_provider_result(provider, rates, timeout);
+    tokio::time::timeout(timeout, fetch)
+        .await
+        .map_err(|_| format!("timed out after {}s", timeout.as_secs()))?
+        .map_err(|e| e.to_string())
+}
+
+/// Fetches one provider, retrying failures with exponential backoff as its
+/// `retry` settings allow. Failures are logged and the last one is returned
+/// so one broken provider never hides the others.
+async fn fetch_one(
+    provider: &ApiProvider,
+    rates: Option<&FxRates>,
+    timeout: Duration,
+) -> Result<ProviderUsageResult, String> {
+    let mut retry = 0;
+    loop {
+        match fetch_attempt(provider, rates, timeout).await {
+            Ok(result) => return Ok(result),
+            Err(e) if retry < provider.retry.attempts => {
+                let delay = provider.retry.backoff(retry);
+                tracing::warn!(
+                    "Provider {} failed ({e}), retrying in {}ms",
+                    provider.id,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            Err(e) => {
+                tracing::warn!("Provider {} failed: {e}", provider.id);
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Tray lines for the enabled `providers` shown in the tray, from their cached
+/// results alone, for re-rendering the menu without fetching.
+#[must_use]
+pub fn cached_stats(
+    providers: &[ApiProvider],
+    cache: &ProviderResultCache,
+) -> Vec<ProviderTrayStats> {
+    providers
+        .iter()
+        .filter(|provider| provider.enabled && provider.show_in_tray)
+        .map(|provider| {
+            let result = cache.get(&provider.id).map(|cached| &cached.result);
+            let stats = ProviderTrayStats::from_provider(provider, result);
+            if provider.consecutive_failures > 0 {
+                stats.degraded()
+            } else {
+                stats
+            }
+        })
+        .collect()
+}
+
+/// Fetches every enabled provider concurrently, each bounded by `timeout`, and
+/// returns the tray lines of those shown in the tray in the order of `providers`.
+///
+/// Results in `cache` younger than `ttl` are reused without fetching. A
+/// provider whose fetch fails, after retrying, falls back to its last cached
+/// result, if any.
+/// Exchange rates are only looked up when a provider reports in a currency
+/// other than USD.
+#[tracing::instrument(name = "service.provider_runner", skip_all)]
+pub async fn fetch_all(
+    providers: &[ApiProvider],
+    timeout: Duration,
+    cache: &ProviderResultCache,
+    ttl: Duration,
+) -> ProviderRun {
+    let now = Utc::now();
+    let enabled: Vec<&ApiProvider> = providers.iter().filter(|p| p.enabled).collect();
+    let stale: Vec<&ApiProvider> = enabled
+        .iter()
+        .copied()
+        .filter(|p| !cache.get(&p.id).is_some_and(|c| is_fresh(c, ttl, now)))
+        .collect();
+
+    let needs_rates = stale.iter().any(|p| {
+        p.currency
+            .as_deref()
+            .is_some_and(|c| !c.trim().is_empty() && !c.eq_ignore_ascii_case(fx::DISPLAY_CURRENCY))
+    });
+    let rates = if needs_rates {
+        fx::get_rates().await
+    } else {
+        None
+    };
+
+    let results = join_all(
+        stale
+            .iter()
+            .map(|provider| fetch_one(provider, rates.as_ref(), timeout)),
+    )
+    .await;
+    let mut fetched = Vec::new();
+    let mut failed = Vec::new();
+    for (provider, result) in stale.iter().zip(results) {
+        match result {
+            Ok(result) => fetched.push((
+                provider.id.clone(),
+                CachedProviderResult {
+                    fetched_at: now,
+                    result,
+                },
+            )),
+            Err(e) => failed.push((provider.id.clone(), e)),
+        }
+    }
+
+    let stats = enabled
+        .iter()
+        .filter(|provider| provider.show_in_tray)
+        .map(|provider| {
+            let fresh = fetched
+                .iter()
+                .find(|(id, _)| *id == provider.id)
+                .map(|(_, cached)| cached);
+            let result = fresh
+                .or_else(|| cache.get(&provider.id))
+                .map(|cached| &cached.result);
+            let stats = ProviderTrayStats::from_provider(provider, result);
+            // Cached results keep the health of the fetch that produced them
+            let failing = failed.iter().any(|(id, _)| *id == provider.id)
+                || (fresh.is_none() && provider.consecutive_failures > 0);
+            if failing {
+                stats.degraded()
+            } else {
+                stats
+            }
+        })
+        .collect();
+    ProviderRun {
+        stats,
+        fetched,
+        failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ProviderRetry, ProviderUnit};
+    use std::collections::{BTreeMap, HashMap};
+
+    fn provider(id: &str, enabled: bool, fetch_script: &str) -> ApiProvider {
+        ApiProvider {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled,
+            fetch_script: fetch_script.to_string(),
+            transform_script: String::new(),
+            transform_paths: BTreeMap::new(),
+            env: HashMap::new(),
+            env_expires_at: HashMap::new(),
+            last_fetched: None,
+            last_error: None,
+            consecutive_failures: 0,
+            currency: None,
+            unit: None,
+            unit_label: None,
+            request: None,
+            oauth: None,
+            steps: Vec::new(),
+            retry: ProviderRetry {
+                attempts: 0,
+                ..ProviderRetry::default()
+            },
+            group: None,
+            sort_order: 0,
+            show_in_tray: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_isolates_failures() {
+        let providers = [
+            provider("broken", true, "definitely-not-a-command"),
+            provider("off", false, "definitely-not-a-command"),
+            provider("empty", true, ""),
+        ];
+        let run = fetch_all(
+            &providers,
+            Duration::from_secs(1),
+            &ProviderResultCache::new(),
+            Duration::ZERO,
+        )
+        .await;
+        let lines: Vec<&str> = run.stats.iter().map(|s| s.display_text.as_str()).collect();
+        assert_eq!(lines, ["⚠️ broken: --", "⚠️ empty: --"]);
+        assert!(run.fetched.is_empty());
+        let failed: Vec<&str> = run.failed.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(failed, ["broken", "empty"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_hides_providers_from_tray() {
+        let mut hidden = provider("hidden", true, "definitely-not-a-command");
+        hidden.show_in_tray = false;
+        let providers = [hidden, provider("shown", true, "")];
+        let run = fetch_all(
+            &providers,
+            Duration::from_secs(1),
+            &ProviderResultCache::new(),
+            Duration::ZERO,
+        )
+        .await;
+        let lines: Vec<&str> = run.stats.iter().map(|s| s.display_text.as_str()).collect();
+        assert_eq!(lines, ["⚠️ shown: --"]);
+        // Still fetched for the dashboard
+        assert_eq!(run.failed.len(), 2);
+        assert_eq!(
+            cached_stats(&providers, &ProviderResultCache::new()).len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_retries_with_backoff() {
+        let mut broken = provider("broken", true, "definitely-not-a-command");
+        broken.retry = ProviderRetry {
+            attempts: 2,
+            backoff_ms: 100,
+        };
+        let started = std::time::Instant::now();
+        let run = fetch_all(
+            &[broken],
+            Duration::from_secs(1),
+            &ProviderResultCache::new(),
+            Duration::ZERO,
+        )
+        .await;
+        // Waits 100ms, then 200ms, before reporting the final failure
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(run.failed.len(), 1);
+        assert!(!run.failed[0].1.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_uses_cache() {
+        let providers = [provider("api", true, "definitely-not-a-command")];
+        let cached = |age_secs: i64| {
+            ProviderResultCache::from([(
+                "api".to_string(),
+                CachedProviderResult {
+                    fetched_at: Utc::now() - chrono::TimeDelta::seconds(age_secs),
+                    result: ProviderUsageResult {
+                        cost: Some(1.5),
+                        tokens: None,
+                        used: None,
+                        total: None,
+                        daily: Vec::new(),
+                    },
+                },
+            )])
+        };
+        let ttl = Duration::from_mins(5);
+
+        let run = fetch_all(&providers, Duration::from_secs(1), &cached(10), ttl).await;
+        assert_eq!(run.stats[0].display_text, "🔋 api: $1.50");
+        assert!(run.fetched.is_empty());
+
+        // A stale entry is refetched, and still shown, marked, when the fetch fails
+        let run = fetch_all(&providers, Duration::from_secs(1), &cached(600), ttl).await;
+        assert_eq!(run.stats[0].display_text, "⚠️ api: $1.50");
+    }
+
+    #[test]
+    fn test_cached_stats_in_provider_units() {
+        let result = |cost, used, total| CachedProviderResult {
+            fetched_at: Utc::now(),
+            result: ProviderUsageResult {
+                cost,
+                tokens: None,
+                used,
+                total,
+                daily: Vec::new(),
+            },
+        };
+        let mut credits = provider("credits", true, "");
+        credits.unit = Some(ProviderUnit::Credits);
+        let mut requests = provider("requests", true, "");
+        requests.unit = Some(ProviderUnit::Requests);
+        requests.unit_label = Some(" premium requests ".to_string());
+        let mut dollars = provider("dollars", true, "");
+        dollars.unit = Some(ProviderUnit::Usd);
+        let cache = ProviderResultCache::from([
+            ("credits".to_string(), result(Some(1200.0), None, None)),
+            (
+                "requests".to_string(),
+                result(None, Some(84.0), Some(300.0)),
+            ),
+            ("dollars".to_string(), result(None, Some(12.5), Some(50.0))),
+        ]);
+
+        let stats = cached_stats(&[credits, requests, dollars], &cache);
+        let lines: Vec<&str> = stats.iter().map(|s| s.display_text.as_str()).collect();
+        assert_eq!(
+            lines,
+            [
+                "🔋 credits: 1,200 credits",
+                "🔋 requests: [███░░░░░░░] 84/300 premium requests (28%)",
+                "🔋 dollars: [███░░░░░░░] $12.50/$50.00 (25%)",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_without_enabled_providers() {
+        let providers = [provider("off", false, "")];
+        let run = fetch_all(
+            &providers,
+            Duration::from_secs(1),
+            &ProviderResultCache::new(),
+            Duration::ZERO,
+        )
+        .await;
+        assert!(run.stats.is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+use crate::config::ProxySourceConfig;
+use crate::services::http;
+use crate::services::local_http::{self, HttpRequest};
+use crate::services::pricing;
+use crate::services::sources::{self, SourceReport, UsageRecord};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+const SOURCE_ID_PREFIX: &str = "proxy";
+/// Provider recorded for `/ollama` requests. These are reported by the Ollama
+/// source rather than as proxy usage.
+pub const OLLAMA_PROVIDER: &str = "ollama";
+const USAGE_FILE: &str = "usage.jsonl";
+/// Responses larger than this are still forwarded but not inspected for usage.
+const MAX_CAPTURE_BYTES: usize = 8 * 1024 * 1024;
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "content-length",
+    // Ask upstream for an identity-encoded body so usage can be parsed.
+    "accept-encoding",
+];
+
+/// Token usage observed in a proxied response.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ObservedUsage {
+    model: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+}
+
+/// One line of the proxy usage log.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProxyRecord {
+    timestamp: String,
+    provider: String,
+    key: String,
+    model: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_tokens: u64,
+    #[serde(default)]
+    cache_read_tokens: u64,
+}
+
+struct ProxyContext {
+    config: ProxySourceConfig,
+    client: reqwest::Client,
+    usage_path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+/// Directory holding the proxy usage log.
+#[must_use]
+pub fn usage_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join("proxy")
+}
+
+/// Maps a request path to `(provider, upstream URL)`.
+///
+/// Clients point their base URL at `http://127.0.0.1:<port>/anthropic`,
+/// `/openai` or `/ollama`; the prefix is stripped before forwarding.
+fn route(config: &ProxySourceConfig, path: &str) -> Option<(&'static str, String)> {
+    let (provider, upstream, rest) = if let Some(rest) = path.strip_prefix("/anthropic") {
+        ("anthropic", &config.anthropic_upstream, rest)
+    } else if let Some(rest) = path.strip_prefix("/openai") {
+        ("openai", &config.openai_upstream, rest)
+    } else if let Some(rest) = path.strip_prefix("/ollama") {
+        (OLLAMA_PROVIDER, &config.ollama_upstream, rest)
+    } else {
+        return None;
+    };
+    if !rest.is_empty() && !rest.starts_with('/') && !rest.starts_with('?') {
+        return None;
+    }
+    Some((
+        provider,
+        format!("{}{rest}", upstream.trim_end_matches('/')),
+    ))
+}
+
+/// Short, non-secret label identifying the API key used for a request.
+fn key_label(request: &HttpRequest) -> String {
+    let key = request.header("x-api-key").or_else(|| {
+        request
+            .header("authorization")
+            .map(|v| v.trim_start_matches("Bearer ").trim())
+    });
+    match key {
+        Some(k) if k.chars().count() >= 8 => {
+            let tail: String = k.chars().skip(k.chars().count() - 4).collect();
+            format!("…{tail}")
+        }
+        _ => "unknown".to_string(),
+    }
+}
+
+fn take_max(target: &mut u64, usage: &Value, field: &str) {
+    if let Some(value) = usage.get(field).and_then(Value::as_u64) {
+        *target = (*target).max(value);
+    }
+}
+
+/// Folds one JSON payload (full response or server-sent event) into the observed usage.
+/// Handles Anthropic (`input_tokens`/`output_tokens`, `message_start`/`message_delta`),
+/// `OpenAI` (`prompt_tokens`/`completion_tokens`) and Ollama
+/// (`prompt_eval_count`/`eval_count`) shapes.
+fn absorb(observed: &mut ObservedUsage, payload: &Value) {
+    let message = payload.get("message").unwrap_or(payload);
+    if observed.model.is_empty() {
+        // Ollama chat responses carry the model next to, not inside, the message
+        if let Some(model) = message
+            .get("model")
+            .or_else(|| payload.get("model"))
+            .and_then(Value::as_str)
+        {
+            observed.model = model.to_string();
+        }
+    }
+
+    // Ollama's native API reports counts on the final (`done`) object
+    take_max(&mut observed.input_tokens, payload, "prompt_eval_count");
+    take_max(&mut observed.output_tokens, payload, "eval_count");
+
+    let Some(usage) = message.get("usage").or_else(|| payload.get("usage")) else {
+        return;
+    };
+    if usage.is_null() {
+        return;
+    }
+
+    take_max(&mut observed.input_tokens, usage, "input_tokens");
+    take_max(&mut observed.output_tokens, usage, "output_tokens");
+    take_max(
+        &mut observed.cache_creation_tokens,
+        usage,
+        "cache_creation_input_tokens",
+    );
+    take_max(
+        &mut observed.cache_read_tokens,
+        usage,
+        "cache_read_input_tokens",
+    );
+
+    if let Some(prompt) = usage.get("prompt_tokens").and_then(Value::as_u64) {
+        let cached = usage
+            .get("prompt_tokens_details")
+            .and_then(|d| d.get("cached_tokens"))
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        observed.input_tokens = observed.input_tokens.max(prompt.saturating_sub(cached));
+        observed.cache_read_tokens = observed.cache_read_tokens.max(cached);
+    }
+    take_max(&mut observed.output_tokens, usage, "completion_tokens");
+}
+
+/// Extracts token usage from a JSON, newline-delimited JSON (Ollama streaming)
+/// or server-sent-events response body.
+fn extract_usage(body: &[u8]) -> Option<ObservedUsage> {
+    let text = String::from_utf8_lossy(body);
+    let mut observed = ObservedUsage::default();
+
+    if text.trim_start().starts_with('{') {
+        if let Ok(payload) = serde_json::from_str::<Value>(text.trim()) {
+            absorb(&mut observed, &payload);
+        } else {
+            for line in text.lines() {
+                if let Ok(payload) = serde_json::from_str::<Value>(line.trim()) {
+                    absorb(&mut observed, &payload);
+                }
+            }
+        }
+    } else {
+        for line in text.lines() {
+            let Some(data) = line.strip_prefix("data:") else {
+                continue;
+            };
+            if let Ok(payload) = serde_json::from_str::<Value>(data.trim()) {
+                absorb(&mut observed, &payload);
+            }
+        }
+    }
+
+    (observed.input_tokens + observed.output_tokens > 0).then_some(observed)
+}
+
+async fn append_record(ctx: &ProxyContext, record: &ProxyRecord) -> Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+
+    let _guard = ctx.write_lock.lock().await;
+    if let Some(parent) = ctx.usage_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&ctx.usage_path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+async fn handle_connection(mut stream: TcpStream, ctx: Arc<ProxyContext>) -> Result<()> {
+    let request = local_http::read_request(&mut stream).await?;
+
+    let Some((provider, url)) = route(&ctx.config, &request.path) else {
+        let body = serde_json::json!({
+            "error": "Unknown route. Use /anthropic/..., /openai/... or /ollama/... as the base path."
+        });
+        return local_http::write_json(&mut stream, 404, &body).await;
+    };
+
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())?;
+    let mut builder = ctx.client.request(method, &url);
+    for (name, value) in &request.headers {
+        if !HOP_BY_HOP_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+    }
+
+    let mut upstream = match builder.body(request.body.clone()).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            let body = serde_json::json!({ "error": format!("Upstream request failed: {e}") });
+            return local_http::write_json(&mut stream, 502, &body).await;
+        }
+    };
+
+    let status = upstream.status();
+    let headers: Vec<(String, String)> = upstream
+        .headers()
+        .iter()
+        .filter(|(name, _)| !HOP_BY_HOP_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+        })
+        .collect();
+    local_http::write_chunked_head(&mut stream, status.as_u16(), &headers).await?;
+
+    let mut captured = Vec::new();
+    while let Some(chunk) = upstream.chunk().await? {
+        if captured.len() + chunk.len() <= MAX_CAPTURE_BYTES {
+            captured.extend_from_slice(&chunk);
+        }
+        local_http::write_chunk(&mut stream, &chunk).await?;
+    }
+    local_http::finish_chunks(&mut stream).await?;
+
+    if status.is_success() {
+        if let Some(observed) = extract_usage(&captured) {
+            let record = ProxyRecord {
+                timestamp: chrono::Local::now().to_rfc3339(),
+                provider: provider.to_string(),
+                key: key_label(&request),
+                model: if observed.model.is_empty() {
+                    "unknown".to_string()
+                } else {
+                    observed.model
+                },
+                input_tokens: observed.input_tokens,
+                output_tokens: observed.output_tokens,
+                cache_creation_tokens: observed.cache_creation_tokens,
+                cache_read_tokens: observed.cache_read_tokens,
+            };
+            append_record(&ctx, &record).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the local API proxy until the task is aborted.
+///
+/// Listens on `127.0.0.1` only; requests are forwarded to the configured
+/// upstream and token usage from successful responses is appended to the
+/// proxy usage log.
+///
+/// # Errors
+/// Returns an error if the listener cannot bind or the HTTP client cannot be built.
+pub async fn serve(config: ProxySourceConfig, config_dir: PathBuf) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", config.port)).await?;
+    let ctx = Arc::new(ProxyContext {
+        config,
+        client: http::client().clone(),
+        usage_path: usage_dir(&config_dir).join(USAGE_FILE),
+        write_lock: Mutex::new(()),
+    });
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let ctx = Arc::clone(&ctx);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, ctx).await {
+                tracing::error!("[Proxy] Request failed: {e}");
+            }
+        });
+    }
+}
+
+fn read_records(path: &Path) -> Vec<ProxyRecord> {
+    std::fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Converts a log line into a zero-cost usage record dated in local time.
+fn usage_record(record: ProxyRecord) -> Option<UsageRecord> {
+    let date = chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+        .ok()?
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d")
+        .to_string();
+    Some(UsageRecord {
+        date,
+        model: record.model,
+        input_tokens: record.input_tokens,
+        output_tokens: record.output_tokens,
+        cache_creation_input_tokens: record.cache_creation_tokens,
+        cache_read_input_tokens: record.cache_read_tokens,
+        cost: 0.0,
+        key: Some(record.key),
+    })
+}
+
+/// Reads the logged requests `keep` accepts by provider, without costs.
+async fn load_records(
+    config_dir: &Path,
+    keep: impl Fn(&str) -> bool + Send + 'static,
+) -> Result<Vec<UsageRecord>> {
+    let path = usage_dir(config_dir).join(USAGE_FILE);
+    Ok(tokio::task::spawn_blocking(move || {
+        read_records(&path)
+            .into_iter()
+            .filter(|record| keep(&record.provider))
+            .filter_map(usage_record)
+            .collect()
+    })
+    .await?)
+}
+
+/// Reads the logged requests forwarded to `provider`, without costs.
+///
+/// # Errors
+/// Returns an error if the log scan task fails.
+pub async fn provider_records(
+    config_dir: &Path,
+    provider: &'static str,
+) -> Result<Vec<UsageRecord>> {
+    load_records(config_dir, move |p| p == provider).await
+}
+
+/// Reads the proxy usage log and returns one report per API key. Requests
+/// forwarded to Ollama are left to the Ollama source.
+///
+/// # Errors
+/// Returns an error if the log scan task fails.
+#[tracing::instrument(name = "source.proxy", skip_all)]
+pub async fn fetch_usage(
+    config_dir: &Path,
+    pricing_timeout: Duration,
+) -> Result<Vec<SourceReport>> {
+    let records = load_records(config_dir, |p| p != OLLAMA_PROVIDER).await?;
+    if records.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let prices = pricing::get_prices(pricing_timeout).await;
+    let mut by_key: BTreeMap<String, Vec<UsageRecord>> = BTreeMap::new();
+    for mut record in records {
+        record.cost = prices.as_ref().map_or(0.0, |prices| {
+            pricing::calculate_fallback_cost(
+                &record.model,
+                record.input_tokens,
+                record.output_tokens,
+                prices,
+            )
+        });
+        let key = record.key.clone().unwrap_or_default();
+        by_key.entry(key).or_default().push(record);
+    }
+
+    Ok(by_key
+        .into_iter()
+        .map(|(key, records)| SourceReport {
+            id: format!("{SOURCE_ID_PREFIX}:{key}"),
+            label: format!("API Proxy ({key})"),
+            keys: sources::aggregate_records_by_key(&records),
+            credits: None,
+            daily: sources::aggregate_records(records),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with(headers: &[(&str, &str)]) -> HttpRequest {
+        HttpRequest {
+            method: "POST".to_string(),
+            path: "/anthropic/v1/messages".to_string(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_route() {
+        let config = ProxySourceConfig::default();
+        assert_eq!(
+            route(&config, "/anthropic/v1/messages"),
+            Some((
+                "anthropic",
+                "https://api.anthropic.com/v1/messages".to_string()
+            ))
+        );
+        assert_eq!(
+            route(&config, "/openai/v1/chat/completions"),
+            Some((
+                "openai",
+                "https://api.openai.com/v1/chat/completions".to_string()
+            ))
+        );
+        assert_eq!(
+            route(&config, "/ollama/api/chat"),
+            Some(("ollama", "http://127.0.0.1:11434/api/chat".to_string()))
+        );
+        assert!(route(&config, "/v1/messages").is_none());
+        assert!(route(&config, "/openaiv1").is_none());
+    }
+
+    #[test]
+    fn test_key_label_never_exposes_full_key() {
+        assert_eq!(
+            key_label(&request_with(&[("x-api-key", "sk-ant-api03-abcdWXYZ")])),
+            "…WXYZ"
+        );
+        assert_eq!(
+            key_label(&request_with(&[(
+                "Authorization",
+                "Bearer sk-proj-12345678"
+            )])),
+            "…5678"
+        );
+        assert_eq!(
+            key_label(&request_with(&[("x-api-key", "short")])),
+            "unknown"
+        );
+        assert_eq!(key_label(&request_with(&[])), "unknown");
+    }
+
+    #[test]
+    fn test_extract_usage_openai_json() {
+        let body = br#"{
+            "model": "gpt-4o-2024-08-06",
+            "usage": {
+                "prompt_tokens": 120,
+                "completion_tokens": 30,
+                "prompt_tokens_details": { "cached_tokens": 20 }
+            }
+        }"#;
+        let usage = extract_usage(body).expect("usage should be found");
+        assert_eq!(usage.model, "gpt-4o-2024-08-06");
+        assert_eq!(usage.input_tokens, 100);
+        assert_eq!(usage.cache_read_tokens, 20);
+        assert_eq!(usage.output_tokens, 30);
+    }
+
+    #[test]
+    fn test_extract_usage_anthropic_stream() {
+        let body = b"event: message_start\n\
+data: {\"type\":\"message_start\",\"message\":{\"model\":\"claude-sonnet-4-5\",\"usage\":{\"input_tokens\":25,\"cache_read_input_tokens\":100,\"output_tokens\":1}}}\n\n\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"Hi\"}}\n\n\
+event: message_delta\n\
+data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":15}}\n\n";
+        let usage = extract_usage(body).expect("usage should be found");
+        assert_eq!(usage.model, "claude-sonnet-4-5");
+        assert_eq!(usage.input_tokens, 25);
+        assert_eq!(usage.cache_read_tokens, 100);
+        assert_eq!(usage.output_tokens, 15);
+    }
+
+    #[test]
+    fn test_extract_usage_ollama() {
+        let body = br#"{"model":"llama3.2","response":"Hi","done":true,"prompt_eval_count":26,"eval_count":12}"#;
+        let usage = extract_usage(body).expect("usage should be found");
+        assert_eq!(usage.model, "llama3.2");
+        assert_eq!(usage.input_tokens, 26);
+        assert_eq!(usage.output_tokens, 12);
+
+        let stream = b"{\"model\":\"qwen2.5\",\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"done\":false}\n\
+{\"model\":\"qwen2.5\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"prompt_eval_count\":40,\"eval_count\":7}\n";
+        let usage = extract_usage(stream).expect("usage should be found");
+        assert_eq!(usage.model, "qwen2.5");
+        assert_eq!(usage.input_tokens, 40);
+        assert_eq!(usage.output_tokens, 7);
+    }
+
+    #[test]
+    fn test_extract_usage_without_usage() {
+        assert!(extract_usage(br#"{"error":{"type":"overloaded"}}"#).is_none());
+        assert!(extract_usage(b"not json").is_none());
+    }
+}
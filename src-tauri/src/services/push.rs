@@ -0,0 +1,216 @@
+use crate::config::{PushNotificationConfig, PushService};
+use crate::services::credentials::{self, Credential};
+use crate::services::http;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::time::Duration;
+
+const PUSHOVER_API_URL: &str = "https://api.pushover.net/1/messages.json";
+
+/// A notification delivered through the configured push service.
+#[derive(Debug, Clone)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    /// Bypasses quiet hours where the service supports it.
+    pub high_priority: bool,
+    /// Keeps alerting until acknowledged (Pushover emergency priority, ntfy urgent).
+    pub persistent: bool,
+}
+
+/// Seconds between repeats of a persistent Pushover alert, and how long it repeats.
+const PUSHOVER_RETRY_SECS: u32 = 300;
+const PUSHOVER_EXPIRE_SECS: u32 = 3600;
+
+/// Publish URL for the configured ntfy server and topic.
+fn ntfy_url(config: &PushNotificationConfig) -> Result<reqwest::Url> {
+    let topic = config.ntfy_topic.trim();
+    if topic.is_empty()
+        || topic.len() > 64
+        || !topic
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow::anyhow!(
+            "ntfy topic must be 1-64 letters, digits, '-' or '_'"
+        ));
+    }
+    let server = config.ntfy_server.trim().trim_end_matches('/');
+    let url = reqwest::Url::parse(&format!("{server}/{topic}"))
+        .context("ntfy server is not a valid URL")?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow::anyhow!("ntfy server must be an http(s) URL"));
+    }
+    Ok(url)
+}
+
+/// Checks the selected service's settings. The Pushover keys are in the
+/// keychain and are checked when sending.
+///
+/// # Errors
+/// Returns an error describing the first invalid setting.
+pub fn validate_config(config: &PushNotificationConfig) -> Result<()> {
+    match config.service {
+        PushService::Ntfy => ntfy_url(config).map(|_| ()),
+        PushService::Pushover => Ok(()),
+    }
+}
+
+fn pushover_form(
+    user_key: &str,
+    app_token: &str,
+    message: &PushMessage,
+) -> Vec<(&'static str, String)> {
+    let priority = if message.persistent {
+        "2"
+    } else if message.high_priority {
+        "1"
+    } else {
+        "0"
+    };
+    let mut form = vec![
+        ("token", app_token.to_string()),
+        ("user", user_key.to_string()),
+        ("title", message.title.clone()),
+        ("message", message.body.clone()),
+        ("priority", priority.to_string()),
+    ];
+    if message.persistent {
+        form.push(("retry", PUSHOVER_RETRY_SECS.to_string()));
+        form.push(("expire", PUSHOVER_EXPIRE_SECS.to_string()));
+    }
+    form
+}
+
+/// Sends `message` through the configured push service.
+///
+/// # Errors
+/// Returns an error if the config is invalid, the Pushover keys are not set
+/// or the service rejects the message.
+#[tracing::instrument(name = "service.push", skip_all)]
+pub async fn send(
+    config: &PushNotificationConfig,
+    message: &PushMessage,
+    timeout: Duration,
+) -> Result<()> {
+    validate_config(config)?;
+    let client = http::client();
+
+    let request = match config.service {
+        PushService::Ntfy => {
+            let mut request = client
+                .post(ntfy_url(config)?)
+                .header("Title", &message.title)
+                .header(
+                    "Priority",
+                    if message.persistent {
+                        "urgent"
+                    } else if message.high_priority {
+                        "high"
+                    } else {
+                        "default"
+                    },
+                )
+                .header("Tags", "money_with_wings")
+                .body(message.body.clone());
+            // Public topics need no token
+            let token = credentials::lookup(Credential::NtfyToken, config.ntfy_token.as_deref())
+                .await
+                .unwrap_or_default();
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            request
+        }
+        PushService::Pushover => {
+            let user_key = credentials::lookup(
+                Credential::PushoverUserKey,
+                config.pushover_user_key.as_deref(),
+            )
+            .await?;
+            let app_token = credentials::lookup(
+                Credential::PushoverAppToken,
+                config.pushover_app_token.as_deref(),
+            )
+            .await?;
+            let (Some(user_key), Some(app_token)) = (user_key, app_token) else {
+                return Err(anyhow::anyhow!(
+                    "Pushover user key and application token are required"
+                ));
+            };
+            client
+                .post(PUSHOVER_API_URL)
+                .form(&pushover_form(&user_key, &app_token, message))
+        }
+    };
+
+    let response = request.timeout(timeout).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        // Pushover explains rejected requests in an "errors" array
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        let detail = body["errors"]
+            .as_array()
+            .and_then(|errors| errors.first())
+            .and_then(Value::as_str)
+            .map(|e| format!(": {e}"))
+            .unwrap_or_default();
+        return Err(anyhow::anyhow!("Push service returned {status}{detail}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ntfy(server: &str, topic: &str) -> PushNotificationConfig {
+        PushNotificationConfig {
+            enabled: true,
+            ntfy_server: server.to_string(),
+            ntfy_topic: topic.to_string(),
+            ..PushNotificationConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_ntfy_url() {
+        let url = ntfy_url(&ntfy("https://ntfy.sh/", "my-spend_alerts")).unwrap();
+        assert_eq!(url.as_str(), "https://ntfy.sh/my-spend_alerts");
+
+        assert!(ntfy_url(&ntfy("https://ntfy.sh", "")).is_err());
+        assert!(ntfy_url(&ntfy("https://ntfy.sh", "a/b")).is_err());
+        assert!(ntfy_url(&ntfy("ftp://ntfy.example.com", "topic")).is_err());
+    }
+
+    #[test]
+    fn test_pushover_form() {
+        let config = PushNotificationConfig {
+            enabled: true,
+            service: PushService::Pushover,
+            ..PushNotificationConfig::default()
+        };
+        // The keys are looked up in the keychain when sending
+        assert!(validate_config(&config).is_ok());
+
+        let message = PushMessage {
+            title: "Title".to_string(),
+            body: "Body".to_string(),
+            high_priority: true,
+            persistent: false,
+        };
+        let form = pushover_form("user", "app", &message);
+        assert!(form.contains(&("token", "app".to_string())));
+        assert!(form.contains(&("user", "user".to_string())));
+        assert!(form.contains(&("priority", "1".to_string())));
+        assert!(!form.iter().any(|(key, _)| *key == "retry"));
+
+        let persistent = PushMessage {
+            persistent: true,
+            ..message
+        };
+        let form = pushover_form("user", "app", &persistent);
+        assert!(form.contains(&("priority", "2".to_string())));
+        assert!(form.contains(&("expire", PUSHOVER_EXPIRE_SECS.to_string())));
+    }
+}
@@ -0,0 +1,340 @@
+use crate::config::RemoteSnapshotConfig;
+use crate::services::{http, keychain};
+use crate::types::{DailyUsage, ModelUsage, TrendInfo, UsageData, UsageSummary};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+/// Keychain account holding the snapshot passphrase.
+pub const PASSPHRASE_ACCOUNT: &str = "remote-snapshot";
+pub const MIN_PASSPHRASE_LEN: usize = 12;
+
+const ENVELOPE_VERSION: u32 = 1;
+const KDF_NAME: &str = "pbkdf2-sha256";
+const CIPHER_NAME: &str = "aes-256-gcm";
+const KDF_ITERATIONS: u32 = 310_000;
+/// Upper bound accepted when opening, so a hostile relay can't stall readers.
+const MAX_KDF_ITERATIONS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+/// Bound to the ciphertext so other data encrypted with the same passphrase
+/// can't be passed off as a snapshot.
+const AAD: &[u8] = b"tokenmeter-snapshot";
+/// Number of most recent days included in the snapshot.
+const SNAPSHOT_DAYS: usize = 7;
+
+/// Current spend as shown to a companion device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    /// RFC 3339 timestamp the snapshot was taken at
+    pub generated_at: String,
+    pub today: UsageData,
+    pub this_month: UsageData,
+    pub daily_usage: Vec<DailyUsage>,
+    pub model_breakdown: Vec<ModelUsage>,
+    pub trend: Option<TrendInfo>,
+}
+
+impl Snapshot {
+    #[must_use]
+    pub fn from_summary(summary: &UsageSummary) -> Self {
+        let mut daily_usage = summary.daily_usage.clone();
+        daily_usage.sort_by(|a, b| a.date.cmp(&b.date));
+        let start = daily_usage.len().saturating_sub(SNAPSHOT_DAYS);
+        daily_usage.drain(..start);
+        Self {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            today: summary.today.clone(),
+            this_month: summary.this_month.clone(),
+            daily_usage,
+            model_breakdown: summary.model_breakdown.clone(),
+            trend: summary.trend.clone(),
+        }
+    }
+}
+
+/// Self-describing encrypted payload uploaded to the relay. Everything needed
+/// to decrypt it except the passphrase is included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    pub version: u32,
+    pub kdf: String,
+    pub iterations: u32,
+    pub salt: String,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey> {
+    let iterations = NonZeroU32::new(iterations).context("KDF iterations must be non-zero")?;
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow::anyhow!("Invalid key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn seal(plaintext: &[u8], passphrase: &str, iterations: u32) -> Result<Envelope> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|()| rng.fill(&mut nonce))
+        .map_err(|_| anyhow::anyhow!("Failed to generate random bytes"))?;
+
+    let key = derive_key(passphrase, &salt, iterations)?;
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(AAD),
+        &mut in_out,
+    )
+    .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    Ok(Envelope {
+        version: ENVELOPE_VERSION,
+        kdf: KDF_NAME.to_string(),
+        iterations,
+        salt: STANDARD.encode(salt),
+        cipher: CIPHER_NAME.to_string(),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(in_out),
+    })
+}
+
+/// Encrypts `plaintext` with a key derived from `passphrase` and a fresh salt.
+///
+/// # Errors
+/// Returns an error if the system random generator fails.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Envelope> {
+    seal(plaintext, passphrase, KDF_ITERATIONS)
+}
+
+/// Decrypts an envelope produced by [`encrypt`].
+///
+/// # Errors
+/// Returns an error for unsupported envelope formats, a wrong passphrase or
+/// tampered data.
+pub fn decrypt(envelope: &Envelope, passphrase: &str) -> Result<Vec<u8>> {
+    if envelope.version != ENVELOPE_VERSION
+        || envelope.kdf != KDF_NAME
+        || envelope.cipher != CIPHER_NAME
+    {
+        return Err(anyhow::anyhow!(
+            "Unsupported snapshot format (version {}, {}, {})",
+            envelope.version,
+            envelope.kdf,
+            envelope.cipher
+        ));
+    }
+    if envelope.iterations > MAX_KDF_ITERATIONS {
+        return Err(anyhow::anyhow!("Snapshot KDF iteration count is too high"));
+    }
+
+    let salt = STANDARD.decode(&envelope.salt).context("Invalid salt")?;
+    let nonce: [u8; NONCE_LEN] = STANDARD
+        .decode(&envelope.nonce)
+        .ok()
+        .and_then(|nonce| nonce.try_into().ok())
+        .context("Invalid nonce")?;
+    let mut in_out = STANDARD
+        .decode(&envelope.ciphertext)
+        .context("Invalid ciphertext")?;
+
+    let key = derive_key(passphrase, &salt, envelope.iterations)?;
+    let plaintext = key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(AAD),
+            &mut in_out,
+        )
+        .map_err(|_| anyhow::anyhow!("Wrong passphrase or corrupted snapshot"))?;
+    Ok(plaintext.to_vec())
+}
+
+/// Decrypts and parses an uploaded snapshot, as a companion device would.
+///
+/// # Errors
+/// Returns an error if the envelope can't be decrypted or isn't a snapshot.
+pub fn open_snapshot(envelope_json: &str, passphrase: &str) -> Result<Snapshot> {
+    let envelope: Envelope =
+        serde_json::from_str(envelope_json).context("Invalid snapshot envelope")?;
+    let plaintext = decrypt(&envelope, passphrase)?;
+    serde_json::from_slice(&plaintext).context("Invalid snapshot contents")
+}
+
+/// Checks a passphrase is long enough to resist offline guessing.
+///
+/// # Errors
+/// Returns an error if the passphrase is too short.
+pub fn validate_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(anyhow::anyhow!(
+            "Passphrase must be at least {MIN_PASSPHRASE_LEN} characters"
+        ));
+    }
+    Ok(())
+}
+
+/// Requires HTTPS, except for a relay on this machine.
+///
+/// # Errors
+/// Returns an error if the URL is missing or not allowed.
+pub fn validate_config(config: &RemoteSnapshotConfig) -> Result<()> {
+    let url = reqwest::Url::parse(config.url.trim()).context("Snapshot URL is not a valid URL")?;
+    let is_loopback = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if is_loopback => Ok(()),
+        _ => Err(anyhow::anyhow!(
+            "Snapshot URL must use https:// (http:// is only allowed for localhost)"
+        )),
+    }
+}
+
+/// Encrypts the current usage with the keychain passphrase and uploads it.
+///
+/// # Errors
+/// Returns an error if no passphrase is set, the config is invalid or the
+/// upload fails.
+#[tracing::instrument(name = "service.remote_snapshot", skip_all)]
+pub async fn push_snapshot(
+    config: &RemoteSnapshotConfig,
+    summary: &UsageSummary,
+    timeout: Duration,
+) -> Result<()> {
+    validate_config(config)?;
+    let passphrase = keychain::get_secret(PASSPHRASE_ACCOUNT)
+        .await?
+        .context("No snapshot passphrase set")?;
+
+    let plaintext = serde_json::to_vec(&Snapshot::from_summary(summary))?;
+    // Key derivation is deliberately slow; keep it off the async workers
+    let envelope = tokio::task::spawn_blocking(move || encrypt(&plaintext, &passphrase)).await??;
+
+    let response = http::client()
+        .put(config.url.trim())
+        .timeout(timeout)
+        .json(&envelope)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("Snapshot upload returned {status}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RefreshMeta;
+
+    /// Keeps key derivation fast in debug builds.
+    const TEST_ITERATIONS: u32 = 1_000;
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    fn day(date: &str) -> DailyUsage {
+        DailyUsage {
+            date: date.to_string(),
+            cost: 1.0,
+            input_tokens: 10,
+            output_tokens: 5,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            models: Vec::new(),
+        }
+    }
+
+    fn summary() -> UsageSummary {
+        UsageSummary {
+            today: UsageData::default(),
+            this_month: UsageData::default(),
+            daily_usage: (1..=10)
+                .rev()
+                .map(|d| day(&format!("2025-01-{d:02}")))
+                .collect(),
+            model_breakdown: Vec::new(),
+            sources: Vec::new(),
+            trend: None,
+            limits: None,
+            active_block_end: None,
+            meta: RefreshMeta::default(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let snapshot = Snapshot::from_summary(&summary());
+        assert_eq!(snapshot.daily_usage.len(), SNAPSHOT_DAYS);
+        assert_eq!(snapshot.daily_usage[0].date, "2025-01-04");
+
+        let plaintext = serde_json::to_vec(&snapshot).unwrap();
+        let envelope = seal(&plaintext, PASSPHRASE, TEST_ITERATIONS).unwrap();
+        assert!(!envelope.ciphertext.contains("2025-01-04"));
+
+        let json = serde_json::to_string(&envelope).unwrap();
+        let opened = open_snapshot(&json, PASSPHRASE).unwrap();
+        assert_eq!(opened.daily_usage.len(), SNAPSHOT_DAYS);
+        assert_eq!(opened.generated_at, snapshot.generated_at);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_passphrase_and_tampering() {
+        let envelope = seal(b"secret", PASSPHRASE, TEST_ITERATIONS).unwrap();
+        assert!(decrypt(&envelope, "wrong passphrase!").is_err());
+
+        let mut tampered = envelope.clone();
+        let mut bytes = STANDARD.decode(&tampered.ciphertext).unwrap();
+        bytes[0] ^= 1;
+        tampered.ciphertext = STANDARD.encode(bytes);
+        assert!(decrypt(&tampered, PASSPHRASE).is_err());
+
+        let mut unsupported = envelope.clone();
+        unsupported.version = 2;
+        assert!(decrypt(&unsupported, PASSPHRASE).is_err());
+
+        assert_eq!(decrypt(&envelope, PASSPHRASE).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_encrypt_uses_fresh_salt_and_nonce() {
+        let a = seal(b"same", PASSPHRASE, TEST_ITERATIONS).unwrap();
+        let b = seal(b"same", PASSPHRASE, TEST_ITERATIONS).unwrap();
+        assert_ne!(a.salt, b.salt);
+        assert_ne!(a.nonce, b.nonce);
+        assert_ne!(a.ciphertext, b.ciphertext);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let config = |url: &str| RemoteSnapshotConfig {
+            enabled: true,
+            url: url.to_string(),
+        };
+        assert!(validate_config(&config("https://relay.example.com/u/abc")).is_ok());
+        assert!(validate_config(&config("http://localhost:8080/snapshot")).is_ok());
+        assert!(validate_config(&config("http://relay.example.com/u/abc")).is_err());
+        assert!(validate_config(&config("ftp://example.com/file")).is_err());
+        assert!(validate_config(&config("")).is_err());
+    }
+
+    #[test]
+    fn test_validate_passphrase() {
+        assert!(validate_passphrase("short").is_err());
+        assert!(validate_passphrase(PASSPHRASE).is_ok());
+    }
+}
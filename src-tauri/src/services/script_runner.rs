@@ -1,32 +1,236 @@
-use crate::config::ApiProvider;
-use crate::services::shell_utils;
-use crate::types::{ProviderTrayStats, ProviderUsageResult};
-use anyhow::Result;
-use boa_engine::{Context, Source};
-use std::process::Command;
-use std::sync::mpsc;
-use std::thread;
+use crate::config::{
+    ApiProvider, FetchStep, GraphqlQuery, HttpMethod, PaginationStrategy, ProviderPagination,
+    ProviderRequest,
+};
+use crate::services::fx::{self, FxRates};
+use crate::services::json_schema::{self, Field, Shape};
+use crate::services::{http, json_path, oauth, shell_utils};
+use crate::types::ProviderUsageResult;
+use anyhow::{Context as _, Result};
+use boa_engine::{Context, JsString, JsValue, Script, Source};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Read as _;
+use std::path::Path;
+use std::pin::pin;
+use std::process::Stdio;
+use std::task::{Poll, Waker};
 use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
 
-const SCRIPT_TIMEOUT: Duration = Duration::from_secs(5);
 const MAX_SCRIPT_LENGTH: usize = 10_000;
 
-/// Runs a JavaScript transform script on JSON data.
+/// Iterations a single loop of a transform may run before it is stopped.
+const MAX_LOOP_ITERATIONS: u64 = 1_000_000;
+
+/// Nested calls a transform may make before it is stopped.
+const MAX_RECURSION_DEPTH: usize = 256;
+
+/// Work, in Boa's cost units of roughly one per bytecode instruction, a
+/// transform may do in total across all its loops and calls.
+const MAX_FUEL: u64 = 50_000_000;
+
+/// Fuel spent between checks of the total.
+const FUEL_STEP: u32 = 100_000;
+
+/// Wall-clock time a transform worker gets before it is killed. This also
+/// stops work the fuel can't see, such as builtins (`'x'.repeat(1e9)`) and
+/// the callbacks they run (`items.map(...)`).
+const TRANSFORM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// First argument that makes the app run one transform for
+/// [`run_transform_isolated`] instead of starting.
+pub const TRANSFORM_WORKER_ARG: &str = "--transform-worker";
+
+/// Shape of a provider result, mirroring [`ProviderUsageResult`].
+static PROVIDER_RESULT_SHAPE: Shape = Shape::Object(&[
+    Field::optional("cost", Shape::Number),
+    Field::optional("tokens", Shape::Unsigned),
+    Field::optional("used", Shape::Number),
+    Field::optional("total", Shape::Number),
+    Field::defaulted("daily", Shape::Array(&PROVIDER_DAILY_SHAPE)),
+]);
+
+static PROVIDER_DAILY_SHAPE: Shape = Shape::Object(&[
+    Field::required("date", Shape::String),
+    Field::optional("cost", Shape::Number),
+    Field::optional("tokens", Shape::Unsigned),
+]);
+
+/// Fields of [`ProviderUsageResult`]; a result must include at least one.
+const PROVIDER_RESULT_FIELDS: &[&str] = &["cost", "tokens", "used", "total", "daily"];
+
+/// Parses the result of a provider (`source` names where it came from, e.g.
+/// "provider transform"), with errors that name the offending field such as
+/// "used: expected number, found string".
+///
+/// # Errors
+/// Returns an error if `text` is not an object matching [`ProviderUsageResult`]
+/// or includes none of its fields.
+pub fn parse_provider_result(text: &str, source: &str) -> Result<ProviderUsageResult> {
+    // JSON.stringify(undefined), from a transform without a return
+    if text.trim() == "undefined" {
+        return Err(anyhow::anyhow!(
+            "Invalid {source} output: nothing was returned; return an object with {}",
+            PROVIDER_RESULT_FIELDS.join(", ")
+        ));
+    }
+    let result = json_schema::parse(text, &PROVIDER_RESULT_SHAPE, source)?;
+
+    let value: Value = serde_json::from_str(text)?;
+    let keys: Vec<&str> = value
+        .as_object()
+        .map(|object| object.keys().map(String::as_str).collect())
+        .unwrap_or_default();
+    if !keys.iter().any(|key| PROVIDER_RESULT_FIELDS.contains(key)) {
+        let found = if keys.is_empty() {
+            "an empty object".to_string()
+        } else {
+            keys.join(", ")
+        };
+        return Err(anyhow::anyhow!(
+            "Invalid {source} output: expected at least one of {}, found {found}",
+            PROVIDER_RESULT_FIELDS.join(", ")
+        ));
+    }
+    Ok(result)
+}
+
+/// `console`, which Boa does not provide, collecting up to 200 calls in
+/// `__tokenmeterConsole`, one line each, with non-string arguments written as
+/// JSON and warnings and errors prefixed.
+const CONSOLE_SHIM: &str = r"var __tokenmeterConsole = [];
+        var console = (function () {
+            function format(value) {
+                if (typeof value === 'string') return value;
+                try {
+                    var json = JSON.stringify(value);
+                    return json === undefined ? String(value) : json;
+                } catch (e) {
+                    return String(value);
+                }
+            }
+            function logger(prefix) {
+                return function () {
+                    if (__tokenmeterConsole.length >= 200) return;
+                    var parts = Array.prototype.map.call(arguments, format);
+                    __tokenmeterConsole.push(prefix + parts.join(' '));
+                };
+            }
+            return {
+                log: logger(''),
+                info: logger(''),
+                debug: logger(''),
+                warn: logger('[warn] '),
+                error: logger('[error] '),
+            };
+        })();";
+
+/// Helpers preloaded for transforms, so common chores don't eat into the
+/// script length limit:
+/// - `get(object, "a.b.c", fallback)` reads a nested field
+/// - `sum(items, key)` adds numbers, or a field (path or function) of each item
+/// - `formatNumber(value, digits)` groups thousands, e.g. "1,234.50"
+/// - `parseDate(value)` reads ISO strings and epoch seconds or milliseconds
+///   into a `Date`, or `null`
+/// - `convert(value, from, to)` between `usd`, `cents`, `millicents` and
+///   `microusd`, or `tokens`, `k`, `m` and `b`
+const TRANSFORM_HELPERS: &str = r"function get(object, path, fallback) {
+            var value = String(path).split('.').reduce(function (current, key) {
+                return current == null ? undefined : current[key];
+            }, object);
+            return value === undefined ? fallback : value;
+        }
+        function sum(items, key) {
+            var total = 0;
+            for (var i = 0; i < (items || []).length; i++) {
+                var value = items[i];
+                if (typeof key === 'function') value = key(value);
+                else if (key != null) value = get(value, key);
+                total += Number(value) || 0;
+            }
+            return total;
+        }
+        function formatNumber(value, digits) {
+            var number = Number(value);
+            if (!isFinite(number)) return String(value);
+            if (digits == null) digits = Number.isInteger(number) ? 0 : 2;
+            var parts = Math.abs(number).toFixed(digits).split('.');
+            parts[0] = parts[0].replace(/\B(?=(\d{3})+(?!\d))/g, ',');
+            return (number < 0 ? '-' : '') + parts.join('.');
+        }
+        function parseDate(value) {
+            if (value instanceof Date) return value;
+            if (typeof value === 'string' && /^\d+(\.\d+)?$/.test(value.trim())) {
+                value = Number(value);
+            }
+            // Epoch numbers below 1e11 are seconds, which stay before 5138
+            if (typeof value === 'number') value = value < 1e11 ? value * 1000 : value;
+            var date = new Date(value);
+            return isNaN(date.getTime()) ? null : date;
+        }
+        var __tokenmeterUnits = {
+            usd: ['money', 1],
+            cents: ['money', 0.01],
+            millicents: ['money', 1e-5],
+            microusd: ['money', 1e-6],
+            tokens: ['count', 1],
+            k: ['count', 1e3],
+            m: ['count', 1e6],
+            b: ['count', 1e9],
+        };
+        function convert(value, from, to) {
+            var source = __tokenmeterUnits[String(from).toLowerCase()];
+            var target = __tokenmeterUnits[String(to).toLowerCase()];
+            if (!source) throw new Error('Unknown unit: ' + from);
+            if (!target) throw new Error('Unknown unit: ' + to);
+            if (source[0] !== target[0]) throw new Error('Cannot convert ' + from + ' to ' + to);
+            return Number(value) * source[1] / target[1];
+        }";
+
+/// Lines collected by [`CONSOLE_SHIM`] as a JSON array, or an empty one if
+/// the script failed before the shim was set up.
+const CONSOLE_LINES: &str =
+    "typeof __tokenmeterConsole === 'object' ? JSON.stringify(__tokenmeterConsole) : '[]'";
+
+/// Runs a JavaScript transform script on JSON data, on the calling thread,
+/// with [`TRANSFORM_HELPERS`] and a `console` that collects what is logged.
 ///
 /// # Security Notes
 /// - Script length is limited to prevent resource exhaustion
-/// - Script execution runs in a separate thread with timeout enforcement
-/// - If timeout is exceeded, the thread is abandoned (`boa_engine` doesn't support interruption)
-/// - For production use with untrusted scripts, consider running in a separate
-///   process with OS-level resource limits
+/// - Loops and recursion are bounded by Boa's runtime limits, and all the
+///   script's own code by [`MAX_FUEL`], none of which scripts can catch
+/// - Builtins and the callbacks they run are not metered, so scripts from
+///   providers should go through [`run_transform_isolated`], which can kill
+///   them
 ///
 /// # Errors
 /// Returns an error if:
 /// - Script exceeds maximum length
 /// - JSON data is invalid
 /// - Script execution fails
-/// - Script execution times out
+/// - Script exceeds a loop, recursion or fuel limit
 pub fn run_transform_script(script: &str, json_data: &str) -> Result<String> {
+    run_transform_script_with_logs(script, json_data).0
+}
+
+/// Like [`run_transform_script`], also returning the lines the script logged
+/// through `console`, which are kept when it fails too.
+pub fn run_transform_script_with_logs(
+    script: &str,
+    json_data: &str,
+) -> (Result<String>, Vec<String>) {
+    let mut context = Context::default();
+    let result = eval_transform(&mut context, script, json_data);
+    let logs = console_logs(&mut context);
+    (result, logs)
+}
+
+fn eval_transform(context: &mut Context, script: &str, json_data: &str) -> Result<String> {
     if script.len() > MAX_SCRIPT_LENGTH {
         return Err(anyhow::anyhow!(
             "Script exceeds maximum length of {MAX_SCRIPT_LENGTH} characters"
@@ -36,43 +240,481 @@ pub fn run_transform_script(script: &str, json_data: &str) -> Result<String> {
     serde_json::from_str::<serde_json::Value>(json_data)
         .map_err(|e| anyhow::anyhow!("Invalid JSON data: {e}"))?;
 
+    // Preludes follow the script so error line numbers match what was written
     let full_script = format!(
         r"
         var response = {json_data};
         var transform = {script};
+        {CONSOLE_SHIM}
+        {TRANSFORM_HELPERS}
         JSON.stringify(transform(response));
         "
     );
 
-    let (tx, rx) = mpsc::channel();
+    let limits = context.runtime_limits_mut();
+    limits.set_loop_iteration_limit(MAX_LOOP_ITERATIONS);
+    limits.set_recursion_limit(MAX_RECURSION_DEPTH);
+
+    let result = eval_with_fuel(context, &full_script)?;
+    result
+        .to_string(context)
+        .map_err(|e| anyhow::anyhow!("Failed to convert result: {e}"))
+        .map(|s| s.to_std_string_escaped())
+}
+
+/// Evaluates `source`, stopping it once it has spent [`MAX_FUEL`]. Boa's
+/// budgeted evaluation yields every [`FUEL_STEP`] units, so polling it by hand
+/// counts the fuel; it never waits on anything else.
+fn eval_with_fuel(context: &mut Context, source: &str) -> Result<JsValue> {
+    let script = Script::parse(Source::from_bytes(source), None, context)
+        .map_err(|e| anyhow::anyhow!("Script execution error: {e}"))?;
+    let mut run = pin!(script.evaluate_async_with_budget(context, FUEL_STEP));
+    let mut poll_context = std::task::Context::from_waker(Waker::noop());
+    let mut spent = 0;
+    loop {
+        if let Poll::Ready(result) = run.as_mut().poll(&mut poll_context) {
+            return result.map_err(|e| anyhow::anyhow!("Script execution error: {e}"));
+        }
+        spent += u64::from(FUEL_STEP);
+        if spent >= MAX_FUEL {
+            return Err(anyhow::anyhow!(
+                "Script execution error: exceeded the limit of {MAX_FUEL} steps"
+            ));
+        }
+    }
+}
+
+/// What [`run_transform_isolated`] sends a worker on stdin.
+#[derive(Serialize, Deserialize)]
+struct WorkerInput {
+    script: String,
+    json: String,
+}
+
+/// What a worker writes to stdout: the transform's result or error, and the
+/// lines it logged.
+#[derive(Default, Serialize, Deserialize)]
+struct WorkerOutput {
+    result: Option<String>,
+    error: Option<String>,
+    console: Vec<String>,
+}
+
+/// Whether this process was started as a transform worker.
+#[must_use]
+pub fn is_transform_worker() -> bool {
+    std::env::args().nth(1).as_deref() == Some(TRANSFORM_WORKER_ARG)
+}
+
+/// Runs the transform a worker started with [`TRANSFORM_WORKER_ARG`] reads
+/// from stdin, writing the outcome to stdout.
+pub fn run_transform_worker() {
+    let mut input = String::new();
+    let output = match std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| Ok(serde_json::from_str::<WorkerInput>(&input)?))
+    {
+        Ok(input) => {
+            let (result, console) = run_transform_script_with_logs(&input.script, &input.json);
+            WorkerOutput {
+                error: result.as_ref().err().map(ToString::to_string),
+                result: result.ok(),
+                console,
+            }
+        }
+        Err(e) => WorkerOutput {
+            error: Some(format!("Invalid transform worker input: {e}")),
+            ..WorkerOutput::default()
+        },
+    };
+    println!("{}", serde_json::to_string(&output).unwrap_or_default());
+}
+
+/// Runs a transform in a `worker` process (the app's own executable, started
+/// with [`TRANSFORM_WORKER_ARG`]) and returns the result and the lines logged,
+/// like [`run_transform_script_with_logs`].
+///
+/// The worker is killed after `timeout` or when the returned future is
+/// dropped, so no script can pin a thread or exhaust the app's memory.
+pub async fn run_transform_in_worker(
+    worker: &Path,
+    script: &str,
+    json_data: &str,
+    timeout: Duration,
+) -> (Result<String>, Vec<String>) {
+    let input = WorkerInput {
+        script: script.to_string(),
+        json: json_data.to_string(),
+    };
+    let run = async {
+        let input = serde_json::to_vec(&input)?;
+        let mut child = Command::new(worker)
+            .arg(TRANSFORM_WORKER_ARG)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start the transform worker")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&input).await?;
+        }
+        let output = child.wait_with_output().await?;
+        serde_json::from_slice::<WorkerOutput>(&output.stdout).map_err(|_| {
+            anyhow::anyhow!(
+                "Transform worker exited with {} without a result",
+                output.status
+            )
+        })
+    };
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return (Err(e), Vec::new()),
+        Err(_) => {
+            return (
+                Err(anyhow::anyhow!(
+                    "Script execution error: stopped after {}s",
+                    timeout.as_secs()
+                )),
+                Vec::new(),
+            )
+        }
+    };
+    let result = match (output.result, output.error) {
+        (_, Some(error)) => Err(anyhow::anyhow!(error)),
+        (Some(result), None) => Ok(result),
+        (None, None) => Err(anyhow::anyhow!("Transform worker returned no result")),
+    };
+    (result, output.console)
+}
+
+/// [`run_transform_in_worker`] on the running executable, with
+/// [`TRANSFORM_TIMEOUT`].
+pub async fn run_transform_isolated(
+    script: &str,
+    json_data: &str,
+) -> (Result<String>, Vec<String>) {
+    match std::env::current_exe() {
+        Ok(worker) => run_transform_in_worker(&worker, script, json_data, TRANSFORM_TIMEOUT).await,
+        Err(e) => (
+            Err(anyhow::anyhow!("Failed to find the transform worker: {e}")),
+            Vec::new(),
+        ),
+    }
+}
+
+fn console_logs(context: &mut Context) -> Vec<String> {
+    context
+        .eval(Source::from_bytes(CONSOLE_LINES))
+        .ok()
+        .and_then(|value| value.as_string().map(JsString::to_std_string_escaped))
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Parses `script` the way [`run_transform_script`] embeds it, without
+/// running it, to catch syntax errors while the transform is being edited.
+///
+/// # Errors
+/// Returns an error if the script is too long or is not valid JavaScript.
+pub fn check_transform_syntax(script: &str) -> Result<()> {
+    if script.len() > MAX_SCRIPT_LENGTH {
+        return Err(anyhow::anyhow!(
+            "Script exceeds maximum length of {MAX_SCRIPT_LENGTH} characters"
+        ));
+    }
+    let full_script = format!("var transform = {script};");
+    let mut context = Context::default();
+    Script::parse(Source::from_bytes(&full_script), None, &mut context)
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("Syntax error: {e}"))
+}
+
+/// JSON body of a GraphQL request, with `${VAR}` in its variables replaced
+/// from `env`.
+fn graphql_body(graphql: &GraphqlQuery, env: &HashMap<String, String>) -> Result<Value> {
+    let variables = match graphql.variables.as_deref().map(str::trim) {
+        Some(text) if !text.is_empty() => {
+            let text = shell_utils::substitute_env_vars(text, env);
+            let variables: Value =
+                serde_json::from_str(&text).context("GraphQL variables are not valid JSON")?;
+            if !variables.is_object() {
+                return Err(anyhow::anyhow!("GraphQL variables must be a JSON object"));
+            }
+            variables
+        }
+        _ => Value::Object(serde_json::Map::new()),
+    };
+    Ok(serde_json::json!({ "query": graphql.query, "variables": variables }))
+}
+
+/// Fails on a GraphQL response that reports errors without returning data.
+fn check_graphql_response(text: &str) -> Result<()> {
+    let Ok(response) = serde_json::from_str::<Value>(text) else {
+        return Ok(());
+    };
+    let no_data = response.get("data").is_none_or(Value::is_null);
+    match response.get("errors").and_then(Value::as_array) {
+        Some(errors) if no_data && !errors.is_empty() => {
+            let message = errors[0]["message"].as_str().unwrap_or("unknown error");
+            Err(anyhow::anyhow!("GraphQL error: {message}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Sends one page of `request` to `url` and returns the response body.
+async fn send_page(
+    request: &ProviderRequest,
+    url: &str,
+    env: &HashMap<String, String>,
+    timeout: Duration,
+) -> Result<String> {
+    let method = if request.graphql.is_some() {
+        HttpMethod::Post
+    } else {
+        request.method
+    };
+    let mut builder = match method {
+        HttpMethod::Get => http::client().get(url),
+        HttpMethod::Post => http::client().post(url),
+    }
+    .timeout(timeout);
+    for (name, value) in &request.headers {
+        builder = builder.header(name, shell_utils::substitute_env_vars(value, env));
+    }
+    if let Some(graphql) = &request.graphql {
+        builder = builder.json(&graphql_body(graphql, env)?);
+    } else if let Some(body) = &request.body {
+        builder = builder.body(shell_utils::substitute_env_vars(body, env));
+    }
+
+    let response = builder.send().await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("HTTP {status}: {text}"));
+    }
+    if request.graphql.is_some() {
+        check_graphql_response(&text)?;
+    }
+    Ok(text)
+}
+
+/// Dotted field path (`data.items`, `meta.0.next`) as a JSON pointer.
+fn json_pointer(path: &str) -> String {
+    path.split('.').filter(|segment| !segment.is_empty()).fold(
+        String::new(),
+        |mut pointer, segment| {
+            pointer.push('/');
+            pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+            pointer
+        },
+    )
+}
+
+/// Non-empty string or number at `path` in `page`, for cursors and next URLs.
+fn page_value(page: &Value, path: &str) -> Option<String> {
+    match page.pointer(&json_pointer(path))? {
+        Value::String(value) if !value.trim().is_empty() => Some(value.trim().to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
 
-    thread::spawn(move || {
-        let mut context = Context::default();
-        let result = context
-            .eval(Source::from_bytes(&full_script))
-            .map_err(|e| anyhow::anyhow!("Script execution error: {e:?}"))
-            .and_then(|result| {
-                result
-                    .to_string(&mut context)
-                    .map_err(|e| anyhow::anyhow!("Failed to convert result: {e:?}"))
-                    .map(|s| s.to_std_string_escaped())
-            });
-        let _ = tx.send(result);
-    });
+/// `url` with the query parameter `name` set to `value`, replacing any earlier value.
+fn with_query_param(url: &Url, name: &str, value: &str) -> Url {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != name)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(name, value);
+    url
+}
 
-    rx.recv_timeout(SCRIPT_TIMEOUT)
-        .map_err(|_| anyhow::anyhow!("Script execution exceeded timeout of {SCRIPT_TIMEOUT:?}"))?
+/// URL of the page after `page`, the `number`th, or `None` when it was the last.
+fn next_page_url(
+    pagination: &ProviderPagination,
+    first: &Url,
+    current: &Url,
+    page: &Value,
+    number: u32,
+    had_items: bool,
+) -> Result<Option<Url>> {
+    let next = match pagination.strategy {
+        PaginationStrategy::Cursor => page_value(page, &pagination.next_path)
+            .map(|cursor| with_query_param(first, &pagination.param, &cursor)),
+        PaginationStrategy::Page => {
+            had_items.then(|| with_query_param(first, &pagination.param, &(number + 1).to_string()))
+        }
+        PaginationStrategy::NextUrl => {
+            let Some(next) = page_value(page, &pagination.next_path) else {
+                return Ok(None);
+            };
+            let next = current.join(&next)?;
+            // Headers often carry credentials, so never send them elsewhere
+            if next.origin() != first.origin() {
+                return Err(anyhow::anyhow!(
+                    "Next page URL {next} is not on the same origin as the request"
+                ));
+            }
+            Some(next)
+        }
+    };
+    // A repeated URL would loop until max_pages
+    Ok(next.filter(|next| next != current))
 }
 
-/// Executes a Provider script and returns tray display format.
+/// Sends a provider's declarative request with the shared HTTP client,
+/// substituting `${VAR}` from `env`, and returns the response body.
+///
+/// With pagination, every page is fetched and their items combined into the
+/// first.
 ///
 /// # Errors
-/// Returns an error if the fetch script fails or transform script fails.
-pub fn fetch_provider_for_tray(provider: &ApiProvider) -> Result<ProviderTrayStats> {
-    let parts =
-        shell_utils::parse_command(&provider.fetch_script, &provider.env).ok_or_else(|| {
-            anyhow::anyhow!("Invalid fetch script: unmatched quotes or escape sequences")
-        })?;
+/// Returns an error if a request fails, its status is not 2xx, or a page lacks
+/// the paginated items.
+#[allow(clippy::implicit_hasher)]
+pub async fn send_request(
+    request: &ProviderRequest,
+    env: &HashMap<String, String>,
+    timeout: Duration,
+) -> Result<String> {
+    let url = shell_utils::substitute_env_vars(&request.url, env);
+    let Some(pagination) = &request.pagination else {
+        return send_page(request, &url, env, timeout).await;
+    };
+
+    let first = Url::parse(&url)?;
+    let mut url = match pagination.strategy {
+        PaginationStrategy::Page => with_query_param(&first, &pagination.param, "1"),
+        PaginationStrategy::Cursor | PaginationStrategy::NextUrl => first.clone(),
+    };
+    let items_pointer = json_pointer(&pagination.items_path);
+    let mut combined: Option<Value> = None;
+    let mut items = Vec::new();
+    for number in 1..=pagination.max_pages {
+        let text = send_page(request, url.as_str(), env, timeout).await?;
+        let mut page: Value = serde_json::from_str(&text)?;
+        let Some(Value::Array(page_items)) = page.pointer_mut(&items_pointer).map(Value::take)
+        else {
+            return Err(anyhow::anyhow!(
+                "Page {number} has no array at '{}'",
+                pagination.items_path
+            ));
+        };
+        let had_items = !page_items.is_empty();
+        items.extend(page_items);
+        let next = next_page_url(pagination, &first, &url, &page, number, had_items)?;
+        combined.get_or_insert(page);
+        match next {
+            Some(next) if number < pagination.max_pages => url = next,
+            Some(_) => tracing::warn!(
+                "Stopped after {} pages; raise max pages to fetch the rest",
+                pagination.max_pages
+            ),
+            None => break,
+        }
+    }
+
+    let mut combined = combined.unwrap_or(Value::Null);
+    if let Some(slot) = combined.pointer_mut(&items_pointer) {
+        *slot = Value::Array(items);
+    }
+    Ok(combined.to_string())
+}
+
+/// Characters refused in values extracted by fetch steps, which are later
+/// substituted into fetch scripts before they are split into arguments.
+const UNSAFE_EXTRACTED_CHARS: &[char] = &['\'', '"', '\\', '`', '$'];
+
+/// Variables `step` sets from its response `body`.
+fn extract_step_vars(step: &FetchStep, body: &str) -> Result<Vec<(String, String)>> {
+    if step.extract.is_empty() {
+        return Ok(Vec::new());
+    }
+    let response: Value = serde_json::from_str(body).context("Response is not JSON")?;
+    step.extract
+        .iter()
+        .map(|(name, path)| {
+            let value = page_value(&response, path)
+                .ok_or_else(|| anyhow::anyhow!("Response has no value at '{path}' for {name}"))?;
+            if value
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_control() || UNSAFE_EXTRACTED_CHARS.contains(&c))
+            {
+                return Err(anyhow::anyhow!(
+                    "Value at '{path}' for {name} contains whitespace, quotes or '$'"
+                ));
+            }
+            Ok((name.clone(), value))
+        })
+        .collect()
+}
+
+/// What a provider's fetch printed.
+pub struct FetchOutput {
+    pub stdout: String,
+    /// Diagnostics a fetch script printed while succeeding; empty for requests.
+    pub stderr: String,
+}
+
+/// Runs the provider's fetch: its declarative request when set, otherwise its
+/// fetch script. Returns the raw response.
+///
+/// With OAuth configured, the access token is added to the env as
+/// [`oauth::ACCESS_TOKEN_VAR`] and sent as a bearer token by requests that
+/// set no `Authorization` header of their own. Fetch steps run first, each
+/// adding the variables it extracts to the env of the steps after it and of
+/// the fetch.
+///
+/// # Errors
+/// Returns an error if obtaining the access token, a step, the request or the
+/// command fails.
+pub async fn fetch_output(provider: &ApiProvider, timeout: Duration) -> Result<FetchOutput> {
+    let mut env = provider.env.clone();
+    let mut request = provider.request.clone();
+    if let Some(settings) = &provider.oauth {
+        let token = oauth::access_token(&provider.id, settings, &provider.env, timeout).await?;
+        if let Some(request) = &mut request {
+            if !request
+                .headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("authorization"))
+            {
+                request.headers.insert(
+                    "Authorization".to_string(),
+                    format!("Bearer ${{{}}}", oauth::ACCESS_TOKEN_VAR),
+                );
+            }
+        }
+        env.insert(oauth::ACCESS_TOKEN_VAR.to_string(), token);
+    }
+    for (index, step) in provider.steps.iter().enumerate() {
+        let body = send_request(&step.request, &env, timeout)
+            .await
+            .with_context(|| format!("Fetch step {} failed", index + 1))?;
+        let vars = extract_step_vars(step, &body)
+            .with_context(|| format!("Fetch step {} failed", index + 1))?;
+        env.extend(vars);
+    }
+
+    if let Some(request) = &request {
+        return Ok(FetchOutput {
+            stdout: send_request(request, &env, timeout).await?,
+            stderr: String::new(),
+        });
+    }
+
+    let parts = shell_utils::parse_command(&provider.fetch_script, &env).ok_or_else(|| {
+        anyhow::anyhow!("Invalid fetch script: unmatched quotes or escape sequences")
+    })?;
     if parts.is_empty() {
         return Err(anyhow::anyhow!("Empty fetch script"));
     }
@@ -80,25 +722,61 @@ pub fn fetch_provider_for_tray(provider: &ApiProvider) -> Result<ProviderTraySta
     let output = Command::new(&parts[0])
         .args(&parts[1..])
         .env_clear()
-        .envs(&provider.env)
-        .output()?;
+        .envs(&env)
+        .kill_on_drop(true)
+        .output()
+        .await?;
 
     if !output.status.success() {
-        return Ok(ProviderTrayStats::from_provider(provider, None));
+        return Err(anyhow::anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
     }
+    Ok(FetchOutput {
+        stdout: String::from_utf8(output.stdout)?,
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
 
-    let stdout = String::from_utf8(output.stdout)?;
+/// Fetches a provider and parses its result, transformed by its `JSONPath`
+/// mapping or else its transform script.
+///
+/// Costs reported in the provider's declared currency are converted to the
+/// display currency using `rates`; costs in a non-money unit are kept as is.
+///
+/// # Errors
+/// Returns an error if the fetch, mapping or transform script fails or its result
+/// is invalid.
+#[tracing::instrument(name = "service.provider", skip_all)]
+pub async fn fetch_provider_result(
+    provider: &ApiProvider,
+    rates: Option<&FxRates>,
+    timeout: Duration,
+) -> Result<ProviderUsageResult> {
+    let raw = fetch_output(provider, timeout).await?.stdout;
 
-    let result_json = if provider.transform_script.is_empty() {
-        stdout
+    let (result_json, source) = if !provider.transform_paths.is_empty() {
+        let mapped = json_path::apply_mapping(&provider.transform_paths, &raw)?;
+        (mapped, "provider mapping")
+    } else if provider.transform_script.is_empty() {
+        (raw, "provider")
     } else {
-        run_transform_script(&provider.transform_script, &stdout)?
+        let transformed = run_transform_isolated(&provider.transform_script, &raw)
+            .await
+            .0?;
+        (transformed, "provider transform")
     };
 
-    let result: ProviderUsageResult = serde_json::from_str(&result_json)
-        .map_err(|e| anyhow::anyhow!("Failed to parse provider result: {e}"))?;
-
-    Ok(ProviderTrayStats::from_provider(provider, Some(&result)))
+    let result = parse_provider_result(&result_json, source)?;
+    if !provider.reports_money() {
+        return Ok(result);
+    }
+    Ok(fx::normalize_provider_result(
+        result,
+        provider.currency.as_deref(),
+        rates,
+    ))
 }
 
 #[cfg(test)]
@@ -146,6 +824,15 @@ mod tests {
         assert!(result.is_err(), "Should fail on invalid JS syntax");
     }
 
+    #[test]
+    fn test_check_transform_syntax() {
+        assert!(check_transform_syntax("(r) => ({ cost: r.total })").is_ok());
+        // Parsing never runs the script, so runtime errors pass
+        assert!(check_transform_syntax("(r) => r.nonexistent.property").is_ok());
+        assert!(check_transform_syntax("(r) => { invalid syntax here").is_err());
+        assert!(check_transform_syntax("1; while (true) {}").is_ok());
+    }
+
     #[test]
     fn test_transform_script_runtime_error() {
         let script = "(r) => r.nonexistent.property";
@@ -154,6 +841,77 @@ mod tests {
         assert!(result.is_err(), "Should fail on runtime error");
     }
 
+    #[test]
+    fn test_transform_script_stops_runaway_scripts() {
+        let json_data = r#"{"data": 1}"#;
+        let err = run_transform_script("(r) => { while (true) {} }", json_data).unwrap_err();
+        assert!(err.to_string().contains("loop"), "{err}");
+
+        // Limit errors can't be caught by the script
+        let script = "(r) => { try { for (;;) {} } catch (e) {} return { cost: 1 } }";
+        assert!(run_transform_script(script, json_data).is_err());
+
+        let script = "(r) => { const f = (n) => f(n + 1); return f(0) }";
+        assert!(run_transform_script(script, json_data).is_err());
+
+        // Loops that each stay under the loop limit still run out of fuel
+        let script = "(r) => { const f = () => { let k = 0; for (let j = 0; j < 1e5; j++) k++; \
+            return k }; let n = 0; for (let i = 0; i < 1e5; i++) n += f(); return { cost: n } }";
+        let err = run_transform_script(script, json_data).unwrap_err();
+        assert!(err.to_string().contains("steps"), "{err}");
+    }
+
+    #[test]
+    fn test_transform_script_helpers() {
+        let json_data = r#"{
+            "items": [{"cost": {"cents": 150}}, {"cost": {"cents": "250"}}, {}],
+            "created": 1700000000,
+            "tokens": 2500000
+        }"#;
+        let script = r"(r) => ({
+            cost: convert(sum(r.items, 'cost.cents'), 'cents', 'usd'),
+            tokens: sum(r.items, (item) => get(item, 'cost.cents', 0)),
+            label: formatNumber(1234567.891) + ' / ' + formatNumber(r.tokens),
+            millions: convert(r.tokens, 'tokens', 'M'),
+            created: parseDate(r.created).toISOString(),
+            invalid: parseDate('soon'),
+        })";
+        let result: Value =
+            serde_json::from_str(&run_transform_script(script, json_data).unwrap()).unwrap();
+        assert_eq!(result["cost"], 4.0);
+        assert_eq!(result["tokens"], 400);
+        assert_eq!(result["label"], "1,234,567.89 / 2,500,000");
+        assert_eq!(result["millions"], 2.5);
+        assert_eq!(result["created"], "2023-11-14T22:13:20.000Z");
+        assert!(result["invalid"].is_null());
+
+        let err = run_transform_script("(r) => convert(1, 'usd', 'k')", json_data).unwrap_err();
+        assert!(err.to_string().contains("Cannot convert usd to k"), "{err}");
+    }
+
+    #[test]
+    fn test_transform_script_collects_console_output() {
+        let json_data = r#"{"data": {"total": 5}}"#;
+        let script = r"(r) => {
+            console.log('total', r.data.total, r.data);
+            console.warn('low balance');
+            return { balance: r.data.total };
+        }";
+        let (result, logs) = run_transform_script_with_logs(script, json_data);
+        assert_eq!(result.unwrap(), r#"{"balance":5}"#);
+        assert_eq!(logs, ["total 5 {\"total\":5}", "[warn] low balance"]);
+
+        // Lines logged before a failure are kept
+        let script = "(r) => { console.error('missing', r.usage); return r.usage.cost }";
+        let (result, logs) = run_transform_script_with_logs(script, json_data);
+        assert!(result.is_err());
+        assert_eq!(logs, ["[error] missing undefined"]);
+
+        let script = "(r) => { for (let i = 0; i < 500; i++) console.log(i); return {} }";
+        let (_, logs) = run_transform_script_with_logs(script, json_data);
+        assert_eq!(logs.len(), 200);
+    }
+
     #[test]
     fn test_transform_script_not_a_function() {
         let script = "42";
@@ -177,4 +935,162 @@ mod tests {
         let result = run_transform_script(&script, json_data);
         assert!(result.is_err(), "Should fail when script is too long");
     }
+
+    #[test]
+    fn test_page_value_and_query_param() {
+        let page = serde_json::json!({"meta": {"next": "abc", "page": 3, "done": ""}});
+        assert_eq!(page_value(&page, "meta.next").as_deref(), Some("abc"));
+        assert_eq!(page_value(&page, "meta.page").as_deref(), Some("3"));
+        assert!(page_value(&page, "meta.done").is_none());
+        assert!(page_value(&page, "meta.missing").is_none());
+
+        let url = Url::parse("https://api.example.com/usage?cursor=old&limit=50").unwrap();
+        let next = with_query_param(&url, "cursor", "new value");
+        assert_eq!(
+            next.as_str(),
+            "https://api.example.com/usage?limit=50&cursor=new+value"
+        );
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let first = Url::parse("https://api.example.com/usage?limit=50").unwrap();
+        let page = serde_json::json!({"next_cursor": "c2", "next": "/usage?page=2"});
+        let pagination = |strategy, next_path: &str| ProviderPagination {
+            strategy,
+            next_path: next_path.to_string(),
+            param: "cursor".to_string(),
+            ..ProviderPagination::default()
+        };
+
+        let cursor = pagination(PaginationStrategy::Cursor, "next_cursor");
+        let next = next_page_url(&cursor, &first, &first, &page, 1, true).unwrap();
+        assert_eq!(
+            next.unwrap().as_str(),
+            "https://api.example.com/usage?limit=50&cursor=c2"
+        );
+
+        let numbered = pagination(PaginationStrategy::Page, "");
+        let next = next_page_url(&numbered, &first, &first, &page, 1, true).unwrap();
+        assert_eq!(
+            next.unwrap().as_str(),
+            "https://api.example.com/usage?limit=50&cursor=2"
+        );
+        assert!(next_page_url(&numbered, &first, &first, &page, 2, false)
+            .unwrap()
+            .is_none());
+
+        let next_url = pagination(PaginationStrategy::NextUrl, "next");
+        let next = next_page_url(&next_url, &first, &first, &page, 1, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.as_str(), "https://api.example.com/usage?page=2");
+        // The same URL again ends the pagination
+        assert!(next_page_url(&next_url, &first, &next, &page, 2, true)
+            .unwrap()
+            .is_none());
+
+        let elsewhere = serde_json::json!({"next": "https://evil.example.net/usage"});
+        assert!(next_page_url(&next_url, &first, &first, &elsewhere, 1, true).is_err());
+    }
+
+    #[test]
+    fn test_extract_step_vars() {
+        let step = FetchStep {
+            extract: [
+                ("SESSION".to_string(), "data.token".to_string()),
+                ("ACCOUNT".to_string(), "data.account.id".to_string()),
+            ]
+            .into(),
+            ..FetchStep::default()
+        };
+        let body = r#"{"data": {"token": "abc.def", "account": {"id": 42}}}"#;
+        let vars = extract_step_vars(&step, body).unwrap();
+        assert_eq!(
+            vars,
+            [
+                ("ACCOUNT".to_string(), "42".to_string()),
+                ("SESSION".to_string(), "abc.def".to_string()),
+            ]
+        );
+
+        assert!(extract_step_vars(&step, r#"{"data": {"token": "abc"}}"#).is_err());
+        let unsafe_body = r#"{"data": {"token": "a' -o /tmp/x '", "account": {"id": 1}}}"#;
+        assert!(extract_step_vars(&step, unsafe_body).is_err());
+        assert!(extract_step_vars(&FetchStep::default(), "not json")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_graphql_body() {
+        let env = HashMap::from([("ORG".to_string(), "acme".to_string())]);
+        let graphql = GraphqlQuery {
+            query: "query($org: String!) { usage(org: $org) { cost } }".to_string(),
+            variables: Some(r#"{"org": "${ORG}"}"#.to_string()),
+        };
+        let body = graphql_body(&graphql, &env).unwrap();
+        assert_eq!(body["variables"]["org"], "acme");
+        assert_eq!(body["query"], graphql.query.as_str());
+
+        let no_variables = GraphqlQuery {
+            variables: None,
+            ..graphql.clone()
+        };
+        let body = graphql_body(&no_variables, &env).unwrap();
+        assert_eq!(body["variables"], serde_json::json!({}));
+        let list = GraphqlQuery {
+            variables: Some("[1]".to_string()),
+            ..graphql
+        };
+        assert!(graphql_body(&list, &env).is_err());
+    }
+
+    #[test]
+    fn test_check_graphql_response() {
+        assert!(check_graphql_response(r#"{"data": {"usage": {"cost": 1}}}"#).is_ok());
+        let failed = r#"{"data": null, "errors": [{"message": "Not authorized"}]}"#;
+        let err = check_graphql_response(failed).unwrap_err();
+        assert!(err.to_string().contains("Not authorized"));
+        // Partial data is still handed to the transform
+        let partial = r#"{"data": {"usage": null}, "errors": [{"message": "Partial"}]}"#;
+        assert!(check_graphql_response(partial).is_ok());
+    }
+
+    #[test]
+    fn test_parse_provider_result() {
+        let result = parse_provider_result(r#"{"cost": 1.5, "used": null}"#, "provider").unwrap();
+        assert_eq!(result.cost, Some(1.5));
+
+        let err = parse_provider_result(r#"{"used": "12", "total": 100}"#, "provider transform")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid provider transform output: used: expected number, found string"
+        );
+        let err = parse_provider_result(r#"{"tokens": -3}"#, "provider").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("tokens: expected non-negative integer"));
+        let err = parse_provider_result("[1, 2]", "provider").unwrap_err();
+        assert!(err.to_string().contains("expected object, found array"));
+
+        let err = parse_provider_result(r#"{"Cost": 1, "usage": 2}"#, "provider").unwrap_err();
+        assert!(err.to_string().ends_with("found Cost, usage"));
+        let err = parse_provider_result("{}", "provider").unwrap_err();
+        assert!(err.to_string().ends_with("found an empty object"));
+        let err = parse_provider_result("undefined", "provider transform").unwrap_err();
+        assert!(err.to_string().contains("nothing was returned"));
+
+        let daily = r#"{"daily": [{"date": "2025-01-02", "cost": 1.25}, {"date": "2025-01-03"}]}"#;
+        let result = parse_provider_result(daily, "provider").unwrap();
+        assert_eq!(result.cost, None);
+        assert_eq!(result.daily.len(), 2);
+        assert_eq!(result.daily[0].date.to_string(), "2025-01-02");
+        assert_eq!(result.daily[0].cost, Some(1.25));
+        assert_eq!(result.daily[1].cost, None);
+        let err = parse_provider_result(r#"{"daily": [{"cost": 1}]}"#, "provider").unwrap_err();
+        assert!(err.to_string().contains("daily[0].date"), "{err}");
+        assert!(parse_provider_result(r#"{"daily": [{"date": "Jan 2"}]}"#, "provider").is_err());
+    }
 }
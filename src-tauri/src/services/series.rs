@@ -0,0 +1,146 @@
+use crate::config::ChartAggregation;
+use crate::types::{DailyUsage, DualSeries};
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// Ranges longer than this are returned as weekly points.
+pub const DOWNSAMPLE_AFTER_DAYS: u32 = 120;
+
+/// Cost, total tokens and cache tokens for each of the `days` days ending on
+/// `today`, in aligned arrays; days without usage are zero.
+///
+/// Ranges longer than [`DOWNSAMPLE_AFTER_DAYS`] are combined into weeks
+/// starting on Monday, each dated by its first day in the range, using
+/// `aggregation`. Means cover only the days of the week inside the range.
+#[must_use]
+pub fn build_dual_series(
+    daily: &[DailyUsage],
+    today: NaiveDate,
+    days: u32,
+    aggregation: ChartAggregation,
+) -> DualSeries {
+    let by_date: HashMap<&str, &DailyUsage> = daily.iter().map(|d| (d.date.as_str(), d)).collect();
+    let weekly = days > DOWNSAMPLE_AFTER_DAYS;
+    let mut series = DualSeries {
+        bucket_days: if weekly { 7 } else { 1 },
+        ..DualSeries::default()
+    };
+
+    let mut bucket: Option<NaiveDate> = None;
+    let mut bucket_len = 0u32;
+    for back in (0..i64::from(days)).rev() {
+        let date = today - Duration::days(back);
+        let week_start = date - Duration::days(i64::from(date.weekday().num_days_from_monday()));
+        let start = if weekly { week_start } else { date };
+        if bucket != Some(start) {
+            finish_bucket(&mut series, bucket_len, aggregation);
+            bucket = Some(start);
+            bucket_len = 0;
+            series.dates.push(date.format("%Y-%m-%d").to_string());
+            series.cost.push(0.0);
+            series.tokens.push(0);
+            series.cache_tokens.push(0);
+        }
+        bucket_len += 1;
+
+        let Some(usage) = by_date.get(date.format("%Y-%m-%d").to_string().as_str()) else {
+            continue;
+        };
+        let cache_tokens = usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+        if let (Some(cost), Some(tokens), Some(cache)) = (
+            series.cost.last_mut(),
+            series.tokens.last_mut(),
+            series.cache_tokens.last_mut(),
+        ) {
+            *cost += usage.cost;
+            *tokens += usage.input_tokens + usage.output_tokens + cache_tokens;
+            *cache += cache_tokens;
+        }
+    }
+    finish_bucket(&mut series, bucket_len, aggregation);
+    series
+}
+
+/// Turns the sums of the last point into means over its `len` days when asked.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn finish_bucket(series: &mut DualSeries, len: u32, aggregation: ChartAggregation) {
+    if aggregation != ChartAggregation::Mean || len <= 1 {
+        return;
+    }
+    let mean = |sum: u64| (sum as f64 / f64::from(len)).round() as u64;
+    if let Some(cost) = series.cost.last_mut() {
+        *cost /= f64::from(len);
+    }
+    if let Some(tokens) = series.tokens.last_mut() {
+        *tokens = mean(*tokens);
+    }
+    if let Some(cache) = series.cache_tokens.last_mut() {
+        *cache = mean(*cache);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, cost: f64) -> DailyUsage {
+        DailyUsage {
+            date: date.to_string(),
+            cost,
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_creation_input_tokens: 10,
+            cache_read_input_tokens: 40,
+            models: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_dual_series_fills_missing_days() {
+        let today = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+        let daily = [
+            day("2025-06-08", 1.5),
+            day("2025-06-10", 2.0),
+            day("2025-05-01", 9.0),
+        ];
+
+        let series = build_dual_series(&daily, today, 3, ChartAggregation::Mean);
+        assert_eq!(series.dates, ["2025-06-08", "2025-06-09", "2025-06-10"]);
+        assert_eq!(series.cost, [1.5, 0.0, 2.0]);
+        assert_eq!(series.tokens, [200, 0, 200]);
+        assert_eq!(series.cache_tokens, [50, 0, 50]);
+        assert_eq!(series.bucket_days, 1);
+    }
+
+    #[test]
+    fn test_build_dual_series_weekly_buckets() {
+        // A Tuesday, so the current week holds two days
+        let today = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+        let daily = [
+            day("2025-06-10", 2.0),
+            day("2025-06-09", 1.0),
+            day("2025-06-08", 4.0),
+        ];
+
+        let sums = build_dual_series(&daily, today, 365, ChartAggregation::Sum);
+        assert_eq!(sums.bucket_days, 7);
+        assert!(sums.dates.len() <= 54);
+        assert_eq!(sums.dates.last().map(String::as_str), Some("2025-06-09"));
+        assert_eq!(sums.cost.last(), Some(&3.0));
+        assert_eq!(sums.tokens.last(), Some(&400));
+        assert_eq!(sums.cost[sums.cost.len() - 2], 4.0);
+        // The first week is cut off at the start of the range
+        assert_eq!(sums.dates[0], "2024-06-11");
+
+        let means = build_dual_series(&daily, today, 365, ChartAggregation::Mean);
+        assert_eq!(means.dates, sums.dates);
+        assert_eq!(means.cost.last(), Some(&1.5));
+        assert_eq!(means.tokens.last(), Some(&200));
+        assert_eq!(means.cost[means.cost.len() - 2], 4.0 / 7.0);
+    }
+}
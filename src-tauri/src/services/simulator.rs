@@ -0,0 +1,210 @@
+use crate::config::{ModelSortKey, TrendsConfig};
+use crate::services::sources::{self, SourceReport};
+use crate::services::trends;
+use crate::storage;
+use crate::types::{DailyUsage, ModelUsage};
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// History length and model count of the default load test.
+pub const DEFAULT_DAYS: usize = 1000;
+pub const DEFAULT_MODELS: usize = 50;
+
+/// Number of synthetic external sources merged into the summary.
+const EXTERNAL_SOURCES: u64 = 3;
+
+/// Slowest acceptable time for each stage of the default simulation in a
+/// release build. Generous on purpose: they catch accidental quadratic work,
+/// not small slowdowns.
+pub const STAGE_BUDGETS: &[(&str, Duration)] = &[
+    ("generate", Duration::from_millis(500)),
+    ("merge", Duration::from_millis(250)),
+    ("save", Duration::from_secs(2)),
+    ("load", Duration::from_secs(1)),
+    ("refresh", Duration::from_millis(250)),
+    ("serialize", Duration::from_millis(500)),
+];
+
+/// How long one stage of a simulated refresh took.
+#[derive(Debug, Clone)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub duration: Duration,
+}
+
+/// Timings of one simulated refresh, in the order the stages ran.
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub days: usize,
+    pub models: usize,
+    pub timings: Vec<StageTiming>,
+    /// Size of the serialized summary sent to the frontend
+    pub summary_bytes: usize,
+}
+
+impl SimulationReport {
+    /// Stages that took longer than their [`STAGE_BUDGETS`] entry.
+    #[must_use]
+    pub fn over_budget(&self) -> Vec<(&StageTiming, Duration)> {
+        self.timings
+            .iter()
+            .filter_map(|timing| {
+                let (_, budget) = STAGE_BUDGETS.iter().find(|(s, _)| *s == timing.stage)?;
+                (timing.duration > *budget).then_some((timing, *budget))
+            })
+            .collect()
+    }
+}
+
+/// Small deterministic generator so runs are comparable without a `rand`
+/// dependency.
+struct XorShift(u64);
+
+impl XorShift {
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: u64) -> u64 {
+        self.next() % max.max(1)
+    }
+}
+
+/// `days` days of usage ending at `end`, each using all of `models` models,
+/// oldest first. The same `seed` always yields the same history.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn synthetic_history(days: usize, models: usize, end: NaiveDate, seed: u64) -> Vec<DailyUsage> {
+    let mut rng = XorShift(seed | 1);
+    (0..days)
+        .rev()
+        .filter_map(|offset| {
+            let date = end - chrono::TimeDelta::days(i64::try_from(offset).ok()?);
+            let models: Vec<ModelUsage> = (0..models)
+                .map(|i| {
+                    let input_tokens = rng.below(200_000);
+                    let output_tokens = rng.below(50_000);
+                    ModelUsage {
+                        model: format!("sim-model-{i:03}"),
+                        cost: (input_tokens * 3 + output_tokens * 15) as f64 / 1_000_000.0,
+                        input_tokens,
+                        output_tokens,
+                    }
+                })
+                .collect();
+            Some(DailyUsage {
+                date: date.format("%Y-%m-%d").to_string(),
+                cost: models.iter().map(|m| m.cost).sum(),
+                input_tokens: models.iter().map(|m| m.input_tokens).sum(),
+                output_tokens: models.iter().map(|m| m.output_tokens).sum(),
+                cache_creation_input_tokens: rng.below(100_000),
+                cache_read_input_tokens: rng.below(1_000_000),
+                models,
+            })
+        })
+        .collect()
+}
+
+fn timed<T>(timings: &mut Vec<StageTiming>, stage: &'static str, run: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let value = run();
+    timings.push(StageTiming {
+        stage,
+        duration: started.elapsed(),
+    });
+    value
+}
+
+/// Runs the storage and aggregation steps of a refresh against a synthetic
+/// history of `days` days and `models` models stored under `dir`, timing each.
+///
+/// # Errors
+/// Returns an error if the history cannot be written to or read from `dir`.
+pub fn simulate(dir: &Path, days: usize, models: usize) -> Result<SimulationReport> {
+    let today = chrono::Local::now().date_naive();
+    let mut timings = Vec::new();
+
+    let (stored, fresh, reports) = timed(&mut timings, "generate", || {
+        let stored = synthetic_history(days, models, today - chrono::TimeDelta::days(1), 1);
+        let window = days.min(30);
+        let fresh = synthetic_history(window, models, today, 2);
+        let reports: Vec<SourceReport> = (0..EXTERNAL_SOURCES)
+            .map(|i| SourceReport {
+                id: format!("sim-{i}"),
+                label: format!("Simulated {i}"),
+                daily: synthetic_history(window, models / 5 + 1, today, 3 + i),
+                keys: BTreeMap::new(),
+                credits: None,
+            })
+            .collect();
+        (stored, fresh, reports)
+    });
+
+    let merged = timed(&mut timings, "merge", || {
+        storage::merge_history(&stored, &fresh)
+    });
+    timed(&mut timings, "save", || storage::save_history(dir, &merged))?;
+    let history = timed(&mut timings, "load", || storage::load_history(dir))?;
+
+    let summary = timed(&mut timings, "refresh", || {
+        let today_str = today.format("%Y-%m-%d").to_string();
+        let mut summary = sources::summary_from_daily(fresh, &today_str);
+        summary.daily_usage = history;
+        let period_start = sources::period_start(today, None);
+        sources::apply_reports_since(&mut summary, &reports, period_start);
+        sources::group_minor_models(&mut summary.model_breakdown, 0.01);
+        sources::sort_models(&mut summary.model_breakdown, ModelSortKey::Cost);
+        trends::apply_trend(&mut summary, &TrendsConfig::default());
+        summary
+    });
+    let json = timed(&mut timings, "serialize", || {
+        serde_json::to_string(&summary)
+    })?;
+
+    Ok(SimulationReport {
+        days,
+        models,
+        timings,
+        summary_bytes: json.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_history_is_deterministic() {
+        let end = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let history = synthetic_history(40, 4, end, 7);
+        assert_eq!(history.len(), 40);
+        assert_eq!(history[0].date, "2025-01-30");
+        assert_eq!(history[39].date, "2025-03-10");
+        assert!(history.iter().all(|day| day.models.len() == 4));
+
+        let again = synthetic_history(40, 4, end, 7);
+        assert_eq!(history[12].cost.to_bits(), again[12].cost.to_bits());
+        assert_ne!(
+            history[12].cost.to_bits(),
+            synthetic_history(40, 4, end, 8)[12].cost.to_bits()
+        );
+    }
+
+    #[test]
+    fn test_simulate_times_every_stage() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        let report = simulate(dir, 60, 5).unwrap();
+        let stages: Vec<&str> = report.timings.iter().map(|t| t.stage).collect();
+        let budgeted: Vec<&str> = STAGE_BUDGETS.iter().map(|(stage, _)| *stage).collect();
+        assert_eq!(stages, budgeted);
+        assert!(report.summary_bytes > 0);
+        assert_eq!(storage::load_history(dir).unwrap().len(), 61);
+    }
+}
@@ -0,0 +1,987 @@
+use crate::config::{DataSourcesConfig, ModelSortKey, TimeoutsConfig};
+use crate::services::{
+    copilot, cursor, custom_jsonl, litellm, ollama, openai, openrouter, proxy, windsurf,
+};
+use crate::types::{
+    DailyUsage, KeyUsage, MemberUsage, ModelUsage, OrgComparison, ProviderHistoryPoint,
+    ProviderUsageResult, RefreshMeta, SourceUsage, UsageData, UsageSummary,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Identifier of the built-in Claude Code (ccusage) source.
+pub const CLAUDE_CODE_SOURCE_ID: &str = "claude-code";
+const CLAUDE_CODE_SOURCE_LABEL: &str = "Claude Code";
+
+/// Prefix of the source ids given to usage imported from other machines.
+pub const IMPORT_SOURCE_PREFIX: &str = "import:";
+
+/// Prefix of the source ids given to custom providers' reported costs.
+pub const PROVIDER_SOURCE_PREFIX: &str = "provider:";
+
+/// Number of days external sources look back, matching `ccusage --days 30`.
+pub const SUMMARY_WINDOW_DAYS: i64 = 30;
+
+/// Number of days of stored history the summary carries, enough for the
+/// dashboard's 54-week contribution graph.
+pub const SUMMARY_HISTORY_DAYS: i64 = 54 * 7;
+
+/// Model name of the row that collects models below the minimum daily cost.
+pub const OTHER_MODELS_LABEL: &str = "Other";
+
+/// Daily usage reported by an external source, ready to be merged into the summary.
+#[derive(Debug, Clone)]
+pub struct SourceReport {
+    pub id: String,
+    pub label: String,
+    pub daily: Vec<DailyUsage>,
+    /// Daily usage per API key or user, for sources that report that dimension.
+    pub keys: BTreeMap<String, Vec<DailyUsage>>,
+    /// Prepaid credit balance, for sources that report one.
+    pub credits: Option<ProviderUsageResult>,
+}
+
+/// Spend reported by a custom provider, folded into the summary totals.
+#[derive(Debug, Clone)]
+pub struct ProviderCost {
+    pub id: String,
+    pub label: String,
+    pub today: f64,
+    pub this_month: f64,
+}
+
+/// A single usage observation normalized by a source before aggregation.
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecord {
+    pub date: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cost: f64,
+    /// API key label or user the usage is attributed to, when the source knows it.
+    pub key: Option<String>,
+}
+
+/// Wraps Claude Code usage imported from another machine, tagged `source`, as
+/// a report so it is merged alongside the local data.
+#[must_use]
+pub fn import_report(source: &str, daily: Vec<DailyUsage>) -> SourceReport {
+    SourceReport {
+        id: format!("{IMPORT_SOURCE_PREFIX}{source}"),
+        label: format!("{CLAUDE_CODE_SOURCE_LABEL} ({source})"),
+        daily,
+        keys: BTreeMap::new(),
+        credits: None,
+    }
+}
+
+/// Aggregates individual usage records into per-day, per-model totals sorted by date.
+pub fn aggregate_records<I: IntoIterator<Item = UsageRecord>>(records: I) -> Vec<DailyUsage> {
+    let mut days: BTreeMap<String, DailyUsage> = BTreeMap::new();
+
+    for record in records {
+        let day = days
+            .entry(record.date.clone())
+            .or_insert_with(|| DailyUsage {
+                date: record.date.clone(),
+                cost: 0.0,
+                input_tokens: 0,
+                output_tokens: 0,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+                models: Vec::new(),
+            });
+        day.cost += record.cost;
+        day.input_tokens += record.input_tokens;
+        day.output_tokens += record.output_tokens;
+        day.cache_creation_input_tokens += record.cache_creation_input_tokens;
+        day.cache_read_input_tokens += record.cache_read_input_tokens;
+
+        add_model(
+            &mut day.models,
+            &ModelUsage {
+                model: record.model,
+                cost: record.cost,
+                input_tokens: record.input_tokens,
+                output_tokens: record.output_tokens,
+            },
+        );
+    }
+
+    days.into_values().collect()
+}
+
+/// Aggregates the records that carry a key into per-key daily usage.
+#[must_use]
+pub fn aggregate_records_by_key(records: &[UsageRecord]) -> BTreeMap<String, Vec<DailyUsage>> {
+    let mut by_key: BTreeMap<String, Vec<UsageRecord>> = BTreeMap::new();
+    for record in records {
+        if let Some(key) = &record.key {
+            by_key.entry(key.clone()).or_default().push(record.clone());
+        }
+    }
+    by_key
+        .into_iter()
+        .map(|(key, records)| (key, aggregate_records(records)))
+        .collect()
+}
+
+/// Fetches all enabled external sources. Failing sources are logged and skipped
+/// so a single broken integration never hides Claude Code usage.
+#[tracing::instrument(name = "service.fetch_external", skip_all)]
+pub async fn fetch_external(
+    config: &DataSourcesConfig,
+    timeouts: &TimeoutsConfig,
+    config_dir: &Path,
+) -> Vec<SourceReport> {
+    let mut reports = Vec::new();
+
+    if config.cursor.enabled {
+        match cursor::fetch_usage(&config.cursor, timeouts.provider()).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to fetch Cursor usage: {e}"),
+        }
+    }
+
+    if config.windsurf.enabled {
+        match windsurf::fetch_usage(&config.windsurf, timeouts.provider()).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to fetch Windsurf usage: {e}"),
+        }
+    }
+
+    if config.openai.enabled {
+        match openai::fetch_usage(&config.openai, timeouts.provider()).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to fetch OpenAI usage: {e}"),
+        }
+    }
+
+    if config.openrouter.enabled {
+        match openrouter::fetch_usage(&config.openrouter, timeouts.provider()).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to fetch OpenRouter usage: {e}"),
+        }
+    }
+
+    if config.litellm.enabled {
+        match litellm::fetch_usage(&config.litellm, timeouts.provider()).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to fetch LiteLLM usage: {e}"),
+        }
+    }
+
+    if config.copilot.enabled {
+        match copilot::fetch_usage(&config.copilot, timeouts.provider()).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to fetch Copilot usage: {e}"),
+        }
+    }
+
+    if config.ollama.enabled {
+        match ollama::fetch_usage(config_dir).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to read Ollama usage: {e}"),
+        }
+    }
+
+    for source in config.custom_jsonl.iter().filter(|s| s.enabled) {
+        match custom_jsonl::fetch_usage(source, timeouts.pricing()).await {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Failed to read custom source '{}': {e}", source.id),
+        }
+    }
+
+    if config.proxy.enabled {
+        match proxy::fetch_usage(config_dir, timeouts.pricing()).await {
+            Ok(mut proxy_reports) => reports.append(&mut proxy_reports),
+            Err(e) => tracing::warn!("Failed to read API proxy usage: {e}"),
+        }
+    }
+
+    reports
+}
+
+/// First day of stored history loaded into the summary. It is rounded down to
+/// the start of its month so only whole history shards are read and saved.
+#[must_use]
+pub fn summary_history_start(today: NaiveDate) -> NaiveDate {
+    let start = today - chrono::Duration::days(SUMMARY_HISTORY_DAYS - 1);
+    start.with_day(1).unwrap_or(start)
+}
+
+/// First day counted toward the monthly totals: the start of the current
+/// billing cycle, or the start of the rolling window when no cycle day is set.
+#[must_use]
+pub fn period_start(today: NaiveDate, billing_cycle_day: Option<u32>) -> NaiveDate {
+    let Some(day) = billing_cycle_day else {
+        return today - chrono::Duration::days(SUMMARY_WINDOW_DAYS - 1);
+    };
+    let cycle_day = |date: NaiveDate| {
+        let days_in_month = (28..=31)
+            .rev()
+            .find(|d| date.with_day(*d).is_some())
+            .unwrap_or(28);
+        date.with_day(day.clamp(1, days_in_month)).unwrap_or(date)
+    };
+    let this_month = cycle_day(today);
+    if this_month <= today {
+        return this_month;
+    }
+    let last_month = today
+        .with_day(1)
+        .and_then(|d| d.pred_opt())
+        .unwrap_or(today);
+    cycle_day(last_month)
+}
+
+/// Recomputes the monthly totals and model breakdown from the days on or after
+/// `period_start`, for billing cycles that differ from the fetched window.
+pub fn restrict_to_period(summary: &mut UsageSummary, period_start: NaiveDate) {
+    let start = period_start.format("%Y-%m-%d").to_string();
+    let mut this_month = UsageData {
+        date: summary.this_month.date.clone(),
+        ..UsageData::default()
+    };
+    let mut model_breakdown = Vec::new();
+    for day in summary.daily_usage.iter().filter(|d| d.date >= start) {
+        add_day(&mut this_month, day);
+        for model in &day.models {
+            add_model(&mut model_breakdown, model);
+        }
+    }
+    summary.this_month = this_month;
+    summary.model_breakdown = model_breakdown;
+}
+
+/// Records the Claude Code totals as the first source and merges every external
+/// report into the combined summary.
+pub fn apply_reports(summary: &mut UsageSummary, reports: &[SourceReport]) {
+    let today = chrono::Local::now().date_naive();
+    apply_reports_since(summary, reports, period_start(today, None));
+}
+
+/// Like [`apply_reports`], counting report days from `period_start` toward the
+/// monthly totals.
+pub fn apply_reports_since(
+    summary: &mut UsageSummary,
+    reports: &[SourceReport],
+    period_start: NaiveDate,
+) {
+    summary.sources = vec![SourceUsage {
+        id: CLAUDE_CODE_SOURCE_ID.to_string(),
+        label: CLAUDE_CODE_SOURCE_LABEL.to_string(),
+        today: summary.today.clone(),
+        this_month: summary.this_month.clone(),
+        models: summary.model_breakdown.clone(),
+        keys: Vec::new(),
+        credits: None,
+    }];
+
+    let today_str = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let window_start = period_start.format("%Y-%m-%d").to_string();
+
+    for report in reports {
+        merge_report(summary, report, &today_str, &window_start);
+    }
+}
+
+fn merge_report(
+    summary: &mut UsageSummary,
+    report: &SourceReport,
+    today: &str,
+    window_start: &str,
+) {
+    let mut source = SourceUsage {
+        id: report.id.clone(),
+        label: report.label.clone(),
+        today: UsageData {
+            date: today.to_string(),
+            ..UsageData::default()
+        },
+        this_month: UsageData {
+            date: today.to_string(),
+            ..UsageData::default()
+        },
+        models: Vec::new(),
+        keys: report
+            .keys
+            .iter()
+            .map(|(key, daily)| key_usage(key, daily, today, window_start))
+            .collect(),
+        credits: report.credits.clone(),
+    };
+    source
+        .keys
+        .sort_by(|a, b| b.this_month.cost.total_cmp(&a.this_month.cost));
+
+    for day in &report.daily {
+        merge_day(&mut summary.daily_usage, day);
+
+        if day.date.as_str() < window_start {
+            continue;
+        }
+        add_day(&mut source.this_month, day);
+        add_day(&mut summary.this_month, day);
+        for model in &day.models {
+            add_model(&mut source.models, model);
+            add_model(&mut summary.model_breakdown, model);
+        }
+        if day.date == today {
+            add_day(&mut source.today, day);
+            add_day(&mut summary.today, day);
+        }
+    }
+
+    summary.daily_usage.sort_by(|a, b| a.date.cmp(&b.date));
+    summary.sources.push(source);
+}
+
+/// Part of a provider's latest reported `cost` spent since `today_start`: the
+/// growth over the last cost recorded in `history` before then.
+///
+/// A lower latest cost means the provider's counter reset, so all of it is
+/// today's. Without an earlier record the day's share is unknown and counted
+/// as zero.
+#[must_use]
+pub fn provider_today_cost(
+    history: &[ProviderHistoryPoint],
+    latest: f64,
+    today_start: DateTime<Utc>,
+) -> f64 {
+    let baseline = history
+        .iter()
+        .filter(|point| point.fetched_at < today_start)
+        .filter_map(|point| point.result.cost)
+        .next_back();
+    match baseline {
+        Some(baseline) if latest >= baseline => latest - baseline,
+        Some(_) => latest,
+        None => 0.0,
+    }
+}
+
+/// Adds each provider's reported spend to the summary's `today` and
+/// `this_month` costs and lists it as its own source.
+pub fn apply_provider_costs(summary: &mut UsageSummary, costs: &[ProviderCost]) {
+    for cost in costs {
+        summary.today.cost += cost.today;
+        summary.this_month.cost += cost.this_month;
+        summary.sources.push(SourceUsage {
+            id: format!("{PROVIDER_SOURCE_PREFIX}{}", cost.id),
+            label: cost.label.clone(),
+            today: UsageData {
+                date: summary.today.date.clone(),
+                cost: cost.today,
+                ..UsageData::default()
+            },
+            this_month: UsageData {
+                date: summary.this_month.date.clone(),
+                cost: cost.this_month,
+                ..UsageData::default()
+            },
+            models: Vec::new(),
+            keys: Vec::new(),
+            credits: None,
+        });
+    }
+}
+
+fn key_usage(key: &str, daily: &[DailyUsage], today: &str, window_start: &str) -> KeyUsage {
+    let mut usage = KeyUsage {
+        key: key.to_string(),
+        today: UsageData {
+            date: today.to_string(),
+            ..UsageData::default()
+        },
+        this_month: UsageData {
+            date: today.to_string(),
+            ..UsageData::default()
+        },
+    };
+    for day in daily.iter().filter(|d| d.date.as_str() >= window_start) {
+        add_day(&mut usage.this_month, day);
+        if day.date == today {
+            add_day(&mut usage.today, day);
+        }
+    }
+    usage
+}
+
+/// Builds a summary from per-day usage, counting every day toward `this_month`
+/// the way ccusage totals its reporting window.
+#[must_use]
+pub fn summary_from_daily(daily_usage: Vec<DailyUsage>, today: &str) -> UsageSummary {
+    let mut today_data = UsageData {
+        date: today.to_string(),
+        ..UsageData::default()
+    };
+    let mut this_month = today_data.clone();
+    let mut model_breakdown = Vec::new();
+    for day in &daily_usage {
+        add_day(&mut this_month, day);
+        if day.date == today {
+            add_day(&mut today_data, day);
+        }
+        for model in &day.models {
+            add_model(&mut model_breakdown, model);
+        }
+    }
+
+    UsageSummary {
+        today: today_data,
+        this_month,
+        daily_usage,
+        model_breakdown,
+        sources: Vec::new(),
+        trend: None,
+        limits: None,
+        active_block_end: None,
+        meta: RefreshMeta::default(),
+    }
+}
+
+/// Combines the key breakdowns of `source` and its sub-sources (ids prefixed
+/// with `"{source}:"`, such as the per-key proxy reports), highest monthly cost first.
+#[must_use]
+pub fn key_breakdown(sources: &[SourceUsage], source: &str) -> Vec<KeyUsage> {
+    let prefix = format!("{source}:");
+    let mut keys: Vec<KeyUsage> = Vec::new();
+    for key in sources
+        .iter()
+        .filter(|s| s.id == source || s.id.starts_with(&prefix))
+        .flat_map(|s| &s.keys)
+    {
+        if let Some(existing) = keys.iter_mut().find(|k| k.key == key.key) {
+            add_usage(&mut existing.today, &key.today);
+            add_usage(&mut existing.this_month, &key.this_month);
+        } else {
+            keys.push(key.clone());
+        }
+    }
+    keys.sort_by(|a, b| b.this_month.cost.total_cmp(&a.this_month.cost));
+    keys
+}
+
+/// Ranks every key or member across all sources by this month's cost.
+#[must_use]
+pub fn member_leaderboard(sources: &[SourceUsage]) -> Vec<MemberUsage> {
+    let mut members: Vec<MemberUsage> = Vec::new();
+    for source in sources {
+        for key in &source.keys {
+            let index = members
+                .iter()
+                .position(|m| m.member == key.key)
+                .unwrap_or_else(|| {
+                    members.push(MemberUsage {
+                        member: key.key.clone(),
+                        sources: Vec::new(),
+                        today: UsageData::default(),
+                        this_month: UsageData::default(),
+                    });
+                    members.len() - 1
+                });
+            let member = &mut members[index];
+            add_usage(&mut member.today, &key.today);
+            add_usage(&mut member.this_month, &key.this_month);
+            if !member.sources.contains(&source.label) {
+                member.sources.push(source.label.clone());
+            }
+        }
+    }
+    members.sort_by(|a, b| b.this_month.cost.total_cmp(&a.this_month.cost));
+    members
+}
+
+/// Compares this month's cost of `member` in the leaderboard, or `own_cost`
+/// when unset, with the other members. `None` when the member isn't listed
+/// or there is nobody to compare with.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn org_comparison(
+    members: &[MemberUsage],
+    member: Option<&str>,
+    own_cost: f64,
+) -> Option<OrgComparison> {
+    let cost = match member {
+        Some(name) => members.iter().find(|m| m.member == name)?.this_month.cost,
+        None => own_cost,
+    };
+    let others: Vec<f64> = members
+        .iter()
+        .filter(|m| member != Some(m.member.as_str()))
+        .map(|m| m.this_month.cost)
+        .collect();
+    if others.is_empty() {
+        return None;
+    }
+    let count = others.len() as f64;
+    let below = others.iter().filter(|other| **other < cost).count() as f64;
+    Some(OrgComparison {
+        member: member.map(str::to_string),
+        cost,
+        org_average: others.iter().sum::<f64>() / count,
+        percentile: below / count * 100.0,
+        member_count: others.len(),
+    })
+}
+
+fn add_usage(data: &mut UsageData, other: &UsageData) {
+    data.cost += other.cost;
+    data.input_tokens += other.input_tokens;
+    data.output_tokens += other.output_tokens;
+    data.cache_creation_input_tokens += other.cache_creation_input_tokens;
+    data.cache_read_input_tokens += other.cache_read_input_tokens;
+    data.total_tokens += other.total_tokens;
+}
+
+fn merge_day(daily_usage: &mut Vec<DailyUsage>, day: &DailyUsage) {
+    let Some(existing) = daily_usage.iter_mut().find(|d| d.date == day.date) else {
+        daily_usage.push(day.clone());
+        return;
+    };
+    existing.cost += day.cost;
+    existing.input_tokens += day.input_tokens;
+    existing.output_tokens += day.output_tokens;
+    existing.cache_creation_input_tokens += day.cache_creation_input_tokens;
+    existing.cache_read_input_tokens += day.cache_read_input_tokens;
+    for model in &day.models {
+        add_model(&mut existing.models, model);
+    }
+}
+
+fn add_day(data: &mut UsageData, day: &DailyUsage) {
+    data.cost += day.cost;
+    data.input_tokens += day.input_tokens;
+    data.output_tokens += day.output_tokens;
+    data.cache_creation_input_tokens += day.cache_creation_input_tokens;
+    data.cache_read_input_tokens += day.cache_read_input_tokens;
+    data.total_tokens += day.input_tokens
+        + day.output_tokens
+        + day.cache_creation_input_tokens
+        + day.cache_read_input_tokens;
+}
+
+/// Folds models averaging less than `min_daily_cost` per day over the summary
+/// window into a single "Other" row. A threshold of zero keeps every model.
+#[allow(clippy::cast_precision_loss)]
+pub fn group_minor_models(models: &mut Vec<ModelUsage>, min_daily_cost: f64) {
+    if min_daily_cost <= 0.0 {
+        return;
+    }
+    let min_cost = min_daily_cost * SUMMARY_WINDOW_DAYS as f64;
+    let (kept, minor): (Vec<ModelUsage>, Vec<ModelUsage>) =
+        models.drain(..).partition(|m| m.cost >= min_cost);
+    *models = kept;
+
+    if minor.is_empty() {
+        return;
+    }
+    let mut other = ModelUsage {
+        model: OTHER_MODELS_LABEL.to_string(),
+        cost: 0.0,
+        input_tokens: 0,
+        output_tokens: 0,
+    };
+    for model in &minor {
+        other.cost += model.cost;
+        other.input_tokens += model.input_tokens;
+        other.output_tokens += model.output_tokens;
+    }
+    add_model(models, &other);
+}
+
+/// Sorts model rows by `key`, breaking ties by cost and then model ID so the
+/// order is the same on every refresh. The "Other" row always comes last.
+pub fn sort_models(models: &mut [ModelUsage], key: ModelSortKey) {
+    let tokens = |m: &ModelUsage| m.input_tokens + m.output_tokens;
+    models.sort_by(|a, b| {
+        (a.model == OTHER_MODELS_LABEL)
+            .cmp(&(b.model == OTHER_MODELS_LABEL))
+            .then_with(|| match key {
+                ModelSortKey::Cost => Ordering::Equal,
+                ModelSortKey::Tokens => tokens(b).cmp(&tokens(a)),
+                ModelSortKey::Name => a.model.cmp(&b.model),
+            })
+            .then_with(|| b.cost.total_cmp(&a.cost))
+            .then_with(|| a.model.cmp(&b.model))
+    });
+}
+
+fn add_model(models: &mut Vec<ModelUsage>, model: &ModelUsage) {
+    if let Some(existing) = models.iter_mut().find(|m| m.model == model.model) {
+        existing.cost += model.cost;
+        existing.input_tokens += model.input_tokens;
+        existing.output_tokens += model.output_tokens;
+    } else {
+        models.push(model.clone());
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn record(date: &str, model: &str, input: u64, output: u64, cost: f64) -> UsageRecord {
+        UsageRecord {
+            date: date.to_string(),
+            model: model.to_string(),
+            input_tokens: input,
+            output_tokens: output,
+            cost,
+            ..UsageRecord::default()
+        }
+    }
+
+    #[test]
+    fn test_aggregate_records_groups_by_day_and_model() {
+        let daily = aggregate_records(vec![
+            record("2024-01-02", "gpt-4o", 100, 50, 0.5),
+            record("2024-01-01", "gpt-4o", 10, 5, 0.1),
+            record("2024-01-02", "gpt-4o", 100, 50, 0.5),
+            record("2024-01-02", "claude-sonnet", 1, 1, 0.25),
+        ]);
+
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].date, "2024-01-01");
+        assert_eq!(daily[1].input_tokens, 201);
+        assert_eq!(daily[1].cost, 1.25);
+        assert_eq!(daily[1].models.len(), 2);
+        assert_eq!(daily[1].models[0].input_tokens, 200);
+    }
+
+    #[test]
+    fn test_apply_reports_merges_external_usage() {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let mut summary = UsageSummary {
+            today: UsageData {
+                date: today.clone(),
+                cost: 1.0,
+                input_tokens: 100,
+                output_tokens: 100,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+                total_tokens: 200,
+            },
+            this_month: UsageData {
+                cost: 10.0,
+                ..UsageData::default()
+            },
+            daily_usage: vec![DailyUsage {
+                date: today.clone(),
+                cost: 1.0,
+                input_tokens: 100,
+                output_tokens: 100,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+                models: vec![],
+            }],
+            model_breakdown: vec![],
+            sources: vec![],
+            trend: None,
+            limits: None,
+            active_block_end: None,
+            meta: RefreshMeta::default(),
+        };
+        let report = SourceReport {
+            id: "cursor".to_string(),
+            label: "Cursor".to_string(),
+            daily: aggregate_records(vec![
+                record(&today, "gpt-4o", 10, 10, 0.5),
+                record("2000-01-01", "gpt-4o", 10, 10, 9.0),
+            ]),
+            keys: BTreeMap::new(),
+            credits: None,
+        };
+
+        apply_reports(&mut summary, &[report]);
+
+        assert_eq!(summary.sources.len(), 2);
+        assert_eq!(summary.sources[0].id, CLAUDE_CODE_SOURCE_ID);
+        assert_eq!(summary.sources[0].today.cost, 1.0);
+        assert_eq!(summary.sources[1].today.cost, 0.5);
+        assert_eq!(summary.sources[1].this_month.cost, 0.5);
+        assert_eq!(summary.today.cost, 1.5);
+        assert_eq!(summary.today.total_tokens, 220);
+        assert_eq!(summary.this_month.cost, 10.5);
+        assert_eq!(summary.daily_usage.len(), 2);
+        assert_eq!(summary.daily_usage[0].date, "2000-01-01");
+        assert_eq!(summary.model_breakdown.len(), 1);
+    }
+
+    #[test]
+    fn test_summary_history_start() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // 54 weeks back from 2025-03-12 is 2024-02-29, rounded to its month
+        assert_eq!(
+            summary_history_start(date("2025-03-12")),
+            date("2024-02-01")
+        );
+        assert_eq!(
+            summary_history_start(date("2025-01-01")),
+            date("2023-12-01")
+        );
+    }
+
+    #[test]
+    fn test_period_start() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(period_start(date("2025-03-30"), None), date("2025-03-01"));
+        assert_eq!(
+            period_start(date("2025-03-12"), Some(12)),
+            date("2025-03-12")
+        );
+        assert_eq!(
+            period_start(date("2025-03-11"), Some(12)),
+            date("2025-02-12")
+        );
+        assert_eq!(
+            period_start(date("2025-03-15"), Some(31)),
+            date("2025-02-28")
+        );
+        assert_eq!(
+            period_start(date("2025-02-28"), Some(31)),
+            date("2025-02-28")
+        );
+        assert_eq!(
+            period_start(date("2025-01-05"), Some(20)),
+            date("2024-12-20")
+        );
+    }
+
+    #[test]
+    fn test_restrict_to_period() {
+        let mut summary = summary_from_daily(
+            aggregate_records(vec![
+                record("2025-03-11", "a", 10, 10, 1.0),
+                record("2025-03-12", "b", 10, 10, 2.0),
+                record("2025-03-13", "a", 10, 10, 4.0),
+            ]),
+            "2025-03-13",
+        );
+        restrict_to_period(&mut summary, NaiveDate::from_ymd_opt(2025, 3, 12).unwrap());
+        assert_eq!(summary.this_month.cost, 6.0);
+        assert_eq!(summary.this_month.total_tokens, 40);
+        assert_eq!(summary.model_breakdown.len(), 2);
+        assert_eq!(summary.daily_usage.len(), 3);
+    }
+
+    #[test]
+    fn test_key_breakdown_combines_sub_sources() {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let keyed = |key: &str, cost: f64| UsageRecord {
+            key: Some(key.to_string()),
+            ..record(&today, "gpt-4o", 10, 10, cost)
+        };
+        let records = vec![
+            keyed("…ab12", 1.0),
+            keyed("…cd34", 3.0),
+            record(&today, "gpt-4o", 1, 1, 0.5),
+        ];
+        let keys = aggregate_records_by_key(&records);
+        assert_eq!(keys.len(), 2);
+
+        let mut summary = UsageSummary {
+            today: UsageData::default(),
+            this_month: UsageData::default(),
+            daily_usage: vec![],
+            model_breakdown: vec![],
+            sources: vec![],
+            trend: None,
+            limits: None,
+            active_block_end: None,
+            meta: RefreshMeta::default(),
+        };
+        let reports: Vec<SourceReport> = ["proxy:…ab12", "proxy:…cd34"]
+            .iter()
+            .map(|id| SourceReport {
+                id: (*id).to_string(),
+                label: (*id).to_string(),
+                daily: aggregate_records(records.clone()),
+                keys: keys.clone(),
+                credits: None,
+            })
+            .collect();
+        apply_reports(&mut summary, &reports);
+
+        assert_eq!(summary.sources[1].keys[0].key, "…cd34");
+        let breakdown = key_breakdown(&summary.sources, "proxy");
+        assert_eq!(breakdown.len(), 2);
+        assert_eq!(breakdown[0].key, "…cd34");
+        assert_eq!(breakdown[0].today.cost, 6.0);
+        assert_eq!(breakdown[1].this_month.cost, 2.0);
+        assert!(key_breakdown(&summary.sources, "prox").is_empty());
+        assert!(key_breakdown(&summary.sources, CLAUDE_CODE_SOURCE_ID).is_empty());
+
+        let leaderboard = member_leaderboard(&summary.sources);
+        assert_eq!(leaderboard.len(), 2);
+        assert_eq!(leaderboard[0].member, "…cd34");
+        assert_eq!(leaderboard[0].sources, vec!["proxy:…ab12", "proxy:…cd34"]);
+        assert_eq!(leaderboard[0].this_month.cost, 6.0);
+    }
+
+    #[test]
+    fn test_group_minor_models() {
+        let model = |name: &str, cost: f64| ModelUsage {
+            model: name.to_string(),
+            cost,
+            input_tokens: 10,
+            output_tokens: 5,
+        };
+        let mut models = vec![
+            model("claude-opus-4-5", 30.0),
+            model("claude-haiku-4-5", 0.6),
+            model("gpt-4o-mini", 0.9),
+        ];
+
+        group_minor_models(&mut models, 0.0);
+        assert_eq!(models.len(), 3);
+
+        // $0.05/day over 30 days = $1.50
+        group_minor_models(&mut models, 0.05);
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].model, "claude-opus-4-5");
+        assert_eq!(models[1].model, OTHER_MODELS_LABEL);
+        assert!((models[1].cost - 1.5).abs() < 1e-9);
+        assert_eq!(models[1].input_tokens, 20);
+    }
+
+    #[test]
+    fn test_sort_models() {
+        let model = |name: &str, cost: f64, tokens: u64| ModelUsage {
+            model: name.to_string(),
+            cost,
+            input_tokens: tokens,
+            output_tokens: 0,
+        };
+        let mut models = vec![
+            model(OTHER_MODELS_LABEL, 50.0, 10),
+            model("gpt-4o", 2.0, 900),
+            model("claude-opus-4-5", 2.0, 100),
+            model("claude-haiku-4-5", 1.0, 900),
+            model("gemini-2.5-pro", 8.0, 10),
+        ];
+        let order =
+            |models: &[ModelUsage]| models.iter().map(|m| m.model.clone()).collect::<Vec<_>>();
+
+        sort_models(&mut models, ModelSortKey::Cost);
+        assert_eq!(
+            order(&models),
+            [
+                "gemini-2.5-pro",
+                "claude-opus-4-5",
+                "gpt-4o",
+                "claude-haiku-4-5",
+                "Other"
+            ]
+        );
+
+        sort_models(&mut models, ModelSortKey::Tokens);
+        assert_eq!(
+            order(&models),
+            [
+                "gpt-4o",
+                "claude-haiku-4-5",
+                "claude-opus-4-5",
+                "gemini-2.5-pro",
+                "Other"
+            ]
+        );
+
+        sort_models(&mut models, ModelSortKey::Name);
+        assert_eq!(
+            order(&models),
+            [
+                "claude-haiku-4-5",
+                "claude-opus-4-5",
+                "gemini-2.5-pro",
+                "gpt-4o",
+                "Other"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_provider_today_cost() {
+        let today_start = DateTime::parse_from_rfc3339("2025-03-10T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let point = |hours: i64, cost: f64| ProviderHistoryPoint {
+            fetched_at: today_start + chrono::TimeDelta::hours(hours),
+            result: ProviderUsageResult {
+                cost: Some(cost),
+                tokens: None,
+                used: None,
+                total: None,
+                daily: Vec::new(),
+            },
+        };
+        let history = [point(-30, 2.0), point(-2, 5.0), point(3, 6.0)];
+
+        assert_eq!(provider_today_cost(&history, 7.5, today_start), 2.5);
+        // The counter reset since yesterday
+        assert_eq!(provider_today_cost(&history, 1.0, today_start), 1.0);
+        assert_eq!(provider_today_cost(&history[2..], 7.5, today_start), 0.0);
+    }
+
+    #[test]
+    fn test_apply_provider_costs() {
+        let mut summary = summary_from_daily(Vec::new(), "2025-03-10");
+        summary.today.cost = 1.0;
+        summary.this_month.cost = 10.0;
+        apply_provider_costs(
+            &mut summary,
+            &[ProviderCost {
+                id: "openai".to_string(),
+                label: "OpenAI".to_string(),
+                today: 0.5,
+                this_month: 4.0,
+            }],
+        );
+
+        assert_eq!(summary.today.cost, 1.5);
+        assert_eq!(summary.this_month.cost, 14.0);
+        let source = summary.sources.last().unwrap();
+        assert_eq!(source.id, "provider:openai");
+        assert_eq!(source.label, "OpenAI");
+        assert_eq!((source.today.cost, source.this_month.cost), (0.5, 4.0));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_org_comparison() {
+        let member = |name: &str, cost: f64| MemberUsage {
+            member: name.to_string(),
+            sources: vec!["Windsurf".to_string()],
+            today: UsageData::default(),
+            this_month: UsageData {
+                cost,
+                ..UsageData::default()
+            },
+        };
+        let members = [
+            member("alice@example.com", 120.0),
+            member("bob@example.com", 60.0),
+            member("carol@example.com", 20.0),
+        ];
+
+        let bob = org_comparison(&members, Some("bob@example.com"), 0.0).unwrap();
+        assert_eq!(bob.cost, 60.0);
+        assert_eq!(bob.org_average, 70.0);
+        assert_eq!(bob.percentile, 50.0);
+        assert_eq!(bob.member_count, 2);
+
+        let own = org_comparison(&members, None, 150.0).unwrap();
+        assert_eq!(own.org_average, 200.0 / 3.0);
+        assert_eq!(own.percentile, 100.0);
+
+        assert!(org_comparison(&members, Some("dave@example.com"), 0.0).is_none());
+        assert!(org_comparison(&members[..1], Some("alice@example.com"), 0.0).is_none());
+        assert!(org_comparison(&[], None, 10.0).is_none());
+    }
+}
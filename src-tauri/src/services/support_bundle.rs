@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Page users are sent to from "Report a problem…".
+const NEW_ISSUE_URL: &str = "https://github.com/shixy96/tokenmeter/issues/new";
+
+/// Directory under the config dir that bundles are written to.
+const BUNDLE_DIR: &str = "support";
+
+/// Bundles kept; older ones are removed when a new one is written.
+const KEPT_BUNDLES: usize = 5;
+
+/// Writes `files` (name and contents) to a new zip under `config_dir`, named
+/// after `stamp`, and removes all but the newest few earlier bundles.
+///
+/// # Errors
+/// Returns an error if the directory or archive cannot be written.
+pub fn write_bundle(config_dir: &Path, stamp: &str, files: &[(&str, Vec<u8>)]) -> Result<PathBuf> {
+    let dir = config_dir.join(BUNDLE_DIR);
+    fs::create_dir_all(&dir).context("Failed to create support bundle directory")?;
+    let path = dir.join(format!("tokenmeter-support-{stamp}.zip"));
+
+    let file = fs::File::create(&path).context("Failed to create support bundle")?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, contents) in files {
+        zip.start_file(*name, options)?;
+        zip.write_all(contents)?;
+    }
+    zip.finish()?;
+
+    prune_bundles(&dir);
+    Ok(path)
+}
+
+/// Removes all but the newest [`KEPT_BUNDLES`] bundles in `dir`; names sort
+/// by their timestamp.
+fn prune_bundles(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut bundles: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zip"))
+        .collect();
+    bundles.sort();
+    let stale = bundles.len().saturating_sub(KEPT_BUNDLES);
+    for path in &bundles[..stale] {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// GitHub new-issue page with a body template that points at `bundle`.
+///
+/// # Errors
+/// Returns an error if the URL cannot be built.
+pub fn issue_url(bundle: &Path, version: &str) -> Result<String> {
+    let body = format!(
+        "**What happened?**\n\n\n**What did you expect?**\n\n\n\
+         TokenMeter {version} on {} ({})\n\n\
+         Support bundle: `{}` (secrets are stripped; attach it by dragging it here)\n",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        bundle.display()
+    );
+    let url = reqwest::Url::parse_with_params(NEW_ISSUE_URL, [("body", body)])?;
+    Ok(url.to_string())
+}
+
+/// Replaces every occurrence of a value in `secrets` within `text`, for free
+/// text such as error messages that may echo a credential.
+#[must_use]
+pub fn scrub(text: &str, secrets: &[String], mask: &str) -> String {
+    secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret.as_str(), mask)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_write_bundle_keeps_newest() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        let files = [("version.json", br#"{"version":"1.0.0"}"#.to_vec())];
+        for day in 1..=7 {
+            write_bundle(dir, &format!("2025010{day}-120000"), &files).unwrap();
+        }
+
+        let bundles: Vec<_> = fs::read_dir(dir.join(BUNDLE_DIR)).unwrap().collect();
+        assert_eq!(bundles.len(), KEPT_BUNDLES);
+        assert!(!dir
+            .join(BUNDLE_DIR)
+            .join("tokenmeter-support-20250102-120000.zip")
+            .exists());
+
+        let path = dir
+            .join(BUNDLE_DIR)
+            .join("tokenmeter-support-20250107-120000.zip");
+        let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        let mut contents = String::new();
+        archive
+            .by_name("version.json")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, r#"{"version":"1.0.0"}"#);
+    }
+
+    #[test]
+    fn test_issue_url_references_bundle() {
+        let url = issue_url(Path::new("/tmp/support/bundle.zip"), "1.2.3").unwrap();
+        assert!(url.starts_with(NEW_ISSUE_URL));
+        let parsed = reqwest::Url::parse(&url).unwrap();
+        let body = parsed
+            .query_pairs()
+            .find(|(key, _)| key == "body")
+            .map(|(_, value)| value.into_owned())
+            .unwrap();
+        assert!(body.contains("`/tmp/support/bundle.zip`"));
+        assert!(body.contains("TokenMeter 1.2.3"));
+    }
+
+    #[test]
+    fn test_scrub() {
+        let secrets = vec!["sk-live-123".to_string(), String::new()];
+        assert_eq!(
+            scrub("401 for key sk-live-123", &secrets, "***"),
+            "401 for key ***"
+        );
+        assert_eq!(scrub("timed out", &secrets, "***"), "timed out");
+    }
+}
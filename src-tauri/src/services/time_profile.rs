@@ -0,0 +1,158 @@
+use crate::services::claude_logs::LogEntry;
+use crate::services::pricing::ModelPrice;
+use crate::types::{HourBucket, PeakWindow, TimeProfile, WeekdayBucket};
+use chrono::{Datelike, Timelike};
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// Width of the peak window reported in the profile.
+pub const PEAK_WINDOW_HOURS: u32 = 4;
+
+#[allow(clippy::cast_precision_loss)]
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
+/// Finds the `width`-hour window (wrapping past midnight) with the most tokens.
+fn find_peak_window(hour_tokens: &[u64; 24], total: u64, width: u32) -> Option<PeakWindow> {
+    if total == 0 {
+        return None;
+    }
+    let (start, tokens) = (0..24u32)
+        .map(|start| {
+            let tokens = (start..start + width)
+                .map(|h| hour_tokens[(h % 24) as usize])
+                .sum::<u64>();
+            (start, tokens)
+        })
+        .max_by_key(|&(start, tokens)| (tokens, std::cmp::Reverse(start)))?;
+
+    Some(PeakWindow {
+        start_hour: start,
+        end_hour: (start + width) % 24,
+        percent: percent(tokens, total),
+    })
+}
+
+/// Aggregates usage entries into an hour-of-day / weekday profile (local time).
+#[must_use]
+pub fn build_profile<S: BuildHasher>(
+    entries: &[LogEntry],
+    days: u32,
+    prices: Option<&HashMap<String, ModelPrice, S>>,
+) -> TimeProfile {
+    let mut hour_tokens = [0u64; 24];
+    let mut hour_cost = [0f64; 24];
+    let mut weekday_tokens = [0u64; 7];
+    let mut weekday_cost = [0f64; 7];
+    let mut heatmap = vec![vec![0u64; 24]; 7];
+
+    for entry in entries {
+        let hour = entry.timestamp.hour() as usize;
+        let weekday = entry.timestamp.weekday().num_days_from_monday() as usize;
+        let tokens = entry.total_tokens();
+        let cost = entry.resolved_cost(prices);
+
+        hour_tokens[hour] += tokens;
+        hour_cost[hour] += cost;
+        weekday_tokens[weekday] += tokens;
+        weekday_cost[weekday] += cost;
+        heatmap[weekday][hour] += tokens;
+    }
+
+    let total_tokens: u64 = hour_tokens.iter().sum();
+    let total_cost: f64 = hour_cost.iter().sum();
+
+    TimeProfile {
+        days,
+        total_tokens,
+        total_cost,
+        hours: (0..24u32)
+            .map(|hour| HourBucket {
+                hour,
+                tokens: hour_tokens[hour as usize],
+                cost: hour_cost[hour as usize],
+                percent: percent(hour_tokens[hour as usize], total_tokens),
+            })
+            .collect(),
+        weekdays: (0..7u32)
+            .map(|weekday| WeekdayBucket {
+                weekday,
+                tokens: weekday_tokens[weekday as usize],
+                cost: weekday_cost[weekday as usize],
+                percent: percent(weekday_tokens[weekday as usize], total_tokens),
+            })
+            .collect(),
+        heatmap,
+        peak_window: find_peak_window(&hour_tokens, total_tokens, PEAK_WINDOW_HOURS),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    const NO_PRICES: Option<&HashMap<String, ModelPrice>> = None;
+
+    fn entry(day: u32, hour: u32, tokens: u64) -> LogEntry {
+        LogEntry {
+            timestamp: Local
+                .with_ymd_and_hms(2025, 6, day, hour, 15, 0)
+                .single()
+                .expect("test timestamp should be valid"),
+            model: "claude-sonnet-4".to_string(),
+            input_tokens: tokens,
+            output_tokens: 0,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            cost: Some(1.0),
+        }
+    }
+
+    #[test]
+    fn test_build_profile_buckets() {
+        // 2025-06-02 is a Monday, 2025-06-08 a Sunday
+        let entries = vec![entry(2, 9, 100), entry(2, 9, 100), entry(8, 14, 200)];
+        let profile = build_profile(&entries, 30, NO_PRICES);
+
+        assert_eq!(profile.total_tokens, 400);
+        assert_eq!(profile.total_cost, 3.0);
+        assert_eq!(profile.hours.len(), 24);
+        assert_eq!(profile.hours[9].tokens, 200);
+        assert_eq!(profile.hours[9].percent, 50.0);
+        assert_eq!(profile.weekdays[0].tokens, 200);
+        assert_eq!(profile.weekdays[6].tokens, 200);
+        assert_eq!(profile.heatmap[6][14], 200);
+    }
+
+    #[test]
+    fn test_peak_window_wraps_midnight() {
+        let entries = vec![
+            entry(2, 22, 300),
+            entry(2, 23, 300),
+            entry(3, 0, 300),
+            entry(3, 12, 100),
+        ];
+        let profile = build_profile(&entries, 30, NO_PRICES);
+        let peak = profile.peak_window.expect("peak window should exist");
+
+        // Earliest start among equally heavy windows covering 22:00-01:00
+        assert_eq!(peak.start_hour, 21);
+        assert_eq!(peak.end_hour, 1);
+        assert_eq!(peak.percent, 90.0);
+    }
+
+    #[test]
+    fn test_empty_profile() {
+        let profile = build_profile(&[], 7, NO_PRICES);
+        assert_eq!(profile.total_tokens, 0);
+        assert!(profile.peak_window.is_none());
+        assert!(profile.hours.iter().all(|h| h.percent == 0.0));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::config::{TrendBaseline, TrendsConfig};
+use crate::types::{DailyUsage, TrendInfo, UsageSummary};
+use chrono::{Datelike, NaiveDate};
+
+/// Days of history considered by the mean and EWMA baselines.
+const LONG_WINDOW_DAYS: i64 = 30;
+/// Days of history considered by the median baseline.
+const SHORT_WINDOW_DAYS: i64 = 7;
+/// Weeks of history considered by the same-weekday baseline.
+const SAME_WEEKDAY_WEEKS: i64 = 4;
+/// Smoothing factor for the exponentially weighted average (weight of the newest day).
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Daily costs recorded in `[today - days, today)`, oldest first.
+fn costs_before(daily: &[DailyUsage], today: NaiveDate, days: i64) -> Vec<(NaiveDate, f64)> {
+    let start = today - chrono::Duration::days(days);
+    let mut costs: Vec<(NaiveDate, f64)> = daily
+        .iter()
+        .filter_map(|d| {
+            let date = NaiveDate::parse_from_str(&d.date, "%Y-%m-%d").ok()?;
+            (date >= start && date < today).then_some((date, d.cost))
+        })
+        .collect();
+    costs.sort_by_key(|(date, _)| *date);
+    costs
+}
+
+/// Drops the most expensive `percent`% of days (rounded up), keeping the rest in order.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn exclude_outliers(mut costs: Vec<(NaiveDate, f64)>, percent: f64) -> Vec<(NaiveDate, f64)> {
+    let count = ((costs.len() as f64) * percent / 100.0).ceil() as usize;
+    if count == 0 || count >= costs.len() {
+        return costs;
+    }
+    let mut by_cost: Vec<f64> = costs.iter().map(|(_, cost)| *cost).collect();
+    by_cost.sort_by(|a, b| b.total_cmp(a));
+    let cutoff = by_cost[count - 1];
+
+    // Remove exactly `count` days, so ties at the cutoff don't drop extra history
+    let mut remaining = count;
+    costs.retain(|(_, cost)| {
+        if remaining > 0 && *cost >= cutoff {
+            remaining -= 1;
+            false
+        } else {
+            true
+        }
+    });
+    costs
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    Some(if sorted.len().is_multiple_of(2) {
+        f64::midpoint(sorted[mid - 1], sorted[mid])
+    } else {
+        sorted[mid]
+    })
+}
+
+fn ewma(values: &[f64]) -> Option<f64> {
+    let (first, rest) = values.split_first()?;
+    Some(
+        rest.iter()
+            .fold(*first, |avg, &v| EWMA_ALPHA.mul_add(v - avg, avg)),
+    )
+}
+
+/// Computes the baseline daily cost from history before `today`.
+///
+/// When outlier exclusion is enabled, the most expensive days in the baseline's
+/// window are left out before averaging.
+#[must_use]
+pub fn baseline_cost(daily: &[DailyUsage], today: NaiveDate, config: &TrendsConfig) -> Option<f64> {
+    let values = |costs: Vec<(NaiveDate, f64)>| -> Vec<f64> {
+        let costs = if config.exclude_outliers {
+            exclude_outliers(costs, config.outlier_percent)
+        } else {
+            costs
+        };
+        costs.into_iter().map(|(_, cost)| cost).collect()
+    };
+
+    match config.baseline {
+        TrendBaseline::Mean30 => mean(&values(costs_before(daily, today, LONG_WINDOW_DAYS))),
+        TrendBaseline::Median7 => median(&values(costs_before(daily, today, SHORT_WINDOW_DAYS))),
+        TrendBaseline::Ewma => ewma(&values(costs_before(daily, today, LONG_WINDOW_DAYS))),
+        TrendBaseline::SameWeekday => {
+            let same_weekday = costs_before(daily, today, SAME_WEEKDAY_WEEKS * 7)
+                .into_iter()
+                .filter(|(date, _)| date.weekday() == today.weekday())
+                .collect();
+            mean(&values(same_weekday))
+        }
+    }
+}
+
+/// Percent change of `today_cost` relative to the configured baseline.
+///
+/// Returns `None` when there is no history or the baseline is zero.
+#[must_use]
+pub fn calculate_change(
+    today_cost: f64,
+    daily: &[DailyUsage],
+    today: NaiveDate,
+    config: &TrendsConfig,
+) -> Option<TrendInfo> {
+    let baseline_cost = baseline_cost(daily, today, config).filter(|&b| b > 0.0)?;
+    Some(TrendInfo {
+        baseline: config.baseline,
+        baseline_cost,
+        change_percent: (today_cost - baseline_cost) / baseline_cost * 100.0,
+    })
+}
+
+/// Recomputes `summary.trend` for the summary's current day.
+pub fn apply_trend(summary: &mut UsageSummary, config: &TrendsConfig) {
+    let today = NaiveDate::parse_from_str(&summary.today.date, "%Y-%m-%d")
+        .unwrap_or_else(|_| chrono::Local::now().date_naive());
+    summary.trend = calculate_change(summary.today.cost, &summary.daily_usage, today, config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, cost: f64) -> DailyUsage {
+        DailyUsage {
+            date: date.to_string(),
+            cost,
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            models: vec![],
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").expect("test date should parse")
+    }
+
+    fn history() -> Vec<DailyUsage> {
+        // 2024-01-15 is a Monday
+        vec![
+            day("2023-12-01", 100.0), // outside every window
+            day("2024-01-01", 4.0),   // Monday
+            day("2024-01-08", 8.0),   // Monday
+            day("2024-01-10", 2.0),
+            day("2024-01-12", 40.0),
+            day("2024-01-14", 6.0),
+            day("2024-01-15", 99.0), // today, excluded
+        ]
+    }
+
+    fn config(baseline: TrendBaseline) -> TrendsConfig {
+        TrendsConfig {
+            baseline,
+            ..TrendsConfig::default()
+        }
+    }
+
+    fn approx(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("baseline should exist");
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_baselines() {
+        let today = date("2024-01-15");
+        let daily = history();
+
+        approx(
+            baseline_cost(&daily, today, &config(TrendBaseline::Mean30)),
+            12.0,
+        );
+        approx(
+            baseline_cost(&daily, today, &config(TrendBaseline::Median7)),
+            7.0,
+        );
+        approx(
+            baseline_cost(&daily, today, &config(TrendBaseline::SameWeekday)),
+            6.0,
+        );
+
+        // 4 → 8 → 2 → 40 → 6 with alpha 0.3
+        let expected = [8.0, 2.0, 40.0, 6.0]
+            .iter()
+            .fold(4.0_f64, |avg, v| 0.3f64.mul_add(v - avg, avg));
+        approx(
+            baseline_cost(&daily, today, &config(TrendBaseline::Ewma)),
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_calculate_change() {
+        let today = date("2024-01-15");
+        let daily = history();
+
+        let trend = calculate_change(18.0, &daily, today, &config(TrendBaseline::Mean30))
+            .expect("trend should exist");
+        assert!((trend.change_percent - 50.0).abs() < 1e-9);
+
+        assert!(calculate_change(18.0, &[], today, &config(TrendBaseline::Mean30)).is_none());
+        let zero = vec![day("2024-01-14", 0.0)];
+        assert!(calculate_change(18.0, &zero, today, &config(TrendBaseline::Median7)).is_none());
+    }
+
+    #[test]
+    fn test_exclude_outliers() {
+        let today = date("2024-01-15");
+        let daily = history();
+        let mut trends = TrendsConfig {
+            exclude_outliers: true,
+            outlier_percent: 20.0,
+            ..TrendsConfig::default()
+        };
+
+        // 40.0 is the top 20% of the five days in the window
+        approx(baseline_cost(&daily, today, &trends), 5.0);
+
+        trends.baseline = TrendBaseline::Median7;
+        approx(baseline_cost(&daily, today, &trends), 6.0);
+
+        // Rounds up, dropping the 8.0 Monday
+        trends.baseline = TrendBaseline::SameWeekday;
+        trends.outlier_percent = 10.0;
+        approx(baseline_cost(&daily, today, &trends), 4.0);
+
+        // Never drops every day
+        trends.baseline = TrendBaseline::Mean30;
+        let single = vec![day("2024-01-14", 6.0)];
+        approx(baseline_cost(&single, today, &trends), 6.0);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::config::WindsurfSourceConfig;
+use crate::services::http;
+use crate::services::sources::{self, SourceReport, UsageRecord};
+use crate::types::DailyUsage;
+use anyhow::Result;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub const SOURCE_ID: &str = "windsurf";
+const SOURCE_LABEL: &str = "Windsurf";
+
+const CASCADE_ANALYTICS_URL: &str = "https://server.codeium.com/api/v1/CascadeAnalytics";
+/// `promptsUsed` is reported in hundredths of a prompt credit.
+const PROMPT_UNITS_PER_CREDIT: f64 = 100.0;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CascadeAnalyticsResponse {
+    #[serde(default)]
+    query_results: Vec<QueryResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryResult {
+    #[serde(default)]
+    cascade_runs: Option<CascadeRuns>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CascadeRuns {
+    #[serde(default)]
+    cascade_runs: Vec<CascadeRun>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CascadeRun {
+    /// RFC 3339 timestamp of the (UTC) day the run happened.
+    day: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default, deserialize_with = "deserialize_int64")]
+    prompts_used: u64,
+}
+
+/// Cascade Analytics encodes int64 fields as JSON strings; accept both forms.
+fn deserialize_int64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(s) => s.parse().map_err(serde::de::Error::custom),
+        StringOrNumber::Number(n) => Ok(n),
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn aggregate_runs(runs: Vec<CascadeRun>, credit_price: f64) -> Vec<DailyUsage> {
+    sources::aggregate_records(runs.into_iter().filter_map(|run| {
+        let date = run.day.get(..10)?.to_string();
+        let model = if run.model.is_empty() {
+            "unknown".to_string()
+        } else {
+            run.model
+        };
+        Some(UsageRecord {
+            date,
+            model,
+            cost: run.prompts_used as f64 / PROMPT_UNITS_PER_CREDIT * credit_price,
+            ..UsageRecord::default()
+        })
+    }))
+}
+
+/// Fetches Cascade usage from the Windsurf Cascade Analytics API.
+///
+/// The API reports prompt credits rather than tokens, so Windsurf contributes
+/// cost (credits × configured credit price) but no token counts.
+///
+/// # Errors
+/// Returns an error if no service key is configured, the request fails,
+/// or the response cannot be parsed.
+#[tracing::instrument(name = "source.windsurf", skip_all)]
+pub async fn fetch_usage(config: &WindsurfSourceConfig, timeout: Duration) -> Result<SourceReport> {
+    let service_key = config
+        .service_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Windsurf service key is not configured"))?;
+
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::days(sources::SUMMARY_WINDOW_DAYS);
+    let mut body = serde_json::json!({
+        "service_key": service_key,
+        "start_timestamp": start.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "end_timestamp": end.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "query_requests": [{ "cascade_runs": {} }],
+    });
+    if let Some(email) = config.email.as_deref().filter(|e| !e.is_empty()) {
+        body["emails"] = serde_json::json!([email]);
+    }
+
+    let response: CascadeAnalyticsResponse = http::client()
+        .post(CASCADE_ANALYTICS_URL)
+        .timeout(timeout)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let runs = response
+        .query_results
+        .into_iter()
+        .filter_map(|r| r.cascade_runs)
+        .flat_map(|r| r.cascade_runs)
+        .collect();
+
+    Ok(SourceReport {
+        id: SOURCE_ID.to_string(),
+        label: SOURCE_LABEL.to_string(),
+        daily: aggregate_runs(runs, config.credit_price),
+        keys: BTreeMap::new(),
+        credits: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cascade_analytics_response() {
+        let json = r#"{
+            "queryResults": [{
+                "cascadeRuns": {
+                    "cascadeRuns": [
+                        {
+                            "day": "2025-05-01T00:00:00Z",
+                            "model": "Claude 3.7 Sonnet",
+                            "mode": "CONVERSATIONAL_PLANNER_MODE_DEFAULT",
+                            "messagesSent": "3",
+                            "promptsUsed": "300",
+                            "cascadeId": "abc"
+                        },
+                        {
+                            "day": "2025-05-01T00:00:00Z",
+                            "model": "SWE-1",
+                            "promptsUsed": 50
+                        },
+                        {
+                            "day": "2025-05-02T00:00:00Z",
+                            "model": "Claude 3.7 Sonnet",
+                            "promptsUsed": "100"
+                        }
+                    ]
+                }
+            }]
+        }"#;
+
+        let response: CascadeAnalyticsResponse =
+            serde_json::from_str(json).expect("test JSON should parse correctly");
+        let runs: Vec<CascadeRun> = response
+            .query_results
+            .into_iter()
+            .filter_map(|r| r.cascade_runs)
+            .flat_map(|r| r.cascade_runs)
+            .collect();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].prompts_used, 300);
+        assert_eq!(runs[1].prompts_used, 50);
+
+        let daily = aggregate_runs(runs, 0.04);
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].date, "2025-05-01");
+        assert_eq!(daily[0].models.len(), 2);
+        assert!((daily[0].cost - 0.14).abs() < 1e-9);
+        assert!((daily[1].cost - 0.04).abs() < 1e-9);
+        assert_eq!(daily[1].input_tokens, 0);
+    }
+
+    #[test]
+    fn test_parse_empty_response() {
+        let response: CascadeAnalyticsResponse =
+            serde_json::from_str("{}").expect("empty response should parse");
+        assert!(response.query_results.is_empty());
+    }
+}
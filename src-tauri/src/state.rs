@@ -1,17 +1,52 @@
-use crate::config::AppConfig;
-use crate::types::UsageSummary;
+use crate::commands::providers;
+use crate::config::{AppConfig, GuardConfig, ProxySourceConfig};
+use crate::guard;
+use crate::services::{credentials, proxy};
+use crate::storage;
+use crate::types::{Diagnostics, ProviderHealth, ProviderResultCache, UsageSummary};
 use anyhow::Result;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::Mutex;
+use tauri::Manager;
+use tokio::sync::{Mutex, RwLock};
+
+/// Access to the [`AppState`] for background services, which run both in the
+/// GUI (through its Tauri handle) and in the headless agent.
+pub trait StateHandle: Clone + Send + Sync + 'static {
+    fn app_state(&self) -> &AppState;
+}
+
+impl StateHandle for tauri::AppHandle {
+    fn app_state(&self) -> &AppState {
+        self.state::<AppState>().inner()
+    }
+}
+
+impl StateHandle for Arc<AppState> {
+    fn app_state(&self) -> &AppState {
+        self
+    }
+}
 
 pub struct AppState {
     pub config: Mutex<AppConfig>,
-    pub usage: Mutex<Option<UsageSummary>>,
+    /// Latest summary, shared by `Arc` so the tray, commands and background
+    /// tasks read it without cloning. Replaced wholesale on each refresh.
+    pub usage: RwLock<Option<Arc<UsageSummary>>>,
     pub usage_fetched_at: Mutex<Option<Instant>>,
     pub usage_refresh_lock: Mutex<()>,
     pub config_dir: PathBuf,
+    pub proxy_task: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    pub guard_task: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Shared with background tasks that report their health.
+    pub diagnostics: Arc<Mutex<Diagnostics>>,
+    /// Last successful result per provider, mirrored to `provider_cache.json`.
+    pub provider_cache: Mutex<ProviderResultCache>,
+    /// Fetch health per provider id, mirrored to the provider files.
+    pub provider_health: Mutex<HashMap<String, ProviderHealth>>,
 }
 
 impl AppState {
@@ -28,13 +63,20 @@ impl AppState {
         fs::create_dir_all(config_dir.join("providers"))?;
 
         let config = Self::load_config(&config_dir);
+        let provider_cache = storage::load_provider_cache(&config_dir);
+        let provider_health = Self::load_provider_health(&config_dir);
 
         Ok(Self {
             config: Mutex::new(config),
-            usage: Mutex::new(None),
+            usage: RwLock::new(None),
             usage_fetched_at: Mutex::new(None),
             usage_refresh_lock: Mutex::new(()),
             config_dir,
+            proxy_task: Mutex::new(None),
+            guard_task: Mutex::new(None),
+            diagnostics: Arc::new(Mutex::new(Diagnostics::default())),
+            provider_cache: Mutex::new(provider_cache),
+            provider_health: Mutex::new(provider_health),
         })
     }
 
@@ -45,13 +87,44 @@ impl AppState {
             .and_then(|content| {
                 serde_json::from_str(&content)
                     .inspect_err(|e| {
-                        eprintln!("Warning: Failed to parse config file, using defaults: {e}");
+                        tracing::warn!("Failed to parse config file, using defaults: {e}");
                     })
                     .ok()
             })
             .unwrap_or_default()
     }
 
+    fn load_provider_health(config_dir: &Path) -> HashMap<String, ProviderHealth> {
+        providers::load_providers(config_dir)
+            .unwrap_or_default()
+            .iter()
+            .map(|provider| (provider.id.clone(), ProviderHealth::of(provider)))
+            .collect()
+    }
+
+    /// Re-reads the fetch health recorded in the provider files, e.g. after
+    /// another profile's providers were put in place.
+    pub async fn reload_provider_health(&self) {
+        *self.provider_health.lock().await = Self::load_provider_health(&self.config_dir);
+    }
+
+    /// Re-reads `config.json`, e.g. after another profile's settings were put in place.
+    #[must_use]
+    pub fn read_config(&self) -> AppConfig {
+        Self::load_config(&self.config_dir)
+    }
+
+    /// Moves credentials found in the loaded config into the keychain, saving
+    /// the config without them.
+    pub async fn move_credentials_to_keychain(&self) {
+        let mut config = self.config.lock().await;
+        if credentials::move_to_keychain(&mut config).await {
+            if let Err(e) = self.save_config(&config) {
+                tracing::warn!("Failed to save config after moving credentials: {e}");
+            }
+        }
+    }
+
     /// Saves the configuration to disk.
     ///
     /// # Errors
@@ -62,4 +135,185 @@ impl AppState {
         fs::write(config_path, content)?;
         Ok(())
     }
+
+    /// Returns the latest usage summary without copying it.
+    pub async fn usage_snapshot(&self) -> Option<Arc<UsageSummary>> {
+        self.usage.read().await.clone()
+    }
+
+    /// Publishes a freshly fetched summary and records when it was fetched.
+    pub async fn set_usage(&self, data: Arc<UsageSummary>) {
+        *self.usage.write().await = Some(data);
+        *self.usage_fetched_at.lock().await = Some(Instant::now());
+    }
+
+    /// Marks the current summary as stale so the next read refetches it,
+    /// keeping it available until then.
+    pub async fn invalidate_usage(&self) {
+        *self.usage_fetched_at.lock().await = None;
+    }
+
+    /// Marks the current summary (if any) with a failed refresh's error. The
+    /// fetch time is left alone so the summary still reads as stale.
+    pub async fn record_refresh_error(&self, error: String) {
+        if let Some(usage) = self.usage.write().await.as_mut() {
+            Arc::make_mut(usage).meta.last_error = Some(error);
+        }
+    }
+
+    /// Stops the running API proxy (if any) and starts a new one when enabled.
+    pub async fn restart_proxy(&self, config: &ProxySourceConfig) {
+        let mut task = self.proxy_task.lock().await;
+        if let Some(handle) = task.take() {
+            handle.abort();
+        }
+        if !config.enabled {
+            return;
+        }
+
+        let config = config.clone();
+        let config_dir = self.config_dir.clone();
+        *task = Some(tauri::async_runtime::spawn(async move {
+            if let Err(e) = proxy::serve(config, config_dir).await {
+                tracing::error!("API proxy stopped: {e}");
+            }
+        }));
+    }
+
+    /// Stops the running guard endpoint (if any) and starts a new one when enabled.
+    pub async fn restart_guard<H: StateHandle>(&self, app: &H, config: &GuardConfig) {
+        let mut task = self.guard_task.lock().await;
+        if let Some(handle) = task.take() {
+            handle.abort();
+        }
+        if !config.enabled {
+            return;
+        }
+
+        let app = app.clone();
+        let config = config.clone();
+        let config_dir = self.config_dir.clone();
+        *task = Some(tauri::async_runtime::spawn(async move {
+            if let Err(e) = guard::serve(app, config, config_dir).await {
+                tracing::error!("Guard endpoint stopped: {e}");
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::types::{RefreshMeta, UsageData};
+
+    fn test_state() -> AppState {
+        AppState {
+            config: Mutex::new(AppConfig::default()),
+            usage: RwLock::new(None),
+            usage_fetched_at: Mutex::new(None),
+            usage_refresh_lock: Mutex::new(()),
+            config_dir: std::env::temp_dir(),
+            proxy_task: Mutex::new(None),
+            guard_task: Mutex::new(None),
+            diagnostics: Arc::new(Mutex::new(Diagnostics::default())),
+            provider_cache: Mutex::new(ProviderResultCache::new()),
+            provider_health: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn summary(cost: f64) -> Arc<UsageSummary> {
+        Arc::new(UsageSummary {
+            today: UsageData {
+                cost,
+                ..UsageData::default()
+            },
+            this_month: UsageData::default(),
+            daily_usage: Vec::new(),
+            model_breakdown: Vec::new(),
+            sources: Vec::new(),
+            trend: None,
+            limits: None,
+            active_block_end: None,
+            meta: RefreshMeta::default(),
+        })
+    }
+
+    async fn snapshot_cost(state: &AppState) -> f64 {
+        state.usage_snapshot().await.expect("usage set").today.cost
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_survives_refresh() {
+        let state = test_state();
+        state.set_usage(summary(1.0)).await;
+        let snapshot = state.usage_snapshot().await.expect("usage set");
+
+        state.set_usage(summary(2.0)).await;
+        assert_eq!(snapshot.today.cost, 1.0);
+        assert_eq!(snapshot_cost(&state).await, 2.0);
+        assert!(state.usage_fetched_at.lock().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_in_place_update_leaves_readers_untouched() {
+        let state = test_state();
+        state.set_usage(summary(1.0)).await;
+        let snapshot = state.usage_snapshot().await.expect("usage set");
+
+        if let Some(usage) = state.usage.write().await.as_mut() {
+            Arc::make_mut(usage).today.cost = 5.0;
+        }
+        assert_eq!(snapshot.today.cost, 1.0);
+        assert_eq!(snapshot_cost(&state).await, 5.0);
+    }
+
+    #[tokio::test]
+    async fn test_record_refresh_error() {
+        let state = test_state();
+        state.record_refresh_error("ignored".to_string()).await;
+        assert!(state.usage_snapshot().await.is_none());
+
+        state.set_usage(summary(1.0)).await;
+        state
+            .record_refresh_error("ccusage failed".to_string())
+            .await;
+        let usage = state.usage_snapshot().await.expect("usage set");
+        assert_eq!(usage.meta.last_error.as_deref(), Some("ccusage failed"));
+        assert_eq!(usage.today.cost, 1.0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_readers_see_monotonic_updates() {
+        let state = Arc::new(test_state());
+        state.set_usage(summary(0.0)).await;
+
+        let writer = {
+            let state = Arc::clone(&state);
+            tokio::spawn(async move {
+                for cost in 1..=100 {
+                    state.set_usage(summary(f64::from(cost))).await;
+                }
+            })
+        };
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let state = Arc::clone(&state);
+                tokio::spawn(async move {
+                    let mut last = 0.0;
+                    for _ in 0..200 {
+                        let cost = snapshot_cost(&state).await;
+                        assert!(cost >= last);
+                        last = cost;
+                    }
+                })
+            })
+            .collect();
+
+        writer.await.expect("writer task");
+        for reader in readers {
+            reader.await.expect("reader task");
+        }
+        assert_eq!(snapshot_cost(&state).await, 100.0);
+    }
 }
@@ -1,50 +1,628 @@
-use crate::types::DailyUsage;
+use crate::types::{
+    DailyUsage, JournalPoint, ProviderDailyUsage, ProviderHistoryPoint, ProviderResultCache,
+    SchedulerState, SummaryCache,
+};
 use anyhow::Result;
-use std::collections::HashMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, NaiveDate, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::Write as _;
 use std::path::Path;
 
-/// Loads usage history from the history.json file.
-pub fn load_history(config_dir: &Path) -> Result<Vec<DailyUsage>> {
-    let history_path = config_dir.join("history.json");
-    if !history_path.exists() {
-        return Ok(Vec::new());
+/// Single-file history written by earlier versions, migrated into shards on first use.
+const LEGACY_HISTORY_FILE: &str = "history.json";
+/// Directory holding one `YYYY-MM.json` shard per month plus `index.json`.
+const HISTORY_DIR: &str = "history";
+const HISTORY_INDEX_FILE: &str = "index.json";
+const HISTORY_JOURNAL_FILE: &str = "journal.jsonl";
+/// Directory holding one `{source}.json` of imported daily usage per source tag.
+const IMPORTS_DIR: &str = "imports";
+/// Directory holding one `{id}.jsonl` of fetched results per provider.
+const PROVIDER_HISTORY_DIR: &str = "provider_history";
+/// Provider history older than this is dropped on the next append.
+const PROVIDER_HISTORY_RETENTION: chrono::TimeDelta = chrono::TimeDelta::days(365);
+
+/// Contents of `history/index.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryIndex {
+    /// SHA-256 (base64) of each shard's contents, keyed by `YYYY-MM`.
+    months: BTreeMap<String, String>,
+    /// Last journal record compacted into the shards.
+    #[serde(default)]
+    journal_seq: u64,
+}
+
+/// One line of `history/journal.jsonl`: the days a save changed.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalRecord {
+    seq: u64,
+    /// RFC 3339 local time of the save.
+    recorded_at: String,
+    days: Vec<DailyUsage>,
+}
+
+fn month_of(date: &str) -> &str {
+    date.get(..7).unwrap_or(date)
+}
+
+fn shard_digest(content: &str) -> String {
+    STANDARD.encode(digest::digest(&digest::SHA256, content.as_bytes()))
+}
+
+/// Reads the shard index, rebuilding the month list from the shard files when
+/// the index is missing or unreadable (digests are then recomputed on save).
+fn load_history_index(history_dir: &Path) -> Result<HistoryIndex> {
+    if let Some(index) = fs::read_to_string(history_dir.join(HISTORY_INDEX_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+    {
+        return Ok(index);
     }
+    if !history_dir.exists() {
+        return Ok(HistoryIndex::default());
+    }
+
+    let mut months = BTreeMap::new();
+    for entry in fs::read_dir(history_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            if let Some(month) = path.file_stem().and_then(|stem| stem.to_str()) {
+                if month != "index" {
+                    months.insert(month.to_string(), String::new());
+                }
+            }
+        }
+    }
+    Ok(HistoryIndex {
+        months,
+        journal_seq: 0,
+    })
+}
+
+/// Loads the shards of months accepted by `include`. A corrupt shard is
+/// skipped with a warning so it only costs its own month.
+fn load_history_months(
+    config_dir: &Path,
+    include: impl Fn(&str) -> bool,
+) -> Result<Vec<DailyUsage>> {
+    let history_dir = config_dir.join(HISTORY_DIR);
+    let index = load_history_index(&history_dir)?;
 
-    let content = fs::read_to_string(history_path)?;
-    let history: Vec<DailyUsage> = serde_json::from_str(&content)?;
+    let mut history = Vec::new();
+    for month in index.months.keys().filter(|month| include(month)) {
+        let path = history_dir.join(format!("{month}.json"));
+        match read_shard(&path) {
+            Ok(days) => history.extend(days),
+            Err(e) => tracing::warn!("Skipping history shard {}: {e}", path.display()),
+        }
+    }
+    history.sort_by(|a, b| a.date.cmp(&b.date));
     Ok(history)
 }
 
-/// Saves usage history to the history.json file atomically.
+/// Brings the shards up to date before they are read or written: migrates a
+/// legacy `history.json` and replays journal records a crash kept from being
+/// compacted.
+fn prepare_history(config_dir: &Path) -> Result<()> {
+    migrate_legacy_history(config_dir)?;
+
+    let history_dir = config_dir.join(HISTORY_DIR);
+    let index = load_history_index(&history_dir)?;
+    let pending: Vec<JournalRecord> = read_journal(&history_dir)?
+        .into_iter()
+        .filter(|record| record.seq > index.journal_seq)
+        .collect();
+    let Some(last_seq) = pending.last().map(|record| record.seq) else {
+        return Ok(());
+    };
+
+    let history = pending.iter().fold(
+        load_history_months(config_dir, |_| true)?,
+        |history, record| merge_history(&history, &record.days),
+    );
+    let mut index = index;
+    let shards = changed_shards(&history_dir, &index, &history)?;
+    commit_shards(&history_dir, &mut index, shards, Some(last_seq))
+}
+
+/// Loads the full usage history from the monthly shards.
+///
+/// # Errors
+/// Returns an error if the history directory cannot be read, or a legacy
+/// `history.json` or pending journal records cannot be applied.
+#[tracing::instrument(name = "storage.load_history", skip_all)]
+pub fn load_history(config_dir: &Path) -> Result<Vec<DailyUsage>> {
+    prepare_history(config_dir)?;
+    load_history_months(config_dir, |_| true)
+}
+
+/// Loads the history between `from` and `to` (inclusive `YYYY-MM-DD` dates),
+/// reading only the shards of the months in range.
+///
+/// # Errors
+/// Returns an error if the history directory cannot be read, or a legacy
+/// `history.json` or pending journal records cannot be applied.
+#[tracing::instrument(name = "storage.load_history_range", skip_all)]
+pub fn load_history_range(config_dir: &Path, from: &str, to: &str) -> Result<Vec<DailyUsage>> {
+    prepare_history(config_dir)?;
+    let (first, last) = (month_of(from), month_of(to));
+    let mut history = load_history_months(config_dir, |month| month >= first && month <= last)?;
+    history.retain(|day| day.date.as_str() >= from && day.date.as_str() <= to);
+    Ok(history)
+}
+
+/// Saves usage history as monthly shards, writing only the months whose
+/// contents changed. Shards of months absent from `history` are left alone.
+///
+/// The changed days are first appended to `history/journal.jsonl` and only
+/// then compacted into the shards, so a crash in between is recovered on the
+/// next load. Records from earlier days are dropped once compacted; today's
+/// are kept as a trail of how its numbers evolved.
+///
+/// # Errors
+/// Returns an error if the history cannot be serialized or written.
+#[tracing::instrument(name = "storage.save_history", skip_all)]
 pub fn save_history(config_dir: &Path, history: &[DailyUsage]) -> Result<()> {
-    if !config_dir.exists() {
-        fs::create_dir_all(config_dir)?;
+    prepare_history(config_dir)?;
+    let history_dir = config_dir.join(HISTORY_DIR);
+    fs::create_dir_all(&history_dir)?;
+
+    let mut index = load_history_index(&history_dir)?;
+    let shards = changed_shards(&history_dir, &index, history)?;
+    if shards.is_empty() {
+        return Ok(());
+    }
+
+    let mut days = Vec::new();
+    for shard in &shards {
+        let stored =
+            read_shard(&history_dir.join(format!("{}.json", shard.month))).unwrap_or_default();
+        days.extend(shard.days.iter().filter(|day| {
+            !stored.iter().any(|old| {
+                old.date == day.date
+                    && serde_json::to_value(old).ok() == serde_json::to_value(day).ok()
+            })
+        }));
+    }
+    let seq = append_journal(&history_dir, &index, days)?;
+    commit_shards(&history_dir, &mut index, shards, Some(seq))?;
+    trim_journal(&history_dir, seq)
+}
+
+/// A month whose serialized shard differs from the one recorded in the index.
+struct ShardWrite<'a> {
+    month: &'a str,
+    days: Vec<&'a DailyUsage>,
+    content: String,
+    digest: String,
+}
+
+fn changed_shards<'a>(
+    history_dir: &Path,
+    index: &HistoryIndex,
+    history: &'a [DailyUsage],
+) -> Result<Vec<ShardWrite<'a>>> {
+    let mut by_month: BTreeMap<&str, Vec<&DailyUsage>> = BTreeMap::new();
+    for day in history {
+        by_month.entry(month_of(&day.date)).or_default().push(day);
+    }
+
+    let mut shards = Vec::new();
+    for (month, mut days) in by_month {
+        days.sort_by(|a, b| a.date.cmp(&b.date));
+        let content = serde_json::to_string_pretty(&days)?;
+        let digest = shard_digest(&content);
+        if index.months.get(month) == Some(&digest)
+            && history_dir.join(format!("{month}.json")).exists()
+        {
+            continue;
+        }
+        shards.push(ShardWrite {
+            month,
+            days,
+            content,
+            digest,
+        });
+    }
+    Ok(shards)
+}
+
+/// Writes `shards` and the index, recording `journal_seq` as compacted.
+fn commit_shards(
+    history_dir: &Path,
+    index: &mut HistoryIndex,
+    shards: Vec<ShardWrite>,
+    journal_seq: Option<u64>,
+) -> Result<()> {
+    fs::create_dir_all(history_dir)?;
+    let mut index_changed = false;
+    for shard in shards {
+        write_atomic(
+            &history_dir.join(format!("{}.json", shard.month)),
+            &shard.content,
+        )?;
+        index.months.insert(shard.month.to_string(), shard.digest);
+        index_changed = true;
+    }
+    if let Some(seq) = journal_seq.filter(|&seq| seq != index.journal_seq) {
+        index.journal_seq = seq;
+        index_changed = true;
     }
 
-    let history_path = config_dir.join("history.json");
-    let tmp_path = config_dir.join("history.json.tmp");
+    let index_path = history_dir.join(HISTORY_INDEX_FILE);
+    if index_changed || !index_path.exists() {
+        write_atomic(&index_path, &serde_json::to_string_pretty(index)?)?;
+    }
+    Ok(())
+}
 
-    let content = serde_json::to_string_pretty(history)?;
+fn read_shard(path: &Path) -> Result<Vec<DailyUsage>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Reads `history/journal.jsonl`, ignoring a torn last line from an interrupted append.
+fn read_journal(history_dir: &Path) -> Result<Vec<JournalRecord>> {
+    let path = history_dir.join(HISTORY_JOURNAL_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Durably appends a journal record for `days` and returns its sequence number.
+fn append_journal(history_dir: &Path, index: &HistoryIndex, days: Vec<&DailyUsage>) -> Result<u64> {
+    let last_seq = read_journal(history_dir)?
+        .last()
+        .map_or(0, |record| record.seq)
+        .max(index.journal_seq);
+    let record = JournalRecord {
+        seq: last_seq + 1,
+        recorded_at: chrono::Local::now().to_rfc3339(),
+        days: days.into_iter().cloned().collect(),
+    };
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_dir.join(HISTORY_JOURNAL_FILE))?;
+    writeln!(file, "{}", serde_json::to_string(&record)?)?;
+    file.sync_all()?;
+    Ok(record.seq)
+}
+
+/// Drops compacted records recorded before today.
+fn trim_journal(history_dir: &Path, compacted_seq: u64) -> Result<()> {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let records = read_journal(history_dir)?;
+    let total = records.len();
+    let kept: Vec<JournalRecord> = records
+        .into_iter()
+        .filter(|record| record.seq > compacted_seq || record.recorded_at.starts_with(&today))
+        .collect();
+    if kept.len() == total {
+        return Ok(());
+    }
+
+    let mut content = String::new();
+    for record in &kept {
+        content.push_str(&serde_json::to_string(record)?);
+        content.push('\n');
+    }
+    write_atomic(&history_dir.join(HISTORY_JOURNAL_FILE), &content)
+}
+
+/// Every journaled state of `date` still in the journal, oldest first.
+///
+/// # Errors
+/// Returns an error if the journal exists but cannot be read.
+pub fn load_day_journal(config_dir: &Path, date: &str) -> Result<Vec<JournalPoint>> {
+    Ok(read_journal(&config_dir.join(HISTORY_DIR))?
+        .into_iter()
+        .flat_map(|record| {
+            let recorded_at = record.recorded_at;
+            record
+                .days
+                .into_iter()
+                .filter(|day| day.date == date)
+                .map(move |usage| JournalPoint {
+                    recorded_at: recorded_at.clone(),
+                    usage,
+                })
+        })
+        .collect())
+}
+
+/// Moves a legacy `history.json` into monthly shards, then renames it to
+/// `history.json.migrated`. Days already in the shards are overwritten by the
+/// legacy file's.
+///
+/// A legacy file that cannot be parsed is renamed to `history.json.corrupt`
+/// with a warning, so it does not block loading the shards.
+fn migrate_legacy_history(config_dir: &Path) -> Result<()> {
+    let legacy_path = config_dir.join(LEGACY_HISTORY_FILE);
+    if !legacy_path.exists() {
+        return Ok(());
+    }
+
+    let legacy: Vec<DailyUsage> = match serde_json::from_str(&fs::read_to_string(&legacy_path)?) {
+        Ok(legacy) => legacy,
+        Err(e) => {
+            let corrupt_path = legacy_path.with_extension("json.corrupt");
+            fs::rename(&legacy_path, &corrupt_path)?;
+            tracing::warn!(
+                "Moved unreadable legacy history to {}: {e}",
+                corrupt_path.display()
+            );
+            return Ok(());
+        }
+    };
+    let history = merge_history(&load_history_months(config_dir, |_| true)?, &legacy);
+    let history_dir = config_dir.join(HISTORY_DIR);
+    let mut index = load_history_index(&history_dir)?;
+    let shards = changed_shards(&history_dir, &index, &history)?;
+    commit_shards(&history_dir, &mut index, shards, None)?;
+    fs::rename(&legacy_path, legacy_path.with_extension("json.migrated"))?;
+    Ok(())
+}
+
+/// Writes `content` to a temp file next to `path`, then renames it into place.
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let tmp_path = path.with_extension("json.tmp");
 
     // Write to temp file first
     fs::write(&tmp_path, content)?;
 
     // Atomically rename. On Windows, rename fails if target exists, so remove first.
     #[cfg(windows)]
-    if history_path.exists() {
-        fs::remove_file(&history_path)?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    fs::rename(&tmp_path, path)?;
+
+    Ok(())
+}
+
+/// Loads scheduler bookkeeping from scheduler.json, defaulting when missing or invalid.
+#[must_use]
+pub fn load_scheduler_state(config_dir: &Path) -> SchedulerState {
+    fs::read_to_string(config_dir.join("scheduler.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Saves scheduler bookkeeping to scheduler.json atomically.
+///
+/// # Errors
+/// Returns an error if the state cannot be serialized or written.
+pub fn save_scheduler_state(config_dir: &Path, state: &SchedulerState) -> Result<()> {
+    let content = serde_json::to_string_pretty(state)?;
+    write_atomic(&config_dir.join("scheduler.json"), &content)
+}
+
+/// Loads the cached Claude Code summary from `summary_cache.json`, if valid.
+#[must_use]
+pub fn load_summary_cache(config_dir: &Path) -> Option<SummaryCache> {
+    fs::read_to_string(config_dir.join("summary_cache.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// Saves the Claude Code summary cache to `summary_cache.json` atomically.
+///
+/// # Errors
+/// Returns an error if the cache cannot be serialized or written.
+pub fn save_summary_cache(config_dir: &Path, cache: &SummaryCache) -> Result<()> {
+    let content = serde_json::to_string(cache)?;
+    write_atomic(&config_dir.join("summary_cache.json"), &content)
+}
+
+/// Deletes the Claude Code summary cache so the next read parses every transcript.
+///
+/// # Errors
+/// Returns an error if the cache exists but cannot be removed.
+pub fn clear_summary_cache(config_dir: &Path) -> Result<()> {
+    match fs::remove_file(config_dir.join("summary_cache.json")) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
     }
+}
+
+/// Loads cached provider results from `provider_cache.json`, empty when missing
+/// or invalid.
+#[must_use]
+pub fn load_provider_cache(config_dir: &Path) -> ProviderResultCache {
+    fs::read_to_string(config_dir.join("provider_cache.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
 
-    fs::rename(&tmp_path, &history_path)?;
+/// Saves cached provider results to `provider_cache.json` atomically.
+///
+/// # Errors
+/// Returns an error if the cache cannot be serialized or written.
+pub fn save_provider_cache(config_dir: &Path, cache: &ProviderResultCache) -> Result<()> {
+    let content = serde_json::to_string(cache)?;
+    write_atomic(&config_dir.join("provider_cache.json"), &content)
+}
+
+fn read_provider_history(path: &Path) -> Result<Vec<ProviderHistoryPoint>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    // A torn last line from an interrupted append is skipped, not fatal
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Appends `point` to provider `id`'s history, dropping points older than a
+/// year.
+///
+/// # Errors
+/// Returns an error if the history file cannot be read or written.
+pub fn append_provider_history(
+    config_dir: &Path,
+    id: &str,
+    point: &ProviderHistoryPoint,
+) -> Result<()> {
+    let dir = config_dir.join(PROVIDER_HISTORY_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{id}.jsonl"));
+
+    let cutoff = point.fetched_at - PROVIDER_HISTORY_RETENTION;
+    let points = read_provider_history(&path)?;
+    if points
+        .first()
+        .is_some_and(|first| first.fetched_at < cutoff)
+    {
+        let mut content = String::new();
+        for kept in points
+            .iter()
+            .filter(|p| p.fetched_at >= cutoff)
+            .chain([point])
+        {
+            content.push_str(&serde_json::to_string(kept)?);
+            content.push('\n');
+        }
+        return write_atomic(&path, &content);
+    }
 
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(point)?)?;
     Ok(())
 }
 
+/// Provider `id`'s results fetched at or after `since`, oldest first.
+///
+/// # Errors
+/// Returns an error if the history file exists but cannot be read.
+pub fn load_provider_history(
+    config_dir: &Path,
+    id: &str,
+    since: DateTime<Utc>,
+) -> Result<Vec<ProviderHistoryPoint>> {
+    let path = config_dir
+        .join(PROVIDER_HISTORY_DIR)
+        .join(format!("{id}.jsonl"));
+    let mut points = read_provider_history(&path)?;
+    points.retain(|p| p.fetched_at >= since);
+    points.sort_by_key(|p| p.fetched_at);
+    Ok(points)
+}
+
+fn provider_daily_path(config_dir: &Path, id: &str) -> std::path::PathBuf {
+    config_dir
+        .join(PROVIDER_HISTORY_DIR)
+        .join(format!("{id}.daily.json"))
+}
+
+fn read_provider_daily(path: &Path) -> Result<BTreeMap<NaiveDate, ProviderDailyUsage>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let days: Vec<ProviderDailyUsage> = serde_json::from_str(&content)?;
+    Ok(days.into_iter().map(|day| (day.date, day)).collect())
+}
+
+/// Merges the per-day usage provider `id` reported into its daily history,
+/// replacing days it reported before and dropping days older than a year
+/// before `today`.
+///
+/// # Errors
+/// Returns an error if the daily history cannot be read or written.
+pub fn merge_provider_daily(
+    config_dir: &Path,
+    id: &str,
+    daily: &[ProviderDailyUsage],
+    today: NaiveDate,
+) -> Result<()> {
+    fs::create_dir_all(config_dir.join(PROVIDER_HISTORY_DIR))?;
+    let path = provider_daily_path(config_dir, id);
+    let mut days = read_provider_daily(&path)?;
+    days.extend(daily.iter().map(|day| (day.date, day.clone())));
+    let cutoff = today - PROVIDER_HISTORY_RETENTION;
+    days.retain(|date, _| *date >= cutoff);
+    let days: Vec<&ProviderDailyUsage> = days.values().collect();
+    write_atomic(&path, &serde_json::to_string(&days)?)
+}
+
+/// Provider `id`'s reported per-day usage on or after `since`, oldest first.
+///
+/// # Errors
+/// Returns an error if the daily history exists but cannot be read.
+pub fn load_provider_daily(
+    config_dir: &Path,
+    id: &str,
+    since: NaiveDate,
+) -> Result<Vec<ProviderDailyUsage>> {
+    let days = read_provider_daily(&provider_daily_path(config_dir, id))?;
+    Ok(days.into_values().filter(|day| day.date >= since).collect())
+}
+
+/// Loads every imported usage dump, keyed by source tag. Unreadable files are
+/// skipped.
+#[must_use]
+pub fn load_imports(config_dir: &Path) -> BTreeMap<String, Vec<DailyUsage>> {
+    let Ok(entries) = fs::read_dir(config_dir.join(IMPORTS_DIR)) else {
+        return BTreeMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                return None;
+            }
+            let source = path.file_stem()?.to_str()?.to_string();
+            match read_shard(&path) {
+                Ok(days) => Some((source, days)),
+                Err(e) => {
+                    tracing::warn!("Failed to read import {}: {e}", path.display());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Merges `days` into the stored import for `source`, so re-importing a newer
+/// dump from the same machine updates days instead of double counting them.
+///
+/// # Errors
+/// Returns an error if the import cannot be serialized or written.
+pub fn save_import(config_dir: &Path, source: &str, days: &[DailyUsage]) -> Result<()> {
+    let imports_dir = config_dir.join(IMPORTS_DIR);
+    fs::create_dir_all(&imports_dir)?;
+    let path = imports_dir.join(format!("{source}.json"));
+    let current = read_shard(&path).unwrap_or_default();
+    let merged = merge_history(&current, days);
+    write_atomic(&path, &serde_json::to_string_pretty(&merged)?)
+}
+
 /// Merges current history with new data.
 /// - Updates existing entries with fresher data.
 /// - Adds new entries.
 /// - Sorts by date.
+#[must_use]
 pub fn merge_history(current: &[DailyUsage], new_data: &[DailyUsage]) -> Vec<DailyUsage> {
     let mut map: HashMap<String, DailyUsage> = HashMap::new();
 
@@ -65,10 +643,156 @@ pub fn merge_history(current: &[DailyUsage], new_data: &[DailyUsage]) -> Vec<Dai
     merged
 }
 
+/// Replaces every history day from `window_start` on with `new_data`, so days
+/// whose source data was deleted disappear instead of being kept. Older days
+/// are left alone.
+#[must_use]
+pub fn replace_history_window(
+    current: &[DailyUsage],
+    new_data: &[DailyUsage],
+    window_start: &str,
+) -> Vec<DailyUsage> {
+    let kept: Vec<DailyUsage> = current
+        .iter()
+        .filter(|day| day.date.as_str() < window_start)
+        .cloned()
+        .collect();
+    merge_history(&kept, new_data)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn day(date: &str, cost: f64) -> DailyUsage {
+        DailyUsage {
+            date: date.to_string(),
+            cost,
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            models: vec![],
+        }
+    }
+
+    fn dates(history: &[DailyUsage]) -> Vec<&str> {
+        history.iter().map(|d| d.date.as_str()).collect()
+    }
+
+    #[test]
+    fn test_history_shards_by_month() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        let history = vec![
+            day("2025-01-30", 1.0),
+            day("2025-02-01", 2.0),
+            day("2025-03-15", 3.0),
+        ];
+        save_history(dir, &history).expect("history should save");
+
+        assert!(dir.join("history/2025-02.json").exists());
+        assert!(dir.join("history/index.json").exists());
+        let loaded = load_history(dir).expect("history should load");
+        assert_eq!(dates(&loaded), ["2025-01-30", "2025-02-01", "2025-03-15"]);
+
+        let range = load_history_range(dir, "2025-01-31", "2025-02-28").expect("range");
+        assert_eq!(dates(&range), ["2025-02-01"]);
+
+        // Unchanged months are not rewritten
+        fs::write(dir.join("history/2025-01.json"), "[]").expect("write");
+        save_history(dir, &[day("2025-01-30", 1.0), day("2025-03-15", 4.0)])
+            .expect("history should save");
+        let loaded = load_history(dir).expect("history should load");
+        assert_eq!(dates(&loaded), ["2025-02-01", "2025-03-15"]);
+        assert!((loaded[1].cost - 4.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_corrupt_shard_is_skipped() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        save_history(dir, &[day("2025-01-01", 1.0), day("2025-02-01", 2.0)])
+            .expect("history should save");
+        fs::write(dir.join("history/2025-01.json"), "{not json").expect("write");
+        fs::remove_file(dir.join("history/index.json")).expect("remove");
+        fs::remove_file(dir.join("history/journal.jsonl")).expect("remove");
+
+        let loaded = load_history(dir).expect("history should load");
+        assert_eq!(dates(&loaded), ["2025-02-01"]);
+    }
+
+    #[test]
+    fn test_legacy_history_is_migrated() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        save_history(dir, &[day("2024-12-31", 9.0)]).expect("history should save");
+        let legacy = vec![day("2024-12-31", 5.0), day("2025-01-02", 1.0)];
+        fs::write(
+            dir.join("history.json"),
+            serde_json::to_string(&legacy).expect("serialize"),
+        )
+        .expect("write");
+
+        let loaded = load_history(dir).expect("history should load");
+        assert_eq!(dates(&loaded), ["2024-12-31", "2025-01-02"]);
+        assert!((loaded[0].cost - 5.0).abs() < f64::EPSILON);
+        assert!(!dir.join("history.json").exists());
+        assert!(dir.join("history.json.migrated").exists());
+        assert!(dir.join("history/2025-01.json").exists());
+    }
+
+    #[test]
+    fn test_corrupt_legacy_history_is_set_aside() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        save_history(dir, &[day("2025-01-01", 1.0)]).expect("history should save");
+        fs::write(dir.join("history.json"), "[{not json").expect("write");
+
+        let loaded = load_history(dir).expect("history should load");
+        assert_eq!(dates(&loaded), ["2025-01-01"]);
+        assert!(!dir.join("history.json").exists());
+        assert!(dir.join("history.json.corrupt").exists());
+        save_history(dir, &[day("2025-01-02", 2.0)]).expect("history should save");
+    }
+
+    #[test]
+    fn test_save_journals_changed_days() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        save_history(dir, &[day("2025-01-01", 1.0), day(&today, 1.0)]).expect("save");
+        save_history(dir, &[day("2025-01-01", 1.0), day(&today, 2.5)]).expect("save");
+        save_history(dir, &[day("2025-01-01", 1.0), day(&today, 2.5)]).expect("save");
+
+        let records = read_journal(&dir.join(HISTORY_DIR)).expect("journal");
+        assert_eq!(records.len(), 2);
+        assert_eq!(dates(&records[1].days), [today.as_str()]);
+
+        let trail = load_day_journal(dir, &today).expect("journal");
+        let costs: Vec<f64> = trail.iter().map(|point| point.usage.cost).collect();
+        assert_eq!(costs, [1.0, 2.5]);
+    }
+
+    #[test]
+    fn test_uncompacted_journal_is_replayed() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        save_history(dir, &[day("2025-01-01", 1.0)]).expect("save");
+
+        // Simulate a crash after the journal append but before compaction
+        let history_dir = dir.join(HISTORY_DIR);
+        let index = load_history_index(&history_dir).expect("index");
+        append_journal(&history_dir, &index, vec![&day("2025-01-02", 3.0)]).expect("append");
+
+        let loaded = load_history(dir).expect("history should load");
+        assert_eq!(dates(&loaded), ["2025-01-01", "2025-01-02"]);
+        assert_eq!(
+            load_history_index(&history_dir).expect("index").journal_seq,
+            2
+        );
+    }
+
     #[test]
     fn test_merge_history() {
         let history = vec![DailyUsage {
@@ -109,4 +833,142 @@ mod tests {
         assert!((merged[0].cost - 2.0).abs() < f64::EPSILON); // Updated
         assert_eq!(merged[1].date, "2024-01-02");
     }
+
+    #[test]
+    fn test_replace_history_window() {
+        let history = vec![
+            day("2024-01-01", 1.0),
+            day("2024-01-10", 2.0),
+            day("2024-01-11", 3.0),
+        ];
+        let new_data = vec![day("2024-01-11", 4.0), day("2024-01-12", 5.0)];
+
+        let replaced = replace_history_window(&history, &new_data, "2024-01-10");
+        let dates: Vec<&str> = replaced.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2024-01-01", "2024-01-11", "2024-01-12"]);
+        assert!((replaced[1].cost - 4.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_save_import_merges_by_source() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        save_import(
+            dir,
+            "laptop",
+            &[day("2024-01-01", 1.0), day("2024-01-02", 2.0)],
+        )
+        .unwrap();
+        save_import(dir, "laptop", &[day("2024-01-02", 5.0)]).unwrap();
+        save_import(dir, "desktop", &[day("2024-01-01", 3.0)]).unwrap();
+
+        let imports = load_imports(dir);
+        assert_eq!(imports.keys().collect::<Vec<_>>(), ["desktop", "laptop"]);
+        assert_eq!(dates(&imports["laptop"]), ["2024-01-01", "2024-01-02"]);
+        assert!((imports["laptop"][1].cost - 5.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_clear_summary_cache() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        assert!(clear_summary_cache(dir).is_ok());
+        save_summary_cache(
+            dir,
+            &SummaryCache {
+                fingerprint: "fp".to_string(),
+                summary: crate::types::UsageSummary::default(),
+            },
+        )
+        .unwrap();
+        assert!(load_summary_cache(dir).is_some());
+        clear_summary_cache(dir).unwrap();
+        assert!(load_summary_cache(dir).is_none());
+    }
+
+    #[test]
+    fn test_provider_history_appends_and_prunes() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        let now = Utc::now();
+        let point = |days_ago: i64, cost: f64| ProviderHistoryPoint {
+            fetched_at: now - chrono::TimeDelta::days(days_ago),
+            result: crate::types::ProviderUsageResult {
+                cost: Some(cost),
+                tokens: None,
+                used: None,
+                total: None,
+                daily: Vec::new(),
+            },
+        };
+        let costs = |points: Vec<ProviderHistoryPoint>| -> Vec<Option<f64>> {
+            points.into_iter().map(|p| p.result.cost).collect()
+        };
+
+        let epoch = DateTime::<Utc>::UNIX_EPOCH;
+        assert!(load_provider_history(dir, "api", epoch).unwrap().is_empty());
+
+        append_provider_history(dir, "api", &point(400, 1.0)).unwrap();
+        append_provider_history(dir, "api", &point(20, 2.0)).unwrap();
+        append_provider_history(dir, "api", &point(2, 3.0)).unwrap();
+        assert_eq!(
+            costs(load_provider_history(dir, "api", epoch).unwrap()),
+            [Some(2.0), Some(3.0)]
+        );
+        let since = now - chrono::TimeDelta::days(7);
+        assert_eq!(
+            costs(load_provider_history(dir, "api", since).unwrap()),
+            [Some(3.0)]
+        );
+        assert!(load_provider_history(dir, "other", epoch)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_provider_daily_merges_by_date() {
+        let temp = tempfile::tempdir().expect("temp dir should be creatable");
+        let dir = temp.path();
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let day = |s: &str, cost: f64| ProviderDailyUsage {
+            date: date(s),
+            cost: Some(cost),
+            tokens: None,
+        };
+        let today = date("2025-06-10");
+
+        assert!(load_provider_daily(dir, "api", today).unwrap().is_empty());
+        merge_provider_daily(
+            dir,
+            "api",
+            &[
+                day("2024-01-01", 9.0),
+                day("2025-06-08", 1.0),
+                day("2025-06-09", 2.0),
+            ],
+            today,
+        )
+        .unwrap();
+        // The latest report wins for a day reported twice
+        let update = [day("2025-06-09", 2.5), day("2025-06-10", 0.5)];
+        merge_provider_daily(dir, "api", &update, today).unwrap();
+
+        let since = date("2024-06-01");
+        assert_eq!(
+            load_provider_daily(dir, "api", since).unwrap(),
+            [
+                day("2025-06-08", 1.0),
+                day("2025-06-09", 2.5),
+                day("2025-06-10", 0.5)
+            ]
+        );
+        let recent = load_provider_daily(dir, "api", date("2025-06-09")).unwrap();
+        assert_eq!(recent.len(), 2);
+        // Appended fetch history is kept apart
+        assert!(
+            load_provider_history(dir, "api", DateTime::<Utc>::UNIX_EPOCH)
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
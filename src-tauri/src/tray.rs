@@ -1,15 +1,27 @@
-use crate::config::AppConfig;
-use crate::types::{format_number, ProviderTrayStats, UsageSummary};
+use crate::app_lock;
+use crate::commands::diagnostics::create_support_bundle;
+use crate::commands::profiles::switch_profile;
+use crate::commands::usage::force_full_refresh;
+use crate::config::{AppConfig, ColorScheme, LimitsConfig, PrivacyStyle};
+use crate::privacy;
+use crate::profiles::ProfileList;
+use crate::services::{ccusage, sources, support_bundle};
+use crate::state::AppState;
+use crate::types::{format_number, LimitUsage, ProviderTrayStats, UsageSummary};
+use std::path::PathBuf;
 #[cfg(not(target_os = "macos"))]
 use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{
     image::Image,
-    menu::{MenuBuilder, MenuItemBuilder},
+    menu::{Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter, Manager,
 };
 #[cfg(target_os = "macos")]
 use tauri_plugin_nspopover::AppExt;
+use tauri_plugin_opener::OpenerExt;
 
 /// Window label for the main dashboard window.
 pub const MAIN_WINDOW_LABEL: &str = "main";
@@ -20,8 +32,101 @@ pub const TRAY_WINDOW_LABEL: &str = "tray";
 /// Tray icon identifier.
 pub const TRAY_ID: &str = "main";
 
-/// Show the dashboard window and bring it to focus.
+/// Number of days listed in the tray menu's "Recent Days" submenu.
+const RECENT_DAYS_COUNT: i64 = 7;
+
+/// Model lines beyond this count are collapsed into a "More Models" submenu.
+const MODEL_SUBMENU_THRESHOLD: usize = 5;
+
+/// Number of top models kept inline when the breakdown is collapsed.
+const MODEL_INLINE_COUNT: usize = 3;
+
+/// Menu item IDs of the "Profile" submenu are this prefix plus the profile name.
+const PROFILE_ITEM_PREFIX: &str = "profile:";
+
+/// Menu item ID of the privacy mode toggle.
+const PRIVACY_ITEM_ID: &str = "privacy_mode";
+
+/// Tray updates requested within this window are merged into one rebuild.
+const TRAY_COALESCE_WINDOW: Duration = Duration::from_millis(250);
+
+/// Fixed labels of the tray context menu in one language.
+#[derive(Debug)]
+struct MenuText {
+    dashboard: &'static str,
+    settings: &'static str,
+    more_models: &'static str,
+    recent_days: &'static str,
+    /// Followed by the active profile.
+    profile: &'static str,
+    privacy_mode: &'static str,
+    force_refresh: &'static str,
+    report_problem: &'static str,
+    quit: &'static str,
+    /// Followed by the member and their cost, then [`Self::today`].
+    top_spender: &'static str,
+    today: &'static str,
+}
+
+static MENU_TEXT_EN: MenuText = MenuText {
+    dashboard: "Dashboard",
+    settings: "Settings",
+    more_models: "More Models",
+    recent_days: "Recent Days",
+    profile: "Profile: ",
+    privacy_mode: "Privacy Mode",
+    force_refresh: "Force Full Refresh",
+    report_problem: "Report a Problem…",
+    quit: "Quit",
+    top_spender: "Top Spender: ",
+    today: " today",
+};
+
+static MENU_TEXT_ZH: MenuText = MenuText {
+    dashboard: "仪表板",
+    settings: "设置",
+    more_models: "更多模型",
+    recent_days: "最近几天",
+    profile: "配置方案：",
+    privacy_mode: "隐私模式",
+    force_refresh: "强制完全刷新",
+    report_problem: "报告问题…",
+    quit: "退出",
+    top_spender: "最高花费：",
+    today: "（今日）",
+};
+
+impl MenuText {
+    /// Labels for the configured `language` (`"en"` or `"zh"`). Unset, it
+    /// follows the system only in the web views, so the menu stays English.
+    fn for_language(language: Option<&str>) -> &'static Self {
+        match language {
+            Some(language) if language.starts_with("zh") => &MENU_TEXT_ZH,
+            _ => &MENU_TEXT_EN,
+        }
+    }
+}
+
+/// Show the dashboard window and bring it to focus, once the user has passed
+/// system authentication if the app lock requires it.
 pub fn show_window_with_dock(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let lock = app.state::<AppState>().config.lock().await.app_lock;
+        match app_lock::unlock(lock, "open the TokenMeter dashboard").await {
+            Ok(prompted) => {
+                if prompted {
+                    // Lets the dashboard reload what it was refused while locked
+                    let _ = app.emit("app-unlocked", ());
+                }
+                reveal_main_window(&app);
+            }
+            Err(e) => tracing::error!("Dashboard stays locked: {e}"),
+        }
+    });
+}
+
+fn reveal_main_window(app: &AppHandle) {
     #[cfg(target_os = "macos")]
     {
         use tauri::ActivationPolicy;
@@ -86,13 +191,18 @@ pub fn blur_hide_delay_ms() -> Option<u64> {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum UsageLevel {
+/// Budget tier reached, in increasing order of severity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UsageLevel {
     NearBudget,
+    /// Past the soft budget: warn and change color.
     OverBudget,
+    /// Past the hard budget: red tray, persistent alert, optional guard deny.
+    OverHardBudget,
 }
 
-fn usage_level_from_cost(
+#[must_use]
+pub fn usage_level_from_cost(
     cost: f64,
     budget: f64,
     near_threshold_percent: f64,
@@ -115,17 +225,324 @@ fn usage_level_from_cost(
     None
 }
 
-/// Formats tray title (supports $cost, $tokens, $input, $output variables)
-fn format_tray_title(format: &str, usage: &UsageSummary) -> String {
+/// Level of `cost` against a soft `budget` and an optional `hard_budget`. The
+/// hard tier wins once reached; below it the soft thresholds apply.
+#[must_use]
+pub fn budget_level(
+    cost: f64,
+    budget: f64,
+    near_threshold_percent: f64,
+    hard_budget: Option<f64>,
+) -> Option<UsageLevel> {
+    if hard_budget.is_some_and(|hard| hard > 0.0 && cost >= hard) {
+        return Some(UsageLevel::OverHardBudget);
+    }
+    usage_level_from_cost(cost, budget, near_threshold_percent)
+}
+
+/// Level of today's cost against the day's soft and hard budgets.
+#[must_use]
+pub fn daily_budget_level(usage: &UsageSummary, config: &AppConfig) -> Option<UsageLevel> {
+    let date = usage_date(usage);
+    budget_level(
+        usage.today.cost,
+        config.menu_bar.budget_for(date),
+        config.menu_bar.near_budget_threshold_percent,
+        config.menu_bar.hard_budget_for(date),
+    )
+}
+
+/// Level of the period's cost against the soft and hard monthly budgets.
+#[must_use]
+pub fn monthly_budget_level(usage: &UsageSummary, config: &AppConfig) -> Option<UsageLevel> {
+    budget_level(
+        usage.this_month.cost,
+        config.menu_bar.monthly_budget.unwrap_or_default(),
+        config.menu_bar.near_budget_threshold_percent,
+        config.menu_bar.hard_monthly_budget(),
+    )
+}
+
+/// Color applied to the tray title for a usage level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TitleColor {
+    SystemOrange,
+    SystemRed,
+    /// sRGB components in `0.0..=1.0`.
+    Srgb(f64, f64, f64),
+}
+
+/// Title color for `level` under `scheme`. Monochrome never colors the title.
+const fn title_color(scheme: ColorScheme, level: UsageLevel) -> Option<TitleColor> {
+    match (scheme, level) {
+        (ColorScheme::Standard, UsageLevel::NearBudget) => Some(TitleColor::SystemOrange),
+        (ColorScheme::Standard, UsageLevel::OverBudget | UsageLevel::OverHardBudget) => {
+            Some(TitleColor::SystemRed)
+        }
+        (ColorScheme::HighContrast, UsageLevel::NearBudget) => {
+            Some(TitleColor::Srgb(1.0, 0.6, 0.0))
+        }
+        (ColorScheme::HighContrast, UsageLevel::OverBudget | UsageLevel::OverHardBudget) => {
+            Some(TitleColor::Srgb(1.0, 0.0, 0.0))
+        }
+        // Okabe-Ito orange, blue and vermillion
+        (ColorScheme::ColorblindSafe, UsageLevel::NearBudget) => {
+            Some(TitleColor::Srgb(0.902, 0.624, 0.0))
+        }
+        (ColorScheme::ColorblindSafe, UsageLevel::OverBudget) => {
+            Some(TitleColor::Srgb(0.0, 0.447, 0.698))
+        }
+        (ColorScheme::ColorblindSafe, UsageLevel::OverHardBudget) => {
+            Some(TitleColor::Srgb(0.835, 0.369, 0.0))
+        }
+        (ColorScheme::Monochrome, _) => None,
+    }
+}
+
+/// Marker appended to the title in monochrome mode: ● within budget, ▲ near,
+/// ■ over, ✖ over the hard budget.
+const fn level_marker(level: Option<UsageLevel>) -> &'static str {
+    match level {
+        None => "●",
+        Some(UsageLevel::NearBudget) => "▲",
+        Some(UsageLevel::OverBudget) => "■",
+        Some(UsageLevel::OverHardBudget) => "✖",
+    }
+}
+
+/// Limit usage to show instead of cost, when limit mode is on and at least
+/// one limit is set.
+fn active_limits<'a>(usage: &'a UsageSummary, config: &AppConfig) -> Option<&'a LimitUsage> {
+    usage.limits.as_ref().filter(|limits| {
+        config.limits.enabled
+            && (limits.block_percent().is_some() || limits.weekly_percent().is_some())
+    })
+}
+
+/// Level of the fuller limit against the configured thresholds. The hard tier
+/// starts at `hard_budget_percent` of the limit, or once it is used up when no
+/// hard budget is set.
+fn limit_level(
+    limits: &LimitUsage,
+    config: &LimitsConfig,
+    hard_budget_percent: Option<f64>,
+) -> Option<UsageLevel> {
+    let percent = limits
+        .block_percent()
+        .into_iter()
+        .chain(limits.weekly_percent())
+        .fold(0.0, f64::max);
+    if percent >= hard_budget_percent.unwrap_or(100.0) {
+        Some(UsageLevel::OverHardBudget)
+    } else if percent >= config.over_percent {
+        Some(UsageLevel::OverBudget)
+    } else if percent >= config.near_percent {
+        Some(UsageLevel::NearBudget)
+    } else {
+        None
+    }
+}
+
+/// Limit mode title, e.g. "5h 42% · 7d 18%". Untracked limits are left out.
+fn format_limit_title(limits: &LimitUsage) -> String {
+    [
+        ("5h", limits.block_percent()),
+        ("7d", limits.weekly_percent()),
+    ]
+    .into_iter()
+    .filter_map(|(label, percent)| percent.map(|p| format!("{label} {p:.0}%")))
+    .collect::<Vec<_>>()
+    .join(" · ")
+}
+
+/// Local date the summary's "today" figures refer to.
+#[must_use]
+pub fn usage_date(usage: &UsageSummary) -> chrono::NaiveDate {
+    chrono::NaiveDate::parse_from_str(&usage.today.date, "%Y-%m-%d")
+        .unwrap_or_else(|_| chrono::Local::now().date_naive())
+}
+
+/// Formats the trend as a signed percentage, e.g. "+12%".
+fn format_trend(usage: &UsageSummary) -> String {
+    usage
+        .trend
+        .as_ref()
+        .map(|t| format!("{:+.0}%", t.change_percent))
+        .unwrap_or_default()
+}
+
+/// Variable names [`format_tray_title`] substitutes, written as `${name}`.
+pub const TRAY_FORMAT_VARIABLES: &[&str] =
+    &["cost", "trend", "tokens", "input", "output", "block_reset"];
+
+/// Placeholder for the time left in the active 5-hour block.
+pub const BLOCK_RESET_VARIABLE: &str = "${block_reset}";
+
+/// `${...}` placeholders in `format` that [`format_tray_title`] would leave as
+/// literal text, in order of appearance. An unterminated `${` is reported as-is.
+#[must_use]
+pub fn unknown_tray_variables(format: &str) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let mut rest = format;
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            unknown.push(rest[start..].to_string());
+            break;
+        };
+        let name = &after[..end];
+        if !TRAY_FORMAT_VARIABLES.contains(&name) {
+            unknown.push(format!("${{{name}}}"));
+        }
+        rest = &after[end + 1..];
+    }
+    unknown
+}
+
+/// Time left in the active block, or an empty string when none is active.
+fn format_block_reset(usage: &UsageSummary, now: chrono::DateTime<chrono::Utc>) -> String {
+    usage
+        .active_block_end
+        .as_deref()
+        .and_then(|end| ccusage::format_block_remaining(end, now))
+        .unwrap_or_default()
+}
+
+/// Formats tray title (supports `$cost`, `$tokens`, `$input`, `$output`,
+/// `$trend` and `$block_reset` variables)
+#[must_use]
+pub fn format_tray_title(format: &str, usage: &UsageSummary) -> String {
+    format_tray_title_at(format, usage, chrono::Utc::now())
+}
+
+fn format_tray_title_at(
+    format: &str,
+    usage: &UsageSummary,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
     format
+        .replace(BLOCK_RESET_VARIABLE, &format_block_reset(usage, now))
         .replace("${cost}", &format!("${:.2}", usage.today.cost))
+        .replace("${trend}", &format_trend(usage))
         .replace("${tokens}", &format_number(usage.today.total_tokens))
         .replace("${input}", &format_number(usage.today.input_tokens))
         .replace("${output}", &format_number(usage.today.output_tokens))
 }
 
+/// Spells out a dollar amount for screen readers, e.g. "34 dollars 2 cents".
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn spell_cost(cost: f64) -> String {
+    let plural = |n: u64, unit: &str| {
+        if n == 1 {
+            format!("{n} {unit}")
+        } else {
+            format!("{n} {unit}s")
+        }
+    };
+    let cents = (cost.max(0.0) * 100.0).round() as u64;
+    let (dollars, cents) = (cents / 100, cents % 100);
+    match (dollars, cents) {
+        (0, 0) => plural(0, "dollar"),
+        (0, c) => plural(c, "cent"),
+        (d, 0) => plural(d, "dollar"),
+        (d, c) => format!("{} {}", plural(d, "dollar"), plural(c, "cent")),
+    }
+}
+
+/// Spells out a token count for screen readers, e.g. "1.2 million tokens".
+#[allow(clippy::cast_precision_loss)]
+fn spell_tokens(num: u64) -> String {
+    if num >= 1_000_000_000 {
+        format!("{:.1} billion tokens", num as f64 / 1_000_000_000.0)
+    } else if num >= 1_000_000 {
+        format!("{:.1} million tokens", num as f64 / 1_000_000.0)
+    } else if num >= 1_000 {
+        format!("{:.1} thousand tokens", num as f64 / 1_000.0)
+    } else if num == 1 {
+        "1 token".to_string()
+    } else {
+        format!("{num} tokens")
+    }
+}
+
+/// Verbose, screen-reader friendly description of today's usage, e.g.
+/// "Today: 34 dollars 2 cents, 62 percent of budget, 1.2 million tokens".
+fn format_accessible_description(usage: &UsageSummary, config: &AppConfig) -> String {
+    if let Some(limits) = active_limits(usage, config) {
+        return [
+            ("5-hour limit", limits.block_percent()),
+            ("weekly limit", limits.weekly_percent()),
+        ]
+        .into_iter()
+        .filter_map(|(name, percent)| percent.map(|p| format!("{p:.0} percent of {name} used")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    }
+
+    let mut parts = vec![format!("Today: {}", spell_cost(usage.today.cost))];
+
+    let budget = config.menu_bar.budget_for(usage_date(usage));
+    if budget > 0.0 {
+        parts.push(format!(
+            "{:.0} percent of budget",
+            usage.today.cost / budget * 100.0
+        ));
+    }
+    parts.push(spell_tokens(usage.today.total_tokens));
+
+    if let Some(trend) = &usage.trend {
+        let direction = if trend.change_percent >= 0.0 {
+            "up"
+        } else {
+            "down"
+        };
+        parts.push(format!(
+            "{direction} {:.0} percent from usual",
+            trend.change_percent.abs()
+        ));
+    }
+
+    parts.join(", ")
+}
+
+/// Sets the tray's tooltip and, on macOS, the status item's accessibility label.
+fn set_tray_accessibility_label(app: &AppHandle, label: &str) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        if let Err(e) = tray.set_tooltip(Some(label)) {
+            tracing::error!("Failed to set tray tooltip: {e}");
+        }
+    }
+    set_macos_tray_accessibility_label(app, label.to_string());
+}
+
 #[cfg(target_os = "macos")]
-fn set_macos_tray_attributed_title(app: &AppHandle, title: String, level: Option<UsageLevel>) {
+fn set_macos_tray_accessibility_label(app: &AppHandle, label: String) {
+    use objc2_app_kit::NSAccessibility;
+    use objc2_foundation::{MainThreadMarker, NSString};
+
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    let _ = tray.with_inner_tray_icon(move |inner| {
+        let Some(ns_status_item) = inner.ns_status_item() else {
+            return;
+        };
+        let Some(mtm) = MainThreadMarker::new() else {
+            return;
+        };
+        let Some(button) = ns_status_item.button(mtm) else {
+            return;
+        };
+        button.setAccessibilityLabel(Some(&NSString::from_str(&label)));
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_macos_tray_accessibility_label(_app: &AppHandle, _label: String) {}
+
+#[cfg(target_os = "macos")]
+fn set_macos_tray_attributed_title(app: &AppHandle, title: String, color: Option<TitleColor>) {
     use objc2::runtime::{AnyObject, ProtocolObject};
     use objc2::ClassType;
     use objc2_app_kit::{NSColor, NSForegroundColorAttributeName};
@@ -153,7 +570,7 @@ fn set_macos_tray_attributed_title(app: &AppHandle, title: String, level: Option
 
         let ns_title = NSString::from_str(&title);
 
-        let Some(level) = level else {
+        let Some(color) = color else {
             // Clear any previous color attributes by setting a plain attributed title.
             let attributed = NSAttributedString::from_nsstring(&ns_title);
             button.setAttributedTitle(&attributed);
@@ -161,9 +578,12 @@ fn set_macos_tray_attributed_title(app: &AppHandle, title: String, level: Option
         };
 
         // UX: color coding only indicates close-to / over-budget states.
-        let color = match level {
-            UsageLevel::NearBudget => NSColor::systemOrangeColor(),
-            UsageLevel::OverBudget => NSColor::systemRedColor(),
+        let color = match color {
+            TitleColor::SystemOrange => NSColor::systemOrangeColor(),
+            TitleColor::SystemRed => NSColor::systemRedColor(),
+            TitleColor::Srgb(red, green, blue) => {
+                NSColor::colorWithSRGBRed_green_blue_alpha(red, green, blue, 1.0)
+            }
         };
 
         // Attributes dictionary: { NSForegroundColorAttributeName: NSColor }
@@ -182,7 +602,7 @@ fn set_macos_tray_attributed_title(app: &AppHandle, title: String, level: Option
 }
 
 #[cfg(not(target_os = "macos"))]
-fn set_macos_tray_attributed_title(_app: &AppHandle, _title: String, _level: Option<UsageLevel>) {}
+fn set_macos_tray_attributed_title(_app: &AppHandle, _title: String, _color: Option<TitleColor>) {}
 
 // NOTE: macOS menubar/tray icon needs to be a monochrome template image.
 // Using a relative path like "icons/tray.png" is fragile because the working
@@ -190,26 +610,290 @@ fn set_macos_tray_attributed_title(_app: &AppHandle, _title: String, _level: Opt
 // Embed the tray icon at compile time to ensure it is always available.
 const TRAY_ICON_PNG: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/icons/tray.png"));
 
+/// Labels for the last [`RECENT_DAYS_COUNT`] days ending on the usage date, newest first.
+/// Days without recorded usage are listed at zero cost.
+fn recent_day_labels(usage: &UsageSummary) -> Vec<String> {
+    let today = usage_date(usage);
+    (0..RECENT_DAYS_COUNT)
+        .map(|offset| {
+            let date = (today - chrono::Duration::days(offset))
+                .format("%Y-%m-%d")
+                .to_string();
+            let cost = if offset == 0 {
+                usage.today.cost
+            } else {
+                usage
+                    .daily_usage
+                    .iter()
+                    .find(|d| d.date == date)
+                    .map_or(0.0, |d| d.cost)
+            };
+            format!("{date}  ${cost:.2}")
+        })
+        .collect()
+}
+
+/// "Top Spender" line naming the key or member with the highest cost today.
+fn top_spender_label(usage: &UsageSummary, text: &MenuText) -> Option<String> {
+    sources::member_leaderboard(&usage.sources)
+        .into_iter()
+        .filter(|m| m.today.cost > 0.0)
+        .max_by(|a, b| a.today.cost.total_cmp(&b.today.cost))
+        .map(|m| {
+            format!(
+                "{}{}  ${:.2}{}",
+                text.top_spender, m.member, m.today.cost, text.today
+            )
+        })
+}
+
+/// Splits model lines (in the configured sort order) into inline and nested groups.
+/// Everything stays inline unless there are more than [`MODEL_SUBMENU_THRESHOLD`] models.
+fn model_menu_labels(usage: &UsageSummary, config: &AppConfig) -> (Vec<String>, Vec<String>) {
+    let mut models = usage.model_breakdown.clone();
+    sources::sort_models(&mut models, config.model_sort);
+    let mut labels: Vec<String> = models
+        .into_iter()
+        .map(|m| format!("{}  ${:.2}", config.model_display_name(&m.model), m.cost))
+        .collect();
+
+    if labels.len() > MODEL_SUBMENU_THRESHOLD {
+        let nested = labels.split_off(MODEL_INLINE_COUNT);
+        (labels, nested)
+    } else {
+        (labels, Vec::new())
+    }
+}
+
+/// One line of the tray context menu, kept independent of the Tauri menu types
+/// so the layout can be checked without an app handle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MenuEntry {
+    Item {
+        id: String,
+        label: String,
+        enabled: bool,
+    },
+    /// Submenu of `(id, label)` lines, clickable only when `selectable`.
+    Submenu {
+        id: String,
+        label: String,
+        items: Vec<(String, String)>,
+        selectable: bool,
+    },
+    Separator,
+}
+
+impl MenuEntry {
+    fn action(id: &str, label: &str) -> Self {
+        Self::Item {
+            id: id.to_string(),
+            label: label.to_string(),
+            enabled: true,
+        }
+    }
+
+    const fn info(id: String, label: String) -> Self {
+        Self::Item {
+            id,
+            label,
+            enabled: false,
+        }
+    }
+
+    /// Masks the dollar amounts in this entry's labels for privacy mode.
+    fn mask_amounts(&mut self) {
+        match self {
+            Self::Item { label, .. } => *label = privacy::mask_amounts(label),
+            Self::Submenu { label, items, .. } => {
+                *label = privacy::mask_amounts(label);
+                for (_, item) in items {
+                    *item = privacy::mask_amounts(item);
+                }
+            }
+            Self::Separator => {}
+        }
+    }
+}
+
+/// Tray context menu layout, including the optional "Recent Days" and
+/// "Profile" submenus, labelled with `text`.
+///
+/// Usage-dependent sections are omitted until the first usage data is available.
+#[allow(clippy::too_many_lines)]
+fn tray_menu_entries(
+    usage: Option<(&UsageSummary, &AppConfig)>,
+    text: &MenuText,
+    profiles: &ProfileList,
+    providers: &[ProviderTrayStats],
+) -> Vec<MenuEntry> {
+    let mut entries = vec![
+        MenuEntry::action("dashboard", text.dashboard),
+        MenuEntry::action("settings", text.settings),
+    ];
+
+    if let Some((usage, config)) = usage.filter(|(u, _)| !u.model_breakdown.is_empty()) {
+        let (inline, nested) = model_menu_labels(usage, config);
+        entries.push(MenuEntry::Separator);
+        entries.extend(
+            inline
+                .into_iter()
+                .enumerate()
+                .map(|(i, label)| MenuEntry::info(format!("model_{i}"), label)),
+        );
+        if !nested.is_empty() {
+            entries.push(MenuEntry::Submenu {
+                id: "models".to_string(),
+                label: text.more_models.to_string(),
+                items: nested
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, label)| (format!("model_more_{i}"), label))
+                    .collect(),
+                selectable: false,
+            });
+        }
+    }
+
+    if let Some((usage, _)) = usage {
+        for source in &usage.sources {
+            if let Some(credits) = &source.credits {
+                entries.push(MenuEntry::Separator);
+                entries.push(MenuEntry::info(
+                    format!("credits_{}", source.id),
+                    credits.format_display(&source.label),
+                ));
+            }
+        }
+    }
+
+    let mut group = None;
+    for (i, stats) in providers.iter().enumerate() {
+        if i == 0 || stats.group != group {
+            entries.push(MenuEntry::Separator);
+            if let Some(name) = &stats.group {
+                entries.push(MenuEntry::info(
+                    format!("provider_group_{i}"),
+                    format!("{name}:"),
+                ));
+            }
+            group.clone_from(&stats.group);
+        }
+        entries.push(MenuEntry::info(
+            format!("provider_{i}"),
+            stats.display_text.clone(),
+        ));
+    }
+
+    if let Some(label) = usage
+        .filter(|(_, c)| c.menu_bar.show_top_spender)
+        .and_then(|(u, _)| top_spender_label(u, text))
+    {
+        entries.push(MenuEntry::Separator);
+        entries.push(MenuEntry::info("top_spender".to_string(), label));
+    }
+
+    if let Some((usage, _)) = usage.filter(|(_, c)| c.menu_bar.show_recent_days) {
+        entries.push(MenuEntry::Separator);
+        entries.push(MenuEntry::Submenu {
+            id: "recent_days".to_string(),
+            label: text.recent_days.to_string(),
+            items: recent_day_labels(usage)
+                .into_iter()
+                .enumerate()
+                .map(|(i, label)| (format!("recent_day_{i}"), label))
+                .collect(),
+            selectable: false,
+        });
+    }
+
+    if usage.is_some_and(|(_, c)| c.menu_bar.show_profiles) && profiles.names.len() > 1 {
+        entries.push(MenuEntry::Separator);
+        entries.push(MenuEntry::Submenu {
+            id: "profiles".to_string(),
+            label: format!("{}{}", text.profile, profiles.active),
+            items: profiles
+                .names
+                .iter()
+                .map(|name| {
+                    let mark = if *name == profiles.active { "✓ " } else { "" };
+                    (
+                        format!("{PROFILE_ITEM_PREFIX}{name}"),
+                        format!("{mark}{name}"),
+                    )
+                })
+                .collect(),
+            selectable: true,
+        });
+    }
+
+    entries.push(MenuEntry::Separator);
+    let privacy_mode = usage.is_some_and(|(_, c)| c.menu_bar.privacy_mode);
+    let mark = if privacy_mode { "✓ " } else { "" };
+    entries.push(MenuEntry::action(
+        PRIVACY_ITEM_ID,
+        &format!("{mark}{}", text.privacy_mode),
+    ));
+    entries.push(MenuEntry::action("force_refresh", text.force_refresh));
+    entries.push(MenuEntry::action("report_problem", text.report_problem));
+    entries.push(MenuEntry::action("quit", text.quit));
+    entries
+}
+
+/// Builds the tray context menu from entries laid out by [`tray_menu_entries`].
+fn build_tray_menu(app: &AppHandle, entries: Vec<MenuEntry>) -> tauri::Result<Menu<tauri::Wry>> {
+    let mut builder = MenuBuilder::new(app);
+    for entry in entries {
+        builder = match entry {
+            MenuEntry::Item { id, label, enabled } => builder.item(
+                &MenuItemBuilder::with_id(id, label)
+                    .enabled(enabled)
+                    .build(app)?,
+            ),
+            MenuEntry::Submenu {
+                id,
+                label,
+                items,
+                selectable,
+            } => {
+                let mut submenu = SubmenuBuilder::with_id(app, id, label);
+                for (id, label) in items {
+                    let item = MenuItemBuilder::with_id(id, label)
+                        .enabled(selectable)
+                        .build(app)?;
+                    submenu = submenu.item(&item);
+                }
+                builder.item(&submenu.build()?)
+            }
+            MenuEntry::Separator => builder.separator(),
+        };
+    }
+    builder.build()
+}
+
+/// Menu labels for the language in the config loaded at startup.
+fn startup_menu_text(app: &AppHandle) -> &'static MenuText {
+    let language = app
+        .try_state::<AppState>()
+        .and_then(|state| state.config.try_lock().ok()?.language.clone());
+    MenuText::for_language(language.as_deref())
+}
+
+/// Builds the tray icon and menu and wires up their event handlers.
+///
+/// # Errors
+/// Returns an error if the tray menu or icon cannot be created.
 pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
-    // Create menu items
-    let dashboard_item = MenuItemBuilder::with_id("dashboard", "Dashboard").build(app)?;
-    let settings_item = MenuItemBuilder::with_id("settings", "Settings").build(app)?;
-    let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
-
-    let menu = MenuBuilder::new(app)
-        .item(&dashboard_item)
-        .item(&settings_item)
-        .separator()
-        .item(&quit_item)
-        .build()?;
+    let entries = tray_menu_entries(None, startup_menu_text(app), &ProfileList::default(), &[]);
+    let menu = build_tray_menu(app, entries)?;
 
     let icon = Image::from_bytes(TRAY_ICON_PNG)
         .or_else(|e| {
-            eprintln!("[Tray] Failed to load embedded tray icon: {e}");
+            tracing::error!("[Tray] Failed to load embedded tray icon: {e}");
             Image::from_path("icons/tray.png")
         })
         .or_else(|e| {
-            eprintln!("[Tray] Failed to load tray icon from path: {e}");
+            tracing::error!("[Tray] Failed to load tray icon from path: {e}");
             app.default_window_icon()
                 .cloned()
                 .ok_or_else(|| tauri::Error::AssetNotFound("default icon".into()))
@@ -229,10 +913,38 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
                 show_window_with_dock(app);
                 navigate_to(app, "settings");
             }
+            "force_refresh" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    let state = app.state::<AppState>();
+                    if let Err(e) = force_full_refresh(app.clone(), state).await {
+                        tracing::error!("Full refresh failed: {e}");
+                    }
+                });
+            }
+            PRIVACY_ITEM_ID => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { toggle_privacy_mode(&app).await });
+            }
+            "report_problem" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { report_problem(&app).await });
+            }
             "quit" => {
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(name) = id.strip_prefix(PROFILE_ITEM_PREFIX) {
+                    let app = app.clone();
+                    let name = name.to_string();
+                    tauri::async_runtime::spawn(async move {
+                        let state = app.state::<AppState>();
+                        if let Err(e) = switch_profile(app.clone(), state, name).await {
+                            tracing::error!("Profile switch failed: {e}");
+                        }
+                    });
+                }
+            }
         })
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
@@ -273,10 +985,35 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
     Ok(())
 }
 
+/// Writes a support bundle, reveals it and opens a new GitHub issue that
+/// refers to it.
+async fn report_problem(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let path = match create_support_bundle(state).await {
+        Ok(path) => PathBuf::from(path),
+        Err(e) => {
+            tracing::error!("Failed to create support bundle: {e}");
+            return;
+        }
+    };
+    let opener = app.opener();
+    if let Err(e) = opener.reveal_item_in_dir(&path) {
+        tracing::error!("Failed to reveal support bundle: {e}");
+    }
+    match support_bundle::issue_url(&path, env!("CARGO_PKG_VERSION")) {
+        Ok(url) => {
+            if let Err(e) = opener.open_url(url, None::<&str>) {
+                tracing::error!("Failed to open issue page: {e}");
+            }
+        }
+        Err(e) => tracing::error!("Failed to build issue URL: {e}"),
+    }
+}
+
 fn set_tray_title(app: &AppHandle, title: &str) {
     if let Some(tray) = app.tray_by_id(TRAY_ID) {
         if let Err(e) = tray.set_title(Some(title)) {
-            eprintln!("Failed to set tray title: {e}");
+            tracing::error!("Failed to set tray title: {e}");
         }
     }
 
@@ -285,49 +1022,282 @@ fn set_tray_title(app: &AppHandle, title: &str) {
     set_macos_tray_attributed_title(app, title.to_string(), None);
 }
 
-/// Sets tray title with optional color coding based on usage level.
-fn set_tray_title_with_level(
-    app: &AppHandle,
+/// Applies the configured color scheme to `title`, returning the text to show and
+/// its color. Monochrome appends a level marker instead of coloring. In limit
+/// mode the level comes from the limit thresholds rather than the budget.
+fn style_tray_title(
     title: &str,
     usage: &UsageSummary,
     config: &AppConfig,
-) {
-    if let Some(tray) = app.tray_by_id(TRAY_ID) {
-        if let Err(e) = tray.set_title(Some(title)) {
-            eprintln!("Failed to set tray title: {e}");
+) -> (String, Option<TitleColor>) {
+    if !config.menu_bar.show_color_coding {
+        return (title.to_string(), None);
+    }
+
+    let level = if let Some(limits) = active_limits(usage, config) {
+        limit_level(limits, &config.limits, config.menu_bar.hard_budget_percent)
+    } else {
+        if config.menu_bar.budget_for(usage_date(usage)) <= 0.0
+            && config.menu_bar.monthly_budget.unwrap_or_default() <= 0.0
+        {
+            return (title.to_string(), None);
         }
+        // The stricter of the daily and monthly budgets colors the title
+        daily_budget_level(usage, config).max(monthly_budget_level(usage, config))
+    };
+    match config.menu_bar.color_scheme {
+        ColorScheme::Monochrome => (format!("{title} {}", level_marker(level)), None),
+        scheme => (
+            title.to_string(),
+            level.and_then(|level| title_color(scheme, level)),
+        ),
     }
+}
+
+/// Everything the tray shows for one summary. Updates that render the same
+/// are dropped instead of rebuilding the menu.
+#[derive(Debug, Clone, PartialEq)]
+struct TrayRender {
+    title: String,
+    color: Option<TitleColor>,
+    accessibility_label: String,
+    entries: Vec<MenuEntry>,
+}
 
-    let level = if config.menu_bar.show_color_coding {
-        usage_level_from_cost(
-            usage.today.cost,
-            config.menu_bar.fixed_budget,
-            config.menu_bar.near_budget_threshold_percent,
-        )
+/// Renders `usage` for the tray. In privacy mode amounts are scaled or masked
+/// as configured; provider lines, which are formatted elsewhere, are always
+/// masked. Color coding still follows the real figures.
+fn tray_render(
+    usage: &UsageSummary,
+    config: &AppConfig,
+    profiles: &ProfileList,
+    providers: &[ProviderTrayStats],
+) -> TrayRender {
+    let menu_bar = &config.menu_bar;
+    let scaled = (menu_bar.privacy_mode && menu_bar.privacy_style == PrivacyStyle::Scale)
+        .then(|| privacy::scale_summary(usage, menu_bar.privacy_scale));
+    let shown = scaled.as_ref().unwrap_or(usage);
+
+    let title = active_limits(usage, config).map_or_else(
+        || format_tray_title(&menu_bar.format, shown),
+        format_limit_title,
+    );
+    let (styled, color) = style_tray_title(&title, usage, config);
+    let accessibility_label = if menu_bar.verbose_accessibility && !menu_bar.privacy_mode {
+        format_accessible_description(usage, config)
     } else {
-        None
+        title
     };
-    set_macos_tray_attributed_title(app, title.to_string(), level);
+    let mut render = TrayRender {
+        title: styled,
+        color,
+        accessibility_label,
+        entries: Vec::new(),
+    };
+    let text = MenuText::for_language(config.language.as_deref());
+    if !menu_bar.privacy_mode {
+        render.entries = tray_menu_entries(Some((usage, config)), text, profiles, providers);
+        return render;
+    }
+
+    let providers: Vec<ProviderTrayStats> = providers
+        .iter()
+        .map(|stats| ProviderTrayStats {
+            display_text: privacy::mask_amounts(&stats.display_text),
+            ..stats.clone()
+        })
+        .collect();
+    render.entries = tray_menu_entries(Some((shown, config)), text, profiles, &providers);
+    if menu_bar.privacy_style == PrivacyStyle::Mask {
+        render.title = privacy::mask_amounts(&render.title);
+        render.accessibility_label = privacy::mask_amounts(&render.accessibility_label);
+        for entry in &mut render.entries {
+            entry.mask_amounts();
+        }
+    }
+    render
+}
+
+/// Flips privacy mode from the tray menu, saving the config and re-rendering.
+async fn toggle_privacy_mode(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let config = {
+        let mut config = state.config.lock().await;
+        config.menu_bar.privacy_mode = !config.menu_bar.privacy_mode;
+        config.clone()
+    };
+    if let Err(e) = state.save_config(&config) {
+        tracing::error!("Failed to save privacy mode: {e}");
+    }
+    if let Some(usage) = state.usage_snapshot().await {
+        update_tray_menu(app, usage, config.clone(), &[]);
+    }
+    let _ = app.emit("config-updated", &config);
 }
 
-/// Updates tray menu content
+fn apply_tray_render(app: &AppHandle, render: TrayRender) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        if let Err(e) = tray.set_title(Some(&render.title)) {
+            tracing::error!("Failed to set tray title: {e}");
+        }
+    }
+    set_macos_tray_attributed_title(app, render.title, render.color);
+    set_tray_accessibility_label(app, &render.accessibility_label);
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        match build_tray_menu(app, render.entries) {
+            Ok(menu) => {
+                if let Err(e) = tray.set_menu(Some(menu)) {
+                    tracing::error!("Failed to set tray menu: {e}");
+                }
+            }
+            Err(e) => tracing::error!("Failed to build tray menu: {e}"),
+        }
+    }
+}
+
+/// Coalesces tray updates from every refresh path (preload, manual refresh,
+/// config changes). Only the latest request in a window is rendered.
+struct TrayUpdates {
+    pending: Option<(Arc<UsageSummary>, AppConfig)>,
+    flush_scheduled: bool,
+    /// What the tray currently shows, if it was set by an update.
+    shown: Option<TrayRender>,
+}
+
+impl TrayUpdates {
+    const fn new() -> Self {
+        Self {
+            pending: None,
+            flush_scheduled: false,
+            shown: None,
+        }
+    }
+
+    /// Replaces any pending update. Returns `true` when no flush is scheduled
+    /// yet, i.e. the caller must schedule one.
+    fn push(&mut self, usage: Arc<UsageSummary>, config: AppConfig) -> bool {
+        self.pending = Some((usage, config));
+        !std::mem::replace(&mut self.flush_scheduled, true)
+    }
+
+    const fn take(&mut self) -> Option<(Arc<UsageSummary>, AppConfig)> {
+        self.flush_scheduled = false;
+        self.pending.take()
+    }
+
+    /// Records `render` as shown. Returns `false` if the tray already shows it.
+    fn mark_shown(&mut self, render: &TrayRender) -> bool {
+        if self.shown.as_ref() == Some(render) {
+            return false;
+        }
+        self.shown = Some(render.clone());
+        true
+    }
+}
+
+static TRAY_UPDATES: Mutex<TrayUpdates> = Mutex::new(TrayUpdates::new());
+
+/// Profiles offered by the tray's "Profile" submenu, set by [`set_profiles`].
+static PROFILES: Mutex<ProfileList> = Mutex::new(ProfileList {
+    names: Vec::new(),
+    active: String::new(),
+});
+
+/// Records the profiles the tray menu offers; shown from the next tray update.
+pub fn set_profiles(profiles: ProfileList) {
+    if let Ok(mut current) = PROFILES.lock() {
+        *current = profiles;
+    }
+}
+
+/// Provider lines listed in the tray menu, set by [`set_providers`].
+static PROVIDERS: Mutex<Vec<ProviderTrayStats>> = Mutex::new(Vec::new());
+
+/// Records the provider lines the tray menu lists; shown from the next tray update.
+pub fn set_providers(providers: Vec<ProviderTrayStats>) {
+    if let Ok(mut current) = PROVIDERS.lock() {
+        *current = providers;
+    }
+}
+
+/// Provider lines last recorded by [`set_providers`].
+pub fn providers() -> Vec<ProviderTrayStats> {
+    PROVIDERS.lock().map(|p| p.clone()).unwrap_or_default()
+}
+
+fn flush_tray_update(app: &AppHandle) {
+    let Some((usage, config)) = TRAY_UPDATES.lock().ok().and_then(|mut u| u.take()) else {
+        return;
+    };
+    let profiles = PROFILES.lock().map(|p| p.clone()).unwrap_or_default();
+    let providers = providers();
+    let render = tray_render(&usage, &config, &profiles, &providers);
+    let changed = TRAY_UPDATES
+        .lock()
+        .map_or(true, |mut updates| updates.mark_shown(&render));
+    if changed {
+        apply_tray_render(app, render);
+    }
+
+    // Emit event so the tray window updates immediately without waiting for poll.
+    let _ = app.emit("usage-updated", &*usage);
+}
+
+/// Updates tray title and menu content.
+///
+/// Updates are applied after a short window; requests arriving meanwhile
+/// replace the pending one, and a result identical to what the tray already
+/// shows is not rebuilt.
 pub fn update_tray_menu(
     app: &AppHandle,
-    usage: &UsageSummary,
-    config: &AppConfig,
+    usage: Arc<UsageSummary>,
+    config: AppConfig,
     _providers: &[ProviderTrayStats],
 ) {
-    let title = format_tray_title(&config.menu_bar.format, usage);
-    set_tray_title_with_level(app, &title, usage, config);
+    let schedule = TRAY_UPDATES
+        .lock()
+        .is_ok_and(|mut updates| updates.push(usage, config));
+    if schedule {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(TRAY_COALESCE_WINDOW).await;
+            flush_tray_update(&app);
+        });
+    }
+}
 
-    // Emit event so the tray window updates immediately without waiting for poll.
-    let _ = app.emit("usage-updated", usage);
+/// How often the `${block_reset}` countdown is re-rendered.
+const BLOCK_RESET_TICK: Duration = Duration::from_mins(1);
+
+/// Re-renders the tray every minute while its title shows `${block_reset}`, so
+/// the countdown moves between data refreshes. Unchanged renders are skipped.
+pub fn spawn_block_reset_timer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(BLOCK_RESET_TICK);
+        loop {
+            interval.tick().await;
+            let state = app.state::<AppState>();
+            let config = state.config.lock().await.clone();
+            if !config.menu_bar.format.contains(BLOCK_RESET_VARIABLE) {
+                continue;
+            }
+            if let Some(usage) = state.usage_snapshot().await {
+                update_tray_menu(&app, usage, config, &[]);
+            }
+        }
+    });
 }
 
 /// Updates tray title to show error state.
 pub fn update_tray_error(app: &AppHandle) {
     // User preference: error title should not be colored.
     set_tray_title(app, "$--");
+    set_tray_accessibility_label(app, "Usage unavailable");
+    // The next successful update must be applied even if it matches the last one.
+    if let Ok(mut updates) = TRAY_UPDATES.lock() {
+        updates.shown = None;
+    }
 }
 
 #[cfg(test)]
@@ -362,12 +1332,27 @@ mod usage_level_tests {
         assert_eq!(usage_level_from_cost(9.49, 10.0, 5.0), None);
         assert_eq!(usage_level_from_cost(9.99, 10.0, 0.0), None);
     }
+
+    #[test]
+    fn test_budget_level_hard_tier() {
+        assert_eq!(
+            budget_level(12.0, 10.0, 10.0, Some(15.0)),
+            Some(UsageLevel::OverBudget)
+        );
+        assert_eq!(
+            budget_level(15.0, 10.0, 10.0, Some(15.0)),
+            Some(UsageLevel::OverHardBudget)
+        );
+        assert_eq!(budget_level(5.0, 10.0, 10.0, Some(15.0)), None);
+        assert!(UsageLevel::OverHardBudget > UsageLevel::OverBudget);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ModelUsage, UsageData};
+    use crate::types::{ModelUsage, RefreshMeta, UsageData};
+    use std::fmt::Write as _;
 
     fn make_usage(today_cost: f64, today_tokens: u64, daily_costs: &[f64]) -> UsageSummary {
         UsageSummary {
@@ -408,6 +1393,11 @@ mod tests {
                     output_tokens: today_tokens / 3,
                 },
             ],
+            sources: vec![],
+            trend: None,
+            limits: None,
+            active_block_end: None,
+            meta: RefreshMeta::default(),
         }
     }
 
@@ -419,6 +1409,241 @@ mod tests {
         assert_eq!(format_number(1_500_000_000), "1.5B");
     }
 
+    #[test]
+    fn test_usage_date() {
+        let mut usage = make_usage(1.0, 100, &[]);
+        assert_eq!(
+            usage_date(&usage),
+            chrono::NaiveDate::from_ymd_opt(2024, 1, 15).expect("valid date")
+        );
+        usage.today.date = String::new();
+        assert_eq!(usage_date(&usage), chrono::Local::now().date_naive());
+    }
+
+    #[test]
+    fn test_model_menu_labels() {
+        let mut usage = make_usage(10.0, 600, &[]);
+        let mut config = AppConfig::default();
+        let (inline, nested) = model_menu_labels(&usage, &config);
+        assert_eq!(
+            inline,
+            vec!["claude-opus-4-5  $6.00", "claude-haiku-4-5  $4.00"]
+        );
+        assert!(nested.is_empty());
+
+        config
+            .model_aliases
+            .insert("claude-opus-4-5".to_string(), "Opus".to_string());
+        let (inline, _) = model_menu_labels(&usage, &config);
+        assert_eq!(inline[0], "Opus  $6.00");
+
+        usage.model_breakdown = (1..=6)
+            .map(|i| ModelUsage {
+                model: format!("model-{i}"),
+                cost: f64::from(i),
+                input_tokens: 0,
+                output_tokens: 0,
+            })
+            .collect();
+        let (inline, nested) = model_menu_labels(&usage, &config);
+        assert_eq!(
+            inline,
+            vec!["model-6  $6.00", "model-5  $5.00", "model-4  $4.00"]
+        );
+        assert_eq!(nested.len(), 3);
+        assert_eq!(nested[2], "model-1  $1.00");
+    }
+
+    #[test]
+    fn test_style_tray_title() {
+        let mut config = AppConfig::default();
+        let over = make_usage(20.0, 100, &[]);
+        let under = make_usage(1.0, 100, &[]);
+
+        assert_eq!(
+            style_tray_title("$20.00", &over, &config),
+            ("$20.00".to_string(), Some(TitleColor::SystemRed))
+        );
+        assert_eq!(
+            style_tray_title("$1.00", &under, &config),
+            ("$1.00".to_string(), None)
+        );
+
+        config.menu_bar.color_scheme = ColorScheme::ColorblindSafe;
+        assert_eq!(
+            style_tray_title("$20.00", &over, &config).1,
+            Some(TitleColor::Srgb(0.0, 0.447, 0.698))
+        );
+
+        config.menu_bar.color_scheme = ColorScheme::Monochrome;
+        assert_eq!(
+            style_tray_title("$20.00", &over, &config),
+            ("$20.00 ■".to_string(), None)
+        );
+        assert_eq!(style_tray_title("$1.00", &under, &config).0, "$1.00 ●");
+
+        config.menu_bar.show_color_coding = false;
+        assert_eq!(style_tray_title("$20.00", &over, &config).0, "$20.00");
+    }
+
+    #[test]
+    fn test_limit_mode_title() {
+        let mut config = AppConfig::default();
+        let mut usage = make_usage(1.0, 100, &[]);
+        usage.limits = Some(LimitUsage {
+            unit: crate::config::LimitUnit::Messages,
+            block_used: 80,
+            block_limit: 100,
+            block_resets_at: None,
+            weekly_used: 180,
+            weekly_limit: 1_000,
+        });
+        assert!(active_limits(&usage, &config).is_none());
+
+        config.limits.enabled = true;
+        let render = tray_render(&usage, &config, &ProfileList::default(), &[]);
+        assert_eq!(render.title, "5h 80% · 7d 18%");
+        assert_eq!(render.color, Some(TitleColor::SystemOrange));
+
+        let limits = usage.limits.as_mut().unwrap();
+        limits.block_limit = 0;
+        assert_eq!(format_limit_title(limits), "7d 18%");
+        assert_eq!(limit_level(limits, &config.limits, None), None);
+        limits.weekly_limit = 0;
+        assert!(active_limits(&usage, &config).is_none());
+    }
+
+    #[test]
+    fn test_limit_level_hard_tier() {
+        let config = AppConfig::default();
+        let limits = |block_used| LimitUsage {
+            unit: crate::config::LimitUnit::Messages,
+            block_used,
+            block_limit: 100,
+            block_resets_at: None,
+            weekly_used: 0,
+            weekly_limit: 0,
+        };
+        assert_eq!(
+            limit_level(&limits(100), &config.limits, None),
+            Some(UsageLevel::OverHardBudget)
+        );
+        assert_eq!(
+            limit_level(&limits(120), &config.limits, Some(150.0)),
+            Some(UsageLevel::OverBudget)
+        );
+        assert_eq!(
+            limit_level(&limits(150), &config.limits, Some(150.0)),
+            Some(UsageLevel::OverHardBudget)
+        );
+    }
+
+    #[test]
+    fn test_monthly_budget_level() {
+        let mut config = AppConfig::default();
+        config.menu_bar.fixed_budget = 0.0;
+        let mut usage = make_usage(1.0, 100, &[]);
+        usage.this_month.cost = 250.0;
+        assert_eq!(monthly_budget_level(&usage, &config), None);
+        assert_eq!(style_tray_title("$1.00", &usage, &config).1, None);
+
+        config.menu_bar.monthly_budget = Some(200.0);
+        assert_eq!(
+            monthly_budget_level(&usage, &config),
+            Some(UsageLevel::OverBudget)
+        );
+        config.menu_bar.hard_budget_percent = Some(125.0);
+        assert_eq!(
+            monthly_budget_level(&usage, &config),
+            Some(UsageLevel::OverHardBudget)
+        );
+        // The month colors the title even when today is within budget
+        config.menu_bar.fixed_budget = 15.0;
+        assert_eq!(
+            style_tray_title("$1.00", &usage, &config).1,
+            Some(TitleColor::SystemRed)
+        );
+    }
+
+    #[test]
+    fn test_spell_cost_and_tokens() {
+        assert_eq!(spell_cost(34.02), "34 dollars 2 cents");
+        assert_eq!(spell_cost(1.0), "1 dollar");
+        assert_eq!(spell_cost(0.01), "1 cent");
+        assert_eq!(spell_cost(0.0), "0 dollars");
+        assert_eq!(spell_tokens(1_234_567), "1.2 million tokens");
+        assert_eq!(spell_tokens(1), "1 token");
+        assert_eq!(spell_tokens(999), "999 tokens");
+    }
+
+    #[test]
+    fn test_format_accessible_description() {
+        let mut usage = make_usage(9.3, 1_200_000, &[]);
+        let mut config = AppConfig::default();
+        assert_eq!(
+            format_accessible_description(&usage, &config),
+            "Today: 9 dollars 30 cents, 62 percent of budget, 1.2 million tokens"
+        );
+
+        config.menu_bar.fixed_budget = 0.0;
+        usage.trend = Some(crate::types::TrendInfo {
+            baseline: crate::config::TrendBaseline::Mean30,
+            baseline_cost: 10.0,
+            change_percent: -7.0,
+        });
+        assert_eq!(
+            format_accessible_description(&usage, &config),
+            "Today: 9 dollars 30 cents, 1.2 million tokens, down 7 percent from usual"
+        );
+    }
+
+    #[test]
+    fn test_recent_day_labels() {
+        // 2024-01-01 .. 2024-01-12; days 13 and 14 have no usage
+        let usage = make_usage(2.5, 100, &[1.0; 12]);
+        let labels = recent_day_labels(&usage);
+        assert_eq!(labels.len(), 7);
+        assert_eq!(labels[0], "2024-01-15  $2.50");
+        assert_eq!(labels[1], "2024-01-14  $0.00");
+        assert_eq!(labels[3], "2024-01-12  $1.00");
+        assert_eq!(labels[6], "2024-01-09  $1.00");
+    }
+
+    #[test]
+    fn test_top_spender_label() {
+        let mut usage = make_usage(5.0, 100, &[]);
+        assert_eq!(top_spender_label(&usage, &MENU_TEXT_EN), None);
+
+        let key = |key: &str, cost: f64| crate::types::KeyUsage {
+            key: key.to_string(),
+            today: crate::types::UsageData {
+                cost,
+                ..crate::types::UsageData::default()
+            },
+            this_month: crate::types::UsageData {
+                cost: cost * 10.0,
+                ..crate::types::UsageData::default()
+            },
+        };
+        usage.sources = vec![crate::types::SourceUsage {
+            id: "proxy:…ab12".to_string(),
+            label: "API Proxy (…ab12)".to_string(),
+            today: crate::types::UsageData::default(),
+            this_month: crate::types::UsageData::default(),
+            models: vec![],
+            keys: vec![key("…ab12", 1.5), key("…cd34", 3.25), key("…ef56", 0.0)],
+            credits: None,
+        }];
+        assert_eq!(
+            top_spender_label(&usage, &MENU_TEXT_EN).as_deref(),
+            Some("Top Spender: …cd34  $3.25 today")
+        );
+        assert_eq!(
+            top_spender_label(&usage, &MENU_TEXT_ZH).as_deref(),
+            Some("最高花费：…cd34  $3.25（今日）")
+        );
+    }
+
     #[test]
     fn test_format_tray_title() {
         let usage = make_usage(34.02, 39_300_000, &[]);
@@ -428,4 +1653,382 @@ mod tests {
         );
         assert_eq!(format_tray_title("${cost}", &usage), "$34.02");
     }
+
+    #[test]
+    fn test_unknown_tray_variables() {
+        assert!(unknown_tray_variables("${cost} ${trend} · ${tokens}").is_empty());
+        assert!(unknown_tray_variables("$ {cost} plain $").is_empty());
+        assert_eq!(
+            unknown_tray_variables("${cost} ${typo} ${Tokens}"),
+            vec!["${typo}", "${Tokens}"]
+        );
+        assert_eq!(unknown_tray_variables("${}${input}"), vec!["${}"]);
+        assert_eq!(unknown_tray_variables("${cost} ${tok"), vec!["${tok"]);
+    }
+
+    #[test]
+    fn test_format_tray_title_trend() {
+        let mut usage = make_usage(12.0, 1_000, &[]);
+        assert_eq!(
+            format_tray_title("${cost} ${trend}", &usage).trim(),
+            "$12.00"
+        );
+
+        usage.trend = Some(crate::types::TrendInfo {
+            baseline: crate::config::TrendBaseline::Median7,
+            baseline_cost: 10.0,
+            change_percent: 20.0,
+        });
+        assert_eq!(format_tray_title("${cost} ${trend}", &usage), "$12.00 +20%");
+        usage
+            .trend
+            .as_mut()
+            .expect("trend set above")
+            .change_percent = -7.6;
+        assert_eq!(format_tray_title("${trend}", &usage), "-8%");
+    }
+
+    #[test]
+    fn test_format_tray_title_block_reset() {
+        let now = chrono::DateTime::parse_from_rfc3339("2025-06-01T16:18:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let mut usage = make_usage(3.5, 1_000, &[]);
+        assert_eq!(
+            format_tray_title_at("${cost} ${block_reset}", &usage, now).trim(),
+            "$3.50"
+        );
+
+        usage.active_block_end = Some("2025-06-01T18:00:00+00:00".to_string());
+        assert_eq!(
+            format_tray_title_at("${cost} ⏳${block_reset}", &usage, now),
+            "$3.50 ⏳1h42m"
+        );
+        assert!(unknown_tray_variables("${block_reset}").is_empty());
+    }
+
+    #[test]
+    fn test_tray_updates_coalesce() {
+        let mut updates = TrayUpdates::new();
+        assert!(updates.take().is_none());
+
+        let config = AppConfig::default();
+        assert!(updates.push(Arc::new(make_usage(1.0, 100, &[])), config.clone()));
+        assert!(!updates.push(Arc::new(make_usage(2.0, 100, &[])), config.clone()));
+        let (usage, _) = updates.take().expect("pending update");
+        assert!((usage.today.cost - 2.0).abs() < f64::EPSILON);
+        assert!(updates.take().is_none());
+
+        // A flush has run, so the next request schedules a new one.
+        assert!(updates.push(usage, config));
+    }
+
+    #[test]
+    fn test_tray_updates_skip_identical_render() {
+        let mut updates = TrayUpdates::new();
+        let config = AppConfig::default();
+        let profiles = ProfileList::default();
+        let first = tray_render(&make_usage(1.0, 100, &[]), &config, &profiles, &[]);
+        let same = tray_render(&make_usage(1.0, 100, &[]), &config, &profiles, &[]);
+        let changed = tray_render(&make_usage(3.0, 100, &[]), &config, &profiles, &[]);
+
+        assert!(updates.mark_shown(&first));
+        assert!(!updates.mark_shown(&same));
+        assert!(updates.mark_shown(&changed));
+        assert_ne!(first.title, changed.title);
+    }
+
+    #[test]
+    fn test_provider_menu_lines() {
+        let usage = make_usage(1.0, 100, &[]);
+        let config = AppConfig::default();
+        let providers = [ProviderTrayStats {
+            name: "OpenAI".to_string(),
+            display_text: "OpenAI: $4.20".to_string(),
+            group: None,
+        }];
+        let profiles = ProfileList::default();
+        let entries = tray_menu_entries(
+            Some((&usage, &config)),
+            &MENU_TEXT_EN,
+            &profiles,
+            &providers,
+        );
+        assert!(entries.contains(&MenuEntry::info(
+            "provider_0".to_string(),
+            "OpenAI: $4.20".to_string()
+        )));
+        let without = tray_menu_entries(Some((&usage, &config)), &MENU_TEXT_EN, &profiles, &[]);
+        assert_eq!(entries.len(), without.len() + 2);
+    }
+
+    #[test]
+    fn test_provider_menu_groups() {
+        let stats = |name: &str, group: Option<&str>| ProviderTrayStats {
+            name: name.to_string(),
+            display_text: format!("{name}: --"),
+            group: group.map(str::to_string),
+        };
+        let providers = [
+            stats("Local", None),
+            stats("OpenAI", Some("Work")),
+            stats("Anthropic", Some("Work")),
+            stats("Groq", Some("Side")),
+        ];
+        let entries = tray_menu_entries(None, &MENU_TEXT_EN, &ProfileList::default(), &providers);
+        let first = MenuEntry::info("provider_0".to_string(), "Local: --".to_string());
+        let start = entries.iter().position(|e| *e == first).unwrap();
+        assert_eq!(
+            entries[start..start + 9],
+            [
+                MenuEntry::info("provider_0".to_string(), "Local: --".to_string()),
+                MenuEntry::Separator,
+                MenuEntry::info("provider_group_1".to_string(), "Work:".to_string()),
+                MenuEntry::info("provider_1".to_string(), "OpenAI: --".to_string()),
+                MenuEntry::info("provider_2".to_string(), "Anthropic: --".to_string()),
+                MenuEntry::Separator,
+                MenuEntry::info("provider_group_3".to_string(), "Side:".to_string()),
+                MenuEntry::info("provider_3".to_string(), "Groq: --".to_string()),
+                MenuEntry::Separator,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_privacy_mode_render() {
+        let usage = make_usage(10.0, 2_000_000, &[]);
+        let providers = [ProviderTrayStats {
+            name: "OpenAI".to_string(),
+            display_text: "OpenAI: $4.20".to_string(),
+            group: None,
+        }];
+        let profiles = ProfileList::default();
+        let mut config = AppConfig::default();
+        let plain = tray_render(&usage, &config, &profiles, &providers);
+
+        config.menu_bar.privacy_mode = true;
+        let masked = tray_render(&usage, &config, &profiles, &providers);
+        assert!(masked.title.starts_with("$••• "));
+        assert_eq!(masked.color, plain.color);
+        assert!(!masked.accessibility_label.contains("dollars"));
+        assert!(masked
+            .entries
+            .contains(&MenuEntry::action(PRIVACY_ITEM_ID, "✓ Privacy Mode")));
+        assert!(masked.entries.contains(&MenuEntry::info(
+            "provider_0".to_string(),
+            "OpenAI: $•••".to_string()
+        )));
+
+        config.menu_bar.privacy_style = PrivacyStyle::Scale;
+        config.menu_bar.privacy_scale = 0.5;
+        let scaled = tray_render(&usage, &config, &profiles, &providers);
+        assert!(scaled.title.starts_with("$5.00 "));
+        assert_eq!(scaled.color, plain.color);
+        assert!(scaled.entries.contains(&MenuEntry::info(
+            "model_0".to_string(),
+            "claude-opus-4-5  $3.00".to_string()
+        )));
+        assert!(scaled.entries.contains(&MenuEntry::info(
+            "provider_0".to_string(),
+            "OpenAI: $•••".to_string()
+        )));
+    }
+
+    /// Everything the tray shows for `usage`: styled title, accessibility label
+    /// and the context menu, one line per entry.
+    fn render_tray(usage: Option<&UsageSummary>, config: &AppConfig) -> String {
+        render_tray_with_profiles(usage, config, &ProfileList::default())
+    }
+
+    /// Like [`render_tray`], with `profiles` offered in the "Profile" submenu.
+    fn render_tray_with_profiles(
+        usage: Option<&UsageSummary>,
+        config: &AppConfig,
+        profiles: &ProfileList,
+    ) -> String {
+        let mut out = String::new();
+        if let Some(usage) = usage {
+            let title = format_tray_title(&config.menu_bar.format, usage);
+            let (title, color) = style_tray_title(&title, usage, config);
+            let _ = writeln!(out, "title: {title}\ncolor: {color:?}");
+            let _ = writeln!(
+                out,
+                "label: {}",
+                format_accessible_description(usage, config)
+            );
+        }
+        out.push_str("menu:\n");
+        let text = MenuText::for_language(config.language.as_deref());
+        for entry in tray_menu_entries(usage.map(|u| (u, config)), text, profiles, &[]) {
+            match entry {
+                MenuEntry::Item { id, label, enabled } => {
+                    let state = if enabled { "" } else { " (disabled)" };
+                    let _ = writeln!(out, "  [{id}] {label}{state}");
+                }
+                MenuEntry::Submenu {
+                    id,
+                    label,
+                    items,
+                    selectable,
+                } => {
+                    let _ = writeln!(out, "  [{id}] {label} >");
+                    let state = if selectable { "" } else { " (disabled)" };
+                    for (id, label) in items {
+                        let _ = writeln!(out, "    [{id}] {label}{state}");
+                    }
+                }
+                MenuEntry::Separator => out.push_str("  ---\n"),
+            }
+        }
+        out
+    }
+
+    fn with_many_models(mut usage: UsageSummary) -> UsageSummary {
+        usage.model_breakdown = [
+            ("claude-opus-4-5", 12.5),
+            ("claude-sonnet-4-5", 8.25),
+            ("claude-haiku-4-5", 0.75),
+            ("gpt-4o", 4.1),
+            ("gemini-2.5-pro", 2.0),
+            ("claude-3-7-sonnet", 0.02),
+        ]
+        .into_iter()
+        .map(|(model, cost)| ModelUsage {
+            model: model.to_string(),
+            cost,
+            input_tokens: 1000,
+            output_tokens: 500,
+        })
+        .collect();
+        usage
+    }
+
+    #[test]
+    fn snapshot_tray_before_first_fetch() {
+        insta::assert_snapshot!(
+            "before_first_fetch",
+            render_tray(None, &AppConfig::default())
+        );
+    }
+
+    #[test]
+    fn snapshot_tray_default_config() {
+        let usage = make_usage(9.3, 1_200_000, &[1.0; 12]);
+        insta::assert_snapshot!(
+            "default_config",
+            render_tray(Some(&usage), &AppConfig::default())
+        );
+    }
+
+    /// Usage and config that fill every optional section of the tray menu.
+    fn all_sections() -> (UsageSummary, AppConfig) {
+        let mut usage = with_many_models(make_usage(27.62, 39_300_000, &[3.5; 14]));
+        usage.trend = Some(crate::types::TrendInfo {
+            baseline: crate::config::TrendBaseline::Mean30,
+            baseline_cost: 20.0,
+            change_percent: 38.1,
+        });
+        usage.sources = vec![crate::types::SourceUsage {
+            id: "proxy:…ab12".to_string(),
+            label: "API Proxy (…ab12)".to_string(),
+            today: crate::types::UsageData::default(),
+            this_month: crate::types::UsageData::default(),
+            models: vec![],
+            keys: vec![crate::types::KeyUsage {
+                key: "…ab12".to_string(),
+                today: crate::types::UsageData {
+                    cost: 6.4,
+                    ..crate::types::UsageData::default()
+                },
+                this_month: crate::types::UsageData::default(),
+            }],
+            credits: None,
+        }];
+        usage.sources.push(crate::types::SourceUsage {
+            id: "openrouter".to_string(),
+            label: "OpenRouter".to_string(),
+            today: crate::types::UsageData::default(),
+            this_month: crate::types::UsageData::default(),
+            models: vec![],
+            keys: vec![],
+            credits: Some(crate::types::ProviderUsageResult {
+                cost: None,
+                tokens: None,
+                used: Some(4.2),
+                total: Some(10.0),
+                daily: Vec::new(),
+            }),
+        });
+
+        let mut config = AppConfig::default();
+        config.menu_bar.format = "${cost} ${trend} · ${tokens}".to_string();
+        config.menu_bar.show_recent_days = true;
+        config.menu_bar.show_top_spender = true;
+        config
+            .model_aliases
+            .insert("claude-opus-4-5".to_string(), "Opus".to_string());
+        (usage, config)
+    }
+
+    #[test]
+    fn snapshot_tray_all_sections() {
+        let (usage, config) = all_sections();
+        insta::assert_snapshot!("all_sections", render_tray(Some(&usage), &config));
+    }
+
+    #[test]
+    fn snapshot_tray_all_sections_zh() {
+        let (usage, mut config) = all_sections();
+        config.language = Some("zh".to_string());
+        config.menu_bar.show_profiles = true;
+        let profiles = ProfileList {
+            names: vec!["personal".to_string(), "work".to_string()],
+            active: "work".to_string(),
+        };
+        insta::assert_snapshot!(
+            "all_sections_zh",
+            render_tray_with_profiles(Some(&usage), &config, &profiles)
+        );
+    }
+
+    #[test]
+    fn test_tray_profiles_submenu() {
+        let usage = make_usage(1.0, 100, &[]);
+        let profiles = ProfileList {
+            names: vec!["personal".to_string(), "work".to_string()],
+            active: "work".to_string(),
+        };
+        let mut config = AppConfig::default();
+        assert!(!render_tray_with_profiles(Some(&usage), &config, &profiles).contains("profile"));
+
+        config.menu_bar.show_profiles = true;
+        let menu = render_tray_with_profiles(Some(&usage), &config, &profiles);
+        assert!(menu.contains("  [profiles] Profile: work >\n"));
+        assert!(menu.contains("    [profile:personal] personal\n"));
+        assert!(menu.contains("    [profile:work] ✓ work\n"));
+
+        let single = ProfileList {
+            names: vec!["work".to_string()],
+            active: "work".to_string(),
+        };
+        assert!(!render_tray_with_profiles(Some(&usage), &config, &single).contains("profile"));
+    }
+
+    #[test]
+    fn snapshot_tray_color_schemes() {
+        let usage = make_usage(14.0, 2_000, &[]);
+        let mut out = String::new();
+        for scheme in [
+            ColorScheme::Standard,
+            ColorScheme::HighContrast,
+            ColorScheme::ColorblindSafe,
+            ColorScheme::Monochrome,
+        ] {
+            let mut config = AppConfig::default();
+            config.menu_bar.color_scheme = scheme;
+            let (title, color) = style_tray_title("$14.00", &usage, &config);
+            let _ = writeln!(out, "{scheme:?}: {title} {color:?}");
+        }
+        insta::assert_snapshot!("color_schemes", out);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::config::ApiProvider;
+use crate::config::{ApiProvider, AppConfig, Feature, LimitUnit, ProviderUnit, TrendBaseline};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +48,68 @@ pub struct DailyUsage {
     pub models: Vec<ModelUsage>,
 }
 
+/// Usage attributed to a single data source (Claude Code, Cursor, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceUsage {
+    pub id: String,
+    pub label: String,
+    pub today: UsageData,
+    pub this_month: UsageData,
+    pub models: Vec<ModelUsage>,
+    /// Per-API-key (or per-user) totals for sources that report them
+    #[serde(default)]
+    pub keys: Vec<KeyUsage>,
+    /// Prepaid credit balance (`used` of `total`) for sources that report one
+    #[serde(default)]
+    pub credits: Option<ProviderUsageResult>,
+}
+
+/// Usage attributed to one API key or user within a source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyUsage {
+    pub key: String,
+    pub today: UsageData,
+    pub this_month: UsageData,
+}
+
+/// One member (API key or user) in the spend leaderboard, combined across sources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberUsage {
+    pub member: String,
+    /// Labels of the sources the member's usage came from
+    pub sources: Vec<String>,
+    pub today: UsageData,
+    pub this_month: UsageData,
+}
+
+/// This month's spend of the user compared with the other members of their
+/// team or organization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgComparison {
+    /// Leaderboard member compared, or `None` for the user's total spend
+    pub member: Option<String>,
+    pub cost: f64,
+    /// Average cost of the other members
+    pub org_average: f64,
+    /// Percent of the other members who spent less
+    pub percentile: f64,
+    /// Number of other members compared against
+    pub member_count: usize,
+}
+
+/// Today's cost compared against the configured trend baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendInfo {
+    pub baseline: TrendBaseline,
+    pub baseline_cost: f64,
+    pub change_percent: f64,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UsageSummary {
@@ -55,6 +117,401 @@ pub struct UsageSummary {
     pub this_month: UsageData,
     pub daily_usage: Vec<DailyUsage>,
     pub model_breakdown: Vec<ModelUsage>,
+    #[serde(default)]
+    pub sources: Vec<SourceUsage>,
+    #[serde(default)]
+    pub trend: Option<TrendInfo>,
+    /// Subscription limit usage, present while limit mode is on
+    #[serde(default)]
+    pub limits: Option<LimitUsage>,
+    /// RFC 3339 end of the active 5-hour block, present while the tray title
+    /// shows `${block_reset}`
+    #[serde(default)]
+    pub active_block_end: Option<String>,
+    #[serde(default)]
+    pub meta: RefreshMeta,
+}
+
+/// How much of the subscription's rolling limits has been used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitUsage {
+    pub unit: LimitUnit,
+    /// Usage in the active 5-hour block; zero when no block is active
+    pub block_used: u64,
+    pub block_limit: u64,
+    /// RFC 3339 end of the active block
+    pub block_resets_at: Option<String>,
+    /// Usage in the last seven days
+    pub weekly_used: u64,
+    pub weekly_limit: u64,
+}
+
+impl LimitUsage {
+    /// Percent of the block limit used, when one is set.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn block_percent(&self) -> Option<f64> {
+        (self.block_limit > 0).then(|| self.block_used as f64 / self.block_limit as f64 * 100.0)
+    }
+
+    /// Percent of the weekly limit used, when one is set.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn weekly_percent(&self) -> Option<f64> {
+        (self.weekly_limit > 0).then(|| self.weekly_used as f64 / self.weekly_limit as f64 * 100.0)
+    }
+}
+
+/// How and when a summary was produced, for the dashboard footer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshMeta {
+    /// RFC 3339 local time the refresh finished
+    pub fetched_at: Option<String>,
+    pub duration_ms: u64,
+    /// How each source was read, keyed by source id (e.g. "ccusage 15.2.0" or "native")
+    pub source_versions: std::collections::BTreeMap<String, String>,
+    /// Error of the latest refresh attempt, when it failed and this summary is stale
+    pub last_error: Option<String>,
+    /// Whether the summary came from a running `tokenmeterd`, which owns refreshes
+    #[serde(default)]
+    pub managed_by_agent: bool,
+}
+
+/// Model metadata from models.dev (context window, output limit, knowledge cutoff)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    pub id: String,
+    pub name: String,
+    pub provider: String,
+    pub context_window: Option<u64>,
+    pub max_output_tokens: Option<u64>,
+    pub knowledge_cutoff: Option<String>,
+    pub release_date: Option<String>,
+}
+
+/// Usage within one hour of the day (0-23, local time)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HourBucket {
+    pub hour: u32,
+    pub tokens: u64,
+    pub cost: f64,
+    pub percent: f64,
+}
+
+/// Usage on one day of the week (0 = Monday, 6 = Sunday)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekdayBucket {
+    pub weekday: u32,
+    pub tokens: u64,
+    pub cost: f64,
+    pub percent: f64,
+}
+
+/// Contiguous hours holding the largest share of tokens; `end_hour` is exclusive
+/// and wraps past midnight (e.g. 21 → 1).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeakWindow {
+    pub start_hour: u32,
+    pub end_hour: u32,
+    pub percent: f64,
+}
+
+/// A 5-hour Claude billing window and the usage recorded in it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBlock {
+    /// RFC 3339 start of the window (the hour of its first request)
+    pub start_time: String,
+    /// RFC 3339 end of the window, five hours after the start
+    pub end_time: String,
+    pub is_active: bool,
+    /// Minutes from the start until now (active) or the last request (finished)
+    pub elapsed_minutes: u64,
+    /// Minutes until the window closes; zero once finished
+    pub remaining_minutes: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub total_tokens: u64,
+    pub cost: f64,
+    /// Cost at the end of the window if spending continues at the current rate
+    pub projected_cost: Option<f64>,
+    pub models: Vec<String>,
+}
+
+/// One Claude Code conversation as reported by `ccusage session`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeSession {
+    pub session_id: String,
+    pub project_path: String,
+    /// Local date (YYYY-MM-DD) of the session's last request
+    pub last_activity: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub cost: f64,
+    pub models: Vec<String>,
+}
+
+/// Cost and token series aligned for a dual-axis chart, with one point per
+/// day, or per week (Monday first) when `bucket_days` is 7
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DualSeries {
+    pub bucket_days: u32,
+    pub dates: Vec<String>,
+    pub cost: Vec<f64>,
+    /// All tokens, including cache tokens
+    pub tokens: Vec<u64>,
+    /// Cache creation and cache read tokens
+    pub cache_tokens: Vec<u64>,
+}
+
+/// Claude Code spending on conversations sharing one title
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicUsage {
+    /// Claude Code's summary of the conversation or its first prompt; `None`
+    /// groups conversations with neither
+    pub title: Option<String>,
+    pub sessions: u32,
+    pub total_tokens: u64,
+    pub cost: f64,
+    /// Local date (YYYY-MM-DD) of the latest request
+    pub last_activity: String,
+}
+
+/// Time-of-day / weekday spending profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeProfile {
+    pub days: u32,
+    pub total_tokens: u64,
+    pub total_cost: f64,
+    pub hours: Vec<HourBucket>,
+    pub weekdays: Vec<WeekdayBucket>,
+    /// Tokens per weekday (rows, Monday first) and hour (columns)
+    pub heatmap: Vec<Vec<u64>>,
+    pub peak_window: Option<PeakWindow>,
+}
+
+/// Outcome of the most recent runs of a background integration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrationStatus {
+    /// RFC 3339 timestamp of the last attempt
+    pub last_attempt: Option<String>,
+    /// RFC 3339 timestamp of the last successful run
+    pub last_success: Option<String>,
+    /// Error from the last attempt, cleared on success
+    pub last_error: Option<String>,
+}
+
+/// Result of checking a tray title format string in the settings UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayFormatPreview {
+    /// The format rendered with current usage (zeros before the first fetch)
+    pub preview: String,
+    /// Placeholders that are not recognized and would be shown literally
+    pub unknown_variables: Vec<String>,
+}
+
+/// An experimental feature and whether it is currently on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureInfo {
+    pub id: Feature,
+    pub description: String,
+    pub enabled: bool,
+    /// State used when the user has not overridden the feature
+    pub default_enabled: bool,
+}
+
+/// What is in place for usage to show up, so a first launch can be explained
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupStatus {
+    /// Claude Code transcripts were found in the configured or default directories
+    pub claude_data_found: bool,
+    /// An explicit ccusage executable is configured
+    pub ccusage_path_set: bool,
+    /// Number of enabled custom providers
+    pub enabled_providers: usize,
+    /// A usage summary has been loaded since launch
+    pub usage_ready: bool,
+}
+
+/// Everything the dashboard needs for its first paint, in one IPC roundtrip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootPayload {
+    pub config: AppConfig,
+    /// Last summary snapshot; `None` until the first refresh completes
+    pub usage: Option<std::sync::Arc<UsageSummary>>,
+    /// Saved providers with their secrets redacted
+    pub providers: Vec<ApiProvider>,
+    pub setup: SetupStatus,
+}
+
+/// Runtime health information surfaced in the settings UI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    /// Status per integration id (e.g. "notion")
+    pub integrations: std::collections::BTreeMap<String, IntegrationStatus>,
+    /// What the configured ccusage supports, once it has been probed
+    #[serde(default)]
+    pub ccusage: Option<CcusageCapabilities>,
+    /// Provider env entries with an expiry date, soonest first
+    #[serde(default)]
+    pub key_expirations: Vec<KeyExpiration>,
+}
+
+/// A provider env entry (typically an API key) with an expiry date
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyExpiration {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub variable: String,
+    pub expires_at: chrono::NaiveDate,
+    /// Negative once the entry has expired
+    pub days_left: i64,
+}
+
+/// Version and optional flags detected for a ccusage executable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CcusageCapabilities {
+    pub version: Option<String>,
+    /// `false` when `--help` could not be read and the flags are assumed
+    pub help_read: bool,
+    pub offline: bool,
+    pub days: bool,
+    pub since: bool,
+    pub breakdown: bool,
+    /// `blocks --recent`
+    pub recent: bool,
+    /// The `session` subcommand
+    pub session: bool,
+}
+
+impl Default for CcusageCapabilities {
+    /// The flags used before detection existed, assumed when help is unavailable.
+    fn default() -> Self {
+        Self {
+            version: None,
+            help_read: false,
+            offline: true,
+            days: true,
+            since: false,
+            breakdown: false,
+            recent: true,
+            session: false,
+        }
+    }
+}
+
+impl Diagnostics {
+    /// Records the result of an integration run.
+    pub fn record_integration<E: std::fmt::Display>(&mut self, id: &str, result: Result<(), E>) {
+        let now = chrono::Local::now().to_rfc3339();
+        let status = self.integrations.entry(id.to_string()).or_default();
+        status.last_attempt = Some(now.clone());
+        match result {
+            Ok(()) => {
+                status.last_success = Some(now);
+                status.last_error = None;
+            }
+            Err(e) => status.last_error = Some(e.to_string()),
+        }
+    }
+}
+
+/// One histogram bucket of span durations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerfBucket {
+    /// Inclusive upper bound in milliseconds; `None` for the overflow bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// Duration statistics for one instrumented command or service call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerfStat {
+    /// Span name, e.g. `command.refresh_usage` or `service.ccusage`
+    pub name: String,
+    pub count: u64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    /// Estimated from the histogram buckets
+    pub p50_ms: f64,
+    /// Estimated from the histogram buckets
+    pub p95_ms: f64,
+    pub buckets: Vec<PerfBucket>,
+}
+
+/// A day's usage as recorded by one save, from the history journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalPoint {
+    /// RFC 3339 local time of the save
+    pub recorded_at: String,
+    pub usage: DailyUsage,
+}
+
+/// Claude Code summary from the last refresh, reused while its transcripts are unchanged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryCache {
+    /// Local date, UTC offset and transcript fingerprint the summary was
+    /// computed from
+    pub fingerprint: String,
+    pub summary: UsageSummary,
+}
+
+/// Persistent bookkeeping for scheduled jobs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerState {
+    /// Local date the weekly email digest was last sent
+    #[serde(default)]
+    pub email_digest_last_sent: Option<chrono::NaiveDate>,
+    /// Most recent budget alert, so each level is only sent once per day
+    #[serde(default)]
+    pub budget_alert: Option<BudgetAlertRecord>,
+    /// When each provider's balance drain was last alerted, so it repeats at
+    /// most once per window
+    #[serde(default)]
+    pub balance_drain_alerts: std::collections::BTreeMap<String, chrono::DateTime<chrono::Utc>>,
+    /// Expiry date each provider env entry was last reminded of, keyed by
+    /// "provider/VARIABLE", so a rotated key with a new date is reminded again
+    #[serde(default)]
+    pub key_expiry_alerts: std::collections::BTreeMap<String, chrono::NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlertRecord {
+    pub date: chrono::NaiveDate,
+    pub over_budget: bool,
+    #[serde(default)]
+    pub over_hard_budget: bool,
 }
 
 /// Provider statistics for tray menu display
@@ -63,6 +520,9 @@ pub struct UsageSummary {
 pub struct ProviderTrayStats {
     pub name: String,
     pub display_text: String,
+    /// Section the line is listed under
+    #[serde(default)]
+    pub group: Option<String>,
 }
 
 impl ProviderTrayStats {
@@ -70,13 +530,108 @@ impl ProviderTrayStats {
     pub fn from_provider(provider: &ApiProvider, result: Option<&ProviderUsageResult>) -> Self {
         let display_text = result.map_or_else(
             || format!("{}: --", provider.name),
-            |r| r.format_display(&provider.name),
+            |r| {
+                provider.unit.map_or_else(
+                    || r.format_display(&provider.name),
+                    |unit| r.format_display_in(&provider.name, unit, provider.unit_label()),
+                )
+            },
         );
         Self {
             name: provider.name.clone(),
             display_text,
+            group: provider.group_name().map(str::to_string),
         }
     }
+
+    /// Marks the line of a provider whose latest fetch failed.
+    #[must_use]
+    pub fn degraded(mut self) -> Self {
+        let text = self
+            .display_text
+            .strip_prefix("🔋 ")
+            .unwrap_or(&self.display_text);
+        self.display_text = format!("⚠️ {text}");
+        self
+    }
+}
+
+/// Failures in a row after which a provider counts as failing, not degraded
+pub const PROVIDER_FAILING_AFTER: u32 = 3;
+
+/// Fetch health of a provider, kept in `AppState` and its provider file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderHealth {
+    pub consecutive_failures: u32,
+    /// RFC 3339 time of the last successful fetch
+    pub last_success: Option<String>,
+    pub last_error: Option<String>,
+}
+
+impl ProviderHealth {
+    /// Health recorded in `provider`'s file.
+    #[must_use]
+    pub fn of(provider: &ApiProvider) -> Self {
+        Self {
+            consecutive_failures: provider.consecutive_failures,
+            last_success: provider.last_fetched.clone(),
+            last_error: provider.last_error.clone(),
+        }
+    }
+
+    #[must_use]
+    pub const fn level(&self) -> ProviderHealthLevel {
+        match self.consecutive_failures {
+            0 if self.last_success.is_none() => ProviderHealthLevel::Unknown,
+            0 => ProviderHealthLevel::Healthy,
+            n if n < PROVIDER_FAILING_AFTER => ProviderHealthLevel::Degraded,
+            _ => ProviderHealthLevel::Failing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderHealthLevel {
+    /// Never fetched
+    Unknown,
+    Healthy,
+    /// The latest fetches failed, fewer than [`PROVIDER_FAILING_AFTER`] in a row
+    Degraded,
+    Failing,
+}
+
+/// Health of one provider, from `get_provider_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStatus {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub level: ProviderHealthLevel,
+    #[serde(flatten)]
+    pub health: ProviderHealth,
+}
+
+/// Last successful result of a provider fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedProviderResult {
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    pub result: ProviderUsageResult,
+}
+
+/// Cached provider results keyed by provider id, persisted in `provider_cache.json`
+pub type ProviderResultCache = std::collections::BTreeMap<String, CachedProviderResult>;
+
+/// One fetched provider result, from `provider_history/{id}.jsonl`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderHistoryPoint {
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub result: ProviderUsageResult,
 }
 
 /// Provider script execution result
@@ -87,6 +642,21 @@ pub struct ProviderUsageResult {
     pub tokens: Option<u64>,
     pub used: Option<f64>,
     pub total: Option<f64>,
+    /// Per-day usage the provider reports alongside its totals, merged into
+    /// its daily history for charts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub daily: Vec<ProviderDailyUsage>,
+}
+
+/// One day of a provider's reported usage, from `provider_history/{id}.daily.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderDailyUsage {
+    pub date: chrono::NaiveDate,
+    #[serde(default)]
+    pub cost: Option<f64>,
+    #[serde(default)]
+    pub tokens: Option<u64>,
 }
 
 impl ProviderUsageResult {
@@ -124,6 +694,75 @@ impl ProviderUsageResult {
         }
         parts.join(" ")
     }
+
+    /// Like [`Self::format_display`], but writes the cost and used/total
+    /// balance in `unit`, e.g. "1,200/5,000 credits", with `label` replacing
+    /// the unit's own word.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn format_display_in(&self, name: &str, unit: ProviderUnit, label: Option<&str>) -> String {
+        let suffix = label
+            .or_else(|| unit.label())
+            .map_or_else(String::new, |label| format!(" {label}"));
+
+        if let (Some(used), Some(total)) = (self.used, self.total) {
+            let percent = if total > 0.0 {
+                (used / total * 100.0).round() as u32
+            } else {
+                0
+            };
+            let bar = render_progress_bar(used, total, 10);
+            return format!(
+                "🔋 {name}: [{bar}] {}/{}{suffix} ({percent}%)",
+                format_amount(used, unit),
+                format_amount(total, unit)
+            );
+        }
+
+        let mut parts = vec![format!("🔋 {name}:")];
+        if let Some(cost) = self.cost {
+            parts.push(format!("{}{suffix}", format_amount(cost, unit)));
+        }
+        if let Some(tokens) = self.tokens {
+            parts.push(format!("/ {} tokens", format_number(tokens)));
+        }
+        if parts.len() == 1 {
+            parts.push("--".to_string());
+        }
+        parts.join(" ")
+    }
+}
+
+/// An amount counted in `unit`, without the unit's word.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn format_amount(value: f64, unit: ProviderUnit) -> String {
+    match unit {
+        ProviderUnit::Usd => format!("${value:.2}"),
+        ProviderUnit::Tokens => format_number(value.max(0.0).round() as u64),
+        ProviderUnit::Credits | ProviderUnit::Requests => format_grouped(value),
+    }
+}
+
+/// Formats `value` with thousands separators and up to two decimals.
+fn format_grouped(value: f64) -> String {
+    let text = format!("{:.2}", value.abs());
+    let (int, frac) = text.split_once('.').unwrap_or((&text, ""));
+    let mut grouped = String::new();
+    if value < 0.0 && text != "0.00" {
+        grouped.push('-');
+    }
+    for (i, digit) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let frac = frac.trim_end_matches('0');
+    if !frac.is_empty() {
+        grouped.push('.');
+        grouped.push_str(frac);
+    }
+    grouped
 }
 
 /// Format numbers with K/M/B suffix
@@ -0,0 +1,191 @@
+use std::path::{Path, PathBuf};
+
+use tokenmeter_lib::config::{ClaudeCodeSourceConfig, FeatureGate, TimeoutsConfig, TrendsConfig};
+use tokenmeter_lib::services::{ccusage, sources, trends};
+use tokenmeter_lib::storage;
+use tokenmeter_lib::tray::format_tray_title;
+use tokenmeter_lib::types::DailyUsage;
+
+fn day_offset(days: i64) -> String {
+    (chrono::Local::now().date_naive() - chrono::Duration::days(days))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// ccusage `--json` daily output covering yesterday and today.
+fn fixture_json() -> String {
+    serde_json::json!({
+        "daily": [
+            {
+                "date": day_offset(1),
+                "inputTokens": 1000,
+                "outputTokens": 500,
+                "cacheCreationTokens": 0,
+                "cacheReadTokens": 0,
+                "totalTokens": 1500,
+                "totalCost": 0.75,
+                "modelBreakdowns": [{
+                    "modelName": "claude-sonnet-4-20250514",
+                    "inputTokens": 1000,
+                    "outputTokens": 500,
+                    "cacheCreationTokens": 0,
+                    "cacheReadTokens": 0,
+                    "cost": 0.75
+                }]
+            },
+            {
+                "date": day_offset(0),
+                "inputTokens": 12000,
+                "outputTokens": 3000,
+                "cacheCreationTokens": 200,
+                "cacheReadTokens": 300,
+                "totalTokens": 15500,
+                "totalCost": 2.5,
+                "modelBreakdowns": [{
+                    "modelName": "claude-opus-4-20250514",
+                    "inputTokens": 12000,
+                    "outputTokens": 3000,
+                    "cacheCreationTokens": 200,
+                    "cacheReadTokens": 300,
+                    "cost": 2.5
+                }]
+            }
+        ],
+        "totals": {
+            "inputTokens": 13000,
+            "outputTokens": 3500,
+            "cacheCreationTokens": 200,
+            "cacheReadTokens": 300,
+            "totalTokens": 17000,
+            "totalCost": 3.25
+        }
+    })
+    .to_string()
+}
+
+/// Writes an executable stand-in for ccusage that prints `stdout` and exits with `code`.
+#[cfg(unix)]
+fn fake_ccusage(dir: &Path, stdout: &str, code: i32) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let output = dir.join("output.txt");
+    std::fs::write(&output, stdout).unwrap();
+    let script = dir.join("ccusage");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\ncat '{}'\necho 'fake ccusage error' >&2\nexit {code}\n",
+            output.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+/// Writes an executable stand-in for ccusage that prints `stdout` and exits with `code`.
+#[cfg(windows)]
+fn fake_ccusage(dir: &Path, stdout: &str, code: i32) -> PathBuf {
+    let output = dir.join("output.txt");
+    std::fs::write(&output, stdout).unwrap();
+    let script = dir.join("ccusage.cmd");
+    std::fs::write(
+        &script,
+        format!(
+            "@echo off\r\ntype \"{}\"\r\necho fake ccusage error 1>&2\r\nexit /b {code}\r\n",
+            output.display()
+        ),
+    )
+    .unwrap();
+    script
+}
+
+fn source_config(path: &Path) -> ClaudeCodeSourceConfig {
+    ClaudeCodeSourceConfig {
+        ccusage_path: Some(path.to_string_lossy().into_owned()),
+        ..ClaudeCodeSourceConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn fake_ccusage_flows_through_merge_and_tray_title() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path();
+    let binary = fake_ccusage(dir, &fixture_json(), 0);
+
+    let seeded = DailyUsage {
+        date: day_offset(10),
+        cost: 1.0,
+        input_tokens: 400,
+        output_tokens: 100,
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: 0,
+        models: Vec::new(),
+    };
+    storage::save_history(dir, &[seeded]).unwrap();
+
+    let mut data = ccusage::fetch_usage(
+        &source_config(&binary),
+        &TimeoutsConfig::default(),
+        &FeatureGate::default(),
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(data.today.date, day_offset(0));
+    assert!((data.today.cost - 2.5).abs() < f64::EPSILON);
+    assert_eq!(data.today.total_tokens, 15_500);
+    assert_eq!(data.meta.source_versions["claude-code"], "ccusage");
+
+    let history = storage::load_history(dir).unwrap();
+    let merged = storage::merge_history(&history, &data.daily_usage);
+    storage::save_history(dir, &merged).unwrap();
+    let dates: Vec<String> = storage::load_history(dir)
+        .unwrap()
+        .into_iter()
+        .map(|d| d.date)
+        .collect();
+    assert_eq!(dates, vec![day_offset(10), day_offset(1), day_offset(0)]);
+
+    data.daily_usage = merged;
+    sources::apply_reports(&mut data, &[]);
+    trends::apply_trend(&mut data, &TrendsConfig::default());
+
+    assert_eq!(data.sources.len(), 1);
+    assert_eq!(format_tray_title("${cost} ${tokens}", &data), "$2.50 15.5K");
+}
+
+#[tokio::test]
+async fn failing_ccusage_surfaces_stderr() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path();
+    let binary = fake_ccusage(dir, "", 1);
+
+    let err = ccusage::fetch_usage(
+        &source_config(&binary),
+        &TimeoutsConfig::default(),
+        &FeatureGate::default(),
+        None,
+    )
+    .await
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("ccusage failed"), "{err}");
+    assert!(err.contains("fake ccusage error"), "{err}");
+}
+
+#[tokio::test]
+async fn malformed_ccusage_output_is_an_error() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path();
+    let binary = fake_ccusage(dir, "not json", 0);
+
+    let result = ccusage::fetch_usage(
+        &source_config(&binary),
+        &TimeoutsConfig::default(),
+        &FeatureGate::default(),
+        None,
+    )
+    .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Local};
+use proptest::prelude::*;
+use serde_json::json;
+use tokenmeter_lib::config::JsonlFieldMapping;
+use tokenmeter_lib::services::ccusage::parse_daily_export;
+use tokenmeter_lib::services::claude_logs::parse_transcript;
+use tokenmeter_lib::services::custom_jsonl::parse_records;
+use tokenmeter_lib::services::shell_utils::parse_command;
+use tokenmeter_lib::storage::merge_history;
+use tokenmeter_lib::tray::format_tray_title;
+use tokenmeter_lib::types::{DailyUsage, RefreshMeta, UsageData, UsageSummary};
+
+const CASES: u32 = 512;
+
+/// Pieces of text that matter to the parsers under test, mixed with plain ones.
+const PIECES: &[&str] = &[
+    "a",
+    "Z",
+    "0",
+    " ",
+    "\t",
+    "\n",
+    "'",
+    "\"",
+    "\\",
+    "$",
+    "{",
+    "}",
+    "${",
+    "${cost}",
+    "${tokens}",
+    "${trend}",
+    "${input}",
+    "${output}",
+    "${unknown}",
+    "é",
+    "日",
+    "🚀",
+    "-",
+    "=",
+    "#",
+    ";",
+    "|",
+];
+
+/// Up to `max_len` of [`PIECES`].
+fn text(max_len: usize) -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(PIECES), 0..=max_len)
+        .prop_map(|pieces| pieces.concat())
+}
+
+fn summary() -> impl Strategy<Value = UsageSummary> {
+    (
+        0..1_000_000u32,
+        0..10_000_000_000u64,
+        0..10_000_000u64,
+        0..10_000_000_000u64,
+    )
+        .prop_map(
+            |(cents, input_tokens, output_tokens, total_tokens)| UsageSummary {
+                today: UsageData {
+                    date: "2026-01-15".to_string(),
+                    cost: f64::from(cents) / 100.0,
+                    input_tokens,
+                    output_tokens,
+                    cache_creation_input_tokens: 0,
+                    cache_read_input_tokens: 0,
+                    total_tokens,
+                },
+                this_month: UsageData::default(),
+                daily_usage: Vec::new(),
+                model_breakdown: Vec::new(),
+                sources: Vec::new(),
+                trend: None,
+                limits: None,
+                active_block_end: None,
+                meta: RefreshMeta::default(),
+            },
+        )
+}
+
+/// Usage history for January 2026 with every entry's cost set to `tag`, so a
+/// merge shows which side each day came from.
+fn history(tag: f64) -> impl Strategy<Value = Vec<DailyUsage>> {
+    prop::collection::vec((1..=28u32, 0..1000u64, 0..1000u64), 0..20).prop_map(move |days| {
+        days.into_iter()
+            .map(|(day, input_tokens, output_tokens)| DailyUsage {
+                date: format!("2026-01-{day:02}"),
+                cost: tag,
+                input_tokens,
+                output_tokens,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+                models: Vec::new(),
+            })
+            .collect()
+    })
+}
+
+/// A ccusage daily entry in January 2026 and the cost it should be read as.
+fn ccusage_day() -> impl Strategy<Value = (serde_json::Value, f64)> {
+    (
+        1..=28u32,
+        0..1_000_000u64,
+        0..1_000_000u64,
+        prop::collection::vec(("[a-z0-9-]{1,20}", 0..100_000u32), 0..4),
+    )
+        .prop_map(|(day, input, output, models)| {
+            let breakdowns: Vec<_> = models
+                .iter()
+                .map(|(name, cents)| {
+                    json!({"modelName": name, "inputTokens": input, "outputTokens": output,
+                        "cost": f64::from(*cents) / 100.0})
+                })
+                .collect();
+            let cost = models
+                .iter()
+                .map(|(_, cents)| f64::from(*cents) / 100.0)
+                .sum();
+            let entry = json!({
+                "date": format!("2026-01-{day:02}"),
+                "inputTokens": input,
+                "outputTokens": output,
+                "totalTokens": input + output,
+                "totalCost": cost,
+                "modelBreakdowns": breakdowns,
+            });
+            (entry, cost)
+        })
+}
+
+/// A transcript line: an assistant response with usage, a prompt, a summary
+/// or arbitrary text, with whether it is usage.
+fn transcript_line() -> impl Strategy<Value = (String, bool)> {
+    prop_oneof![
+        (0..1000u64, 0..1000u64, 0..10u32, 0..59u32, "[a-z-]{0,12}").prop_map(
+            |(input, output, id, minute, model)| {
+                let line = json!({
+                    "timestamp": format!("2026-01-15T10:{minute:02}:00Z"),
+                    "requestId": format!("req-{id}"),
+                    "message": {"id": format!("msg-{id}"), "model": model,
+                        "usage": {"input_tokens": input, "output_tokens": output}},
+                });
+                (line.to_string(), input + output > 0)
+            }
+        ),
+        text(30).prop_map(|prompt| {
+            let line = json!({"type": "user", "message": {"content": prompt}});
+            (line.to_string(), false)
+        }),
+        text(30).prop_map(|summary| {
+            let line = json!({"type": "summary", "summary": summary});
+            (line.to_string(), false)
+        }),
+        text(60).prop_map(|text| (text.replace('\n', " "), false)),
+    ]
+}
+
+fn jsonl_fields() -> JsonlFieldMapping {
+    JsonlFieldMapping {
+        timestamp: "ts".to_string(),
+        model: "model".to_string(),
+        input_tokens: "usage.input".to_string(),
+        output_tokens: "usage.output".to_string(),
+        cache_creation_tokens: None,
+        cache_read_tokens: None,
+        cost: Some("cost".to_string()),
+        key: Some("user".to_string()),
+    }
+}
+
+fn since() -> DateTime<Local> {
+    DateTime::from_timestamp(0, 0)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn tray_title_substitutes_every_known_variable(template in text(24), usage in summary()) {
+        const KNOWN: &[&str] = &["${cost}", "${tokens}", "${trend}", "${input}", "${output}"];
+        let title = format_tray_title(&template, &usage);
+
+        for var in KNOWN {
+            prop_assert!(
+                !title.contains(var) || !template.contains(var),
+                "{var} left in {title:?} from {template:?}"
+            );
+        }
+        if !template.contains("${") {
+            prop_assert_eq!(title, template);
+        }
+    }
+
+    #[test]
+    fn tray_title_formats_cost_with_two_decimals(usage in summary()) {
+        prop_assert_eq!(
+            format_tray_title("${cost}", &usage),
+            format!("${:.2}", usage.today.cost)
+        );
+    }
+
+    #[test]
+    fn parse_command_round_trips_quoted_arguments(
+        args in prop::collection::vec(text(12), 1..=6)
+    ) {
+        let script = shlex::try_join(args.iter().map(String::as_str)).unwrap();
+        prop_assert_eq!(parse_command(&script, &HashMap::new()), Some(args), "{:?}", script);
+    }
+
+    #[test]
+    fn parse_command_handles_arbitrary_input(args in text(40)) {
+        let env = HashMap::from([
+            ("TOKEN".to_string(), "a 'quoted' value".to_string()),
+            ("HOST".to_string(), "api.example.com".to_string()),
+        ]);
+        if let Some(parts) = parse_command(&format!("curl {args}"), &env) {
+            prop_assert_eq!(parts.first().map(String::as_str), Some("curl"));
+        }
+    }
+
+    #[test]
+    fn merge_history_is_sorted_unique_and_prefers_new_data(
+        current in history(1.0),
+        new_data in history(2.0)
+    ) {
+        let merged = merge_history(&current, &new_data);
+
+        prop_assert!(merged.windows(2).all(|w| w[0].date < w[1].date), "not strictly sorted");
+        for entry in current.iter().chain(&new_data) {
+            prop_assert!(merged.iter().any(|m| m.date == entry.date), "lost {}", entry.date);
+        }
+        for entry in &merged {
+            let from_new = new_data.iter().any(|n| n.date == entry.date);
+            let expected = if from_new { 2.0 } else { 1.0 };
+            prop_assert!(
+                (entry.cost - expected).abs() < f64::EPSILON,
+                "{} came from the wrong side",
+                entry.date
+            );
+        }
+
+        let again = merge_history(&merged, &new_data);
+        let dates = |v: &[DailyUsage]| v.iter().map(|d| d.date.clone()).collect::<Vec<_>>();
+        prop_assert_eq!(dates(&again), dates(&merged), "merge not idempotent");
+    }
+
+    #[test]
+    fn ccusage_export_reads_generated_reports(days in prop::collection::vec(ccusage_day(), 0..8)) {
+        let export = json!({"daily": days.iter().map(|(entry, _)| entry).collect::<Vec<_>>()});
+        let parsed = parse_daily_export(&export.to_string()).unwrap();
+
+        prop_assert_eq!(parsed.len(), days.len());
+        for (day, (entry, cost)) in parsed.iter().zip(&days) {
+            prop_assert_eq!(&day.date, entry["date"].as_str().unwrap());
+            prop_assert!((day.cost - cost).abs() < 1e-6, "{} cost {}", day.date, day.cost);
+        }
+    }
+
+    #[test]
+    fn ccusage_export_rejects_other_input(input in text(60)) {
+        prop_assert!(parse_daily_export(&input).is_err());
+    }
+
+    #[test]
+    fn transcript_keeps_each_usage_line_once(
+        lines in prop::collection::vec(transcript_line(), 0..30)
+    ) {
+        let content: Vec<&str> = lines.iter().map(|(line, _)| line.as_str()).collect();
+        let content = content.join("\n");
+        let transcript = parse_transcript(content.as_bytes(), since(), &mut HashSet::new());
+
+        // Streamed copies of a response share its message and request ids
+        let ids: HashSet<&str> = lines
+            .iter()
+            .filter(|(_, usage)| *usage)
+            .filter_map(|(line, _)| line.split("\"requestId\":\"").nth(1)?.split('"').next())
+            .collect();
+        prop_assert_eq!(transcript.entries.len(), ids.len());
+        prop_assert!(transcript.entries.iter().all(|entry| entry.total_tokens() > 0));
+    }
+
+    #[test]
+    fn custom_jsonl_reads_mapped_lines(
+        lines in prop::collection::vec(
+            prop_oneof![
+                (0..1_000_000u64, 0..1_000_000u64, 0..10_000u32)
+                    .prop_map(|(input, output, cents)| Some((input, output, cents))),
+                Just(None),
+            ],
+            0..20,
+        ),
+        noise in text(40)
+    ) {
+        let content: Vec<String> = lines
+            .iter()
+            .map(|line| match line {
+                Some((input, output, cents)) => json!({
+                    "ts": "2026-01-15T10:00:00Z", "model": "m", "user": "alice",
+                    "usage": {"input": input, "output": output},
+                    "cost": f64::from(*cents) / 100.0,
+                })
+                .to_string(),
+                None => noise.replace('\n', " "),
+            })
+            .collect();
+        let records = parse_records(content.join("\n").as_bytes(), &jsonl_fields());
+
+        let expected: Vec<_> = lines.iter().flatten().collect();
+        prop_assert_eq!(records.len(), expected.len());
+        for (parsed, (input, output, _)) in records.iter().zip(expected) {
+            prop_assert_eq!(parsed.record.input_tokens, *input);
+            prop_assert_eq!(parsed.record.output_tokens, *output);
+            prop_assert_eq!(parsed.record.key.as_deref(), Some("alice"));
+            prop_assert!(parsed.has_cost);
+        }
+    }
+}
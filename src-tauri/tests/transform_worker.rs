@@ -0,0 +1,39 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokenmeter_lib::services::script_runner::run_transform_in_worker;
+
+fn worker() -> &'static Path {
+    Path::new(env!("CARGO_BIN_EXE_tokenmeterd"))
+}
+
+#[tokio::test]
+async fn test_worker_runs_transform() {
+    let script = "(r) => { console.log('total', r.total); return { cost: r.total / 100 } }";
+    let (result, logs) = run_transform_in_worker(
+        worker(),
+        script,
+        r#"{"total": 250}"#,
+        Duration::from_secs(10),
+    )
+    .await;
+    assert_eq!(result.unwrap(), r#"{"cost":2.5}"#);
+    assert_eq!(logs, ["total 250"]);
+
+    let (result, _) =
+        run_transform_in_worker(worker(), "(r) => r.a.b", "{}", Duration::from_secs(10)).await;
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Script execution error"));
+}
+
+#[tokio::test]
+async fn test_worker_is_killed_on_timeout() {
+    // Builtins aren't metered, so only the deadline stops this
+    let script = "(r) => { let a = Array(1e6).fill(0); \
+        for (let i = 0; i < 1e5; i++) a = a.map((x) => x + 1); return { cost: a[0] } }";
+    let started = Instant::now();
+    let (result, _) = run_transform_in_worker(worker(), script, "{}", Duration::from_secs(1)).await;
+    assert!(result.unwrap_err().to_string().contains("stopped after 1s"));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
@@ -7,13 +7,19 @@ import { ProviderEditor } from '@/components/ProviderEditor'
 import { Settings } from '@/components/Settings'
 import { Tabs, TabsContent, TabsList, TabsTrigger } from '@/components/ui/tabs'
 import { useConfigEvents } from '@/hooks/useConfigEvents'
+import { usePrivacyMode } from '@/hooks/usePrivacyMode'
+import { useRefreshHotkey } from '@/hooks/useRefreshHotkey'
 import { useTheme } from '@/hooks/useTheme'
+import { useConfig } from '@/hooks/useUsageData'
 
 function App() {
   const [activeTab, setActiveTab] = useState('dashboard')
   const { t } = useTranslation()
   useTheme()
   useConfigEvents()
+  usePrivacyMode()
+  const { data: config } = useConfig()
+  useRefreshHotkey(config?.refreshHotkey)
 
   useEffect(() => {
     let unlisten: (() => void) | undefined
@@ -13,6 +13,7 @@ import { useTranslation } from 'react-i18next'
 import { DailyBarChart } from '@/components/DailyBarChart'
 import { ModelIcon } from '@/components/icons/ModelIcon'
 import { useConfigEvents } from '@/hooks/useConfigEvents'
+import { usePrivacyMode } from '@/hooks/usePrivacyMode'
 import { useRefreshState } from '@/hooks/useRefreshState'
 import { useTheme } from '@/hooks/useTheme'
 import { useRefreshUsage, useUsageData } from '@/hooks/useUsageData'
@@ -87,6 +88,7 @@ export function Tray() {
   const queryClient = useQueryClient()
   useTheme()
   useConfigEvents()
+  usePrivacyMode()
   const { data: usage, isLoading, isFetching } = useUsageData()
   const refreshMutation = useRefreshUsage()
   const isGlobalRefreshing = useRefreshState()
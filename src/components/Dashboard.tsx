@@ -1,9 +1,13 @@
-import type { DailyUsage, ModelUsage } from '@/types'
+import type { ApiProvider, DailyUsage, ModelUsage, ProviderHistoryPoint } from '@/types'
 import { useQueryClient } from '@tanstack/react-query'
 import { listen } from '@tauri-apps/api/event'
 import {
   BarChart3,
   DollarSign,
+  Hourglass,
+  LineChart as LineChartIcon,
+  ListTree,
+  MessagesSquare,
   Moon,
   PieChart as PieChartIcon,
   RefreshCw,
@@ -27,12 +31,19 @@ import {
   YAxis,
 } from 'recharts'
 import { ContributionGraph } from '@/components/ContributionGraph'
+import { RefreshFooter } from '@/components/RefreshFooter'
 import { Button } from '@/components/ui/button'
 import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card'
+import { useClaudeSessions } from '@/hooks/useClaudeSessions'
+import { useDualSeries } from '@/hooks/useDualSeries'
 import { useRefreshState } from '@/hooks/useRefreshState'
+import { useModelInfo } from '@/hooks/useModelInfo'
+import { useProviderDaily, useProviderHistory, useProviders } from '@/hooks/useProviders'
+import { useSessionBlocks } from '@/hooks/useSessionBlocks'
 import { useTheme } from '@/hooks/useTheme'
-import { useRefreshUsage, useUsageData } from '@/hooks/useUsageData'
-import { cn, getDailyTotalTokens } from '@/lib/utils'
+import { useTopicsBreakdown } from '@/hooks/useTopicsBreakdown'
+import { useConfig, useRefreshUsage, useUsageData } from '@/hooks/useUsageData'
+import { billingCycleStart, cn, getDailyTotalTokens, getModelDisplayName, groupProviders, sortModels } from '@/lib/utils'
 import { formatCost, formatTokens } from '@/types'
 
 const COLORS = [
@@ -43,7 +54,7 @@ const COLORS = [
   'var(--color-chart-5)',
 ]
 
-type TimeRange = 7 | 30
+type TimeRange = 7 | 30 | 'cycle'
 
 function getTimeRangeButtonClass(isActive: boolean): string {
   return cn(
@@ -54,8 +65,220 @@ function getTimeRangeButtonClass(isActive: boolean): string {
   )
 }
 
+function ModelContextLabel({ model }: { model: string }) {
+  const { t } = useTranslation('dashboard')
+  const { data: info } = useModelInfo(model)
+
+  if (!info?.contextWindow)
+    return null
+
+  return (
+    <span
+      className="text-xs text-muted-foreground/70 shrink-0"
+      title={info.knowledgeCutoff ? t('chart.knowledgeCutoff', { date: info.knowledgeCutoff }) : undefined}
+    >
+      {t('chart.contextWindow', { tokens: formatTokens(info.contextWindow, false) })}
+    </span>
+  )
+}
+
+function formatMinutes(minutes: number): string {
+  const hours = Math.floor(minutes / 60)
+  return hours > 0 ? `${hours}h ${minutes % 60}m` : `${minutes}m`
+}
+
+function CurrentBlockCard() {
+  const { data: blocks } = useSessionBlocks()
+  const { t } = useTranslation('dashboard')
+  const block = blocks?.filter(b => b.isActive).pop()
+
+  if (!block)
+    return null
+
+  const total = block.elapsedMinutes + block.remainingMinutes
+  const percent = total > 0 ? Math.min(100, (block.elapsedMinutes / total) * 100) : 0
+
+  return (
+    <Card className="border-primary/20">
+      <CardHeader className="flex flex-row items-center justify-between pb-2">
+        <CardTitle className="text-sm font-medium text-muted-foreground">{t('block.title')}</CardTitle>
+        <Hourglass className="w-4 h-4 text-primary" />
+      </CardHeader>
+      <CardContent className="space-y-3">
+        <div className="flex items-baseline gap-2">
+          <span className="text-3xl font-bold text-primary">{formatCost(block.cost)}</span>
+          {block.projectedCost !== null && (
+            <span className="text-sm text-muted-foreground">
+              {t('block.projected', { cost: formatCost(block.projectedCost) })}
+            </span>
+          )}
+        </div>
+        <div className="h-2 rounded-full bg-muted overflow-hidden">
+          <div className="h-full bg-primary" style={{ width: `${percent}%` }} />
+        </div>
+        <div className="flex justify-between text-sm text-muted-foreground">
+          <span>{t('block.elapsed', { time: formatMinutes(block.elapsedMinutes) })}</span>
+          <span>{t('block.tokens', { tokens: formatTokens(block.totalTokens) })}</span>
+          <span>{t('block.remaining', { time: formatMinutes(block.remainingMinutes) })}</span>
+        </div>
+      </CardContent>
+    </Card>
+  )
+}
+
+const RECENT_SESSION_COUNT = 5
+
+function RecentSessionsCard() {
+  const { data: sessions } = useClaudeSessions()
+  const { t } = useTranslation('dashboard')
+
+  if (!sessions?.length)
+    return null
+
+  return (
+    <Card>
+      <CardHeader className="flex flex-row items-center justify-between pb-2">
+        <CardTitle className="text-sm font-medium text-muted-foreground">{t('sessions.title')}</CardTitle>
+        <MessagesSquare className="w-4 h-4 text-muted-foreground" />
+      </CardHeader>
+      <CardContent className="space-y-2 text-sm">
+        {sessions.slice(0, RECENT_SESSION_COUNT).map(session => (
+          <div key={session.sessionId} className="flex justify-between gap-4">
+            <span className="truncate" title={session.projectPath || session.sessionId}>
+              {session.projectPath || session.sessionId}
+            </span>
+            <span className="shrink-0 text-muted-foreground">
+              {t('sessions.detail', {
+                date: session.lastActivity,
+                tokens: formatTokens(session.totalTokens),
+                cost: formatCost(session.cost),
+              })}
+            </span>
+          </div>
+        ))}
+      </CardContent>
+    </Card>
+  )
+}
+
+const TOP_TOPIC_COUNT = 5
+
+function TopicsCard() {
+  const { data: topics } = useTopicsBreakdown()
+  const { t } = useTranslation('dashboard')
+
+  if (!topics?.length)
+    return null
+
+  return (
+    <Card>
+      <CardHeader className="flex flex-row items-center justify-between pb-2">
+        <CardTitle className="text-sm font-medium text-muted-foreground">{t('topics.title')}</CardTitle>
+        <ListTree className="w-4 h-4 text-muted-foreground" />
+      </CardHeader>
+      <CardContent className="space-y-2 text-sm">
+        {topics.slice(0, TOP_TOPIC_COUNT).map(topic => (
+          <div key={topic.title ?? ''} className="flex justify-between gap-4">
+            <span className={cn('truncate', !topic.title && 'text-muted-foreground')} title={topic.title ?? undefined}>
+              {topic.title ?? t('topics.untitled')}
+            </span>
+            <span className="shrink-0 text-muted-foreground">
+              {t('topics.detail', {
+                count: topic.sessions,
+                tokens: formatTokens(topic.totalTokens),
+                cost: formatCost(topic.cost),
+              })}
+            </span>
+          </div>
+        ))}
+      </CardContent>
+    </Card>
+  )
+}
+
+/** Remaining balance when the provider reports a quota, otherwise its spend. */
+function providerTrendValue(point: ProviderHistoryPoint): number | null {
+  if (point.used != null && point.total != null)
+    return point.total - point.used
+  return point.cost ?? point.used ?? null
+}
+
+function ProviderTrendChart({ provider, days }: { provider: ApiProvider, days: number }) {
+  const { data: history } = useProviderHistory(provider.id, days)
+  const { data: daily } = useProviderDaily(provider.id, days)
+  const { t } = useTranslation('dashboard')
+
+  // A series reported by the provider itself beats spend sampled at each refresh
+  const dailyPoints = (daily ?? [])
+    .filter(day => day.cost != null)
+    .map(day => ({ time: day.date, value: day.cost ?? null }))
+  const useDaily = dailyPoints.length >= 2
+  const points = useDaily
+    ? dailyPoints
+    : (history ?? [])
+        .map(point => ({ time: point.fetchedAt.slice(0, 16).replace('T', ' '), value: providerTrendValue(point) }))
+        .filter(point => point.value !== null)
+  if (points.length < 2)
+    return null
+
+  const hasQuota = history?.some(point => point.used != null && point.total != null)
+  let label = hasQuota ? t('providerTrends.balance') : t('providerTrends.spend')
+  if (useDaily)
+    label = t('providerTrends.dailySpend')
+
+  return (
+    <div className="space-y-1">
+      <div className="flex justify-between text-sm">
+        <span>{provider.name}</span>
+        <span className="text-muted-foreground">
+          {label}
+        </span>
+      </div>
+      <ResponsiveContainer width="100%" height={80}>
+        <ComposedChart data={points}>
+          <XAxis dataKey="time" hide />
+          <YAxis hide domain={['auto', 'auto']} />
+          <Tooltip formatter={value => formatCost(Number(value))} />
+          <Line type="monotone" dataKey="value" stroke="var(--color-chart-1)" strokeWidth={2} dot={false} />
+        </ComposedChart>
+      </ResponsiveContainer>
+    </div>
+  )
+}
+
+function ProviderTrendsCard({ days }: { days: number }) {
+  const { data: providers } = useProviders()
+  const { t } = useTranslation('dashboard')
+  const enabled = providers?.filter(p => p.enabled) ?? []
+
+  if (!enabled.length)
+    return null
+
+  return (
+    <Card>
+      <CardHeader className="flex flex-row items-center justify-between pb-2">
+        <CardTitle className="text-sm font-medium text-muted-foreground">{t('providerTrends.title')}</CardTitle>
+        <LineChartIcon className="w-4 h-4 text-muted-foreground" />
+      </CardHeader>
+      <CardContent className="space-y-4">
+        {groupProviders(enabled).map(section => (
+          <div key={section.group ?? ''} className="space-y-4">
+            {section.group && (
+              <p className="text-xs font-medium text-muted-foreground">{section.group}</p>
+            )}
+            {section.providers.map(provider => (
+              <ProviderTrendChart key={provider.id} provider={provider} days={days} />
+            ))}
+          </div>
+        ))}
+      </CardContent>
+    </Card>
+  )
+}
+
 export function Dashboard() {
   const { data: usage, isLoading, isFetching, error } = useUsageData()
+  const { data: config } = useConfig()
   const refreshMutation = useRefreshUsage()
   const isGlobalRefreshing = useRefreshState()
   const queryClient = useQueryClient()
@@ -88,9 +311,15 @@ export function Dashboard() {
       return null
 
     const now = new Date()
-    const cutoffDate = new Date(now)
-    cutoffDate.setDate(cutoffDate.getDate() - timeRange)
-    const cutoffStr = cutoffDate.toISOString().split('T')[0]
+    let cutoffStr: string
+    if (timeRange === 'cycle') {
+      cutoffStr = billingCycleStart(now, config?.billingCycleDay ?? 1)
+    }
+    else {
+      const cutoffDate = new Date(now)
+      cutoffDate.setDate(cutoffDate.getDate() - timeRange)
+      cutoffStr = cutoffDate.toISOString().split('T')[0]
+    }
 
     const dailyUsage = usage.dailyUsage.filter(d => d.date >= cutoffStr)
 
@@ -109,8 +338,7 @@ export function Dashboard() {
         }
       }
     }
-    const modelBreakdown = Array.from(modelMap.values())
-      .sort((a, b) => b.cost - a.cost)
+    const modelBreakdown = sortModels(Array.from(modelMap.values()), config?.modelSort)
 
     // Calculate totals for the period
     const periodTotals = dailyUsage.reduce(
@@ -133,7 +361,15 @@ export function Dashboard() {
       modelBreakdown,
       periodTotals,
     }
-  }, [usage, timeRange])
+  }, [usage, timeRange, config?.modelSort, config?.billingCycleDay])
+
+  const rangeDays = useMemo(() => {
+    if (timeRange !== 'cycle')
+      return timeRange
+    const start = new Date(`${billingCycleStart(new Date(), config?.billingCycleDay ?? 1)}T00:00:00`)
+    return Math.max(1, Math.ceil((Date.now() - start.getTime()) / 86_400_000))
+  }, [timeRange, config?.billingCycleDay])
+  const { data: series } = useDualSeries(rangeDays, usage?.meta?.fetchedAt)
 
   if (isLoading) {
     return (
@@ -189,12 +425,14 @@ export function Dashboard() {
   const isRefreshing = isGlobalRefreshing || refreshMutation.isPending || isFetching
   const { dailyUsage, modelBreakdown, periodTotals } = filteredData
 
-  // Prepare chart data with tokens in millions for better display
-  const chartData = dailyUsage.map((d: DailyUsage) => ({
-    date: d.date,
-    tokens: getDailyTotalTokens(d),
-    cost: d.cost,
-  }))
+  // Prefer the backend series, which is downsampled for long ranges
+  const chartData = series
+    ? series.dates.map((date, i) => ({ date, tokens: series.tokens[i], cost: series.cost[i] }))
+    : dailyUsage.map((d: DailyUsage) => ({
+        date: d.date,
+        tokens: getDailyTotalTokens(d),
+        cost: d.cost,
+      }))
 
   return (
     <div className={cn('relative p-6 space-y-6', 'select-none')}>
@@ -222,6 +460,15 @@ export function Dashboard() {
             >
               {t('timeRange.days30')}
             </button>
+            {config?.billingCycleDay && (
+              <button
+                type="button"
+                onClick={() => setTimeRange('cycle')}
+                className={getTimeRangeButtonClass(timeRange === 'cycle')}
+              >
+                {t('timeRange.cycle')}
+              </button>
+            )}
           </div>
 
           {/* Refresh Button */}
@@ -258,7 +505,9 @@ export function Dashboard() {
               {formatCost(periodTotals.cost)}
             </div>
             <p className="text-xs text-muted-foreground mt-1">
-              {t('stats.lastNDays', { days: timeRange })}
+              {timeRange === 'cycle'
+                ? t('stats.sinceCycleStart', { date: billingCycleStart(new Date(), config?.billingCycleDay ?? 1) })
+                : t('stats.lastNDays', { days: timeRange })}
             </p>
           </CardContent>
         </Card>
@@ -296,6 +545,11 @@ export function Dashboard() {
         </Card>
       </div>
 
+      <CurrentBlockCard />
+      <RecentSessionsCard />
+      <TopicsCard />
+      <ProviderTrendsCard days={rangeDays} />
+
       {/* Charts */}
       <div className="flex flex-wrap gap-4">
         {/* Usage Trend Chart */}
@@ -335,7 +589,7 @@ export function Dashboard() {
                 <YAxis
                   yAxisId="right"
                   orientation="right"
-                  tickFormatter={value => `$${value.toFixed(2)}`}
+                  tickFormatter={value => formatCost(value)}
                   fontSize={12}
                   stroke="var(--color-muted-foreground)"
                 />
@@ -349,9 +603,9 @@ export function Dashboard() {
                   formatter={(value, name) => {
                     if (name === 'tokens')
                       return [formatTokens(Number(value)), t('chart.tokens')]
-                    return [`$${Number(value).toFixed(4)}`, t('chart.cost')]
+                    return [formatCost(Number(value), 4), t('chart.cost')]
                   }}
-                  labelFormatter={label => `${t('chart.date')}: ${label}`}
+                  labelFormatter={label => `${series?.bucketDays === 7 ? t('chart.weekOf') : t('chart.date')}: ${label}`}
                   labelStyle={{ color: 'var(--color-popover-foreground)' }}
                 />
                 <Legend
@@ -429,7 +683,7 @@ export function Dashboard() {
                             itemStyle={{
                               color: 'var(--color-popover-foreground)',
                             }}
-                            formatter={value => [`$${Number(value).toFixed(4)}`, t('chart.cost')]}
+                            formatter={value => [formatCost(Number(value), 4), t('chart.cost')]}
                           />
                         </PieChart>
                       </ResponsiveContainer>
@@ -447,8 +701,9 @@ export function Dashboard() {
                               style={{ backgroundColor: COLORS[index % COLORS.length] }}
                             />
                             <span className="truncate text-muted-foreground" title={model.model}>
-                              {model.model}
+                              {getModelDisplayName(model.model, config?.modelAliases)}
                             </span>
+                            <ModelContextLabel model={model.model} />
                           </div>
                           <span className="font-medium ml-2 shrink-0">
                             {formatCost(model.cost)}
@@ -474,6 +729,8 @@ export function Dashboard() {
 
       {/* Yearly Activity */}
       <ContributionGraph data={usage.dailyUsage} />
+
+      <RefreshFooter meta={usage.meta} />
     </div>
   )
 }
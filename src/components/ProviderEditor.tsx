@@ -1,30 +1,41 @@
-import type { TestProviderResult } from '@/lib/api'
-import type { ApiProvider } from '@/types'
-import { Check, Play, Plus, Trash2, X } from 'lucide-react'
+import type { TestProviderDebug, TestProviderResult } from '@/lib/api'
+import type { ApiProvider, FetchStep, HttpMethod, OAuthGrant, PaginationStrategy, ProviderIssue, ProviderOAuth, ProviderPagination, ProviderRequest, ProviderRetry, ProviderStatus, ProviderUnit } from '@/types'
+import { AlertTriangle, Check, ChevronDown, ChevronUp, Play, Plus, RotateCcw, Trash2, X } from 'lucide-react'
 import { useState } from 'react'
 import { useTranslation } from 'react-i18next'
 import { Button } from '@/components/ui/button'
 import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card'
 import { Input } from '@/components/ui/input'
 import { Label } from '@/components/ui/label'
+import { Select } from '@/components/ui/select'
 import { Separator } from '@/components/ui/separator'
 import { Switch } from '@/components/ui/switch'
 import { Textarea } from '@/components/ui/textarea'
 import {
   useDeleteProvider,
+  useProviderIssues,
   useProviders,
+  useProviderStatus,
+  usePurgeTrash,
+  useReorderProviders,
+  useRestoreProvider,
   useSaveProvider,
+  useSetProvidersEnabled,
   useTestProvider,
+  useTrashedProviders,
 } from '@/hooks/useProviders'
+import { groupProviders } from '@/lib/utils'
 
 const TEMP_KEY_PREFIX = 'KEY_'
 
 interface EnvEditorProps {
   env: Record<string, string>
-  onChange: (env: Record<string, string>) => void
+  /** Expiry dates by key; when given, each entry gets a date input */
+  expiresAt?: Record<string, string>
+  onChange: (env: Record<string, string>, expiresAt?: Record<string, string>) => void
 }
 
-function EnvEditor({ env, onChange }: EnvEditorProps) {
+function EnvEditor({ env, expiresAt, onChange }: EnvEditorProps) {
   const { t } = useTranslation('providers')
   const [tempKeys, setTempKeys] = useState<Set<string>>(() => new Set())
   const entries = Object.entries(env)
@@ -43,11 +54,19 @@ function EnvEditor({ env, onChange }: EnvEditorProps) {
     onChange({ ...env, [newKey]: '' })
   }
 
+  const withoutExpiry = (key: string) => {
+    if (!expiresAt)
+      return undefined
+    const rest = { ...expiresAt }
+    delete rest[key]
+    return rest
+  }
+
   const handleRemove = (key: string) => {
     const newEnv = { ...env }
     delete newEnv[key]
     removeTempKey(key)
-    onChange(newEnv)
+    onChange(newEnv, withoutExpiry(key))
   }
 
   const handleKeyChange = (oldKey: string, newKey: string) => {
@@ -62,11 +81,18 @@ function EnvEditor({ env, onChange }: EnvEditorProps) {
     if (tempKeys.has(oldKey)) {
       removeTempKey(oldKey)
     }
-    onChange(newEnv)
+    const newExpiresAt = withoutExpiry(oldKey)
+    if (newExpiresAt && expiresAt?.[oldKey])
+      newExpiresAt[newKey] = expiresAt[oldKey]
+    onChange(newEnv, newExpiresAt)
   }
 
   const handleValueChange = (key: string, value: string) => {
-    onChange({ ...env, [key]: value })
+    onChange({ ...env, [key]: value }, expiresAt)
+  }
+
+  const handleExpiryChange = (key: string, date: string) => {
+    onChange(env, date ? { ...expiresAt, [key]: date } : withoutExpiry(key))
   }
 
   const hasTempKeys = tempKeys.size > 0
@@ -90,6 +116,17 @@ function EnvEditor({ env, onChange }: EnvEditorProps) {
               onChange={e => handleValueChange(key, e.target.value)}
               className="font-mono text-sm flex-1"
             />
+            {expiresAt && (
+              <Input
+                type="date"
+                value={expiresAt[key] ?? ''}
+                onChange={e => handleExpiryChange(key, e.target.value)}
+                title={t('editor.envExpiresAt')}
+                aria-label={t('editor.envExpiresAt')}
+                className="text-sm w-40 shrink-0"
+                disabled={isTempKey}
+              />
+            )}
             <Button
               variant="ghost"
               size="icon"
@@ -114,6 +151,105 @@ function EnvEditor({ env, onChange }: EnvEditorProps) {
   )
 }
 
+function FieldIssues({ issues, field }: { issues: ProviderIssue[], field: string }) {
+  return (
+    <>
+      {issues.filter(issue => issue.field === field).map(issue => (
+        <p key={issue.message} className="text-xs text-red-600">{issue.message}</p>
+      ))}
+    </>
+  )
+}
+
+interface DebugStageProps {
+  label: string
+  ms?: number | null
+  output?: string | null
+}
+
+function DebugStage({ label, ms, output }: DebugStageProps) {
+  const { t } = useTranslation('providers')
+  if (ms == null && !output)
+    return null
+  return (
+    <div className="space-y-1">
+      <p className="text-xs font-medium">
+        {label}
+        {ms != null && <span className="text-muted-foreground font-normal">{` · ${t('testResult.debug.ms', { ms })}`}</span>}
+      </p>
+      {output && (
+        <pre className="text-xs overflow-auto max-h-40 whitespace-pre-wrap break-all rounded bg-muted p-2">
+          {output}
+        </pre>
+      )}
+    </div>
+  )
+}
+
+function TestDebugDetails({ debug }: { debug: TestProviderDebug }) {
+  const { t } = useTranslation('providers')
+  return (
+    <details className="mt-2">
+      <summary className="text-xs cursor-pointer text-muted-foreground">
+        {t('testResult.debug.title')}
+      </summary>
+      <div className="space-y-2 mt-2">
+        <DebugStage label={t('testResult.debug.stdout')} ms={debug.fetchMs} output={debug.stdout} />
+        <DebugStage label={t('testResult.debug.stderr')} output={debug.stderr} />
+        <DebugStage label={t('testResult.debug.console')} output={debug.console?.join('\n')} />
+        <DebugStage label={t('testResult.debug.transform')} ms={debug.transformMs} output={debug.transformOutput} />
+        <DebugStage
+          label={t('testResult.debug.parsed')}
+          ms={debug.parseMs}
+          output={debug.parsed && JSON.stringify(debug.parsed, null, 2)}
+        />
+      </div>
+    </details>
+  )
+}
+
+const defaultRequest: ProviderRequest = {
+  method: 'GET',
+  url: '',
+  headers: {},
+}
+
+const defaultGraphqlRequest: ProviderRequest = {
+  method: 'POST',
+  url: '',
+  headers: {},
+  graphql: { query: '', variables: null },
+}
+
+type FetchMode = 'request' | 'graphql' | 'script'
+
+const PROVIDER_UNITS: ProviderUnit[] = ['usd', 'credits', 'requests', 'tokens']
+
+function fetchModeOf(request: ProviderRequest | null | undefined): FetchMode {
+  if (!request)
+    return 'script'
+  return request.graphql ? 'graphql' : 'request'
+}
+
+type TransformMode = 'script' | 'paths'
+
+const MAPPED_FIELDS = ['cost', 'tokens', 'used', 'total'] as const
+
+/** The provider as it is saved, without mapped fields left blank. */
+function withoutBlankPaths(provider: ApiProvider): ApiProvider {
+  const paths = Object.entries(provider.transformPaths ?? {}).filter(([, path]) => path.trim())
+  return { ...provider, transformPaths: Object.fromEntries(paths) }
+}
+
+const defaultOAuth: ProviderOAuth = {
+  grant: 'clientCredentials',
+  tokenUrl: '',
+  clientId: '',
+  clientSecret: '',
+  scopes: [],
+  refreshToken: '',
+}
+
 const defaultProvider: ApiProvider = {
   id: '',
   name: '',
@@ -121,24 +257,449 @@ const defaultProvider: ApiProvider = {
   fetchScript: '',
   transformScript: '',
   env: {},
+  showInTray: true,
+}
+
+const defaultRetry: ProviderRetry = {
+  attempts: 2,
+  backoffMs: 1000,
+}
+
+const defaultPagination: ProviderPagination = {
+  strategy: 'cursor',
+  itemsPath: 'data',
+  nextPath: 'next_cursor',
+  param: 'cursor',
+  maxPages: 10,
+}
+
+const MAX_PAGES = 50
+
+interface PaginationEditorProps {
+  pagination: ProviderPagination
+  onChange: (pagination: ProviderPagination) => void
+}
+
+function PaginationEditor({ pagination, onChange }: PaginationEditorProps) {
+  const { t } = useTranslation('providers')
+  const update = (updates: Partial<ProviderPagination>) => onChange({ ...pagination, ...updates })
+
+  return (
+    <div className="space-y-4">
+      <div className="grid gap-4 md:grid-cols-2">
+        <div className="space-y-2">
+          <Label htmlFor="paginationStrategy">{t('editor.pagination.strategy')}</Label>
+          <Select
+            id="paginationStrategy"
+            value={pagination.strategy}
+            onChange={e => update({ strategy: e.target.value as PaginationStrategy })}
+          >
+            <option value="cursor">{t('editor.pagination.strategies.cursor')}</option>
+            <option value="page">{t('editor.pagination.strategies.page')}</option>
+            <option value="nextUrl">{t('editor.pagination.strategies.nextUrl')}</option>
+          </Select>
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="paginationItemsPath">{t('editor.pagination.itemsPath')}</Label>
+          <Input
+            id="paginationItemsPath"
+            value={pagination.itemsPath}
+            onChange={e => update({ itemsPath: e.target.value })}
+            placeholder="data.items"
+            className="font-mono text-sm"
+          />
+        </div>
+        {pagination.strategy !== 'page' && (
+          <div className="space-y-2">
+            <Label htmlFor="paginationNextPath">
+              {pagination.strategy === 'cursor' ? t('editor.pagination.cursorPath') : t('editor.pagination.nextUrlPath')}
+            </Label>
+            <Input
+              id="paginationNextPath"
+              value={pagination.nextPath}
+              onChange={e => update({ nextPath: e.target.value })}
+              placeholder={pagination.strategy === 'cursor' ? 'meta.next_cursor' : 'links.next'}
+              className="font-mono text-sm"
+            />
+          </div>
+        )}
+        {pagination.strategy !== 'nextUrl' && (
+          <div className="space-y-2">
+            <Label htmlFor="paginationParam">{t('editor.pagination.param')}</Label>
+            <Input
+              id="paginationParam"
+              value={pagination.param}
+              onChange={e => update({ param: e.target.value })}
+              placeholder={pagination.strategy === 'cursor' ? 'cursor' : 'page'}
+              className="font-mono text-sm"
+            />
+          </div>
+        )}
+        <div className="space-y-2">
+          <Label htmlFor="paginationMaxPages">{t('editor.pagination.maxPages')}</Label>
+          <Input
+            id="paginationMaxPages"
+            type="number"
+            min={1}
+            max={MAX_PAGES}
+            value={pagination.maxPages}
+            onChange={e => update({ maxPages: Number(e.target.value) || defaultPagination.maxPages })}
+            className="w-24"
+          />
+        </div>
+      </div>
+      <p className="text-xs text-muted-foreground">
+        {t('editor.pagination.hint')}
+      </p>
+    </div>
+  )
+}
+
+interface RequestEditorProps {
+  request: ProviderRequest
+  onChange: (request: ProviderRequest) => void
+}
+
+function RequestEditor({ request, onChange }: RequestEditorProps) {
+  const { t } = useTranslation('providers')
+  const update = (updates: Partial<ProviderRequest>) => onChange({ ...request, ...updates })
+  const graphql = request.graphql
+
+  return (
+    <div className="space-y-4">
+      <div className="flex gap-2">
+        {!graphql && (
+          <Select
+            aria-label={t('editor.requestMethod')}
+            value={request.method}
+            onChange={(e) => {
+              const method = e.target.value as HttpMethod
+              update(method === 'GET' ? { method, body: null } : { method })
+            }}
+            className="w-28"
+          >
+            <option value="GET">GET</option>
+            <option value="POST">POST</option>
+          </Select>
+        )}
+        <Input
+          aria-label={t('editor.requestUrl')}
+          value={request.url}
+          onChange={e => update({ url: e.target.value })}
+          placeholder={t('editor.requestUrlPlaceholder')}
+          className="font-mono text-sm"
+        />
+      </div>
+      <div className="space-y-2">
+        <Label>{t('editor.requestHeaders')}</Label>
+        <EnvEditor env={request.headers} onChange={headers => update({ headers })} />
+      </div>
+      {graphql && (
+        <>
+          <div className="space-y-2">
+            <Label htmlFor="graphqlQuery">{t('editor.graphql.query')}</Label>
+            <Textarea
+              id="graphqlQuery"
+              value={graphql.query}
+              onChange={e => update({ graphql: { ...graphql, query: e.target.value } })}
+              placeholder={t('editor.graphql.queryPlaceholder')}
+              className="font-mono text-sm"
+              rows={5}
+            />
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="graphqlVariables">{t('editor.graphql.variables')}</Label>
+            <Textarea
+              id="graphqlVariables"
+              value={graphql.variables ?? ''}
+              onChange={e =>
+                update({ graphql: { ...graphql, variables: e.target.value || null } })}
+              placeholder={t('editor.graphql.variablesPlaceholder')}
+              className="font-mono text-sm"
+              rows={3}
+            />
+            <p className="text-xs text-muted-foreground">
+              {t('editor.graphql.hint')}
+            </p>
+          </div>
+        </>
+      )}
+      {!graphql && request.method === 'POST' && (
+        <div className="space-y-2">
+          <Label htmlFor="requestBody">{t('editor.requestBody')}</Label>
+          <Textarea
+            id="requestBody"
+            value={request.body ?? ''}
+            onChange={e => update({ body: e.target.value || null })}
+            className="font-mono text-sm"
+            rows={3}
+          />
+        </div>
+      )}
+      <p className="text-xs text-muted-foreground">
+        {t('editor.fetchScriptHint')}
+      </p>
+      <div className="flex items-center justify-between">
+        <Label>{t('editor.pagination.enabled')}</Label>
+        <Switch
+          checked={!!request.pagination}
+          onCheckedChange={checked => update({ pagination: checked ? defaultPagination : null })}
+        />
+      </div>
+      {request.pagination && (
+        <PaginationEditor
+          pagination={request.pagination}
+          onChange={pagination => update({ pagination })}
+        />
+      )}
+    </div>
+  )
+}
+
+function ProviderHealthBadge({ status }: { status?: ProviderStatus }) {
+  const { t } = useTranslation('providers')
+  if (status?.level !== 'degraded' && status?.level !== 'failing')
+    return null
+
+  const label = t(`list.health.${status.level}`, { count: status.consecutiveFailures })
+  return (
+    <span title={status.lastError ? `${label}: ${status.lastError}` : label} aria-label={label}>
+      <AlertTriangle
+        className={`w-3 h-3 ${status.level === 'failing' ? 'text-red-500' : 'text-amber-500'}`}
+      />
+    </span>
+  )
+}
+
+const MAX_FETCH_STEPS = 5
+
+interface StepsEditorProps {
+  steps: FetchStep[]
+  onChange: (steps: FetchStep[]) => void
+}
+
+interface PathsEditorProps {
+  paths: Record<string, string>
+  onChange: (paths: Record<string, string>) => void
+}
+
+function PathsEditor({ paths, onChange }: PathsEditorProps) {
+  const { t } = useTranslation('providers')
+
+  return (
+    <div className="space-y-2">
+      {MAPPED_FIELDS.map(field => (
+        <div key={field} className="flex items-center gap-2">
+          <Label htmlFor={`transformPath-${field}`} className="w-16 font-mono text-sm">{field}</Label>
+          <Input
+            id={`transformPath-${field}`}
+            value={paths[field] ?? ''}
+            onChange={e => onChange({ ...paths, [field]: e.target.value })}
+            placeholder={t(`editor.transformPathPlaceholders.${field}`)}
+            className="font-mono text-sm"
+          />
+        </div>
+      ))}
+      <p className="text-xs text-muted-foreground">
+        {t('editor.transformPathsHint')}
+      </p>
+    </div>
+  )
+}
+
+function StepsEditor({ steps, onChange }: StepsEditorProps) {
+  const { t } = useTranslation('providers')
+  const updateStep = (index: number, updates: Partial<FetchStep>) =>
+    onChange(steps.map((step, i) => (i === index ? { ...step, ...updates } : step)))
+
+  return (
+    <div className="space-y-4">
+      {steps.map((step, index) => (
+        // eslint-disable-next-line react/no-array-index-key -- steps have no identity besides their position
+        <div key={index} className="space-y-4 rounded-md border p-3">
+          <div className="flex items-center justify-between">
+            <Label>{t('editor.steps.step', { number: index + 1 })}</Label>
+            <Button
+              variant="ghost"
+              size="icon"
+              aria-label={t('editor.steps.remove')}
+              onClick={() => onChange(steps.filter((_, i) => i !== index))}
+            >
+              <X className="w-4 h-4" />
+            </Button>
+          </div>
+          <RequestEditor request={step.request} onChange={request => updateStep(index, { request })} />
+          <div className="space-y-2">
+            <Label>{t('editor.steps.extract')}</Label>
+            <EnvEditor env={step.extract} onChange={extract => updateStep(index, { extract })} />
+          </div>
+        </div>
+      ))}
+      <Button
+        variant="outline"
+        size="sm"
+        onClick={() => onChange([...steps, { request: { method: 'POST', url: '', headers: {} }, extract: {} }])}
+        disabled={steps.length >= MAX_FETCH_STEPS}
+      >
+        <Plus className="w-4 h-4 mr-1" />
+        {t('editor.steps.add')}
+      </Button>
+      <p className="text-xs text-muted-foreground">
+        {t('editor.steps.hint')}
+      </p>
+    </div>
+  )
+}
+
+interface OAuthEditorProps {
+  oauth: ProviderOAuth
+  onChange: (oauth: ProviderOAuth) => void
+}
+
+function OAuthEditor({ oauth, onChange }: OAuthEditorProps) {
+  const { t } = useTranslation('providers')
+  const update = (updates: Partial<ProviderOAuth>) => onChange({ ...oauth, ...updates })
+
+  return (
+    <div className="space-y-4">
+      <div className="grid gap-4 md:grid-cols-2">
+        <div className="space-y-2">
+          <Label htmlFor="oauthGrant">{t('editor.oauth.grant')}</Label>
+          <Select
+            id="oauthGrant"
+            value={oauth.grant}
+            onChange={e => update({ grant: e.target.value as OAuthGrant })}
+          >
+            <option value="clientCredentials">{t('editor.oauth.grants.clientCredentials')}</option>
+            <option value="refreshToken">{t('editor.oauth.grants.refreshToken')}</option>
+          </Select>
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="oauthTokenUrl">{t('editor.oauth.tokenUrl')}</Label>
+          <Input
+            id="oauthTokenUrl"
+            value={oauth.tokenUrl}
+            onChange={e => update({ tokenUrl: e.target.value })}
+            placeholder="https://auth.example.com/oauth/token"
+            className="font-mono text-sm"
+          />
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="oauthClientId">{t('editor.oauth.clientId')}</Label>
+          <Input
+            id="oauthClientId"
+            value={oauth.clientId}
+            onChange={e => update({ clientId: e.target.value })}
+            className="font-mono text-sm"
+          />
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="oauthClientSecret">{t('editor.oauth.clientSecret')}</Label>
+          <Input
+            id="oauthClientSecret"
+            type="password"
+            value={oauth.clientSecret}
+            onChange={e => update({ clientSecret: e.target.value })}
+            placeholder={t('editor.oauth.clientSecretPlaceholder')}
+            className="font-mono text-sm"
+          />
+        </div>
+      </div>
+      <div className="space-y-2">
+        <Label htmlFor="oauthScopes">{t('editor.oauth.scopes')}</Label>
+        <Input
+          id="oauthScopes"
+          value={oauth.scopes.join(' ')}
+          onChange={e => update({ scopes: e.target.value.split(/\s+/).filter(Boolean) })}
+          placeholder="usage:read"
+          className="font-mono text-sm"
+        />
+      </div>
+      {oauth.grant === 'refreshToken' && (
+        <div className="space-y-2">
+          <Label htmlFor="oauthRefreshToken">{t('editor.oauth.refreshToken')}</Label>
+          <Input
+            id="oauthRefreshToken"
+            type="password"
+            value={oauth.refreshToken}
+            onChange={e => update({ refreshToken: e.target.value })}
+            placeholder={t('editor.oauth.refreshTokenPlaceholder')}
+            className="font-mono text-sm"
+          />
+        </div>
+      )}
+      <p className="text-xs text-muted-foreground">
+        {t('editor.oauth.hint')}
+      </p>
+    </div>
+  )
+}
+
+function TrashList() {
+  const { data: trashed = [] } = useTrashedProviders()
+  const restoreMutation = useRestoreProvider()
+  const purgeMutation = usePurgeTrash()
+  const { t } = useTranslation('providers')
+
+  if (trashed.length === 0)
+    return null
+
+  return (
+    <div className="space-y-2 pt-2">
+      <Separator />
+      <div className="flex items-center justify-between">
+        <span className="text-xs font-medium text-muted-foreground">{t('trash.title')}</span>
+        <Button
+          variant="ghost"
+          size="sm"
+          onClick={() => purgeMutation.mutate()}
+          disabled={purgeMutation.isPending}
+        >
+          {t('trash.empty')}
+        </Button>
+      </div>
+      {trashed.map(({ provider, deletedAt }) => (
+        <div key={`${provider.id}-${deletedAt}`} className="flex items-center justify-between px-3 text-sm">
+          <span className="truncate text-muted-foreground" title={new Date(deletedAt).toLocaleString()}>
+            {provider.name}
+          </span>
+          <Button
+            variant="ghost"
+            size="sm"
+            aria-label={t('trash.restore')}
+            onClick={() => restoreMutation.mutate(provider.id)}
+            disabled={restoreMutation.isPending}
+          >
+            <RotateCcw className="w-3 h-3" />
+          </Button>
+        </div>
+      ))}
+    </div>
+  )
 }
 
 export function ProviderEditor() {
   const { data: providers = [], isLoading } = useProviders()
+  const { data: statuses = [] } = useProviderStatus()
   const saveMutation = useSaveProvider()
   const deleteMutation = useDeleteProvider()
+  const setEnabledMutation = useSetProvidersEnabled()
+  const reorderMutation = useReorderProviders()
   const testMutation = useTestProvider()
   const { t } = useTranslation('providers')
 
   const [selectedId, setSelectedId] = useState<string | null>(null)
   const [editingProvider, setEditingProvider] = useState<ApiProvider | null>(null)
   const [testResult, setTestResult] = useState<TestProviderResult | null>(null)
+  const { data: issues = [] } = useProviderIssues(editingProvider && withoutBlankPaths(editingProvider))
 
   const handleNew = () => {
     const newProvider: ApiProvider = {
       ...defaultProvider,
       id: `provider-${Date.now()}`,
       name: 'New Provider',
+      // Listed after the existing providers
+      sortOrder: Math.max(0, ...providers.map(provider => (provider.sortOrder ?? 0) + 1)),
     }
     setEditingProvider(newProvider)
     setSelectedId(null)
@@ -159,7 +720,7 @@ export function ProviderEditor() {
     if (hasTempKeys) {
       return
     }
-    saveMutation.mutate(editingProvider, {
+    saveMutation.mutate(withoutBlankPaths(editingProvider), {
       onSuccess: () => {
         setSelectedId(editingProvider.id)
       },
@@ -181,13 +742,23 @@ export function ProviderEditor() {
     if (!editingProvider)
       return
     setTestResult(null)
-    testMutation.mutate(editingProvider, {
+    testMutation.mutate(withoutBlankPaths(editingProvider), {
       onSuccess: (result) => {
         setTestResult(result)
       },
     })
   }
 
+  const sections = groupProviders(providers)
+
+  /** Swaps a provider with its neighbour in the same section. */
+  const handleMove = (sectionIndex: number, index: number, offset: -1 | 1) => {
+    const reordered = sections.map(section => section.providers.map(provider => provider.id))
+    const ids = reordered[sectionIndex]
+    ;[ids[index], ids[index + offset]] = [ids[index + offset], ids[index]]
+    reorderMutation.mutate(reordered.flat())
+  }
+
   const updateProvider = (updates: Partial<ApiProvider>) => {
     if (!editingProvider)
       return
@@ -210,33 +781,77 @@ export function ProviderEditor() {
 
       <div className="grid gap-6 md:grid-cols-[250px_1fr]">
         <Card>
-          <CardHeader>
+          <CardHeader className="flex flex-row items-center justify-between space-y-0">
             <CardTitle className="text-sm">{t('list.title')}</CardTitle>
+            {providers.some(provider => provider.enabled) && (
+              <Button
+                variant="ghost"
+                size="sm"
+                onClick={() => setEnabledMutation.mutate({ ids: null, enabled: false })}
+                disabled={setEnabledMutation.isPending}
+              >
+                {t('list.disableAll')}
+              </Button>
+            )}
           </CardHeader>
           <CardContent className="space-y-2">
-            {providers.map(provider => (
-              <button
-                key={provider.id}
-                onClick={() => handleSelect(provider)}
-                className={`w-full text-left px-3 py-2 rounded-md text-sm transition-colors ${
-                  selectedId === provider.id
-                    ? 'bg-primary text-primary-foreground'
-                    : 'hover:bg-muted'
-                }`}
-              >
-                <div className="flex items-center justify-between">
-                  <span>{provider.name}</span>
-                  {provider.enabled
-                    ? <Check className="w-3 h-3" />
-                    : <X className="w-3 h-3 opacity-50" />}
-                </div>
-              </button>
+            {sections.map((section, sectionIndex) => (
+              <div key={section.group ?? ''} className="space-y-1">
+                {section.group && (
+                  <p className="px-3 pt-2 text-xs font-medium text-muted-foreground">{section.group}</p>
+                )}
+                {section.providers.map((provider, index) => (
+                  <div key={provider.id} className="flex items-center gap-1">
+                    <button
+                      onClick={() => handleSelect(provider)}
+                      className={`flex-1 text-left px-3 py-2 rounded-md text-sm transition-colors ${
+                        selectedId === provider.id
+                          ? 'bg-primary text-primary-foreground'
+                          : 'hover:bg-muted'
+                      }`}
+                    >
+                      <div className="flex items-center justify-between">
+                        <span className="flex items-center gap-1">
+                          {provider.name}
+                          <ProviderHealthBadge status={statuses.find(status => status.id === provider.id)} />
+                        </span>
+                        {provider.enabled
+                          ? <Check className="w-3 h-3" />
+                          : <X className="w-3 h-3 opacity-50" />}
+                      </div>
+                    </button>
+                    <div className="flex flex-col">
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        className="h-4 px-1"
+                        aria-label={t('list.moveUp')}
+                        onClick={() => handleMove(sectionIndex, index, -1)}
+                        disabled={index === 0 || reorderMutation.isPending}
+                      >
+                        <ChevronUp className="w-3 h-3" />
+                      </Button>
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        className="h-4 px-1"
+                        aria-label={t('list.moveDown')}
+                        onClick={() => handleMove(sectionIndex, index, 1)}
+                        disabled={index === section.providers.length - 1 || reorderMutation.isPending}
+                      >
+                        <ChevronDown className="w-3 h-3" />
+                      </Button>
+                    </div>
+                  </div>
+                ))}
+              </div>
             ))}
             {providers.length === 0 && (
               <p className="text-sm text-muted-foreground text-center py-4">
                 {t('noProviders')}
               </p>
             )}
+            <TrashList />
           </CardContent>
         </Card>
 
@@ -248,6 +863,11 @@ export function ProviderEditor() {
               </CardTitle>
             </CardHeader>
             <CardContent className="space-y-4">
+              {editingProvider.lastError && (
+                <p className="text-sm text-destructive">
+                  {t('editor.lastError', { error: editingProvider.lastError })}
+                </p>
+              )}
               <div className="grid gap-4 md:grid-cols-2">
                 <div className="space-y-2">
                   <Label htmlFor="name">{t('editor.name')}</Label>
@@ -256,6 +876,7 @@ export function ProviderEditor() {
                     value={editingProvider.name}
                     onChange={e => updateProvider({ name: e.target.value })}
                   />
+                  <FieldIssues issues={issues} field="id" />
                 </div>
                 <div className="flex items-center justify-between">
                   <Label>{t('editor.enabled')}</Label>
@@ -267,44 +888,238 @@ export function ProviderEditor() {
                 </div>
               </div>
 
-              <Separator />
+              <div className="grid gap-4 md:grid-cols-2">
+                <div className="space-y-2">
+                  <Label htmlFor="group">{t('editor.group')}</Label>
+                  <Input
+                    id="group"
+                    value={editingProvider.group ?? ''}
+                    onChange={e => updateProvider({ group: e.target.value || null })}
+                    placeholder={t('editor.groupPlaceholder')}
+                    className="w-48"
+                  />
+                </div>
+                <div className="flex items-center justify-between">
+                  <div className="space-y-0.5">
+                    <Label>{t('editor.showInTray')}</Label>
+                    <p className="text-sm text-muted-foreground">{t('editor.showInTrayHint')}</p>
+                  </div>
+                  <Switch
+                    checked={editingProvider.showInTray ?? true}
+                    onCheckedChange={checked =>
+                      updateProvider({ showInTray: checked })}
+                  />
+                </div>
+              </div>
 
               <div className="space-y-2">
-                <Label htmlFor="fetchScript">{t('editor.fetchScript')}</Label>
-                <Textarea
-                  id="fetchScript"
-                  value={editingProvider.fetchScript}
-                  onChange={e => updateProvider({ fetchScript: e.target.value })}
-                  placeholder={t('editor.fetchScriptPlaceholder')}
-                  className="font-mono text-sm"
-                  rows={3}
+                <Label htmlFor="currency">{t('editor.currency')}</Label>
+                <Input
+                  id="currency"
+                  value={editingProvider.currency ?? ''}
+                  onChange={e =>
+                    updateProvider({ currency: e.target.value.trim().toUpperCase() || undefined })}
+                  placeholder="USD"
+                  maxLength={3}
+                  className="w-24 font-mono"
                 />
                 <p className="text-xs text-muted-foreground">
-                  {t('editor.fetchScriptHint')}
+                  {t('editor.currencyHint')}
                 </p>
+                <FieldIssues issues={issues} field="currency" />
               </div>
 
+              <div className="grid gap-4 md:grid-cols-2">
+                <div className="space-y-2">
+                  <Label htmlFor="unit">{t('editor.unit')}</Label>
+                  <Select
+                    id="unit"
+                    value={editingProvider.unit ?? ''}
+                    onChange={e =>
+                      updateProvider({ unit: (e.target.value || null) as ProviderUnit | null })}
+                  >
+                    <option value="">{t('editor.units.none')}</option>
+                    {PROVIDER_UNITS.map(unit => (
+                      <option key={unit} value={unit}>{t(`editor.units.${unit}`)}</option>
+                    ))}
+                  </Select>
+                </div>
+                <div className="space-y-2">
+                  <Label htmlFor="unitLabel">{t('editor.unitLabel')}</Label>
+                  <Input
+                    id="unitLabel"
+                    value={editingProvider.unitLabel ?? ''}
+                    onChange={e => updateProvider({ unitLabel: e.target.value || null })}
+                    placeholder={editingProvider.unit && editingProvider.unit !== 'usd' ? editingProvider.unit : ''}
+                    disabled={!editingProvider.unit}
+                  />
+                </div>
+              </div>
+              <p className="text-xs text-muted-foreground">
+                {t('editor.unitHint')}
+              </p>
+              <FieldIssues issues={issues} field="unitLabel" />
+
+              <div className="grid gap-4 md:grid-cols-2">
+                <div className="space-y-2">
+                  <Label htmlFor="retryAttempts">{t('editor.retryAttempts')}</Label>
+                  <Input
+                    id="retryAttempts"
+                    type="number"
+                    min={0}
+                    max={5}
+                    value={(editingProvider.retry ?? defaultRetry).attempts}
+                    onChange={(e) => {
+                      const attempts = Math.min(5, Math.max(0, Math.floor(Number(e.target.value))))
+                      updateProvider({ retry: { ...(editingProvider.retry ?? defaultRetry), attempts } })
+                    }}
+                    className="w-24"
+                  />
+                </div>
+                <div className="space-y-2">
+                  <Label htmlFor="retryBackoff">{t('editor.retryBackoff')}</Label>
+                  <Input
+                    id="retryBackoff"
+                    type="number"
+                    min={100}
+                    max={30000}
+                    step={100}
+                    value={(editingProvider.retry ?? defaultRetry).backoffMs}
+                    onChange={(e) => {
+                      const backoffMs = Math.min(30000, Math.max(100, Math.floor(Number(e.target.value))))
+                      updateProvider({ retry: { ...(editingProvider.retry ?? defaultRetry), backoffMs } })
+                    }}
+                    className="w-32"
+                  />
+                </div>
+              </div>
+              <p className="text-xs text-muted-foreground">
+                {t('editor.retryHint')}
+              </p>
+              <FieldIssues issues={issues} field="retry" />
+
+              <Separator />
+
               <div className="space-y-2">
-                <Label htmlFor="transformScript">
-                  {t('editor.transformScript')}
-                </Label>
-                <Textarea
-                  id="transformScript"
-                  value={editingProvider.transformScript}
-                  onChange={e =>
-                    updateProvider({ transformScript: e.target.value })}
-                  placeholder={t('editor.transformScriptPlaceholder')}
-                  className="font-mono text-sm"
-                  rows={4}
+                <Label>{t('editor.steps.title')}</Label>
+                <StepsEditor
+                  steps={editingProvider.steps ?? []}
+                  onChange={steps => updateProvider({ steps })}
+                />
+              </div>
+
+              <div className="space-y-2">
+                <Label htmlFor="fetchMode">{t('editor.fetchMode')}</Label>
+                <Select
+                  id="fetchMode"
+                  value={fetchModeOf(editingProvider.request)}
+                  onChange={(e) => {
+                    const mode = e.target.value as FetchMode
+                    const requests = { request: defaultRequest, graphql: defaultGraphqlRequest }
+                    updateProvider({ request: mode === 'script' ? null : requests[mode] })
+                  }}
+                >
+                  <option value="request">{t('editor.fetchModes.request')}</option>
+                  <option value="graphql">{t('editor.fetchModes.graphql')}</option>
+                  <option value="script">{t('editor.fetchModes.script')}</option>
+                </Select>
+              </div>
+
+              {editingProvider.request
+                ? (
+                    <RequestEditor
+                      request={editingProvider.request}
+                      onChange={request => updateProvider({ request })}
+                    />
+                  )
+                : (
+                    <div className="space-y-2">
+                      <Label htmlFor="fetchScript">{t('editor.fetchScript')}</Label>
+                      <Textarea
+                        id="fetchScript"
+                        value={editingProvider.fetchScript}
+                        onChange={e => updateProvider({ fetchScript: e.target.value })}
+                        placeholder={t('editor.fetchScriptPlaceholder')}
+                        className="font-mono text-sm"
+                        rows={3}
+                      />
+                      <p className="text-xs text-muted-foreground">
+                        {t('editor.fetchScriptHint')}
+                      </p>
+                    </div>
+                  )}
+              <FieldIssues issues={issues} field="fetch" />
+
+              <div className="flex items-center justify-between">
+                <Label>{t('editor.oauth.enabled')}</Label>
+                <Switch
+                  checked={!!editingProvider.oauth}
+                  onCheckedChange={checked =>
+                    updateProvider({ oauth: checked ? defaultOAuth : null })}
                 />
               </div>
 
+              {editingProvider.oauth && (
+                <OAuthEditor
+                  oauth={editingProvider.oauth}
+                  onChange={oauth => updateProvider({ oauth })}
+                />
+              )}
+
+              <div className="space-y-2">
+                <Label htmlFor="transformMode">{t('editor.transformMode')}</Label>
+                <Select
+                  id="transformMode"
+                  value={Object.keys(editingProvider.transformPaths ?? {}).length ? 'paths' : 'script'}
+                  onChange={(e) => {
+                    const mode = e.target.value as TransformMode
+                    updateProvider({ transformPaths: mode === 'paths' ? { cost: '' } : {} })
+                  }}
+                >
+                  <option value="script">{t('editor.transformModes.script')}</option>
+                  <option value="paths">{t('editor.transformModes.paths')}</option>
+                </Select>
+              </div>
+
+              {Object.keys(editingProvider.transformPaths ?? {}).length
+                ? (
+                    <PathsEditor
+                      paths={editingProvider.transformPaths ?? {}}
+                      onChange={transformPaths => updateProvider({ transformPaths })}
+                    />
+                  )
+                : (
+                    <div className="space-y-2">
+                      <Label htmlFor="transformScript">
+                        {t('editor.transformScript')}
+                      </Label>
+                      <Textarea
+                        id="transformScript"
+                        value={editingProvider.transformScript}
+                        onChange={e =>
+                          updateProvider({ transformScript: e.target.value })}
+                        placeholder={t('editor.transformScriptPlaceholder')}
+                        className="font-mono text-sm"
+                        rows={4}
+                      />
+                      <p className="text-xs text-muted-foreground">
+                        {t('editor.transformHelpers')}
+                      </p>
+                    </div>
+                  )}
+              <FieldIssues issues={issues} field="transform" />
+
               <div className="space-y-2">
                 <Label>{t('editor.envVariables')}</Label>
                 <EnvEditor
                   env={editingProvider.env}
-                  onChange={env => updateProvider({ env })}
+                  expiresAt={editingProvider.envExpiresAt ?? {}}
+                  onChange={(env, envExpiresAt) => updateProvider({ env, envExpiresAt })}
                 />
+                <p className="text-xs text-muted-foreground">
+                  {t('editor.envExpiresAtDescription')}
+                </p>
+                <FieldIssues issues={issues} field="env" />
               </div>
 
               <Separator />
@@ -356,6 +1171,7 @@ export function ProviderEditor() {
                       {JSON.stringify(testResult.data, null, 2)}
                     </pre>
                   )}
+                  {testResult.debug && <TestDebugDetails debug={testResult.debug} />}
                 </div>
               )}
             </CardContent>
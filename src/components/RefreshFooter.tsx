@@ -0,0 +1,47 @@
+import type { RefreshMeta } from '@/types'
+import { useEffect, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+
+interface RefreshFooterProps {
+  meta?: RefreshMeta
+}
+
+const MINUTE_MS = 60_000
+
+export function RefreshFooter({ meta }: RefreshFooterProps) {
+  const { t } = useTranslation('dashboard')
+  const [now, setNow] = useState(() => Date.now())
+
+  useEffect(() => {
+    const timer = setInterval(() => setNow(Date.now()), MINUTE_MS)
+    return () => clearInterval(timer)
+  }, [])
+
+  if (!meta?.fetchedAt)
+    return null
+
+  const minutes = Math.max(0, Math.floor((now - new Date(meta.fetchedAt).getTime()) / MINUTE_MS))
+  const ago = minutes < 1
+    ? t('footer.justNow')
+    : minutes < 60
+      ? t('footer.minutesAgo', { count: minutes })
+      : t('footer.hoursAgo', { count: Math.floor(minutes / 60) })
+  const readers = Object.values(meta.sourceVersions ?? {})
+
+  return (
+    <div className="text-xs text-muted-foreground text-center space-y-1">
+      <div>
+        {t('footer.updated', { ago, seconds: (meta.durationMs / 1000).toFixed(1) })}
+        {readers.length > 0 && ` ${t('footer.via', { sources: readers.join(', ') })}`}
+      </div>
+      {meta.managedByAgent && (
+        <div>{t('footer.managedByAgent')}</div>
+      )}
+      {meta.lastError && (
+        <div className="text-destructive">
+          {t('footer.lastError', { error: meta.lastError })}
+        </div>
+      )}
+    </div>
+  )
+}
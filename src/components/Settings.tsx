@@ -1,4 +1,6 @@
-import type { AppConfig } from '@/types'
+import type { AppConfig, AppLockConfig, BalanceDrainConfig, ChartAggregation, ColorScheme, Credential, EmailDigestConfig, GuardBudgetTier, GuardConfig, IntegrationsConfig, KeyExpiryConfig, LimitsConfig, LimitUnit, ModelSortKey, NotionIntegrationConfig, PrivacyStyle, PushNotificationConfig, PushService, RefreshHook, RemoteSnapshotConfig, TimeoutsConfig, TrendBaseline } from '@/types'
+import { useQueryClient } from '@tanstack/react-query'
+import { Plus, X } from 'lucide-react'
 import * as React from 'react'
 import { useState } from 'react'
 import { useTranslation } from 'react-i18next'
@@ -9,9 +11,15 @@ import { Label } from '@/components/ui/label'
 import { Select } from '@/components/ui/select'
 import { Separator } from '@/components/ui/separator'
 import { Switch } from '@/components/ui/switch'
+import { useConfigSchema } from '@/hooks/useConfigSchema'
+import { useClearCredential, useSetCredential, useStoredCredentials } from '@/hooks/useCredentials'
+import { useDiagnostics } from '@/hooks/useDiagnostics'
 import { useLanguage } from '@/hooks/useLanguage'
+import { useProfiles, useSwitchProfile } from '@/hooks/useProfiles'
+import { useClearSnapshotPassphrase, useHasSnapshotPassphrase, useSetSnapshotPassphrase } from '@/hooks/useSnapshotPassphrase'
+import { useTrayFormatPreview } from '@/hooks/useTrayFormatPreview'
 import { useConfig, useSaveConfig } from '@/hooks/useUsageData'
-import { setLaunchAtLogin } from '@/lib/api'
+import { generateGuardToken, importUsageDump, pushSnapshotNow, sendTestDigest, sendTestPush, setLaunchAtLogin, setSmtpPassword } from '@/lib/api'
 
 interface NumberInputHandlers {
   onChange: (e: React.ChangeEvent<HTMLInputElement>) => void
@@ -42,11 +50,647 @@ function createNumberInputHandlers(
   }
 }
 
+function TrayFormatFeedback({ format }: { format: string }) {
+  const { t } = useTranslation('settings')
+  const { data } = useTrayFormatPreview(format)
+
+  if (!data) {
+    return null
+  }
+
+  return (
+    <>
+      <p className="text-sm">
+        {t('menuBar.formatPreview')}
+        {' '}
+        <span className="font-mono">{data.preview}</span>
+      </p>
+      {data.unknownVariables.length > 0 && (
+        <p className="text-sm text-destructive">
+          {t('menuBar.formatUnknown', { variables: data.unknownVariables.join(', ') })}
+        </p>
+      )}
+    </>
+  )
+}
+
+interface ModelAliasEditorProps {
+  aliases: Record<string, string>
+  onChange: (aliases: Record<string, string>) => void
+}
+
+function ModelAliasEditor({ aliases, onChange }: ModelAliasEditorProps) {
+  const { t } = useTranslation('settings')
+  const [newModel, setNewModel] = useState('')
+  const [newAlias, setNewAlias] = useState('')
+
+  const handleAdd = () => {
+    const model = newModel.trim()
+    const alias = newAlias.trim()
+    if (!model || !alias)
+      return
+    onChange({ ...aliases, [model]: alias })
+    setNewModel('')
+    setNewAlias('')
+  }
+
+  const handleRemove = (model: string) => {
+    const next = { ...aliases }
+    delete next[model]
+    onChange(next)
+  }
+
+  return (
+    <div className="space-y-2">
+      {Object.entries(aliases).map(([model, alias]) => (
+        <div key={model} className="flex gap-2 items-center">
+          <span className="font-mono text-sm flex-1 truncate" title={model}>{model}</span>
+          <span className="text-muted-foreground">→</span>
+          <Input
+            value={alias}
+            onChange={e => onChange({ ...aliases, [model]: e.target.value })}
+            className="text-sm flex-1"
+          />
+          <Button
+            variant="ghost"
+            size="icon"
+            onClick={() => handleRemove(model)}
+            className="shrink-0"
+          >
+            <X className="w-4 h-4" />
+          </Button>
+        </div>
+      ))}
+      <div className="flex gap-2 items-center">
+        <Input
+          placeholder={t('modelAliases.modelPlaceholder')}
+          value={newModel}
+          onChange={e => setNewModel(e.target.value)}
+          className="font-mono text-sm flex-1"
+        />
+        <span className="text-muted-foreground">→</span>
+        <Input
+          placeholder={t('modelAliases.aliasPlaceholder')}
+          value={newAlias}
+          onChange={e => setNewAlias(e.target.value)}
+          className="text-sm flex-1"
+        />
+        <Button
+          variant="outline"
+          size="icon"
+          onClick={handleAdd}
+          disabled={!newModel.trim() || !newAlias.trim()}
+          className="shrink-0"
+        >
+          <Plus className="w-4 h-4" />
+        </Button>
+      </div>
+    </div>
+  )
+}
+
+const DEFAULT_NOTION_CONFIG: NotionIntegrationConfig = {
+  enabled: false,
+  token: '',
+  databaseId: '',
+  titleProperty: 'Date',
+}
+
+const DEFAULT_EMAIL_DIGEST_CONFIG: EmailDigestConfig = {
+  enabled: false,
+  smtpUrl: '',
+  username: '',
+  from: '',
+  to: [],
+  weekday: 0,
+  hour: 9,
+}
+
+const WEEKDAY_KEYS = ['mon', 'tue', 'wed', 'thu', 'fri', 'sat', 'sun'] as const
+
+function EmailDigestActions({ disabled }: { disabled: boolean }) {
+  const { t } = useTranslation('settings')
+  const [password, setPassword] = useState('')
+  const [pending, setPending] = useState(false)
+  const [message, setMessage] = useState<{ error: boolean, text: string } | null>(null)
+
+  const run = async (action: () => Promise<void>, successText: string) => {
+    setPending(true)
+    setMessage(null)
+    try {
+      await action()
+      setMessage({ error: false, text: successText })
+    }
+    catch (e) {
+      setMessage({ error: true, text: String(e) })
+    }
+    finally {
+      setPending(false)
+    }
+  }
+
+  const handleSavePassword = () => run(async () => {
+    await setSmtpPassword(password)
+    setPassword('')
+  }, t('integrations.emailDigest.passwordSaved'))
+
+  return (
+    <div className="space-y-2">
+      <Label htmlFor="smtpPassword">{t('integrations.emailDigest.password')}</Label>
+      <div className="flex gap-2">
+        <Input
+          id="smtpPassword"
+          type="password"
+          value={password}
+          onChange={e => setPassword(e.target.value)}
+          placeholder={t('integrations.emailDigest.passwordPlaceholder')}
+        />
+        <Button
+          variant="outline"
+          onClick={handleSavePassword}
+          disabled={disabled || pending || !password}
+          className="shrink-0"
+        >
+          {t('integrations.emailDigest.savePassword')}
+        </Button>
+        <Button
+          variant="outline"
+          onClick={() => run(sendTestDigest, t('integrations.emailDigest.testSent'))}
+          disabled={disabled || pending}
+          className="shrink-0"
+        >
+          {t('integrations.emailDigest.sendTest')}
+        </Button>
+      </div>
+      <p className="text-sm text-muted-foreground">
+        {disabled ? t('integrations.emailDigest.saveFirst') : t('integrations.emailDigest.passwordDescription')}
+      </p>
+      {message && (
+        <p className={message.error ? 'text-sm text-red-500' : 'text-sm text-muted-foreground'}>
+          {message.text}
+        </p>
+      )}
+    </div>
+  )
+}
+
+const DEFAULT_REMOTE_SNAPSHOT_CONFIG: RemoteSnapshotConfig = {
+  enabled: false,
+  url: '',
+}
+
+function SnapshotPassphraseEditor({ canPush }: { canPush: boolean }) {
+  const { t } = useTranslation('settings')
+  const { data: hasPassphrase } = useHasSnapshotPassphrase()
+  const setMutation = useSetSnapshotPassphrase()
+  const clearMutation = useClearSnapshotPassphrase()
+  const [passphrase, setPassphrase] = useState('')
+  const [pushing, setPushing] = useState(false)
+  const [message, setMessage] = useState<{ error: boolean, text: string } | null>(null)
+
+  const handleSave = () => {
+    setMessage(null)
+    setMutation.mutate(passphrase, {
+      onSuccess: () => {
+        setPassphrase('')
+        setMessage({ error: false, text: t('integrations.remoteSnapshot.passphraseSaved') })
+      },
+      onError: e => setMessage({ error: true, text: String(e) }),
+    })
+  }
+
+  const handleClear = () => {
+    setMessage(null)
+    clearMutation.mutate(undefined, {
+      onError: e => setMessage({ error: true, text: String(e) }),
+    })
+  }
+
+  const handlePush = async () => {
+    setPushing(true)
+    setMessage(null)
+    try {
+      await pushSnapshotNow()
+      setMessage({ error: false, text: t('integrations.remoteSnapshot.pushed') })
+    }
+    catch (e) {
+      setMessage({ error: true, text: String(e) })
+    }
+    finally {
+      setPushing(false)
+    }
+  }
+
+  const pending = setMutation.isPending || clearMutation.isPending || pushing
+
+  return (
+    <div className="space-y-2">
+      <Label htmlFor="snapshotPassphrase">{t('integrations.remoteSnapshot.passphrase')}</Label>
+      <div className="flex gap-2">
+        <Input
+          id="snapshotPassphrase"
+          type="password"
+          value={passphrase}
+          onChange={e => setPassphrase(e.target.value)}
+          placeholder={hasPassphrase
+            ? t('integrations.remoteSnapshot.passphraseSet')
+            : t('integrations.remoteSnapshot.passphraseNotSet')}
+        />
+        <Button
+          variant="outline"
+          onClick={handleSave}
+          disabled={pending || passphrase.length < 12}
+          className="shrink-0"
+        >
+          {hasPassphrase ? t('integrations.remoteSnapshot.changePassphrase') : t('integrations.remoteSnapshot.savePassphrase')}
+        </Button>
+        {hasPassphrase && (
+          <Button
+            variant="outline"
+            onClick={handleClear}
+            disabled={pending}
+            className="shrink-0"
+          >
+            {t('integrations.remoteSnapshot.clearPassphrase')}
+          </Button>
+        )}
+        <Button
+          variant="outline"
+          onClick={handlePush}
+          disabled={pending || !canPush || !hasPassphrase}
+          className="shrink-0"
+        >
+          {t('integrations.remoteSnapshot.pushNow')}
+        </Button>
+      </div>
+      <p className="text-sm text-muted-foreground">
+        {t('integrations.remoteSnapshot.passphraseDescription')}
+      </p>
+      {message && (
+        <p className={message.error ? 'text-sm text-red-500' : 'text-sm text-muted-foreground'}>
+          {message.text}
+        </p>
+      )}
+    </div>
+  )
+}
+
+function CredentialEditor({ credential, label, placeholder }: { credential: Credential, label: string, placeholder?: string }) {
+  const { t } = useTranslation('settings')
+  const { data: stored } = useStoredCredentials()
+  const setMutation = useSetCredential()
+  const clearMutation = useClearCredential()
+  const [value, setValue] = useState('')
+  const [error, setError] = useState<string | null>(null)
+  const isStored = stored?.includes(credential) ?? false
+  const pending = setMutation.isPending || clearMutation.isPending
+
+  const handleSave = () => {
+    setError(null)
+    setMutation.mutate({ credential, value }, {
+      onSuccess: () => setValue(''),
+      onError: e => setError(String(e)),
+    })
+  }
+
+  const handleClear = () => {
+    setError(null)
+    clearMutation.mutate(credential, {
+      onError: e => setError(String(e)),
+    })
+  }
+
+  return (
+    <div className="space-y-2">
+      <Label htmlFor={credential}>{label}</Label>
+      <div className="flex gap-2">
+        <Input
+          id={credential}
+          type="password"
+          value={value}
+          onChange={e => setValue(e.target.value)}
+          placeholder={isStored ? t('credentials.stored') : placeholder}
+          className="font-mono text-sm"
+        />
+        <Button
+          variant="outline"
+          onClick={handleSave}
+          disabled={pending || !value.trim()}
+          className="shrink-0"
+        >
+          {t('credentials.save')}
+        </Button>
+        {isStored && (
+          <Button
+            variant="outline"
+            onClick={handleClear}
+            disabled={pending}
+            className="shrink-0"
+          >
+            {t('credentials.clear')}
+          </Button>
+        )}
+      </div>
+      {error && <p className="text-sm text-red-500">{error}</p>}
+    </div>
+  )
+}
+
+const DEFAULT_PUSH_CONFIG: PushNotificationConfig = {
+  enabled: false,
+  service: 'ntfy',
+  ntfyServer: 'https://ntfy.sh',
+  ntfyTopic: '',
+}
+
+const DEFAULT_BALANCE_DRAIN_CONFIG: BalanceDrainConfig = {
+  enabled: false,
+  percent: 10,
+  windowMinutes: 60,
+}
+
+const DEFAULT_KEY_EXPIRY_CONFIG: KeyExpiryConfig = {
+  enabled: true,
+  daysBefore: 7,
+}
+
+function TestPushButton({ disabled }: { disabled: boolean }) {
+  const { t } = useTranslation('settings')
+  const [pending, setPending] = useState(false)
+  const [message, setMessage] = useState<{ error: boolean, text: string } | null>(null)
+
+  const handleTest = async () => {
+    setPending(true)
+    setMessage(null)
+    try {
+      await sendTestPush()
+      setMessage({ error: false, text: t('alerts.push.testSent') })
+    }
+    catch (e) {
+      setMessage({ error: true, text: String(e) })
+    }
+    finally {
+      setPending(false)
+    }
+  }
+
+  return (
+    <div className="space-y-2">
+      <Button variant="outline" onClick={handleTest} disabled={disabled || pending}>
+        {t('alerts.push.sendTest')}
+      </Button>
+      {disabled && (
+        <p className="text-sm text-muted-foreground">{t('alerts.push.saveFirst')}</p>
+      )}
+      {message && (
+        <p className={message.error ? 'text-sm text-red-500' : 'text-sm text-muted-foreground'}>
+          {message.text}
+        </p>
+      )}
+    </div>
+  )
+}
+
+const DEFAULT_TIMEOUTS_CONFIG: TimeoutsConfig = {
+  ccusageSecs: 60,
+  providerSecs: 15,
+  pricingSecs: 10,
+  integrationSecs: 15,
+}
+
+const TIMEOUT_FIELDS = [
+  { key: 'ccusageSecs', label: 'ccusage', min: 10, max: 600 },
+  { key: 'providerSecs', label: 'provider', min: 1, max: 120 },
+  { key: 'pricingSecs', label: 'pricing', min: 1, max: 120 },
+  { key: 'integrationSecs', label: 'integration', min: 1, max: 120 },
+] as const
+
+const DEFAULT_APP_LOCK_CONFIG: AppLockConfig = {
+  enabled: false,
+  graceMinutes: 0,
+}
+
+const DEFAULT_GUARD_CONFIG: GuardConfig = {
+  enabled: false,
+  port: 8788,
+  token: '',
+  minHeadroom: 0,
+}
+
+const DEFAULT_LIMITS_CONFIG: LimitsConfig = {
+  enabled: false,
+  unit: 'tokens',
+  blockLimit: 0,
+  weeklyLimit: 0,
+  nearPercent: 75,
+  overPercent: 95,
+}
+
+function IntegrationStatusLine({ id }: { id: string }) {
+  const { t } = useTranslation('settings')
+  const { data: diagnostics } = useDiagnostics()
+  const status = diagnostics?.integrations[id]
+
+  if (!status?.lastAttempt)
+    return null
+  if (status.lastError) {
+    return (
+      <p className="text-sm text-red-500">
+        {t('integrations.lastError')}
+        {' '}
+        {status.lastError}
+      </p>
+    )
+  }
+  return (
+    <p className="text-sm text-muted-foreground">
+      {t('integrations.lastSuccess', { time: new Date(status.lastSuccess ?? status.lastAttempt).toLocaleString() })}
+    </p>
+  )
+}
+
+const CCUSAGE_FLAGS = ['offline', 'breakdown', 'recent'] as const
+
+function CcusageCompatibilityLine() {
+  const { t } = useTranslation('settings')
+  const { data: diagnostics } = useDiagnostics()
+  const capabilities = diagnostics?.ccusage
+
+  if (!capabilities)
+    return null
+  const version = capabilities.version ?? t('timeouts.ccusageUnknownVersion')
+  if (!capabilities.helpRead) {
+    return (
+      <p className="text-sm text-muted-foreground">
+        {t('timeouts.ccusageAssumed', { version })}
+      </p>
+    )
+  }
+  const missing = CCUSAGE_FLAGS.filter(flag => !capabilities[flag]).map(flag => `--${flag}`)
+  // Either day-range flag is enough
+  if (!capabilities.days && !capabilities.since)
+    missing.unshift('--days/--since')
+  return (
+    <p className="text-sm text-muted-foreground">
+      {missing.length > 0
+        ? t('timeouts.ccusageMissing', { version, flags: missing.join(', ') })
+        : t('timeouts.ccusageDetected', { version })}
+    </p>
+  )
+}
+
+function KeyExpirationList({ daysBefore }: { daysBefore: number }) {
+  const { t } = useTranslation('settings')
+  const { data: diagnostics } = useDiagnostics()
+  const expirations = diagnostics?.keyExpirations ?? []
+
+  if (expirations.length === 0)
+    return null
+  return (
+    <div className="space-y-1">
+      <Label>{t('alerts.keyExpiry.upcoming')}</Label>
+      {expirations.map((expiration) => {
+        const expired = expiration.daysLeft < 0
+        let color = 'text-muted-foreground'
+        if (expired)
+          color = 'text-red-500'
+        else if (expiration.daysLeft <= daysBefore)
+          color = 'text-yellow-600'
+        return (
+          <p key={`${expiration.providerId}/${expiration.variable}`} className={`text-sm ${color}`}>
+            <span className="font-medium">{expiration.providerName}</span>
+            {' '}
+            <span className="font-mono">{expiration.variable}</span>
+            {' — '}
+            {expired
+              ? t('alerts.keyExpiry.expired', { date: expiration.expiresAt })
+              : t('alerts.keyExpiry.expiresIn', { count: expiration.daysLeft, date: expiration.expiresAt })}
+          </p>
+        )
+      })}
+    </div>
+  )
+}
+
+function ProfileSwitcher({ hasChanges }: { hasChanges: boolean }) {
+  const { t } = useTranslation('settings')
+  const { data: profiles } = useProfiles()
+  const switchMutation = useSwitchProfile()
+  const [newName, setNewName] = useState('')
+
+  if (!profiles)
+    return null
+
+  const switchTo = (name: string) => switchMutation.mutate(name, {
+    onSuccess: () => setNewName(''),
+  })
+
+  return (
+    <div className="space-y-2">
+      <Label htmlFor="profile">{t('general.profile')}</Label>
+      <div className="flex gap-2">
+        <Select
+          id="profile"
+          value={profiles.active}
+          onChange={e => switchTo(e.target.value)}
+          disabled={hasChanges || switchMutation.isPending}
+        >
+          {profiles.names.map(name => (
+            <option key={name} value={name}>{name}</option>
+          ))}
+        </Select>
+        <Input
+          value={newName}
+          onChange={e => setNewName(e.target.value)}
+          placeholder={t('general.newProfilePlaceholder')}
+        />
+        <Button
+          variant="outline"
+          onClick={() => switchTo(newName.trim())}
+          disabled={hasChanges || switchMutation.isPending || !newName.trim()}
+          className="shrink-0"
+        >
+          {t('general.createProfile')}
+        </Button>
+      </div>
+      <p className="text-sm text-muted-foreground">
+        {hasChanges ? t('general.profileSaveFirst') : t('general.profileDescription')}
+      </p>
+      {switchMutation.error && (
+        <p className="text-sm text-red-500">{String(switchMutation.error)}</p>
+      )}
+    </div>
+  )
+}
+
+function UsageImportCard() {
+  const { t } = useTranslation('settings')
+  const queryClient = useQueryClient()
+  const [source, setSource] = useState('')
+  const [pending, setPending] = useState(false)
+  const [message, setMessage] = useState<{ error: boolean, text: string } | null>(null)
+
+  const handleFile = async (e: React.ChangeEvent<HTMLInputElement>) => {
+    const file = e.target.files?.[0]
+    e.target.value = ''
+    if (!file)
+      return
+    setPending(true)
+    setMessage(null)
+    try {
+      const data = await importUsageDump(source.trim(), await file.text())
+      queryClient.setQueryData(['usage'], data)
+      setMessage({ error: false, text: t('import.imported', { source: source.trim() }) })
+    }
+    catch (err) {
+      setMessage({ error: true, text: String(err) })
+    }
+    finally {
+      setPending(false)
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>{t('import.title')}</CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-2">
+        <Label htmlFor="importSource">{t('import.source')}</Label>
+        <div className="flex gap-2">
+          <Input
+            id="importSource"
+            value={source}
+            onChange={e => setSource(e.target.value)}
+            placeholder={t('import.sourcePlaceholder')}
+          />
+          <Button variant="outline" asChild className="shrink-0" disabled={pending || !source.trim()}>
+            <label className={pending || !source.trim() ? 'pointer-events-none opacity-50' : 'cursor-pointer'}>
+              {t('import.chooseFile')}
+              <input type="file" accept=".json,application/json" className="hidden" onChange={handleFile} />
+            </label>
+          </Button>
+        </div>
+        <p className="text-sm text-muted-foreground">
+          {t('import.description')}
+        </p>
+        {message && (
+          <p className={message.error ? 'text-sm text-red-500' : 'text-sm text-muted-foreground'}>
+            {message.text}
+          </p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
+
 export function Settings() {
   const { data: config, isLoading } = useConfig()
+  const { data: configSchema } = useConfigSchema()
   const saveMutation = useSaveConfig()
   const [localConfig, setLocalConfig] = useState<AppConfig | null>(null)
   const [autoLaunchError, setAutoLaunchError] = useState<string | null>(null)
+  const [guardTokenError, setGuardTokenError] = useState<string | null>(null)
   const { t } = useTranslation('settings')
   const { languagePreference, changeLanguage } = useLanguage()
 
@@ -104,6 +748,151 @@ export function Settings() {
     })
   }
 
+  const updateTrends = (updates: Partial<AppConfig['trends']>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      return { ...base, trends: { ...base.trends, ...updates } }
+    })
+  }
+
+  const notionConfig = currentConfig.integrations?.notion ?? DEFAULT_NOTION_CONFIG
+  const emailDigestConfig = currentConfig.integrations?.emailDigest ?? DEFAULT_EMAIL_DIGEST_CONFIG
+  const remoteSnapshotConfig = currentConfig.integrations?.remoteSnapshot ?? DEFAULT_REMOTE_SNAPSHOT_CONFIG
+
+  const updateIntegrations = (update: (integrations: IntegrationsConfig) => IntegrationsConfig) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      const integrations = update({
+        notion: base.integrations?.notion ?? DEFAULT_NOTION_CONFIG,
+        emailDigest: base.integrations?.emailDigest ?? DEFAULT_EMAIL_DIGEST_CONFIG,
+        remoteSnapshot: base.integrations?.remoteSnapshot ?? DEFAULT_REMOTE_SNAPSHOT_CONFIG,
+        hooks: base.integrations?.hooks ?? [],
+      })
+      return { ...base, integrations }
+    })
+  }
+
+  const updateNotion = (updates: Partial<NotionIntegrationConfig>) =>
+    updateIntegrations(integrations => ({ ...integrations, notion: { ...integrations.notion, ...updates } }))
+
+  const updateEmailDigest = (updates: Partial<EmailDigestConfig>) =>
+    updateIntegrations(integrations => ({ ...integrations, emailDigest: { ...integrations.emailDigest, ...updates } }))
+
+  const updateRemoteSnapshot = (updates: Partial<RemoteSnapshotConfig>) =>
+    updateIntegrations(integrations => ({ ...integrations, remoteSnapshot: { ...integrations.remoteSnapshot, ...updates } }))
+
+  const hooks = currentConfig.integrations?.hooks ?? []
+
+  const updateHook = (index: number, updates: Partial<RefreshHook>) =>
+    updateIntegrations(integrations => ({
+      ...integrations,
+      hooks: integrations.hooks.map((hook, i) => (i === index ? { ...hook, ...updates } : hook)),
+    }))
+
+  const addHook = () =>
+    updateIntegrations(integrations => ({
+      ...integrations,
+      hooks: [...integrations.hooks, { name: `hook-${integrations.hooks.length + 1}`, enabled: true, command: '' }],
+    }))
+
+  const removeHook = (index: number) =>
+    updateIntegrations(integrations => ({ ...integrations, hooks: integrations.hooks.filter((_, i) => i !== index) }))
+
+  const pushConfig = currentConfig.alerts?.push ?? DEFAULT_PUSH_CONFIG
+
+  const updatePush = (updates: Partial<PushNotificationConfig>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      const push = { ...(base.alerts?.push ?? DEFAULT_PUSH_CONFIG), ...updates }
+      return { ...base, alerts: { ...base.alerts, push } }
+    })
+  }
+
+  const balanceDrainConfig = currentConfig.alerts?.balanceDrain ?? DEFAULT_BALANCE_DRAIN_CONFIG
+
+  const updateBalanceDrain = (updates: Partial<BalanceDrainConfig>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      const balanceDrain = { ...(base.alerts?.balanceDrain ?? DEFAULT_BALANCE_DRAIN_CONFIG), ...updates }
+      return { ...base, alerts: { push: DEFAULT_PUSH_CONFIG, ...base.alerts, balanceDrain } }
+    })
+  }
+
+  const keyExpiryConfig = currentConfig.alerts?.keyExpiry ?? DEFAULT_KEY_EXPIRY_CONFIG
+
+  const updateKeyExpiry = (updates: Partial<KeyExpiryConfig>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      const keyExpiry = { ...(base.alerts?.keyExpiry ?? DEFAULT_KEY_EXPIRY_CONFIG), ...updates }
+      return { ...base, alerts: { push: DEFAULT_PUSH_CONFIG, ...base.alerts, keyExpiry } }
+    })
+  }
+
+  const guardConfig = currentConfig.guard ?? DEFAULT_GUARD_CONFIG
+
+  const updateGuard = (updates: Partial<GuardConfig>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      return { ...base, guard: { ...(base.guard ?? DEFAULT_GUARD_CONFIG), ...updates } }
+    })
+  }
+
+  const appLockConfig = currentConfig.appLock ?? DEFAULT_APP_LOCK_CONFIG
+
+  const updateAppLock = (updates: Partial<AppLockConfig>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      return { ...base, appLock: { ...(base.appLock ?? DEFAULT_APP_LOCK_CONFIG), ...updates } }
+    })
+  }
+
+  const limitsConfig = currentConfig.limits ?? DEFAULT_LIMITS_CONFIG
+
+  const updateLimits = (updates: Partial<LimitsConfig>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      return { ...base, limits: { ...(base.limits ?? DEFAULT_LIMITS_CONFIG), ...updates } }
+    })
+  }
+
+  const timeoutsConfig = currentConfig.timeouts ?? DEFAULT_TIMEOUTS_CONFIG
+  const timeoutsSchema = configSchema?.definitions?.TimeoutsConfig?.properties
+
+  const updateTimeouts = (updates: Partial<TimeoutsConfig>) => {
+    if (!config)
+      return
+    setLocalConfig((prev) => {
+      const base = prev ?? config
+      return { ...base, timeouts: { ...(base.timeouts ?? DEFAULT_TIMEOUTS_CONFIG), ...updates } }
+    })
+  }
+
+  const handleGenerateGuardToken = async () => {
+    setGuardTokenError(null)
+    try {
+      updateGuard({ token: await generateGuardToken() })
+    }
+    catch (e) {
+      setGuardTokenError(String(e))
+    }
+  }
+
   const hasChanges = localConfig !== null
     && JSON.stringify(localConfig) !== JSON.stringify(config)
 
@@ -162,6 +951,39 @@ export function Settings() {
             </p>
           </div>
 
+          <div className="space-y-2">
+            <Label htmlFor="refreshHotkey">{t('general.refreshHotkey')}</Label>
+            <Input
+              id="refreshHotkey"
+              value={currentConfig.refreshHotkey ?? ''}
+              onChange={e => updateConfig({ refreshHotkey: e.target.value.trim() || null })}
+              placeholder="CmdOrCtrl+Shift+R"
+            />
+            <p className="text-sm text-muted-foreground">
+              {t('general.refreshHotkeyDescription')}
+            </p>
+          </div>
+
+          <div className="space-y-2">
+            <Label htmlFor="billingCycleDay">{t('general.billingCycleDay')}</Label>
+            <Input
+              id="billingCycleDay"
+              type="number"
+              min={1}
+              max={31}
+              value={currentConfig.billingCycleDay ?? ''}
+              onChange={(e) => {
+                const day = Math.floor(Number(e.target.value))
+                updateConfig({ billingCycleDay: day >= 1 && day <= 31 ? day : null })
+              }}
+              placeholder={t('general.billingCycleDayPlaceholder')}
+              className="w-32"
+            />
+            <p className="text-sm text-muted-foreground">
+              {t('general.billingCycleDayDescription')}
+            </p>
+          </div>
+
           <Separator />
 
           <div className="space-y-2">
@@ -179,8 +1001,12 @@ export function Settings() {
               {t('general.languageDescription')}
             </p>
           </div>
-        </CardContent>
-      </Card>
+
+          <Separator />
+
+          <ProfileSwitcher hasChanges={hasChanges} />
+        </CardContent>
+      </Card>
 
       <Card>
         <CardHeader>
@@ -198,6 +1024,7 @@ export function Settings() {
             <p className="text-sm text-muted-foreground">
               {t('menuBar.formatDescription')}
             </p>
+            <TrayFormatFeedback format={currentConfig.menuBar.format} />
           </div>
 
           <Separator />
@@ -223,6 +1050,75 @@ export function Settings() {
 
           <Separator />
 
+          <div className="space-y-2">
+            <Label htmlFor="weekendBudget">{t('menuBar.weekendBudget')}</Label>
+            <Input
+              id="weekendBudget"
+              type="number"
+              min={0}
+              step={0.01}
+              value={currentConfig.menuBar.weekendBudget ?? ''}
+              placeholder={String(currentConfig.menuBar.fixedBudget)}
+              onChange={(e) => {
+                if (e.target.value === '') {
+                  updateMenuBar({ weekendBudget: null })
+                  return
+                }
+                const value = Number.parseFloat(e.target.value)
+                if (!Number.isNaN(value))
+                  updateMenuBar({ weekendBudget: Math.max(0, value) })
+              }}
+            />
+            <p className="text-sm text-muted-foreground">
+              {t('menuBar.weekendBudgetDescription')}
+            </p>
+          </div>
+
+          <div className="space-y-2">
+            <Label htmlFor="monthlyBudget">{t('menuBar.monthlyBudget')}</Label>
+            <Input
+              id="monthlyBudget"
+              type="number"
+              min={0}
+              step={1}
+              value={currentConfig.menuBar.monthlyBudget ?? ''}
+              placeholder={t('menuBar.monthlyBudgetPlaceholder')}
+              onChange={(e) => {
+                if (e.target.value === '') {
+                  updateMenuBar({ monthlyBudget: null })
+                  return
+                }
+                const value = Number.parseFloat(e.target.value)
+                if (!Number.isNaN(value))
+                  updateMenuBar({ monthlyBudget: Math.max(0, value) })
+              }}
+            />
+            <p className="text-sm text-muted-foreground">
+              {t('menuBar.monthlyBudgetDescription')}
+            </p>
+          </div>
+
+          <div className="space-y-2">
+            <Label htmlFor="hardBudgetPercent">{t('menuBar.hardBudgetPercent')}</Label>
+            <Input
+              id="hardBudgetPercent"
+              type="number"
+              min={101}
+              step={5}
+              value={currentConfig.menuBar.hardBudgetPercent ?? ''}
+              placeholder={t('menuBar.hardBudgetPercentPlaceholder')}
+              onChange={(e) => {
+                const value = Number.parseFloat(e.target.value)
+                updateMenuBar({ hardBudgetPercent: Number.isNaN(value) ? null : value })
+              }}
+            />
+            <p className="text-sm text-muted-foreground">
+              {t('menuBar.hardBudgetPercentDescription')}
+            </p>
+          </div>
+
+          <Separator />
+
           <div className="space-y-2">
             <Label htmlFor="nearBudgetThresholdPercent">{t('menuBar.nearBudgetThreshold')}</Label>
             <Input
@@ -258,6 +1154,954 @@ export function Settings() {
                 updateMenuBar({ showColorCoding: checked })}
             />
           </div>
+
+          {currentConfig.menuBar.showColorCoding && (
+            <div className="space-y-2">
+              <Label htmlFor="colorScheme">{t('menuBar.colorScheme')}</Label>
+              <Select
+                id="colorScheme"
+                value={currentConfig.menuBar.colorScheme ?? 'standard'}
+                onChange={e => updateMenuBar({ colorScheme: e.target.value as ColorScheme })}
+              >
+                <option value="standard">{t('menuBar.colorSchemeStandard')}</option>
+                <option value="highContrast">{t('menuBar.colorSchemeHighContrast')}</option>
+                <option value="colorblindSafe">{t('menuBar.colorSchemeColorblindSafe')}</option>
+                <option value="monochrome">{t('menuBar.colorSchemeMonochrome')}</option>
+              </Select>
+              <p className="text-sm text-muted-foreground">
+                {t('menuBar.colorSchemeDescription')}
+              </p>
+            </div>
+          )}
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('menuBar.privacyMode')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('menuBar.privacyModeDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.menuBar.privacyMode ?? false}
+              onCheckedChange={checked =>
+                updateMenuBar({ privacyMode: checked })}
+            />
+          </div>
+
+          {currentConfig.menuBar.privacyMode && (
+            <div className="grid grid-cols-2 gap-4">
+              <div className="space-y-2">
+                <Label htmlFor="privacyStyle">{t('menuBar.privacyStyle')}</Label>
+                <Select
+                  id="privacyStyle"
+                  value={currentConfig.menuBar.privacyStyle ?? 'mask'}
+                  onChange={e => updateMenuBar({ privacyStyle: e.target.value as PrivacyStyle })}
+                >
+                  <option value="mask">{t('menuBar.privacyStyleMask')}</option>
+                  <option value="scale">{t('menuBar.privacyStyleScale')}</option>
+                </Select>
+              </div>
+              {currentConfig.menuBar.privacyStyle === 'scale' && (
+                <div className="space-y-2">
+                  <Label htmlFor="privacyScale">{t('menuBar.privacyScale')}</Label>
+                  <Input
+                    id="privacyScale"
+                    type="number"
+                    min={0.01}
+                    max={100}
+                    step={0.01}
+                    value={currentConfig.menuBar.privacyScale ?? 0.37}
+                    onChange={e => updateMenuBar({ privacyScale: Number(e.target.value) || 0.37 })}
+                  />
+                </div>
+              )}
+            </div>
+          )}
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('menuBar.recentDays')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('menuBar.recentDaysDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.menuBar.showRecentDays ?? false}
+              onCheckedChange={checked =>
+                updateMenuBar({ showRecentDays: checked })}
+            />
+          </div>
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('menuBar.topSpender')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('menuBar.topSpenderDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.menuBar.showTopSpender ?? false}
+              onCheckedChange={checked =>
+                updateMenuBar({ showTopSpender: checked })}
+            />
+          </div>
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('menuBar.profiles')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('menuBar.profilesDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.menuBar.showProfiles ?? false}
+              onCheckedChange={checked =>
+                updateMenuBar({ showProfiles: checked })}
+            />
+          </div>
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('menuBar.providerCosts')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('menuBar.providerCostsDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.includeProviderCosts ?? false}
+              onCheckedChange={checked =>
+                updateConfig({ includeProviderCosts: checked })}
+            />
+          </div>
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('menuBar.verboseAccessibility')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('menuBar.verboseAccessibilityDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.menuBar.verboseAccessibility ?? true}
+              onCheckedChange={checked =>
+                updateMenuBar({ verboseAccessibility: checked })}
+            />
+          </div>
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('trends.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="space-y-2">
+            <Label htmlFor="trendBaseline">{t('trends.baseline')}</Label>
+            <Select
+              id="trendBaseline"
+              value={currentConfig.trends.baseline}
+              onChange={e => updateTrends({ baseline: e.target.value as TrendBaseline })}
+            >
+              <option value="mean30">{t('trends.baselineMean30')}</option>
+              <option value="median7">{t('trends.baselineMedian7')}</option>
+              <option value="ewma">{t('trends.baselineEwma')}</option>
+              <option value="sameWeekday">{t('trends.baselineSameWeekday')}</option>
+            </Select>
+            <p className="text-sm text-muted-foreground">
+              {t('trends.baselineDescription')}
+            </p>
+          </div>
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('trends.excludeOutliers')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('trends.excludeOutliersDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.trends.excludeOutliers}
+              onCheckedChange={checked =>
+                updateTrends({ excludeOutliers: checked })}
+            />
+          </div>
+
+          {currentConfig.trends.excludeOutliers && (
+            <div className="space-y-2">
+              <Label htmlFor="outlierPercent">{t('trends.outlierPercent')}</Label>
+              <Input
+                id="outlierPercent"
+                type="number"
+                min={1}
+                max={49}
+                step={1}
+                value={currentConfig.trends.outlierPercent}
+                {...createNumberInputHandlers(
+                  value => updateTrends({ outlierPercent: value }),
+                  str => Number.parseFloat(str),
+                  { min: 0, max: 49 },
+                )}
+              />
+              <p className="text-sm text-muted-foreground">
+                {t('trends.outlierPercentDescription')}
+              </p>
+            </div>
+          )}
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('trends.orgComparison')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('trends.orgComparisonDescription')}
+              </p>
+            </div>
+            <Switch
+              checked={currentConfig.trends.orgComparison}
+              onCheckedChange={checked =>
+                updateTrends({ orgComparison: checked })}
+            />
+          </div>
+
+          {currentConfig.trends.orgComparison && (
+            <div className="space-y-2">
+              <Label htmlFor="orgMember">{t('trends.orgMember')}</Label>
+              <Input
+                id="orgMember"
+                value={currentConfig.trends.orgMember ?? ''}
+                onChange={e => updateTrends({ orgMember: e.target.value.trim() || null })}
+                placeholder="you@example.com"
+              />
+              <p className="text-sm text-muted-foreground">
+                {t('trends.orgMemberDescription')}
+              </p>
+            </div>
+          )}
+
+          <Separator />
+
+          <div className="space-y-2">
+            <Label htmlFor="chartAggregation">{t('trends.chartAggregation')}</Label>
+            <Select
+              id="chartAggregation"
+              value={currentConfig.chartAggregation ?? 'sum'}
+              onChange={e => updateConfig({ chartAggregation: e.target.value as ChartAggregation })}
+            >
+              <option value="sum">{t('trends.chartAggregationSum')}</option>
+              <option value="mean">{t('trends.chartAggregationMean')}</option>
+            </Select>
+            <p className="text-sm text-muted-foreground">
+              {t('trends.chartAggregationDescription')}
+            </p>
+          </div>
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('models.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="space-y-2">
+            <Label htmlFor="minModelDailyCost">{t('models.minDailyCost')}</Label>
+            <Input
+              id="minModelDailyCost"
+              type="number"
+              min={0}
+              step={0.01}
+              value={currentConfig.minModelDailyCost ?? 0}
+              {...createNumberInputHandlers(
+                value => updateConfig({ minModelDailyCost: value }),
+                str => Number.parseFloat(str),
+                { min: 0 },
+              )}
+            />
+            <p className="text-sm text-muted-foreground">
+              {t('models.minDailyCostDescription')}
+            </p>
+          </div>
+
+          <div className="space-y-2">
+            <Label htmlFor="modelSort">{t('models.sort')}</Label>
+            <Select
+              id="modelSort"
+              value={currentConfig.modelSort ?? 'cost'}
+              onChange={e => updateConfig({ modelSort: e.target.value as ModelSortKey })}
+            >
+              <option value="cost">{t('models.sortCost')}</option>
+              <option value="tokens">{t('models.sortTokens')}</option>
+              <option value="name">{t('models.sortName')}</option>
+            </Select>
+            <p className="text-sm text-muted-foreground">
+              {t('models.sortDescription')}
+            </p>
+          </div>
+
+          <Separator />
+
+          <div className="space-y-0.5">
+            <Label>{t('modelAliases.title')}</Label>
+            <p className="text-sm text-muted-foreground">
+              {t('modelAliases.description')}
+            </p>
+          </div>
+          <ModelAliasEditor
+            aliases={currentConfig.modelAliases ?? {}}
+            onChange={modelAliases => updateConfig({ modelAliases })}
+          />
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('alerts.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('alerts.push.enabled')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('alerts.push.description')}
+              </p>
+            </div>
+            <Switch
+              checked={pushConfig.enabled}
+              onCheckedChange={checked => updatePush({ enabled: checked })}
+            />
+          </div>
+
+          {pushConfig.enabled && (
+            <>
+              <div className="space-y-2">
+                <Label htmlFor="pushService">{t('alerts.push.service')}</Label>
+                <Select
+                  id="pushService"
+                  value={pushConfig.service}
+                  onChange={e => updatePush({ service: e.target.value as PushService })}
+                >
+                  <option value="ntfy">ntfy</option>
+                  <option value="pushover">Pushover</option>
+                </Select>
+              </div>
+
+              {pushConfig.service === 'ntfy'
+                ? (
+                    <>
+                      <div className="space-y-2">
+                        <Label htmlFor="ntfyServer">{t('alerts.push.ntfyServer')}</Label>
+                        <Input
+                          id="ntfyServer"
+                          value={pushConfig.ntfyServer}
+                          onChange={e => updatePush({ ntfyServer: e.target.value })}
+                          className="font-mono text-sm"
+                        />
+                      </div>
+                      <div className="space-y-2">
+                        <Label htmlFor="ntfyTopic">{t('alerts.push.ntfyTopic')}</Label>
+                        <Input
+                          id="ntfyTopic"
+                          value={pushConfig.ntfyTopic}
+                          onChange={e => updatePush({ ntfyTopic: e.target.value })}
+                          className="font-mono text-sm"
+                        />
+                        <p className="text-sm text-muted-foreground">
+                          {t('alerts.push.ntfyTopicDescription')}
+                        </p>
+                      </div>
+                      <CredentialEditor credential="ntfyToken" label={t('alerts.push.ntfyToken')} placeholder="tk_..." />
+                    </>
+                  )
+                : (
+                    <>
+                      <CredentialEditor credential="pushoverUserKey" label={t('alerts.push.pushoverUserKey')} />
+                      <CredentialEditor credential="pushoverAppToken" label={t('alerts.push.pushoverAppToken')} />
+                    </>
+                  )}
+
+              <TestPushButton disabled={hasChanges} />
+              <IntegrationStatusLine id="push" />
+
+              <div className="flex items-center justify-between">
+                <div className="space-y-0.5">
+                  <Label>{t('alerts.balanceDrain.enabled')}</Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t('alerts.balanceDrain.description')}
+                  </p>
+                </div>
+                <Switch
+                  checked={balanceDrainConfig.enabled}
+                  onCheckedChange={checked => updateBalanceDrain({ enabled: checked })}
+                />
+              </div>
+
+              {balanceDrainConfig.enabled && (
+                <div className="grid grid-cols-2 gap-4">
+                  <div className="space-y-2">
+                    <Label htmlFor="balanceDrainPercent">{t('alerts.balanceDrain.percent')}</Label>
+                    <Input
+                      id="balanceDrainPercent"
+                      type="number"
+                      min={1}
+                      max={100}
+                      value={balanceDrainConfig.percent}
+                      {...createNumberInputHandlers(
+                        value => updateBalanceDrain({ percent: value }),
+                        str => Number.parseFloat(str),
+                        { min: 1, max: 100 },
+                      )}
+                    />
+                  </div>
+                  <div className="space-y-2">
+                    <Label htmlFor="balanceDrainWindow">{t('alerts.balanceDrain.window')}</Label>
+                    <Input
+                      id="balanceDrainWindow"
+                      type="number"
+                      min={5}
+                      max={1440}
+                      value={balanceDrainConfig.windowMinutes}
+                      {...createNumberInputHandlers(
+                        value => updateBalanceDrain({ windowMinutes: value }),
+                        str => Number.parseInt(str, 10),
+                        { min: 5, max: 1440 },
+                      )}
+                    />
+                  </div>
+                </div>
+              )}
+
+              <div className="flex items-center justify-between">
+                <div className="space-y-0.5">
+                  <Label>{t('alerts.keyExpiry.enabled')}</Label>
+                  <p className="text-sm text-muted-foreground">
+                    {t('alerts.keyExpiry.description')}
+                  </p>
+                </div>
+                <Switch
+                  checked={keyExpiryConfig.enabled}
+                  onCheckedChange={checked => updateKeyExpiry({ enabled: checked })}
+                />
+              </div>
+
+              {keyExpiryConfig.enabled && (
+                <div className="space-y-2">
+                  <Label htmlFor="keyExpiryDays">{t('alerts.keyExpiry.daysBefore')}</Label>
+                  <Input
+                    id="keyExpiryDays"
+                    type="number"
+                    min={1}
+                    max={90}
+                    value={keyExpiryConfig.daysBefore}
+                    {...createNumberInputHandlers(
+                      value => updateKeyExpiry({ daysBefore: value }),
+                      str => Number.parseInt(str, 10),
+                      { min: 1, max: 90 },
+                    )}
+                  />
+                </div>
+              )}
+            </>
+          )}
+
+          <KeyExpirationList daysBefore={keyExpiryConfig.daysBefore} />
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('limits.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('limits.enabled')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('limits.description')}
+              </p>
+            </div>
+            <Switch
+              checked={limitsConfig.enabled}
+              onCheckedChange={checked => updateLimits({ enabled: checked })}
+            />
+          </div>
+
+          {limitsConfig.enabled && (
+            <>
+              <div className="space-y-2">
+                <Label htmlFor="limitsUnit">{t('limits.unit')}</Label>
+                <Select
+                  id="limitsUnit"
+                  value={limitsConfig.unit}
+                  onChange={e => updateLimits({ unit: e.target.value as LimitUnit })}
+                >
+                  <option value="tokens">{t('limits.units.tokens')}</option>
+                  <option value="messages">{t('limits.units.messages')}</option>
+                </Select>
+              </div>
+              <div className="grid grid-cols-2 gap-4">
+                <div className="space-y-2">
+                  <Label htmlFor="limitsBlock">{t('limits.blockLimit')}</Label>
+                  <Input
+                    id="limitsBlock"
+                    type="number"
+                    min={0}
+                    value={limitsConfig.blockLimit}
+                    onChange={e => updateLimits({ blockLimit: Math.max(0, Math.floor(Number(e.target.value) || 0)) })}
+                  />
+                </div>
+                <div className="space-y-2">
+                  <Label htmlFor="limitsWeekly">{t('limits.weeklyLimit')}</Label>
+                  <Input
+                    id="limitsWeekly"
+                    type="number"
+                    min={0}
+                    value={limitsConfig.weeklyLimit}
+                    onChange={e => updateLimits({ weeklyLimit: Math.max(0, Math.floor(Number(e.target.value) || 0)) })}
+                  />
+                </div>
+              </div>
+              <p className="text-sm text-muted-foreground">
+                {t('limits.limitDescription')}
+              </p>
+              <div className="grid grid-cols-2 gap-4">
+                <div className="space-y-2">
+                  <Label htmlFor="limitsNear">{t('limits.nearPercent')}</Label>
+                  <Input
+                    id="limitsNear"
+                    type="number"
+                    min={0}
+                    max={100}
+                    value={limitsConfig.nearPercent}
+                    onChange={e => updateLimits({ nearPercent: Number(e.target.value) || 0 })}
+                  />
+                </div>
+                <div className="space-y-2">
+                  <Label htmlFor="limitsOver">{t('limits.overPercent')}</Label>
+                  <Input
+                    id="limitsOver"
+                    type="number"
+                    min={0}
+                    max={100}
+                    value={limitsConfig.overPercent}
+                    onChange={e => updateLimits({ overPercent: Number(e.target.value) || 0 })}
+                  />
+                </div>
+              </div>
+            </>
+          )}
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('guard.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('guard.enabled')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('guard.description')}
+              </p>
+            </div>
+            <Switch
+              checked={guardConfig.enabled}
+              onCheckedChange={checked => updateGuard({ enabled: checked })}
+            />
+          </div>
+
+          {guardConfig.enabled && (
+            <>
+              <div className="grid grid-cols-2 gap-4">
+                <div className="space-y-2">
+                  <Label htmlFor="guardPort">{t('guard.port')}</Label>
+                  <Input
+                    id="guardPort"
+                    type="number"
+                    min={1024}
+                    max={65535}
+                    value={guardConfig.port}
+                    onChange={e => updateGuard({ port: Number(e.target.value) || DEFAULT_GUARD_CONFIG.port })}
+                  />
+                </div>
+                <div className="space-y-2">
+                  <Label htmlFor="guardHeadroom">{t('guard.minHeadroom')}</Label>
+                  <Input
+                    id="guardHeadroom"
+                    type="number"
+                    min={0}
+                    step={0.5}
+                    value={guardConfig.minHeadroom}
+                    onChange={e => updateGuard({ minHeadroom: Math.max(0, Number(e.target.value) || 0) })}
+                  />
+                </div>
+              </div>
+              <p className="text-sm text-muted-foreground">
+                {t('guard.minHeadroomDescription')}
+              </p>
+              <div className="space-y-2">
+                <Label htmlFor="guardBudgetTier">{t('guard.budgetTier')}</Label>
+                <Select
+                  id="guardBudgetTier"
+                  value={guardConfig.budgetTier ?? 'soft'}
+                  onChange={e => updateGuard({ budgetTier: e.target.value as GuardBudgetTier })}
+                >
+                  <option value="soft">{t('guard.budgetTiers.soft')}</option>
+                  <option value="hard">{t('guard.budgetTiers.hard')}</option>
+                </Select>
+                <p className="text-sm text-muted-foreground">
+                  {t('guard.budgetTierDescription')}
+                </p>
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="guardToken">{t('guard.token')}</Label>
+                <div className="flex gap-2">
+                  <Input
+                    id="guardToken"
+                    readOnly
+                    value={guardConfig.token}
+                    placeholder={t('guard.tokenPlaceholder')}
+                    className="font-mono text-sm select-text"
+                  />
+                  <Button variant="outline" onClick={handleGenerateGuardToken} className="shrink-0">
+                    {guardConfig.token ? t('guard.regenerateToken') : t('guard.generateToken')}
+                  </Button>
+                </div>
+                {guardTokenError && <p className="text-sm text-red-500">{guardTokenError}</p>}
+              </div>
+              <div className="space-y-2">
+                <Label>{t('guard.example')}</Label>
+                <code className="block bg-muted px-3 py-2 rounded text-xs font-mono select-text cursor-text break-all">
+                  {`curl -s -X POST http://127.0.0.1:${guardConfig.port}/guard/check -H "Authorization: Bearer ${guardConfig.token || '<token>'}" -d '{"estimatedCost": 2}'`}
+                </code>
+                <p className="text-sm text-muted-foreground">
+                  {t('guard.exampleDescription')}
+                </p>
+              </div>
+            </>
+          )}
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('appLock.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('appLock.enabled')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('appLock.description')}
+              </p>
+            </div>
+            <Switch
+              checked={appLockConfig.enabled}
+              onCheckedChange={checked => updateAppLock({ enabled: checked })}
+            />
+          </div>
+
+          {appLockConfig.enabled && (
+            <div className="space-y-2">
+              <Label htmlFor="appLockGrace">{t('appLock.graceMinutes')}</Label>
+              <Input
+                id="appLockGrace"
+                type="number"
+                min={0}
+                max={60}
+                value={appLockConfig.graceMinutes}
+                onChange={e => updateAppLock({ graceMinutes: Math.min(60, Math.max(0, Math.floor(Number(e.target.value) || 0))) })}
+                className="w-32"
+              />
+              <p className="text-sm text-muted-foreground">
+                {t('appLock.graceMinutesDescription')}
+              </p>
+            </div>
+          )}
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('integrations.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('integrations.notion.enabled')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('integrations.notion.description')}
+              </p>
+            </div>
+            <Switch
+              checked={notionConfig.enabled}
+              onCheckedChange={checked => updateNotion({ enabled: checked })}
+            />
+          </div>
+
+          {notionConfig.enabled && (
+            <>
+              <div className="space-y-2">
+                <Label htmlFor="notionToken">{t('integrations.notion.token')}</Label>
+                <Input
+                  id="notionToken"
+                  type="password"
+                  value={notionConfig.token}
+                  onChange={e => updateNotion({ token: e.target.value })}
+                  placeholder="ntn_..."
+                  className="font-mono text-sm"
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="notionDatabaseId">{t('integrations.notion.databaseId')}</Label>
+                <Input
+                  id="notionDatabaseId"
+                  value={notionConfig.databaseId}
+                  onChange={e => updateNotion({ databaseId: e.target.value })}
+                  className="font-mono text-sm"
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="notionTitleProperty">{t('integrations.notion.titleProperty')}</Label>
+                <Input
+                  id="notionTitleProperty"
+                  value={notionConfig.titleProperty}
+                  onChange={e => updateNotion({ titleProperty: e.target.value })}
+                />
+                <p className="text-sm text-muted-foreground">
+                  {t('integrations.notion.titlePropertyDescription')}
+                </p>
+              </div>
+              <IntegrationStatusLine id="notion" />
+            </>
+          )}
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('integrations.emailDigest.enabled')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('integrations.emailDigest.description')}
+              </p>
+            </div>
+            <Switch
+              checked={emailDigestConfig.enabled}
+              onCheckedChange={checked => updateEmailDigest({ enabled: checked })}
+            />
+          </div>
+
+          {emailDigestConfig.enabled && (
+            <>
+              <div className="space-y-2">
+                <Label htmlFor="smtpUrl">{t('integrations.emailDigest.smtpUrl')}</Label>
+                <Input
+                  id="smtpUrl"
+                  value={emailDigestConfig.smtpUrl}
+                  onChange={e => updateEmailDigest({ smtpUrl: e.target.value })}
+                  placeholder="smtps://smtp.example.com:465"
+                  className="font-mono text-sm"
+                />
+                <p className="text-sm text-muted-foreground">
+                  {t('integrations.emailDigest.smtpUrlDescription')}
+                </p>
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="smtpUsername">{t('integrations.emailDigest.username')}</Label>
+                <Input
+                  id="smtpUsername"
+                  value={emailDigestConfig.username}
+                  onChange={e => updateEmailDigest({ username: e.target.value })}
+                />
+              </div>
+              <EmailDigestActions disabled={hasChanges || !emailDigestConfig.username.trim()} />
+              <div className="space-y-2">
+                <Label htmlFor="digestFrom">{t('integrations.emailDigest.from')}</Label>
+                <Input
+                  id="digestFrom"
+                  type="email"
+                  value={emailDigestConfig.from}
+                  onChange={e => updateEmailDigest({ from: e.target.value })}
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="digestTo">{t('integrations.emailDigest.to')}</Label>
+                <Input
+                  id="digestTo"
+                  value={emailDigestConfig.to.join(', ')}
+                  onChange={e => updateEmailDigest({
+                    to: e.target.value.split(',').map(address => address.trim()).filter(Boolean),
+                  })}
+                />
+                <p className="text-sm text-muted-foreground">
+                  {t('integrations.emailDigest.toDescription')}
+                </p>
+              </div>
+              <div className="grid grid-cols-2 gap-4">
+                <div className="space-y-2">
+                  <Label htmlFor="digestWeekday">{t('integrations.emailDigest.weekday')}</Label>
+                  <Select
+                    id="digestWeekday"
+                    value={String(emailDigestConfig.weekday)}
+                    onChange={e => updateEmailDigest({ weekday: Number(e.target.value) })}
+                  >
+                    {WEEKDAY_KEYS.map((key, index) => (
+                      <option key={key} value={index}>{t(`integrations.emailDigest.weekdays.${key}`)}</option>
+                    ))}
+                  </Select>
+                </div>
+                <div className="space-y-2">
+                  <Label htmlFor="digestHour">{t('integrations.emailDigest.hour')}</Label>
+                  <Input
+                    id="digestHour"
+                    type="number"
+                    min={0}
+                    max={23}
+                    value={emailDigestConfig.hour}
+                    onChange={e => updateEmailDigest({ hour: Math.min(23, Math.max(0, Number(e.target.value) || 0)) })}
+                  />
+                </div>
+              </div>
+              <IntegrationStatusLine id="emailDigest" />
+            </>
+          )}
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>{t('integrations.remoteSnapshot.enabled')}</Label>
+              <p className="text-sm text-muted-foreground">
+                {t('integrations.remoteSnapshot.description')}
+              </p>
+            </div>
+            <Switch
+              checked={remoteSnapshotConfig.enabled}
+              onCheckedChange={checked => updateRemoteSnapshot({ enabled: checked })}
+            />
+          </div>
+
+          {remoteSnapshotConfig.enabled && (
+            <>
+              <div className="space-y-2">
+                <Label htmlFor="snapshotUrl">{t('integrations.remoteSnapshot.url')}</Label>
+                <Input
+                  id="snapshotUrl"
+                  value={remoteSnapshotConfig.url}
+                  onChange={e => updateRemoteSnapshot({ url: e.target.value })}
+                  placeholder="https://dav.example.com/tokenmeter/snapshot.json"
+                  className="font-mono text-sm"
+                />
+                <p className="text-sm text-muted-foreground">
+                  {t('integrations.remoteSnapshot.urlDescription')}
+                </p>
+              </div>
+              <SnapshotPassphraseEditor canPush={!hasChanges && !!remoteSnapshotConfig.url.trim()} />
+              <IntegrationStatusLine id="remoteSnapshot" />
+            </>
+          )}
+
+          <Separator />
+
+          <div className="space-y-2">
+            <Label>{t('integrations.hooks.title')}</Label>
+            <p className="text-sm text-muted-foreground">
+              {t('integrations.hooks.description')}
+            </p>
+            {hooks.map((hook, index) => (
+              // eslint-disable-next-line react/no-array-index-key -- names are edited in place
+              <div key={index} className="space-y-2 rounded-md border p-3">
+                <div className="flex items-center gap-2">
+                  <Input
+                    value={hook.name}
+                    onChange={e => updateHook(index, { name: e.target.value })}
+                    placeholder={t('integrations.hooks.name')}
+                    className="flex-1"
+                  />
+                  <Switch
+                    checked={hook.enabled}
+                    onCheckedChange={checked => updateHook(index, { enabled: checked })}
+                  />
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={() => removeHook(index)}
+                    className="shrink-0"
+                  >
+                    <X className="w-4 h-4" />
+                  </Button>
+                </div>
+                <Input
+                  value={hook.command}
+                  onChange={e => updateHook(index, { command: e.target.value })}
+                  placeholder="/usr/local/bin/sync-usage --team"
+                  className="font-mono text-sm"
+                />
+                <IntegrationStatusLine id={`hook:${hook.name}`} />
+              </div>
+            ))}
+            <Button variant="outline" size="sm" onClick={addHook}>
+              <Plus className="w-4 h-4 mr-1" />
+              {t('integrations.hooks.add')}
+            </Button>
+          </div>
+        </CardContent>
+      </Card>
+
+      <UsageImportCard />
+
+      <Card>
+        <CardHeader>
+          <CardTitle>{t('timeouts.title')}</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <p className="text-sm text-muted-foreground">
+            {t('timeouts.description')}
+          </p>
+          <div className="grid grid-cols-2 gap-4">
+            {TIMEOUT_FIELDS.map(({ key, label, min, max }) => (
+              <div key={key} className="space-y-2">
+                <Label htmlFor={`timeout-${key}`}>{t(`timeouts.${label}`)}</Label>
+                <Input
+                  id={`timeout-${key}`}
+                  type="number"
+                  min={timeoutsSchema?.[key]?.minimum ?? min}
+                  max={timeoutsSchema?.[key]?.maximum ?? max}
+                  value={timeoutsConfig[key]}
+                  onChange={e => updateTimeouts({ [key]: Number(e.target.value) || DEFAULT_TIMEOUTS_CONFIG[key] })}
+                />
+              </div>
+            ))}
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="providerCacheTtl">{t('timeouts.providerCacheTtl')}</Label>
+            <Input
+              id="providerCacheTtl"
+              type="number"
+              min={0}
+              max={86400}
+              value={currentConfig.providerCacheTtlSecs ?? 300}
+              onChange={e => updateConfig({ providerCacheTtlSecs: Math.max(0, Number(e.target.value) || 0) })}
+            />
+            <p className="text-sm text-muted-foreground">
+              {t('timeouts.providerCacheTtlDescription')}
+            </p>
+          </div>
+          <CcusageCompatibilityLine />
         </CardContent>
       </Card>
     </div>
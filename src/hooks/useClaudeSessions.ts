@@ -0,0 +1,13 @@
+import { useQuery } from '@tanstack/react-query'
+import { getClaudeSessions } from '@/lib/api'
+
+const SESSION_REFRESH_INTERVAL = 5 * 60 * 1000
+
+export function useClaudeSessions() {
+  return useQuery({
+    queryKey: ['claudeSessions'],
+    queryFn: getClaudeSessions,
+    refetchInterval: SESSION_REFRESH_INTERVAL,
+    retry: false,
+  })
+}
@@ -1,5 +1,5 @@
 import type { SupportedLanguage } from '@/i18n'
-import type { AppConfig } from '@/types'
+import type { AppConfig, ProfileList } from '@/types'
 import { useQueryClient } from '@tanstack/react-query'
 import { listen } from '@tauri-apps/api/event'
 import { useEffect } from 'react'
@@ -18,6 +18,8 @@ export function useConfigEvents() {
 
   useEffect(() => {
     let unlisten: (() => void) | undefined
+    let unlistenProfile: (() => void) | undefined
+    let unlistenUnlock: (() => void) | undefined
 
     async function setupListener() {
       unlisten = await listen<AppConfig>('config-updated', (event) => {
@@ -25,12 +27,23 @@ export function useConfigEvents() {
         queryClient.setQueryData(['config'], config)
         i18nChangeLanguage(normalizeLanguage(config.language))
       })
+      unlistenProfile = await listen<ProfileList>('profile-switched', (event) => {
+        queryClient.setQueryData(['profiles'], event.payload)
+        queryClient.invalidateQueries({ queryKey: ['providers'] })
+        queryClient.invalidateQueries({ queryKey: ['providerStatus'] })
+      })
+      // Providers are refused while the app lock is engaged
+      unlistenUnlock = await listen('app-unlocked', () => {
+        queryClient.invalidateQueries({ queryKey: ['providers'] })
+      })
     }
 
     setupListener().catch(() => {})
 
     return () => {
       unlisten?.()
+      unlistenProfile?.()
+      unlistenUnlock?.()
     }
   }, [queryClient])
 }
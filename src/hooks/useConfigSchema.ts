@@ -0,0 +1,10 @@
+import { useQuery } from '@tanstack/react-query'
+import { getConfigSchema } from '@/lib/api'
+
+export function useConfigSchema() {
+  return useQuery({
+    queryKey: ['configSchema'],
+    queryFn: getConfigSchema,
+    staleTime: Infinity,
+  })
+}
@@ -0,0 +1,33 @@
+import type { Credential } from '@/types'
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { clearCredential, getStoredCredentials, setCredential } from '@/lib/api'
+
+export function useStoredCredentials() {
+  return useQuery({
+    queryKey: ['storedCredentials'],
+    queryFn: getStoredCredentials,
+  })
+}
+
+export function useSetCredential() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: ({ credential, value }: { credential: Credential, value: string }) =>
+      setCredential(credential, value),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['storedCredentials'] })
+    },
+  })
+}
+
+export function useClearCredential() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: clearCredential,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['storedCredentials'] })
+    },
+  })
+}
@@ -0,0 +1,10 @@
+import { useQuery } from '@tanstack/react-query'
+import { getDiagnostics } from '@/lib/api'
+
+export function useDiagnostics() {
+  return useQuery({
+    queryKey: ['diagnostics'],
+    queryFn: getDiagnostics,
+    refetchInterval: 30_000,
+  })
+}
@@ -0,0 +1,11 @@
+import { useQuery } from '@tanstack/react-query'
+import { getDualSeries } from '@/lib/api'
+
+/** Chart series for the last `days` days, refetched whenever `fetchedAt` (the usage refresh time) changes. */
+export function useDualSeries(days: number, fetchedAt?: string | null) {
+  return useQuery({
+    queryKey: ['dualSeries', days, fetchedAt],
+    queryFn: () => getDualSeries(days),
+    retry: false,
+  })
+}
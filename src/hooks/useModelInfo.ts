@@ -0,0 +1,11 @@
+import { useQuery } from '@tanstack/react-query'
+import { getModelInfo } from '@/lib/api'
+
+export function useModelInfo(model: string) {
+  return useQuery({
+    queryKey: ['modelInfo', model],
+    queryFn: () => getModelInfo(model),
+    staleTime: Infinity,
+    retry: false,
+  })
+}
@@ -0,0 +1,12 @@
+import { useConfig } from '@/hooks/useUsageData'
+import { setPrivacy } from '@/types'
+
+/**
+ * Keeps formatCost and formatTokens in line with the configured privacy mode.
+ * Call it in a root component so every child renders with the current setting.
+ */
+export function usePrivacyMode(): boolean {
+  const { data: config } = useConfig()
+  setPrivacy(config?.menuBar)
+  return config?.menuBar.privacyMode ?? false
+}
@@ -0,0 +1,21 @@
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { getProfiles, switchProfile } from '@/lib/api'
+
+export function useProfiles() {
+  return useQuery({
+    queryKey: ['profiles'],
+    queryFn: getProfiles,
+  })
+}
+
+export function useSwitchProfile() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: switchProfile,
+    onSuccess: (profiles) => {
+      queryClient.setQueryData(['profiles'], profiles)
+      queryClient.invalidateQueries({ queryKey: ['providers'] })
+    },
+  })
+}
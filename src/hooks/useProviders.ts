@@ -1,5 +1,6 @@
-import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
-import { deleteProvider, getProviders, saveProvider, testProvider } from '@/lib/api'
+import type { ApiProvider } from '@/types'
+import { keepPreviousData, useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { deleteProvider, getProviderDaily, getProviderHistory, getProviders, getProviderStatus, getTrashedProviders, purgeTrash, reorderProviders, restoreProvider, saveProvider, setProvidersEnabled, testProvider, validateProvider } from '@/lib/api'
 
 export function useProviders() {
   return useQuery({
@@ -9,6 +10,27 @@ export function useProviders() {
   })
 }
 
+export function useProviderStatus() {
+  return useQuery({
+    queryKey: ['providerStatus'],
+    queryFn: getProviderStatus,
+  })
+}
+
+export function useProviderHistory(id: string, range: number) {
+  return useQuery({
+    queryKey: ['providerHistory', id, range],
+    queryFn: () => getProviderHistory(id, range),
+  })
+}
+
+export function useProviderDaily(id: string, range: number) {
+  return useQuery({
+    queryKey: ['providerDaily', id, range],
+    queryFn: () => getProviderDaily(id, range),
+  })
+}
+
 export function useSaveProvider() {
   const queryClient = useQueryClient()
 
@@ -16,6 +38,31 @@ export function useSaveProvider() {
     mutationFn: saveProvider,
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ['providers'] })
+      // Key expirations are listed in diagnostics
+      queryClient.invalidateQueries({ queryKey: ['diagnostics'] })
+    },
+  })
+}
+
+export function useSetProvidersEnabled() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: ({ ids, enabled }: { ids: string[] | null, enabled: boolean }) =>
+      setProvidersEnabled(ids, enabled),
+    onSuccess: (providers) => {
+      queryClient.setQueryData(['providers'], providers)
+    },
+  })
+}
+
+export function useReorderProviders() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: reorderProviders,
+    onSuccess: (providers) => {
+      queryClient.setQueryData(['providers'], providers)
     },
   })
 }
@@ -27,6 +74,37 @@ export function useDeleteProvider() {
     mutationFn: deleteProvider,
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ['providers'] })
+      queryClient.invalidateQueries({ queryKey: ['trashedProviders'] })
+    },
+  })
+}
+
+export function useTrashedProviders() {
+  return useQuery({
+    queryKey: ['trashedProviders'],
+    queryFn: getTrashedProviders,
+  })
+}
+
+export function useRestoreProvider() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: restoreProvider,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['providers'] })
+      queryClient.invalidateQueries({ queryKey: ['trashedProviders'] })
+    },
+  })
+}
+
+export function usePurgeTrash() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: purgeTrash,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['trashedProviders'] })
     },
   })
 }
@@ -36,3 +114,13 @@ export function useTestProvider() {
     mutationFn: testProvider,
   })
 }
+
+/** Static problems with the provider being edited, rechecked as it changes. */
+export function useProviderIssues(provider: ApiProvider | null) {
+  return useQuery({
+    queryKey: ['providerIssues', provider],
+    queryFn: () => (provider ? validateProvider(provider) : []),
+    enabled: provider !== null,
+    placeholderData: keepPreviousData,
+  })
+}
@@ -0,0 +1,63 @@
+import { useQueryClient } from '@tanstack/react-query'
+import { useEffect } from 'react'
+import { forceFullRefresh } from '@/lib/api'
+
+interface Hotkey {
+  mod: boolean
+  cmd: boolean
+  ctrl: boolean
+  alt: boolean
+  shift: boolean
+  key: string
+}
+
+const IS_MAC = navigator.userAgent.includes('Mac')
+
+/** Parses combos such as "CmdOrCtrl+Shift+R" (validated by the backend on save). */
+function parseHotkey(hotkey: string): Hotkey | null {
+  const parts = hotkey.split('+').map(part => part.trim().toLowerCase())
+  const key = parts.pop()
+  if (!key || parts.length === 0)
+    return null
+  return {
+    mod: parts.includes('cmdorctrl'),
+    cmd: parts.includes('cmd'),
+    ctrl: parts.includes('ctrl'),
+    alt: parts.includes('alt'),
+    shift: parts.includes('shift'),
+    key,
+  }
+}
+
+function matches(hotkey: Hotkey, event: KeyboardEvent): boolean {
+  const meta = hotkey.cmd || (hotkey.mod && IS_MAC)
+  const ctrl = hotkey.ctrl || (hotkey.mod && !IS_MAC)
+  return event.key.toLowerCase() === hotkey.key
+    && event.metaKey === meta
+    && event.ctrlKey === ctrl
+    && event.altKey === hotkey.alt
+    && event.shiftKey === hotkey.shift
+}
+
+/** Runs a full refresh when the configured shortcut is pressed in this window. */
+export function useRefreshHotkey(hotkey: string | null | undefined) {
+  const queryClient = useQueryClient()
+
+  useEffect(() => {
+    const parsed = hotkey ? parseHotkey(hotkey) : null
+    if (!parsed)
+      return
+
+    function onKeyDown(event: KeyboardEvent) {
+      if (!parsed || event.repeat || !matches(parsed, event))
+        return
+      event.preventDefault()
+      forceFullRefresh()
+        .then(data => queryClient.setQueryData(['usage'], data))
+        .catch(err => console.warn('Full refresh failed:', err))
+    }
+
+    window.addEventListener('keydown', onKeyDown)
+    return () => window.removeEventListener('keydown', onKeyDown)
+  }, [hotkey, queryClient])
+}
@@ -0,0 +1,13 @@
+import { useQuery } from '@tanstack/react-query'
+import { getSessionBlocks } from '@/lib/api'
+
+const BLOCK_REFRESH_INTERVAL = 60 * 1000
+
+export function useSessionBlocks() {
+  return useQuery({
+    queryKey: ['sessionBlocks'],
+    queryFn: getSessionBlocks,
+    refetchInterval: BLOCK_REFRESH_INTERVAL,
+    retry: false,
+  })
+}
@@ -0,0 +1,31 @@
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { clearSnapshotPassphrase, hasSnapshotPassphrase, setSnapshotPassphrase } from '@/lib/api'
+
+export function useHasSnapshotPassphrase() {
+  return useQuery({
+    queryKey: ['snapshotPassphrase'],
+    queryFn: hasSnapshotPassphrase,
+  })
+}
+
+export function useSetSnapshotPassphrase() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: setSnapshotPassphrase,
+    onSuccess: () => {
+      queryClient.setQueryData(['snapshotPassphrase'], true)
+    },
+  })
+}
+
+export function useClearSnapshotPassphrase() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: clearSnapshotPassphrase,
+    onSuccess: () => {
+      queryClient.setQueryData(['snapshotPassphrase'], false)
+    },
+  })
+}
@@ -0,0 +1,13 @@
+import { useQuery } from '@tanstack/react-query'
+import { getTopicsBreakdown } from '@/lib/api'
+
+const TOPICS_REFRESH_INTERVAL = 5 * 60 * 1000
+
+export function useTopicsBreakdown(days?: number) {
+  return useQuery({
+    queryKey: ['topicsBreakdown', days],
+    queryFn: () => getTopicsBreakdown(days),
+    refetchInterval: TOPICS_REFRESH_INTERVAL,
+    retry: false,
+  })
+}
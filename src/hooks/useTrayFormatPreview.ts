@@ -0,0 +1,10 @@
+import { keepPreviousData, useQuery } from '@tanstack/react-query'
+import { validateTrayFormat } from '@/lib/api'
+
+export function useTrayFormatPreview(format: string) {
+  return useQuery({
+    queryKey: ['trayFormatPreview', format],
+    queryFn: () => validateTrayFormat(format),
+    placeholderData: keepPreviousData,
+  })
+}
@@ -1,4 +1,4 @@
-import type { ApiProvider, AppConfig, UsageSummary } from '@/types'
+import type { ApiProvider, AppConfig, BootPayload, ClaudeSession, ConfigSchema, Credential, Diagnostics, DualSeries, FeatureInfo, JournalPoint, KeyUsage, MemberUsage, ModelInfo, OrgComparison, PerfStat, ProfileList, ProviderDailyUsage, ProviderHistoryPoint, ProviderIssue, ProviderStatus, ProviderUsageResult, SessionBlock, TimeProfile, TopicUsage, TrashedProvider, TrayFormatPreview, UsageSummary } from '@/types'
 import { invoke } from '@tauri-apps/api/core'
 
 export async function getUsageSummary(): Promise<UsageSummary> {
@@ -9,14 +9,134 @@ export async function refreshUsage(): Promise<UsageSummary> {
   return invoke<UsageSummary>('refresh_usage')
 }
 
+export async function forceFullRefresh(): Promise<UsageSummary> {
+  return invoke<UsageSummary>('force_full_refresh')
+}
+
+export async function importUsageDump(source: string, content: string): Promise<UsageSummary> {
+  return invoke<UsageSummary>('import_usage_dump', { source, content })
+}
+
 export async function getConfig(): Promise<AppConfig> {
   return invoke<AppConfig>('get_config')
 }
 
+export async function getBootPayload(): Promise<BootPayload> {
+  return invoke<BootPayload>('get_boot_payload')
+}
+
 export async function saveConfig(config: AppConfig): Promise<void> {
   return invoke('save_config', { config })
 }
 
+export async function getConfigSchema(): Promise<ConfigSchema> {
+  return invoke<ConfigSchema>('get_config_schema')
+}
+
+export async function listFeatures(): Promise<FeatureInfo[]> {
+  return invoke<FeatureInfo[]>('list_features')
+}
+
+export async function validateTrayFormat(format: string): Promise<TrayFormatPreview> {
+  return invoke<TrayFormatPreview>('validate_tray_format', { format })
+}
+
+export async function getModelInfo(model: string): Promise<ModelInfo | null> {
+  return invoke<ModelInfo | null>('get_model_info', { model })
+}
+
+export async function getTimeProfile(days?: number): Promise<TimeProfile> {
+  return invoke<TimeProfile>('get_time_profile', { days })
+}
+
+export async function getSessionBlocks(): Promise<SessionBlock[]> {
+  return invoke<SessionBlock[]>('get_session_blocks')
+}
+
+export async function getClaudeSessions(): Promise<ClaudeSession[]> {
+  return invoke<ClaudeSession[]>('get_claude_sessions')
+}
+
+export async function getTopicsBreakdown(days?: number): Promise<TopicUsage[]> {
+  return invoke<TopicUsage[]>('get_topics_breakdown', { days })
+}
+
+export async function getDualSeries(days?: number): Promise<DualSeries> {
+  return invoke<DualSeries>('get_dual_series', { days })
+}
+
+export async function getUsageJournal(date?: string): Promise<JournalPoint[]> {
+  return invoke<JournalPoint[]>('get_usage_journal', { date })
+}
+
+export async function getKeyBreakdown(source: string): Promise<KeyUsage[]> {
+  return invoke<KeyUsage[]>('get_key_breakdown', { source })
+}
+
+export async function getMemberLeaderboard(limit?: number): Promise<MemberUsage[]> {
+  return invoke<MemberUsage[]>('get_member_leaderboard', { limit })
+}
+
+export async function getOrgComparison(): Promise<OrgComparison | null> {
+  return invoke<OrgComparison | null>('get_org_comparison')
+}
+
+export async function getDiagnostics(): Promise<Diagnostics> {
+  return invoke<Diagnostics>('get_diagnostics')
+}
+
+export async function getPerfStats(): Promise<PerfStat[]> {
+  return invoke<PerfStat[]>('get_perf_stats')
+}
+
+export async function createSupportBundle(): Promise<string> {
+  return invoke<string>('create_support_bundle')
+}
+
+export async function setSmtpPassword(password: string): Promise<void> {
+  return invoke('set_smtp_password', { password })
+}
+
+export async function sendTestDigest(): Promise<void> {
+  return invoke('send_test_digest')
+}
+
+export async function setSnapshotPassphrase(passphrase: string): Promise<void> {
+  return invoke('set_snapshot_passphrase', { passphrase })
+}
+
+export async function clearSnapshotPassphrase(): Promise<void> {
+  return invoke('clear_snapshot_passphrase')
+}
+
+export async function hasSnapshotPassphrase(): Promise<boolean> {
+  return invoke<boolean>('has_snapshot_passphrase')
+}
+
+export async function pushSnapshotNow(): Promise<void> {
+  return invoke('push_snapshot_now')
+}
+
+export async function setCredential(credential: Credential, value: string): Promise<void> {
+  return invoke('set_credential', { credential, value })
+}
+
+export async function clearCredential(credential: Credential): Promise<void> {
+  return invoke('clear_credential', { credential })
+}
+
+export async function getStoredCredentials(): Promise<Credential[]> {
+  return invoke<Credential[]>('get_stored_credentials')
+}
+
+export async function sendTestPush(): Promise<void> {
+  return invoke('send_test_push')
+}
+
+export async function generateGuardToken(): Promise<string> {
+  return invoke<string>('generate_guard_token')
+}
+
 export async function getProviders(): Promise<ApiProvider[]> {
   return invoke<ApiProvider[]>('get_providers')
 }
@@ -29,16 +149,73 @@ export async function deleteProvider(id: string): Promise<void> {
   return invoke('delete_provider', { id })
 }
 
+export async function getProviderHistory(id: string, range?: number): Promise<ProviderHistoryPoint[]> {
+  return invoke<ProviderHistoryPoint[]>('get_provider_history', { id, range })
+}
+
+export async function getProviderDaily(id: string, range?: number): Promise<ProviderDailyUsage[]> {
+  return invoke<ProviderDailyUsage[]>('get_provider_daily', { id, range })
+}
+
+export async function getProviderStatus(): Promise<ProviderStatus[]> {
+  return invoke<ProviderStatus[]>('get_provider_status')
+}
+
+export async function setProvidersEnabled(ids: string[] | null, enabled: boolean): Promise<ApiProvider[]> {
+  return invoke<ApiProvider[]>('set_providers_enabled', { ids, enabled })
+}
+
+export async function reorderProviders(ids: string[]): Promise<ApiProvider[]> {
+  return invoke<ApiProvider[]>('reorder_providers', { ids })
+}
+
+export async function getProfiles(): Promise<ProfileList> {
+  return invoke<ProfileList>('get_profiles')
+}
+
+export async function switchProfile(name: string): Promise<ProfileList> {
+  return invoke<ProfileList>('switch_profile', { name })
+}
+
+export async function getTrashedProviders(): Promise<TrashedProvider[]> {
+  return invoke<TrashedProvider[]>('get_trashed_providers')
+}
+
+export async function restoreProvider(id: string): Promise<void> {
+  return invoke('restore_provider', { id })
+}
+
+export async function purgeTrash(): Promise<void> {
+  return invoke('purge_trash')
+}
+
 export interface TestProviderResult {
   success: boolean
   data?: Record<string, unknown>
   error?: string
+  debug?: TestProviderDebug
+}
+
+/** What each stage of a provider test produced, up to the one that failed */
+export interface TestProviderDebug {
+  stdout?: string | null
+  stderr?: string | null
+  transformOutput?: string | null
+  console?: string[]
+  parsed?: ProviderUsageResult | null
+  fetchMs?: number | null
+  transformMs?: number | null
+  parseMs?: number | null
 }
 
 export async function testProvider(provider: ApiProvider): Promise<TestProviderResult> {
   return invoke('test_provider', { provider })
 }
 
+export async function validateProvider(provider: ApiProvider): Promise<ProviderIssue[]> {
+  return invoke('validate_provider', { provider })
+}
+
 export async function openDashboard(): Promise<void> {
   return invoke('open_dashboard')
 }
@@ -1,5 +1,5 @@
 import type { ClassValue } from 'clsx'
-import type { DailyUsage } from '@/types'
+import type { DailyUsage, ModelSortKey, ModelUsage } from '@/types'
 import { clsx } from 'clsx'
 import { twMerge } from 'tailwind-merge'
 
@@ -37,6 +37,24 @@ export function normalizeDate(dateStr: string): string {
   return `${year}-${month}-${day}`
 }
 
+/**
+ * First day (YYYY-MM-DD) of the billing cycle containing `today`. Cycles that
+ * start past the end of a short month start on its last day.
+ */
+export function billingCycleStart(today: Date, cycleDay: number): string {
+  const cycleDate = (year: number, month: number) => {
+    const lastDay = new Date(year, month + 1, 0).getDate()
+    return new Date(year, month, Math.min(cycleDay, lastDay))
+  }
+  let start = cycleDate(today.getFullYear(), today.getMonth())
+  if (start.getDate() > today.getDate())
+    start = cycleDate(today.getFullYear(), today.getMonth() - 1)
+
+  const month = String(start.getMonth() + 1).padStart(2, '0')
+  const day = String(start.getDate()).padStart(2, '0')
+  return `${start.getFullYear()}-${month}-${day}`
+}
+
 /**
  * Convert YYYY-MM-DD date string to timestamp (local time)
  */
@@ -105,3 +123,59 @@ export function getDailyTotalTokens(
     + day.cacheReadInputTokens
   )
 }
+
+/**
+ * Resolve a model's display name from user-configured aliases, falling back to the model ID.
+ */
+export function getModelDisplayName(model: string, aliases?: Record<string, string>): string {
+  const alias = aliases?.[model]?.trim()
+  return alias || model
+}
+
+/**
+ * Sort model rows the same way the backend does: by `key`, then cost, then model ID,
+ * with the "Other" row last.
+ */
+export function sortModels(models: ModelUsage[], key: ModelSortKey = 'cost'): ModelUsage[] {
+  const tokens = (m: ModelUsage) => m.inputTokens + m.outputTokens
+  // Plain string order rather than locale order, as on the backend
+  const byId = (a: ModelUsage, b: ModelUsage) => Number(a.model > b.model) - Number(a.model < b.model)
+  const byKey = (a: ModelUsage, b: ModelUsage) => {
+    switch (key) {
+      case 'tokens':
+        return tokens(b) - tokens(a)
+      case 'name':
+        return byId(a, b)
+      default:
+        return 0
+    }
+  }
+  return [...models].sort((a, b) =>
+    Number(a.model === 'Other') - Number(b.model === 'Other')
+    || byKey(a, b)
+    || b.cost - a.cost
+    || byId(a, b),
+  )
+}
+
+export interface ProviderSection<T> {
+  group: string | null
+  providers: T[]
+}
+
+/**
+ * Splits providers, already sorted by the backend, into consecutive sections
+ * sharing a group; ungrouped providers form a section with a null group.
+ */
+export function groupProviders<T extends { group?: string | null }>(providers: T[]): ProviderSection<T>[] {
+  const sections: ProviderSection<T>[] = []
+  for (const provider of providers) {
+    const group = provider.group?.trim() || null
+    const last = sections.at(-1)
+    if (last && last.group === group)
+      last.providers.push(provider)
+    else
+      sections.push({ group, providers: [provider] })
+  }
+  return sections
+}
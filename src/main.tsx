@@ -4,7 +4,7 @@ import * as React from 'react'
 import ReactDOM from 'react-dom/client'
 import App from './App'
 import { initI18n } from './i18n'
-import { getConfig } from './lib/api'
+import { getBootPayload } from './lib/api'
 import { Tray } from './Tray'
 import './index.css'
 
@@ -25,8 +25,16 @@ function Root(): React.ReactElement {
 async function bootstrap() {
   let savedLanguage: string | null = null
   try {
-    const config = await getConfig()
-    savedLanguage = config.language ?? null
+    // One roundtrip seeds the config and last usage snapshot before first paint
+    const boot = await getBootPayload()
+    savedLanguage = boot.config.language ?? null
+    queryClient.setQueryData(['config'], boot.config)
+    if (boot.usage) {
+      const fetchedAt = boot.usage.meta?.fetchedAt ? Date.parse(boot.usage.meta.fetchedAt) : Number.NaN
+      queryClient.setQueryData(['usage'], boot.usage, {
+        updatedAt: Number.isNaN(fetchedAt) ? undefined : fetchedAt,
+      })
+    }
   }
   catch {
     // Ignore error, will use system language
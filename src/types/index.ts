@@ -25,11 +25,209 @@ export interface DailyUsage {
   models: ModelUsage[]
 }
 
+export interface SourceUsage {
+  id: string
+  label: string
+  today: UsageData
+  thisMonth: UsageData
+  models: ModelUsage[]
+  keys: KeyUsage[]
+  credits?: ProviderUsageResult | null
+}
+
+export interface ProviderUsageResult {
+  cost?: number | null
+  tokens?: number | null
+  used?: number | null
+  total?: number | null
+  /** Per-day usage, when the provider's transform reports a series */
+  daily?: ProviderDailyUsage[]
+}
+
+export interface ProviderDailyUsage {
+  date: string
+  cost?: number | null
+  tokens?: number | null
+}
+
+export interface ProviderHistoryPoint extends ProviderUsageResult {
+  fetchedAt: string
+}
+
+export interface KeyUsage {
+  key: string
+  today: UsageData
+  thisMonth: UsageData
+}
+
+export interface MemberUsage {
+  member: string
+  sources: string[]
+  today: UsageData
+  thisMonth: UsageData
+}
+
+export interface OrgComparison {
+  member: string | null
+  cost: number
+  orgAverage: number
+  percentile: number
+  memberCount: number
+}
+
+export interface ModelInfo {
+  id: string
+  name: string
+  provider: string
+  contextWindow: number | null
+  maxOutputTokens: number | null
+  knowledgeCutoff: string | null
+  releaseDate: string | null
+}
+
 export interface UsageSummary {
   today: UsageData
   thisMonth: UsageData
   dailyUsage: DailyUsage[]
   modelBreakdown: ModelUsage[]
+  sources: SourceUsage[]
+  trend?: TrendInfo | null
+  limits?: LimitUsage | null
+  activeBlockEnd?: string | null
+  meta?: RefreshMeta
+}
+
+export interface LimitUsage {
+  unit: LimitUnit
+  /** Usage in the active 5-hour block; zero when no block is active */
+  blockUsed: number
+  blockLimit: number
+  /** RFC 3339 end of the active block */
+  blockResetsAt?: string | null
+  /** Usage in the last seven days */
+  weeklyUsed: number
+  weeklyLimit: number
+}
+
+export interface RefreshMeta {
+  /** RFC 3339 local time the refresh finished */
+  fetchedAt?: string | null
+  durationMs: number
+  /** How each source was read, keyed by source id (e.g. "ccusage 15.2.0" or "native") */
+  sourceVersions: Record<string, string>
+  /** Error of the latest refresh attempt, when it failed and this summary is stale */
+  lastError?: string | null
+  /** Whether the summary came from a running tokenmeterd, which owns refreshes */
+  managedByAgent?: boolean
+}
+
+export interface TrendInfo {
+  baseline: TrendBaseline
+  baselineCost: number
+  changePercent: number
+}
+
+export interface HourBucket {
+  hour: number
+  tokens: number
+  cost: number
+  percent: number
+}
+
+export interface WeekdayBucket {
+  /** 0 = Monday, 6 = Sunday */
+  weekday: number
+  tokens: number
+  cost: number
+  percent: number
+}
+
+export interface PeakWindow {
+  startHour: number
+  /** Exclusive, wraps past midnight */
+  endHour: number
+  percent: number
+}
+
+export interface JournalPoint {
+  /** RFC 3339 local time of the save */
+  recordedAt: string
+  usage: DailyUsage
+}
+
+export interface SessionBlock {
+  /** RFC 3339 start of the 5-hour window */
+  startTime: string
+  endTime: string
+  isActive: boolean
+  elapsedMinutes: number
+  /** Zero once the window has closed */
+  remainingMinutes: number
+  inputTokens: number
+  outputTokens: number
+  cacheCreationInputTokens: number
+  cacheReadInputTokens: number
+  totalTokens: number
+  cost: number
+  /** Cost at the end of the window at the current rate; active blocks only */
+  projectedCost: number | null
+  models: string[]
+}
+
+/** One Claude Code conversation as reported by `ccusage session` */
+export interface ClaudeSession {
+  sessionId: string
+  projectPath: string
+  /** Local date (YYYY-MM-DD) of the last request */
+  lastActivity: string
+  inputTokens: number
+  outputTokens: number
+  cacheCreationTokens: number
+  cacheReadTokens: number
+  totalTokens: number
+  cost: number
+  models: string[]
+}
+
+/** Aligned cost and token arrays, one point per day or per week (Monday first) when bucketDays is 7 */
+export interface DualSeries {
+  bucketDays: number
+  dates: string[]
+  cost: number[]
+  /** All tokens, including cache tokens */
+  tokens: number[]
+  cacheTokens: number[]
+}
+
+/** Claude Code spending on conversations sharing one title */
+export interface TopicUsage {
+  /** Claude Code's conversation summary or first prompt; null groups untitled conversations */
+  title: string | null
+  sessions: number
+  totalTokens: number
+  cost: number
+  /** Local date (YYYY-MM-DD) of the latest request */
+  lastActivity: string
+}
+
+export interface TimeProfile {
+  days: number
+  totalTokens: number
+  totalCost: number
+  hours: HourBucket[]
+  weekdays: WeekdayBucket[]
+  /** Tokens per weekday (rows, Monday first) and hour (columns) */
+  heatmap: number[][]
+  peakWindow: PeakWindow | null
+}
+
+export type ProviderUnit = 'usd' | 'credits' | 'requests' | 'tokens'
+
+/** A problem found while statically validating a provider */
+export interface ProviderIssue {
+  /** Editor field it concerns, e.g. "fetch" or "transform" */
+  field: string
+  message: string
 }
 
 export interface ApiProvider {
@@ -38,17 +236,240 @@ export interface ApiProvider {
   enabled: boolean
   fetchScript: string
   transformScript: string
+  /** Result fields mapped to JSONPath expressions; used instead of the transform script when set */
+  transformPaths?: Record<string, string>
   env: Record<string, string>
+  /** Expiry date (YYYY-MM-DD) of env entries such as API keys, by variable name */
+  envExpiresAt?: Record<string, string>
   lastFetched?: string
   lastError?: string
+  /** Fetches failed in a row since the last success */
+  consecutiveFailures?: number
+  currency?: string
+  /** What the cost and used/total balance count; plain numbers when unset */
+  unit?: ProviderUnit | null
+  /** Word written after amounts instead of the unit's own */
+  unitLabel?: string | null
+  /** Built-in HTTP request; used instead of fetchScript when set */
+  request?: ProviderRequest | null
+  oauth?: ProviderOAuth | null
+  /** Requests run before the fetch whose responses set variables for it */
+  steps?: FetchStep[]
+  retry?: ProviderRetry
+  /** Section listed under in the tray menu and dashboard; ungrouped come first */
+  group?: string | null
+  /** Position in the provider list, set by reorderProviders */
+  sortOrder?: number
+  /** List in the tray menu; hidden providers are still fetched for the dashboard */
+  showInTray?: boolean
+}
+
+export interface ProviderRetry {
+  /** Retries after the first failed attempt (0-5) */
+  attempts: number
+  /** Delay before the first retry, doubled before each further one */
+  backoffMs: number
+}
+
+export type OAuthGrant = 'clientCredentials' | 'refreshToken'
+
+export interface ProviderOAuth {
+  grant: OAuthGrant
+  tokenUrl: string
+  clientId: string
+  clientSecret: string
+  scopes: string[]
+  /** Initial refresh token for the refreshToken grant */
+  refreshToken: string
 }
 
+export type HttpMethod = 'GET' | 'POST'
+
+export interface ProviderRequest {
+  method: HttpMethod
+  url: string
+  headers: Record<string, string>
+  body?: string | null
+  /** Fetches every page and combines their items before the transform */
+  pagination?: ProviderPagination | null
+  /** Sends a GraphQL operation as a POST body instead of `body` */
+  graphql?: GraphqlQuery | null
+}
+
+export interface GraphqlQuery {
+  query: string
+  /** JSON object of variables; ${VAR} is replaced from the provider env */
+  variables?: string | null
+}
+
+export interface FetchStep {
+  request: ProviderRequest
+  /** Dotted response paths keyed by the variable they set, used as ${NAME} afterwards */
+  extract: Record<string, string>
+}
+
+export type PaginationStrategy = 'cursor' | 'page' | 'nextUrl'
+
+export interface ProviderPagination {
+  strategy: PaginationStrategy
+  /** Dotted path to the array combined across pages; empty when the response is the array */
+  itemsPath: string
+  /** Dotted path to the next cursor or URL (cursor and nextUrl strategies) */
+  nextPath: string
+  /** Query parameter set to the cursor or page number (cursor and page strategies) */
+  param: string
+  maxPages: number
+}
+
+export interface ProfileList {
+  names: string[]
+  active: string
+}
+
+export type ProviderHealthLevel = 'unknown' | 'healthy' | 'degraded' | 'failing'
+
+export interface ProviderStatus {
+  id: string
+  name: string
+  enabled: boolean
+  level: ProviderHealthLevel
+  consecutiveFailures: number
+  /** RFC 3339 time of the last successful fetch */
+  lastSuccess: string | null
+  lastError: string | null
+}
+
+export interface TrashedProvider {
+  provider: ApiProvider
+  /** RFC 3339 UTC time the provider was deleted */
+  deletedAt: string
+}
+
+export type ColorScheme = 'standard' | 'highContrast' | 'colorblindSafe' | 'monochrome'
+
+export type PrivacyStyle = 'mask' | 'scale'
+
+export type ModelSortKey = 'cost' | 'tokens' | 'name'
+
+/** How weekly points of long-range charts combine their days */
+export type ChartAggregation = 'sum' | 'mean'
+
 export interface MenuBarConfig {
   format: string
   thresholdMode: 'fixed' | 'percentage'
   fixedBudget: number
   nearBudgetThresholdPercent: number
+  /** Budget on Saturdays/Sundays; unset uses fixedBudget, 0 disables weekend color coding */
+  weekendBudget?: number | null
+  /** Budget for the period's cost; unset or 0 leaves the period unbudgeted */
+  monthlyBudget?: number | null
+  /** Hard budget as a percent (above 100) of the soft budgets and limits */
+  hardBudgetPercent?: number | null
+  showRecentDays?: boolean
+  showTopSpender?: boolean
+  showProfiles?: boolean
+  verboseAccessibility?: boolean
+  colorScheme?: ColorScheme
   showColorCoding: boolean
+  /** Obscure amounts in the tray and windows for screen sharing */
+  privacyMode?: boolean
+  privacyStyle?: PrivacyStyle
+  /** Factor applied to amounts when privacyStyle is 'scale' */
+  privacyScale?: number
+}
+
+export interface CursorSourceConfig {
+  enabled: boolean
+  sessionToken?: string
+}
+
+export interface WindsurfSourceConfig {
+  enabled: boolean
+  serviceKey?: string
+  email?: string
+  creditPrice: number
+}
+
+export interface OpenAiSourceConfig {
+  enabled: boolean
+  projectId?: string
+}
+
+export interface OpenRouterSourceConfig {
+  enabled: boolean
+}
+
+export interface LiteLlmSourceConfig {
+  enabled: boolean
+  /** Proxy base URL, e.g. http://localhost:4000 */
+  baseUrl: string
+  apiKey?: string
+}
+
+export interface CopilotSourceConfig {
+  enabled: boolean
+  /** GitHub username whose premium requests are billed */
+  username: string
+  /** Fine-grained token with the "Plan" user permission (read) */
+  token?: string | null
+  /** Premium requests included in the plan each month */
+  monthlyAllowance: number
+}
+
+/** Tokens of local Ollama models, recorded by the API proxy's /ollama route */
+export interface OllamaSourceConfig {
+  enabled: boolean
+}
+
+export interface JsonlFieldMapping {
+  timestamp: string
+  model: string
+  inputTokens: string
+  outputTokens: string
+  cacheCreationTokens?: string
+  cacheReadTokens?: string
+  cost?: string
+  key?: string
+}
+
+export interface CustomJsonlSourceConfig {
+  id: string
+  label: string
+  enabled: boolean
+  pathGlob: string
+  fields: JsonlFieldMapping
+}
+
+export interface ProxySourceConfig {
+  enabled: boolean
+  port: number
+  openaiUpstream: string
+  anthropicUpstream: string
+  ollamaUpstream?: string
+}
+
+export type CcusageRunner = 'auto' | 'npx' | 'bunx' | 'disabled'
+
+export interface ClaudeCodeSourceConfig {
+  /** Absolute path of a ccusage executable to run instead of parsing transcripts */
+  ccusagePath?: string | null
+  /** Claude data directories to read; empty uses CLAUDE_CONFIG_DIR or the defaults */
+  dataDirs?: string[]
+  /** Package runner tried when ccusage is not installed globally */
+  runner?: CcusageRunner
+}
+
+export interface DataSourcesConfig {
+  claudeCode?: ClaudeCodeSourceConfig
+  cursor: CursorSourceConfig
+  windsurf: WindsurfSourceConfig
+  openai?: OpenAiSourceConfig
+  openrouter?: OpenRouterSourceConfig
+  litellm?: LiteLlmSourceConfig
+  ollama?: OllamaSourceConfig
+  copilot?: CopilotSourceConfig
+  customJsonl: CustomJsonlSourceConfig[]
+  proxy: ProxySourceConfig
 }
 
 export interface AppConfig {
@@ -56,6 +477,253 @@ export interface AppConfig {
   launchAtLogin: boolean
   menuBar: MenuBarConfig
   language?: string
+  dataSources: DataSourcesConfig
+  trends: TrendsConfig
+  modelAliases?: Record<string, string>
+  minModelDailyCost?: number
+  modelSort?: ModelSortKey
+  chartAggregation?: ChartAggregation
+  /** Shortcut for a full refresh, e.g. "CmdOrCtrl+Shift+R" */
+  refreshHotkey?: string | null
+  /** Day of the month (1-31) the billing cycle starts on */
+  billingCycleDay?: number | null
+  integrations?: IntegrationsConfig
+  alerts?: AlertsConfig
+  guard?: GuardConfig
+  limits?: LimitsConfig
+  timeouts?: TimeoutsConfig
+  /** Seconds a provider's last result is reused before fetching again; 0 disables */
+  providerCacheTtlSecs?: number
+  /** Add provider-reported costs to the today and monthly totals */
+  includeProviderCosts?: boolean
+  appLock?: AppLockConfig
+  /** Experimental feature overrides keyed by feature id */
+  features?: Record<string, boolean>
+}
+
+export type FeatureId = 'nativeParser' | 'scheduler'
+
+export interface FeatureInfo {
+  id: FeatureId
+  description: string
+  enabled: boolean
+  defaultEnabled: boolean
+}
+
+export interface AppLockConfig {
+  /** Require system authentication to open the dashboard or read provider secrets */
+  enabled: boolean
+  /** Minutes the app stays unlocked after the dashboard is closed (0-60) */
+  graceMinutes: number
+}
+
+export interface TimeoutsConfig {
+  ccusageSecs: number
+  providerSecs: number
+  pricingSecs: number
+  integrationSecs: number
+}
+
+export type LimitUnit = 'tokens' | 'messages'
+
+export interface LimitsConfig {
+  enabled: boolean
+  unit: LimitUnit
+  /** Allowance per 5-hour block; 0 leaves the block untracked */
+  blockLimit: number
+  /** Allowance per rolling 7 days; 0 leaves the week untracked */
+  weeklyLimit: number
+  nearPercent: number
+  overPercent: number
+}
+
+export interface GuardConfig {
+  enabled: boolean
+  port: number
+  token: string
+  minHeadroom: number
+  budgetTier?: GuardBudgetTier
+}
+
+export type GuardBudgetTier = 'soft' | 'hard'
+
+export type PushService = 'ntfy' | 'pushover'
+
+export interface PushNotificationConfig {
+  enabled: boolean
+  service: PushService
+  ntfyServer: string
+  ntfyTopic: string
+}
+
+/** A credential kept in the system keychain rather than config.json */
+export type Credential = 'ntfyToken' | 'pushoverUserKey' | 'pushoverAppToken' | 'openAiAdminKey' | 'openRouterApiKey'
+
+export interface BalanceDrainConfig {
+  enabled: boolean
+  /** Percent of a provider's used/total balance that may be used within the window */
+  percent: number
+  windowMinutes: number
+}
+
+export interface KeyExpiryConfig {
+  enabled: boolean
+  /** Days before an env entry's expiry date the reminder is sent */
+  daysBefore: number
+}
+
+export interface AlertsConfig {
+  push: PushNotificationConfig
+  balanceDrain?: BalanceDrainConfig
+  keyExpiry?: KeyExpiryConfig
+}
+
+export interface NotionIntegrationConfig {
+  enabled: boolean
+  token: string
+  databaseId: string
+  titleProperty: string
+}
+
+export interface EmailDigestConfig {
+  enabled: boolean
+  smtpUrl: string
+  username: string
+  from: string
+  to: string[]
+  /** 0 = Monday through 6 = Sunday */
+  weekday: number
+  hour: number
+}
+
+export interface RemoteSnapshotConfig {
+  enabled: boolean
+  url: string
+}
+
+/** Local program run with the usage summary as JSON on stdin after each refresh */
+export interface RefreshHook {
+  name: string
+  enabled: boolean
+  /** Absolute program path and arguments; run without a shell */
+  command: string
+}
+
+export interface IntegrationsConfig {
+  notion: NotionIntegrationConfig
+  emailDigest: EmailDigestConfig
+  remoteSnapshot: RemoteSnapshotConfig
+  hooks: RefreshHook[]
+}
+
+export interface IntegrationStatus {
+  lastAttempt: string | null
+  lastSuccess: string | null
+  lastError: string | null
+}
+
+/** One field of the JSON Schema returned by `get_config_schema` */
+export interface ConfigSchemaProperty {
+  type?: string | string[]
+  description?: string
+  default?: unknown
+  minimum?: number
+  maximum?: number
+  enum?: unknown[]
+  $ref?: string
+}
+
+export interface ConfigSchemaObject {
+  description?: string
+  properties?: Record<string, ConfigSchemaProperty>
+}
+
+/** JSON Schema of `AppConfig`; nested structs live under `definitions` */
+export interface ConfigSchema extends ConfigSchemaObject {
+  definitions?: Record<string, ConfigSchemaObject>
+}
+
+export interface TrayFormatPreview {
+  /** The format rendered with current usage */
+  preview: string
+  /** Placeholders that would be shown literally */
+  unknownVariables: string[]
+}
+
+export interface SetupStatus {
+  /** Claude Code transcripts were found in the configured or default directories */
+  claudeDataFound: boolean
+  ccusagePathSet: boolean
+  enabledProviders: number
+  /** A usage summary has been loaded since launch */
+  usageReady: boolean
+}
+
+export interface BootPayload {
+  config: AppConfig
+  /** Last summary snapshot; null until the first refresh completes */
+  usage: UsageSummary | null
+  /** Saved providers with env values masked and fetch scripts cleared */
+  providers: ApiProvider[]
+  setup: SetupStatus
+}
+
+export interface Diagnostics {
+  integrations: Record<string, IntegrationStatus>
+  /** What the configured ccusage supports, once it has been probed */
+  ccusage?: CcusageCapabilities | null
+  /** Provider env entries with an expiry date, soonest first */
+  keyExpirations?: KeyExpiration[]
+}
+
+export interface KeyExpiration {
+  providerId: string
+  providerName: string
+  variable: string
+  expiresAt: string
+  /** Negative once the entry has expired */
+  daysLeft: number
+}
+
+export interface CcusageCapabilities {
+  version: string | null
+  /** False when `--help` could not be read and the flags are assumed */
+  helpRead: boolean
+  offline: boolean
+  days: boolean
+  since: boolean
+  breakdown: boolean
+  recent: boolean
+  /** The `session` subcommand */
+  session: boolean
+}
+
+export interface PerfBucket {
+  /** Inclusive upper bound in milliseconds; null for the overflow bucket */
+  leMs: number | null
+  count: number
+}
+
+export interface PerfStat {
+  /** Span name, e.g. "command.refresh_usage" or "service.ccusage" */
+  name: string
+  count: number
+  totalMs: number
+  meanMs: number
+  maxMs: number
+  p50Ms: number
+  p95Ms: number
+  buckets: PerfBucket[]
+}
+
+export type TrendBaseline = 'mean30' | 'median7' | 'ewma' | 'sameWeekday'
+
+export interface TrendsConfig {
+  baseline: TrendBaseline
+  excludeOutliers: boolean
+  outlierPercent: number
+  orgComparison: boolean
+  orgMember: string | null
 }
 
 export type UsageLevel = 'low' | 'medium' | 'high' | 'critical'
@@ -88,11 +756,26 @@ export function getUsageColor(level: UsageLevel): string {
   }
 }
 
-export function formatCost(cost: number): string {
-  return `$${cost.toFixed(2)}`
+let privacy: Pick<MenuBarConfig, 'privacyMode' | 'privacyStyle' | 'privacyScale'> = {}
+
+/** Applies the privacy mode settings to formatCost and formatTokens. */
+export function setPrivacy(menuBar?: MenuBarConfig) {
+  privacy = menuBar ?? {}
+}
+
+function privacyScale(): number {
+  return privacy.privacyMode && privacy.privacyStyle === 'scale' ? privacy.privacyScale ?? 1 : 1
+}
+
+export function formatCost(cost: number, digits = 2): string {
+  if (privacy.privacyMode && (privacy.privacyStyle ?? 'mask') === 'mask')
+    return '$•••'
+  return `$${(cost * privacyScale()).toFixed(digits)}`
 }
 
-export function formatTokens(tokens: number): string {
+/** Formats a token count; `usage` counts are scaled in privacy mode, limits are not. */
+export function formatTokens(count: number, usage = true): string {
+  const tokens = usage ? Math.round(count * privacyScale()) : count
   if (tokens >= 1_000_000) {
     return `${(tokens / 1_000_000).toFixed(1)}M`
   }